@@ -0,0 +1,43 @@
+//! Adding many items to one list, like a bulk import does, which used to compare every new
+//! item with each item already in the list. Run with `cargo bench`
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use wlist::{DuplicatePolicy, Item, WatchList};
+
+fn titles(count: usize) -> Vec<String> {
+    (0..count).map(|i| format!("Movie {i}")).collect()
+}
+
+fn item_add(c: &mut Criterion) {
+    let mut group = c.benchmark_group("item_add");
+    for count in [1_000, 5_000, 20_000] {
+        let titles = titles(count);
+        group.bench_with_input(BenchmarkId::new("index", count), &titles, |b, titles| {
+            b.iter(|| {
+                let mut watchlist = WatchList::new();
+                watchlist.list_add("Movies").unwrap();
+                for title in titles {
+                    watchlist
+                        .item_add("Movies", title.as_str(), DuplicatePolicy::Exact)
+                        .unwrap();
+                }
+                watchlist
+            })
+        });
+        // What adding did before, checking each title against the whole list
+        group.bench_with_input(BenchmarkId::new("scan", count), &titles, |b, titles| {
+            b.iter(|| {
+                let mut items: Vec<Item> = Vec::new();
+                for title in titles {
+                    assert!(!items.iter().any(|i| &i.title == title));
+                    items.push(Item::new(title.as_str()));
+                }
+                items
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, item_add);
+criterion_main!(benches);
@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use wlist::{import, limits};
+
+fuzz_target!(|input: (&str, Option<&str>)| {
+    let (html, folder) = input;
+    if let Ok(items) = import::parse_bookmarks(html, folder) {
+        let _ = limits::check_items(&items);
+    }
+});
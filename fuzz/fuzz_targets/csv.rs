@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use wlist::{import, limits};
+
+fuzz_target!(|data: &str| {
+    let _ = limits::check_items(&import::parse_text(data));
+    if let Ok(items) = import::parse_csv(data) {
+        let _ = limits::check_items(&items);
+    }
+});
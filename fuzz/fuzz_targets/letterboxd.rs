@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use wlist::{import, limits};
+
+fuzz_target!(|data: &str| {
+    if let Ok(items) = import::parse_letterboxd(data) {
+        let _ = limits::check_items(&items);
+    }
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use wlist::{limits, WatchList};
+
+fuzz_target!(|data: &str| {
+    if let Ok(watchlist) = serde_json::from_str::<WatchList>(data) {
+        let _ = limits::check_watchlist(&watchlist);
+    }
+});
@@ -0,0 +1,301 @@
+//! Sync of anime and manga lists with the "Planning" lists on AniList
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::http::{self, network_error};
+use crate::sync::{self, ConflictStrategy, SyncReport, Token};
+use crate::{Item, WatchList, WatchListError};
+
+const API: &str = "https://graphql.anilist.co";
+/// Name the token is stored under
+pub const SERVICE: &str = "anilist";
+
+/// Kind of media in an AniList list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaType {
+    Anime,
+    Manga,
+}
+
+impl MediaType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MediaType::Anime => "ANIME",
+            MediaType::Manga => "MANGA",
+        }
+    }
+}
+
+/// Gets the page where an access token can be created for the AniList app with `client_id`
+pub fn authorize_url(client_id: &str) -> String {
+    format!("https://anilist.co/api/v2/oauth/authorize?client_id={client_id}&response_type=token")
+}
+
+#[derive(Deserialize)]
+struct Response<T> {
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<GraphQLError>,
+}
+
+#[derive(Deserialize)]
+struct GraphQLError {
+    message: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    id: u64,
+    media: Media,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Media {
+    id: u64,
+    id_mal: Option<u64>,
+    title: Title,
+    site_url: Option<String>,
+    start_date: Option<FuzzyDate>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Title {
+    user_preferred: Option<String>,
+    romaji: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct FuzzyDate {
+    year: Option<u16>,
+}
+
+impl Media {
+    fn item(&self) -> Option<Item> {
+        let title = self
+            .title
+            .user_preferred
+            .as_ref()
+            .or(self.title.romaji.as_ref())?;
+        let mut item = Item::new(title);
+        if let Some(year) = self.start_date.as_ref().and_then(|d| d.year) {
+            item = item.with_year(year);
+        }
+        if let Some(url) = &self.site_url {
+            item = item.with_url(url);
+        }
+        item = item.with_id("anilist", self.id.to_string());
+        if let Some(id_mal) = self.id_mal {
+            item = item.with_id("mal", id_mal.to_string());
+        }
+        Some(item)
+    }
+}
+
+/// Client for the AniList GraphQL API using a stored access token
+pub struct AniList {
+    token: Token,
+}
+
+impl AniList {
+    pub fn new(token: Token) -> Self {
+        AniList { token }
+    }
+
+    fn query<T: DeserializeOwned>(
+        &self,
+        query: &str,
+        variables: Value,
+    ) -> Result<T, WatchListError> {
+        let authorization = format!("Bearer {}", self.token.access_token);
+        let response: Response<T> = http::post_json(
+            API,
+            &[
+                ("Authorization", &authorization),
+                ("Accept", "application/json"),
+            ],
+            &json!({ "query": query, "variables": variables }),
+        )
+        .map_err(network_error)?;
+
+        if let Some(error) = response.errors.first() {
+            return Err(WatchListError::NetworkError(error.message.clone()));
+        }
+        response.data.ok_or(WatchListError::NetworkError(
+            "empty response from AniList".to_string(),
+        ))
+    }
+
+    fn user_id(&self) -> Result<u64, WatchListError> {
+        let data: Value = self.query("query { Viewer { id } }", json!({}))?;
+        data["Viewer"]["id"]
+            .as_u64()
+            .ok_or(WatchListError::NetworkError(
+                "couldn't find the AniList user".to_string(),
+            ))
+    }
+
+    fn planning(&self, user_id: u64, media_type: MediaType) -> Result<Vec<Entry>, WatchListError> {
+        const QUERY: &str = "
+            query ($userId: Int, $type: MediaType) {
+                MediaListCollection(userId: $userId, type: $type, status: PLANNING) {
+                    lists {
+                        entries {
+                            id
+                            media {
+                                id
+                                idMal
+                                title { userPreferred romaji }
+                                siteUrl
+                                startDate { year }
+                            }
+                        }
+                    }
+                }
+            }";
+        let data: Value = self.query(
+            QUERY,
+            json!({ "userId": user_id, "type": media_type.as_str() }),
+        )?;
+
+        let mut entries = Vec::new();
+        let lists = data["MediaListCollection"]["lists"].as_array().cloned();
+        for list in lists.unwrap_or_default() {
+            let list_entries: Vec<Entry> = serde_json::from_value(list["entries"].clone())
+                .map_err(|e| WatchListError::ParseError(e.to_string()))?;
+            entries.extend(list_entries);
+        }
+        Ok(entries)
+    }
+
+    // Finds the ID of an item, from the IDs it has where possible and otherwise
+    // from the closest match for its title
+    fn search(&self, item: &Item, media_type: MediaType) -> Result<Option<u64>, WatchListError> {
+        if let Some(id) = item.id("anilist").and_then(|id| id.parse().ok()) {
+            return Ok(Some(id));
+        }
+        if let Some(id_mal) = item.id("mal").and_then(|id| id.parse::<u64>().ok()) {
+            const QUERY: &str = "
+                query ($idMal: Int, $type: MediaType) {
+                    Media(idMal: $idMal, type: $type) { id }
+                }";
+            // AniList answers with an error if it doesn't know the MyAnimeList ID
+            let data = self.query::<Value>(
+                QUERY,
+                json!({ "idMal": id_mal, "type": media_type.as_str() }),
+            );
+            if let Some(id) = data.ok().and_then(|d| d["Media"]["id"].as_u64()) {
+                return Ok(Some(id));
+            }
+        }
+
+        const QUERY: &str = "
+            query ($search: String, $type: MediaType) {
+                Page(perPage: 1) { media(search: $search, type: $type) { id } }
+            }";
+        let data: Value = self.query(
+            QUERY,
+            json!({ "search": item.title, "type": media_type.as_str() }),
+        )?;
+        Ok(data["Page"]["media"][0]["id"].as_u64())
+    }
+
+    fn add_planning(&self, media_id: u64) -> Result<(), WatchListError> {
+        const MUTATION: &str = "
+            mutation ($mediaId: Int) {
+                SaveMediaListEntry(mediaId: $mediaId, status: PLANNING) { id }
+            }";
+        self.query::<Value>(MUTATION, json!({ "mediaId": media_id }))?;
+        Ok(())
+    }
+
+    fn remove_entry(&self, entry_id: u64) -> Result<(), WatchListError> {
+        const MUTATION: &str = "
+            mutation ($id: Int) {
+                DeleteMediaListEntry(id: $id) { deleted }
+            }";
+        self.query::<Value>(MUTATION, json!({ "id": entry_id }))?;
+        Ok(())
+    }
+
+    /// Syncs each list with the AniList "Planning" list of the same media type,
+    /// creating the lists if they don't exist
+    ///
+    /// Items which can't be found on AniList are not pushed
+    pub fn sync(
+        &self,
+        watchlist: &mut WatchList,
+        lists: &[(&str, MediaType)],
+        strategy: ConflictStrategy,
+    ) -> Result<SyncReport, WatchListError> {
+        let user_id = self.user_id()?;
+        let mut report = SyncReport::default();
+
+        for (title, media_type) in lists {
+            if !watchlist.contains(title) {
+                watchlist.list_add(title)?;
+            }
+
+            let entries = self.planning(user_id, *media_type)?;
+            let (entries, remote): (Vec<&Entry>, Vec<Item>) = entries
+                .iter()
+                .filter_map(|e| Some((e, e.media.item()?)))
+                .unzip();
+            let local = watchlist[*title].clone();
+            let plan = sync::plan(&local, &remote, strategy);
+
+            let mut pushed = 0;
+            for item in &plan.push {
+                if let Some(id) = self.search(item, *media_type)? {
+                    self.add_planning(id)?;
+                    pushed += 1;
+                }
+            }
+            for (entry, _) in entries
+                .iter()
+                .zip(&remote)
+                .filter(|(_, item)| plan.remove_remote.contains(item))
+            {
+                self.remove_entry(entry.id)?;
+            }
+            sync::apply_local(watchlist, title, &plan)?;
+
+            report += SyncReport {
+                pulled: plan.pull.len(),
+                pushed,
+                removed_local: plan.remove_local.len(),
+                removed_remote: plan.remove_remote.len(),
+            };
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn media_items() {
+        let media: Media = serde_json::from_value(json!({
+            "id": 1,
+            "idMal": 1,
+            "title": { "userPreferred": "Cowboy Bebop", "romaji": "Cowboy Bebop" },
+            "siteUrl": "https://anilist.co/anime/1",
+            "startDate": { "year": 1998 }
+        }))
+        .unwrap();
+        assert_eq!(
+            media.item().unwrap(),
+            Item::new("Cowboy Bebop")
+                .with_year(1998)
+                .with_url("https://anilist.co/anime/1")
+                .with_id("anilist", "1")
+                .with_id("mal", "1")
+        );
+    }
+}
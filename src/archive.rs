@@ -0,0 +1,122 @@
+//! Finished items moved out of their lists, kept so they can still be looked through
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::dates::Timestamp;
+use crate::{encryption, Item, WatchList, WatchListError};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    pub archived_at: Timestamp,
+    /// List the item was archived from
+    pub list: String,
+    pub item: Item,
+}
+
+/// Archived items saved to a file, oldest first
+#[derive(Debug, Default)]
+pub struct Archive {
+    file_path: PathBuf,
+    path: PathBuf,
+    pub entries: Vec<ArchiveEntry>,
+}
+
+impl Archive {
+    /// Reads the archive next to the data file, starting with an empty one if there isn't any
+    pub fn from_file(file_path: &Path) -> Result<Self, WatchListError> {
+        let path = crate::sidecar_path(file_path, "archive");
+        let entries = match encryption::read_sidecar(file_path, &path)? {
+            Some(data) => serde_json::from_str(&data)
+                .map_err(|e| WatchListError::ParseError(e.to_string()))?,
+            None => Vec::new(),
+        };
+        Ok(Archive {
+            file_path: file_path.to_path_buf(),
+            path,
+            entries,
+        })
+    }
+
+    pub fn to_file(&self) -> Result<(), WatchListError> {
+        let data =
+            serde_json::to_string(&self.entries).map_err(|e| WatchListError::IOError(e.into()))?;
+        encryption::write_sidecar(&self.file_path, &self.path, data.as_bytes())
+    }
+
+    pub fn add(&mut self, list: &str, item: Item, archived_at: Timestamp) {
+        self.entries.push(ArchiveEntry {
+            archived_at,
+            list: list.to_string(),
+            item,
+        });
+    }
+
+    /// Moves the watched items of a list into the archive, returning them
+    pub fn archive_watched(
+        &mut self,
+        watchlist: &mut WatchList,
+        list: &str,
+        archived_at: Timestamp,
+    ) -> Result<Vec<Item>, WatchListError> {
+        let items = watchlist.item_get_all(list)?;
+        let (watched, kept): (Vec<Item>, Vec<Item>) =
+            items.iter().cloned().partition(|i| i.meta.watched);
+        watchlist.insert(list.to_string(), kept);
+        for item in &watched {
+            self.add(list, item.clone(), archived_at);
+        }
+        Ok(watched)
+    }
+
+    /// Titles of the lists items were archived from, in order
+    pub fn lists(&self) -> Vec<&str> {
+        let mut lists: Vec<&str> = self.entries.iter().map(|e| e.list.as_str()).collect();
+        lists.sort_unstable();
+        lists.dedup();
+        lists
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archiving() {
+        let mut watchlist = WatchList::new();
+        let mut watched = Item::new("Alien");
+        watched.meta.watched = true;
+        watchlist.insert(
+            "Movies".to_string(),
+            vec![Item::new("Dune"), watched.clone(), Item::new("Heat")],
+        );
+        let mut archive = Archive::default();
+        let now: Timestamp = "2023-03-01T12:00:00Z".parse().unwrap();
+        archive.add("TV", Item::new("Lost"), now);
+
+        let archived = archive
+            .archive_watched(&mut watchlist, "Movies", now)
+            .unwrap();
+        assert_eq!(archived, vec![watched.clone()]);
+        assert_eq!(watchlist["Movies"], vec!["Dune", "Heat"]);
+        assert_eq!(archive.entries[1].list, "Movies");
+        assert_eq!(archive.entries[1].item, watched);
+        assert_eq!(archive.lists(), vec!["Movies", "TV"]);
+
+        // Nothing is left to archive the second time
+        let archived = archive
+            .archive_watched(&mut watchlist, "Movies", now)
+            .unwrap();
+        assert!(archived.is_empty());
+        assert_eq!(archive.entries.len(), 2);
+        assert_eq!(
+            archive.archive_watched(&mut watchlist, "Books", now),
+            Err(WatchListError::TitleNotPresent(
+                "Books".to_string(),
+                Vec::new()
+            ))
+        );
+    }
+}
@@ -0,0 +1,542 @@
+//! Finding problems in the data file with `wl check`, like parts of it which can't be read
+//! or titles other commands trip over, and repairing the ones which can be
+//!
+//! The file is read without the lists it holds being trusted, so problems which stop it
+//! from being read at all are found along with the rest. Most problems are repaired by
+//! leaving out what's broken, so repairing them loses that part of the file
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde_json::{Map, Number, Value};
+
+use crate::{schema, Item, ListSettings, Metadata, WatchList};
+
+type Settings = BTreeMap<String, ListSettings>;
+
+/// Title given to lists which didn't have one
+const UNTITLED: &str = "Untitled";
+
+/// Something wrong with the data file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    /// Line of the file it was found on, counting from 1, when it can be told
+    pub line: Option<usize>,
+    pub message: String,
+    /// What repairing the file does about it, none if it can't be repaired
+    pub fix: Option<String>,
+}
+
+impl Problem {
+    fn new(line: Option<usize>, message: String, fix: Option<&str>) -> Self {
+        Problem {
+            line,
+            message,
+            fix: fix.map(String::from),
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "Line {line}: ")?;
+        }
+        write!(f, "{}", self.message)?;
+        if let Some(fix) = &self.fix {
+            write!(f, " ({fix})")?;
+        }
+        Ok(())
+    }
+}
+
+/// Problems found in the data file, along with the lists with them repaired
+#[derive(Debug, Default)]
+pub struct Report {
+    pub problems: Vec<Problem>,
+    /// Lists with every problem which can be repaired repaired, and the version of the file,
+    /// none if the file can't be read at all
+    pub repaired: Option<(WatchList, u32)>,
+}
+
+impl Report {
+    /// Whether repairing the file changes anything
+    pub fn can_repair(&self) -> bool {
+        self.repaired.is_some() && self.problems.iter().any(|p| p.fix.is_some())
+    }
+}
+
+// JSON which keeps every key of an object, even ones which are in it more than once
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Value(Value),
+    Array(Vec<Node>),
+    Object(Vec<(String, Node)>),
+}
+
+impl Node {
+    // Uses the last value of each key, the same as the lists are read
+    fn into_value(self) -> Value {
+        match self {
+            Node::Value(value) => value,
+            Node::Array(nodes) => Value::Array(nodes.into_iter().map(Node::into_value).collect()),
+            Node::Object(entries) => Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, node)| (key, node.into_value()))
+                    .collect::<Map<_, _>>(),
+            ),
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<&Node> {
+        match self {
+            Node::Object(entries) => entries.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Node {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(NodeVisitor)
+    }
+}
+
+struct NodeVisitor;
+
+impl<'de> Visitor<'de> for NodeVisitor {
+    type Value = Node;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "any JSON value")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Node, E> {
+        Ok(Node::Value(Value::Null))
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Node, E> {
+        Ok(Node::Value(Value::Bool(v)))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Node, E> {
+        Ok(Node::Value(Value::Number(v.into())))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Node, E> {
+        Ok(Node::Value(Value::Number(v.into())))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Node, E> {
+        Ok(Node::Value(
+            Number::from_f64(v).map_or(Value::Null, Value::Number),
+        ))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Node, E> {
+        Ok(Node::Value(Value::String(v.to_string())))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Node, A::Error> {
+        let mut nodes = Vec::new();
+        while let Some(node) = seq.next_element()? {
+            nodes.push(node);
+        }
+        Ok(Node::Array(nodes))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Node, A::Error> {
+        let mut entries = Vec::new();
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(Node::Object(entries))
+    }
+}
+
+/// Checks the data of a file for problems
+pub fn check(data: &str) -> Report {
+    let mut report = Report::default();
+    let root: Node = match serde_json::from_str(data) {
+        Ok(root) => root,
+        Err(e) => {
+            // The position is given by the line instead
+            let message = e.to_string();
+            let message = match message.rfind(" at line ") {
+                Some(at) => message[..at].to_string(),
+                None => message,
+            };
+            let line = (e.line() > 0).then_some(e.line());
+            report.problems.push(Problem::new(
+                line,
+                format!("The file isn't valid JSON, {message}"),
+                None,
+            ));
+            return report;
+        }
+    };
+
+    let (lists, version) = match root.get("version").zip(root.get("lists")) {
+        Some((Node::Value(Value::Number(version)), lists)) => {
+            let version = version
+                .as_u64()
+                .map_or(u32::MAX, |v| v.min(u32::MAX as u64) as u32);
+            (lists, version)
+        }
+        // Lists which are in the file more than once are all looked at
+        _ => {
+            let lists = match &root {
+                Node::Object(entries) => Value::Object(
+                    (entries.iter().enumerate())
+                        .map(|(i, (_, node))| (i.to_string(), node.clone().into_value()))
+                        .collect(),
+                ),
+                root => root.clone().into_value(),
+            };
+            (&root, schema::detect(&lists))
+        }
+    };
+    if version > schema::CURRENT {
+        report.problems.push(Problem::new(
+            None,
+            format!("The file is in version {version}, which a newer version of wl saved"),
+            None,
+        ));
+        return report;
+    }
+    let Node::Object(entries) = lists else {
+        report.problems.push(Problem::new(
+            None,
+            "The file doesn't hold an object of lists".to_string(),
+            None,
+        ));
+        return report;
+    };
+
+    let mut repaired: BTreeMap<String, Vec<Item>> = BTreeMap::new();
+    let mut seen: BTreeMap<&str, usize> = BTreeMap::new();
+    for (title, node) in entries {
+        let count = seen.entry(title).or_default();
+        *count += 1;
+        let line = find_key(data, title, *count);
+        if *count == 2 {
+            report.problems.push(Problem::new(
+                line,
+                format!(
+                    "The list {title} is in the file more than once, and only the last one is read"
+                ),
+                Some("its items are put together in one list"),
+            ));
+        }
+
+        let title = match title.trim() {
+            "" => {
+                let untitled = unique_title(&repaired, entries);
+                report.problems.push(Problem::new(
+                    line,
+                    "A list has an empty title".to_string(),
+                    Some(&format!("it's renamed to {untitled}")),
+                ));
+                untitled
+            }
+            _ => title.clone(),
+        };
+        let items = check_items(data, &title, line, node, &mut report.problems);
+        // Items of lists which are in the file more than once are put together quietly,
+        // since they were already reported
+        let list = repaired.entry(title).or_default();
+        for item in items {
+            if !list.iter().any(|i| i.title == item.title) {
+                list.push(item);
+            }
+        }
+    }
+    let mut repaired: WatchList = repaired.into_iter().collect();
+    // Settings of the lists are kept as they are, as long as they can be read
+    let settings = root
+        .get("settings")
+        .filter(|_| version >= 4)
+        .and_then(|s| serde_json::from_value::<Settings>(s.clone().into_value()).ok());
+    for (title, settings) in settings.unwrap_or_default() {
+        if let Ok(list) = repaired.list_settings_mut(&title) {
+            *list = settings;
+        }
+    }
+    report.repaired = Some((repaired, version));
+    report
+}
+
+// Line is where the list starts
+fn check_items(
+    data: &str,
+    list: &str,
+    line: Option<usize>,
+    node: &Node,
+    problems: &mut Vec<Problem>,
+) -> Vec<Item> {
+    let nodes = match node {
+        Node::Array(nodes) => nodes.as_slice(),
+        Node::Value(Value::Null) => &[],
+        _ => {
+            problems.push(Problem::new(
+                line,
+                format!("The list {list} doesn't hold a list of items"),
+                Some("it's left empty"),
+            ));
+            return Vec::new();
+        }
+    };
+    let mut items: Vec<Item> = Vec::new();
+    for node in nodes {
+        let Some(item) = check_item(data, list, line, node, problems) else {
+            continue;
+        };
+        if items.iter().any(|i| i.title == item.title) {
+            problems.push(Problem::new(
+                find_string(data, &item.title),
+                format!("'{}' is in {list} more than once", item.title),
+                Some("only the first one is kept"),
+            ));
+        } else {
+            items.push(item);
+        }
+    }
+    items
+}
+
+fn check_item(
+    data: &str,
+    list: &str,
+    list_line: Option<usize>,
+    node: &Node,
+    problems: &mut Vec<Problem>,
+) -> Option<Item> {
+    let title = match node {
+        Node::Value(Value::String(title)) => title.as_str(),
+        Node::Object(_) => match node.get("title") {
+            Some(Node::Value(Value::String(title))) => title.as_str(),
+            _ => "",
+        },
+        _ => {
+            problems.push(Problem::new(
+                list_line,
+                format!("An item in {list} isn't a title or an item with details"),
+                Some("it's removed"),
+            ));
+            return None;
+        }
+    };
+    if title.trim().is_empty() {
+        problems.push(Problem::new(
+            list_line,
+            format!("An item in {list} has an empty title"),
+            Some("it's removed"),
+        ));
+        return None;
+    }
+    let line = find_string(data, title);
+    let Node::Object(entries) = node else {
+        return Some(Item::new(title));
+    };
+
+    let mut fields: Vec<&str> = Vec::new();
+    for (field, _) in entries {
+        if fields.contains(&field.as_str()) {
+            problems.push(Problem::new(
+                line,
+                format!("'{title}' in {list} has `{field}` more than once"),
+                Some("the last one is kept"),
+            ));
+        } else {
+            fields.push(field);
+        }
+    }
+
+    // Details which can't be read are left out one at a time, keeping the rest
+    let mut details = Map::new();
+    for (field, node) in entries.iter().filter(|(f, _)| f != "title") {
+        let value = node.clone().into_value();
+        let detail = Map::from_iter([(field.clone(), value.clone())]);
+        match serde_json::from_value::<Metadata>(Value::Object(detail)) {
+            Ok(_) => {
+                details.insert(field.clone(), value);
+            }
+            Err(e) => problems.push(Problem::new(
+                line,
+                format!("'{title}' in {list} has a malformed `{field}`, {e}"),
+                Some("it's removed"),
+            )),
+        }
+    }
+    let mut item = Item {
+        title: title.to_string(),
+        meta: serde_json::from_value(Value::Object(details)).unwrap_or_default(),
+    };
+
+    if let Some(rating) = item.meta.rating.filter(|r| !(1..=10).contains(r)) {
+        problems.push(Problem::new(
+            line,
+            format!("'{title}' in {list} is rated {rating}, outside of 1 to 10"),
+            Some("the rating is removed"),
+        ));
+        item.meta.rating = None;
+    }
+    if item.meta.tags.iter().any(|t| t.trim().is_empty()) {
+        problems.push(Problem::new(
+            line,
+            format!("'{title}' in {list} has an empty tag"),
+            Some("it's removed"),
+        ));
+        item.meta.tags.retain(|t| !t.trim().is_empty());
+    }
+    Some(item)
+}
+
+// A title for a list which didn't have one, which no other list has
+fn unique_title(repaired: &BTreeMap<String, Vec<Item>>, entries: &[(String, Node)]) -> String {
+    let taken =
+        |title: &str| repaired.contains_key(title) || entries.iter().any(|(t, _)| t == title);
+    (1..)
+        .map(|n| match n {
+            1 => UNTITLED.to_string(),
+            n => format!("{UNTITLED} {n}"),
+        })
+        .find(|title| !taken(title))
+        .unwrap_or_default()
+}
+
+// Line of the nth time the key of an object is in the file
+fn find_key(data: &str, key: &str, nth: usize) -> Option<usize> {
+    let quoted = serde_json::to_string(key).ok()?;
+    let at = data
+        .match_indices(&quoted)
+        .filter(|(at, _)| data[at + quoted.len()..].trim_start().starts_with(':'))
+        .nth(nth - 1)?
+        .0;
+    Some(line_of(data, at))
+}
+
+// Line of the first time the text is in the file as a string
+fn find_string(data: &str, text: &str) -> Option<usize> {
+    let quoted = serde_json::to_string(text).ok()?;
+    data.find(&quoted).map(|at| line_of(data, at))
+}
+
+fn line_of(data: &str, at: usize) -> usize {
+    data[..at].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(report: &Report) -> Vec<String> {
+        report.problems.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn valid_files() {
+        for data in [
+            r#"{"Movies":["Alien",{"title":"Heat","rating":8}],"Books":[]}"#,
+            r#"{"version":3,"lists":{"Movies":["Alien"]}}"#,
+            r#"{"version":4,"lists":{"Movies":["Alien"]},"settings":{"Movies":{"no_random":true}}}"#,
+            "{}",
+        ] {
+            let report = check(data);
+            assert_eq!(report.problems, Vec::new(), "{data}");
+            assert!(!report.can_repair());
+            let (lists, _) = report.repaired.unwrap();
+            assert_eq!(lists, schema::parse(data.as_bytes()).unwrap().0);
+        }
+    }
+
+    #[test]
+    fn unreadable_files() {
+        let report = check("{\n  \"Movies\": [\"Alien\",]\n}");
+        assert_eq!(
+            messages(&report),
+            ["Line 2: The file isn't valid JSON, trailing comma"]
+        );
+        assert!(report.repaired.is_none() && !report.can_repair());
+
+        let report = check(r#"{"version":5,"lists":{}}"#);
+        assert_eq!(report.problems[0].fix, None);
+        let report = check(r#"["Alien"]"#);
+        assert_eq!(
+            messages(&report),
+            ["The file doesn't hold an object of lists"]
+        );
+    }
+
+    #[test]
+    fn duplicates_and_empty_titles() {
+        let data = r#"{
+  "Movies": ["Alien", "Heat"],
+  "": ["Dune"],
+  "Movies": ["Heat", "  ", "Ran", "Ran"]
+}"#;
+        let report = check(data);
+        assert_eq!(
+            messages(&report),
+            [
+                "Line 3: A list has an empty title (it's renamed to Untitled)",
+                "Line 4: The list Movies is in the file more than once, and only the last one is read (its items are put together in one list)",
+                "Line 4: An item in Movies has an empty title (it's removed)",
+                "Line 4: 'Ran' is in Movies more than once (only the first one is kept)",
+            ]
+        );
+        assert!(report.can_repair());
+        let (lists, version) = report.repaired.unwrap();
+        assert_eq!(version, 1);
+        let titles: Vec<&str> = lists
+            .get("Movies")
+            .unwrap()
+            .iter()
+            .map(|i| i.title.as_str())
+            .collect();
+        assert_eq!(titles, ["Alien", "Heat", "Ran"]);
+        assert_eq!(lists.get("Untitled"), Some(&vec![Item::new("Dune")]));
+    }
+
+    #[test]
+    fn malformed_details() {
+        let data = r#"{
+  "Movies": [
+    {"title": "Alien", "rating": "great", "year": 1979, "year": 1980},
+    {"title": "Heat", "rating": 11, "tags": ["crime", ""]},
+    {"rating": 8},
+    3
+  ],
+  "Books": "Dune"
+}"#;
+        let report = check(data);
+        let messages = messages(&report);
+        assert_eq!(messages.len(), 7, "{messages:#?}");
+        assert_eq!(
+            messages[0],
+            "Line 3: 'Alien' in Movies has `year` more than once (the last one is kept)"
+        );
+        assert!(messages[1].starts_with("Line 3: 'Alien' in Movies has a malformed `rating`"));
+        assert_eq!(
+            messages[2..],
+            [
+                "Line 4: 'Heat' in Movies is rated 11, outside of 1 to 10 (the rating is removed)",
+                "Line 4: 'Heat' in Movies has an empty tag (it's removed)",
+                "Line 2: An item in Movies has an empty title (it's removed)",
+                "Line 2: An item in Movies isn't a title or an item with details (it's removed)",
+                "Line 8: The list Books doesn't hold a list of items (it's left empty)",
+            ]
+        );
+
+        let (lists, version) = report.repaired.unwrap();
+        assert_eq!(version, 2);
+        let mut alien = Item::new("Alien");
+        alien.meta.year = Some(1980);
+        let mut heat = Item::new("Heat");
+        heat.meta.tags = vec!["crime".to_string()];
+        assert_eq!(lists.get("Movies"), Some(&vec![alien, heat]));
+        assert_eq!(lists.get("Books"), Some(&Vec::new()));
+    }
+}
@@ -1,5 +1,82 @@
-use clap::{ArgGroup, Args, Parser, Subcommand};
-use wlist::{WatchList, WatchListError, WatchListFuncs};
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use clap::error::ErrorKind;
+use clap::{ArgGroup, Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::env::Shells;
+use clap_complete::{ArgValueCandidates, CompletionCandidate, Shell};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+#[cfg(feature = "net")]
+use wlist::anilist::{self, AniList, MediaType};
+use wlist::archive::Archive;
+use wlist::check;
+#[cfg(feature = "clipboard")]
+use wlist::clipboard;
+use wlist::config::Config;
+#[cfg(unix)]
+use wlist::daemon::{self, Daemon};
+use wlist::diff::{self, Difference};
+use wlist::editor;
+use wlist::encryption;
+use wlist::export::{self, ExportFormat};
+use wlist::fields::{self, Entry, Field, OutputFormat};
+use wlist::fold;
+use wlist::forecast;
+use wlist::git;
+use wlist::history::{self, Operation};
+#[cfg(unix)]
+use wlist::import;
+use wlist::index::SearchIndex;
+use wlist::limits;
+use wlist::links;
+use wlist::lock::FileLock;
+use wlist::merge::{self, MergeStrategy};
+use wlist::notify;
+use wlist::output;
+use wlist::picks::{DailyPick, DailyPicks, PickHistory};
+use wlist::profiles;
+use wlist::query::{self, Query};
+use wlist::recent;
+#[cfg(feature = "net")]
+use wlist::remote::{self, SyncState};
+use wlist::render::{Align, Column, Table};
+use wlist::review::{Decision, Reviews};
+use wlist::schema;
+use wlist::selection;
+use wlist::serve;
+use wlist::setup;
+use wlist::shell;
+use wlist::stage::{Change, Stage as StagedChanges};
+use wlist::stats::{self, Activity, Heatmap};
+use wlist::status::Status;
+use wlist::suggest;
+#[cfg(feature = "net")]
+use wlist::sync::{ConflictStrategy, Token, TokenStore};
+use wlist::theme::{self, Style, Theme};
+#[cfg(feature = "tmdb")]
+use wlist::tmdb;
+use wlist::tmp::TmpList;
+#[cfg(feature = "net")]
+use wlist::trakt::Trakt;
+use wlist::trash::{Trash, Trashed};
+use wlist::undo::UndoHistory;
+use wlist::workspace;
+use wlist::{dates, due};
+use wlist::{DuplicatePolicy, Item, Match, SortBy, WatchList, WatchListError};
+
+// Prints confirmations and other messages which `--quiet` leaves out
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if !theme::quiet() {
+            println!($($arg)*);
+        }
+    };
+}
 
 #[derive(Parser)]
 #[command(author, version, long_about = None)]
@@ -8,10 +85,82 @@ use wlist::{WatchList, WatchListError, WatchListFuncs};
 pub struct Cli {
     #[command(subcommand)]
     commands: Commands,
+
+    /// Only print results and errors, leaving out confirmations, headings
+    /// and warnings about items which are due soon
+    #[clap(long, short, global = true)]
+    quiet: bool,
+
+    /// Use the closest list when a list isn't found, if only one list is close
+    #[clap(long, global = true)]
+    lenient: bool,
+
+    /// Answer questions without asking, for scripts and keybindings. Confirmations are
+    /// agreed to and questions with a default answer take it
+    #[clap(long, short = 'y', visible_alias = "non-interactive", global = true)]
+    yes: bool,
+
+    /// Upgrade the data file without asking if saving the Lists needs a newer version
+    #[clap(long, global = true)]
+    upgrade: bool,
+
+    /// Save the data file as compact JSON on one line instead of pretty-printed
+    #[clap(long, global = true)]
+    compact: bool,
+
+    /// Colors to use instead of the theme in the config file
+    #[clap(long, global = true, value_enum)]
+    theme: Option<ThemeName>,
+
+    /// Data file to use instead of the one from the environment or the config file
+    #[clap(long = "file", short = 'f', global = true, value_name = "PATH")]
+    data_file: Option<String>,
+
+    /// Profile to use instead of the default one, see `wl profile`
+    #[clap(
+        long,
+        short,
+        global = true,
+        value_name = "NAME",
+        conflicts_with = "data_file"
+    )]
+    profile: Option<String>,
+
+    /// Use the Lists of every data file in a workspace from the config file, with their
+    /// titles prefixed by the name of their file like `shared/Movies`
+    #[clap(
+        long,
+        global = true,
+        value_name = "NAME",
+        conflicts_with_all = ["data_file", "profile"]
+    )]
+    workspace: Option<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ThemeName {
+    Default,
+    Solarized,
+    Monochrome,
+    HighContrast,
+}
+
+impl From<ThemeName> for Theme {
+    fn from(name: ThemeName) -> Self {
+        match name {
+            ThemeName::Default => Theme::Default,
+            ThemeName::Solarized => Theme::Solarized,
+            ThemeName::Monochrome => Theme::Monochrome,
+            ThemeName::HighContrast => Theme::HighContrast,
+        }
+    }
 }
 
 #[derive(Subcommand, Debug)]
 enum Commands {
+    /// Write a config file, asking for the main settings with --interactive
+    Init(Init),
+
     /// Create new Lists
     #[clap(visible_alias = "n")]
     New(New),
@@ -21,7 +170,7 @@ enum Commands {
     Add(Add),
 
     /// Display Lists/Items
-    #[clap(visible_alias = "l")]
+    #[clap(visible_aliases = ["l", "show"])]
     List(List),
 
     /// Get a Random Item
@@ -32,179 +181,5081 @@ enum Commands {
     #[clap(visible_aliases = ["d", "del"])]
     Delete(Delete),
 
+    /// Go through the Items of a List one at a time, deciding which to keep
+    Review(Review),
+
     /// Searches for Items in a list
     #[clap(visible_aliases = ["se"])]
     Search(Search),
+
+    /// Rename a List
+    #[clap(visible_aliases = ["rn"])]
+    Rename(Rename),
+
+    /// Copy a List with all of its Items into a new List
+    #[clap(visible_aliases = ["cp"])]
+    Copy(Copy),
+
+    /// Give a List a short alias to use instead of its title, or show the aliases
+    Alias(Alias),
+
+    /// Edit the text of an Item
+    #[clap(visible_aliases = ["ed"])]
+    Edit(Edit),
+
+    /// Edit a List in the editor from VISUAL or EDITOR, one Item per line
+    ///
+    /// Lines added become Items, Items whose lines are deleted are moved to the trash and
+    /// the Items are put in the order of the lines
+    EditList(EditList),
+
+    /// Move an Item to another List
+    #[clap(visible_aliases = ["mv"])]
+    Move(Move),
+
+    /// Change the status of every matching Item at once, after showing what will change
+    SetStatus(SetStatus),
+
+    /// Change the settings of a List
+    Set(Set),
+
+    /// Rate an Item from 1 to 10
+    Rate(Rate),
+
+    /// Pin an Item so it's shown before the other Items of its List
+    Pin(Pin),
+
+    /// Move an Item up or down its List
+    MoveItem(MoveItem),
+
+    /// Show the next Item of a List worked through in order, and take it off the front
+    Next(Next),
+
+    /// Track the episode or chapter reached in an Item, or show Items in progress
+    Progress(Progress),
+
+    /// Set the date an Item is due by, like when it leaves a streaming service
+    Due(Due),
+
+    /// Remind of Items which are due soon, printing them and raising a desktop notification
+    ///
+    /// Meant to be run from cron or a systemd timer. Items which are overdue are included,
+    /// and ones already watched or dropped are left out
+    Notify(Notify),
+
+    /// Set how long an Item takes to watch, used to forecast the hours left in a List
+    Runtime(Runtime),
+
+    /// Show or set the IDs of an Item on other services, like IMDb or TMDB
+    Id(Id),
+
+    /// Set the link of an Item to its page elsewhere, like on a streaming service or IMDb
+    SetUrl(SetUrl),
+
+    /// Open the link of an Item in the web browser
+    Open(Open),
+
+    /// Move all Items from a List into another and delete it
+    #[clap(visible_aliases = ["m"])]
+    Merge(Merge),
+
+    /// Add the Lists and Items from another watchlist file
+    #[clap(visible_aliases = ["mf"])]
+    MergeFile(MergeFile),
+
+    /// Show the Lists and Items added, removed or changed in another watchlist file
+    Diff(Diff),
+
+    /// Export Lists to CSV, Markdown or plain text
+    #[clap(visible_aliases = ["e", "ex"])]
+    Export(Export),
+
+    /// Import Items from a file into a List
+    #[clap(visible_aliases = ["i", "im"])]
+    Import(Import),
+
+    /// Add the title of a web page as an Item
+    #[cfg(feature = "net")]
+    #[clap(visible_aliases = ["g"])]
+    Grab(Grab),
+
+    /// Draft Items in a temporary List before adding them to a List
+    Tmp(Tmp),
+
+    /// Stage changes to review and apply together
+    Stage(Stage),
+
+    /// Undo the last command which changed the Lists
+    Undo,
+
+    /// Run commands one after another, saving the Lists when done
+    Shell,
+
+    /// Run the commands in a file, one per line, saving the Lists only if every one succeeds
+    ///
+    /// Commands are written like in `wl shell`, and blank lines and ones starting with #
+    /// are left out. The commands are all checked before any of them runs, and if one of
+    /// them fails the Lists are left as they were
+    Batch(Batch),
+
+    /// Show or create profiles, separate sets of Lists kept in their own files
+    Profile(ProfileArgs),
+
+    /// Print the script which sets up tab completion for a shell
+    ///
+    /// For example, add `source <(wl completions bash)` to ~/.bashrc
+    Completions(Completions),
+
+    /// Rebuild the index used to search the Lists
+    Reindex,
+
+    /// Show the commands which changed the Lists and when they were run
+    History(HistoryArgs),
+
+    /// Show the commits of the data file, when `git` is turned on in the config file
+    Log(HistoryArgs),
+
+    /// Undo the changes a commit of the data file made, keeping the changes made since
+    Revert(Revert),
+
+    /// Check the data file for problems, like parts of it which can't be read
+    ///
+    /// Each problem is shown with the line of the file it's on where that can be told.
+    /// Problems which can be repaired are with --fix, keeping a copy of the file first
+    Check(Check),
+
+    /// Count the Items in each List, or draw a heatmap of activity over the last year
+    Stats(Stats),
+
+    /// Estimate how long clearing a List will take at the recent rate of completing Items
+    Forecast(Forecast),
+
+    /// Show, restore or remove deleted Lists and Items
+    Trash(TrashArgs),
+
+    /// Move the watched Items of a List, or the ones picked, into the archive, or show it
+    Archive(ArchiveArgs),
+
+    /// Serve the Lists as a web page and JSON API, or share a List as a read-only web page
+    Serve(Serve),
+
+    /// Encrypt the data file with a passphrase, or change the passphrase it's encrypted with
+    #[cfg(feature = "encryption")]
+    Encrypt(Encrypt),
+
+    /// Save the data file without encryption again
+    #[cfg(feature = "encryption")]
+    Decrypt,
+
+    /// Keep the Lists in memory for other commands, which use it while it's running
+    ///
+    /// Commands are run by the daemon in the terminal they were started in, without
+    /// reading the data file again each time
+    #[cfg(unix)]
+    Daemon,
+
+    /// Browse and edit the Lists in an interactive terminal interface
+    #[cfg(feature = "tui")]
+    Tui,
+
+    /// Sync a List with a web service
+    #[cfg(feature = "net")]
+    Sync(Sync),
+
+    /// Upload the data file to the WebDAV server or S3 bucket set as `remote` in the config file
+    #[cfg(feature = "net")]
+    Push(Transfer),
+
+    /// Replace the Lists with the data file on the WebDAV server or S3 bucket set as `remote` in the config file
+    #[cfg(feature = "net")]
+    Pull(Transfer),
+}
+
+#[derive(Args, Debug)]
+struct Init {
+    /// Ask where to store the Lists, which Lists to create and which colors to use
+    #[clap(long, short)]
+    pub interactive: bool,
 }
 
 #[derive(Args, Debug)]
 struct New {
     /// Title of new list
     pub list: String,
+
+    /// Save a smart List instead, showing the Items from every List which match
+    /// a filter like "tag=horror AND status=unwatched"
+    #[clap(long, value_name = "FILTER")]
+    pub smart: Option<String>,
+
+    /// Leave the list out when `wl random` picks a list, like for finished items
+    #[clap(long, conflicts_with = "smart")]
+    pub no_random: bool,
 }
 
 #[derive(Args, Debug)]
 struct Add {
     /// List Title
+    ///
+    /// Without an existing list followed by items, every title given is an item
+    /// and the list they most likely belong in is suggested
+    #[clap(add = ArgValueCandidates::new(list_titles))]
     pub list: String,
     /// Items to be added
     ///
     /// Multiple items can be specified as space separated values
-    #[clap(required = true)]
     pub items: Vec<String>,
 
+    /// List to add the items to, making every title given an item
+    #[clap(
+        long = "list",
+        short = 'l',
+        value_name = "LIST",
+        add = ArgValueCandidates::new(list_titles)
+    )]
+    pub to: Option<String>,
+
     /// Ignore additions of duplicate
     /// items to the same list
     #[clap(long, short)]
     pub ignore_duplicate: bool,
+
+    /// How to tell if an item is already in the list, instead of the way in the config file
+    #[clap(
+        long,
+        value_enum,
+        value_name = "POLICY",
+        conflicts_with = "ignore_duplicate"
+    )]
+    pub duplicates: Option<Duplicates>,
+
+    /// Tag to attach to the items, can be repeated
+    #[clap(long = "tag", short)]
+    pub tags: Vec<String>,
+
+    /// Read more items from standard input, one per line until it ends, skipping the ones
+    /// already in the list instead of stopping at them
+    #[clap(long, conflicts_with = "to")]
+    pub stdin: bool,
+
+    /// Link to the page of the item, like on a streaming service or IMDb
+    #[clap(long, conflicts_with = "stdin")]
+    pub url: Option<String>,
+
+    /// Look up the items on TMDB and pick the right movie
+    #[cfg(feature = "tmdb")]
+    #[clap(long)]
+    pub lookup: bool,
+}
+
+impl Add {
+    // --ignore-duplicate is kept as the short way of allowing duplicates
+    fn duplicates(&self, config: &Config) -> DuplicatePolicy {
+        match (self.ignore_duplicate, self.duplicates) {
+            (true, _) => DuplicatePolicy::Allow,
+            (false, Some(duplicates)) => duplicates.into(),
+            (false, None) => config.duplicates,
+        }
+    }
+
+    // The list the items are added to and the items themselves
+    fn target(&self) -> (&String, Vec<&String>) {
+        match &self.to {
+            Some(to) => (to, std::iter::once(&self.list).chain(&self.items).collect()),
+            None => (&self.list, self.items.iter().collect()),
+        }
+    }
+
+    // Only an existing list followed by items is taken as the list to add them to, or
+    // the list given along with --stdin
+    fn needs_list(&self, watchlists: &WatchList) -> bool {
+        !self.stdin
+            && self.to.is_none()
+            && (self.items.is_empty() || !watchlists.contains(&self.list))
+    }
 }
 
 #[derive(Args, Debug)]
 #[command(group(ArgGroup::new("list_group").args(["list", "all_items"])))]
 struct List {
     /// List from which to display items
+    #[clap(add = ArgValueCandidates::new(list_titles))]
     pub list: Option<String>,
 
     /// Show all items from all lists
     /// excluding empty lists
     #[clap(long, short)]
     pub all_items: bool,
+
+    /// Show how many items each list has instead of the items
+    #[clap(long, conflicts_with_all = ["all_items", "sort", "fields", "due"])]
+    pub counts: bool,
+
+    /// Only show items with this tag, can be repeated
+    #[clap(long = "tag", short)]
+    pub tags: Vec<String>,
+
+    /// Order to show the items in, the one set with `wl set --sort` or added by default
+    #[clap(long, short, value_enum)]
+    pub sort: Option<Sort>,
+
+    /// Comma separated fields to show for each item, like title,status,tags
+    #[clap(long, value_delimiter = ',')]
+    pub fields: Vec<String>,
+
+    /// Show items with a due date, soonest first,
+    /// from the given list or from all lists
+    #[clap(long, conflicts_with_all = ["all_items", "sort"])]
+    pub due: bool,
+
+    /// Show the items added last, newest first, from the given list or from all lists
+    #[clap(long, value_name = "N", num_args = 0..=1, default_missing_value = "10")]
+    #[clap(conflicts_with_all = ["all_items", "sort", "due", "counts"])]
+    pub recent: Option<usize>,
+
+    /// Show the items added since a date, like 2weeks or 2023-03-01, newest first
+    #[clap(long, value_name = "DATE", conflicts_with_all = ["all_items", "sort", "due", "counts"])]
+    pub since: Option<String>,
+
+    /// How to show the items, json, tsv and plain are meant for other programs
+    #[clap(long, value_enum, default_value_t = Output::Table)]
+    pub format: Output,
+
+    /// Show the lists with the same title in every file of the workspace together
+    #[clap(long)]
+    pub workspace_all: bool,
+
+    /// Print every item even when they don't fit in the terminal, instead of using a pager
+    #[clap(long)]
+    pub no_pager: bool,
+}
+
+impl List {
+    // Order asked for, or the one set for the list
+    fn sort_by(&self, watchlists: &WatchList, title: &str) -> SortBy {
+        self.sort
+            .map_or_else(|| watchlists.list_sort(title), SortBy::from)
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Duplicates {
+    /// Titles have to be exactly the same
+    Exact,
+    /// Titles which only differ in case or spacing are the same
+    Normalized,
+    /// Titles which only differ in case, spacing or accents are the same
+    Folded,
+    /// Add the items anyway
+    Allow,
+}
+
+impl From<Duplicates> for DuplicatePolicy {
+    fn from(duplicates: Duplicates) -> Self {
+        match duplicates {
+            Duplicates::Exact => DuplicatePolicy::Exact,
+            Duplicates::Normalized => DuplicatePolicy::Normalized,
+            Duplicates::Folded => DuplicatePolicy::Folded,
+            Duplicates::Allow => DuplicatePolicy::Allow,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Output {
+    /// Numbered table
+    Table,
+    /// Array of objects with the list, number and every field of each item
+    Json,
+    /// Rows with the list, number and every field of each item, separated by tabs
+    Tsv,
+    /// Only the titles, or the chosen fields, one item per line
+    Plain,
+}
+
+impl Output {
+    // Format used instead of the table, if any
+    fn machine(self) -> Option<OutputFormat> {
+        match self {
+            Output::Table => None,
+            Output::Json => Some(OutputFormat::Json),
+            Output::Tsv => Some(OutputFormat::Tsv),
+            Output::Plain => Some(OutputFormat::Plain),
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Sort {
+    /// The order the items were added in
+    Added,
+    /// By title
+    Alpha,
+    /// Highest rated first
+    Rating,
+    /// Shuffled
+    Random,
+}
+
+impl From<Sort> for SortBy {
+    fn from(sort: Sort) -> Self {
+        match sort {
+            Sort::Added => SortBy::Added,
+            Sort::Alpha => SortBy::Alpha,
+            Sort::Rating => SortBy::Rating,
+            Sort::Random => SortBy::Random,
+        }
+    }
 }
 
 #[derive(Args, Debug)]
 struct Random {
     // List from which to get random item
+    #[clap(add = ArgValueCandidates::new(list_titles))]
     pub name: Option<String>,
+
+    /// Only pick items with this tag, can be repeated
+    #[clap(long = "tag", short)]
+    pub tags: Vec<String>,
+
+    /// How to show the item, json, tsv and plain are meant for other programs
+    #[clap(long, value_enum, default_value_t = Output::Table)]
+    pub format: Output,
+
+    /// Pick lists with more items more often when no list is given, so every item is as likely
+    #[clap(long, short, conflicts_with = "name")]
+    pub weighted: bool,
+
+    /// Pick from every item, including the ones picked recently
+    #[clap(long)]
+    pub allow_repeat: bool,
+
+    /// Number to start the random picks from, so the same seed picks the same items from
+    /// the same lists every time. Recent picks aren't skipped, like with --allow-repeat
+    #[clap(long, conflicts_with = "daily")]
+    pub seed: Option<u64>,
+
+    /// Pick this many different items at once
+    #[clap(long, short = 'n', value_name = "N")]
+    pub count: Option<usize>,
+
+    /// Pick the same item every time until the day is over, like for a status bar
+    #[clap(long, conflicts_with = "count")]
+    pub daily: bool,
+
+    /// Ask whether to accept the item picked or reroll for another one
+    #[clap(long, short, conflicts_with_all = ["count", "daily", "format"])]
+    pub interactive: bool,
+
+    /// Open the link of the item picked in the web browser
+    #[clap(long, short, conflicts_with = "count")]
+    pub open: bool,
+
+    /// Copy the title of the item picked to the clipboard, one per line with --count
+    #[cfg(feature = "clipboard")]
+    #[clap(long)]
+    pub copy: bool,
 }
 
 #[derive(Args, Debug)]
-struct Delete {
-    /// List to delete
+struct Rename {
+    /// List to rename
+    #[clap(add = ArgValueCandidates::new(list_titles))]
+    pub list: String,
+    /// New title of the list
+    pub new_title: String,
+}
+
+#[derive(Args, Debug)]
+struct Copy {
+    /// List to copy
+    #[clap(add = ArgValueCandidates::new(list_titles))]
     pub list: String,
-    /// Search prompt for deletion
+    /// Title of the new list
+    pub new_title: String,
+}
+
+#[derive(Args, Debug)]
+struct Alias {
+    /// Alias to give the list, which can be used anywhere a list is expected
     ///
-    /// Gives a list of items from the list which match the prompt
+    /// Shows the list the alias is for when no list is given, and every alias when the alias
+    /// isn't given either
+    pub name: Option<String>,
+    /// List the alias is for
+    #[clap(add = ArgValueCandidates::new(list_titles))]
+    pub list: Option<String>,
+
+    /// Remove the alias instead
+    #[clap(long, short, requires = "name", conflicts_with = "list")]
+    pub remove: bool,
+}
+
+#[derive(Args, Debug)]
+struct Edit {
+    /// List containing the item
+    #[clap(add = ArgValueCandidates::new(list_titles))]
+    pub list: String,
+    /// Search prompt for the item to edit
+    #[clap(required_unless_present = "index")]
+    #[clap(add = ArgValueCandidates::new(item_titles))]
     pub prompt: Option<String>,
+
+    /// Edit the item with this number in `wl show` instead of searching for it
+    #[clap(long, short = 'n', value_name = "N", conflicts_with = "prompt")]
+    pub index: Option<usize>,
 }
 
 #[derive(Args, Debug)]
-struct Search {
-    /// List to search
+struct Batch {
+    /// File with the commands to run, or - to read them from standard input
+    pub file: PathBuf,
+}
+
+#[derive(Args, Debug)]
+struct EditList {
+    /// List to edit
+    #[clap(add = ArgValueCandidates::new(list_titles))]
     pub list: String,
-    /// Search prompt
-    pub prompt: String,
 }
 
-fn cli_delete(watchlists: &mut WatchList, delete: &Delete) -> Result<(), WatchListError> {
-    if let Some(i) = &delete.prompt {
-        let results = watchlists.list_search(&delete.list, i)?;
-        if results.is_empty() {
-            println!("No Matches");
-            return Ok(());
-        }
-        wlist::list_display(&results, "Matched Items");
+#[derive(Args, Debug)]
+struct Move {
+    /// List containing the item
+    #[clap(add = ArgValueCandidates::new(list_titles))]
+    pub from: String,
+    /// List to move the item to
+    #[clap(add = ArgValueCandidates::new(list_titles))]
+    pub to: String,
+    /// Item to move
+    #[clap(required_unless_present = "index")]
+    #[clap(add = ArgValueCandidates::new(item_titles))]
+    pub item: Option<String>,
 
-        // Validate input
-        let index: usize = loop {
-            let input = wlist::input("Enter Item to Delete (DEFAULT: 1): ", true)?;
-            // Default option
-            if input.is_empty() {
-                break 1;
-            }
-            if let Ok(n) = input.parse::<usize>() {
-                if 0 < n && n <= results.len() {
-                    break n;
-                }
-            }
-        };
+    /// Move the item with this number in `wl show` instead of giving its title
+    #[clap(long, short = 'n', value_name = "N", conflicts_with = "item")]
+    pub index: Option<usize>,
 
-        // Converts from 1-indexed list back to 0-indexed list
-        watchlists.item_remove(&delete.list, &results[index - 1].to_string())?;
-        println!("Item Deleted");
-        return Ok(());
-    }
+    /// Keep the item in the original list
+    #[clap(long, short)]
+    pub copy: bool,
+}
 
-    match wlist::input(
-        &format!(
-            "Are you sure you want to delete the list '{}'? (y/N): ",
-            &delete.list
-        ),
-        true,
-    )?
-    .to_lowercase()
-    .as_str()
-    {
-        "y" => {
-            watchlists.list_remove(&delete.list)?;
-            println!("Deleted List '{}'", &delete.list);
-        }
-        _ => println!("Deleting Cancelled"),
-    }
-    Ok(())
+#[derive(Args, Debug)]
+#[command(group(
+    ArgGroup::new("setting")
+        .args(["no_random", "random", "sort"])
+        .required(true)
+        .multiple(true)
+))]
+struct Set {
+    /// List to change
+    #[clap(add = ArgValueCandidates::new(list_titles))]
+    pub list: String,
+
+    /// Leave the list out when `wl random` picks a list
+    #[clap(long)]
+    pub no_random: bool,
+
+    /// Let `wl random` pick the list again
+    #[clap(long, conflicts_with = "no_random")]
+    pub random: bool,
+
+    /// Order `wl show` shows the items of the list in unless another is asked for
+    #[clap(long, value_enum)]
+    pub sort: Option<Sort>,
 }
 
-pub fn cli_run(watchlists: &mut WatchList) -> Result<(), WatchListError> {
-    let cli = Cli::parse();
+#[derive(Args, Debug)]
+struct Rate {
+    /// List containing the item
+    #[clap(add = ArgValueCandidates::new(list_titles))]
+    pub list: String,
+    /// Item to rate
+    #[clap(add = ArgValueCandidates::new(item_titles))]
+    pub item: String,
+    /// Rating from 1 to 10
+    pub rating: u8,
+}
 
-    match &cli.commands {
-        Commands::New(new) => {
-            watchlists.list_add(&new.list)?;
-            println!("List Created!");
-        }
-        Commands::Add(add) => {
-            for item in &add.items {
-                watchlists.item_add(&add.list, item, add.ignore_duplicate)?
-            }
-            println!("Item(s) Added!");
-        }
-        Commands::List(list) => {
-            let all_lists = watchlists.list_get_all()?;
-            if list.all_items {
-                for list in all_lists {
-                    // Index into watchlists cannot fail as the list_get_all method
-                    // already returns all the keys of watchlists
-                    // TODO: figure out a better way to do this though
-                    let items = &watchlists[list];
-                    if !items.is_empty() {
-                        wlist::list_display(items, list);
-                        println!();
-                    }
-                }
-            // Display List Items
-            } else if let Some(l) = &list.list {
-                let items = watchlists.item_get_all(l)?;
-                wlist::list_display(items, l);
-            // Display All List Titles
-            } else {
-                wlist::list_display(&all_lists, "All Lists");
-            }
-        }
-        Commands::Random(random) => {
-            if let Some(n) = &random.name {
-                println!("{}", watchlists.item_get_random(n)?);
-            } else {
-                println!(
-                    "{}",
-                    loop {
-                        let list = watchlists.list_get_random()?;
-                        // list will always be a key of watchlists
-                        // due to the list_get_random function
-                        if !watchlists[list].is_empty() {
-                            break watchlists.item_get_random(list)?;
-                        }
-                    }
-                );
-            }
-        }
-        Commands::Delete(delete) => cli_delete(watchlists, delete)?,
-        Commands::Search(search) => {
-            let results = watchlists.list_search(&search.list, &search.prompt)?;
-            if results.is_empty() {
-                println!("No Matches");
-                return Ok(());
-            }
-            wlist::list_display(&results, "Matches");
+#[derive(Args, Debug)]
+struct Next {
+    /// List to take the item from
+    #[clap(add = ArgValueCandidates::new(list_titles))]
+    pub list: String,
+
+    /// Take the item off the front without asking
+    #[clap(long)]
+    pub pop: bool,
+
+    /// Mark the item watched instead of removing it
+    #[clap(long, short)]
+    pub watched: bool,
+}
+
+#[derive(Args, Debug)]
+struct Pin {
+    /// List containing the item
+    #[clap(add = ArgValueCandidates::new(list_titles))]
+    pub list: String,
+    /// Item to pin
+    #[clap(add = ArgValueCandidates::new(item_titles))]
+    pub item: String,
+
+    /// Unpin the item instead
+    #[clap(long, short)]
+    pub unpin: bool,
+}
+
+#[derive(Args, Debug)]
+#[command(group(ArgGroup::new("direction").args(["up", "down", "to"]).required(true)))]
+struct MoveItem {
+    /// List containing the item
+    #[clap(add = ArgValueCandidates::new(list_titles))]
+    pub list: String,
+    /// Item to move
+    #[clap(add = ArgValueCandidates::new(item_titles))]
+    pub item: String,
+
+    /// Move the item up by this many places
+    #[clap(long, value_name = "N", num_args = 0..=1, default_missing_value = "1")]
+    pub up: Option<usize>,
+
+    /// Move the item down by this many places
+    #[clap(long, value_name = "N", num_args = 0..=1, default_missing_value = "1")]
+    pub down: Option<usize>,
+
+    /// Move the item to this place in the list, counting from 1
+    #[clap(long, value_name = "N")]
+    pub to: Option<usize>,
+}
+
+#[derive(Args, Debug)]
+struct Progress {
+    /// List containing the item
+    #[clap(add = ArgValueCandidates::new(list_titles))]
+    pub list: String,
+    /// Item to track, all Items in progress are shown if not given
+    #[clap(requires = "progress")]
+    #[clap(add = ArgValueCandidates::new(item_titles))]
+    pub item: Option<String>,
+    /// Episode or chapter reached, like s3e5 or ch120
+    pub progress: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct Due {
+    /// List containing the item
+    #[clap(add = ArgValueCandidates::new(list_titles))]
+    pub list: String,
+    /// Item to set the due date of
+    #[clap(add = ArgValueCandidates::new(item_titles))]
+    pub item: String,
+    /// Date the item is due by, like 2023-03-01, friday or "in 2 weeks"
+    #[clap(required_unless_present = "clear")]
+    pub date: Option<String>,
+
+    /// Remove the due date instead
+    #[clap(long, conflicts_with = "date")]
+    pub clear: bool,
+}
+
+#[derive(Args, Debug)]
+struct Notify {
+    /// Number of days ahead to remind of, `due_soon_days` from the config file if not given
+    #[clap(long, short)]
+    pub days: Option<u32>,
+
+    /// Only print the reminder, without raising a desktop notification
+    #[cfg(feature = "notify")]
+    #[clap(long)]
+    pub no_desktop: bool,
+}
+
+#[derive(Args, Debug)]
+struct Runtime {
+    /// List containing the item
+    #[clap(add = ArgValueCandidates::new(list_titles))]
+    pub list: String,
+    /// Item to set the runtime of
+    #[clap(add = ArgValueCandidates::new(item_titles))]
+    pub item: String,
+    /// Time the item takes to watch, like 1h30m, 45m or 90
+    #[clap(required_unless_present = "clear")]
+    pub runtime: Option<String>,
+
+    /// Remove the runtime instead
+    #[clap(long, conflicts_with = "runtime")]
+    pub clear: bool,
+}
+
+#[derive(Args, Debug)]
+struct SetUrl {
+    /// List containing the item
+    #[clap(add = ArgValueCandidates::new(list_titles))]
+    pub list: String,
+    /// Item to set the link of
+    #[clap(add = ArgValueCandidates::new(item_titles))]
+    pub item: String,
+    /// Web address of the page, starting with http:// or https://
+    #[clap(required_unless_present = "clear")]
+    pub url: Option<String>,
+
+    /// Remove the link instead
+    #[clap(long, conflicts_with = "url")]
+    pub clear: bool,
+}
+
+#[derive(Args, Debug)]
+struct Open {
+    /// List containing the item
+    #[clap(add = ArgValueCandidates::new(list_titles))]
+    pub list: String,
+    /// Item to open the link of
+    #[clap(add = ArgValueCandidates::new(item_titles))]
+    pub item: String,
+}
+
+#[derive(Args, Debug)]
+struct Id {
+    /// List containing the item
+    #[clap(add = ArgValueCandidates::new(list_titles))]
+    pub list: String,
+    /// Item to show or set the IDs of
+    #[clap(add = ArgValueCandidates::new(item_titles))]
+    pub item: String,
+
+    #[clap(subcommand)]
+    pub command: Option<IdCommands>,
+}
+
+#[derive(Subcommand, Debug)]
+enum IdCommands {
+    /// Set the ID of the Item on a service, like `set imdb tt0133093`
+    Set {
+        /// Service the ID belongs to, like imdb, tmdb or anilist
+        service: String,
+        /// ID of the Item on the service
+        id: String,
+    },
+    /// Remove the ID of the Item on a service
+    Remove {
+        /// Service to remove the ID of
+        service: String,
+    },
+}
+
+#[derive(Args, Debug)]
+struct Merge {
+    /// List to merge and delete
+    #[clap(add = ArgValueCandidates::new(list_titles))]
+    pub from: String,
+    /// List to move the items to
+    #[clap(add = ArgValueCandidates::new(list_titles))]
+    pub to: String,
+}
+
+#[derive(Args, Debug)]
+struct MergeFile {
+    /// Watchlist file to merge
+    pub file: PathBuf,
+
+    /// How to handle items which are in both files
+    #[clap(long, short, value_enum, default_value_t = MergeMode::Keep)]
+    pub strategy: MergeMode,
+}
+
+#[derive(Args, Debug)]
+struct Diff {
+    /// Watchlist file to compare the Lists with, like a backup or the copy from another machine
+    pub file: PathBuf,
+
+    /// How to show the differences, json is meant for other programs
+    #[clap(long, value_enum, default_value_t = DiffFormat::Text)]
+    pub format: DiffFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum DiffFormat {
+    /// A line for each difference, like `+ Heat (Movies)`
+    Text,
+    /// Array of objects with the kind of change, the list and the item
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum MergeMode {
+    /// Keep the item from this file
+    Keep,
+    /// Replace the item with the one from the other file
+    Replace,
+    /// Fill in details missing from the item with the other one
+    Combine,
+}
+
+impl From<MergeMode> for MergeStrategy {
+    fn from(mode: MergeMode) -> Self {
+        match mode {
+            MergeMode::Keep => MergeStrategy::Keep,
+            MergeMode::Replace => MergeStrategy::Replace,
+            MergeMode::Combine => MergeStrategy::Combine,
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+struct SetStatus {
+    /// List containing the items
+    #[clap(add = ArgValueCandidates::new(list_titles))]
+    pub list: String,
+
+    /// Only change items whose titles contain this
+    #[clap(long, short)]
+    pub search: Option<String>,
+
+    /// Only change items matching a filter, like "tag=horror AND year<2000"
+    #[clap(long)]
+    pub filter: Option<String>,
+
+    /// Status to change the items to
+    #[clap(long, value_enum, value_name = "STATUS")]
+    pub to: ItemStatus,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ItemStatus {
+    Unwatched,
+    Watched,
+    /// Given up on without finishing it
+    Dropped,
+}
+
+impl From<ItemStatus> for Status {
+    fn from(status: ItemStatus) -> Self {
+        match status {
+            ItemStatus::Unwatched => Status::Unwatched,
+            ItemStatus::Watched => Status::Watched,
+            ItemStatus::Dropped => Status::Dropped,
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+struct Tmp {
+    #[clap(subcommand)]
+    pub command: TmpCommands,
+}
+
+#[derive(Subcommand, Debug)]
+enum TmpCommands {
+    /// Add Items to the temporary List
+    Add {
+        /// Items to be added
+        #[clap(required = true)]
+        items: Vec<String>,
+    },
+    /// Display the temporary List
+    Show,
+    /// Add the Items to a List (creating it if needed) and clear the temporary List
+    Commit {
+        /// List to add the items to
+        list: String,
+    },
+    /// Clear the temporary List
+    Discard,
+}
+
+#[derive(Args, Debug)]
+struct Stage {
+    #[clap(subcommand)]
+    pub command: StageCommands,
+}
+
+#[derive(Subcommand, Debug)]
+enum StageCommands {
+    /// Stage adding Items to a List
+    Add {
+        /// List to add the Items to
+        list: String,
+        /// Items to be added
+        #[clap(required = true)]
+        items: Vec<String>,
+    },
+    /// Stage removing Items from a List
+    Remove {
+        /// List to remove the Items from
+        list: String,
+        /// Items to be removed
+        #[clap(required = true)]
+        items: Vec<String>,
+    },
+    /// Stage moving Items from one List to another
+    Move {
+        /// List to move the Items from
+        from: String,
+        /// List to move the Items to
+        to: String,
+        /// Items to be moved
+        #[clap(required = true)]
+        items: Vec<String>,
+    },
+    /// Show the staged changes
+    Diff,
+    /// Apply all the staged changes, or none if any of them fail
+    Apply,
+    /// Clear the staged changes
+    Reset,
+}
+
+#[cfg(feature = "encryption")]
+#[derive(Args, Debug)]
+struct Encrypt {
+    /// Save the passphrase to the system keyring, so it isn't asked for
+    #[clap(long)]
+    pub keyring: bool,
+}
+
+#[derive(Args, Debug)]
+struct Serve {
+    /// List to share as a read-only web page, instead of serving every List through the web page and JSON API
+    #[clap(long)]
+    pub public: Option<String>,
+
+    /// Don't allow any changes, which is always the case for a shared List
+    #[clap(long)]
+    pub read_only: bool,
+
+    /// Address to listen on
+    #[clap(long, short, default_value = "127.0.0.1:8080")]
+    pub address: String,
+
+    /// Port to listen on at 127.0.0.1, instead of the whole address
+    #[clap(long, conflicts_with = "address")]
+    pub port: Option<u16>,
+
+    /// Token the API needs in the `Authorization: Bearer` header of every request, taken from WATCHLIST_SERVE_TOKEN if it isn't given
+    #[clap(long, conflicts_with = "public")]
+    pub token: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct Delete {
+    /// List to delete
+    #[clap(add = ArgValueCandidates::new(list_titles))]
+    pub list: String,
+    /// Search prompts for deletion
+    ///
+    /// Gives a list of items from the list which match each prompt, allowing for typos,
+    /// with the closest matches first. Several of them can be picked, like 1,3-5
+    #[clap(add = ArgValueCandidates::new(item_titles))]
+    pub prompts: Vec<String>,
+
+    /// Delete the items with these numbers in `wl show` instead of searching for them,
+    /// can be repeated or separated by commas
+    #[clap(long, short = 'n', value_name = "N", value_delimiter = ',')]
+    pub index: Vec<usize>,
+
+    /// Pick the item to delete by typing part of its title, narrowing down the matches
+    #[clap(long, short, conflicts_with_all = ["prompts", "index"])]
+    pub interactive: bool,
+}
+
+impl Delete {
+    // Deleting items, rather than the whole list, is taken as having finished them.
+    // Each prompt is counted as one item, as the matches picked aren't logged
+    fn items_deleted(&self) -> usize {
+        self.prompts.len() + self.index.len() + usize::from(self.interactive)
+    }
+}
+
+#[derive(Args, Debug)]
+struct Review {
+    /// List to review
+    #[clap(add = ArgValueCandidates::new(list_titles))]
+    pub list: String,
+
+    /// List archived Items are moved to, created if it doesn't exist
+    #[clap(long, value_name = "LIST", default_value = "Archive")]
+    pub archive: String,
+
+    /// Start again from the first Item instead of carrying on from the last review
+    #[clap(long)]
+    pub restart: bool,
+}
+
+#[derive(Args, Debug)]
+struct Completions {
+    /// Shell to complete commands in
+    pub shell: Shell,
+}
+
+#[derive(Args, Debug)]
+struct HistoryArgs {
+    /// Only show this many of the most recent commands
+    #[clap(long, short)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Args, Debug)]
+struct Revert {
+    /// Commit to undo, like `a1b2c3d` from `wl log` or `HEAD~2`
+    pub commit: String,
+}
+
+#[derive(Args, Debug)]
+struct Check {
+    /// Repair the problems which can be, keeping a copy of the file as it was first
+    #[clap(long)]
+    pub fix: bool,
+}
+
+#[derive(Args, Debug)]
+struct Stats {
+    /// Draw the Items added and completed each day as a calendar, read from the history
+    #[clap(long)]
+    pub heatmap: bool,
+
+    /// How to show the counts, json is meant for other programs
+    #[clap(long, value_enum, default_value_t = StatsFormat::Table, conflicts_with = "heatmap")]
+    pub format: StatsFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum StatsFormat {
+    /// A line for each list followed by the totals
+    Table,
+    /// Object with the counts of each list and of all of them
+    Json,
+}
+
+#[derive(Args, Debug)]
+struct Forecast {
+    /// List to forecast
+    #[clap(add = ArgValueCandidates::new(list_titles))]
+    pub list: String,
+    /// Number of recent days to measure the rate of completing Items over
+    #[clap(long, short, default_value_t = forecast::DEFAULT_DAYS)]
+    pub days: u32,
+}
+
+#[derive(Args, Debug)]
+struct ProfileArgs {
+    #[clap(subcommand)]
+    pub command: ProfileCommands,
+}
+
+#[derive(Subcommand, Debug)]
+enum ProfileCommands {
+    /// Display the profiles, marking the one in use
+    List,
+    /// Create a profile without any Lists
+    New {
+        /// Name of the profile, made of letters, digits, - and _
+        name: String,
+    },
+    /// Use a profile by default, until another is given with --profile
+    Use {
+        /// Name of the profile, or `default` for the usual data file
+        name: String,
+    },
+}
+
+#[derive(Args, Debug)]
+struct TrashArgs {
+    #[clap(subcommand)]
+    pub command: TrashCommands,
+}
+
+#[derive(Subcommand, Debug)]
+enum TrashCommands {
+    /// Display the deleted Lists and Items
+    Show,
+    /// Put a deleted List or Item back
+    Restore {
+        /// Number of the entry as shown by `trash show`
+        number: usize,
+    },
+    /// Remove everything in the trash for good
+    Empty,
+}
+
+#[derive(Args, Debug)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct ArchiveArgs {
+    #[clap(subcommand)]
+    pub command: Option<ArchiveCommands>,
+
+    /// List to archive Items from
+    #[clap(required = true)]
+    #[clap(add = ArgValueCandidates::new(list_titles))]
+    pub list: Option<String>,
+    /// Search prompts for the Items to archive instead of the watched ones
+    #[clap(add = ArgValueCandidates::new(item_titles))]
+    pub prompts: Vec<String>,
+
+    /// Archive the items with these numbers in `wl show` instead of the watched ones, can
+    /// be repeated or separated by commas
+    #[clap(long, short = 'n', value_name = "N", value_delimiter = ',')]
+    pub index: Vec<usize>,
+}
+
+#[derive(Subcommand, Debug)]
+enum ArchiveCommands {
+    /// Display the archived Items under the List they were in
+    Show {
+        /// Only show the Items archived from this List
+        list: Option<String>,
+    },
+}
+
+#[derive(Args, Debug)]
+struct Search {
+    /// List to search
+    #[clap(required_unless_present = "all")]
+    #[clap(add = ArgValueCandidates::new(list_titles))]
+    pub list: Option<String>,
+    /// Search prompt
+    #[clap(required_unless_present_any = ["all", "interactive"])]
+    pub prompt: Option<String>,
+
+    /// Search every list instead, showing the matches under the title of their list
+    #[clap(long, value_name = "PROMPT")]
+    #[clap(conflicts_with_all = ["list", "prompt", "fuzzy", "regex"])]
+    pub all: Option<String>,
+
+    /// Allow typos and words in any order, showing the closest matches first
+    #[clap(long)]
+    pub fuzzy: bool,
+
+    /// Match the prompt as a regular expression, ignoring case unless it starts with (?-i)
+    #[clap(long, conflicts_with = "fuzzy")]
+    pub regex: bool,
+
+    /// Ignore accents too, so "pokemon" finds "Pokémon"
+    #[clap(long, short = 'a', conflicts_with_all = ["fuzzy", "regex"])]
+    pub ignore_accents: bool,
+
+    /// Only match items with this tag, can be repeated
+    #[clap(long = "tag", short)]
+    pub tags: Vec<String>,
+
+    /// Comma separated fields to show for each item, like title,status,tags
+    #[clap(long, value_delimiter = ',')]
+    pub fields: Vec<String>,
+
+    /// How to show the matches, json, tsv and plain are meant for other programs
+    #[clap(long, value_enum, default_value_t = Output::Table)]
+    pub format: Output,
+
+    /// Print every match even when they don't fit in the terminal, instead of using a pager
+    #[clap(long)]
+    pub no_pager: bool,
+
+    /// Pick one of the matches by typing part of its title, starting from the prompt, and
+    /// print only its title
+    #[clap(long, short)]
+    #[clap(conflicts_with_all = ["all", "fuzzy", "regex", "ignore_accents", "fields", "format"])]
+    pub interactive: bool,
+
+    /// Copy the titles of the matches to the clipboard, one per line
+    #[cfg(feature = "clipboard")]
+    #[clap(long)]
+    pub copy: bool,
+}
+
+#[derive(Args, Debug)]
+struct Export {
+    /// List to export
+    ///
+    /// Exports all lists if not specified
+    #[clap(add = ArgValueCandidates::new(list_titles))]
+    pub list: Option<String>,
+
+    /// Format to export in
+    #[clap(long, value_enum, default_value_t = Format::Txt)]
+    pub format: Format,
+
+    /// File to write to instead of stdout
+    #[clap(long, short)]
+    pub output: Option<PathBuf>,
+
+    /// Comma separated fields to show for each item, like title,status,tags
+    #[clap(long, value_delimiter = ',')]
+    pub fields: Vec<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Format {
+    Csv,
+    Json,
+    Md,
+    Txt,
+}
+
+impl From<Format> for ExportFormat {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Csv => ExportFormat::Csv,
+            Format::Json => ExportFormat::Json,
+            Format::Md => ExportFormat::Markdown,
+            Format::Txt => ExportFormat::Text,
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+struct Import {
+    /// List to import into
+    ///
+    /// The list is created if it doesn't already exist.
+    /// Defaults to "Anime" when importing from MyAnimeList
+    /// and "Movies" when importing from Letterboxd
+    #[clap(required_unless_present_any = ["mal", "letterboxd"])]
+    #[clap(add = ArgValueCandidates::new(list_titles))]
+    pub list: Option<String>,
+    /// File to import from
+    #[clap(required_unless_present_any = ["mal", "letterboxd"])]
+    pub file: Option<PathBuf>,
+
+    /// Format of the file
+    ///
+    /// Guessed from the file extension if not specified
+    #[clap(long, value_enum)]
+    pub from: Option<Source>,
+
+    /// Only import bookmarks from this folder
+    #[clap(long)]
+    pub folder: Option<String>,
+
+    /// Import from an XML export of a MyAnimeList anime list
+    ///
+    /// Completed entries are marked as watched and dropped entries are skipped
+    #[clap(long, value_name = "FILE", conflicts_with_all = ["file", "from", "folder"])]
+    pub mal: Option<PathBuf>,
+
+    /// Import from a CSV export of a Letterboxd watchlist
+    ///
+    /// The year and Letterboxd link of each movie are stored along with it
+    #[clap(long, value_name = "FILE", conflicts_with_all = ["file", "from", "folder", "mal"])]
+    pub letterboxd: Option<PathBuf>,
+
+    /// Also skip Items at least this similar to another, from 0 to 1
+    ///
+    /// Titles are compared ignoring case and punctuation, in the way set by `similarity`
+    /// in the config file. By default 1 skips Items which are only written differently,
+    /// like "Spider-Man" and "spider man"
+    #[clap(short, long, value_parser = parse_similarity)]
+    pub similar: Option<f64>,
+}
+
+fn parse_similarity(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(n) if (0.0..=1.0).contains(&n) => Ok(n),
+        _ => Err(format!("`{s}` isn't a number from 0 to 1")),
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Source {
+    /// One item per line
+    Txt,
+    /// Items from the item/title/name column or the first column
+    Csv,
+    /// Bookmarks exported from a browser
+    Bookmarks,
+}
+
+impl Source {
+    fn from_path(path: &Path) -> Self {
+        let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("csv") => Source::Csv,
+            Some("html" | "htm") => Source::Bookmarks,
+            _ => Source::Txt,
+        }
+    }
+}
+
+#[cfg(feature = "net")]
+#[derive(Args, Debug)]
+struct Grab {
+    /// Link to a YouTube video, movie or show
+    pub url: String,
+
+    /// List to add the item to
+    ///
+    /// The list is created if it doesn't already exist
+    #[clap(long, short, default_value = "Inbox")]
+    pub list: String,
+}
+
+#[cfg(feature = "net")]
+#[derive(Args, Debug)]
+struct Sync {
+    /// Service to sync with
+    #[clap(value_enum)]
+    pub service: Service,
+
+    /// How to resolve differences between the list and the service
+    #[clap(long, short, value_enum, default_value_t = Strategy::Merge)]
+    pub strategy: Strategy,
+}
+
+#[cfg(feature = "net")]
+#[derive(Args, Debug)]
+struct Transfer {
+    /// Overwrite it without asking, even if it changed since the last push or pull
+    #[clap(long)]
+    pub force: bool,
+}
+
+#[cfg(feature = "net")]
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Service {
+    /// Sync with the Trakt watchlist
+    Trakt,
+    /// Sync with the anime and manga "Planning" lists on AniList
+    Anilist,
+}
+
+#[cfg(feature = "net")]
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Strategy {
+    /// Keep items from both sides
+    Merge,
+    /// Make the service match the list
+    Local,
+    /// Make the list match the service
+    Remote,
+}
+
+#[cfg(feature = "net")]
+impl From<Strategy> for ConflictStrategy {
+    fn from(strategy: Strategy) -> Self {
+        match strategy {
+            Strategy::Merge => ConflictStrategy::Merge,
+            Strategy::Local => ConflictStrategy::LocalWins,
+            Strategy::Remote => ConflictStrategy::RemoteWins,
+        }
+    }
+}
+
+#[cfg(feature = "net")]
+fn cli_sync(
+    watchlists: &mut WatchList,
+    sync: &Sync,
+    config: &Config,
+    file_path: &Path,
+    yes: bool,
+) -> Result<(), WatchListError> {
+    let mut tokens = TokenStore::from_file(&wlist::sidecar_path(file_path, "tokens"))?;
+    let report = match sync.service {
+        Service::Trakt => {
+            let trakt_config = config.trakt.clone().unwrap_or_default();
+            let client_id = env::var("TRAKT_CLIENT_ID").unwrap_or(trakt_config.client_id);
+            let client_secret =
+                env::var("TRAKT_CLIENT_SECRET").unwrap_or(trakt_config.client_secret);
+            if client_id.is_empty() || client_secret.is_empty() {
+                eprintln!("Set the client ID and secret of a Trakt app (from https://trakt.tv/oauth/applications) in the config file to sync with Trakt");
+                return Ok(());
+            }
+
+            let mut trakt = Trakt::new(&client_id, &client_secret, &tokens);
+            trakt.authorize(&mut tokens, |url, code| {
+                println!("Go to {url} and enter the code {code} to allow access to Trakt");
+            })?;
+            trakt.sync(watchlists, &trakt_config.list, sync.strategy.into())?
+        }
+        Service::Anilist => {
+            let anilist_config = config.anilist.clone().unwrap_or_default();
+            let token = match (env::var("ANILIST_TOKEN"), tokens.get(anilist::SERVICE)) {
+                (Ok(access_token), _) => Token {
+                    access_token,
+                    refresh_token: None,
+                    expires_at: None,
+                },
+                (_, Some(token)) if !token.is_expired() => token.clone(),
+                _ if anilist_config.client_id.is_empty() => {
+                    eprintln!("Set the client ID of an AniList app (from https://anilist.co/settings/developer) in the config file to sync with AniList");
+                    return Ok(());
+                }
+                _ => {
+                    println!(
+                        "Go to {} to allow access to AniList",
+                        anilist::authorize_url(&anilist_config.client_id)
+                    );
+                    let token = Token {
+                        access_token: wlist::input("Paste the access token: ", true, yes)?,
+                        refresh_token: None,
+                        // AniList tokens are valid for a year
+                        expires_at: Some(wlist::dates::now() + chrono::TimeDelta::days(365)),
+                    };
+                    tokens.set(anilist::SERVICE, token.clone())?;
+                    token
+                }
+            };
+            let lists = [
+                (anilist_config.anime_list.as_str(), MediaType::Anime),
+                (anilist_config.manga_list.as_str(), MediaType::Manga),
+            ];
+            AniList::new(token).sync(watchlists, &lists, sync.strategy.into())?
+        }
+    };
+    status!(
+        "Pulled {} Item(s), Pushed {} Item(s), Removed {} Local Item(s) and {} Remote Item(s)",
+        report.pulled,
+        report.pushed,
+        report.removed_local,
+        report.removed_remote
+    );
+    Ok(())
+}
+
+#[cfg(feature = "net")]
+fn cli_push(
+    watchlists: &WatchList,
+    push: &Transfer,
+    config: &Config,
+    file_path: &Path,
+    yes: bool,
+) -> Result<(), WatchListError> {
+    let Some(remote_config) = &config.remote else {
+        eprintln!("Set `remote` in the config file to a WebDAV server or S3 bucket to push to");
+        return Ok(());
+    };
+    let location = remote::location(remote_config);
+    let state_path = wlist::sidecar_path(file_path, "remote");
+    let state = SyncState::from_file(&state_path)?;
+    let version = remote::version(remote_config)?;
+    if !push.force && remote::remote_changed(state.as_ref(), version.as_deref()) {
+        confirm_overwrite(format!("The copy at {location} changed"), yes)?;
+    }
+    // The Lists are saved once the command is done, but there has to be a file to push
+    if !file_path.exists() {
+        watchlists.to_file(file_path)?;
+    }
+    let version = remote::upload(remote_config, &fs::read(file_path)?)?;
+    SyncState {
+        version,
+        lists: remote::lists_hash(watchlists),
+    }
+    .to_file(&state_path)?;
+    status!("Pushed the Lists to {location}");
+    Ok(())
+}
+
+#[cfg(feature = "net")]
+fn cli_pull(
+    watchlists: &mut WatchList,
+    pull: &Transfer,
+    config: &Config,
+    file_path: &Path,
+    yes: bool,
+    run: &mut Run,
+) -> Result<(), WatchListError> {
+    let Some(remote_config) = &config.remote else {
+        eprintln!("Set `remote` in the config file to a WebDAV server or S3 bucket to pull from");
+        return Ok(());
+    };
+    let location = remote::location(remote_config);
+    let state_path = wlist::sidecar_path(file_path, "remote");
+    let state = SyncState::from_file(&state_path)?;
+    if !pull.force && remote::lists_changed(state.as_ref(), watchlists) {
+        confirm_overwrite("The Lists changed".to_string(), yes)?;
+    }
+    let Some((data, version)) = remote::download(remote_config)? else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("nothing was pushed to {location} yet"),
+        )
+        .into());
+    };
+    *watchlists = remote::replace_file(file_path, &data, |path| read_lists(path, run))?;
+    SyncState {
+        version,
+        lists: remote::lists_hash(watchlists),
+    }
+    .to_file(&state_path)?;
+    status!("Pulled the Lists from {location}");
+    Ok(())
+}
+
+// Asks whether to overwrite what changed since the last push or pull, refusing when it
+// can't ask
+#[cfg(feature = "net")]
+fn confirm_overwrite(changed: String, yes: bool) -> Result<(), WatchListError> {
+    let overwrite = (yes || io::stdin().is_terminal())
+        && wlist::confirm(
+            &format!("{changed} since the last push or pull, overwrite it? (y/N): "),
+            yes,
+        )?;
+    match overwrite {
+        true => Ok(()),
+        false => Err(WatchListError::RemoteChanged(changed)),
+    }
+}
+
+/// Runs `wl check` when it's the command given, before the Lists are read from the data
+/// file, since it has to run on files which can't be read
+pub fn run_check(config: &Config, file_path: &Path) -> Option<Result<(), WatchListError>> {
+    let cli = Cli::try_parse().ok()?;
+    let Commands::Check(check) = &cli.commands else {
+        return None;
+    };
+    if let Some(e) = usage_error(&cli, false) {
+        e.exit();
+    }
+    Some(cli_check(check, cli.compact || config.compact, file_path).map(|_| ()))
+}
+
+// Shows the problems found in the data file, repairing them with `--fix`, saved as compact
+// JSON if `compact` is given. Gives whether the file was repaired
+fn cli_check(check: &Check, compact: bool, file_path: &Path) -> Result<bool, WatchListError> {
+    if !file_path.exists() {
+        status!("There's no data file at {} yet", file_path.display());
+        return Ok(false);
+    }
+    let data = match encryption::read_file(file_path)? {
+        Some(data) => String::from_utf8(data)
+            .map_err(|e| WatchListError::ParseError(format!("the file isn't UTF-8, {e}")))?,
+        None => limits::read_file(file_path)?,
+    };
+    let report = check::check(&data);
+    if report.problems.is_empty() {
+        status!("No problems found in {}", file_path.display());
+        return Ok(false);
+    }
+    let lines: Vec<&str> = data.lines().collect();
+    for problem in &report.problems {
+        println!("{}", theme::paint(&problem.to_string(), Style::Warning));
+        if let Some(line) = problem.line.and_then(|l| lines.get(l - 1)) {
+            println!("    {}", theme::paint(line.trim(), Style::Detail));
+        }
+    }
+    let found = format!(
+        "{} problem(s) in {}",
+        report.problems.len(),
+        file_path.display()
+    );
+    let Some((watchlists, version)) = report.repaired.as_ref().filter(|_| report.can_repair())
+    else {
+        return Err(WatchListError::ParseError(format!(
+            "found {found}, which can't be repaired"
+        )));
+    };
+    if !check.fix {
+        return Err(WatchListError::ParseError(format!(
+            "found {found}\nRun `wl check --fix` to repair them"
+        )));
+    }
+
+    let backup = wlist::sidecar_path(
+        file_path,
+        &format!("backup-{}", dates::now().format("%Y%m%d%H%M%S")),
+    );
+    fs::copy(file_path, &backup)?;
+    let mut watchlists = watchlists.clone();
+    watchlists.set_compact(compact);
+    watchlists.to_file_with_version(file_path, schema::save_version(*version, &watchlists))?;
+    status!(
+        "Repaired {found}, keeping a copy of the file as it was at {}",
+        backup.display()
+    );
+    Ok(true)
+}
+
+fn cli_import(
+    watchlists: &mut WatchList,
+    im: &Import,
+    config: &Config,
+) -> Result<(), WatchListError> {
+    let (list, items) = if let Some(mal) = &im.mal {
+        let data = limits::read_file(mal)?;
+        let list = im.list.as_deref().unwrap_or("Anime");
+        (list, import::parse_mal(&data)?)
+    } else if let Some(letterboxd) = &im.letterboxd {
+        let data = limits::read_file(letterboxd)?;
+        let list = im.list.as_deref().unwrap_or("Movies");
+        (list, import::parse_letterboxd(&data)?)
+    } else {
+        // Both are required when not importing from a service
+        let (list, file) = (im.list.as_ref().unwrap(), im.file.as_ref().unwrap());
+        let data = limits::read_file(file)?;
+        let items = match im.from.unwrap_or_else(|| Source::from_path(file)) {
+            Source::Txt => import::parse_text(&data),
+            Source::Csv => import::parse_csv(&data)?,
+            Source::Bookmarks => import::parse_bookmarks(&data, im.folder.as_deref())?,
+        };
+        (list.as_str(), items)
+    };
+
+    limits::check_items(&items)?;
+    let report = match im.similar {
+        Some(threshold) => {
+            let similarity = config.similarity(list);
+            import::import_similar(watchlists, list, items, similarity, threshold)?
+        }
+        None => import::import_items(watchlists, list, items)?,
+    };
+    let mut message = format!(
+        "Imported {} Item(s), Skipped {} Duplicate(s)",
+        report.added, report.skipped
+    );
+    if report.updated > 0 {
+        message.push_str(&format!(", Marked {} Item(s) as Watched", report.updated));
+    }
+    status!("{message}");
+    Ok(())
+}
+
+#[cfg(feature = "tmdb")]
+fn cli_lookup(
+    watchlists: &mut WatchList,
+    add: &Add,
+    config: &Config,
+    yes: bool,
+) -> Result<(), WatchListError> {
+    let Some(api_key) = env::var("TMDB_API_KEY")
+        .ok()
+        .or(config.tmdb_api_key.clone())
+    else {
+        eprintln!("Set a TMDB API key (from https://www.themoviedb.org/settings/api) in the config file to look up movies");
+        return Ok(());
+    };
+
+    let duplicates = add.duplicates(config);
+    let (list, items) = add.target();
+    for title in items {
+        let matches = tmdb::search_movies(title, &api_key)?;
+        if matches.is_empty() {
+            status!("No Matches for '{title}', Adding as Typed");
+            let item = Item::new(title).with_added_at(dates::now());
+            watchlists.item_add(list, item, duplicates)?;
+            continue;
+        }
+        let names: Vec<String> = matches
+            .iter()
+            .map(|m| match m.meta.year {
+                Some(year) => format!("{m} ({year})"),
+                None => m.to_string(),
+            })
+            .collect();
+        wlist::list_display(&names, "Matched Movies")?;
+
+        // Validate input, 0 keeps the title as typed
+        let index: usize = loop {
+            let input = wlist::input_or_default(
+                "Enter Movie to Add (DEFAULT: 1, 0 to Add as Typed): ",
+                yes,
+            )?;
+            // Default option
+            if input.is_empty() {
+                break 1;
+            }
+            if let Ok(n) = input.parse::<usize>() {
+                if n <= matches.len() {
+                    break n;
+                }
+            }
+        };
+
+        let item = match index {
+            0 => Item::new(title),
+            // Converts from 1-indexed list back to 0-indexed list
+            n => matches[n - 1].clone(),
+        };
+        watchlists.item_add(list, item.with_added_at(dates::now()), duplicates)?;
+    }
+    status!("Item(s) Added!");
+    Ok(())
+}
+
+// Asks which of the numbered matches to use until a valid one is entered,
+// returning its index
+fn select_match(prompt: &str, matches: usize, yes: bool) -> Result<usize, WatchListError> {
+    // Validate input
+    let index: usize = loop {
+        let input = wlist::input_or_default(prompt, yes)?;
+        // Default option
+        if input.is_empty() {
+            break 1;
+        }
+        if let Ok(n) = input.parse::<usize>() {
+            if 0 < n && n <= matches {
+                break n;
+            }
+        }
+    };
+
+    // Converts from 1-indexed list back to 0-indexed list
+    Ok(index - 1)
+}
+
+// Lets the user pick one of the items by typing part of its title, narrowing down the
+// matches as it's typed. Gives the index of the item picked, if any
+#[cfg(feature = "tui")]
+fn pick_item(
+    items: &[Item],
+    title: &str,
+    filter: &str,
+    yes: bool,
+) -> Result<Option<usize>, WatchListError> {
+    wlist::require_terminal(&format!("Pick an item of {title}"), yes)?;
+    wlist::picker::pick(items, title, filter)
+}
+
+// Without the terminal interface the filter is typed a line at a time, narrowing down the
+// matches until one of them is picked by its number, or nothing is entered to cancel
+#[cfg(not(feature = "tui"))]
+fn pick_item(
+    items: &[Item],
+    title: &str,
+    filter: &str,
+    yes: bool,
+) -> Result<Option<usize>, WatchListError> {
+    let mut filter = filter.to_string();
+    loop {
+        let matches = wlist::fuzzy::search(items, &filter);
+        if matches.is_empty() {
+            eprintln!("No items match '{filter}'");
+        } else {
+            wlist::list_display(
+                &matches,
+                &format!("{title} ({}/{})", matches.len(), items.len()),
+            )?;
+        }
+        let input = wlist::input(
+            "Type to filter, a number to pick or nothing to cancel: ",
+            true,
+            yes,
+        )?;
+        if input.is_empty() {
+            return Ok(None);
+        }
+        match input
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| matches.get(n.wrapping_sub(1)))
+        {
+            Some(item) => return Ok(items.iter().position(|i| std::ptr::eq(i, *item))),
+            None => filter = input,
+        }
+    }
+}
+
+// Asks which of the numbered matches to use until a valid selection like 1,3-5 is
+// entered, returning their indices
+fn select_matches(prompt: &str, matches: usize, yes: bool) -> Result<Vec<usize>, WatchListError> {
+    loop {
+        let input = wlist::input_or_default(prompt, yes)?;
+        // Default option
+        if input.is_empty() {
+            return Ok(vec![0]);
+        }
+        if let Some(picked) = selection::parse(&input, matches) {
+            return Ok(picked);
+        }
+    }
+}
+
+fn cli_tmp(watchlists: &mut WatchList, tmp: &Tmp, file_path: &Path) -> Result<(), WatchListError> {
+    let mut tmp_list = TmpList::from_file(file_path)?;
+    match &tmp.command {
+        TmpCommands::Add { items } => {
+            let added = items.iter().filter(|i| tmp_list.add(*i)).count();
+            status!(
+                "Added {added} Item(s), Skipped {} Duplicate(s)",
+                items.len() - added
+            );
+        }
+        TmpCommands::Show => {
+            if tmp_list.items.is_empty() {
+                status!("The temporary list is empty");
+            } else {
+                wlist::list_display(&tmp_list.items, "Temporary List")?;
+            }
+        }
+        TmpCommands::Commit { list } => {
+            let report = tmp_list.commit(watchlists, list)?;
+            status!(
+                "Added {} Item(s) to '{list}', Skipped {} Duplicate(s)",
+                report.added,
+                report.skipped
+            );
+        }
+        TmpCommands::Discard => {
+            tmp_list.items.clear();
+            status!("Temporary List Discarded");
+        }
+    }
+    tmp_list.to_file()
+}
+
+fn cli_stage(
+    watchlists: &mut WatchList,
+    stage: &Stage,
+    file_path: &Path,
+) -> Result<(), WatchListError> {
+    let mut staged = StagedChanges::from_file(file_path)?;
+    let changes: Vec<Change> = match &stage.command {
+        StageCommands::Add { list, items } => items
+            .iter()
+            .map(|i| Change::Add {
+                list: list.clone(),
+                item: Item::new(i),
+            })
+            .collect(),
+        StageCommands::Remove { list, items } => items
+            .iter()
+            .map(|i| Change::Remove {
+                list: list.clone(),
+                item: i.clone(),
+            })
+            .collect(),
+        StageCommands::Move { from, to, items } => items
+            .iter()
+            .map(|i| Change::Move {
+                from: from.clone(),
+                to: to.clone(),
+                item: i.clone(),
+            })
+            .collect(),
+        StageCommands::Diff => {
+            if staged.changes.is_empty() {
+                status!("No changes staged");
+            }
+            print_changes(&staged.changes);
+            return Ok(());
+        }
+        StageCommands::Apply => {
+            let applied = staged.apply(watchlists)?;
+            status!("Applied {applied} Change(s)");
+            return staged.to_file();
+        }
+        StageCommands::Reset => {
+            staged.changes.clear();
+            status!("Staged Changes Cleared");
+            return staged.to_file();
+        }
+    };
+
+    let count = changes.len();
+    for change in changes {
+        staged.push(watchlists, change)?;
+    }
+    status!("Staged {count} Change(s)");
+    staged.to_file()
+}
+
+// Shows changes like a diff, or in words in the accessible mode
+fn print_changes(changes: &[Change]) {
+    for change in changes {
+        let line = match theme::accessible() {
+            true => change.describe(),
+            false => change.to_string(),
+        };
+        let style = match change {
+            Change::Add { .. } => Style::Added,
+            Change::Remove { .. } => Style::Removed,
+            Change::Move { .. } | Change::SetStatus { .. } => Style::Changed,
+        };
+        println!("{}", theme::paint(&line, style));
+    }
+}
+
+// Shows the differences from another file like the staged changes
+fn print_differences(differences: &[Difference]) {
+    for difference in differences {
+        let line = match theme::accessible() {
+            true => difference.describe(),
+            false => difference.to_string(),
+        };
+        let style = match difference {
+            Difference::ListAdded { .. } | Difference::ItemAdded { .. } => Style::Added,
+            Difference::ListRemoved { .. } | Difference::ItemRemoved { .. } => Style::Removed,
+            Difference::ItemChanged { .. } => Style::Changed,
+        };
+        println!("{}", theme::paint(&line, style));
+    }
+}
+
+// Every change is checked before any is made, so either all of them happen or none
+fn cli_set_status(
+    watchlists: &mut WatchList,
+    set: &SetStatus,
+    yes: bool,
+) -> Result<(), WatchListError> {
+    let status: Status = set.to.into();
+    let mut items: Vec<&Item> = match &set.search {
+        Some(search) => watchlists.list_search(&set.list, search)?,
+        None => watchlists.item_get_all(&set.list)?.iter().collect(),
+    };
+    if let Some(filter) = &set.filter {
+        let query: Query = filter.parse()?;
+        items.retain(|i| query.matches(&set.list, i));
+    }
+    if items.is_empty() {
+        let prompt = [set.search.as_deref(), set.filter.as_deref()];
+        let prompt: Vec<&str> = prompt.into_iter().flatten().collect();
+        return Err(WatchListError::NoMatches(prompt.join(", ")));
+    }
+    let changes: Vec<Change> = items
+        .iter()
+        .filter(|i| i.status() != status)
+        .map(|i| Change::SetStatus {
+            list: set.list.clone(),
+            item: i.title.clone(),
+            status,
+        })
+        .collect();
+    if changes.is_empty() {
+        status!("Every matching Item is already {status}");
+        return Ok(());
+    }
+
+    print_changes(&changes);
+    let prompt = format!("Mark {} Item(s) as {status}? (y/N): ", changes.len());
+    if !wlist::confirm(&prompt, yes)? {
+        status!("Cancelled");
+        return Ok(());
+    }
+    let mut staged = StagedChanges::default();
+    staged.changes = changes;
+    let applied = staged.apply(watchlists)?;
+    status!("Marked {applied} Item(s) as {status}");
+    Ok(())
+}
+
+// Picks a random item, from a random list if none is given, skipping the items picked
+// recently. Items picked for the day are picked again until the day is over
+fn cli_random(
+    watchlists: &WatchList,
+    random: &Random,
+    config: &Config,
+    file_path: &Path,
+    yes: bool,
+) -> Result<(), WatchListError> {
+    let today = dates::today();
+    let mut daily = match random.daily {
+        true => Some(DailyPicks::from_file(file_path)?),
+        false => None,
+    };
+    let key = random.name.as_deref().unwrap_or_default();
+    // The item picked today, as long as the list it was picked from is still there
+    let cached = daily
+        .as_ref()
+        .and_then(|d| d.get(key, today))
+        .filter(|p| {
+            watchlists.contains(&p.list)
+                || is_smart_list(watchlists, config, &p.list)
+                || watchlists.is_category(&p.list)
+        })
+        .cloned();
+
+    let mut rng = match random.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+
+    let list = match (&random.name, &cached) {
+        (Some(n), _) => n,
+        (None, Some(pick)) => &pick.list,
+        (None, None) => {
+            // Only lists with items to pick from, along with how many
+            let lists: Vec<(&String, usize)> = watchlists
+                .random_lists()?
+                .into_iter()
+                .map(|l| {
+                    let tagged = watchlists[l].iter().filter(|i| i.has_tags(&random.tags));
+                    (l, tagged.count())
+                })
+                .filter(|(_, count)| *count > 0)
+                .collect();
+            let list = match random.weighted {
+                true => lists.choose_weighted(&mut rng, |(_, count)| *count).ok(),
+                false => lists.choose(&mut rng),
+            };
+            match list {
+                Some(&(list, _)) => list,
+                None => return Err(WatchListError::NoMatches(random.tags.join(", "))),
+            }
+        }
+    };
+    // Items of smart lists and categories are gathered from the lists they are in
+    let smart = match is_smart_list(watchlists, config, list) {
+        true => config
+            .smart_list(list)?
+            .map(|q| query::filter(watchlists, &q)),
+        false if watchlists.is_category(list) => Some(
+            watchlists
+                .category_lists(list)
+                .into_iter()
+                .flat_map(|l| watchlists[l].iter().map(move |i| (l.as_str(), i)))
+                .collect(),
+        ),
+        false => None,
+    };
+    let items = match &smart {
+        Some(matches) => matches
+            .iter()
+            .map(|(_, i)| *i)
+            .filter(|i| i.has_tags(&random.tags))
+            .collect(),
+        None => watchlists.item_get_tagged(list, &random.tags)?,
+    };
+    if items.is_empty() && random.tags.is_empty() {
+        return Err(WatchListError::EmptyList(list.to_string()));
+    }
+
+    let mut history = PickHistory::from_file(file_path)?;
+    let now = dates::now();
+    // The picks from before would change what a seed picks
+    let (cooldown, recent) = match random.allow_repeat || random.seed.is_some() {
+        true => (chrono::TimeDelta::zero(), 0),
+        false => (config.random_cooldown(list), config.random_avoid_recent),
+    };
+    let count = random.count.unwrap_or(1);
+    let cached_item = cached
+        .as_ref()
+        .and_then(|p| items.iter().copied().find(|i| i.title == p.item));
+    let picked = match cached_item {
+        Some(item) => vec![item],
+        None => history.pick_n_with_rng(list, &items, count, cooldown, recent, now, &mut rng),
+    };
+    if picked.is_empty() && count > 0 {
+        return Err(WatchListError::NoMatches(random.tags.join(", ")));
+    }
+    let picked = match random.interactive {
+        true => {
+            let pick = |rest: &[_]| {
+                history.pick_n_with_rng(list, rest, 1, cooldown, recent, now, &mut rng)
+            };
+            match accept_or_reroll(&items, picked[0], pick, yes)? {
+                Some(item) => vec![item],
+                None => {
+                    status!("Nothing Picked");
+                    return Ok(());
+                }
+            }
+        }
+        false => picked,
+    };
+    match random.format.machine() {
+        Some(format) => {
+            // Items of smart lists are numbered in the lists they are in
+            let sources: Vec<(&str, &Item)> = picked
+                .iter()
+                .map(|item| {
+                    let source = smart
+                        .iter()
+                        .flatten()
+                        .find(|(_, i)| std::ptr::eq(*i, *item))
+                        .map_or(list.as_str(), |(l, _)| l);
+                    (source, *item)
+                })
+                .collect();
+            let entries = Entry::numbered(watchlists, &sources);
+            // A single item is printed on its own unless a count was asked for
+            match (random.count, entries.first()) {
+                (None, Some(entry)) => println!("{}", entry.format(&[], format)),
+                _ => println!("{}", fields::format_entries(&entries, &[], format)),
+            }
+        }
+        None => picked.iter().for_each(|item| println!("{item}")),
+    }
+    #[cfg(feature = "clipboard")]
+    if random.copy {
+        clipboard::copy(picked.iter().map(|i| i.title.as_str()))?;
+    }
+    // Showing the item picked for the day again isn't another pick
+    if cached_item.is_some() {
+        return Ok(());
+    }
+    for item in &picked {
+        history.record(list, &item.title, now);
+    }
+    history.to_file()?;
+    if let (Some(daily), Some(item)) = (&mut daily, picked.first()) {
+        let pick = DailyPick {
+            date: today,
+            list: list.clone(),
+            item: item.title.clone(),
+        };
+        daily.set(key, pick);
+        daily.to_file()?;
+    }
+    match picked.first() {
+        Some(item) if random.open => open_url(item),
+        _ => Ok(()),
+    }
+}
+
+fn open_url(item: &Item) -> Result<(), WatchListError> {
+    let url = (item.meta.url.as_deref()).ok_or(WatchListError::NoUrl(item.title.clone()))?;
+    links::open(url)?;
+    status!("Opened {url}");
+    Ok(())
+}
+
+fn cli_next(watchlists: &mut WatchList, next: &Next, yes: bool) -> Result<(), WatchListError> {
+    let Some(item) = watchlists.item_get_next(&next.list)? else {
+        status!("Nothing left to watch in {}", next.list);
+        return Ok(());
+    };
+    println!("{item}");
+    // Without anyone to ask, the item is only shown unless taking it was asked for
+    let take = next.pop
+        || (yes || io::stdin().is_terminal())
+            && wlist::confirm(&format!("Done with '{item}'? (y/N): "), yes)?;
+    if !take {
+        return Ok(());
+    }
+    match next.watched {
+        true => {
+            let item = watchlists.item_finish_next(&next.list)?;
+            status!(
+                "Marked '{}' watched",
+                item.expect("the next item was found").title
+            );
+        }
+        false => {
+            let item = watchlists.item_pop_next(&next.list)?;
+            status!(
+                "Removed '{}' from {}",
+                item.expect("the next item was found").title,
+                next.list
+            );
+        }
+    }
+    Ok(())
+}
+
+// Asks whether to accept the item picked, picking another one for as long as the user
+// rerolls. Items turned down aren't picked again until every item has been
+fn accept_or_reroll<'w>(
+    items: &[&'w Item],
+    first: &'w Item,
+    mut pick: impl FnMut(&[&'w Item]) -> Vec<&'w Item>,
+    yes: bool,
+) -> Result<Option<&'w Item>, WatchListError> {
+    let mut item = first;
+    let mut rejected: Vec<&Item> = Vec::new();
+    loop {
+        let prompt = format!("Picked '{item}', Accept, Reroll or Quit? (Y/r/q): ");
+        match wlist::input_or_default(&prompt, yes)?
+            .to_lowercase()
+            .as_str()
+        {
+            "" | "y" => return Ok(Some(item)),
+            "q" => return Ok(None),
+            "r" => {
+                rejected.push(item);
+                let mut rest: Vec<&Item> = items
+                    .iter()
+                    .copied()
+                    .filter(|i| !rejected.iter().any(|r| std::ptr::eq(*r, *i)))
+                    .collect();
+                if rest.is_empty() {
+                    rejected.clear();
+                    rest = items.to_vec();
+                }
+                item = pick(&rest).first().copied().unwrap_or(item);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn cli_search(
+    watchlists: &WatchList,
+    search: &Search,
+    file_path: &Path,
+    yes: bool,
+) -> Result<(), WatchListError> {
+    let fields = fields::lookup(&search.fields)?;
+    let (list, prompt) = match (&search.all, &search.list, &search.prompt) {
+        (Some(prompt), _, _) => return search_all(watchlists, search, prompt, &fields),
+        (None, Some(list), prompt) if search.interactive => {
+            return search_interactive(watchlists, search, list, prompt.as_deref(), yes)
+        }
+        (None, Some(list), Some(prompt)) => (list, prompt),
+        // Clap requires both without --all
+        _ => unreachable!(),
+    };
+    let index = match search.fuzzy || search.regex || search.ignore_accents {
+        true => None,
+        false => Some(SearchIndex::from_file(file_path)?),
+    };
+    let search_list = |list: &str| -> Result<Vec<Match>, WatchListError> {
+        let mut results = match &index {
+            Some(index) => index
+                .search(watchlists, list, prompt)?
+                .into_iter()
+                .map(|item| Match {
+                    item,
+                    spans: item.match_spans(prompt),
+                })
+                .collect(),
+            None if search.fuzzy => watchlists.list_search_fuzzy_matches(list, prompt)?,
+            None if search.ignore_accents => watchlists.list_search_folded_matches(list, prompt)?,
+            None => watchlists.list_search_regex_matches(list, prompt)?,
+        };
+        results.retain(|m| m.item.has_tags(&search.tags));
+        Ok(results)
+    };
+    // The lists of a category are searched like every list is with --all
+    if watchlists.is_category(list) {
+        let results = watchlists
+            .category_lists(list)
+            .into_iter()
+            .map(|l| Ok((l, search_list(l)?)))
+            .collect::<Result<Vec<_>, WatchListError>>()?;
+        return show_grouped(watchlists, search, prompt, results, &fields);
+    }
+    let results = search_list(list)?;
+    if let Some(format) = search.format.machine() {
+        let items: Vec<(&str, &Item)> = results.iter().map(|m| (list.as_str(), m.item)).collect();
+        print_entries(&Entry::numbered(watchlists, &items), &fields, format)?;
+    }
+    if results.is_empty() {
+        return Err(WatchListError::NoMatches(prompt.clone()));
+    }
+    if search.format.machine().is_none() {
+        let mut out = output::stdout();
+        write_matches(&mut out, &results, "Matches", &fields)
+            .and_then(|()| out.flush())
+            .map_err(WatchListError::IOError)?;
+    }
+    #[cfg(feature = "clipboard")]
+    if search.copy {
+        clipboard::copy(results.iter().map(|m| m.item.title.as_str()))?;
+    }
+    Ok(())
+}
+
+// Picks one of the items by typing part of its title and prints it, so it can be passed
+// on to other commands
+fn search_interactive(
+    watchlists: &WatchList,
+    search: &Search,
+    list: &str,
+    prompt: Option<&str>,
+    yes: bool,
+) -> Result<(), WatchListError> {
+    let items: Vec<Item> = watchlists
+        .item_get_tagged(list, &search.tags)?
+        .into_iter()
+        .cloned()
+        .collect();
+    if items.is_empty() {
+        return Err(WatchListError::NoMatches(search.tags.join(", ")));
+    }
+    match pick_item(&items, list, prompt.unwrap_or_default(), yes)? {
+        Some(index) => {
+            println!("{}", items[index].title);
+            #[cfg(feature = "clipboard")]
+            if search.copy {
+                clipboard::copy([items[index].title.as_str()])?;
+            }
+        }
+        None => status!("Nothing Picked"),
+    }
+    Ok(())
+}
+
+fn search_all(
+    watchlists: &WatchList,
+    search: &Search,
+    prompt: &str,
+    fields: &[&Field],
+) -> Result<(), WatchListError> {
+    let results = match search.ignore_accents {
+        true => watchlists.search_all_folded(prompt),
+        false => watchlists.search_all(prompt),
+    };
+    let results = results
+        .into_iter()
+        .map(|(title, items)| {
+            let matches = items
+                .into_iter()
+                .filter(|i| i.has_tags(&search.tags))
+                .map(|item| Match {
+                    item,
+                    spans: match search.ignore_accents {
+                        true => fold::spans(&item.title, prompt),
+                        false => item.match_spans(prompt),
+                    },
+                })
+                .collect();
+            (title, matches)
+        })
+        .collect();
+    show_grouped(watchlists, search, prompt, results, fields)
+}
+
+// Shows the matches under the title of their list, leaving out lists without any
+fn show_grouped(
+    watchlists: &WatchList,
+    search: &Search,
+    prompt: &str,
+    mut results: Vec<(&String, Vec<Match>)>,
+    fields: &[&Field],
+) -> Result<(), WatchListError> {
+    results.retain(|(_, matches)| !matches.is_empty());
+    if let Some(format) = search.format.machine() {
+        let items: Vec<(&str, &Item)> = results
+            .iter()
+            .flat_map(|(title, matches)| matches.iter().map(|m| (title.as_str(), m.item)))
+            .collect();
+        print_entries(&Entry::numbered(watchlists, &items), fields, format)?;
+    }
+    if results.is_empty() {
+        return Err(WatchListError::NoMatches(prompt.to_string()));
+    }
+    if search.format.machine().is_none() {
+        let mut out = output::stdout();
+        results
+            .iter()
+            .try_for_each(|(title, matches)| write_matches(&mut out, matches, title, fields))
+            .and_then(|()| out.flush())
+            .map_err(WatchListError::IOError)?;
+    }
+    #[cfg(feature = "clipboard")]
+    if search.copy {
+        let matches = results.iter().flat_map(|(_, matches)| matches);
+        clipboard::copy(matches.map(|m| m.item.title.as_str()))?;
+    }
+    Ok(())
+}
+
+// Prints items for other programs, leaving out the empty output of no items
+fn print_entries(
+    entries: &[Entry],
+    fields: &[&Field],
+    format: OutputFormat,
+) -> Result<(), WatchListError> {
+    let mut out = output::stdout();
+    fields::write_entries(&mut out, entries, fields, format)
+        .and_then(|()| out.flush())
+        .map_err(WatchListError::IOError)
+}
+
+// Prints the list titles and their numbers of items for other programs
+fn print_titles(counts: &[(&String, usize)], format: OutputFormat) -> Result<(), WatchListError> {
+    let mut out = output::stdout();
+    let written = match format {
+        OutputFormat::Json => {
+            let lists: Vec<serde_json::Value> = counts
+                .iter()
+                .map(|(t, count)| serde_json::json!({ "title": t, "items": count }))
+                .collect();
+            writeln!(out, "{}", serde_json::Value::Array(lists))
+        }
+        OutputFormat::Tsv => counts.iter().try_for_each(|(title, count)| {
+            writeln!(out, "{}\t{count}", title.replace(['\t', '\n'], " "))
+        }),
+        OutputFormat::Plain => counts.iter().try_for_each(|(t, _)| writeln!(out, "{t}")),
+    };
+    written
+        .and_then(|()| out.flush())
+        .map_err(WatchListError::IOError)
+}
+
+// Shows items along with their ratings and tags, or only the chosen fields
+fn display_items(items: &[&Item], title: &str, fields: &[&Field]) -> Result<(), WatchListError> {
+    let mut out = output::stdout();
+    write_items(&mut out, items, title, fields)
+        .and_then(|()| out.flush())
+        .map_err(WatchListError::IOError)
+}
+
+fn write_items(
+    out: &mut impl Write,
+    items: &[&Item],
+    title: &str,
+    fields: &[&Field],
+) -> io::Result<()> {
+    let matches: Vec<Match> = items
+        .iter()
+        .map(|&item| Match {
+            item,
+            spans: Vec::new(),
+        })
+        .collect();
+    write_matches(out, &matches, title, fields)
+}
+
+// Writes the items found by a search with the parts of their titles which matched
+// highlighted
+fn write_matches(
+    out: &mut impl Write,
+    matches: &[Match],
+    title: &str,
+    fields: &[&Field],
+) -> io::Result<()> {
+    let highlighted = |m: &Match| theme::highlight(&m.item.title, &m.spans);
+    let accessible = theme::accessible();
+    // Single lines which are easier to read out or to pick numbers from
+    if accessible || theme::quiet() {
+        let separator = if accessible { ", " } else { " | " };
+        let lines: Vec<String> = matches
+            .iter()
+            .map(|m| match (fields.is_empty(), accessible) {
+                (false, _) => fields::text_row(m.item, fields, separator),
+                (true, true) => describe_item(m.item),
+                (true, false) => [highlighted(m), item_details(m.item).join(" ")].join(" "),
+            })
+            .map(|line| line.trim_end().to_string())
+            .collect();
+        return output::write_list(out, &lines, title);
+    }
+
+    let index = Column::new("#").align(Align::Right).style(Style::Index);
+    let mut table = Table::new().column(index);
+    if !fields.is_empty() {
+        for field in fields {
+            table = table.column(Column::new(field.name));
+        }
+        table = table.headers();
+        for (n, m) in matches.iter().enumerate() {
+            let cells = fields.iter().map(|f| match f.name {
+                "title" => highlighted(m),
+                _ => f.text(m.item),
+            });
+            table.row(std::iter::once(format!("{}.", n + 1)).chain(cells));
+        }
+        return output::write_table(out, &table, title);
+    }
+    let details = ["progress", "rating", "status", "tags"];
+    table = table.column(Column::new("title"));
+    for detail in details {
+        table = table.column(Column::new(detail).style(Style::Detail));
+    }
+    for (n, m) in matches.iter().enumerate() {
+        let item = m.item;
+        let status = match item.status() {
+            Status::Unwatched => String::new(),
+            status => status.to_string(),
+        };
+        table.row([
+            format!("{}.", n + 1),
+            highlighted(m),
+            item.meta
+                .progress
+                .map(|p| p.to_string())
+                .unwrap_or_default(),
+            item.meta
+                .rating
+                .map(|r| format!("{r}/10"))
+                .unwrap_or_default(),
+            status,
+            tag_list(item),
+        ]);
+    }
+    output::write_table(out, &table, title)
+}
+
+// Progress, rating and tags of an item, as shown after its title
+fn item_details(item: &Item) -> Vec<String> {
+    let progress = item.meta.progress.map(|p| p.to_string());
+    let rating = item.meta.rating.map(|r| format!("{r}/10"));
+    let tags = Some(tag_list(item)).filter(|t| !t.is_empty());
+    [progress, rating, tags].into_iter().flatten().collect()
+}
+
+fn tag_list(item: &Item) -> String {
+    let tags: Vec<String> = item.meta.tags.iter().map(|t| format!("#{t}")).collect();
+    tags.join(" ")
+}
+
+// Details of an item spelled out in words for the accessible output mode
+fn describe_item(item: &Item) -> String {
+    let mut details = Vec::new();
+    if item.status() != Status::Unwatched {
+        details.push(item.status().to_string());
+    }
+    if let Some(progress) = item.meta.progress {
+        details.push(format!("at {progress}"));
+    }
+    if let Some(rating) = item.meta.rating {
+        details.push(format!("rated {rating} out of 10"));
+    }
+    if !item.meta.tags.is_empty() {
+        details.push(format!("tagged {}", item.meta.tags.join(", ")));
+    }
+    match details.is_empty() {
+        true => item.to_string(),
+        false => format!("{item}, {}", details.join(", ")),
+    }
+}
+
+// Draws the activity read from the history, or sums it up by month in the accessible mode
+fn display_heatmap(config: &Config, file_path: &Path) -> Result<(), WatchListError> {
+    let mut heatmap = Heatmap::default();
+    for operation in history::read(file_path, None)? {
+        heatmap.record(dates::local_date(&operation.at), activity(&operation));
+    }
+    let today = dates::today();
+    let days = heatmap.active_days(stats::first_day(today), today);
+    let added: usize = days.iter().map(|(_, a)| a.added).sum();
+    let completed: usize = days.iter().map(|(_, a)| a.completed).sum();
+    let summary = format!(
+        "{added} Item(s) added and {completed} completed on {} day(s) in the last year",
+        days.len()
+    );
+
+    if theme::accessible() {
+        // Months are summed up as the calendar can't be read out
+        let mut months: Vec<(String, Activity)> = Vec::new();
+        for (day, activity) in days {
+            let month = dates::format_date(day, "%B %Y", config.locale());
+            match months.last_mut() {
+                Some((last, total)) if *last == month => {
+                    total.added += activity.added;
+                    total.completed += activity.completed;
+                }
+                _ => months.push((month, activity)),
+            }
+        }
+        println!("{summary}");
+        for (month, activity) in months {
+            println!(
+                "{month}: {} added, {} completed",
+                activity.added, activity.completed
+            );
+        }
+        return Ok(());
+    }
+
+    let mut lines = heatmap.render(today, config.locale()).into_iter();
+    if let Some(months) = lines.next() {
+        println!("{}", theme::paint(&months, Style::Heading));
+    }
+    for line in lines {
+        let (label, cells) =
+            line.split_at(line.char_indices().nth(4).map_or(line.len(), |(i, _)| i));
+        println!("{label}{}", theme::paint(cells, Style::Added));
+    }
+    println!("\n{summary}");
+    Ok(())
+}
+
+// Parses the arguments of a logged command again, none if they are no longer valid
+fn logged_command(operation: &Operation) -> Option<Commands> {
+    let args = ["wl", &operation.command]
+        .into_iter()
+        .chain(operation.args.iter().map(String::as_str));
+    Cli::try_parse_from(args).ok().map(|cli| cli.commands)
+}
+
+// What a logged command did to items. Items are completed by deleting them from a list
+fn activity(operation: &Operation) -> Activity {
+    match logged_command(operation) {
+        Some(Commands::Add(add)) => Activity {
+            added: add.target().1.len(),
+            completed: 0,
+        },
+        #[cfg(feature = "net")]
+        Some(Commands::Grab(_)) => Activity {
+            added: 1,
+            completed: 0,
+        },
+        Some(Commands::Delete(delete)) => Activity {
+            added: 0,
+            completed: delete.items_deleted(),
+        },
+        _ => Activity::default(),
+    }
+}
+
+fn display_stats(
+    watchlists: &WatchList,
+    args: &Stats,
+    file_path: &Path,
+) -> Result<(), WatchListError> {
+    watchlists.list_get_all()?;
+    let mut stats = watchlists.stats();
+    // Items in older files don't keep when they were added, so they are counted from the history
+    let since = dates::today() - chrono::TimeDelta::days(stats::RECENT_DAYS - 1);
+    let added = history::read(file_path, None)?
+        .iter()
+        .filter(|operation| dates::local_date(&operation.at) >= since)
+        .map(|operation| activity(operation).added)
+        .sum();
+    stats.recently_added = Some(added);
+
+    if let StatsFormat::Json = args.format {
+        let json = serde_json::to_string(&stats).map_err(|e| WatchListError::IOError(e.into()))?;
+        println!("{json}");
+        return Ok(());
+    }
+    let mut lines: Vec<String> = stats
+        .lists
+        .iter()
+        .map(|list| format!("{}: {}", list.title, describe_counts(&list.counts)))
+        .collect();
+    lines.push(format!("Total: {}", describe_counts(&stats.total)));
+    if let Some(largest) = &stats.largest {
+        let items = watchlists[largest].len();
+        lines.push(format!("Largest List: {largest} with {items} Item(s)"));
+    }
+    lines.push(format!(
+        "Added in the Last {} Days: {added} Item(s)",
+        stats::RECENT_DAYS
+    ));
+    wlist::list_display(&lines, "Stats")?;
+    Ok(())
+}
+
+// Like "3 Item(s), 2 Watched (67%), 1 Unwatched", leaving out dropped items when there are none
+fn describe_counts(counts: &stats::Counts) -> String {
+    let mut text = format!("{} Item(s), {} Watched", counts.items, counts.watched);
+    if let Some(ratio) = counts.watched_ratio {
+        text.push_str(&format!(" ({:.0}%)", ratio * 100.0));
+    }
+    text.push_str(&format!(", {} Unwatched", counts.unwatched));
+    if counts.dropped > 0 {
+        text.push_str(&format!(", {} Dropped", counts.dropped));
+    }
+    text
+}
+
+fn display_forecast(
+    watchlists: &WatchList,
+    args: &Forecast,
+    config: &Config,
+    file_path: &Path,
+) -> Result<(), WatchListError> {
+    let items = watchlists
+        .get(&args.list)
+        .ok_or(WatchListError::TitleNotPresent(
+            args.list.clone(),
+            Vec::new(),
+        ))?;
+    let completions: Vec<_> = history::read(file_path, None)?
+        .iter()
+        .flat_map(|operation| {
+            let deleted = match logged_command(operation) {
+                Some(Commands::Delete(delete)) if delete.list == args.list => {
+                    delete.items_deleted()
+                }
+                _ => 0,
+            };
+            std::iter::repeat_n(dates::local_date(&operation.at), deleted)
+        })
+        .collect();
+    let today = dates::today();
+    let forecast = forecast::Forecast::new(items, &completions, today, args.days);
+
+    if forecast.remaining == 0 {
+        println!("Nothing left to watch in {}", args.list);
+        return Ok(());
+    }
+    println!(
+        "{}: {} Item(s) left, {} completed in the last {} day(s)",
+        args.list, forecast.remaining, forecast.completed, forecast.days
+    );
+    match (forecast.days_left(), forecast.finish(today)) {
+        (Some(days), Some(finish)) => println!(
+            "At {:.1} Item(s) a week, clearing it takes about {days} day(s), until {}",
+            forecast.rate() * 7.0,
+            config.format_date(finish)
+        ),
+        _ => {
+            println!("Nothing was completed lately, so there's no telling when it will be cleared")
+        }
+    }
+    if let Some(minutes) = forecast.minutes {
+        println!(
+            "About {} of watching left, {} of {} Item(s) have a runtime and the rest are counted as the average",
+            forecast::format_runtime(minutes),
+            forecast.with_runtime,
+            forecast.remaining
+        );
+    }
+    Ok(())
+}
+
+// Smart lists are only used when there isn't a real list with the same title
+fn is_smart_list(watchlists: &WatchList, config: &Config, title: &str) -> bool {
+    !watchlists.contains(title) && config.smart_lists.contains_key(title)
+}
+
+// Shows the number of items with the tags in the list, or in every list along with the
+// smart lists when none is given
+fn display_counts(
+    watchlists: &WatchList,
+    list: &List,
+    config: &Config,
+) -> Result<(), WatchListError> {
+    let count = |title: &String| -> Result<usize, WatchListError> {
+        if !is_smart_list(watchlists, config, title) {
+            return Ok(watchlists.item_get_tagged(title, &list.tags)?.len());
+        }
+        let matches = config
+            .smart_list(title)?
+            .map(|q| query::filter(watchlists, &q))
+            .unwrap_or_default();
+        Ok(matches
+            .iter()
+            .filter(|(_, i)| i.has_tags(&list.tags))
+            .count())
+    };
+    let titles = match &list.list {
+        Some(title) if watchlists.is_category(title) => watchlists.category_lists(title),
+        Some(title) => vec![title],
+        None => watchlists.list_get_all()?,
+    };
+    let mut lists = titles
+        .into_iter()
+        .map(|t| Ok((t, count(t)?)))
+        .collect::<Result<Vec<_>, WatchListError>>()?;
+    lists.sort();
+    let mut smart = match list.list {
+        Some(_) => Vec::new(),
+        None => config
+            .smart_lists
+            .keys()
+            .map(|t| Ok((t, count(t)?)))
+            .collect::<Result<Vec<_>, WatchListError>>()?,
+    };
+    smart.sort();
+    if let Some(format) = list.format.machine() {
+        return print_titles(&lists, format);
+    }
+
+    let mut out = output::stdout();
+    let heading = match list.list {
+        Some(_) => "Lists",
+        None => "All Lists",
+    };
+    output::write_counts(&mut out, &lists, heading)
+        .and_then(|()| match smart.is_empty() {
+            true => Ok(()),
+            false => {
+                writeln!(out).and_then(|()| output::write_counts(&mut out, &smart, "Smart Lists"))
+            }
+        })
+        .and_then(|()| out.flush())
+        .map_err(WatchListError::IOError)
+}
+
+fn display_smart(
+    watchlists: &WatchList,
+    list: &List,
+    config: &Config,
+) -> Result<(), WatchListError> {
+    let title = list.list.as_deref().unwrap_or_default();
+    let Some(query) = config.smart_list(title)? else {
+        return Err(WatchListError::TitleNotPresent(
+            title.to_string(),
+            Vec::new(),
+        ));
+    };
+    let mut items: Vec<(&str, &Item)> = query::filter(watchlists, &query);
+    items.retain(|(_, i)| i.has_tags(&list.tags));
+    // Items which compare the same stay in the order of their lists
+    list.sort_by(watchlists, title)
+        .sort(&mut items, |(_, i)| *i);
+    let fields = fields::lookup(&list.fields)?;
+    match list.format.machine() {
+        Some(format) => print_entries(&Entry::numbered(watchlists, &items), &fields, format)?,
+        None if items.is_empty() => status!("No Items match {title}"),
+        None => {
+            let items: Vec<&Item> = items.into_iter().map(|(_, i)| i).collect();
+            display_items(&items, title, &fields)?;
+        }
+    }
+    Ok(())
+}
+
+// Shows items by their due dates with overdue items highlighted
+fn display_due(watchlists: &WatchList, list: &List, config: &Config) -> Result<(), WatchListError> {
+    if let Some(title) = &list.list {
+        if !watchlists.contains(title) {
+            return Err(WatchListError::TitleNotPresent(title.clone(), Vec::new()));
+        }
+    }
+    let today = dates::today();
+    let due: Vec<(&String, &Item)> = due::items_by_due(watchlists)
+        .into_iter()
+        .filter(|(title, item)| {
+            list.list.as_ref().is_none_or(|l| l == *title) && item.has_tags(&list.tags)
+        })
+        .collect();
+    if let Some(format) = list.format.machine() {
+        let items: Vec<(&str, &Item)> = due.into_iter().map(|(t, i)| (t.as_str(), i)).collect();
+        print_entries(
+            &Entry::numbered(watchlists, &items),
+            &fields::lookup(&list.fields)?,
+            format,
+        )?;
+        return Ok(());
+    }
+    let lines: Vec<String> = due
+        .into_iter()
+        .map(|(title, item)| {
+            let date = config.format_date(item.meta.due.unwrap_or(today));
+            let line = match list.list {
+                Some(_) => format!("{item} - {date}"),
+                None => format!("{item} ({title}) - {date}"),
+            };
+            match due::is_overdue(item, today) {
+                true if theme::accessible() => format!("{line}, overdue"),
+                true => theme::paint(&line, Style::Overdue).to_string(),
+                false => line,
+            }
+        })
+        .collect();
+
+    if lines.is_empty() {
+        status!("No Items with Due Dates");
+    } else {
+        wlist::list_display(&lines, list.list.as_deref().unwrap_or("Due Items"))?;
+    }
+    Ok(())
+}
+
+fn display_recent(
+    watchlists: &WatchList,
+    list: &List,
+    config: &Config,
+) -> Result<(), WatchListError> {
+    if let Some(title) = &list.list {
+        if !watchlists.contains(title) {
+            return Err(WatchListError::TitleNotPresent(title.clone(), Vec::new()));
+        }
+    }
+    let added = match &list.since {
+        Some(since) => {
+            recent::items_added_since(watchlists, config.parse_since(since, dates::today())?)
+        }
+        None => recent::items_by_added(watchlists),
+    };
+    let added: Vec<(&String, &Item)> = added
+        .into_iter()
+        .filter(|(title, item)| {
+            list.list.as_ref().is_none_or(|l| l == *title) && item.has_tags(&list.tags)
+        })
+        .take(list.recent.unwrap_or(usize::MAX))
+        .collect();
+    if let Some(format) = list.format.machine() {
+        let items: Vec<(&str, &Item)> = added.into_iter().map(|(t, i)| (t.as_str(), i)).collect();
+        print_entries(
+            &Entry::numbered(watchlists, &items),
+            &fields::lookup(&list.fields)?,
+            format,
+        )?;
+        return Ok(());
+    }
+    let lines: Vec<String> = added
+        .into_iter()
+        .filter_map(|(title, item)| {
+            let at = config.format_timestamp(&item.meta.added_at?);
+            Some(match list.list {
+                Some(_) => format!("{item} - {at}"),
+                None => format!("{item} ({title}) - {at}"),
+            })
+        })
+        .collect();
+
+    if lines.is_empty() {
+        status!("No Recently Added Items");
+    } else {
+        wlist::list_display(&lines, list.list.as_deref().unwrap_or("Recently Added"))?;
+    }
+    Ok(())
+}
+
+// Prints the items due soon, raising them as a desktop notification too. A notification which
+// can't be raised, like without a desktop to show it on, is only warned about
+fn cli_notify(watchlists: &WatchList, notify: &Notify, config: &Config) {
+    let days = notify.days.unwrap_or(config.due_soon_days);
+    let Some(reminder) = notify::reminder(watchlists, dates::today(), days, |date| {
+        config.format_date(date)
+    }) else {
+        match days {
+            0 => status!("No Items due by today"),
+            days => status!("No Items due within {days} day(s)"),
+        }
+        return;
+    };
+    println!("{}", reminder.summary);
+    for line in &reminder.body {
+        println!("  {line}");
+    }
+    #[cfg(feature = "notify")]
+    if !notify.no_desktop {
+        if let Err(e) = notify::show(&reminder) {
+            let warning = format!("Couldn't raise a desktop notification, {e}");
+            eprintln!("{}", theme::paint(&warning, Style::Warning));
+        }
+    }
+}
+
+// Index of the item `wl show` numbers with the number, in the order set for the list.
+// Shuffled lists are numbered in the order the items were added, as they're never
+// shown in the same order twice
+fn item_index(
+    watchlists: &WatchList,
+    list: &str,
+    number: usize,
+) -> Result<(usize, SortBy), WatchListError> {
+    let sort = match watchlists.list_sort(list) {
+        SortBy::Random => SortBy::Added,
+        sort => sort,
+    };
+    match number.checked_sub(1) {
+        Some(index) => Ok((index, sort)),
+        None => Err(WatchListError::ItemToRemoveNotPresent("Item 0".to_string())),
+    }
+}
+
+// Title of the item with the number in `wl show`
+fn item_at(watchlists: &WatchList, list: &str, number: usize) -> Result<String, WatchListError> {
+    let (index, sort) = item_index(watchlists, list, number)?;
+    Ok(watchlists.item_get_index(list, index, sort)?.title.clone())
+}
+
+fn cli_edit(watchlists: &mut WatchList, edit: &Edit, yes: bool) -> Result<(), WatchListError> {
+    let item = match (edit.index, &edit.prompt) {
+        (Some(number), _) => item_at(watchlists, &edit.list, number)?,
+        (None, Some(prompt)) => {
+            let results = watchlists.list_search_matches(&edit.list, prompt)?;
+            if results.is_empty() {
+                return Err(WatchListError::NoMatches(prompt.clone()));
+            }
+            let titles: Vec<String> = results
+                .iter()
+                .map(|m| theme::highlight(&m.item.title, &m.spans))
+                .collect();
+            wlist::list_display(&titles, "Matched Items")?;
+
+            let index = select_match("Enter Item to Edit (DEFAULT: 1): ", results.len(), yes)?;
+            results[index].item.to_string()
+        }
+        // Clap requires one of them
+        (None, None) => unreachable!(),
+    };
+    let new_item = wlist::input(&format!("Enter New Text for '{item}': "), true, yes)?;
+    if new_item.is_empty() || new_item == item {
+        status!("Editing Cancelled");
+        return Ok(());
+    }
+    watchlists.item_rename(&edit.list, &item, &new_item)?;
+    status!("Item Edited");
+    Ok(())
+}
+
+fn cli_edit_list(
+    watchlists: &mut WatchList,
+    edit: &EditList,
+    config: &Config,
+    file_path: &Path,
+) -> Result<(), WatchListError> {
+    let items = watchlists.item_get_all(&edit.list)?;
+    let text = editor::open(&editor::to_text(items), &edit.list)?;
+    let edited = editor::apply(items, &text, watchlists.validator())?;
+    if !edited.is_changed() {
+        status!("No Changes");
+        return Ok(());
+    }
+
+    let mut trash = load_trash(config, file_path)?;
+    for item in &edited.removed {
+        let list = edit.list.clone();
+        let item = item.clone();
+        trash.add(Trashed::Item { list, item }, dates::now());
+    }
+    trash.to_file()?;
+    let (added, removed) = (edited.added.len(), edited.removed.len());
+    let reordered = edited.reordered;
+    watchlists.insert(edit.list.clone(), edited.items);
+    status!(
+        "List Edited! {added} Item(s) added, {removed} moved to the trash{}",
+        if reordered { ", reordered" } else { "" }
+    );
+    Ok(())
+}
+
+// Steps through the items of a list one at a time, asking whether to keep, delete, archive
+// or tag each. Everything is saved after each decision, so stopping part way through
+// loses nothing and the review carries on from there the next time
+fn cli_review(
+    watchlists: &mut WatchList,
+    review: &Review,
+    config: &Config,
+    file_path: &Path,
+    answers: Answers,
+    run: &mut Run,
+) -> Result<(), WatchListError> {
+    let list = &review.list;
+    let mut reviews = Reviews::from_file(file_path)?;
+    if review.restart {
+        reviews.restart(list);
+    }
+    let items: Vec<Item> = reviews
+        .remaining(list, watchlists.item_get_all(list)?)
+        .into_iter()
+        .cloned()
+        .collect();
+    let total = watchlists[list].len();
+    wlist::require_terminal("Keep, Delete, Archive, Tag or Quit?", answers.yes)?;
+    status!(
+        "k: keep, d: delete, a: archive to {}, t: tag, q: quit and carry on later",
+        review.archive
+    );
+
+    let mut reviewed = total - items.len();
+    for item in &items {
+        println!("\n[{}/{total}] {}", reviewed + 1, describe_item(item));
+        let decision = loop {
+            print!("Keep, Delete, Archive, Tag or Quit? (K/d/a/t/q): ");
+            io::stdout().flush()?;
+            let mut answer = String::new();
+            // Running out of input stops the review instead of keeping every item left
+            if io::stdin().read_line(&mut answer)? == 0 {
+                println!();
+                break Decision::Quit;
+            }
+            match answer.parse() {
+                Ok(decision) => break decision,
+                Err(()) => println!("Answer k, d, a, t or q"),
+            }
+        };
+
+        match decision {
+            Decision::Keep => {}
+            Decision::Delete => {
+                watchlists.item_remove(list, &item.title)?;
+                let mut trash = load_trash(config, file_path)?;
+                let trashed = Trashed::Item {
+                    list: list.clone(),
+                    item: item.clone(),
+                };
+                trash.add(trashed, dates::now());
+                trash.to_file()?;
+            }
+            Decision::Archive => {
+                if !watchlists.contains(&review.archive) {
+                    watchlists.list_add(&review.archive)?;
+                }
+                watchlists.item_move(list, &review.archive, &item.title)?;
+            }
+            Decision::Tag => {
+                let tags = wlist::input("Tags to add, separated by commas: ", true, answers.yes)?;
+                let tags: Vec<&str> = tags.split(',').collect();
+                if let Some(entry) = watchlists
+                    .get_mut(list)
+                    .and_then(|items| items.iter_mut().find(|i| i.title == item.title))
+                {
+                    *entry = entry.clone().with_tags(&tags);
+                }
+            }
+            Decision::Quit => break,
+        }
+        reviews.mark(list, &item.title);
+        reviews.to_file()?;
+        save_lists(watchlists, file_path, answers, run)?;
+        reviewed += 1;
+    }
+
+    if reviewed == total {
+        reviews.restart(list);
+        reviews.to_file()?;
+        status!("Reviewed all {total} Item(s) in {list}");
+    } else {
+        status!("Reviewed {reviewed} of {total} Item(s), `wl review {list}` carries on from here");
+    }
+    Ok(())
+}
+
+// Loads the trash, dropping entries older than the configured age
+fn load_trash(config: &Config, file_path: &Path) -> Result<Trash, WatchListError> {
+    let mut trash = Trash::from_file(file_path)?;
+    if let Some(max_age) = config.trash_max_age() {
+        trash.purge(max_age, dates::now());
+    }
+    Ok(trash)
+}
+
+fn cli_profile(
+    profile_args: &ProfileArgs,
+    config: &Config,
+    file_path: &Path,
+) -> Result<(), WatchListError> {
+    let profiles = crate::get_profiles(config);
+    match &profile_args.command {
+        ProfileCommands::List => {
+            let mut lines = Vec::new();
+            for name in profiles.names()? {
+                match profiles.path(&name)? == file_path {
+                    true => lines.push(format!("{name} (in use)")),
+                    false => lines.push(name),
+                }
+            }
+            wlist::list_display(&lines, "Profiles")?;
+        }
+        ProfileCommands::New { name } => {
+            profiles.create(name)?;
+            status!("Created the profile '{name}', use it with `wl --profile {name}`");
+        }
+        ProfileCommands::Use { name } => {
+            profiles.path(name)?;
+            let config_path = crate::get_config_path();
+            let mut config = Config::from_file(Path::new(&config_path))?;
+            config.profile = (name != profiles::DEFAULT).then(|| name.clone());
+            config.to_file(Path::new(&config_path))?;
+            match config.profile {
+                Some(_) => status!("Using the profile '{name}' by default"),
+                None => status!("Using the default profile"),
+            }
+            if env::var(crate::WATCHLIST_ENV_VAR).is_ok() {
+                status!("It won't be used while {} is set", crate::WATCHLIST_ENV_VAR);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn cli_trash(
+    watchlists: &mut WatchList,
+    trash_args: &TrashArgs,
+    config: &Config,
+    file_path: &Path,
+) -> Result<(), WatchListError> {
+    let mut trash = load_trash(config, file_path)?;
+    match trash_args.command {
+        TrashCommands::Show => {
+            let lines: Vec<String> = trash
+                .entries
+                .iter()
+                .map(|e| {
+                    let deleted_at = config.format_timestamp(&e.deleted_at);
+                    match &e.trashed {
+                        Trashed::List { title, items, .. } => {
+                            format!(
+                                "List '{title}' with {} Item(s), deleted {deleted_at}",
+                                items.len()
+                            )
+                        }
+                        Trashed::Item { list, item } => {
+                            format!("'{item}' from {list}, deleted {deleted_at}")
+                        }
+                    }
+                })
+                .collect();
+            match lines.is_empty() {
+                true => status!("The trash is empty"),
+                false => wlist::list_display(&lines, "Trash")?,
+            }
+        }
+        TrashCommands::Restore { number } => {
+            let entry = trash.restore(number.saturating_sub(1), watchlists)?;
+            match entry.trashed {
+                Trashed::List { title, .. } => status!("Restored List '{title}'"),
+                Trashed::Item { list, item } => status!("Restored '{item}' to {list}"),
+            }
+        }
+        TrashCommands::Empty => {
+            trash.entries.clear();
+            status!("Emptied the trash");
+        }
+    }
+    trash.to_file()
+}
+
+fn cli_delete(
+    watchlists: &mut WatchList,
+    delete: &Delete,
+    config: &Config,
+    file_path: &Path,
+    yes: bool,
+) -> Result<(), WatchListError> {
+    if delete.items_deleted() > 0 {
+        return delete_items(watchlists, delete, config, file_path, yes);
+    }
+
+    if is_smart_list(watchlists, config, &delete.list) {
+        let prompt = format!(
+            "Are you sure you want to delete the smart list '{}'? (y/N): ",
+            &delete.list
+        );
+        match wlist::confirm(&prompt, yes)? {
+            true => {
+                let config_path = crate::get_config_path();
+                let mut config = Config::from_file(Path::new(&config_path))?;
+                config.smart_lists.remove(&delete.list);
+                config.to_file(Path::new(&config_path))?;
+                status!("Deleted Smart List '{}'", &delete.list);
+            }
+            false => status!("Deleting Cancelled"),
+        }
+        return Ok(());
+    }
+
+    let prompt = format!(
+        "Are you sure you want to delete the list '{}'? (y/N): ",
+        &delete.list
+    );
+    match wlist::confirm(&prompt, yes)? {
+        true => {
+            let settings = watchlists.list_settings(&delete.list).cloned();
+            let items = watchlists
+                .remove(&delete.list)
+                .ok_or(WatchListError::TitleNotPresent(
+                    delete.list.clone(),
+                    Vec::new(),
+                ))?;
+
+            let mut trash = load_trash(config, file_path)?;
+            let title = delete.list.clone();
+            let settings = settings.unwrap_or_default();
+            trash.add(
+                Trashed::List {
+                    title,
+                    items,
+                    settings,
+                },
+                dates::now(),
+            );
+            trash.to_file()?;
+            status!("Deleted List '{}'", &delete.list);
+        }
+        false => status!("Deleting Cancelled"),
+    }
+    Ok(())
+}
+
+// Deletes the items with the numbers given and the ones picked from the matches of each
+// prompt, picking all of them before any is deleted
+fn delete_items(
+    watchlists: &mut WatchList,
+    delete: &Delete,
+    config: &Config,
+    file_path: &Path,
+    yes: bool,
+) -> Result<(), WatchListError> {
+    let list = &delete.list;
+    let mut positions = Vec::new();
+    if delete.interactive {
+        match pick_item(&watchlists[list], list, "", yes)? {
+            Some(position) => positions.push(position),
+            None => {
+                status!("Deleting Cancelled");
+                return Ok(());
+            }
+        }
+    }
+    let picked = pick_positions(
+        watchlists,
+        list,
+        &delete.prompts,
+        &delete.index,
+        "Delete",
+        yes,
+    )?;
+    positions.extend(picked);
+    let items = watchlists.item_remove_positions(list, &positions)?;
+
+    let mut trash = load_trash(config, file_path)?;
+    for item in &items {
+        let trashed = Trashed::Item {
+            list: list.clone(),
+            item: item.clone(),
+        };
+        trash.add(trashed, dates::now());
+    }
+    trash.to_file()?;
+    match items.as_slice() {
+        [item] => status!("Deleted '{item}'"),
+        items => status!("Deleted {} Item(s)", items.len()),
+    }
+    Ok(())
+}
+
+// Finds the positions of the items with the numbers given and of the ones picked from the
+// matches of each prompt, picking all of them before anything is done with them. Items are
+// found by their positions, so only the ones picked of several items with the same title are
+// used, rather than every item with that title
+fn pick_positions(
+    watchlists: &WatchList,
+    list: &str,
+    prompts: &[String],
+    numbers: &[usize],
+    action: &str,
+    yes: bool,
+) -> Result<Vec<usize>, WatchListError> {
+    let position = |item: &Item| watchlists[list].iter().position(|i| std::ptr::eq(i, item));
+    let mut positions = Vec::new();
+    for &number in numbers {
+        let (index, sort) = item_index(watchlists, list, number)?;
+        positions.extend(position(watchlists.item_get_index(list, index, sort)?));
+    }
+    for prompt in prompts {
+        let results = watchlists.list_search_fuzzy_matches(list, prompt)?;
+        if results.is_empty() {
+            return Err(WatchListError::NoMatches(prompt.clone()));
+        }
+        let titles: Vec<String> = results
+            .iter()
+            .map(|m| theme::highlight(&m.item.title, &m.spans))
+            .collect();
+        wlist::list_display(&titles, "Matched Items")?;
+
+        let picked = select_matches(
+            &format!("Enter Items to {action}, like 1,3-5 (DEFAULT: 1): "),
+            results.len(),
+            yes,
+        )?;
+        positions.extend(picked.into_iter().filter_map(|n| position(results[n].item)));
+    }
+    Ok(positions)
+}
+
+// Moves items into the archive, the watched ones unless some are picked like for deleting
+fn cli_archive(
+    watchlists: &mut WatchList,
+    args: &ArchiveArgs,
+    config: &Config,
+    file_path: &Path,
+    yes: bool,
+) -> Result<(), WatchListError> {
+    let mut archive = Archive::from_file(file_path)?;
+    let list = match (&args.command, &args.list) {
+        (Some(ArchiveCommands::Show { list }), _) => {
+            return show_archive(&archive, list.as_deref(), config)
+        }
+        (None, Some(list)) => list,
+        // Clap requires a list without a subcommand
+        (None, None) => unreachable!(),
+    };
+    let now = dates::now();
+    let items = match args.prompts.is_empty() && args.index.is_empty() {
+        true => archive.archive_watched(watchlists, list, now)?,
+        false => {
+            let positions =
+                pick_positions(watchlists, list, &args.prompts, &args.index, "Archive", yes)?;
+            let items = watchlists.item_remove_positions(list, &positions)?;
+            for item in &items {
+                archive.add(list, item.clone(), now);
+            }
+            items
+        }
+    };
+    match items.as_slice() {
+        [] => {
+            status!("No Watched Items to Archive in '{list}'");
+            return Ok(());
+        }
+        [item] => status!("Archived '{item}'"),
+        items => status!("Archived {} Item(s)", items.len()),
+    }
+    archive.to_file()
+}
+
+fn show_archive(
+    archive: &Archive,
+    list: Option<&str>,
+    config: &Config,
+) -> Result<(), WatchListError> {
+    let lists = match list {
+        Some(list) => vec![list],
+        None => archive.lists(),
+    };
+    if lists.is_empty() {
+        status!("The archive is empty");
+        return Ok(());
+    }
+    for list in lists {
+        let lines: Vec<String> = archive
+            .entries
+            .iter()
+            .filter(|e| e.list == list)
+            .map(|e| {
+                let archived_at = config.format_timestamp(&e.archived_at);
+                format!("{}, archived {archived_at}", e.item)
+            })
+            .collect();
+        if lines.is_empty() {
+            return Err(WatchListError::NoMatches(list.to_string()));
+        }
+        wlist::list_display(&lines, list)?;
+    }
+    Ok(())
+}
+
+fn due_banner(watchlists: &WatchList, config: &Config) {
+    if config.due_soon_days == 0 {
+        return;
+    }
+    let due = due::items_due_within(watchlists, dates::today(), config.due_soon_days);
+    // Items are sorted by their due dates
+    if let Some(date) = due.first().and_then(|(_, item)| item.meta.due) {
+        let warning = format!(
+            "{} item(s) due within {} day(s), the first on {}",
+            due.len(),
+            config.due_soon_days,
+            config.format_date(date)
+        );
+        eprintln!("{}", theme::paint(&warning, Style::Warning));
+    }
+}
+
+/// Checks if the first run wizard should be shown before running the command
+///
+/// It is skipped for `init`, which runs it itself, and when only help is shown
+/// Command line interface, used to complete commands as they are typed
+pub fn command() -> clap::Command {
+    Cli::command()
+}
+
+// `requires` doesn't see global arguments given before the subcommand,
+// so `--workspace-all` needing a workspace is checked after parsing, along
+// with items added without a list when there's no terminal to pick one in. Commands
+// run in a shell or batch `in_workspace` use the workspace it was started with
+fn usage_error(cli: &Cli, in_workspace: bool) -> Option<clap::Error> {
+    match &cli.commands {
+        Commands::Add(add)
+            if add.to.is_none()
+                && add.items.is_empty()
+                && !add.stdin
+                && !io::stdin().is_terminal() =>
+        {
+            Some(command().bin_name("wl").error(
+                ErrorKind::MissingRequiredArgument,
+                "adding items without a terminal to pick their list in needs the list given with --list",
+            ))
+        }
+        Commands::Add(add) if add.url.is_some() && add.target().1.len() > 1 => {
+            Some(command().bin_name("wl").error(
+                ErrorKind::ArgumentConflict,
+                "--url can only be given when adding a single item",
+            ))
+        }
+        Commands::List(list)
+            if list.workspace_all && cli.workspace.is_none() && !in_workspace =>
+        {
+            Some(command().bin_name("wl").error(
+                ErrorKind::MissingRequiredArgument,
+                "--workspace-all needs the workspace given with --workspace",
+            ))
+        }
+        #[cfg(unix)]
+        Commands::Daemon if cli.workspace.is_some() => Some(command().bin_name("wl").error(
+            ErrorKind::ArgumentConflict,
+            "--workspace can't be used with the daemon, which keeps the Lists of one file",
+        )),
+        #[cfg(feature = "encryption")]
+        Commands::Encrypt(_) | Commands::Decrypt if cli.workspace.is_some() => {
+            Some(command().bin_name("wl").error(
+                ErrorKind::ArgumentConflict,
+                "--workspace can't be used when encrypting, which is done one data file at a time",
+            ))
+        }
+        Commands::Check(_) if cli.workspace.is_some() => Some(command().bin_name("wl").error(
+            ErrorKind::ArgumentConflict,
+            "--workspace can't be used when checking, which is done one data file at a time",
+        )),
+        #[cfg(feature = "net")]
+        Commands::Push(_) | Commands::Pull(_) if cli.workspace.is_some() => {
+            Some(command().bin_name("wl").error(
+                ErrorKind::ArgumentConflict,
+                "--workspace can't be used when pushing or pulling, which is done one data file at a time",
+            ))
+        }
+        _ => None,
+    }
+}
+
+// Lists for completing titles, which are read from the data file each time a command is completed
+fn completion_lists() -> WatchList {
+    // The command being completed can't be parsed yet, so `--file` and `--profile`
+    // are looked for directly
+    let args: Vec<String> = std::env::args().collect();
+    let flag = |short: &str, long: &str| {
+        args.windows(2)
+            .find(|w| w[0] == short || w[0] == long)
+            .map(|w| w[1].clone())
+            .or_else(|| {
+                args.iter()
+                    .find_map(|a| a.strip_prefix(&format!("{long}=")).map(String::from))
+            })
+    };
+    let file_path = flag("-f", "--file").or_else(|| {
+        let config = Config::from_file(Path::new(&crate::get_config_path())).unwrap_or_default();
+        crate::get_profile_path(&config, flag("-p", "--profile")).ok()
+    });
+    file_path
+        .and_then(|path| WatchList::from_file(Path::new(&path)).ok())
+        .unwrap_or_default()
+}
+
+fn list_titles() -> Vec<CompletionCandidate> {
+    completion_lists()
+        .into_iter()
+        .map(|(title, _)| CompletionCandidate::new(title))
+        .collect()
+}
+
+// Items of the list given earlier on the command line, or of every list if it isn't known yet
+fn item_titles() -> Vec<CompletionCandidate> {
+    let watchlists = completion_lists();
+    let list = std::env::args().find_map(|arg| watchlists.get(&arg));
+    let items: Vec<&Item> = match list {
+        Some(items) => items.iter().collect(),
+        None => watchlists.values().flatten().collect(),
+    };
+    items
+        .into_iter()
+        .map(|i| CompletionCandidate::new(&i.title))
+        .collect()
+}
+
+/// Data file given with `--file`, if any
+pub fn file_flag() -> Option<String> {
+    Cli::try_parse().ok().and_then(|cli| cli.data_file)
+}
+
+/// Profile given with `--profile`, if any
+pub fn profile_flag() -> Option<String> {
+    Cli::try_parse().ok().and_then(|cli| cli.profile)
+}
+
+/// Workspace given with `--workspace`, if any
+pub fn workspace_flag() -> Option<String> {
+    Cli::try_parse().ok().and_then(|cli| cli.workspace)
+}
+
+/// What a run of `wl` keeps track of from reading the Lists until they're saved. The
+/// daemon starts a new one for each command, so nothing is left over from the one before
+#[derive(Default)]
+pub struct Run {
+    // Versions the data files were read in, or were upgraded to since, by path
+    versions: BTreeMap<PathBuf, u32>,
+    // Files of the workspace given with `--workspace`, which the Lists are split back into
+    workspace: Option<Vec<PathBuf>>,
+    // Messages of the commits to make once the Lists are saved, when `git` is turned on
+    commits: Vec<String>,
+}
+
+/// Reads the Lists from a data file, noting its version to check before saving to it
+pub fn read_lists(file_path: &Path, run: &mut Run) -> Result<WatchList, WatchListError> {
+    let (watchlists, version) = WatchList::from_file_with_version(file_path)?;
+    run.versions.insert(file_path.to_path_buf(), version);
+    Ok(watchlists)
+}
+
+/// Moves the settings of lists older versions kept in the config file onto the Lists,
+/// saving the config file without them
+pub fn move_list_settings(
+    watchlists: &mut WatchList,
+    config: &mut Config,
+    config_path: &Path,
+) -> Result<(), WatchListError> {
+    match config.move_list_settings(watchlists) {
+        true => config.to_file(config_path),
+        false => Ok(()),
+    }
+}
+
+/// Uses the Lists of every file of a workspace together, see [`workspace`]
+pub fn use_workspace(files: Vec<(PathBuf, WatchList)>, run: &mut Run) -> WatchList {
+    run.workspace = Some(files.iter().map(|(path, _)| path.clone()).collect());
+    workspace::join(files)
+}
+
+/// Saves the Lists to the data file, or to the files of the workspace they came from
+pub fn save_lists(
+    watchlists: &WatchList,
+    file_path: &Path,
+    answers: Answers,
+    run: &mut Run,
+) -> Result<(), WatchListError> {
+    match &run.workspace {
+        Some(files) => workspace::split(watchlists, files)
+            .iter()
+            .try_for_each(|(path, lists)| save_file(lists, path, answers, &mut run.versions))?,
+        None => save_file(watchlists, file_path, answers, &mut run.versions)?,
+    }
+    commit_files(file_path, run)
+}
+
+// Commits the saved files with the messages of the commands which changed them, all in
+// one commit when several commands were run before saving, like in the shell
+fn commit_files(file_path: &Path, run: &mut Run) -> Result<(), WatchListError> {
+    let messages = std::mem::take(&mut run.commits);
+    let message = match messages.as_slice() {
+        [] => return Ok(()),
+        [message] => message.clone(),
+        messages => {
+            let subjects: Vec<&str> = messages.iter().filter_map(|m| m.lines().next()).collect();
+            format!("{} changes\n\n- {}", messages.len(), subjects.join("\n- "))
+        }
+    };
+    match &run.workspace {
+        Some(files) => files.iter().try_for_each(|file| {
+            git::commit(file, &message)?;
+            Ok(())
+        }),
+        None => git::commit(file_path, &message).map(|_| ()),
+    }
+}
+
+// Saves Lists to a file, first showing what changes and asking to go ahead
+// if they need a newer version of the file than the one it was read in
+fn save_file(
+    watchlists: &WatchList,
+    file_path: &Path,
+    answers: Answers,
+    versions: &mut BTreeMap<PathBuf, u32>,
+) -> Result<(), WatchListError> {
+    // Files which didn't exist yet are written in the newest version straight away
+    let version = versions.get(file_path).copied().unwrap_or(schema::CURRENT);
+    let new_version = schema::save_version(version, watchlists);
+    if new_version > version {
+        println!(
+            "Saving the Lists upgrades {} from version {version} to version {new_version}:",
+            file_path.display()
+        );
+        for change in schema::changes(version, new_version) {
+            println!("- {change}");
+        }
+        let upgrade = answers.upgrade
+            || ((answers.yes || io::stdin().is_terminal())
+                && wlist::confirm("Upgrade the data file? (y/N): ", answers.yes)?);
+        if !upgrade {
+            return Err(WatchListError::UpgradeRequired(new_version));
+        }
+        let backup = schema::backup(file_path, version)?;
+        status!(
+            "Kept a copy for older versions of wl at {}",
+            backup.display()
+        );
+        versions.insert(file_path.to_path_buf(), new_version);
+    }
+    watchlists.to_file_with_version(file_path, new_version)
+}
+
+pub fn wants_first_run_setup() -> bool {
+    Cli::try_parse().is_ok_and(|cli| !cli.yes && !matches!(cli.commands, Commands::Init(_)))
+}
+
+// Serving reads the changes other commands make to the file while it runs, so it
+// can't keep them waiting for the lock, while the daemon locks it for each command.
+// Help and mistakes in the arguments are shown without touching the file
+pub fn needs_lock() -> bool {
+    Cli::try_parse().is_ok_and(|cli| match cli.commands {
+        Commands::Serve(_) => false,
+        #[cfg(unix)]
+        Commands::Daemon => false,
+        _ => true,
+    })
+}
+
+// Commands which a daemon can run, leaving out the ones taking over the terminal or
+// running until they're stopped, and ones using more than one file
+#[cfg(unix)]
+pub fn runs_in_daemon() -> bool {
+    Cli::try_parse().is_ok_and(|cli| {
+        cli.workspace.is_none()
+            && !cli.upgrade
+            && match cli.commands {
+                Commands::Init(_)
+                | Commands::Shell
+                | Commands::Serve(_)
+                | Commands::Daemon
+                | Commands::Check(_)
+                | Commands::EditList(_)
+                | Commands::Batch(_)
+                | Commands::Completions(_) => false,
+                #[cfg(feature = "tui")]
+                Commands::Tui => false,
+                // Passphrases are asked for in the terminal of the command
+                #[cfg(feature = "encryption")]
+                Commands::Encrypt(_) | Commands::Decrypt => false,
+                _ => true,
+            }
+    })
+}
+
+fn cli_init(
+    watchlists: &mut WatchList,
+    init: &Init,
+    file_path: &Path,
+    yes: bool,
+) -> Result<(), WatchListError> {
+    let config_path = crate::get_config_path();
+    if !init.interactive {
+        if Path::new(&config_path).exists() {
+            status!("A config file already exists at {config_path}");
+        } else {
+            Config::default().to_file(Path::new(&config_path))?;
+            status!("Wrote the default settings to {config_path}");
+        }
+        return Ok(());
+    }
+
+    wlist::require_terminal("Settings for the config file", yes)?;
+    let setup = setup::wizard(&mut io::stdin().lock(), &mut io::stdout(), file_path)?;
+    let data_path = setup::apply(&setup, Path::new(&config_path), file_path)?;
+    // The lists in the data file are saved again once the command finishes
+    if data_path == file_path {
+        let compact = watchlists.compact();
+        *watchlists = WatchList::from_file(file_path)?;
+        watchlists.set_compact(compact);
+    }
+    status!(
+        "Saved settings to {config_path}, the Lists are stored in {}",
+        data_path.display()
+    );
+    Ok(())
+}
+
+/// Reads the command from the arguments, showing help or mistakes in them and exiting
+/// like `Cli::parse` does
+pub fn parse() -> Cli {
+    let cli = Cli::parse();
+    if let Some(e) = usage_error(&cli, false) {
+        e.exit();
+    }
+    cli
+}
+
+impl Cli {
+    /// How the questions asked while running the command are answered
+    pub fn answers(&self) -> Answers {
+        Answers {
+            yes: self.yes,
+            upgrade: self.upgrade,
+        }
+    }
+}
+
+/// How questions are answered, passed down from the options of each command as commands
+/// sent to the daemon each come with their own
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Answers {
+    /// Every question is answered without asking, with `--yes`
+    yes: bool,
+    /// Upgrading the data file is agreed to without asking, with `--upgrade`
+    upgrade: bool,
+}
+
+impl Answers {
+    // Commands run in the shell or a batch also take the answers given to it
+    fn combined(self, other: Answers) -> Answers {
+        Answers {
+            yes: self.yes || other.yes,
+            upgrade: self.upgrade || other.upgrade,
+        }
+    }
+}
+
+pub fn cli_run(
+    cli: &Cli,
+    watchlists: &mut WatchList,
+    config: &Config,
+    file_path: &Path,
+    run: &mut Run,
+) -> Result<(), WatchListError> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    run_cli(cli, args, watchlists, config, file_path, run)
+}
+
+/// How the output of a run is styled, from `--theme`, `--quiet` and the config file
+pub fn output_context(cli: &Cli, config: &Config) -> theme::Context {
+    theme::Context {
+        theme: cli.theme.map_or(config.theme, Theme::from),
+        colors: config.colors.clone(),
+        accessible: config.accessible,
+        quiet: cli.quiet,
+    }
+}
+
+// Commands run in the shell or a batch are styled like it, unless they're given a
+// `--theme` or `--quiet` of their own
+fn line_context(cli: &Cli) -> theme::Context {
+    let context = theme::context();
+    theme::Context {
+        theme: cli.theme.map_or(context.theme, Theme::from),
+        quiet: context.quiet || cli.quiet,
+        ..context
+    }
+}
+
+fn run_cli(
+    cli: &Cli,
+    args: Vec<String>,
+    watchlists: &mut WatchList,
+    config: &Config,
+    file_path: &Path,
+    run: &mut Run,
+) -> Result<(), WatchListError> {
+    watchlists.set_compact(cli.compact || config.compact);
+    if !cli.quiet && !matches!(cli.commands, Commands::Notify(_)) {
+        due_banner(watchlists, config);
+    }
+    run_recorded(
+        &cli.commands,
+        args,
+        watchlists,
+        config,
+        file_path,
+        cli.lenient,
+        cli.answers(),
+        run,
+    )
+}
+
+// Reads items one per line, leaving out blank lines
+fn read_items(input: impl io::BufRead) -> Result<Vec<String>, WatchListError> {
+    let mut items = Vec::new();
+    for line in input.lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            items.push(line.trim().to_string());
+        }
+    }
+    Ok(items)
+}
+
+// Asks which list items added without one go in, suggesting the one they're most like first
+fn pick_list(
+    add: &Add,
+    watchlists: &WatchList,
+    file_path: &Path,
+    yes: bool,
+) -> Result<Option<String>, WatchListError> {
+    let mut past: HashMap<String, usize> = HashMap::new();
+    for operation in history::read(file_path, None)? {
+        if let Some(Commands::Add(add)) = logged_command(&operation) {
+            let (list, items) = add.target();
+            *past.entry(list.clone()).or_default() += items.len();
+        }
+    }
+    let items: Vec<&str> = std::iter::once(&add.list)
+        .chain(&add.items)
+        .map(String::as_str)
+        .collect();
+    let likely = suggest::likely_lists(watchlists, &items, &past);
+    match likely.as_slice() {
+        [] => return Err(WatchListError::NoTitles),
+        [only] => return Ok(Some(only.clone())),
+        _ => {}
+    }
+
+    let shown = &likely[..likely.len().min(3)];
+    wlist::list_display(shown, "Likely Lists")?;
+    loop {
+        let input = wlist::input_or_default(
+            &format!(
+                "Add {} Item(s) to {}? (Y/n, or the number or title of another list): ",
+                items.len(),
+                likely[0]
+            ),
+            yes,
+        )?;
+        match input.to_lowercase().as_str() {
+            "" | "y" => return Ok(Some(likely[0].clone())),
+            "n" => return Ok(None),
+            _ => {}
+        }
+        if let Some(n) = input
+            .parse::<usize>()
+            .ok()
+            .filter(|n| (1..=shown.len()).contains(n))
+        {
+            return Ok(Some(shown[n - 1].clone()));
+        }
+        if watchlists.contains(&input) {
+            return Ok(Some(input));
+        }
+    }
+}
+
+// Aliases of lists are saved with the list in the data file, while the ones of smart lists
+// and categories, which aren't in it, are saved in the config file
+fn cli_alias(
+    watchlists: &mut WatchList,
+    alias: &Alias,
+    config: &Config,
+) -> Result<(), WatchListError> {
+    let name = match (&alias.name, &alias.list) {
+        (None, _) => {
+            let mut aliases: Vec<(&String, &String)> = watchlists
+                .settings()
+                .flat_map(|(list, settings)| settings.aliases.iter().map(move |a| (a, list)))
+                .chain(&config.aliases)
+                .collect();
+            aliases.sort();
+            let aliases: Vec<String> = aliases
+                .into_iter()
+                .map(|(name, list)| format!("{name} - {list}"))
+                .collect();
+            match aliases.is_empty() {
+                true => status!("No Aliases Added"),
+                false => wlist::list_display(&aliases, "Aliases")?,
+            }
+            return Ok(());
+        }
+        (Some(name), None) if !alias.remove => {
+            let list = watchlists
+                .list_for_alias(name)
+                .map(String::as_str)
+                .or(config.alias(name))
+                .ok_or(WatchListError::NoMatches(name.clone()))?;
+            println!("{list}");
+            return Ok(());
+        }
+        (Some(name), _) => name,
+    };
+    let config_path = crate::get_config_path();
+    let mut config = Config::from_file(Path::new(&config_path))?;
+    let before = config.aliases.clone();
+    match &alias.list {
+        Some(list) => {
+            let known = watchlists.contains(list)
+                || is_smart_list(watchlists, &config, list)
+                || watchlists.is_category(list);
+            if !known {
+                return Err(WatchListError::TitleNotPresent(list.clone(), Vec::new()));
+            }
+            // A list with the same title would always be used instead of the alias
+            if watchlists.contains(name) || config.smart_lists.contains_key(name) {
+                return Err(WatchListError::TitleAlreadyPresent(name.clone()));
+            }
+            config.aliases.remove(name);
+            match watchlists.contains(list) {
+                true => watchlists.list_add_alias(list, name)?,
+                false => {
+                    watchlists.list_remove_alias(name);
+                    config.aliases.insert(name.clone(), list.clone());
+                }
+            }
+            status!("Added Alias '{name}' for '{list}'");
+        }
+        None => {
+            let removed = watchlists.list_remove_alias(name).is_some();
+            if config.aliases.remove(name).is_none() && !removed {
+                return Err(WatchListError::NoMatches(name.clone()));
+            }
+            status!("Removed Alias '{name}'");
+        }
+    }
+    match config.aliases != before {
+        true => config.to_file(Path::new(&config_path)),
+        false => Ok(()),
+    }
+}
+
+// Runs a command, recording any changes it makes in the undo history and the history log
+#[allow(clippy::too_many_arguments)]
+fn run_recorded(
+    command: &Commands,
+    args: Vec<String>,
+    watchlists: &mut WatchList,
+    config: &Config,
+    file_path: &Path,
+    lenient: bool,
+    answers: Answers,
+    run: &mut Run,
+) -> Result<(), WatchListError> {
+    let before = watchlists.clone();
+    let mut args = args;
+
+    // The list picked for items added without one is recorded with the command,
+    // so running it again from the history adds them to the same list
+    let picked;
+    let command = match command {
+        // Items read from standard input are recorded with the command as if they were
+        // given, so it's logged with the items it added
+        Commands::Add(add) if add.stdin => {
+            let mut items = read_items(io::stdin().lock())?;
+            if !args.iter().any(|a| a == "--") {
+                items.insert(0, "--".to_string());
+            }
+            args.extend(items);
+            picked = Cli::try_parse_from(std::iter::once("wl".to_string()).chain(args.clone()))
+                .map_err(|e| WatchListError::ParseError(e.to_string()))?;
+            &picked.commands
+        }
+        Commands::Add(add) if add.needs_list(watchlists) && io::stdin().is_terminal() => {
+            let Some(list) = pick_list(add, watchlists, file_path, answers.yes)? else {
+                status!("Adding Cancelled");
+                return Ok(());
+            };
+            let at = args.iter().position(|a| a == "--").unwrap_or(args.len());
+            args.splice(at..at, ["--list".to_string(), list]);
+            picked = Cli::try_parse_from(std::iter::once("wl".to_string()).chain(args.clone()))
+                .map_err(|e| WatchListError::ParseError(e.to_string()))?;
+            &picked.commands
+        }
+        _ => command,
+    };
+    let mut result = run_command(command, watchlists, config, file_path, answers, run)
+        .map_err(|e| e.with_suggestions(watchlists));
+
+    // Nothing was changed before the list wasn't found, so the command can safely run
+    // again with the list of the alias, or the one list it was most likely meant to be.
+    // Aliases are only looked up then, so lists with the same title are used instead
+    let resolve = |title: &str, list: &str| {
+        let resolved: Vec<String> = args
+            .iter()
+            .map(|a| match a == title {
+                true => list.to_string(),
+                false => a.clone(),
+            })
+            .collect();
+        Cli::try_parse_from(std::iter::once("wl".to_string()).chain(resolved.clone()))
+            .ok()
+            .filter(|_| resolved != args)
+            .map(|cli| (cli, resolved))
+    };
+    let retry = match &result {
+        Err(WatchListError::TitleNotPresent(title, suggestions)) if *watchlists == before => {
+            let alias = watchlists
+                .list_for_alias(title)
+                .map(String::as_str)
+                .or(config.alias(title));
+            match (alias, suggestions.as_slice()) {
+                (Some(list), _) => resolve(title, list).map(|(cli, args)| (cli, args, None)),
+                (None, [list]) if lenient => {
+                    resolve(title, list).map(|(cli, args)| (cli, args, Some(list)))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+    if let Some((cli, resolved, suggested)) = retry {
+        if let Some(list) = suggested {
+            status!("Using the list {list}");
+        }
+        args = resolved;
+        result = run_command(&cli.commands, watchlists, config, file_path, answers, run)
+            .map_err(|e| e.with_suggestions(watchlists));
+    }
+    // Changes are recorded even if the command failed part way through, while
+    // each command run in the shell is recorded on its own
+    if !matches!(command, Commands::Shell) && *watchlists != before {
+        record_change(command, args, before, watchlists, config, file_path, run)?;
+    }
+    result
+}
+
+// Records a change made by the command in the index, the undo history and the history,
+// and notes the commit to make once the Lists are saved when `git` is turned on
+fn record_change(
+    command: &Commands,
+    args: Vec<String>,
+    before: WatchList,
+    watchlists: &WatchList,
+    config: &Config,
+    file_path: &Path,
+    run: &mut Run,
+) -> Result<(), WatchListError> {
+    let mut index = SearchIndex::from_file(file_path)?;
+    index.update(&before, watchlists);
+    index.to_file()?;
+
+    let operation = Operation::new(args.clone(), dates::now());
+    // Reverting gives its commit a message of its own
+    if config.git && !matches!(command, Commands::Revert(_)) {
+        let message = git::message(&before, watchlists, &operation.to_string());
+        run.commits.push(message);
+    }
+    if !matches!(command, Commands::Undo) {
+        let mut history = UndoHistory::from_file(file_path)?;
+        history.record(args.join(" "), before);
+        history.to_file()?;
+    }
+    history::append(file_path, &operation)
+}
+
+// Makes a change asked for through the API of `wl serve` like the command which does the
+// same, so it's recorded, what it deletes goes to the trash and the hooks run for it
+fn serve_change(
+    method: &str,
+    path: &str,
+    body: &str,
+    compact: bool,
+    config: &Config,
+    file_path: &Path,
+    workspace: &Option<Vec<PathBuf>>,
+) -> Result<serve::Response, WatchListError> {
+    let mut run = Run {
+        workspace: workspace.clone(),
+        ..Run::default()
+    };
+    let (response, changed) = WatchList::update_file(file_path, |watchlists| {
+        watchlists.set_validation(config.validation.clone());
+        watchlists.set_compact(compact);
+        let before = watchlists.clone();
+        let response = serve::respond_api(method, path, body, watchlists);
+        let args = serve::command(method, path, body).unwrap_or_default();
+        let cli = Cli::try_parse_from(std::iter::once("wl".to_string()).chain(args.clone()));
+        let (Ok(cli), false) = (cli, *watchlists == before) else {
+            return Ok((response, None));
+        };
+        trash_removed(&before, watchlists, config, file_path)?;
+        record_change(
+            &cli.commands,
+            args,
+            before.clone(),
+            watchlists,
+            config,
+            file_path,
+            &mut run,
+        )?;
+        Ok((response, Some((before, watchlists.clone()))))
+    })?;
+    if let Some((before, after)) = changed {
+        commit_files(file_path, &mut run)?;
+        for failed in config.hooks.fire(&before, &after) {
+            eprintln!("Hook failed\n{failed}");
+        }
+    }
+    Ok(response)
+}
+
+// Puts the Lists and Items which were deleted into the trash
+fn trash_removed(
+    before: &WatchList,
+    after: &WatchList,
+    config: &Config,
+    file_path: &Path,
+) -> Result<(), WatchListError> {
+    let mut trashed = Vec::new();
+    for difference in diff::diff(before, after) {
+        match difference {
+            Difference::ListRemoved { list, .. } => trashed.push(Trashed::List {
+                items: before[&list].clone(),
+                settings: before.list_settings(&list).cloned().unwrap_or_default(),
+                title: list,
+            }),
+            Difference::ItemRemoved { list, item } => {
+                if let Some(item) = before[&list].iter().find(|i| i.title == item) {
+                    trashed.push(Trashed::Item {
+                        item: item.clone(),
+                        list,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    if trashed.is_empty() {
+        return Ok(());
+    }
+    let mut trash = load_trash(config, file_path)?;
+    for trashed in trashed {
+        trash.add(trashed, dates::now());
+    }
+    trash.to_file()
+}
+
+// Runs the commands sent by other `wl` commands on the Lists kept in memory, which are
+// only read again when something else changed the file
+#[cfg(unix)]
+fn cli_daemon(
+    watchlists: &mut WatchList,
+    file_path: &Path,
+    run: &Run,
+) -> Result<(), WatchListError> {
+    let daemon = Daemon::bind(file_path)?;
+    status!(
+        "Keeping the Lists of {} in memory, listening on {}, press Ctrl+C to stop",
+        file_path.display(),
+        daemon.socket().display()
+    );
+    let mut saved = file_state(file_path);
+    let mut versions = run.versions.clone();
+    daemon.serve(|request| {
+        // Each command is run on its own, only keeping the version of the Lists in memory
+        let mut run = Run {
+            versions: std::mem::take(&mut versions),
+            ..Run::default()
+        };
+        let result = run_request(request, watchlists, file_path, &mut saved, &mut run);
+        versions = run.versions;
+        match result {
+            Ok(code) => code,
+            // Whatever was reading the output stopped early, like `head`, which is no error
+            Err(e) if output::is_closed(&e) => 0,
+            Err(e) => {
+                eprintln!("{}", theme::paint(&e.to_string(), Style::Error));
+                e.exit_code()
+            }
+        }
+    })
+}
+
+// Runs a command sent to the daemon the way `main` runs one, giving its exit code
+#[cfg(unix)]
+fn run_request(
+    request: &daemon::Request,
+    watchlists: &mut WatchList,
+    file_path: &Path,
+    saved: &mut Option<(std::time::SystemTime, u64)>,
+    run: &mut Run,
+) -> Result<u8, WatchListError> {
+    let cli =
+        match Cli::try_parse_from(std::iter::once("wl".to_string()).chain(request.args.clone())) {
+            Ok(cli) => cli,
+            // Help and mistakes in the arguments are shown by clap
+            Err(e) => {
+                let _ = e.print();
+                return Ok(e.exit_code() as u8);
+            }
+        };
+    if let Some(e) = usage_error(&cli, false) {
+        let _ = e.print();
+        return Ok(e.exit_code() as u8);
+    }
+    let mut config = match Config::from_file(&request.config) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Error reading config file! Using default settings\n{e}");
+            Config::default()
+        }
+    };
+
+    let _context = theme::enter(output_context(&cli, &config));
+    let _lock = FileLock::acquire(file_path)?;
+    // Changed by a command which didn't go through the daemon
+    if file_state(file_path) != *saved {
+        *watchlists = match read_lists(file_path, run) {
+            Err(WatchListError::IOError(e)) if e.kind() == io::ErrorKind::NotFound => {
+                WatchList::new()
+            }
+            lists => lists?,
+        };
+    }
+    move_list_settings(watchlists, &mut config, &request.config)?;
+    watchlists.set_validation(config.validation.clone());
+    #[cfg(feature = "mqtt")]
+    let before = config.mqtt.as_ref().map(|_| watchlists.clone());
+    let unhooked = (!config.hooks.is_empty()).then(|| watchlists.clone());
+
+    let result = run_cli(
+        &cli,
+        request.args.clone(),
+        watchlists,
+        &config,
+        file_path,
+        run,
+    );
+
+    #[cfg(feature = "mqtt")]
+    if let (Some(mqtt), Some(before)) = (&config.mqtt, &before) {
+        if let Err(e) = wlist::mqtt::publish_changes(mqtt, before, watchlists) {
+            eprintln!("Couldn't publish changes to MQTT broker\n{e}");
+        }
+    }
+    save_lists(watchlists, file_path, cli.answers(), run)?;
+    *saved = file_state(file_path);
+    if let Some(before) = &unhooked {
+        for failed in config.hooks.fire(before, watchlists) {
+            eprintln!("Hook failed\n{failed}");
+        }
+    }
+    result.map(|()| 0)
+}
+
+// When the file was last changed and how long it is, none if it doesn't exist
+#[cfg(unix)]
+fn file_state(file_path: &Path) -> Option<(std::time::SystemTime, u64)> {
+    let metadata = fs::metadata(file_path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+fn cli_shell(
+    watchlists: &mut WatchList,
+    config: &Config,
+    file_path: &Path,
+    answers: Answers,
+    run: &mut Run,
+) -> Result<(), WatchListError> {
+    println!("Enter commands without `wl`, like `add Movies \"The Thing\"`");
+    println!("`:w` saves the Lists and `:q` saves them and quits");
+    let mut line = String::new();
+    loop {
+        print!("wl> ");
+        io::stdout().flush()?;
+        line.clear();
+        if io::stdin().read_line(&mut line)? == 0 {
+            println!();
+            break;
+        }
+        match line.trim() {
+            "" => continue,
+            ":q" | "exit" | "quit" => break,
+            ":w" => {
+                match save_lists(watchlists, file_path, answers, run) {
+                    Ok(()) => status!("Saved!"),
+                    Err(e) => eprintln!("{}", theme::paint(&e.to_string(), Style::Error)),
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        let args = match shell::split_args(&line) {
+            Ok(args) => args,
+            Err(e) => {
+                let message = format!("Couldn't read the command, {e}!");
+                eprintln!("{}", theme::paint(&message, Style::Error));
+                continue;
+            }
+        };
+        let cli = match Cli::try_parse_from(std::iter::once("wl".to_string()).chain(args.clone())) {
+            Ok(cli) => cli,
+            // Help and mistakes in the arguments are shown by clap
+            Err(e) => {
+                let _ = e.print();
+                continue;
+            }
+        };
+        if matches!(cli.commands, Commands::Init(_) | Commands::Shell) {
+            println!("Can't be used in the shell");
+            continue;
+        }
+        if cli.data_file.is_some() || cli.profile.is_some() || cli.workspace.is_some() {
+            println!("--file, --profile and --workspace can't be used in the shell, start another shell with them instead");
+            continue;
+        }
+        if let Some(e) = usage_error(&cli, run.workspace.is_some()) {
+            let _ = e.print();
+            continue;
+        }
+        let _context = theme::enter(line_context(&cli));
+        if let Err(e) = run_recorded(
+            &cli.commands,
+            args,
+            watchlists,
+            config,
+            file_path,
+            cli.lenient,
+            answers.combined(cli.answers()),
+            run,
+        ) {
+            eprintln!("{}", theme::paint(&e.to_string(), Style::Error));
+        }
+    }
+    Ok(())
+}
+
+// Runs the commands of a batch file on the Lists in memory, putting the Lists and the
+// config file back the way they were when one of them fails. The batch is recorded as one
+// change, so it's undone all at once
+fn cli_batch(
+    watchlists: &mut WatchList,
+    batch: &Batch,
+    config: &Config,
+    file_path: &Path,
+    answers: Answers,
+    run: &mut Run,
+) -> Result<(), WatchListError> {
+    let (script, name) = match batch.file.as_os_str() == "-" {
+        true => (
+            io::read_to_string(io::stdin())?,
+            "standard input".to_string(),
+        ),
+        false => (
+            limits::read_file(&batch.file)?,
+            batch.file.display().to_string(),
+        ),
+    };
+    let mut commands = Vec::new();
+    for (number, line) in script.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let at_line = |reason: String| {
+            WatchListError::ParseError(format!("line {} of {name}, {reason}", number + 1))
+        };
+        let mut args = shell::split_args(line).map_err(at_line)?;
+        // Commands copied from a terminal can start with `wl`
+        if args.first().is_some_and(|a| a == "wl") {
+            args.remove(0);
+        }
+        let command = args.first().cloned().unwrap_or_default();
+        let cli = Cli::try_parse_from(std::iter::once("wl".to_string()).chain(args))
+            .map_err(|e| at_line(clap_reason(&e)))?;
+        if let Some(reason) = batch_error(&cli, &command) {
+            return Err(at_line(reason));
+        }
+        if let Some(e) = usage_error(&cli, run.workspace.is_some()) {
+            return Err(at_line(clap_reason(&e)));
+        }
+        commands.push((number + 1, cli));
+    }
+
+    let before = watchlists.clone();
+    // Commands like `new --smart` and `alias` save the config file straight away, and
+    // ones like `delete` and `archive` the files next to the data file
+    let files = Snapshot::take(
+        std::iter::once(PathBuf::from(crate::get_config_path())).chain(
+            wlist::encryption::SIDECARS
+                .iter()
+                .map(|name| wlist::sidecar_path(file_path, name)),
+        ),
+    )?;
+    for (number, cli) in &commands {
+        let _context = theme::enter(line_context(cli));
+        if let Err(e) = run_command(
+            &cli.commands,
+            watchlists,
+            config,
+            file_path,
+            answers.combined(cli.answers()),
+            run,
+        ) {
+            *watchlists = before;
+            files.restore();
+            eprintln!("Line {number} failed, so the Lists and settings were left as they were");
+            return Err(e);
+        }
+    }
+    status!("Ran {} Command(s) from {name}", commands.len());
+    Ok(())
+}
+
+// Contents of files from before a batch, or none for the ones which didn't exist yet
+struct Snapshot(Vec<(PathBuf, Option<Vec<u8>>)>);
+
+impl Snapshot {
+    fn take(paths: impl IntoIterator<Item = PathBuf>) -> io::Result<Snapshot> {
+        let mut files = Vec::new();
+        for path in paths {
+            let data = match fs::read(&path) {
+                Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+                data => Some(data?),
+            };
+            files.push((path, data));
+        }
+        Ok(Snapshot(files))
+    }
+
+    // Puts every file back the way it was, going on to the rest when one can't be
+    fn restore(&self) {
+        for (path, data) in &self.0 {
+            let restored = match data {
+                Some(data) => fs::write(path, data),
+                None => fs::remove_file(path).or_else(|e| match e.kind() {
+                    io::ErrorKind::NotFound => Ok(()),
+                    _ => Err(e),
+                }),
+            };
+            if let Err(e) = restored {
+                eprintln!("Couldn't put {} back the way it was\n{e}", path.display());
+            }
+        }
+    }
+}
+
+// Commands which save files of their own or don't finish can't be run in a batch, which
+// only saves the Lists once every command is done
+fn batch_error(cli: &Cli, name: &str) -> Option<String> {
+    if cli.data_file.is_some() || cli.profile.is_some() || cli.workspace.is_some() {
+        return Some("--file, --profile and --workspace can't be used in a batch".to_string());
+    }
+    let allowed = match &cli.commands {
+        Commands::Init(_)
+        | Commands::Shell
+        | Commands::Batch(_)
+        | Commands::Serve(_)
+        | Commands::Check(_) => false,
+        #[cfg(unix)]
+        Commands::Daemon => false,
+        #[cfg(feature = "tui")]
+        Commands::Tui => false,
+        #[cfg(feature = "encryption")]
+        Commands::Encrypt(_) | Commands::Decrypt => false,
+        #[cfg(feature = "net")]
+        Commands::Push(_) | Commands::Pull(_) => false,
+        Commands::Add(add) if add.stdin => {
+            return Some(
+                "--stdin can't be used in a batch, give the items on the line instead".to_string(),
+            )
+        }
+        _ => true,
+    };
+    (!allowed).then(|| format!("`{name}` can't be used in a batch"))
+}
+
+// The first line of a clap error, without its `error:` prefix
+fn clap_reason(e: &clap::Error) -> String {
+    let message = e.to_string();
+    let first = message.lines().next().unwrap_or_default();
+    first.strip_prefix("error: ").unwrap_or(first).to_string()
+}
+
+fn run_command(
+    command: &Commands,
+    watchlists: &mut WatchList,
+    config: &Config,
+    file_path: &Path,
+    answers: Answers,
+    run: &mut Run,
+) -> Result<(), WatchListError> {
+    // Only the items and matches shown can be long enough to page
+    output::set_paging(match command {
+        Commands::List(list) => !list.no_pager,
+        Commands::Search(search) => !search.no_pager,
+        _ => false,
+    });
+    match command {
+        // Shown from a copy combining the lists of every file, which isn't saved
+        Commands::List(list) if list.workspace_all => {
+            let mut combined = workspace::combine(watchlists);
+            run_subcommand(command, &mut combined, config, file_path, answers, run)
+        }
+        _ => run_subcommand(command, watchlists, config, file_path, answers, run),
+    }
+}
+
+fn run_subcommand(
+    command: &Commands,
+    watchlists: &mut WatchList,
+    config: &Config,
+    file_path: &Path,
+    answers: Answers,
+    run: &mut Run,
+) -> Result<(), WatchListError> {
+    match command {
+        Commands::Init(init) => cli_init(watchlists, init, file_path, answers.yes)?,
+        Commands::New(new) => match &new.smart {
+            Some(filter) => {
+                filter.parse::<Query>()?;
+                if watchlists.contains(&new.list) || config.smart_lists.contains_key(&new.list) {
+                    return Err(WatchListError::TitleAlreadyPresent(new.list.clone()));
+                }
+                let config_path = crate::get_config_path();
+                let mut config = Config::from_file(Path::new(&config_path))?;
+                config.smart_lists.insert(new.list.clone(), filter.clone());
+                config.to_file(Path::new(&config_path))?;
+                status!("Smart List Created!");
+            }
+            None => {
+                watchlists.list_add(&new.list)?;
+                watchlists.list_settings_mut(&new.list)?.no_random = new.no_random;
+                status!("List Created!");
+            }
+        },
+        Commands::Add(add) => {
+            #[cfg(feature = "tmdb")]
+            if add.lookup {
+                return cli_lookup(watchlists, add, config, answers.yes);
+            }
+            let duplicates = add.duplicates(config);
+            let (list, items) = add.target();
+            let url = add.url.as_deref().map(str::trim);
+            if let Some(url) = url {
+                links::check(url)?;
+            }
+            let mut skipped = 0;
+            for item in items {
+                let mut item = Item::new(item)
+                    .with_tags(&add.tags)
+                    .with_added_at(dates::now());
+                if let Some(url) = url {
+                    item = item.with_url(url);
+                }
+                match watchlists.item_add(list, item, duplicates) {
+                    Err(WatchListError::ItemAlreadyPresent(..)) if add.stdin => skipped += 1,
+                    added => added?,
+                }
+            }
+            match add.stdin {
+                true => status!(
+                    "Added {} Item(s), skipped {skipped} already in {list}",
+                    add.target().1.len() - skipped
+                ),
+                false => status!("Item(s) Added!"),
+            }
+        }
+        Commands::List(list) if list.due => display_due(watchlists, list, config)?,
+        Commands::List(list) if list.recent.is_some() || list.since.is_some() => {
+            display_recent(watchlists, list, config)?
+        }
+        Commands::List(list) if list.counts || (list.list.is_none() && !list.all_items) => {
+            display_counts(watchlists, list, config)?
+        }
+        Commands::List(list)
+            if !list.all_items
+                && list
+                    .list
+                    .as_ref()
+                    .is_some_and(|l| is_smart_list(watchlists, config, l)) =>
+        {
+            display_smart(watchlists, list, config)?
+        }
+        Commands::List(list) => {
+            let fields = fields::lookup(&list.fields)?;
+            // The lists of a category are shown one after another, like every list
+            let category = list
+                .list
+                .as_ref()
+                .filter(|l| !list.all_items && watchlists.is_category(l));
+            let all_lists = match category {
+                Some(category) => watchlists.category_lists(category),
+                None => watchlists.list_get_all()?,
+            };
+            if let Some(format) = list.format.machine() {
+                let titles = match &list.list {
+                    Some(title) if !list.all_items && category.is_none() => vec![title],
+                    _ => all_lists,
+                };
+                let mut items = Vec::new();
+                for title in titles {
+                    let sorted =
+                        watchlists.item_get_sorted(title, list.sort_by(watchlists, title))?;
+                    items.extend(
+                        sorted
+                            .into_iter()
+                            .filter(|i| i.has_tags(&list.tags))
+                            .map(|i| (title.as_str(), i)),
+                    );
+                }
+                print_entries(&Entry::numbered(watchlists, &items), &fields, format)?;
+            } else if list.all_items || category.is_some() {
+                // Each list is written as soon as it's sorted
+                let mut out = output::stdout();
+                for title in all_lists {
+                    if watchlists[title].is_empty() {
+                        continue;
+                    }
+                    let mut items =
+                        watchlists.item_get_sorted(title, list.sort_by(watchlists, title))?;
+                    items.retain(|i| i.has_tags(&list.tags));
+                    if !items.is_empty() {
+                        write_items(&mut out, &items, title, &fields)
+                            .and_then(|()| writeln!(out))?;
+                    }
+                }
+                out.flush()?;
+            // Display List Items
+            } else if let Some(l) = &list.list {
+                let mut items = watchlists.item_get_sorted(l, list.sort_by(watchlists, l))?;
+                items.retain(|i| i.has_tags(&list.tags));
+                display_items(&items, l, &fields)?;
+            }
+        }
+        Commands::Random(random) => cli_random(watchlists, random, config, file_path, answers.yes)?,
+        Commands::Delete(delete) => cli_delete(watchlists, delete, config, file_path, answers.yes)?,
+        Commands::Review(review) => {
+            cli_review(watchlists, review, config, file_path, answers, run)?
+        }
+        Commands::Shell => cli_shell(watchlists, config, file_path, answers, run)?,
+        Commands::Batch(batch) => cli_batch(watchlists, batch, config, file_path, answers, run)?,
+        Commands::Completions(completions) => {
+            let shell = completions.shell.to_string();
+            // Every builtin shell has a completer
+            let shells = Shells::builtins();
+            let completer = shells.completer(&shell).unwrap();
+            completer.write_registration("COMPLETE", "wl", "wl", "wl", &mut io::stdout())?;
+        }
+        Commands::Reindex => {
+            let mut index = SearchIndex::from_file(file_path)?;
+            index.rebuild(watchlists);
+            index.to_file()?;
+            let items: usize = watchlists.values().map(|items| items.len()).sum();
+            status!("Indexed {items} Item(s) in {} List(s)", watchlists.len());
+        }
+        Commands::History(history) => {
+            let lines: Vec<String> = history::read(file_path, history.limit)?
+                .iter()
+                .map(|o| format!("{} - {o}", config.format_timestamp(&o.at)))
+                .collect();
+            match lines.is_empty() {
+                true => status!("No History"),
+                false => wlist::list_display(&lines, "History")?,
+            }
+        }
+        Commands::Log(log) => {
+            let lines: Vec<String> = git::log(file_path, log.limit)?
+                .iter()
+                .map(|c| {
+                    format!(
+                        "{} {} - {}",
+                        c.id,
+                        config.format_timestamp(&c.at),
+                        c.subject
+                    )
+                })
+                .collect();
+            match lines.is_empty() {
+                true => status!("No Commits"),
+                false => wlist::list_display(&lines, "Log")?,
+            }
+        }
+        Commands::Revert(revert) => {
+            let commit = git::find(file_path, &revert.commit)?;
+            let (before, after) = git::changes(file_path, &revert.commit)?;
+            git::revert(watchlists, &before, &after);
+            if config.git {
+                run.commits.push(format!(
+                    "revert \"{}\"\n\nwl revert {}",
+                    commit.subject, commit.id
+                ));
+            }
+            status!("Reverted {} - {}", commit.id, commit.subject);
+        }
+        // The Lists in memory are replaced with the ones repaired
+        Commands::Check(check) => {
+            let compact = watchlists.compact();
+            if cli_check(check, compact, file_path)? {
+                *watchlists = read_lists(file_path, run)?;
+                watchlists.set_compact(compact);
+            }
+        }
+        Commands::Trash(trash) => cli_trash(watchlists, trash, config, file_path)?,
+        Commands::Archive(archive) => {
+            cli_archive(watchlists, archive, config, file_path, answers.yes)?
+        }
+        Commands::Stats(stats) if stats.heatmap => display_heatmap(config, file_path)?,
+        Commands::Forecast(args) => display_forecast(watchlists, args, config, file_path)?,
+        Commands::Stats(args) => display_stats(watchlists, args, file_path)?,
+        Commands::Profile(profile) => cli_profile(profile, config, file_path)?,
+        Commands::Undo => {
+            let mut history = UndoHistory::from_file(file_path)?;
+            match history.undo() {
+                Some(snapshot) => {
+                    // What comes back from the trash this way can't be restored again
+                    let mut trash = load_trash(config, file_path)?;
+                    if trash.remove_returned(watchlists, &snapshot.lists) > 0 {
+                        trash.to_file()?;
+                    }
+                    *watchlists = snapshot.lists;
+                    status!("Undid `wl {}`", snapshot.command);
+                    history.to_file()?;
+                }
+                None => status!("Nothing to Undo"),
+            }
+        }
+        Commands::Tmp(tmp) => cli_tmp(watchlists, tmp, file_path)?,
+        Commands::Stage(stage) => cli_stage(watchlists, stage, file_path)?,
+        #[cfg(feature = "tui")]
+        Commands::Tui => {
+            let trashed = wlist::tui::run(watchlists)?;
+            if !trashed.is_empty() {
+                let mut trash = load_trash(config, file_path)?;
+                for entry in trashed {
+                    trash.add(entry, dates::now());
+                }
+                trash.to_file()?;
+            }
+        }
+        #[cfg(unix)]
+        Commands::Daemon => cli_daemon(watchlists, file_path, run)?,
+        #[cfg(feature = "encryption")]
+        Commands::Encrypt(encrypt) => {
+            let passphrase = encryption::new_passphrase()?;
+            // The Lists are saved once the command is done, but there has to be a file to encrypt
+            if !file_path.exists() {
+                watchlists.to_file(file_path)?;
+            }
+            // Saved first so a file is never left encrypted with a passphrase that wasn't kept
+            if encrypt.keyring {
+                encryption::keyring_save(file_path, &passphrase)?;
+                status!("Saved the passphrase to the system keyring");
+            }
+            encryption::encrypt_file(file_path, &passphrase)?;
+            status!("Encrypted {}", file_path.display());
+        }
+        #[cfg(feature = "encryption")]
+        Commands::Decrypt => match encryption::is_encrypted(file_path)? {
+            true => {
+                encryption::decrypt_file(file_path)?;
+                encryption::keyring_remove(file_path);
+                status!("Decrypted {}", file_path.display());
+            }
+            false => status!("{} isn't encrypted", file_path.display()),
+        },
+        Commands::Serve(serve) => {
+            let address = match serve.port {
+                Some(port) => format!("127.0.0.1:{port}"),
+                None => serve.address.clone(),
+            };
+            if let Some(public) = &serve.public {
+                if !watchlists.contains(public) {
+                    return Err(WatchListError::TitleNotPresent(public.clone(), Vec::new()));
+                }
+            }
+            // Changes made while serving are read from the file
+            watchlists.to_file(file_path)?;
+            let compact = watchlists.compact();
+            match &serve.public {
+                Some(public) => {
+                    status!(
+                        "Sharing '{public}' at http://{address}/ (JSON at /list.json), press Ctrl+C to stop"
+                    );
+                    serve::serve(&address, file_path, public)?;
+                }
+                None => {
+                    let mode = match serve.read_only {
+                        true => " read-only",
+                        false => "",
+                    };
+                    status!(
+                        "Serving the{mode} web page and API for every List at http://{address}/ (API at /lists), press Ctrl+C to stop"
+                    );
+                    let token = serve
+                        .token
+                        .clone()
+                        .or_else(|| env::var(serve::TOKEN_VAR).ok());
+                    serve::serve_api(
+                        &address,
+                        file_path,
+                        serve.read_only,
+                        token.as_deref().filter(|t| !t.is_empty()),
+                        |method, path, body| {
+                            serve_change(
+                                method,
+                                path,
+                                body,
+                                compact,
+                                config,
+                                file_path,
+                                &run.workspace,
+                            )
+                        },
+                    )?;
+                }
+            }
+        }
+        Commands::MergeFile(mf) => {
+            let other = WatchList::from_file(&mf.file)?;
+            let report = merge::merge_watchlists(watchlists, other, mf.strategy.into());
+            status!(
+                "Added {} List(s) and {} Item(s), Updated {} Item(s), Skipped {} Duplicate(s)",
+                report.lists_added,
+                report.added,
+                report.updated,
+                report.skipped
+            );
+        }
+        Commands::Diff(args) => {
+            let other = WatchList::from_file(&args.file)?;
+            let differences = diff::diff(watchlists, &other);
+            match args.format {
+                DiffFormat::Json => {
+                    let json = serde_json::to_string(&differences)
+                        .map_err(|e| WatchListError::IOError(e.into()))?;
+                    println!("{json}");
+                }
+                DiffFormat::Text if differences.is_empty() => status!("No differences"),
+                DiffFormat::Text => print_differences(&differences),
+            }
+        }
+        Commands::SetStatus(set) => cli_set_status(watchlists, set, answers.yes)?,
+        Commands::Rate(rate) => {
+            watchlists.item_set_rating(&rate.list, &rate.item, rate.rating)?;
+            status!("Rated '{}' {}/10", rate.item, rate.rating);
+        }
+        Commands::Pin(pin) => {
+            watchlists.item_set_pinned(&pin.list, &pin.item, !pin.unpin)?;
+            match pin.unpin {
+                true => status!("Unpinned '{}'", pin.item),
+                false => status!("Pinned '{}' to the top of {}", pin.item, pin.list),
+            }
+        }
+        Commands::Next(next) => cli_next(watchlists, next, answers.yes)?,
+        Commands::MoveItem(mv) => {
+            let from = watchlists.item_position(&mv.list, &mv.item)?;
+            let to = match (mv.up, mv.down, mv.to) {
+                (Some(places), _, _) => from.saturating_sub(places),
+                (_, Some(places), _) => from.saturating_add(places),
+                (_, _, Some(place)) => place.saturating_sub(1),
+                _ => unreachable!(),
+            };
+            let to = watchlists.item_move_to(&mv.list, &mv.item, to)?;
+            status!("Moved '{}' to place {} in {}", mv.item, to + 1, mv.list);
+        }
+        Commands::Progress(progress) => match (&progress.item, &progress.progress) {
+            (Some(item), Some(reached)) => {
+                let reached: wlist::progress::Progress = reached.parse()?;
+                watchlists.item_set_progress(&progress.list, item, reached)?;
+                status!("'{item}' is at {reached}");
+            }
+            _ => {
+                let items = watchlists.item_get_in_progress(&progress.list)?;
+                if items.is_empty() {
+                    status!("No Items in Progress in {}", progress.list);
+                } else {
+                    display_items(&items, &progress.list, &[])?;
+                }
+            }
+        },
+        Commands::Due(due) => {
+            let date = match &due.date {
+                Some(date) if !due.clear => Some(config.parse_date(date, dates::today())?),
+                _ => None,
+            };
+            watchlists.item_set_due(&due.list, &due.item, date)?;
+            match date {
+                Some(date) => status!("'{}' is due on {}", due.item, config.format_date(date)),
+                None => status!("Removed the due date of '{}'", due.item),
+            }
+        }
+        Commands::Notify(notify) => cli_notify(watchlists, notify, config),
+        Commands::Runtime(runtime) => {
+            let minutes = match &runtime.runtime {
+                Some(text) if !runtime.clear => Some(forecast::parse_runtime(text)?),
+                _ => None,
+            };
+            watchlists.item_set_runtime(&runtime.list, &runtime.item, minutes)?;
+            match minutes {
+                Some(minutes) => status!(
+                    "'{}' takes {} to watch",
+                    runtime.item,
+                    forecast::format_runtime(minutes)
+                ),
+                None => status!("Removed the runtime of '{}'", runtime.item),
+            }
+        }
+        Commands::Id(id) => match &id.command {
+            Some(IdCommands::Set { service, id: value }) => {
+                watchlists.item_set_id(&id.list, &id.item, service, Some(value))?;
+                status!("Set the {service} ID of '{}' to {value}", id.item);
+            }
+            Some(IdCommands::Remove { service }) => {
+                watchlists.item_set_id(&id.list, &id.item, service, None)?;
+                status!("Removed the {service} ID of '{}'", id.item);
+            }
+            None => {
+                let item = watchlists
+                    .item_get_all(&id.list)?
+                    .iter()
+                    .find(|i| i.title == id.item)
+                    .ok_or(WatchListError::ItemToRemoveNotPresent(id.item.clone()))?;
+                if item.meta.ids.is_empty() {
+                    status!("No IDs for '{}'", id.item);
+                }
+                for (service, value) in &item.meta.ids {
+                    println!("{service}: {value}");
+                }
+            }
+        },
+        Commands::SetUrl(set) => {
+            let url = set.url.as_deref().filter(|_| !set.clear);
+            watchlists.item_set_url(&set.list, &set.item, url)?;
+            match url {
+                Some(url) => status!("Set the link of '{}' to {}", set.item, url.trim()),
+                None => status!("Removed the link of '{}'", set.item),
+            }
+        }
+        Commands::Open(open) => {
+            let item = watchlists
+                .item_get_all(&open.list)?
+                .iter()
+                .find(|i| i.title == open.item)
+                .ok_or(WatchListError::ItemToRemoveNotPresent(open.item.clone()))?;
+            open_url(item)?;
+        }
+        Commands::Merge(merge) => {
+            let report = watchlists.list_merge(&merge.from, &merge.to)?;
+            status!(
+                "Merged '{}' into '{}', Moved {} Item(s), Dropped {} Duplicate(s)",
+                merge.from,
+                merge.to,
+                report.moved,
+                report.duplicates
+            );
+        }
+        Commands::Edit(edit) => cli_edit(watchlists, edit, answers.yes)?,
+        Commands::EditList(edit) => cli_edit_list(watchlists, edit, config, file_path)?,
+        Commands::Move(mv) => {
+            let item = match (mv.index, &mv.item) {
+                (Some(number), _) => item_at(watchlists, &mv.from, number)?,
+                (None, Some(item)) => item.clone(),
+                // Clap requires one of them
+                (None, None) => unreachable!(),
+            };
+            if mv.copy {
+                watchlists.item_copy(&mv.from, &mv.to, &item)?;
+                status!("Item Copied to '{}'", mv.to);
+            } else {
+                watchlists.item_move(&mv.from, &mv.to, &item)?;
+                status!("Item Moved to '{}'", mv.to);
+            }
+        }
+        Commands::Set(set) => {
+            if !watchlists.contains(&set.list) {
+                return Err(WatchListError::TitleNotPresent(
+                    set.list.clone(),
+                    Vec::new(),
+                ));
+            }
+            if set.no_random || set.random {
+                watchlists.list_settings_mut(&set.list)?.no_random = set.no_random;
+                match set.no_random {
+                    true => status!("'{}' is Left Out of Random Picks", set.list),
+                    false => status!("'{}' can be Picked at Random", set.list),
+                }
+            }
+            if let Some(sort) = set.sort {
+                watchlists.list_settings_mut(&set.list)?.sort = sort.into();
+                let order = match sort {
+                    Sort::Added => "in the Order they were Added",
+                    Sort::Alpha => "Alphabetically",
+                    Sort::Rating => "by Rating",
+                    Sort::Random => "Shuffled",
+                };
+                status!("Items of '{}' are Shown {order}", set.list);
+            }
+        }
+        Commands::Rename(rename) => {
+            watchlists.list_rename(&rename.list, &rename.new_title)?;
+            // Keep the random cooldown of the renamed list
+            let mut history = PickHistory::from_file(file_path)?;
+            if history.rename_list(&rename.list, &rename.new_title) {
+                history.to_file()?;
+            }
+            status!("Renamed List '{}' to '{}'", rename.list, rename.new_title);
+        }
+        Commands::Alias(alias) => cli_alias(watchlists, alias, config)?,
+        Commands::Copy(copy) => {
+            watchlists.list_copy(&copy.list, &copy.new_title)?;
+            status!("Copied List '{}' to '{}'", copy.list, copy.new_title);
+        }
+        Commands::Search(search) => cli_search(watchlists, search, file_path, answers.yes)?,
+        Commands::Export(ex) => {
+            let titles: Vec<&str> = ex.list.iter().map(|l| l.as_str()).collect();
+            let fields = fields::lookup(&ex.fields)?;
+            match &ex.output {
+                // Exported in full first, so a failed export doesn't leave a half written file
+                Some(path) => {
+                    let output = export::export(watchlists, &titles, ex.format.into(), &fields)?;
+                    fs::write(path, output)?;
+                    status!("Exported to {}", path.display());
+                }
+                None => {
+                    let mut out = output::stdout();
+                    export::export_to(&mut out, watchlists, &titles, ex.format.into(), &fields)?;
+                    out.flush()?;
+                }
+            }
+        }
+        Commands::Import(im) => cli_import(watchlists, im, config)?,
+        #[cfg(feature = "net")]
+        Commands::Grab(grab) => {
+            let title = wlist::grab::fetch_title(&grab.url)?;
+            let item = wlist::Item::new(&title)
+                .with_url(&grab.url)
+                .with_added_at(dates::now());
+            import::import_items(watchlists, &grab.list, [item])?;
+            status!("Added '{title}' to {}", grab.list);
         }
+        #[cfg(feature = "net")]
+        Commands::Sync(sync) => cli_sync(watchlists, sync, config, file_path, answers.yes)?,
+        #[cfg(feature = "net")]
+        Commands::Push(push) => cli_push(watchlists, push, config, file_path, answers.yes)?,
+        #[cfg(feature = "net")]
+        Commands::Pull(pull) => cli_pull(watchlists, pull, config, file_path, answers.yes, run)?,
     }
 
     Ok(())
@@ -0,0 +1,17 @@
+//! Copying titles to the clipboard with `--copy`, to paste them into the search box of a
+//! streaming service
+//!
+//! On Linux the clipboard is only kept after `wl` exits when a clipboard manager takes it
+//! over, which most desktops run
+
+use std::io;
+
+use crate::WatchListError;
+
+/// Puts the titles on the clipboard, one per line
+pub fn copy<'a>(titles: impl IntoIterator<Item = &'a str>) -> Result<(), WatchListError> {
+    let text = titles.into_iter().collect::<Vec<_>>().join("\n");
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|e| io::Error::other(format!("couldn't copy to the clipboard, {e}")).into())
+}
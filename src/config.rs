@@ -0,0 +1,497 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::{self, read_to_string};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{Locale, NaiveDate, TimeDelta};
+use serde::{Deserialize, Serialize};
+
+use crate::dates::{self, Timestamp, DEFAULT_DATE_FORMAT, DEFAULT_TIMESTAMP_FORMAT};
+use crate::dedup::Similarity;
+use crate::hooks::Hooks;
+use crate::query::Query;
+use crate::theme::{Paint, Style, Theme};
+use crate::validation::Validation;
+use crate::{DuplicatePolicy, SortBy, WatchList, WatchListError};
+
+/// User preferences read from the config file
+///
+/// Missing fields take their default values
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Warn about items due within this many days, 0 disables the warning
+    pub due_soon_days: u32,
+    /// strftime style format used to display (and optionally enter) dates
+    pub date_format: String,
+    /// strftime style format used to display timestamps in the local time zone
+    pub timestamp_format: String,
+    /// Locale used for month and day names in dates, like "de_DE"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date_locale: Option<String>,
+    /// Days deleted lists and items are kept in the trash, 0 keeps them until it is emptied
+    pub trash_days: u32,
+    /// Days before the random command suggests an item from a list again, by list title
+    pub random_cooldown: HashMap<String, u32>,
+    /// Number of items picked last from a list which the random command skips
+    pub random_avoid_recent: usize,
+    /// Lists the random command doesn't pick when no list is given, by title, as older
+    /// versions of `wl` saved them. They're moved onto the lists in the data file, see
+    /// [`move_list_settings`](Config::move_list_settings)
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub exclude_from_random: BTreeSet<String>,
+    /// How items being added are checked against the items already in their list
+    pub duplicates: DuplicatePolicy,
+    /// How titles are compared when skipping similar items during imports
+    pub similarity: Similarity,
+    /// Comparisons to use for some lists instead of `similarity`, by list title
+    pub list_similarity: HashMap<String, Similarity>,
+    /// Order the items of some lists are shown in unless another is asked for, by list
+    /// title, as older versions of `wl` saved them. They're moved onto the lists like
+    /// `exclude_from_random`
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub list_sort: HashMap<String, SortBy>,
+    /// Rules the titles of items and lists being added have to follow
+    #[serde(skip_serializing_if = "Validation::is_empty")]
+    pub validation: Validation,
+    /// Filter expressions picking the items of smart lists from every list, by title
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub smart_lists: HashMap<String, String>,
+    /// Short names which can be used instead of the titles of smart lists and categories, by
+    /// alias. Aliases of lists are saved with the list, and the ones older versions of `wl`
+    /// saved here are moved onto them like `exclude_from_random`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, String>,
+    /// API key used to look up movies on TMDB
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tmdb_api_key: Option<String>,
+    /// Settings for syncing with Trakt
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trakt: Option<TraktConfig>,
+    /// Settings for syncing with AniList
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anilist: Option<AniListConfig>,
+    /// Broker to publish list counts and new items to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mqtt: Option<MqttConfig>,
+    /// Colors used for output
+    pub theme: Theme,
+    /// Colors and styles used instead of the ones of the theme, by what the text is used for
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub colors: BTreeMap<Style, Paint>,
+    /// Plain single column output which doesn't rely on colors
+    pub accessible: bool,
+    /// File the lists are stored in, unless set by the environment variable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_file: Option<PathBuf>,
+    /// Profile used when none is given with `--profile`, unless the environment variable is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Directory the profiles are kept in, next to the data file by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profiles_dir: Option<PathBuf>,
+    /// Data files used together with `--workspace`, by workspace name
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub workspaces: HashMap<String, Vec<PathBuf>>,
+    /// Commit the data file to the git repository it's in after each command which changes it
+    pub git: bool,
+    /// Save the data file as compact JSON on one line instead of pretty-printed
+    pub compact: bool,
+    /// Shell commands run after items are added, deleted or done
+    #[serde(skip_serializing_if = "Hooks::is_empty")]
+    pub hooks: Hooks,
+    /// Where `wl push` and `wl pull` keep a copy of the data file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote: Option<RemoteConfig>,
+}
+
+/// Credentials of the Trakt app used for syncing and the list to sync
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TraktConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    pub list: String,
+}
+
+impl Default for TraktConfig {
+    fn default() -> Self {
+        TraktConfig {
+            client_id: String::new(),
+            client_secret: String::new(),
+            list: "Trakt".to_string(),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            due_soon_days: 7,
+            date_format: DEFAULT_DATE_FORMAT.to_string(),
+            timestamp_format: DEFAULT_TIMESTAMP_FORMAT.to_string(),
+            date_locale: None,
+            trash_days: 30,
+            random_cooldown: HashMap::new(),
+            random_avoid_recent: 3,
+            exclude_from_random: BTreeSet::new(),
+            duplicates: DuplicatePolicy::default(),
+            similarity: Similarity::default(),
+            list_similarity: HashMap::new(),
+            list_sort: HashMap::new(),
+            validation: Validation::default(),
+            smart_lists: HashMap::new(),
+            aliases: BTreeMap::new(),
+            tmdb_api_key: None,
+            trakt: None,
+            anilist: None,
+            mqtt: None,
+            theme: Theme::Default,
+            colors: BTreeMap::new(),
+            accessible: false,
+            data_file: None,
+            profile: None,
+            profiles_dir: None,
+            workspaces: HashMap::new(),
+            git: false,
+            compact: false,
+            hooks: Hooks::default(),
+            remote: None,
+        }
+    }
+}
+
+/// Client ID of the AniList app used for syncing and the lists to sync
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AniListConfig {
+    pub client_id: String,
+    pub anime_list: String,
+    pub manga_list: String,
+}
+
+impl Default for AniListConfig {
+    fn default() -> Self {
+        AniListConfig {
+            client_id: String::new(),
+            anime_list: "Anime".to_string(),
+            manga_list: "Manga".to_string(),
+        }
+    }
+}
+
+/// MQTT broker and the topics to publish to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MqttConfig {
+    pub host: String,
+    pub port: u16,
+    pub client_id: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Topic for the number of items in each list
+    pub counts_topic: String,
+    /// Topic for each item added to a list
+    pub latest_topic: String,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        MqttConfig {
+            host: "localhost".to_string(),
+            port: 1883,
+            client_id: "wlist".to_string(),
+            username: None,
+            password: None,
+            counts_topic: "watchlist/counts".to_string(),
+            latest_topic: "watchlist/latest".to_string(),
+        }
+    }
+}
+
+/// Copy of the data file on a WebDAV server or in an S3 bucket
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RemoteConfig {
+    Webdav(WebDavConfig),
+    S3(S3Config),
+}
+
+/// URL of the file on a WebDAV server, like Nextcloud, and the account to use
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebDavConfig {
+    pub url: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+/// Object in an S3 bucket and the keys to access it with, which are read from
+/// `AWS_ACCESS_KEY_ID` and `AWS_SECRET_ACCESS_KEY` when left out
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct S3Config {
+    /// Address of a service compatible with S3, instead of AWS
+    pub endpoint: Option<String>,
+    pub region: String,
+    pub bucket: String,
+    pub key: String,
+    pub access_key: Option<String>,
+    pub secret_key: Option<String>,
+}
+
+impl Default for S3Config {
+    fn default() -> Self {
+        S3Config {
+            endpoint: None,
+            region: "us-east-1".to_string(),
+            bucket: String::new(),
+            key: "watchlist.json".to_string(),
+            access_key: None,
+            secret_key: None,
+        }
+    }
+}
+
+impl Config {
+    /// Reads the config file, using the defaults if it doesn't exist
+    pub fn from_file(file_path: &Path) -> Result<Self, WatchListError> {
+        let data = match read_to_string(file_path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(e) => return Err(WatchListError::IOError(e)),
+        };
+        serde_json::from_str(&data)
+            .map_err(|e| WatchListError::ParseError(format!("{}: {e}", file_path.display())))
+    }
+
+    /// Saves the config as pretty printed JSON so it is easy to edit by hand
+    pub fn to_file(&self, file_path: &Path) -> Result<(), WatchListError> {
+        let data =
+            serde_json::to_string_pretty(self).map_err(|e| WatchListError::IOError(e.into()))?;
+        fs::write(file_path, data).map_err(WatchListError::IOError)
+    }
+
+    /// Gets the configured locale, falling back to POSIX if it is invalid
+    pub fn locale(&self) -> Locale {
+        self.date_locale
+            .as_deref()
+            .and_then(|l| l.parse().ok())
+            .unwrap_or(Locale::POSIX)
+    }
+
+    /// Gets how long items from a list are skipped by the random command after being picked
+    pub fn random_cooldown(&self, list: &str) -> TimeDelta {
+        let days = self.random_cooldown.get(list).copied().unwrap_or(0);
+        TimeDelta::days(days.into())
+    }
+
+    /// Gets how titles are compared when skipping items in a list similar to others
+    pub fn similarity(&self, list: &str) -> Similarity {
+        self.list_similarity
+            .get(list)
+            .copied()
+            .unwrap_or(self.similarity)
+    }
+
+    /// Moves the settings older versions of `wl` saved here by title onto the lists in the
+    /// watchlist, where they follow the list when it's renamed. Settings of lists which
+    /// aren't in it are kept, as they may be for the lists of another data file. Gives
+    /// whether any were moved
+    pub fn move_list_settings(&mut self, watchlist: &mut WatchList) -> bool {
+        let before = self.exclude_from_random.len() + self.list_sort.len() + self.aliases.len();
+        self.exclude_from_random
+            .retain(|title| match watchlist.list_settings_mut(title) {
+                Ok(settings) => {
+                    settings.no_random = true;
+                    false
+                }
+                Err(_) => true,
+            });
+        self.list_sort
+            .retain(|title, sort| match watchlist.list_settings_mut(title) {
+                Ok(settings) => {
+                    settings.sort = *sort;
+                    false
+                }
+                Err(_) => true,
+            });
+        self.aliases
+            .retain(|name, title| watchlist.list_add_alias(title, name).is_err());
+        self.exclude_from_random.len() + self.list_sort.len() + self.aliases.len() != before
+    }
+
+    /// Gets the filter of the smart list with the title, if there is one
+    pub fn smart_list(&self, title: &str) -> Result<Option<Query>, WatchListError> {
+        self.smart_lists.get(title).map(|q| q.parse()).transpose()
+    }
+
+    /// Gets the title of the list an alias is for
+    pub fn alias(&self, name: &str) -> Option<&str> {
+        self.aliases.get(name).map(String::as_str)
+    }
+
+    /// Gets the data files of the workspace with the name
+    pub fn workspace(&self, name: &str) -> Result<&[PathBuf], WatchListError> {
+        self.workspaces
+            .get(name)
+            .filter(|files| !files.is_empty())
+            .map(Vec::as_slice)
+            .ok_or(WatchListError::WorkspaceNotPresent(name.to_string()))
+    }
+
+    /// Gets how long deleted lists and items are kept in the trash, `None` if they are kept forever
+    pub fn trash_max_age(&self) -> Option<TimeDelta> {
+        (self.trash_days > 0).then(|| TimeDelta::days(self.trash_days.into()))
+    }
+
+    /// Parses a date entered by the user, see [`dates::parse_date`]
+    pub fn parse_date(&self, input: &str, today: NaiveDate) -> Result<NaiveDate, WatchListError> {
+        dates::parse_date(input, today, &self.date_format)
+    }
+
+    /// Parses the first date of a period entered by the user, see [`dates::parse_since`]
+    pub fn parse_since(&self, input: &str, today: NaiveDate) -> Result<NaiveDate, WatchListError> {
+        dates::parse_since(input, today, &self.date_format)
+    }
+
+    /// Formats a date with the configured format and locale
+    pub fn format_date(&self, date: NaiveDate) -> String {
+        dates::format_date(date, &self.date_format, self.locale())
+    }
+
+    /// Formats a timestamp in the local time zone with the configured format and locale
+    pub fn format_timestamp(&self, timestamp: &Timestamp) -> String {
+        dates::format_timestamp(timestamp, &self.timestamp_format, self.locale())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults() {
+        let config: Config = serde_json::from_str("{}").unwrap();
+        assert_eq!(config, Config::default());
+
+        let config = Config::from_file(Path::new("does_not_exist.json")).unwrap();
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn localized_dates() {
+        let config: Config =
+            serde_json::from_str(r#"{ "date_format": "%e %B %Y", "date_locale": "fr_FR" }"#)
+                .unwrap();
+        let date = NaiveDate::from_ymd_opt(2023, 3, 1).unwrap();
+        assert_eq!(config.format_date(date), " 1 mars 2023");
+
+        let config = Config {
+            date_locale: Some("not a locale".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(config.locale(), Locale::POSIX);
+        assert_eq!(config.format_date(date), "2023-03-01");
+    }
+
+    #[test]
+    fn moving_list_settings() {
+        let mut config: Config = serde_json::from_str(
+            r#"{ "exclude_from_random": ["Finished", "Anime"], "list_sort": { "Finished": "alpha" } }"#,
+        )
+        .unwrap();
+        let mut watchlist = WatchList::new();
+        watchlist.list_add("Finished").unwrap();
+        assert!(config.move_list_settings(&mut watchlist));
+        assert!(watchlist.excluded_from_random("Finished"));
+        assert_eq!(watchlist.list_sort("Finished"), SortBy::Alpha);
+        assert!(config.list_sort.is_empty());
+
+        // Anime may be a list in another data file
+        assert_eq!(
+            config.exclude_from_random,
+            BTreeSet::from(["Anime".to_string()])
+        );
+        assert!(!config.move_list_settings(&mut watchlist));
+
+        let mut config: Config =
+            serde_json::from_str(r#"{ "aliases": { "f": "Finished", "h": "Horror" } }"#).unwrap();
+        assert!(config.move_list_settings(&mut watchlist));
+        assert_eq!(watchlist.list_for_alias("f").unwrap(), "Finished");
+        // Horror may be a smart list
+        assert_eq!(config.alias("h"), Some("Horror"));
+        assert_eq!(config.alias("f"), None);
+    }
+
+    #[test]
+    fn partial() {
+        let config: Config = serde_json::from_str(r#"{ "due_soon_days": 0 }"#).unwrap();
+        assert_eq!(config.due_soon_days, 0);
+
+        let config: Config = serde_json::from_str(r#"{ "duplicates": "normalized" }"#).unwrap();
+        assert_eq!(config.duplicates, DuplicatePolicy::Normalized);
+
+        let config: Config =
+            serde_json::from_str(r#"{ "exclude_from_random": ["Finished"] }"#).unwrap();
+        assert!(config.exclude_from_random.contains("Finished"));
+        assert!(!serde_json::to_string(&Config::default())
+            .unwrap()
+            .contains("exclude_from_random"));
+
+        let config: Config =
+            serde_json::from_str(r#"{ "list_sort": { "Movies": "alpha" } }"#).unwrap();
+        assert_eq!(config.list_sort["Movies"], SortBy::Alpha);
+
+        let config: Config = serde_json::from_str(
+            r#"{ "similarity": "token-set", "list_similarity": { "Movies": "jaro-winkler" } }"#,
+        )
+        .unwrap();
+        assert_eq!(config.similarity("Anime"), Similarity::TokenSet);
+        assert_eq!(config.similarity("Movies"), Similarity::JaroWinkler);
+
+        let config: Config =
+            serde_json::from_str(r#"{ "smart_lists": { "Horror": "tag=horror" } }"#).unwrap();
+        assert_eq!(
+            config.smart_list("Horror").unwrap(),
+            Some("tag=horror".parse().unwrap())
+        );
+        assert_eq!(config.smart_list("Movies").unwrap(), None);
+
+        let config: Config =
+            serde_json::from_str(r#"{ "validation": { "lists": { "max_length": 20 } } }"#).unwrap();
+        assert_eq!(config.validation.lists.max_length, Some(20));
+        assert_eq!(config.validation.items, Default::default());
+
+        let config: Config =
+            serde_json::from_str(r#"{ "hooks": { "done": ["echo $2"] } }"#).unwrap();
+        assert_eq!(config.hooks.done, ["echo $2"]);
+        assert!(config.hooks.add.is_empty());
+        assert!(!serde_json::to_string(&Config::default())
+            .unwrap()
+            .contains("hooks"));
+
+        let config: Config = serde_json::from_str(
+            r#"{ "workspaces": { "media": ["a.json", "b.json"], "none": [] } }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.workspace("media").unwrap(),
+            [PathBuf::from("a.json"), PathBuf::from("b.json")]
+        );
+        assert_eq!(
+            config.workspace("none"),
+            Err(WatchListError::WorkspaceNotPresent("none".to_string()))
+        );
+
+        let config: Config =
+            serde_json::from_str(r#"{ "remote": { "s3": { "bucket": "lists" } } }"#).unwrap();
+        let Some(RemoteConfig::S3(s3)) = config.remote else {
+            panic!("not an S3 remote: {:?}", config.remote);
+        };
+        assert_eq!(
+            (s3.bucket.as_str(), s3.key.as_str()),
+            ("lists", "watchlist.json")
+        );
+    }
+}
@@ -0,0 +1,336 @@
+//! Keeping the lists in memory between commands with `wl daemon`, which runs the commands
+//! sent to it over a Unix socket next to the data file instead of each command reading the
+//! whole file again
+//!
+//! Commands are sent along with their standard input, output and error, which the daemon
+//! uses while running them, so they print, page and prompt in the terminal they were started
+//! in the same as they would without the daemon. Their working directory and environment go
+//! along with them too. Commands are run one at a time, making the daemon the only writer of
+//! the data file while it's running
+
+use std::env;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::mem;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::WatchListError;
+
+/// Standard input, output and error, which are sent to the daemon in this order
+const STDIO: [RawFd; 3] = [libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO];
+
+/// Command sent to the daemon, with what it needs to run as if it was started on its own
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Request {
+    /// Arguments of the command, leaving out `wl` itself
+    pub args: Vec<String>,
+    /// Config file the command was going to read
+    pub config: PathBuf,
+    pub dir: PathBuf,
+    pub env: Vec<(OsString, OsString)>,
+}
+
+impl Request {
+    /// Makes a request for the arguments from the working directory and environment of
+    /// this process
+    pub fn new(args: Vec<String>, config: &Path) -> io::Result<Self> {
+        Ok(Request {
+            args,
+            config: config.to_path_buf(),
+            dir: env::current_dir()?,
+            env: env::vars_os().collect(),
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Response {
+    code: u8,
+}
+
+/// Gets the path of the socket the daemon for the data file listens on
+///
+/// For example, the daemon for `watchlist.json` listens on `watchlist.sock`
+pub fn socket_path(file_path: &Path) -> PathBuf {
+    file_path.with_extension("sock")
+}
+
+/// Runs the command in the daemon for the data file, giving the exit code it finished with,
+/// or none if there is no daemon running for it
+pub fn send(file_path: &Path, request: &Request) -> Result<Option<u8>, WatchListError> {
+    // Left behind by a daemon which was killed if nothing is listening on it
+    let Ok(mut stream) = UnixStream::connect(socket_path(file_path)) else {
+        return Ok(None);
+    };
+    send_fds(&stream, &STDIO)?;
+    let request = serde_json::to_string(request).map_err(io::Error::from)?;
+    writeln!(stream, "{request}")?;
+
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    let response: Response = serde_json::from_str(&line)
+        .map_err(|e| WatchListError::ParseError(format!("the daemon stopped, {e}")))?;
+    Ok(Some(response.code))
+}
+
+/// Daemon listening for commands, which removes its socket when it's dropped
+#[derive(Debug)]
+pub struct Daemon {
+    listener: UnixListener,
+    socket: PathBuf,
+}
+
+impl Daemon {
+    /// Listens on the socket for the data file, failing if another daemon is already running
+    /// for it
+    pub fn bind(file_path: &Path) -> Result<Daemon, WatchListError> {
+        let socket = socket_path(file_path);
+        if UnixStream::connect(&socket).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("a daemon is already running for {}", file_path.display()),
+            )
+            .into());
+        }
+        // Left behind by a daemon which was killed
+        if socket.exists() {
+            std::fs::remove_file(&socket)?;
+        }
+        let listener = UnixListener::bind(&socket)?;
+        Ok(Daemon { listener, socket })
+    }
+
+    pub fn socket(&self) -> &Path {
+        &self.socket
+    }
+
+    /// Runs each command sent to the daemon with `run`, which gives the exit code to send
+    /// back, until the process is stopped
+    pub fn serve(&self, mut run: impl FnMut(&Request) -> u8) -> Result<(), WatchListError> {
+        for stream in self.listener.incoming() {
+            // A command which went wrong shouldn't stop the daemon
+            if let Err(e) = stream.and_then(|s| handle(s, &mut run)) {
+                eprintln!("Command failed: {e}");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.socket);
+    }
+}
+
+fn handle(mut stream: UnixStream, run: &mut impl FnMut(&Request) -> u8) -> io::Result<()> {
+    let fds = receive_fds(&stream, STDIO.len())?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    let request: Request = serde_json::from_str(&line)?;
+
+    let code = {
+        let _session = Session::start(&fds, &request)?;
+        run(&request)
+    };
+    let response = serde_json::to_string(&Response { code })?;
+    writeln!(stream, "{response}")
+}
+
+// Standard input, output and error, the working directory and environment of the daemon,
+// which are put back once the command sent to it is done
+struct Session {
+    stdio: Vec<OwnedFd>,
+    dir: PathBuf,
+    env: Vec<(OsString, OsString)>,
+}
+
+impl Session {
+    fn start(fds: &[OwnedFd], request: &Request) -> io::Result<Session> {
+        let session = Session {
+            stdio: STDIO.iter().map(|&fd| dup(fd)).collect::<io::Result<_>>()?,
+            dir: env::current_dir()?,
+            env: env::vars_os().collect(),
+        };
+        flush();
+        for (fd, &target) in fds.iter().zip(&STDIO) {
+            dup2(fd.as_raw_fd(), target)?;
+        }
+        env::set_current_dir(&request.dir)?;
+        replace_env(&request.env);
+        // Decided the same way as it would be for the command on its own
+        colored::control::set_override(
+            colored::control::ShouldColorize::from_env().should_colorize(),
+        );
+        Ok(session)
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        flush();
+        // Input the command didn't read isn't left for the next one
+        if let Ok(null) = File::open("/dev/null") {
+            let _ = dup2(null.as_raw_fd(), libc::STDIN_FILENO);
+            let mut stdin = io::stdin().lock();
+            while let Ok(buffered) = stdin.fill_buf().map(<[u8]>::len) {
+                if buffered == 0 {
+                    break;
+                }
+                stdin.consume(buffered);
+            }
+        }
+        for (fd, &target) in self.stdio.iter().zip(&STDIO) {
+            let _ = dup2(fd.as_raw_fd(), target);
+        }
+        let _ = env::set_current_dir(&self.dir);
+        replace_env(&mem::take(&mut self.env));
+        colored::control::unset_override();
+    }
+}
+
+fn flush() {
+    let _ = io::stdout().flush();
+    let _ = io::stderr().flush();
+}
+
+fn replace_env(vars: &[(OsString, OsString)]) {
+    for (key, _) in env::vars_os() {
+        env::remove_var(key);
+    }
+    for (key, value) in vars {
+        env::set_var(key, value);
+    }
+}
+
+// Copies of the descriptor aren't passed on to programs started by the daemon, like the pager
+fn dup(fd: RawFd) -> io::Result<OwnedFd> {
+    // SAFETY: F_DUPFD_CLOEXEC gives a new descriptor which nothing else owns
+    match unsafe { libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, 0) } {
+        -1 => Err(io::Error::last_os_error()),
+        new => Ok(unsafe { OwnedFd::from_raw_fd(new) }),
+    }
+}
+
+fn dup2(fd: RawFd, target: RawFd) -> io::Result<()> {
+    // SAFETY: only standard input, output and error are replaced, which are never closed
+    match unsafe { libc::dup2(fd, target) } {
+        -1 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+// Space for the control message carrying the descriptors, aligned for its header
+fn control_buffer(count: usize) -> Vec<u64> {
+    let size = mem::size_of::<RawFd>() * count;
+    // SAFETY: CMSG_SPACE only does arithmetic
+    let space = unsafe { libc::CMSG_SPACE(size as u32) } as usize;
+    vec![0; space.div_ceil(mem::size_of::<u64>())]
+}
+
+// Sends the descriptors along with a single byte, as the message can't be empty
+fn send_fds(stream: &UnixStream, fds: &[RawFd]) -> io::Result<()> {
+    let mut byte = [0u8];
+    let mut iov = libc::iovec {
+        iov_base: byte.as_mut_ptr().cast(),
+        iov_len: byte.len(),
+    };
+    let mut control = control_buffer(fds.len());
+    let size = mem::size_of_val(fds);
+    // SAFETY: the header points at buffers which outlive the call, and the control message
+    // is written within the space made for it
+    unsafe {
+        let mut message: libc::msghdr = mem::zeroed();
+        message.msg_iov = &mut iov;
+        message.msg_iovlen = 1;
+        message.msg_control = control.as_mut_ptr().cast();
+        message.msg_controllen = mem::size_of_val(control.as_slice()) as _;
+        let header = libc::CMSG_FIRSTHDR(&message);
+        (*header).cmsg_level = libc::SOL_SOCKET;
+        (*header).cmsg_type = libc::SCM_RIGHTS;
+        (*header).cmsg_len = libc::CMSG_LEN(size as u32) as _;
+        std::ptr::copy_nonoverlapping(fds.as_ptr().cast::<u8>(), libc::CMSG_DATA(header), size);
+        if libc::sendmsg(stream.as_raw_fd(), &message, 0) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+fn receive_fds(stream: &UnixStream, count: usize) -> io::Result<Vec<OwnedFd>> {
+    let mut byte = [0u8];
+    let mut iov = libc::iovec {
+        iov_base: byte.as_mut_ptr().cast(),
+        iov_len: byte.len(),
+    };
+    let mut control = control_buffer(count);
+    let mut fds = Vec::new();
+    // SAFETY: the header points at buffers which outlive the call, and only the control
+    // messages the kernel wrote are read
+    unsafe {
+        let mut message: libc::msghdr = mem::zeroed();
+        message.msg_iov = &mut iov;
+        message.msg_iovlen = 1;
+        message.msg_control = control.as_mut_ptr().cast();
+        message.msg_controllen = mem::size_of_val(control.as_slice()) as _;
+        match libc::recvmsg(stream.as_raw_fd(), &mut message, 0) {
+            -1 => return Err(io::Error::last_os_error()),
+            0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+            _ => {}
+        }
+        let mut header = libc::CMSG_FIRSTHDR(&message);
+        while !header.is_null() {
+            if (*header).cmsg_level == libc::SOL_SOCKET && (*header).cmsg_type == libc::SCM_RIGHTS {
+                let data = libc::CMSG_DATA(header).cast::<RawFd>();
+                let size = (*header).cmsg_len as usize - libc::CMSG_LEN(0) as usize;
+                for i in 0..size / mem::size_of::<RawFd>() {
+                    fds.push(OwnedFd::from_raw_fd(data.add(i).read_unaligned()));
+                }
+            }
+            header = libc::CMSG_NXTHDR(&message, header);
+        }
+    }
+    if fds.len() != count {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the command didn't send its input and output",
+        ));
+    }
+    Ok(fds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn passing_descriptors() {
+        assert_eq!(
+            socket_path(Path::new("lists/watchlist.json")),
+            Path::new("lists/watchlist.sock")
+        );
+
+        let (a, b) = UnixStream::pair().unwrap();
+        let (reader, mut writer) = UnixStream::pair().unwrap();
+        send_fds(&a, &[reader.as_raw_fd()]).unwrap();
+        let fds = receive_fds(&b, 1).unwrap();
+
+        // The descriptor received reads from the same socket as the one sent
+        writer.write_all(b"Alien").unwrap();
+        drop(writer);
+        let mut received = UnixStream::from(fds.into_iter().next().unwrap());
+        let mut text = String::new();
+        received.read_to_string(&mut text).unwrap();
+        assert_eq!(text, "Alien");
+
+        // Nothing else can be mistaken for the descriptors
+        (&a).write_all(b"x").unwrap();
+        assert!(receive_fds(&b, 1).is_err());
+    }
+}
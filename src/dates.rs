@@ -0,0 +1,257 @@
+use std::fmt::Write;
+
+use chrono::{DateTime, Datelike, Days, Local, Locale, Months, NaiveDate, TimeZone, Utc, Weekday};
+
+use crate::WatchListError;
+
+/// Format used for dates when no other format is configured
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+/// Format used for timestamps when no other format is configured
+pub const DEFAULT_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// A point in time
+///
+/// Timestamps are always stored in UTC (serialized as RFC 3339) so that files
+/// edited in different time zones still order their history consistently.
+/// They are only converted to the local time zone for display
+pub type Timestamp = DateTime<Utc>;
+
+/// Gets the current time
+pub fn now() -> Timestamp {
+    Utc::now()
+}
+
+/// Gets the current date in the local time zone
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// Gets the date of a timestamp in the local time zone
+pub fn local_date(timestamp: &Timestamp) -> NaiveDate {
+    timestamp.with_timezone(&Local).date_naive()
+}
+
+/// Parses a date relative to `today`
+///
+/// Accepts dates in the ISO format (2023-03-01) or in `format`, along with
+/// human friendly dates like "today", "tomorrow", "friday", "next friday",
+/// "next week", "in 2 weeks" or "3 days ago"
+pub fn parse_date(
+    input: &str,
+    today: NaiveDate,
+    format: &str,
+) -> Result<NaiveDate, WatchListError> {
+    let invalid = || WatchListError::InvalidDate(input.to_string());
+    let input = input.trim().to_lowercase();
+
+    if let Ok(date) = NaiveDate::parse_from_str(&input, DEFAULT_DATE_FORMAT) {
+        return Ok(date);
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(&input, format) {
+        return Ok(date);
+    }
+
+    let words: Vec<&str> = input.split_whitespace().collect();
+    let date = match words.as_slice() {
+        ["today"] => Some(today),
+        ["tomorrow"] => today.succ_opt(),
+        ["yesterday"] => today.pred_opt(),
+        ["next", unit @ ("week" | "month" | "year")] => offset(today, 1, unit),
+        ["next", day] => next_weekday(today, day.parse().map_err(|_| invalid())?, false),
+        [day] if day.parse::<Weekday>().is_ok() => next_weekday(today, day.parse().unwrap(), true),
+        ["in", amount @ ..] => relative(today, amount, false),
+        [amount @ .., "ago"] => relative(today, amount, true),
+        amount => relative(today, amount, false),
+    };
+    date.ok_or_else(invalid)
+}
+
+/// Parses the first date of a period ending `today`, see [`parse_date`]
+///
+/// Amounts like "2weeks" or "3 days" count back from `today` instead of forward, as if
+/// they ended in "ago"
+pub fn parse_since(
+    input: &str,
+    today: NaiveDate,
+    format: &str,
+) -> Result<NaiveDate, WatchListError> {
+    let lowercase = input.trim().to_lowercase();
+    let words: Vec<&str> = lowercase.split_whitespace().collect();
+    match relative(today, &words, true) {
+        Some(date) => Ok(date),
+        None => parse_date(input, today, format),
+    }
+}
+
+// Finds the next date falling on `day`, which can be today if `include_today` is set
+fn next_weekday(today: NaiveDate, day: Weekday, include_today: bool) -> Option<NaiveDate> {
+    let mut days_ahead =
+        (day.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+    if days_ahead == 0 && !include_today {
+        days_ahead = 7;
+    }
+    today.checked_add_days(Days::new(days_ahead.into()))
+}
+
+// Parses amounts like "2 weeks" or "2weeks" and applies them to today
+fn relative(today: NaiveDate, words: &[&str], past: bool) -> Option<NaiveDate> {
+    let (number, unit) = match words {
+        [number, unit] => (*number, *unit),
+        [word] => {
+            let split = word.find(|c: char| !c.is_ascii_digit())?;
+            word.split_at(split)
+        }
+        _ => return None,
+    };
+    let number: i64 = number.parse().ok()?;
+    offset(today, if past { -number } else { number }, unit)
+}
+
+fn offset(today: NaiveDate, amount: i64, unit: &str) -> Option<NaiveDate> {
+    let unit = unit.strip_suffix('s').unwrap_or(unit);
+    let (days, months) = match unit {
+        "d" | "day" => (amount, 0),
+        "w" | "week" => (amount.checked_mul(7)?, 0),
+        "m" | "month" => (0, amount),
+        "y" | "year" => (0, amount.checked_mul(12)?),
+        _ => return None,
+    };
+
+    let date = match days {
+        0 => today,
+        d if d > 0 => today.checked_add_days(Days::new(d as u64))?,
+        d => today.checked_sub_days(Days::new(d.unsigned_abs()))?,
+    };
+    let months_abs = u32::try_from(months.unsigned_abs()).ok()?;
+    if months >= 0 {
+        date.checked_add_months(Months::new(months_abs))
+    } else {
+        date.checked_sub_months(Months::new(months_abs))
+    }
+}
+
+/// Formats a date with a strftime style format in the given locale
+///
+/// Falls back to the ISO format if `format` is invalid
+pub fn format_date(date: NaiveDate, format: &str, locale: Locale) -> String {
+    let mut formatted = String::new();
+    match write!(formatted, "{}", date.format_localized(format, locale)) {
+        Ok(()) => formatted,
+        Err(_) => date.format(DEFAULT_DATE_FORMAT).to_string(),
+    }
+}
+
+/// Formats a timestamp in the local time zone, see [`format_date`]
+pub fn format_timestamp(timestamp: &Timestamp, format: &str, locale: Locale) -> String {
+    format_timestamp_in(timestamp, &Local, format, locale)
+}
+
+/// Formats a timestamp in the given time zone, see [`format_date`]
+pub fn format_timestamp_in<Tz: TimeZone>(
+    timestamp: &Timestamp,
+    tz: &Tz,
+    format: &str,
+    locale: Locale,
+) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let local = timestamp.with_timezone(tz);
+    let mut formatted = String::new();
+    match write!(formatted, "{}", local.format_localized(format, locale)) {
+        Ok(()) => formatted,
+        Err(_) => local.format(DEFAULT_TIMESTAMP_FORMAT).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    // A Wednesday
+    const TODAY: &str = "2023-03-01";
+
+    fn parse(input: &str) -> Result<NaiveDate, WatchListError> {
+        parse_date(input, date(TODAY), "%d/%m/%Y")
+    }
+
+    #[test]
+    fn absolute_dates() {
+        assert_eq!(parse("2023-04-05").unwrap(), date("2023-04-05"));
+        assert_eq!(parse("05/04/2023").unwrap(), date("2023-04-05"));
+    }
+
+    #[test]
+    fn relative_dates() {
+        assert_eq!(parse("today").unwrap(), date(TODAY));
+        assert_eq!(parse("Tomorrow").unwrap(), date("2023-03-02"));
+        assert_eq!(parse("yesterday").unwrap(), date("2023-02-28"));
+        assert_eq!(parse("friday").unwrap(), date("2023-03-03"));
+        assert_eq!(parse("wednesday").unwrap(), date(TODAY));
+        assert_eq!(parse("next wed").unwrap(), date("2023-03-08"));
+        assert_eq!(parse("next month").unwrap(), date("2023-04-01"));
+        assert_eq!(parse("in 2 weeks").unwrap(), date("2023-03-15"));
+        assert_eq!(parse("3 days").unwrap(), date("2023-03-04"));
+        assert_eq!(parse("2weeks ago").unwrap(), date("2023-02-15"));
+        assert_eq!(parse("in 1 year").unwrap(), date("2024-03-01"));
+    }
+
+    #[test]
+    fn since_dates() {
+        let since = |input| parse_since(input, date(TODAY), "%d/%m/%Y").unwrap();
+        assert_eq!(since("2weeks"), date("2023-02-15"));
+        assert_eq!(since("3 Days"), date("2023-02-26"));
+        assert_eq!(since("1 month ago"), date("2023-02-01"));
+        assert_eq!(since("yesterday"), date("2023-02-28"));
+        assert_eq!(since("05/02/2023"), date("2023-02-05"));
+        assert!(parse_since("someday", date(TODAY), "%d/%m/%Y").is_err());
+    }
+
+    #[test]
+    fn invalid_dates() {
+        for input in ["someday", "next decade", "in two weeks", "2023-02-30", ""] {
+            assert_eq!(
+                parse(input).err().unwrap(),
+                WatchListError::InvalidDate(input.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn formatting() {
+        let d = date("2023-03-01");
+        assert_eq!(
+            format_date(d, "%A %e %B", Locale::POSIX),
+            "Wednesday  1 March"
+        );
+        assert_eq!(
+            format_date(d, "%A %e %B", Locale::de_DE),
+            "Mittwoch  1 März"
+        );
+        assert_eq!(format_date(d, "%Q", Locale::POSIX), "2023-03-01");
+    }
+
+    #[test]
+    fn timestamps() {
+        // Timestamps with offsets are converted to UTC when read
+        let timestamp: Timestamp = serde_json::from_str(r#""2023-03-01T23:30:00-05:00""#).unwrap();
+        assert_eq!(
+            serde_json::to_string(&timestamp).unwrap(),
+            r#""2023-03-02T04:30:00Z""#
+        );
+
+        let ist = chrono::FixedOffset::east_opt(5 * 3600 + 1800).unwrap();
+        assert_eq!(
+            format_timestamp_in(&timestamp, &ist, DEFAULT_TIMESTAMP_FORMAT, Locale::POSIX),
+            "2023-03-02 10:00"
+        );
+        assert_eq!(
+            format_timestamp_in(&timestamp, &Utc, "%Q", Locale::POSIX),
+            "2023-03-02 04:30"
+        );
+    }
+}
@@ -0,0 +1,368 @@
+//! Finding items which are likely the same even though their titles are written differently
+//!
+//! Titles are normalized first, so "Spider-Man: Homecoming" and "spider man homecoming"
+//! are the same, and then compared using one of the [`Similarity`] measures. Only titles
+//! sharing some trigrams or words are compared, which keeps checking a large import
+//! against itself from comparing every pair of titles
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use serde::{Deserialize, Serialize};
+
+use crate::par_map;
+
+/// Ways of measuring how alike two titles are
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Similarity {
+    /// Number of characters which need to be added, removed or changed to turn one
+    /// title into the other, which suits typos and is the fastest
+    #[default]
+    Levenshtein,
+    /// Characters in common and in about the same place, favouring titles which start
+    /// the same way, which suits short titles
+    JaroWinkler,
+    /// Words in common, ignoring their order and any extra words in the longer title,
+    /// which suits titles like anime with alternative names or subtitles added
+    TokenSet,
+}
+
+impl Similarity {
+    /// How alike two titles are, from 0 for nothing in common to 1 for the same
+    pub fn compare(self, a: &str, b: &str) -> f64 {
+        let chars = |s: &str| s.chars().collect::<Vec<char>>();
+        match self {
+            Similarity::Levenshtein => levenshtein_similarity(&chars(a), &chars(b)),
+            Similarity::JaroWinkler => jaro_winkler(&chars(a), &chars(b)),
+            Similarity::TokenSet => token_set(&words(a), &words(b)),
+        }
+    }
+}
+
+/// Lowercases the title and keeps only its letters and digits, separated by single spaces
+pub fn normalize(title: &str) -> String {
+    title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn levenshtein_similarity(a: &[char], b: &[char]) -> f64 {
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(a, b) as f64 / longest as f64
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let changed = previous[j] + usize::from(ca != cb);
+            current[j + 1] = changed.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+fn jaro_winkler(a: &[char], b: &[char]) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    // Characters match if they are the same and not too far apart
+    let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+    let mut b_matched = vec![false; b.len()];
+    let mut a_matches = Vec::new();
+    for (i, ca) in a.iter().enumerate() {
+        let start = i.saturating_sub(window);
+        let end = (i + window + 1).min(b.len());
+        if let Some(j) = (start..end).find(|&j| !b_matched[j] && b[j] == *ca) {
+            b_matched[j] = true;
+            a_matches.push(*ca);
+        }
+    }
+    if a_matches.is_empty() {
+        return 0.0;
+    }
+    let b_matches = b.iter().zip(&b_matched).filter(|(_, &m)| m).map(|(c, _)| c);
+    let transpositions = a_matches
+        .iter()
+        .zip(b_matches)
+        .filter(|(x, y)| x != y)
+        .count()
+        / 2;
+
+    let matches = a_matches.len() as f64;
+    let jaro = (matches / a.len() as f64
+        + matches / b.len() as f64
+        + (matches - transpositions as f64) / matches)
+        / 3.0;
+    // Up to four characters at the start in common make it more similar
+    let prefix = a.iter().zip(b).take(4).take_while(|(x, y)| x == y).count();
+    jaro + prefix as f64 * 0.1 * (1.0 - jaro)
+}
+
+// Words of a normalized title, sorted and without duplicates
+fn words(title: &str) -> Vec<&str> {
+    let mut words: Vec<&str> = title.split_whitespace().collect();
+    words.sort_unstable();
+    words.dedup();
+    words
+}
+
+fn token_set(a: &[&str], b: &[&str]) -> f64 {
+    let fewest = a.len().min(b.len());
+    if fewest == 0 {
+        return f64::from(u8::from(a.len() == b.len()));
+    }
+    shared(a, b) as f64 / fewest as f64
+}
+
+// Sequences of three characters in the text, sorted and without duplicates
+fn trigrams(text: &[char]) -> Vec<[char; 3]> {
+    let mut trigrams: Vec<[char; 3]> = text.windows(3).map(|w| [w[0], w[1], w[2]]).collect();
+    trigrams.sort_unstable();
+    trigrams.dedup();
+    trigrams
+}
+
+// Number of values in both sorted lists
+fn shared<T: Ord>(a: &[T], b: &[T]) -> usize {
+    let (mut i, mut j, mut count) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            Ordering::Less => i += 1,
+            Ordering::Greater => j += 1,
+            Ordering::Equal => {
+                count += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    count
+}
+
+// Most changes two titles can be apart while still being `threshold` similar,
+// when the longer one has `len` characters
+fn max_changes(threshold: f64, len: usize) -> usize {
+    // Rounding errors could otherwise drop a change
+    ((1.0 - threshold) * len as f64 + 1e-9) as usize
+}
+
+/// Finds the titles which duplicate an earlier one
+///
+/// Gives, for each title, the position of an earlier title whose normalized form is
+/// at least `threshold` similar to its own, so a threshold of 1 only matches titles
+/// which are written differently. Titles with no three characters in a row in common
+/// (or no words in common with [`Similarity::TokenSet`]) are never similar unless they
+/// are normalized to the same text
+pub fn find_duplicates<T>(
+    titles: &[T],
+    similarity: Similarity,
+    threshold: f64,
+) -> Vec<Option<usize>>
+where
+    T: AsRef<str> + Sync,
+{
+    let normalized = par_map(titles, |t| normalize(t.as_ref()));
+    let chars = par_map(&normalized, |t| t.chars().collect::<Vec<_>>());
+    let (trigrams, words) = match similarity {
+        Similarity::TokenSet => (Vec::new(), par_map(&normalized, |t| words(t))),
+        _ => (par_map(&chars, |t| trigrams(t)), Vec::new()),
+    };
+
+    // Positions of the titles containing each trigram or word, in order
+    let mut trigram_postings: HashMap<[char; 3], Vec<usize>> = HashMap::new();
+    let mut word_postings: HashMap<&str, Vec<usize>> = HashMap::new();
+    let mut first_of: HashMap<&str, usize> = HashMap::new();
+    let mut same = Vec::with_capacity(titles.len());
+    for (position, title) in normalized.iter().enumerate() {
+        if let Some(&first) = first_of.get(title.as_str()) {
+            // Later titles would match the first one anyway
+            same.push(Some(first));
+            continue;
+        }
+        same.push(None);
+        first_of.insert(title, position);
+        for trigram in trigrams.get(position).into_iter().flatten() {
+            trigram_postings.entry(*trigram).or_default().push(position);
+        }
+        for word in words.get(position).into_iter().flatten() {
+            word_postings.entry(word).or_default().push(position);
+        }
+    }
+
+    let candidates = Candidates {
+        chars: &chars,
+        trigrams: &trigrams,
+        words: &words,
+        trigram_postings: &trigram_postings,
+        word_postings: &word_postings,
+        similarity,
+        threshold,
+    };
+    let positions: Vec<usize> = (0..titles.len()).collect();
+    par_map(&positions, |&position| {
+        same[position].or_else(|| candidates.earliest_similar(position))
+    })
+}
+
+struct Candidates<'a> {
+    chars: &'a [Vec<char>],
+    trigrams: &'a [Vec<[char; 3]>],
+    words: &'a [Vec<&'a str>],
+    trigram_postings: &'a HashMap<[char; 3], Vec<usize>>,
+    word_postings: &'a HashMap<&'a str, Vec<usize>>,
+    similarity: Similarity,
+    threshold: f64,
+}
+
+// Titles before `position` containing any of the keys, in order
+fn earlier<K>(keys: &[K], postings: &HashMap<K, Vec<usize>>, position: usize) -> Vec<usize>
+where
+    K: Eq + Hash,
+{
+    let mut titles: Vec<usize> = keys
+        .iter()
+        .flat_map(|k| postings[k].iter().take_while(|&&o| o < position))
+        .copied()
+        .collect();
+    titles.sort_unstable();
+    titles.dedup();
+    titles
+}
+
+impl Candidates<'_> {
+    // Earliest title before `position` which is similar enough
+    fn earliest_similar(&self, position: usize) -> Option<usize> {
+        if self.threshold <= 0.0 {
+            return (position > 0).then_some(0);
+        }
+        let candidates = match self.similarity {
+            Similarity::Levenshtein => {
+                let title = &self.chars[position];
+                // A similar title can't be much longer, so it is at most this many changes away
+                let longest = (title.len() as f64 / self.threshold + 1e-9) as usize;
+                let changes = max_changes(self.threshold, longest);
+
+                // Each change removes at most three trigrams, so a similar title has to
+                // share at least one of any `3 * changes + 1` of them
+                let mut rarest = self.trigrams[position].clone();
+                rarest.sort_by_key(|t| self.trigram_postings[t].len());
+                rarest.truncate(3 * changes + 1);
+                earlier(&rarest, self.trigram_postings, position)
+            }
+            Similarity::JaroWinkler => {
+                earlier(&self.trigrams[position], self.trigram_postings, position)
+            }
+            Similarity::TokenSet => earlier(&self.words[position], self.word_postings, position),
+        };
+        candidates
+            .into_iter()
+            .find(|&other| self.is_similar(position, other))
+    }
+
+    fn is_similar(&self, position: usize, other: usize) -> bool {
+        let (a, b) = (&self.chars[position], &self.chars[other]);
+        match self.similarity {
+            Similarity::Levenshtein => {
+                let (a_trigrams, b_trigrams) = (&self.trigrams[position], &self.trigrams[other]);
+                let changes = max_changes(self.threshold, a.len().max(b.len()));
+                // Cheaper checks first, since the lengths and trigrams of similar titles
+                // can only differ so much
+                a.len().abs_diff(b.len()) <= changes
+                    && shared(a_trigrams, b_trigrams) + 3 * changes
+                        >= a_trigrams.len().max(b_trigrams.len())
+                    && levenshtein_similarity(a, b) >= self.threshold
+            }
+            Similarity::JaroWinkler => jaro_winkler(a, b) >= self.threshold,
+            Similarity::TokenSet => {
+                token_set(&self.words[position], &self.words[other]) >= self.threshold
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comparing() {
+        assert_eq!(
+            normalize("  Spider-Man: Into the Spider-Verse!"),
+            "spider man into the spider verse"
+        );
+        let levenshtein = Similarity::Levenshtein;
+        assert_eq!(levenshtein.compare("dune", "dune"), 1.0);
+        assert_eq!(levenshtein.compare("dune", "dunes"), 0.8);
+        assert_eq!(levenshtein.compare("kitten", "sitting"), 1.0 - 3.0 / 7.0);
+        assert_eq!(levenshtein.compare("", ""), 1.0);
+        assert_eq!(levenshtein.compare("abc", ""), 0.0);
+
+        let jaro_winkler = Similarity::JaroWinkler;
+        assert_eq!(jaro_winkler.compare("dune", "dune"), 1.0);
+        assert!((jaro_winkler.compare("martha", "marhta") - 0.961).abs() < 0.001);
+        assert!((jaro_winkler.compare("dixon", "dicksonx") - 0.813).abs() < 0.001);
+        assert_eq!(jaro_winkler.compare("abc", "xyz"), 0.0);
+
+        let token_set = Similarity::TokenSet;
+        assert_eq!(
+            token_set.compare("shingeki no kyojin", "attack on titan shingeki no kyojin"),
+            1.0
+        );
+        assert_eq!(token_set.compare("the thing", "thing the"), 1.0);
+        assert_eq!(token_set.compare("the thing", "the fly"), 0.5);
+        assert_eq!(token_set.compare("", ""), 1.0);
+    }
+
+    #[test]
+    fn finding_duplicates() {
+        let titles = [
+            "Spider-Man: Homecoming",
+            "The Thing",
+            "spider man homecoming",
+            "Spiderman Homecoming",
+            "The Things",
+            "Up",
+            "up!",
+            "Alien",
+        ];
+        assert_eq!(
+            find_duplicates(&titles, Similarity::Levenshtein, 1.0),
+            vec![None, None, Some(0), None, None, None, Some(5), None]
+        );
+        assert_eq!(
+            find_duplicates(&titles, Similarity::Levenshtein, 0.9),
+            vec![None, None, Some(0), Some(0), Some(1), None, Some(5), None]
+        );
+        assert_eq!(
+            find_duplicates(&titles, Similarity::JaroWinkler, 0.95),
+            vec![None, None, Some(0), Some(0), Some(1), None, Some(5), None]
+        );
+
+        let titles = [
+            "Attack on Titan",
+            "Shingeki no Kyojin",
+            "Shingeki no Kyojin (Attack on Titan)",
+            "Kyojin",
+        ];
+        assert_eq!(
+            find_duplicates(&titles, Similarity::TokenSet, 1.0),
+            vec![None, None, Some(0), Some(1)]
+        );
+        assert_eq!(
+            find_duplicates(&titles, Similarity::Levenshtein, 0.8),
+            vec![None, None, None, None]
+        );
+    }
+}
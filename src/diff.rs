@@ -0,0 +1,224 @@
+//! Differences between two watchlists, like the data file and a backup of it or the copy
+//! from another machine, shown with `wl diff`
+
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use serde::Serialize;
+
+use crate::{Item, WatchList};
+
+/// A way the other watchlist differs from the first one
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum Difference {
+    /// A list only in the other watchlist, with the titles of its items
+    ListAdded {
+        list: String,
+        items: Vec<String>,
+    },
+    /// A list only in the first watchlist, with the titles of its items
+    ListRemoved {
+        list: String,
+        items: Vec<String>,
+    },
+    ItemAdded {
+        list: String,
+        item: String,
+    },
+    ItemRemoved {
+        list: String,
+        item: String,
+    },
+    /// An item in both whose details differ, with the names of the ones which do as they
+    /// are in the data file, like `watched` or `rating`
+    ItemChanged {
+        list: String,
+        item: String,
+        fields: Vec<String>,
+    },
+}
+
+impl Difference {
+    /// Describes the difference in words, without the symbols used by the diff
+    pub fn describe(&self) -> String {
+        match self {
+            Difference::ListAdded { list, items } => {
+                format!("List {list} added with {} Item(s)", items.len())
+            }
+            Difference::ListRemoved { list, items } => {
+                format!("List {list} removed with {} Item(s)", items.len())
+            }
+            Difference::ItemAdded { list, item } => format!("{item} added to {list}"),
+            Difference::ItemRemoved { list, item } => format!("{item} removed from {list}"),
+            Difference::ItemChanged { list, item, fields } => {
+                format!("{item} in {list} changed its {}", fields.join(", "))
+            }
+        }
+    }
+}
+
+impl Display for Difference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Difference::ListAdded { list, items } => {
+                write!(f, "+ List {list} ({} Item(s))", items.len())
+            }
+            Difference::ListRemoved { list, items } => {
+                write!(f, "- List {list} ({} Item(s))", items.len())
+            }
+            Difference::ItemAdded { list, item } => write!(f, "+ {item} ({list})"),
+            Difference::ItemRemoved { list, item } => write!(f, "- {item} ({list})"),
+            Difference::ItemChanged { list, item, fields } => {
+                write!(f, "* {item} ({list}: {})", fields.join(", "))
+            }
+        }
+    }
+}
+
+/// Finds what changed going from `watchlist` to `other`, list by list in alphabetical
+/// order. Items are matched by their titles, and moving items around within a list
+/// isn't a difference
+pub fn diff(watchlist: &WatchList, other: &WatchList) -> Vec<Difference> {
+    let mut titles: Vec<&String> = watchlist.titles().chain(other.titles()).collect();
+    titles.sort();
+    titles.dedup();
+
+    let mut differences = Vec::new();
+    for list in titles {
+        let titles_of = |items: &[Item]| items.iter().map(|i| i.title.clone()).collect();
+        match (watchlist.get(list), other.get(list)) {
+            (Some(items), None) => differences.push(Difference::ListRemoved {
+                list: list.clone(),
+                items: titles_of(items),
+            }),
+            (None, Some(items)) => differences.push(Difference::ListAdded {
+                list: list.clone(),
+                items: titles_of(items),
+            }),
+            (Some(before), Some(after)) => diff_items(list, before, after, &mut differences),
+            (None, None) => unreachable!(),
+        }
+    }
+    differences
+}
+
+fn diff_items(list: &str, before: &[Item], after: &[Item], differences: &mut Vec<Difference>) {
+    // Items which are in a list more than once are matched up in the order they come in
+    let mut unmatched: HashMap<&str, Vec<&Item>> = HashMap::new();
+    for item in after.iter().rev() {
+        unmatched.entry(&item.title).or_default().push(item);
+    }
+    for item in before {
+        let difference = match unmatched.get_mut(item.title.as_str()).and_then(Vec::pop) {
+            None => Difference::ItemRemoved {
+                list: list.to_string(),
+                item: item.title.clone(),
+            },
+            Some(other) if other != item => Difference::ItemChanged {
+                list: list.to_string(),
+                item: item.title.clone(),
+                fields: changed_fields(item, other),
+            },
+            Some(_) => continue,
+        };
+        differences.push(difference);
+    }
+    for item in after {
+        if let Some(other) = unmatched.get_mut(item.title.as_str()) {
+            if other.last().is_some_and(|o| std::ptr::eq(*o, item)) {
+                other.pop();
+                differences.push(Difference::ItemAdded {
+                    list: list.to_string(),
+                    item: item.title.clone(),
+                });
+            }
+        }
+    }
+}
+
+// Names of the details which differ, compared as they're saved so every detail is named
+// the same way as in the data file
+fn changed_fields(item: &Item, other: &Item) -> Vec<String> {
+    let fields = |item: &Item| match serde_json::to_value(&item.meta) {
+        Ok(serde_json::Value::Object(fields)) => fields,
+        _ => serde_json::Map::new(),
+    };
+    let (before, after) = (fields(item), fields(other));
+    let mut names: Vec<String> = before
+        .keys()
+        .chain(after.keys())
+        .filter(|name| before.get(*name) != after.get(*name))
+        .cloned()
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn differences() {
+        let mut watchlist = WatchList::new();
+        watchlist.insert(
+            "Movies".to_string(),
+            vec![
+                Item::new("Alien"),
+                Item::new("Dune").with_year(2021),
+                Item::new("Heat"),
+            ],
+        );
+        watchlist.insert("TV".to_string(), vec![Item::new("Lost")]);
+        let mut other = WatchList::new();
+        other.insert(
+            "Movies".to_string(),
+            vec![
+                Item::new("Heat"),
+                Item::new("Dune").with_url("https://example.com/dune"),
+                Item::new("Akira"),
+                Item::new("Akira"),
+            ],
+        );
+        other.insert("Books".to_string(), vec![Item::new("Emma")]);
+
+        let (movies, title) = ("Movies".to_string(), |title: &str| title.to_string());
+        assert_eq!(
+            diff(&watchlist, &other),
+            vec![
+                Difference::ListAdded {
+                    list: "Books".to_string(),
+                    items: vec![title("Emma")],
+                },
+                Difference::ItemRemoved {
+                    list: movies.clone(),
+                    item: title("Alien"),
+                },
+                Difference::ItemChanged {
+                    list: movies.clone(),
+                    item: title("Dune"),
+                    fields: vec![title("url"), title("year")],
+                },
+                Difference::ItemAdded {
+                    list: movies.clone(),
+                    item: title("Akira"),
+                },
+                Difference::ItemAdded {
+                    list: movies.clone(),
+                    item: title("Akira"),
+                },
+                Difference::ListRemoved {
+                    list: "TV".to_string(),
+                    items: vec![title("Lost")],
+                },
+            ]
+        );
+        assert!(diff(&watchlist, &watchlist).is_empty());
+        assert_eq!(
+            diff(&watchlist, &other)[2].to_string(),
+            "* Dune (Movies: url, year)"
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use chrono::{Days, NaiveDate};
+
+use crate::{Item, WatchList};
+
+/// Gets all items with a due date (along with their list titles), sorted by their due dates
+pub fn items_by_due(watchlist: &WatchList) -> Vec<(&String, &Item)> {
+    let mut due: Vec<(&String, &Item)> = watchlist
+        .iter()
+        .flat_map(|(title, items)| items.iter().map(move |i| (title, i)))
+        .filter(|(_, item)| item.meta.due.is_some())
+        .collect();
+    due.sort_by_key(|(title, item)| (item.meta.due, *title));
+    due
+}
+
+/// Gets all items (along with their list titles) which are due within `days` days of `today`
+///
+/// Overdue items are included and the items are sorted by their due dates
+pub fn items_due_within(
+    watchlist: &WatchList,
+    today: NaiveDate,
+    days: u32,
+) -> Vec<(&String, &Item)> {
+    let last_day = today
+        .checked_add_days(Days::new(days.into()))
+        .unwrap_or(NaiveDate::MAX);
+
+    let mut due = items_by_due(watchlist);
+    due.retain(|(_, item)| item.meta.due.is_some_and(|d| d <= last_day));
+    due
+}
+
+/// Checks if an item's due date has passed
+pub fn is_overdue(item: &Item, today: NaiveDate) -> bool {
+    item.meta.due.is_some_and(|d| d < today)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn due_within() {
+        let mut watchlist = WatchList::new();
+        watchlist.insert(
+            "Movies".to_string(),
+            vec![
+                Item::new("Movie 1").with_due(date("2023-03-10")),
+                Item::new("Movie 2"),
+                Item::new("Movie 3").with_due(date("2023-02-01")),
+                Item::new("Movie 4").with_due(date("2023-03-02")),
+            ],
+        );
+
+        let due = items_due_within(&watchlist, date("2023-03-01"), 7);
+        let titles: Vec<&str> = due.iter().map(|(_, i)| i.title.as_str()).collect();
+        assert_eq!(titles, vec!["Movie 3", "Movie 4"]);
+
+        assert!(items_due_within(&watchlist, date("2023-01-01"), 7).is_empty());
+    }
+
+    #[test]
+    fn by_due() {
+        let mut watchlist = WatchList::new();
+        watchlist.insert(
+            "Movies".to_string(),
+            vec![
+                Item::new("Movie 1").with_due(date("2023-03-10")),
+                Item::new("Movie 2"),
+            ],
+        );
+        watchlist.insert(
+            "TV".to_string(),
+            vec![Item::new("Show 1").with_due(date("2023-02-01"))],
+        );
+
+        let due = items_by_due(&watchlist);
+        let titles: Vec<(&str, &str)> = due
+            .iter()
+            .map(|(list, i)| (list.as_str(), i.title.as_str()))
+            .collect();
+        assert_eq!(titles, vec![("TV", "Show 1"), ("Movies", "Movie 1")]);
+
+        assert!(is_overdue(due[0].1, date("2023-03-01")));
+        assert!(!is_overdue(due[1].1, date("2023-03-01")));
+        assert!(!is_overdue(&Item::new("Movie 2"), date("2023-03-01")));
+    }
+}
@@ -0,0 +1,151 @@
+//! Editing a list as text in the user's editor with `wl edit-list`
+//!
+//! The titles of the items are written one per line, and the lines saved become the list:
+//! new lines are added as items, lines which were deleted remove their items and the order
+//! of the lines is the new order of the items. Items which are kept keep their details
+
+use std::env;
+use std::fs;
+use std::io;
+use std::process::Command;
+
+use crate::validation::Validator;
+use crate::{Item, WatchListError};
+
+/// Items of a list after it was edited, along with what changed
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Edited {
+    pub items: Vec<Item>,
+    pub added: Vec<String>,
+    pub removed: Vec<Item>,
+    /// Whether the items which were kept are in a different order
+    pub reordered: bool,
+}
+
+impl Edited {
+    pub fn is_changed(&self) -> bool {
+        !self.added.is_empty() || !self.removed.is_empty() || self.reordered
+    }
+}
+
+/// Writes the titles of the items one per line
+pub fn to_text(items: &[Item]) -> String {
+    items.iter().map(|i| format!("{}\n", i.title)).collect()
+}
+
+/// Takes the lines of the text edited as the new items, leaving out blank lines and
+/// titles which are already on an earlier line
+pub fn apply(items: &[Item], text: &str, validator: &Validator) -> Result<Edited, WatchListError> {
+    let mut edited = Edited::default();
+    // Positions the items which were kept had before
+    let mut kept = Vec::new();
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if edited.items.iter().any(|i| i.title.trim() == line) {
+            continue;
+        }
+        match items.iter().position(|i| i.title.trim() == line) {
+            Some(index) => {
+                kept.push(index);
+                edited.items.push(items[index].clone());
+            }
+            None => {
+                validator.check_item(line)?;
+                edited.added.push(line.to_string());
+                edited.items.push(Item::new(line));
+            }
+        }
+    }
+    edited.removed = (items.iter().enumerate())
+        .filter(|(index, _)| !kept.contains(index))
+        .map(|(_, item)| item.clone())
+        .collect();
+    edited.reordered = kept.windows(2).any(|w| w[0] > w[1]);
+    Ok(edited)
+}
+
+/// Opens the text in the editor from `VISUAL` or `EDITOR`, giving the text once it's saved
+/// and the editor is closed. The file it's edited in is named after the list
+pub fn open(text: &str, list: &str) -> Result<String, WatchListError> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| default_editor().to_string());
+    open_with(&editor, text, list)
+}
+
+/// Opens the text with the editor command, which can have arguments of its own like
+/// `code --wait`
+pub fn open_with(editor: &str, text: &str, list: &str) -> Result<String, WatchListError> {
+    let name: String = list
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    let path = env::temp_dir().join(format!("wl-{name}-{}.txt", std::process::id()));
+    fs::write(&path, text)?;
+
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or(default_editor());
+    let status = Command::new(program).args(words).arg(&path).status();
+    let edited = match status {
+        Ok(status) if status.success() => fs::read_to_string(&path),
+        Ok(status) => Err(io::Error::other(format!(
+            "the editor {program} exited with {status}, so the list was left as it was"
+        ))),
+        Err(e) => Err(io::Error::new(
+            e.kind(),
+            format!(
+                "couldn't run the editor {program}, set VISUAL or EDITOR to the one to use: {e}"
+            ),
+        )),
+    };
+    let _ = fs::remove_file(&path);
+    Ok(edited?)
+}
+
+fn default_editor() -> &'static str {
+    match cfg!(windows) {
+        true => "notepad",
+        false => "vi",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(titles: &[&str]) -> Vec<Item> {
+        titles.iter().map(|t| Item::new(*t)).collect()
+    }
+
+    #[test]
+    fn applying() {
+        let mut before = items(&["Alien", "Heat", "Ran"]);
+        before[1].meta.rating = Some(8);
+        assert_eq!(to_text(&before), "Alien\nHeat\nRan\n");
+        let validator = Validator::default();
+
+        let unchanged = apply(&before, "Alien\n\nHeat\r\nRan", &validator).unwrap();
+        assert!(!unchanged.is_changed());
+        assert_eq!(unchanged.items, before);
+
+        let edited = apply(&before, "Heat\n  Dune \nAlien\nDune\n", &validator).unwrap();
+        assert!(edited.is_changed() && edited.reordered);
+        assert_eq!(edited.added, ["Dune"]);
+        assert_eq!(edited.removed, items(&["Ran"]));
+        // Kept items keep their details
+        assert_eq!(edited.items[0], before[1]);
+        assert_eq!(edited.items[1..], [Item::new("Dune"), before[0].clone()]);
+
+        let emptied = apply(&before, "", &validator).unwrap();
+        assert!(emptied.items.is_empty() && !emptied.reordered);
+        assert_eq!(emptied.removed, before);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn opening() {
+        let edited = open_with("sed -i s/Alien/Aliens/", "Alien\nHeat\n", "Movies/Old").unwrap();
+        assert_eq!(edited, "Aliens\nHeat\n");
+        assert!(open_with("false", "Alien\n", "Movies").is_err());
+        assert!(open_with("wl-no-such-editor", "Alien\n", "Movies").is_err());
+    }
+}
@@ -0,0 +1,502 @@
+//! Encrypting the data file with a passphrase, so that copies of it synced through a cloud
+//! drive or kept in a backup can't be read without it
+//!
+//! Files are encrypted with [age](https://age-encryption.org), so they can also be decrypted
+//! with `age -d`. The passphrase is taken from `WATCHLIST_PASSPHRASE`, the system keyring
+//! or asked for in the terminal, in that order, and remembered until the program exits. A
+//! file which is encrypted stays encrypted when it's saved, while one which isn't is saved
+//! as JSON
+//!
+//! The files kept next to an encrypted data file which hold the titles of its items, like
+//! the undo history and the trash, are encrypted along with it. They use a key of their
+//! own, kept in the `key` file next to the data file and encrypted with the passphrase, so
+//! the slow work of turning the passphrase into a key is only done once per command
+//!
+//! Reading and writing encrypted files needs the `encryption` feature. Without it they're
+//! still recognised, so they're refused instead of being mistaken for broken JSON
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::WatchListError;
+
+/// Start of every file encrypted with age
+pub const HEADER: &[u8] = b"age-encryption.org/v1\n";
+
+/// Environment variable the passphrase is read from before looking anywhere else
+pub const PASSPHRASE_VAR: &str = "WATCHLIST_PASSPHRASE";
+
+/// Names of the files kept next to the data file which hold the titles of its items, and
+/// are encrypted along with it
+pub const SIDECARS: &[&str] = &[
+    "undo", "trash", "history", "index", "archive", "stage", "tmp", "picks", "daily", "review",
+];
+
+/// Name of the file next to the data file with the key its other files are encrypted with
+pub const KEY: &str = "key";
+
+/// Whether the file is encrypted, which a file that doesn't exist yet isn't
+pub fn is_encrypted(file_path: &Path) -> io::Result<bool> {
+    let file = match File::open(file_path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    let mut start = Vec::with_capacity(HEADER.len());
+    file.take(HEADER.len() as u64).read_to_end(&mut start)?;
+    Ok(start == HEADER)
+}
+
+/// Reads and decrypts the file, or gives none if it isn't encrypted
+pub fn read_file(file_path: &Path) -> Result<Option<Vec<u8>>, WatchListError> {
+    if !is_encrypted(file_path)? {
+        return Ok(None);
+    }
+    #[cfg(feature = "encryption")]
+    return decrypt_file_data(file_path).map(Some);
+    #[cfg(not(feature = "encryption"))]
+    Err(WatchListError::EncryptionError(format!(
+        "{} is encrypted, which needs wl built with the `encryption` feature to read it",
+        file_path.display()
+    )))
+}
+
+/// Encrypts the data and writes it to the file, with the same passphrase it was read with
+pub fn write_file(file_path: &Path, data: &[u8]) -> Result<(), WatchListError> {
+    #[cfg(feature = "encryption")]
+    {
+        let data = encrypt(data, &passphrase(file_path)?)?;
+        std::fs::write(file_path, data)?;
+        Ok(())
+    }
+    #[cfg(not(feature = "encryption"))]
+    {
+        let _ = data;
+        Err(WatchListError::EncryptionError(format!(
+            "{} is encrypted, which needs wl built with the `encryption` feature to save it",
+            file_path.display()
+        )))
+    }
+}
+
+/// Reads a file kept next to the data file, decrypting it when the data file is encrypted,
+/// or gives none if it doesn't exist yet
+pub fn read_sidecar(file_path: &Path, path: &Path) -> Result<Option<String>, WatchListError> {
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(WatchListError::IOError(e)),
+    };
+    if !data.starts_with(HEADER) {
+        return utf8(data).map(Some);
+    }
+    #[cfg(feature = "encryption")]
+    return utf8(decrypt_sidecar(file_path, &data)?).map(Some);
+    #[cfg(not(feature = "encryption"))]
+    {
+        let _ = file_path;
+        Err(WatchListError::EncryptionError(format!(
+            "{} is encrypted, which needs wl built with the `encryption` feature to read it",
+            path.display()
+        )))
+    }
+}
+
+/// Writes a file kept next to the data file, encrypted when the data file is
+pub fn write_sidecar(file_path: &Path, path: &Path, data: &[u8]) -> Result<(), WatchListError> {
+    if !is_encrypted(file_path)? {
+        return std::fs::write(path, data).map_err(WatchListError::IOError);
+    }
+    #[cfg(feature = "encryption")]
+    {
+        std::fs::write(path, encrypt_sidecar(file_path, data)?)?;
+        Ok(())
+    }
+    #[cfg(not(feature = "encryption"))]
+    {
+        let _ = data;
+        Err(WatchListError::EncryptionError(format!(
+            "{} is encrypted, which needs wl built with the `encryption` feature to save {}",
+            file_path.display(),
+            path.display()
+        )))
+    }
+}
+
+fn utf8(data: Vec<u8>) -> Result<String, WatchListError> {
+    String::from_utf8(data).map_err(|e| WatchListError::ParseError(e.to_string()))
+}
+
+#[cfg(feature = "encryption")]
+pub use enabled::*;
+
+#[cfg(feature = "encryption")]
+mod enabled {
+    use std::collections::BTreeMap;
+    use std::env;
+    use std::io::{self, IsTerminal, Read};
+    use std::path::{Path, PathBuf};
+    use std::sync::{Mutex, PoisonError};
+
+    use age::secrecy::{ExposeSecret, SecretString};
+    use age::x25519::Identity;
+
+    use super::{is_encrypted, read_file, HEADER, KEY, PASSPHRASE_VAR, SIDECARS};
+    use crate::{limits, sidecar_path, WatchListError};
+
+    /// Name the passphrases are saved under in the system keyring, along with the path of
+    /// the data file
+    const KEYRING_SERVICE: &str = "wlist";
+
+    // Passphrases of the files which were read or encrypted, so they're only asked for once
+    static PASSPHRASES: Mutex<BTreeMap<PathBuf, String>> = Mutex::new(BTreeMap::new());
+
+    // Keys the files next to each data file are encrypted with, once they've been decrypted
+    static KEYS: Mutex<BTreeMap<PathBuf, Identity>> = Mutex::new(BTreeMap::new());
+
+    /// Encrypts the data with a key derived from the passphrase
+    pub fn encrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>, WatchListError> {
+        let mut recipient = age::scrypt::Recipient::new(SecretString::from(passphrase));
+        // Deriving the key is made to take about a second, which tests don't need to wait for
+        if cfg!(test) {
+            recipient.set_work_factor(2);
+        }
+        age::encrypt(&recipient, data).map_err(|e| WatchListError::EncryptionError(e.to_string()))
+    }
+
+    pub fn decrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>, WatchListError> {
+        let identity = age::scrypt::Identity::new(SecretString::from(passphrase));
+        age::decrypt(&identity, data).map_err(|e| match e {
+            age::DecryptError::DecryptionFailed | age::DecryptError::NoMatchingKeys => {
+                WatchListError::EncryptionError("the passphrase is wrong".to_string())
+            }
+            e => WatchListError::EncryptionError(e.to_string()),
+        })
+    }
+
+    /// Gets the passphrase of the file from `WATCHLIST_PASSPHRASE` or the system keyring, or
+    /// asks for it when run in a terminal
+    pub fn passphrase(file_path: &Path) -> Result<String, WatchListError> {
+        let mut passphrases = PASSPHRASES.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(passphrase) = passphrases.get(file_path) {
+            return Ok(passphrase.clone());
+        }
+        let passphrase = match env::var(PASSPHRASE_VAR) {
+            Ok(passphrase) => passphrase,
+            Err(_) => match keyring_entry(file_path).and_then(|e| e.get_password().ok()) {
+                Some(passphrase) => passphrase,
+                None if io::stdin().is_terminal() => {
+                    rpassword::prompt_password(format!("Passphrase for {}: ", file_path.display()))?
+                }
+                None => {
+                    return Err(WatchListError::EncryptionError(format!(
+                        "no passphrase was given for {}, set {PASSPHRASE_VAR} or save it with `wl encrypt --keyring`",
+                        file_path.display()
+                    )))
+                }
+            },
+        };
+        passphrases.insert(file_path.to_path_buf(), passphrase.clone());
+        Ok(passphrase)
+    }
+
+    /// Gets a new passphrase from `WATCHLIST_PASSPHRASE`, or asks for it twice in the terminal
+    /// to make sure it wasn't mistyped
+    pub fn new_passphrase() -> Result<String, WatchListError> {
+        let passphrase = match env::var(PASSPHRASE_VAR) {
+            Ok(passphrase) => passphrase,
+            Err(_) if io::stdin().is_terminal() => {
+                let passphrase = rpassword::prompt_password("New passphrase: ")?;
+                if rpassword::prompt_password("Repeat the passphrase: ")? != passphrase {
+                    return Err(WatchListError::EncryptionError(
+                        "the passphrases don't match".to_string(),
+                    ));
+                }
+                passphrase
+            }
+            Err(_) => {
+                return Err(WatchListError::EncryptionError(format!(
+                    "no passphrase was given, set {PASSPHRASE_VAR} or run wl in a terminal"
+                )))
+            }
+        };
+        if passphrase.is_empty() {
+            return Err(WatchListError::EncryptionError(
+                "the passphrase can't be empty".to_string(),
+            ));
+        }
+        Ok(passphrase)
+    }
+
+    /// Uses the passphrase for the file from now on, instead of looking for it
+    pub fn set_passphrase(file_path: &Path, passphrase: &str) {
+        let mut passphrases = PASSPHRASES.lock().unwrap_or_else(PoisonError::into_inner);
+        passphrases.insert(file_path.to_path_buf(), passphrase.to_string());
+    }
+
+    // A wrong passphrase isn't kept, so it can be given again
+    fn forget_passphrase(file_path: &Path) {
+        let mut passphrases = PASSPHRASES.lock().unwrap_or_else(PoisonError::into_inner);
+        passphrases.remove(file_path);
+    }
+
+    pub(super) fn decrypt_file_data(file_path: &Path) -> Result<Vec<u8>, WatchListError> {
+        let (file, _) = limits::open_file(file_path).map_err(crate::read_error)?;
+        let mut data = Vec::new();
+        file.take(limits::MAX_FILE_SIZE).read_to_end(&mut data)?;
+        decrypt(&data, &passphrase(file_path)?).inspect_err(|_| forget_passphrase(file_path))
+    }
+
+    /// Encrypts the file with the passphrase, along with the files next to it, which changes
+    /// the passphrase of a file that's already encrypted
+    pub fn encrypt_file(file_path: &Path, passphrase: &str) -> Result<(), WatchListError> {
+        let (data, key) = match read_file(file_path)? {
+            Some(data) => (data, sidecar_key(file_path)?),
+            None => {
+                let data = limits::read_file(file_path).map_err(crate::read_error)?;
+                (data.into_bytes(), Identity::generate())
+            }
+        };
+        let key_data = encrypt(key.to_string().expose_secret().as_bytes(), passphrase)?;
+        std::fs::write(sidecar_path(file_path, KEY), key_data)?;
+        remember_key(file_path, key);
+        for path in sidecars(file_path) {
+            let data = std::fs::read(&path)?;
+            if !data.starts_with(HEADER) {
+                std::fs::write(&path, encrypt_sidecar(file_path, &data)?)?;
+            }
+        }
+
+        std::fs::write(file_path, encrypt(&data, passphrase)?)?;
+        set_passphrase(file_path, passphrase);
+        Ok(())
+    }
+
+    /// Saves the file decrypted along with the files next to it, leaving files which aren't
+    /// encrypted as they are
+    pub fn decrypt_file(file_path: &Path) -> Result<(), WatchListError> {
+        let Some(data) = read_file(file_path)? else {
+            return Ok(());
+        };
+        for path in sidecars(file_path) {
+            let data = std::fs::read(&path)?;
+            if data.starts_with(HEADER) {
+                std::fs::write(&path, decrypt_sidecar(file_path, &data)?)?;
+            }
+        }
+        std::fs::write(file_path, data)?;
+        match std::fs::remove_file(sidecar_path(file_path, KEY)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+        KEYS.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(file_path);
+        Ok(())
+    }
+
+    // Files next to the data file which hold its items and exist
+    fn sidecars(file_path: &Path) -> impl Iterator<Item = PathBuf> + '_ {
+        SIDECARS
+            .iter()
+            .map(|name| sidecar_path(file_path, name))
+            .filter(|path| path.exists())
+    }
+
+    pub(super) fn encrypt_sidecar(
+        file_path: &Path,
+        data: &[u8],
+    ) -> Result<Vec<u8>, WatchListError> {
+        let key = sidecar_key(file_path)?;
+        age::encrypt(&key.to_public(), data)
+            .map_err(|e| WatchListError::EncryptionError(e.to_string()))
+    }
+
+    pub(super) fn decrypt_sidecar(
+        file_path: &Path,
+        data: &[u8],
+    ) -> Result<Vec<u8>, WatchListError> {
+        let key = sidecar_key(file_path)?;
+        age::decrypt(&key, data).map_err(|e| WatchListError::EncryptionError(e.to_string()))
+    }
+
+    // Gets the key the files next to the data file are encrypted with from its key file,
+    // making one the first time they're encrypted
+    fn sidecar_key(file_path: &Path) -> Result<Identity, WatchListError> {
+        if let Some(key) = KEYS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(file_path)
+        {
+            return Ok(key.clone());
+        }
+        let key_path = sidecar_path(file_path, KEY);
+        let key = match std::fs::read(&key_path) {
+            Ok(data) => {
+                let data = decrypt(&data, &passphrase(file_path)?)
+                    .inspect_err(|_| forget_passphrase(file_path))?;
+                String::from_utf8_lossy(&data)
+                    .trim()
+                    .parse::<Identity>()
+                    .map_err(|e| {
+                        WatchListError::EncryptionError(format!(
+                            "the key in {} is broken, {e}",
+                            key_path.display()
+                        ))
+                    })?
+            }
+            // Data files encrypted before their other files were had no key yet
+            Err(e) if e.kind() == io::ErrorKind::NotFound && is_encrypted(file_path)? => {
+                let key = Identity::generate();
+                let data = encrypt(
+                    key.to_string().expose_secret().as_bytes(),
+                    &passphrase(file_path)?,
+                )?;
+                std::fs::write(&key_path, data)?;
+                key
+            }
+            Err(e) => return Err(e.into()),
+        };
+        remember_key(file_path, key.clone());
+        Ok(key)
+    }
+
+    fn remember_key(file_path: &Path, key: Identity) {
+        let mut keys = KEYS.lock().unwrap_or_else(PoisonError::into_inner);
+        keys.insert(file_path.to_path_buf(), key);
+    }
+
+    /// Saves the passphrase of the file to the system keyring, so it isn't asked for
+    pub fn keyring_save(file_path: &Path, passphrase: &str) -> Result<(), WatchListError> {
+        keyring_entry(file_path)
+            .ok_or_else(|| {
+                WatchListError::EncryptionError("there's no system keyring to use".to_string())
+            })?
+            .set_password(passphrase)
+            .map_err(|e| {
+                WatchListError::EncryptionError(format!(
+                    "the passphrase couldn't be saved to the keyring, {e}"
+                ))
+            })
+    }
+
+    /// Removes the passphrase of the file from the system keyring, if it was saved there
+    pub fn keyring_remove(file_path: &Path) {
+        if let Some(entry) = keyring_entry(file_path) {
+            let _ = entry.delete_credential();
+        }
+    }
+
+    // Entries are named after the whole path, so moving to another directory doesn't use
+    // the passphrase of a different file
+    fn keyring_entry(file_path: &Path) -> Option<keyring::Entry> {
+        let path = std::path::absolute(file_path).ok()?;
+        keyring::Entry::new(KEYRING_SERVICE, &path.to_string_lossy()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn temp_file(name: &str) -> std::path::PathBuf {
+        env::temp_dir().join(format!(
+            "wlist-encryption-{name}-{}.json",
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn detecting() {
+        let path = temp_file("detecting");
+        assert!(!is_encrypted(&path).unwrap());
+        assert_eq!(read_file(&path).unwrap(), None);
+
+        std::fs::write(&path, r#"{"Movies":["Alien"]}"#).unwrap();
+        assert!(!is_encrypted(&path).unwrap());
+        std::fs::write(&path, b"age-encryption.org/v1\n-> scrypt").unwrap();
+        assert!(is_encrypted(&path).unwrap());
+        #[cfg(not(feature = "encryption"))]
+        assert!(matches!(
+            read_file(&path),
+            Err(WatchListError::EncryptionError(_))
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn round_trip() {
+        let encrypted = encrypt(b"Alien", "hunter2").unwrap();
+        assert!(encrypted.starts_with(HEADER));
+        assert_eq!(decrypt(&encrypted, "hunter2").unwrap(), b"Alien");
+        assert_eq!(
+            decrypt(&encrypted, "hunter3"),
+            Err(WatchListError::EncryptionError(
+                "the passphrase is wrong".to_string()
+            ))
+        );
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn encrypted_files() {
+        use crate::dates::now;
+        use crate::history;
+        use crate::undo::UndoHistory;
+        use crate::{Item, WatchList};
+
+        let path = temp_file("files");
+        let mut watchlist = WatchList::new();
+        watchlist.insert("Movies", vec![Item::new("Alien")]);
+        watchlist.to_file(&path).unwrap();
+        let mut undo = UndoHistory::from_file(&path).unwrap();
+        undo.record("wl add Movies Alien", watchlist.clone());
+        undo.to_file().unwrap();
+        encrypt_file(&path, "hunter2").unwrap();
+        assert!(is_encrypted(&path).unwrap());
+        assert_eq!(WatchList::from_file(&path).unwrap(), watchlist);
+
+        // The files next to it are encrypted along with it, including ones saved afterwards
+        let undo_path = crate::sidecar_path(&path, "undo");
+        assert!(is_encrypted(&undo_path).unwrap());
+        history::append(
+            &path,
+            &history::Operation::new(vec!["add".to_string()], now()),
+        )
+        .unwrap();
+        history::append(
+            &path,
+            &history::Operation::new(vec!["new".to_string()], now()),
+        )
+        .unwrap();
+        assert!(is_encrypted(&crate::sidecar_path(&path, "history")).unwrap());
+        assert_eq!(history::read(&path, None).unwrap().len(), 2);
+        let mut undo = UndoHistory::from_file(&path).unwrap();
+        assert_eq!(undo.undo().unwrap().lists, watchlist);
+
+        // Saving keeps the file encrypted
+        watchlist.insert("Books", Vec::new());
+        watchlist.to_file(&path).unwrap();
+        assert!(is_encrypted(&path).unwrap());
+        assert_eq!(WatchList::from_file(&path).unwrap(), watchlist);
+
+        set_passphrase(&path, "hunter3");
+        assert!(matches!(
+            WatchList::from_file(&path),
+            Err(WatchListError::EncryptionError(_))
+        ));
+        set_passphrase(&path, "hunter2");
+        decrypt_file(&path).unwrap();
+        assert!(!is_encrypted(&path).unwrap());
+        assert_eq!(WatchList::from_file(&path).unwrap(), watchlist);
+        assert!(!is_encrypted(&undo_path).unwrap());
+        assert!(!crate::sidecar_path(&path, KEY).exists());
+        assert_eq!(history::read(&path, None).unwrap().len(), 2);
+        std::fs::remove_file(undo_path).unwrap();
+        std::fs::remove_file(crate::sidecar_path(&path, "history")).unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,263 @@
+use std::io::{self, Write};
+
+use crate::fields::{self, Field};
+use crate::{Item, WatchList, WatchListError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Markdown,
+    Text,
+}
+
+// Text of an item made up of the chosen fields, or just its title
+fn item_text(item: &Item, fields: &[&Field], separator: &str) -> String {
+    match fields.is_empty() {
+        true => item.title.clone(),
+        false => fields::text_row(item, fields, separator),
+    }
+}
+
+/// Exports the given lists in the chosen format
+///
+/// Exports all the lists (sorted by title) if `titles` is empty. Only the
+/// titles of the items are exported (or the whole items for JSON) unless
+/// `fields` are given
+pub fn export(
+    watchlist: &WatchList,
+    titles: &[&str],
+    format: ExportFormat,
+    fields: &[&Field],
+) -> Result<String, WatchListError> {
+    let mut output = Vec::new();
+    export_to(&mut output, watchlist, titles, format, fields)?;
+    Ok(String::from_utf8_lossy(&output).into_owned())
+}
+
+/// Exports the given lists like [`export`], writing each item as soon as it's formatted
+pub fn export_to(
+    out: &mut impl Write,
+    watchlist: &WatchList,
+    titles: &[&str],
+    format: ExportFormat,
+    fields: &[&Field],
+) -> Result<(), WatchListError> {
+    let mut titles: Vec<&str> = titles.to_vec();
+    if titles.is_empty() {
+        titles = watchlist.titles().map(|t| t.as_str()).collect();
+        titles.sort_unstable();
+    }
+
+    let mut lists = Vec::with_capacity(titles.len());
+    for title in titles {
+        let items = watchlist.get(title).ok_or(WatchListError::TitleNotPresent(
+            title.to_string(),
+            Vec::new(),
+        ))?;
+        lists.push((title, items));
+    }
+    write_lists(out, lists, format, fields).map_err(WatchListError::IOError)
+}
+
+fn write_lists(
+    output: &mut impl Write,
+    mut lists: Vec<(&str, &Vec<Item>)>,
+    format: ExportFormat,
+    fields: &[&Field],
+) -> io::Result<()> {
+    match format {
+        ExportFormat::Csv => {
+            match fields.is_empty() {
+                true => writeln!(output, "list,item")?,
+                false => {
+                    let names: Vec<&str> = fields.iter().map(|f| f.name).collect();
+                    writeln!(output, "list,{}", names.join(","))?;
+                }
+            }
+            for (title, items) in lists {
+                for item in items {
+                    let row: Vec<String> = match fields.is_empty() {
+                        true => vec![csv_escape(&item.title)],
+                        false => fields.iter().map(|f| csv_escape(&f.text(item))).collect(),
+                    };
+                    writeln!(output, "{},{}", csv_escape(title), row.join(","))?;
+                }
+            }
+        }
+        ExportFormat::Json => {
+            // Written as one object with the lists sorted by title, each only once
+            lists.sort_by_key(|(title, _)| *title);
+            lists.dedup_by_key(|(title, _)| *title);
+            write!(output, "{{")?;
+            for (i, (title, items)) in lists.into_iter().enumerate() {
+                if i > 0 {
+                    write!(output, ",")?;
+                }
+                serde_json::to_writer(&mut *output, title)?;
+                write!(output, ":[")?;
+                for (j, item) in items.iter().enumerate() {
+                    if j > 0 {
+                        write!(output, ",")?;
+                    }
+                    let item = match fields.is_empty() {
+                        true => serde_json::to_value(item).unwrap_or_default(),
+                        false => fields::json_object(item, fields),
+                    };
+                    serde_json::to_writer(&mut *output, &item)?;
+                }
+                write!(output, "]")?;
+            }
+            writeln!(output, "}}")?;
+        }
+        ExportFormat::Markdown => {
+            for (i, (title, items)) in lists.into_iter().enumerate() {
+                if i > 0 {
+                    writeln!(output)?;
+                }
+                writeln!(output, "## {title}\n")?;
+                for item in items {
+                    writeln!(output, "- [ ] {}", item_text(item, fields, " | "))?;
+                }
+            }
+        }
+        ExportFormat::Text => {
+            // List headers are only needed to tell multiple lists apart
+            let with_headers = lists.len() > 1;
+            for (i, (title, items)) in lists.into_iter().enumerate() {
+                if with_headers {
+                    if i > 0 {
+                        writeln!(output)?;
+                    }
+                    writeln!(output, "{title}:")?;
+                }
+                // Tabs keep the fields easy to split in scripts
+                for item in items {
+                    writeln!(output, "{}", item_text(item, fields, "\t"))?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Quotes a CSV field if it contains a comma, quote or newline
+pub fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::csv_records;
+    use crate::tests::watchlists;
+    use proptest::prelude::*;
+
+    fn setup() -> WatchList {
+        let data = r#"
+        {
+            "Movies": [ "Movie 1", "Movie, The Sequel" ],
+            "Anime": [ "Anime \"1\"" ]
+        }"#;
+        serde_json::from_str(data).unwrap()
+    }
+
+    #[test]
+    fn export_csv() {
+        let watchlist = setup();
+        assert_eq!(
+            export(&watchlist, &[], ExportFormat::Csv, &[]).unwrap(),
+            "list,item\nAnime,\"Anime \"\"1\"\"\"\nMovies,Movie 1\nMovies,\"Movie, The Sequel\"\n"
+        );
+    }
+
+    #[test]
+    fn export_markdown() {
+        let watchlist = setup();
+        assert_eq!(
+            export(&watchlist, &[], ExportFormat::Markdown, &[]).unwrap(),
+            "## Anime\n\n- [ ] Anime \"1\"\n\n## Movies\n\n- [ ] Movie 1\n- [ ] Movie, The Sequel\n"
+        );
+    }
+
+    #[test]
+    fn export_text() {
+        let watchlist = setup();
+        assert_eq!(
+            export(&watchlist, &["Movies"], ExportFormat::Text, &[]).unwrap(),
+            "Movie 1\nMovie, The Sequel\n"
+        );
+        assert_eq!(
+            export(&watchlist, &[], ExportFormat::Text, &[]).unwrap(),
+            "Anime:\nAnime \"1\"\n\nMovies:\nMovie 1\nMovie, The Sequel\n"
+        );
+    }
+
+    #[test]
+    fn export_fields() {
+        let mut watchlist = setup();
+        watchlist.get_mut("Movies").unwrap()[0].meta.rating = Some(8);
+        let fields = fields::lookup(&["title", "rating"]).unwrap();
+        assert_eq!(
+            export(&watchlist, &["Movies"], ExportFormat::Csv, &fields).unwrap(),
+            "list,title,rating\nMovies,Movie 1,8\nMovies,\"Movie, The Sequel\",\n"
+        );
+        assert_eq!(
+            export(&watchlist, &["Movies"], ExportFormat::Text, &fields).unwrap(),
+            "Movie 1\t8\nMovie, The Sequel\t\n"
+        );
+        assert_eq!(
+            export(&watchlist, &["Movies"], ExportFormat::Json, &fields[1..]).unwrap(),
+            "{\"Movies\":[{\"rating\":8},{\"rating\":null}]}\n"
+        );
+        assert_eq!(
+            export(&watchlist, &["Anime"], ExportFormat::Json, &[]).unwrap(),
+            "{\"Anime\":[\"Anime \\\"1\\\"\"]}\n"
+        );
+    }
+
+    #[test]
+    fn export_errors() {
+        let watchlist = setup();
+        assert_eq!(
+            export(&watchlist, &["TV"], ExportFormat::Text, &[])
+                .err()
+                .unwrap(),
+            WatchListError::TitleNotPresent("TV".to_string(), Vec::new())
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn json_round_trip(watchlist in watchlists()) {
+            let data = export(&watchlist, &[], ExportFormat::Json, &[]).unwrap();
+            prop_assert_eq!(serde_json::from_str::<WatchList>(&data).unwrap(), watchlist);
+        }
+
+        #[test]
+        fn csv_round_trip(watchlist in watchlists()) {
+            let data = export(&watchlist, &[], ExportFormat::Csv, &[]).unwrap();
+            let records = csv_records(&data).unwrap();
+            prop_assert_eq!(&records[0], &["list", "item"]);
+
+            // Empty lists have no rows so they can't be read back
+            let mut expected = watchlist;
+            expected.retain(|_, items| !items.is_empty());
+            let mut read = WatchList::new();
+            for record in &records[1..] {
+                prop_assert_eq!(record.len(), 2);
+                read.get_or_insert(record[0].clone()).push(Item::new(&record[1]));
+            }
+            for (_, items) in expected.iter_mut() {
+                for item in items.iter_mut() {
+                    item.meta = Default::default();
+                }
+            }
+            prop_assert_eq!(read, expected);
+        }
+    }
+}
@@ -0,0 +1,328 @@
+//! Registry of the item fields which can be picked for output
+//!
+//! New metadata only needs an entry in [`FIELDS`] to be selectable with `--fields`
+
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+use serde_json::{json, Map, Value};
+
+use crate::{Item, WatchList, WatchListError};
+
+/// A piece of information about an item
+pub struct Field {
+    pub name: &'static str,
+    value: fn(&Item) -> Value,
+}
+
+impl Field {
+    /// Gets the value of the field, null if the item doesn't have it
+    pub fn value(&self, item: &Item) -> Value {
+        (self.value)(item)
+    }
+
+    /// Gets the value of the field as text, empty if the item doesn't have it
+    pub fn text(&self, item: &Item) -> String {
+        match self.value(item) {
+            Value::Null => String::new(),
+            Value::String(s) => s,
+            Value::Array(values) => values
+                .iter()
+                .map(|v| v.as_str().map_or_else(|| v.to_string(), str::to_string))
+                .collect::<Vec<_>>()
+                .join(", "),
+            Value::Object(ids) => ids
+                .iter()
+                .map(|(service, id)| format!("{service}:{}", id.as_str().unwrap_or_default()))
+                .collect::<Vec<_>>()
+                .join(", "),
+            value => value.to_string(),
+        }
+    }
+}
+
+/// Every field which can be selected
+pub const FIELDS: &[Field] = &[
+    Field {
+        name: "title",
+        value: |i| json!(i.title),
+    },
+    Field {
+        name: "status",
+        value: |i| json!(i.status().to_string()),
+    },
+    Field {
+        name: "url",
+        value: |i| json!(i.meta.url),
+    },
+    Field {
+        name: "year",
+        value: |i| json!(i.meta.year),
+    },
+    Field {
+        name: "due",
+        value: |i| json!(i.meta.due),
+    },
+    Field {
+        name: "rating",
+        value: |i| json!(i.meta.rating),
+    },
+    Field {
+        name: "progress",
+        value: |i| json!(i.meta.progress),
+    },
+    Field {
+        name: "tags",
+        value: |i| json!(i.meta.tags),
+    },
+    Field {
+        name: "poster",
+        value: |i| json!(i.meta.poster),
+    },
+    Field {
+        name: "ids",
+        value: |i| json!(i.meta.ids),
+    },
+    Field {
+        name: "runtime",
+        value: |i| json!(i.meta.runtime),
+    },
+    Field {
+        name: "added",
+        value: |i| json!(i.meta.added_at),
+    },
+];
+
+/// Looks up fields by name, ignoring case
+pub fn lookup<T: AsRef<str>>(names: &[T]) -> Result<Vec<&'static Field>, WatchListError> {
+    names
+        .iter()
+        .map(|name| {
+            let name = name.as_ref().trim();
+            FIELDS
+                .iter()
+                .find(|f| f.name.eq_ignore_ascii_case(name))
+                .ok_or(WatchListError::UnknownField(name.to_string()))
+        })
+        .collect()
+}
+
+/// Joins the fields of an item as text
+pub fn text_row(item: &Item, fields: &[&Field], separator: &str) -> String {
+    fields
+        .iter()
+        .map(|f| f.text(item))
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// Gets the fields of an item as a JSON object
+pub fn json_object(item: &Item, fields: &[&Field]) -> Value {
+    let object: Map<String, Value> = fields
+        .iter()
+        .map(|f| (f.name.to_string(), f.value(item)))
+        .collect();
+    Value::Object(object)
+}
+
+/// Formats of output meant to be read by other programs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// An array of objects with every field unless some are chosen
+    Json,
+    /// Tab separated rows with every field unless some are chosen
+    Tsv,
+    /// Only the titles, or the chosen fields, without any decoration
+    Plain,
+}
+
+/// An item along with the list it is in and its number in the list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry<'a> {
+    pub list: &'a str,
+    /// Position of the item in the list, starting from 1 like `list_display`
+    pub index: usize,
+    pub item: &'a Item,
+}
+
+impl<'a> Entry<'a> {
+    /// Numbers items by their positions in the lists they were taken from
+    pub fn numbered(watchlist: &'a WatchList, items: &[(&'a str, &'a Item)]) -> Vec<Entry<'a>> {
+        // Items are found by address as titles don't have to be unique
+        let mut positions: HashMap<*const Item, usize> = HashMap::new();
+        let mut numbered = HashSet::new();
+        for &(list, _) in items {
+            if !numbered.insert(list) {
+                continue;
+            }
+            if let Some(list_items) = watchlist.get(list) {
+                positions.extend(
+                    list_items
+                        .iter()
+                        .enumerate()
+                        .map(|(i, item)| (item as *const Item, i + 1)),
+                );
+            }
+        }
+        items
+            .iter()
+            .map(|&(list, item)| Entry {
+                list,
+                index: positions.get(&(item as *const Item)).copied().unwrap_or(0),
+                item,
+            })
+            .collect()
+    }
+
+    /// Formats the entry as a JSON object, a tab separated row or a plain line
+    pub fn format(&self, fields: &[&Field], format: OutputFormat) -> String {
+        let fields = default_fields(fields, format);
+        match format {
+            OutputFormat::Json => self.json(&fields).to_string(),
+            OutputFormat::Tsv => {
+                let mut row = vec![self.list.to_string(), self.index.to_string()];
+                row.extend(fields.iter().map(|f| f.text(self.item)));
+                row.iter()
+                    .map(|value| value.replace(['\t', '\n', '\r'], " "))
+                    .collect::<Vec<_>>()
+                    .join("\t")
+            }
+            OutputFormat::Plain => text_row(self.item, &fields, " | "),
+        }
+    }
+
+    fn json(&self, fields: &[&Field]) -> Value {
+        let mut object = Map::new();
+        object.insert("list".to_string(), json!(self.list));
+        object.insert("index".to_string(), json!(self.index));
+        if let Value::Object(values) = json_object(self.item, fields) {
+            object.extend(values);
+        }
+        Value::Object(object)
+    }
+}
+
+/// Formats entries for other programs, as a JSON array or with one line each
+pub fn format_entries(entries: &[Entry], fields: &[&Field], format: OutputFormat) -> String {
+    let mut output = Vec::new();
+    // Writing to memory can't fail
+    write_entries(&mut output, entries, fields, format).unwrap();
+    let output = String::from_utf8_lossy(&output);
+    output.strip_suffix('\n').unwrap_or(&output).to_string()
+}
+
+/// Writes entries for other programs one at a time, like [`format_entries`] but ending
+/// every line, where no entries write nothing unless they're written as a JSON array
+pub fn write_entries(
+    out: &mut impl Write,
+    entries: &[Entry],
+    fields: &[&Field],
+    format: OutputFormat,
+) -> io::Result<()> {
+    match format {
+        OutputFormat::Json => {
+            let fields = default_fields(fields, format);
+            write!(out, "[")?;
+            for (i, entry) in entries.iter().enumerate() {
+                if i > 0 {
+                    write!(out, ",")?;
+                }
+                serde_json::to_writer(&mut *out, &entry.json(&fields))?;
+            }
+            writeln!(out, "]")
+        }
+        _ => entries
+            .iter()
+            .try_for_each(|e| writeln!(out, "{}", e.format(fields, format))),
+    }
+}
+
+// Plain output shows just the titles by default, while the others show everything
+fn default_fields<'f>(fields: &[&'f Field], format: OutputFormat) -> Vec<&'f Field> {
+    match (fields.is_empty(), format) {
+        (false, _) => fields.to_vec(),
+        (true, OutputFormat::Plain) => vec![&FIELDS[0]],
+        (true, _) => FIELDS.iter().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::Progress;
+
+    #[test]
+    fn selecting() {
+        let item = Item::new("Lost")
+            .with_year(2004)
+            .with_tags(&["drama", "mystery"])
+            .with_id("imdb", "tt0411008")
+            .with_id("tvdb", "73739")
+            .with_progress(Progress::Episode {
+                season: 2,
+                episode: 3,
+            });
+        let fields = lookup(&["title", "Status", "tags", "progress", "rating"]).unwrap();
+        assert_eq!(
+            text_row(&item, &fields, " | "),
+            "Lost | unwatched | drama, mystery | s2e3 | "
+        );
+        assert_eq!(
+            text_row(&item, &lookup(&["ids"]).unwrap(), " | "),
+            "imdb:tt0411008, tvdb:73739"
+        );
+        assert_eq!(
+            json_object(&item, &fields).to_string(),
+            r#"{"progress":"s2e3","rating":null,"status":"unwatched","tags":["drama","mystery"],"title":"Lost"}"#
+        );
+
+        assert_eq!(
+            lookup(&["title", "genre"]).err().unwrap(),
+            WatchListError::UnknownField("genre".to_string())
+        );
+    }
+
+    #[test]
+    fn machine_output() {
+        let mut watchlist = WatchList::new();
+        watchlist.insert(
+            "TV".to_string(),
+            vec![Item::new("Lost"), Item::new("Dark\tSeason").watched(true)],
+        );
+        watchlist.get_mut("TV").unwrap()[1].meta.rating = Some(9);
+        let items: Vec<(&str, &Item)> =
+            vec![("TV", &watchlist["TV"][1]), ("TV", &watchlist["TV"][0])];
+        let entries = Entry::numbered(&watchlist, &items);
+        assert_eq!(
+            entries.iter().map(|e| e.index).collect::<Vec<_>>(),
+            vec![2, 1]
+        );
+
+        let fields = lookup(&["title", "rating"]).unwrap();
+        assert_eq!(
+            format_entries(&entries, &fields, OutputFormat::Json),
+            r#"[{"index":2,"list":"TV","rating":9,"title":"Dark\tSeason"},{"index":1,"list":"TV","rating":null,"title":"Lost"}]"#
+        );
+        assert_eq!(
+            format_entries(&entries, &fields, OutputFormat::Tsv),
+            "TV\t2\tDark Season\t9\nTV\t1\tLost\t"
+        );
+        assert_eq!(
+            format_entries(&entries, &[], OutputFormat::Plain),
+            "Dark\tSeason\nLost"
+        );
+        let written = |entries: &[Entry], format| {
+            let mut out = Vec::new();
+            write_entries(&mut out, entries, &fields, format).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(written(&entries[1..], OutputFormat::Plain), "Lost | \n");
+        assert_eq!(written(&[], OutputFormat::Json), "[]\n");
+        assert_eq!(written(&[], OutputFormat::Tsv), "");
+
+        let everything = entries[0].format(&[], OutputFormat::Json);
+        let value: Value = serde_json::from_str(&everything).unwrap();
+        assert_eq!(value["status"], "watched");
+        assert_eq!(value.as_object().unwrap().len(), FIELDS.len() + 2);
+    }
+}
@@ -0,0 +1,87 @@
+//! Matching of titles which ignores accents, so "pokemon" finds "Pokémon"
+//!
+//! Titles are decomposed (NFKD) and their combining marks left out before comparing,
+//! which also turns compatibility characters like "ﬁ" and full width letters into
+//! their plain forms
+
+use std::ops::Range;
+
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Lowercases the text and takes the accents off its letters
+pub fn fold(text: &str) -> String {
+    text.nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Checks if the title contains the search once both are folded
+pub fn contains(title: &str, search: &str) -> bool {
+    fold(title).contains(&fold(search))
+}
+
+/// Byte ranges of the title containing the search once both are folded, none if the title
+/// doesn't contain it
+pub fn spans(title: &str, search: &str) -> Vec<Range<usize>> {
+    let search: Vec<char> = fold(search).chars().collect();
+    if search.is_empty() {
+        return Vec::new();
+    }
+    // Each folded character remembers the character of the title it came from, since
+    // folding can change how long the title is
+    let folded: Vec<(char, Range<usize>)> = title
+        .char_indices()
+        .flat_map(|(start, c)| {
+            let source = start..start + c.len_utf8();
+            fold(c.encode_utf8(&mut [0; 4]))
+                .chars()
+                .map(move |f| (f, source.clone()))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    let mut spans: Vec<Range<usize>> = Vec::new();
+    let mut i = 0;
+    while i + search.len() <= folded.len() {
+        let window = &folded[i..i + search.len()];
+        if window.iter().map(|(c, _)| *c).eq(search.iter().copied()) {
+            let span = window[0].1.start..window[window.len() - 1].1.end;
+            match spans.last_mut() {
+                // A character folding into several can be split between two matches
+                Some(last) if last.end > span.start => last.end = span.end,
+                _ => spans.push(span),
+            }
+            i += search.len();
+        } else {
+            i += 1;
+        }
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folding() {
+        assert_eq!(fold("Pokémon"), "pokemon");
+        assert_eq!(fold("Amélie"), fold("AMELIE"));
+        assert_eq!(fold("Ｄｕｎｅ"), "dune");
+        assert_eq!(fold("ﬁve"), "five");
+        assert!(contains("Pokémon: The First Movie", "pokemon"));
+        assert!(contains("Pokemon", "Pokémon"));
+        assert!(!contains("Pokémon", "digimon"));
+    }
+
+    #[test]
+    fn folded_spans() {
+        assert_eq!(spans("Pokémon", "kemo"), vec![2..7]);
+        assert_eq!(spans("Léon: The Professional", "LEON"), vec![0..5]);
+        assert_eq!(spans("ﬁve ﬁsh", "fi"), vec![0..3, 6..9]);
+        assert_eq!(spans("Amélie", "x"), Vec::<Range<usize>>::new());
+        assert_eq!(spans("Amélie", ""), Vec::<Range<usize>>::new());
+    }
+}
@@ -0,0 +1,193 @@
+//! Estimates of how long clearing a list will take at the recent rate of completing items
+
+use chrono::{Days, NaiveDate, TimeDelta};
+
+use crate::status::Status;
+use crate::{Item, WatchListError};
+
+/// Number of days the completion rate is measured over when no other number is given
+pub const DEFAULT_DAYS: u32 = 30;
+
+/// How far through a list is and how long the rest of it should take
+#[derive(Debug, Clone, PartialEq)]
+pub struct Forecast {
+    /// Items left in the list which are neither watched nor dropped
+    pub remaining: usize,
+    /// Items completed in the days measured
+    pub completed: usize,
+    /// Number of days the completions were counted over
+    pub days: u32,
+    /// Minutes needed to watch the remaining items, if any items have a runtime
+    pub minutes: Option<u32>,
+    /// Remaining items with a runtime of their own, the others are assumed to take the average
+    pub with_runtime: usize,
+}
+
+impl Forecast {
+    /// Measures the rate from the days items were completed, counting the ones in the
+    /// `days` up to and including `today`
+    pub fn new(items: &[Item], completions: &[NaiveDate], today: NaiveDate, days: u32) -> Self {
+        let first = today - TimeDelta::days(i64::from(days.max(1)) - 1);
+        let completed = completions
+            .iter()
+            .filter(|d| (first..=today).contains(d))
+            .count();
+
+        let remaining: Vec<&Item> = items
+            .iter()
+            .filter(|i| i.status() == Status::Unwatched)
+            .collect();
+        let runtimes: Vec<u32> = items.iter().filter_map(|i| i.meta.runtime).collect();
+        let minutes = match runtimes.is_empty() {
+            true => None,
+            false => {
+                let average = runtimes.iter().sum::<u32>() / runtimes.len() as u32;
+                Some(
+                    remaining
+                        .iter()
+                        .map(|i| i.meta.runtime.unwrap_or(average))
+                        .sum(),
+                )
+            }
+        };
+
+        Forecast {
+            remaining: remaining.len(),
+            completed,
+            days: days.max(1),
+            minutes,
+            with_runtime: remaining
+                .iter()
+                .filter(|i| i.meta.runtime.is_some())
+                .count(),
+        }
+    }
+
+    /// Gets the average number of items completed a day
+    pub fn rate(&self) -> f64 {
+        self.completed as f64 / f64::from(self.days)
+    }
+
+    /// Gets the number of days until the list is cleared, none if nothing was completed lately
+    pub fn days_left(&self) -> Option<u64> {
+        match (self.remaining, self.completed) {
+            (0, _) => Some(0),
+            (_, 0) => None,
+            (remaining, completed) => {
+                Some((remaining as u64 * u64::from(self.days)).div_ceil(completed as u64))
+            }
+        }
+    }
+
+    /// Gets the day the list should be cleared on
+    pub fn finish(&self, today: NaiveDate) -> Option<NaiveDate> {
+        today.checked_add_days(Days::new(self.days_left()?))
+    }
+}
+
+/// Parses a runtime like `1h30m`, `2h`, `90m` or `90` into minutes
+pub fn parse_runtime(input: &str) -> Result<u32, WatchListError> {
+    let invalid = || WatchListError::InvalidRuntime(input.to_string());
+    let text: String = input.split_whitespace().collect::<String>().to_lowercase();
+    if let Ok(minutes) = text.parse::<u32>() {
+        return Some(minutes).filter(|m| *m > 0).ok_or_else(invalid);
+    }
+
+    let (hours, rest) = match text.split_once('h') {
+        Some((hours, rest)) => (hours.parse::<u32>().map_err(|_| invalid())?, rest),
+        None => (0, text.as_str()),
+    };
+    let minutes = match rest.strip_suffix('m') {
+        Some(minutes) => minutes.parse::<u32>().map_err(|_| invalid())?,
+        None if rest.is_empty() => 0,
+        None => return Err(invalid()),
+    };
+    hours
+        .checked_mul(60)
+        .and_then(|h| h.checked_add(minutes))
+        .filter(|m| *m > 0)
+        .ok_or_else(invalid)
+}
+
+/// Formats minutes like `1h 30m`
+pub fn format_runtime(minutes: u32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{m}m"),
+        (h, 0) => format!("{h}h"),
+        (h, m) => format!("{h}h {m}m"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WatchList;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn forecasting() {
+        let today = date("2024-03-31");
+        let items = vec![
+            Item::new("Alien").with_runtime(117),
+            Item::new("Dune").with_runtime(155),
+            Item::new("Heat"),
+            Item::new("Jaws").with_runtime(124).watched(true),
+        ];
+        let completions = [
+            date("2024-03-02"),
+            date("2024-03-10"),
+            date("2024-03-31"),
+            // Too long ago to count
+            date("2024-03-01"),
+        ];
+        let forecast = Forecast::new(&items, &completions, today, 30);
+        assert_eq!(forecast.remaining, 3);
+        assert_eq!(forecast.completed, 3);
+        assert_eq!(forecast.rate(), 0.1);
+        assert_eq!(forecast.days_left(), Some(30));
+        assert_eq!(forecast.finish(today), Some(date("2024-04-30")));
+        // Heat takes the average of all the runtimes known
+        assert_eq!(forecast.minutes, Some(117 + 155 + 132));
+        assert_eq!(forecast.with_runtime, 2);
+
+        let stalled = Forecast::new(&items, &[], today, 30);
+        assert_eq!(stalled.days_left(), None);
+        assert_eq!(stalled.finish(today), None);
+
+        let cleared = Forecast::new(&items[3..], &[], today, 7);
+        assert_eq!(cleared.days_left(), Some(0));
+        assert_eq!(cleared.minutes, Some(0));
+        assert_eq!(Forecast::new(&items[2..3], &[], today, 7).minutes, None);
+    }
+
+    #[test]
+    fn runtimes() {
+        assert_eq!(parse_runtime("1h30m"), Ok(90));
+        assert_eq!(parse_runtime("2H"), Ok(120));
+        assert_eq!(parse_runtime("1h 5m"), Ok(65));
+        assert_eq!(parse_runtime("45m"), Ok(45));
+        assert_eq!(parse_runtime("90"), Ok(90));
+        for invalid in ["", "0", "0h", "h", "1.5h", "90s", "m30", "1h30"] {
+            assert_eq!(
+                parse_runtime(invalid),
+                Err(WatchListError::InvalidRuntime(invalid.to_string()))
+            );
+        }
+
+        assert_eq!(format_runtime(45), "45m");
+        assert_eq!(format_runtime(120), "2h");
+        assert_eq!(format_runtime(155), "2h 35m");
+
+        let mut watchlist = WatchList::new();
+        watchlist.insert("Movies".to_string(), vec![Item::new("Alien")]);
+        watchlist
+            .item_set_runtime("Movies", "Alien", Some(117))
+            .unwrap();
+        assert_eq!(watchlist["Movies"][0].meta.runtime, Some(117));
+        watchlist.item_set_runtime("Movies", "Alien", None).unwrap();
+        assert_eq!(watchlist["Movies"][0].meta.runtime, None);
+    }
+}
@@ -0,0 +1,118 @@
+//! Fuzzy matching of item titles which allows for typos and words in any order
+//!
+//! Each word of a search is matched on its own, first as letters appearing in
+//! order in the title and otherwise as a misspelling of one of the title's words
+
+use std::ops::Range;
+
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+
+use crate::{Item, Match};
+
+/// Similarity from 0 to 1 a word needs to count as a misspelling of another
+const MIN_SIMILARITY: f64 = 0.75;
+
+/// Gets the items matching the search, best matches first
+pub fn search<'a>(items: &'a [Item], search: &str) -> Vec<&'a Item> {
+    search_matches(items, search)
+        .into_iter()
+        .map(|m| m.item)
+        .collect()
+}
+
+/// Gets the items matching the search like [`search`], along with the letters of their
+/// titles which matched
+pub fn search_matches<'a>(items: &'a [Item], search: &str) -> Vec<Match<'a>> {
+    let matcher = SkimMatcherV2::default().ignore_case();
+    let mut scored: Vec<(i64, Match)> = items
+        .iter()
+        .filter_map(|item| {
+            let (score, letters) = score(&matcher, &item.title, search)?;
+            let spans = spans(&item.title, &letters);
+            Some((score, Match { item, spans }))
+        })
+        .collect();
+    // Sorting is stable so items with the same score stay in the order of the list
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    scored.into_iter().map(|(_, m)| m).collect()
+}
+
+// Higher is better, none if any word of the search can't be found in the title. Gives the
+// positions of the letters of the title which matched too, counted in characters
+fn score(matcher: &SkimMatcherV2, title: &str, search: &str) -> Option<(i64, Vec<usize>)> {
+    let title_words: Vec<String> = title.split_whitespace().map(str::to_lowercase).collect();
+    let mut letters = Vec::new();
+    let total = search.split_whitespace().try_fold(0, |total, word| {
+        if let Some((score, indices)) = matcher.fuzzy_indices(title, word) {
+            letters.extend(indices);
+            return Some(total + score);
+        }
+        let word = word.to_lowercase();
+        let similarity = title_words
+            .iter()
+            .map(|t| strsim::normalized_damerau_levenshtein(t, &word))
+            .fold(0.0, f64::max);
+        // Misspelled words count for less than the ones which were found
+        (similarity >= MIN_SIMILARITY).then(|| total + (similarity * 10.0) as i64)
+    })?;
+    Some((total, letters))
+}
+
+// Byte ranges of the letters at the character positions, with letters next to each other
+// put together
+fn spans(title: &str, letters: &[usize]) -> Vec<Range<usize>> {
+    let mut spans: Vec<Range<usize>> = Vec::new();
+    for (position, (start, c)) in title.char_indices().enumerate() {
+        if !letters.contains(&position) {
+            continue;
+        }
+        let end = start + c.len_utf8();
+        match spans.last_mut() {
+            Some(last) if last.end == start => last.end = end,
+            _ => spans.push(start..end),
+        }
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matching() {
+        let items = [
+            Item::new("The Dark Knight"),
+            Item::new("The Godfather"),
+            Item::new("Dark City"),
+            Item::new("Knight and Day"),
+        ];
+        let titles = |prompt: &str| -> Vec<&str> {
+            search(&items, prompt)
+                .iter()
+                .map(|i| i.title.as_str())
+                .collect()
+        };
+        assert_eq!(titles("knight dark"), vec!["The Dark Knight"]);
+        assert_eq!(titles("godfahter"), vec!["The Godfather"]);
+        assert_eq!(titles("GODFTHR"), vec!["The Godfather"]);
+        // Closer matches come first
+        assert_eq!(titles("dark")[..2], ["Dark City", "The Dark Knight"]);
+        assert_eq!(titles("zodiac"), Vec::<&str>::new());
+        assert_eq!(titles("").len(), items.len());
+    }
+
+    #[test]
+    fn matched_letters() {
+        let items = [Item::new("The Dark Knight"), Item::new("Pokémon")];
+        let matches = search_matches(&items, "dk knigt");
+        assert_eq!(matches[0].spans, vec![4..5, 9..13, 14..15]);
+        assert_eq!(
+            search_matches(&items, "pkmn")[0].spans,
+            vec![0..1, 2..3, 5..6, 7..8]
+        );
+        // Misspelled words are found without matching any letters
+        assert!(search_matches(&items, "drak")[0].spans.is_empty());
+    }
+}
@@ -0,0 +1,355 @@
+//! Keeping the data file in a git repository, with a commit for every command which changes
+//! the lists, so their history can be looked through and changes rolled back
+//!
+//! Commits are made by running `git`, so they're signed, hooked and attributed the way the
+//! repository is set up to. Only the data file is committed, anything else staged in the
+//! repository is left as it is
+
+use std::io;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use chrono::DateTime;
+
+use crate::dates::Timestamp;
+use crate::{Item, WatchList, WatchListError};
+
+/// Commit which changed the data file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Commit {
+    /// Abbreviated hash of the commit
+    pub id: String,
+    pub at: Timestamp,
+    /// First line of the commit message
+    pub subject: String,
+}
+
+// Runs git in the directory of the data file, giving what it printed
+fn git(file_path: &Path, args: &[&str]) -> Result<Output, WatchListError> {
+    let dir = match file_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => WatchListError::GitError("git isn't installed".to_string()),
+            _ => e.into(),
+        })
+}
+
+// Runs git, failing with what it printed to standard error if it doesn't succeed
+fn run(file_path: &Path, args: &[&str]) -> Result<String, WatchListError> {
+    let output = git(file_path, args)?;
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        let error = error.trim().trim_start_matches("fatal: ");
+        return Err(WatchListError::GitError(error.to_string()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// Path of the data file for git, relative to the directory git is run in
+fn name(file_path: &Path) -> String {
+    let name = file_path.file_name().unwrap_or_default().to_string_lossy();
+    format!("./{name}")
+}
+
+/// Commits the data file with the message, giving whether there was anything to commit
+pub fn commit(file_path: &Path, message: &str) -> Result<bool, WatchListError> {
+    let name = name(file_path);
+    run(file_path, &["add", "--", &name])?;
+    if run(file_path, &["status", "--porcelain", "--", &name])?.is_empty() {
+        return Ok(false);
+    }
+    run(
+        file_path,
+        &["commit", "--quiet", "-m", message, "--", &name],
+    )?;
+    Ok(true)
+}
+
+/// Format of the commits read from git, parsed by [`parse_commit`]
+const FORMAT: &str = "--format=%h%x1f%at%x1f%s";
+
+fn parse_commit(line: &str) -> Result<Commit, WatchListError> {
+    let mut fields = line.splitn(3, '\x1f');
+    let (id, at, subject) = (fields.next(), fields.next(), fields.next());
+    let at = at
+        .and_then(|at| at.parse().ok())
+        .and_then(|at| DateTime::from_timestamp(at, 0));
+    match (id, at, subject) {
+        (Some(id), Some(at), Some(subject)) => Ok(Commit {
+            id: id.to_string(),
+            at,
+            subject: subject.to_string(),
+        }),
+        _ => Err(WatchListError::GitError(format!(
+            "couldn't read the commit {line}"
+        ))),
+    }
+}
+
+/// Gets the commits which changed the data file, newest first, only the last `limit` of
+/// them if it's given
+pub fn log(file_path: &Path, limit: Option<usize>) -> Result<Vec<Commit>, WatchListError> {
+    let limit = limit.map(|l| format!("--max-count={l}"));
+    let mut args = vec!["log", FORMAT];
+    args.extend(limit.as_deref());
+    let name = name(file_path);
+    args.extend(["--", &name]);
+    let log = match run(file_path, &args) {
+        Ok(log) => log,
+        // A repository without any commits yet
+        Err(WatchListError::GitError(e)) if e.contains("does not have any commits") => {
+            return Ok(Vec::new())
+        }
+        Err(e) => return Err(e),
+    };
+    log.lines().map(parse_commit).collect()
+}
+
+/// Finds the commit, which can be given any way git understands like `HEAD~2`
+pub fn find(file_path: &Path, commit: &str) -> Result<Commit, WatchListError> {
+    let revision = format!("{commit}^{{commit}}");
+    let id = run(file_path, &["rev-parse", "--verify", "--quiet", &revision])
+        .map_err(|_| WatchListError::GitError(format!("there's no commit {commit}")))?;
+    let info = run(file_path, &["log", "--max-count=1", FORMAT, id.trim()])?;
+    parse_commit(info.trim_end())
+}
+
+/// Reads the lists as they were in the commit, where a commit without the data file has none
+pub fn lists_at(file_path: &Path, commit: &str) -> Result<WatchList, WatchListError> {
+    let object = format!("{commit}:{}", name(file_path));
+    let output = git(file_path, &["show", &object])?;
+    if !output.status.success() {
+        return Ok(WatchList::new());
+    }
+    let data = output.stdout;
+    #[cfg(feature = "encryption")]
+    let data = match data.starts_with(crate::encryption::HEADER) {
+        true => {
+            let passphrase = crate::encryption::passphrase(file_path)?;
+            crate::encryption::decrypt(&data, &passphrase)?
+        }
+        false => data,
+    };
+    let (watchlist, _) = crate::schema::parse(&data).map_err(crate::read_error)?;
+    Ok(watchlist)
+}
+
+/// Reads the lists from before and after the commit
+pub fn changes(file_path: &Path, commit: &str) -> Result<(WatchList, WatchList), WatchListError> {
+    // The first commit has no parent, so the lists were empty before it
+    let parent = format!("{commit}^");
+    let before = match run(file_path, &["rev-parse", "--verify", "--quiet", &parent]) {
+        Ok(_) => lists_at(file_path, &parent)?,
+        Err(_) => WatchList::new(),
+    };
+    Ok((before, lists_at(file_path, commit)?))
+}
+
+/// Describes what changed between the lists in a few words for the subject of a commit,
+/// like `add 'Dune' to Movies`, followed by the command which changed them
+pub fn message(before: &WatchList, after: &WatchList, command: &str) -> String {
+    let changes = describe(before, after);
+    let subject = match changes.as_slice() {
+        [] => "change the order of the lists".to_string(),
+        [change] => change.clone(),
+        [change, rest @ ..] => format!("{change} and {} more change(s)", rest.len()),
+    };
+    let mut message = format!("{subject}\n\n{command}");
+    if changes.len() > 1 {
+        message.push('\n');
+        for change in &changes {
+            message.push_str(&format!("\n- {change}"));
+        }
+    }
+    message
+}
+
+/// Changes made to the lists, one for each list or item
+pub fn describe(before: &WatchList, after: &WatchList) -> Vec<String> {
+    let mut created: Vec<&String> = after.titles().filter(|t| !before.contains(t)).collect();
+    let mut deleted: Vec<&String> = before.titles().filter(|t| !after.contains(t)).collect();
+    created.sort();
+    deleted.sort();
+
+    let mut changes: Vec<String> = Vec::new();
+    match (deleted.as_slice(), created.as_slice()) {
+        // A list with the same items under another title was renamed
+        ([from], [to]) if before[from.as_str()] == after[to.as_str()] => {
+            changes.push(format!("rename {from} to {to}"));
+        }
+        _ => {
+            changes.extend(created.iter().map(|t| format!("create {t}")));
+            changes.extend(deleted.iter().map(|t| format!("delete {t}")));
+        }
+    }
+
+    let mut titles: Vec<&String> = after.titles().filter(|t| before.contains(t)).collect();
+    titles.sort();
+    for title in titles {
+        let (old, new) = (&before[title.as_str()], &after[title.as_str()]);
+        for item in new {
+            match find_item(old, &item.title) {
+                None => changes.push(format!("add '{}' to {title}", item.title)),
+                Some(old) if old != item => {
+                    changes.push(format!("change '{}' in {title}", item.title))
+                }
+                Some(_) => {}
+            }
+        }
+        for item in old.iter().filter(|i| find_item(new, &i.title).is_none()) {
+            changes.push(format!("delete '{}' from {title}", item.title));
+        }
+    }
+    changes
+}
+
+fn find_item<'a>(items: &'a [Item], title: &str) -> Option<&'a Item> {
+    items.iter().find(|i| i.title == title)
+}
+
+/// Undoes the changes made between `before` and `after` to the lists, keeping the changes
+/// made since then
+///
+/// Items which were added are deleted, ones which were deleted are added back and ones
+/// which were changed are put back the way they were, unless they were changed again since.
+/// Lists which were created are deleted once they have no items left
+pub fn revert(lists: &mut WatchList, before: &WatchList, after: &WatchList) {
+    for (title, items) in after {
+        let old = before.get(title).map(Vec::as_slice).unwrap_or_default();
+        if let Some(current) = lists.get_mut(title) {
+            revert_items(current, old, items);
+        }
+        if !before.contains(title) && lists.get(title).is_some_and(Vec::is_empty) {
+            lists.remove(title);
+        }
+    }
+    for (title, items) in before {
+        if !after.contains(title) {
+            let current = lists.get_or_insert(title);
+            revert_items(current, items, &[]);
+        }
+    }
+}
+
+fn revert_items(current: &mut Vec<Item>, before: &[Item], after: &[Item]) {
+    current.retain(|item| match find_item(after, &item.title) {
+        Some(added) if find_item(before, &item.title).is_none() => item != added,
+        _ => true,
+    });
+    for item in before {
+        let now = current.iter_mut().find(|i| i.title == item.title);
+        match (find_item(after, &item.title), now) {
+            (None, None) => current.push(item.clone()),
+            (Some(changed), Some(now)) if changed != item && now == changed => *now = item.clone(),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn lists(lists: &[(&str, &[&str])]) -> WatchList {
+        lists
+            .iter()
+            .map(|(title, items)| (title.to_string(), items.iter().map(|&i| i.into()).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn messages() {
+        let before = lists(&[("Movies", &["Alien"]), ("Books", &[])]);
+        let added = lists(&[("Movies", &["Alien", "Dune"]), ("Books", &[])]);
+        assert_eq!(
+            message(&before, &added, "wl add Movies Dune"),
+            "add 'Dune' to Movies\n\nwl add Movies Dune"
+        );
+
+        let mut rated = before.clone();
+        rated.item_set_rating("Movies", "Alien", 9).unwrap();
+        assert_eq!(describe(&before, &rated), ["change 'Alien' in Movies"]);
+        let renamed = lists(&[("Films", &["Alien"]), ("Books", &[])]);
+        assert_eq!(describe(&before, &renamed), ["rename Movies to Films"]);
+
+        let mixed = lists(&[("Movies", &[]), ("Anime", &["Akira"])]);
+        assert_eq!(
+            message(&before, &mixed, "wl shell"),
+            "create Anime and 2 more change(s)\n\nwl shell\n\n\
+             - create Anime\n- delete Books\n- delete 'Alien' from Movies"
+        );
+    }
+
+    #[test]
+    fn reverting() {
+        let before = lists(&[("Movies", &["Alien", "Heat"]), ("Books", &["Dune"])]);
+        let after = lists(&[("Movies", &["Alien", "Ran"]), ("Anime", &["Akira"])]);
+        // Changed again since the commit
+        let mut current = after.clone();
+        current.insert("Anime", vec!["Akira".into(), "Ran".into()]);
+        current
+            .item_add("Movies", "Up", crate::DuplicatePolicy::Exact)
+            .unwrap();
+        current.item_set_rating("Movies", "Ran", 8).unwrap();
+
+        revert(&mut current, &before, &after);
+        let mut reverted = lists(&[
+            ("Movies", &["Alien", "Ran", "Up", "Heat"]),
+            ("Books", &["Dune"]),
+            ("Anime", &["Ran"]),
+        ]);
+        reverted.item_set_rating("Movies", "Ran", 8).unwrap();
+        assert_eq!(current, reverted);
+
+        // Reverting every change since gets back to where it started
+        let mut current = after.clone();
+        revert(&mut current, &before, &after);
+        assert_eq!(
+            current,
+            lists(&[("Movies", &["Alien", "Heat"]), ("Books", &["Dune"])])
+        );
+    }
+
+    #[test]
+    fn committing() {
+        let dir = env::temp_dir().join(format!("wlist-git-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("watchlist.json");
+        assert!(matches!(
+            commit(&path, "add 'Alien' to Movies"),
+            Err(WatchListError::GitError(_))
+        ));
+        run(&path, &["init", "--quiet"]).unwrap();
+        run(&path, &["config", "user.name", "wl"]).unwrap();
+        run(&path, &["config", "user.email", "wl@example.com"]).unwrap();
+        assert_eq!(log(&path, None).unwrap(), []);
+
+        let first = lists(&[("Movies", &["Alien"])]);
+        first.to_file(&path).unwrap();
+        assert!(commit(&path, "add 'Alien' to Movies\n\nwl add Movies Alien").unwrap());
+        assert!(!commit(&path, "nothing").unwrap());
+        let second = lists(&[("Movies", &["Alien", "Dune"])]);
+        second.to_file(&path).unwrap();
+        assert!(commit(&path, "add 'Dune' to Movies").unwrap());
+
+        let log = log(&path, None).unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].subject, "add 'Dune' to Movies");
+        assert_eq!(find(&path, "HEAD~1").unwrap(), log[1]);
+        assert!(find(&path, "HEAD~5").is_err());
+        assert_eq!(changes(&path, &log[0].id).unwrap(), (first.clone(), second));
+        assert_eq!(
+            changes(&path, &log[1].id).unwrap(),
+            (WatchList::new(), first)
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
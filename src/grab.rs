@@ -0,0 +1,122 @@
+#[cfg(feature = "net")]
+use crate::http::{self, network_error};
+use crate::import::{attribute, decode_entities};
+#[cfg(feature = "net")]
+use crate::WatchListError;
+
+/// Finds the title of a web page
+///
+/// Prefers the `og:title` meta tag used by most streaming sites
+/// and falls back to the `<title>` of the page
+pub fn page_title(html: &str) -> Option<String> {
+    let mut title = None;
+
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        let end = start + rest[start..].find('>')?;
+        let tag = &rest[start + 1..end];
+        rest = &rest[end + 1..];
+
+        let name = tag
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match name.as_str() {
+            "meta" => {
+                let property = attribute(tag, "property").or_else(|| attribute(tag, "name"));
+                if property == Some("og:title") {
+                    if let Some(content) = attribute(tag, "content") {
+                        return Some(decode_entities(content.trim()));
+                    }
+                }
+            }
+            "title" if title.is_none() => {
+                let text = rest[..rest.find('<').unwrap_or(rest.len())].trim();
+                title = Some(decode_entities(text));
+            }
+            // Meta tags are only present in the head
+            "/head" | "body" => break,
+            _ => {}
+        }
+    }
+    title.filter(|t| !t.is_empty())
+}
+
+#[cfg(any(feature = "net", test))]
+fn is_youtube(url: &str) -> bool {
+    let host = url
+        .split("://")
+        .nth(1)
+        .unwrap_or(url)
+        .split('/')
+        .next()
+        .unwrap_or_default();
+    host == "youtu.be" || host == "youtube.com" || host.ends_with(".youtube.com")
+}
+
+/// Fetches the title of the page at `url`
+///
+/// YouTube links are looked up using its oEmbed endpoint
+#[cfg(feature = "net")]
+pub fn fetch_title(url: &str) -> Result<String, WatchListError> {
+    if is_youtube(url) {
+        #[derive(serde::Deserialize)]
+        struct OEmbed {
+            title: String,
+        }
+        let oembed: OEmbed = serde_json::from_str(
+            &http::agent()
+                .get("https://www.youtube.com/oembed")
+                .query("url", url)
+                .query("format", "json")
+                .call()
+                .map_err(network_error)?
+                .body_mut()
+                .read_to_string()
+                .map_err(network_error)?,
+        )
+        .map_err(|e| WatchListError::ParseError(e.to_string()))?;
+        return Ok(oembed.title);
+    }
+
+    let html = http::agent()
+        .get(url)
+        .header("Accept-Language", "en")
+        .call()
+        .map_err(network_error)?
+        .body_mut()
+        .read_to_string()
+        .map_err(network_error)?;
+    page_title(&html).ok_or(WatchListError::ParseError(format!(
+        "couldn't find a title for {url}"
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn og_title() {
+        let html = r#"<html><head>
+            <title>Dune (2021) - IMDb</title>
+            <meta property='og:title' content="Dune: Part One &amp; More">
+            </head><body><meta property="og:title" content="Wrong"></body></html>"#;
+        assert_eq!(page_title(html).unwrap(), "Dune: Part One & More");
+    }
+
+    #[test]
+    fn fallback_title() {
+        let html = "<html><head><title> Dune </title></head><body></body></html>";
+        assert_eq!(page_title(html).unwrap(), "Dune");
+        assert_eq!(page_title("<html><head></head></html>"), None);
+    }
+
+    #[test]
+    fn youtube_urls() {
+        assert!(is_youtube("https://www.youtube.com/watch?v=dQw4w9WgXcQ"));
+        assert!(is_youtube("https://youtu.be/dQw4w9WgXcQ"));
+        assert!(!is_youtube("https://www.imdb.com/title/tt1160419/"));
+    }
+}
@@ -0,0 +1,572 @@
+//! GraphQL endpoint served at `/graphql` along with the JSON API of [`serve`](crate::serve),
+//! for pages which want the lists, their items and what they pick from them in one request
+//!
+//! Queries can be sent with `GET /graphql?query=...` or in the body of `POST /graphql`,
+//! like `{"query": "{ lists { title items(limit: 5) { title year } } }"}`. Mutations
+//! change the lists, so they're only taken with `POST`, and each request can only make one
+//! change so it's recorded like the `wl` command which makes it, given by [`command`]
+//!
+//! The schema is:
+//!
+//! ```graphql
+//! type Query {
+//!   lists(offset: Int, limit: Int): [List!]!
+//!   list(title: String!): List!
+//!   search(text: String!): [List!]!
+//!   random(list: String): Pick!
+//! }
+//!
+//! type Mutation {
+//!   createList(title: String!): List!
+//!   deleteList(title: String!): Boolean!
+//!   addItem(list: String!, item: ItemInput!): Item!
+//!   deleteItem(list: String!, item: String!): Boolean!
+//! }
+//! ```
+//!
+//! The items of a `List` take `filter`, `offset` and `limit` like `GET /lists/{title}`,
+//! and searching gives each list with only the items found in it
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+use async_graphql::parser::parse_query;
+use async_graphql::parser::types::{DocumentOperations, Field, OperationType, Selection};
+use async_graphql::Positioned;
+use async_graphql::{
+    Context, EmptySubscription, InputObject, Object, Request, Schema, ServerError, SimpleObject,
+    Value,
+};
+use serde_json::json;
+
+use crate::fields;
+use crate::query::Query;
+use crate::serve::{Call, Response};
+use crate::{DuplicatePolicy, Item, WatchList, WatchListError};
+
+type Lists = Arc<Mutex<WatchList>>;
+
+// Runs the resolver on the lists, giving errors with the lists they might have meant
+fn with_lists<T>(
+    context: &Context<'_>,
+    resolve: impl FnOnce(&mut WatchList) -> Result<T, WatchListError>,
+) -> async_graphql::Result<T> {
+    let mut watchlist = context
+        .data_unchecked::<Lists>()
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    resolve(&mut watchlist)
+        .map_err(|e| async_graphql::Error::new(e.with_suggestions(&watchlist).to_string()))
+}
+
+/// A list with its items
+struct List {
+    title: String,
+    items: Vec<Item>,
+}
+
+impl List {
+    fn of(watchlist: &WatchList, title: &str) -> Result<List, WatchListError> {
+        let items = watchlist
+            .get(title)
+            .ok_or_else(|| WatchListError::TitleNotPresent(title.to_string(), Vec::new()))?;
+        Ok(List {
+            title: title.to_string(),
+            items: items.clone(),
+        })
+    }
+}
+
+#[Object]
+impl List {
+    async fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Number of items in the list
+    async fn total(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Items of the list, picked by a filter written like the ones of smart lists
+    async fn items(
+        &self,
+        filter: Option<String>,
+        offset: Option<usize>,
+        limit: Option<usize>,
+    ) -> async_graphql::Result<Vec<ItemFields>> {
+        let filter = filter.map(|f| f.parse::<Query>()).transpose()?;
+        Ok(self
+            .items
+            .iter()
+            .filter(|item| filter.as_ref().is_none_or(|f| f.matches(&self.title, item)))
+            .skip(offset.unwrap_or(0))
+            .take(limit.unwrap_or(usize::MAX))
+            .cloned()
+            .map(ItemFields)
+            .collect())
+    }
+}
+
+/// An item with its details
+struct ItemFields(Item);
+
+#[Object(name = "Item")]
+impl ItemFields {
+    async fn title(&self) -> &str {
+        &self.0.title
+    }
+
+    /// Whether it's unwatched, watching, watched or dropped
+    async fn status(&self) -> String {
+        self.0.status().to_string()
+    }
+
+    async fn url(&self) -> Option<&str> {
+        self.0.meta.url.as_deref()
+    }
+
+    async fn year(&self) -> Option<u16> {
+        self.0.meta.year
+    }
+
+    /// Date it's due by, like 2024-05-01
+    async fn due(&self) -> Option<String> {
+        self.0.meta.due.map(|due| due.to_string())
+    }
+
+    /// Score from 1 to 10
+    async fn rating(&self) -> Option<u8> {
+        self.0.meta.rating
+    }
+
+    /// Last episode or chapter reached, like S02E05
+    async fn progress(&self) -> Option<String> {
+        self.0.meta.progress.map(|progress| progress.to_string())
+    }
+
+    async fn tags(&self) -> &[String] {
+        &self.0.meta.tags
+    }
+
+    async fn poster(&self) -> Option<&str> {
+        self.0.meta.poster.as_deref()
+    }
+
+    /// Length in minutes
+    async fn runtime(&self) -> Option<u32> {
+        self.0.meta.runtime
+    }
+
+    async fn pinned(&self) -> bool {
+        self.0.meta.pinned
+    }
+
+    /// Any field which can be picked with `--fields`, as text, for ones without a field
+    /// of their own here
+    async fn field(&self, name: String) -> async_graphql::Result<Option<String>> {
+        let [field] = fields::lookup(&[name])?[..] else {
+            return Ok(None);
+        };
+        Ok(Some(field.text(&self.0)).filter(|text| !text.is_empty()))
+    }
+}
+
+/// An item picked at random, with the list it's from
+#[derive(SimpleObject)]
+struct Pick {
+    list: String,
+    item: ItemFields,
+}
+
+/// Item to add, with only its title needed
+#[derive(InputObject)]
+struct ItemInput {
+    title: String,
+    url: Option<String>,
+    year: Option<u16>,
+    rating: Option<u8>,
+    #[graphql(default)]
+    tags: Vec<String>,
+}
+
+impl TryFrom<ItemInput> for Item {
+    type Error = WatchListError;
+
+    fn try_from(input: ItemInput) -> Result<Self, Self::Error> {
+        let mut item = Item::new(input.title);
+        if let Some(rating) = input.rating.filter(|r| !(1..=10).contains(r)) {
+            return Err(WatchListError::InvalidRating(rating));
+        }
+        item.meta.url = input.url;
+        item.meta.year = input.year;
+        item.meta.rating = input.rating;
+        item.meta.tags = input.tags;
+        Ok(item)
+    }
+}
+
+struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Every list in order of title
+    async fn lists(
+        &self,
+        context: &Context<'_>,
+        offset: Option<usize>,
+        limit: Option<usize>,
+    ) -> async_graphql::Result<Vec<List>> {
+        with_lists(context, |watchlist| {
+            let mut titles: Vec<&String> = watchlist.titles().collect();
+            titles.sort();
+            titles
+                .into_iter()
+                .skip(offset.unwrap_or(0))
+                .take(limit.unwrap_or(usize::MAX))
+                .map(|title| List::of(watchlist, title))
+                .collect()
+        })
+    }
+
+    async fn list(&self, context: &Context<'_>, title: String) -> async_graphql::Result<List> {
+        with_lists(context, |watchlist| List::of(watchlist, &title))
+    }
+
+    /// Lists with the items in them containing the text
+    async fn search(
+        &self,
+        context: &Context<'_>,
+        text: String,
+    ) -> async_graphql::Result<Vec<List>> {
+        with_lists(context, |watchlist| {
+            Ok(watchlist
+                .search_all(&text)
+                .into_iter()
+                .map(|(title, items)| List {
+                    title: title.clone(),
+                    items: items.into_iter().cloned().collect(),
+                })
+                .collect())
+        })
+    }
+
+    /// Random item of the list, or of a random list picked by how many items it has
+    async fn random(
+        &self,
+        context: &Context<'_>,
+        list: Option<String>,
+    ) -> async_graphql::Result<Pick> {
+        with_lists(context, |watchlist| {
+            let list = match list {
+                Some(list) => list,
+                None => watchlist.list_get_random_weighted()?.clone(),
+            };
+            let item = watchlist.item_get_random(&list)?.clone();
+            Ok(Pick {
+                list,
+                item: ItemFields(item),
+            })
+        })
+    }
+}
+
+struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    async fn create_list(
+        &self,
+        context: &Context<'_>,
+        title: String,
+    ) -> async_graphql::Result<List> {
+        with_lists(context, |watchlist| {
+            watchlist.list_add(&title)?;
+            List::of(watchlist, &title)
+        })
+    }
+
+    async fn delete_list(
+        &self,
+        context: &Context<'_>,
+        title: String,
+    ) -> async_graphql::Result<bool> {
+        with_lists(context, |watchlist| {
+            watchlist.list_remove(&title).map(|()| true)
+        })
+    }
+
+    async fn add_item(
+        &self,
+        context: &Context<'_>,
+        list: String,
+        item: ItemInput,
+    ) -> async_graphql::Result<ItemFields> {
+        with_lists(context, |watchlist| {
+            let item = Item::try_from(item)?;
+            watchlist.item_add(&list, item.clone(), DuplicatePolicy::Exact)?;
+            Ok(ItemFields(item))
+        })
+    }
+
+    async fn delete_item(
+        &self,
+        context: &Context<'_>,
+        list: String,
+        item: String,
+    ) -> async_graphql::Result<bool> {
+        with_lists(context, |watchlist| {
+            watchlist.item_remove(&list, &item).map(|()| true)
+        })
+    }
+}
+
+// The one field of the mutation a request runs, none when it only reads the lists.
+// Mutations making more than one change are refused, so every change can be recorded
+fn mutation(request: &Request) -> Result<Option<Positioned<Field>>, String> {
+    // Mistakes in the query are given by running it
+    let Ok(document) = parse_query(&request.query) else {
+        return Ok(None);
+    };
+    let operation = match (&document.operations, &request.operation_name) {
+        (DocumentOperations::Single(operation), _) => operation,
+        (DocumentOperations::Multiple(operations), Some(name)) => {
+            match operations.iter().find(|(n, _)| n.as_str() == name) {
+                Some((_, operation)) => operation,
+                None => return Ok(None),
+            }
+        }
+        // Named operations are run without a name when there's only one
+        (DocumentOperations::Multiple(operations), None) if operations.len() == 1 => {
+            operations.values().next().expect("one operation")
+        }
+        (DocumentOperations::Multiple(_), None) => return Ok(None),
+    };
+    if operation.node.ty != OperationType::Mutation {
+        return Ok(None);
+    }
+    match operation.node.selection_set.node.items.as_slice() {
+        [Positioned {
+            node: Selection::Field(field),
+            ..
+        }] => Ok(Some(field.clone())),
+        _ => Err("A mutation can only make one change, so each one is recorded".to_string()),
+    }
+}
+
+/// Arguments of the `wl` command which makes the same change as a mutation in the body of
+/// a request, like [`serve::command`](crate::serve::command) gives for the rest of the API
+pub fn command(body: &str) -> Option<Vec<String>> {
+    let request: Request = serde_json::from_str(body).ok()?;
+    let field = mutation(&request).ok()??;
+    let argument = |name: &str| {
+        let value = field.node.get_argument(name)?.node.clone();
+        value
+            .into_const_with(|variable| request.variables.get(&variable).cloned().ok_or(()))
+            .ok()
+    };
+    let text = |name: &str| match argument(name)? {
+        Value::String(text) => Some(text),
+        _ => None,
+    };
+    let args = match field.node.name.node.as_str() {
+        "createList" => vec!["new".to_string(), text("title")?],
+        "deleteList" => vec!["delete".to_string(), text("title")?],
+        "addItem" => {
+            let Value::Object(item) = argument("item")? else {
+                return None;
+            };
+            let Some(Value::String(title)) = item.get("title") else {
+                return None;
+            };
+            vec!["add".to_string(), text("list")?, title.clone()]
+        }
+        "deleteItem" => vec!["delete".to_string(), text("list")?, text("item")?],
+        _ => return None,
+    };
+    Some(args)
+}
+
+// Runs the request on the lists, changing them for mutations when `changes` is set
+fn execute(request: Request, watchlist: &mut WatchList, changes: bool) -> Response {
+    let response = match mutation(&request) {
+        Ok(Some(_)) if !changes => async_graphql::Response::from_errors(vec![ServerError::new(
+            "Mutations have to be sent with POST",
+            None,
+        )]),
+        Err(reason) => async_graphql::Response::from_errors(vec![ServerError::new(reason, None)]),
+        Ok(_) => {
+            let lists: Lists = Arc::new(Mutex::new(std::mem::take(watchlist)));
+            let schema = Schema::new(QueryRoot, MutationRoot, EmptySubscription);
+            let response = pollster::block_on(schema.execute(request.data(lists.clone())));
+            *watchlist = std::mem::take(&mut *lists.lock().unwrap_or_else(PoisonError::into_inner));
+            response
+        }
+    };
+    Response::json("200 OK", json!(response))
+}
+
+/// Replies to `GET /graphql`, with the query in `query` and its variables as JSON in
+/// `variables`
+pub fn get(call: &Call, watchlist: &mut WatchList) -> Result<Response, WatchListError> {
+    let param = |name| crate::serve::query_param(call.query, name);
+    let Some(query) = param("query") else {
+        return Ok(Response::json(
+            "400 Bad Request",
+            json!({ "error": "GraphQL requests need the query in `query`" }),
+        ));
+    };
+    let mut request = Request::new(query);
+    if let Some(variables) = param("variables") {
+        match crate::serve::parse(&variables) {
+            Ok(variables) => request = request.variables(variables),
+            Err(response) => return Ok(response),
+        }
+    }
+    if let Some(name) = param("operationName") {
+        request = request.operation_name(name);
+    }
+    Ok(execute(request, watchlist, false))
+}
+
+/// Replies to `POST /graphql`, with the query, its variables and the name of the operation
+/// to run in the body
+pub fn post(call: &Call, watchlist: &mut WatchList) -> Result<Response, WatchListError> {
+    match crate::serve::parse::<Request>(call.body) {
+        Ok(request) => Ok(execute(request, watchlist, true)),
+        Err(response) => Ok(response),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serve::respond_api;
+
+    fn graphql(watchlist: &mut WatchList, query: &str) -> serde_json::Value {
+        let body = json!({ "query": query }).to_string();
+        let response = respond_api("POST", "/graphql", &body, watchlist);
+        assert_eq!(response.status, "200 OK");
+        serde_json::from_str(&response.body).unwrap()
+    }
+
+    #[test]
+    fn queries() {
+        let mut watchlist = WatchList::new();
+        let items = [Item::new("Alien").with_year(1979), Item::new("Heat")];
+        watchlist.insert("Movies", items.to_vec());
+        watchlist.insert("Anime", vec![Item::new("Akira").with_year(1988)]);
+
+        assert_eq!(
+            graphql(
+                &mut watchlist,
+                "{ lists { title total items(limit: 1) { title year } } }"
+            ),
+            json!({ "data": { "lists": [
+                { "title": "Anime", "total": 1, "items": [{ "title": "Akira", "year": 1988 }] },
+                { "title": "Movies", "total": 2, "items": [{ "title": "Alien", "year": 1979 }] },
+            ] } })
+        );
+        assert_eq!(
+            graphql(
+                &mut watchlist,
+                r#"{ list(title: "Movies") { items(filter: "title=heat") { title status } } }"#
+            ),
+            json!({ "data": { "list": { "items": [{ "title": "Heat", "status": "unwatched" }] } } })
+        );
+        assert_eq!(
+            graphql(
+                &mut watchlist,
+                r#"{ search(text: "ki") { title items { title } } }"#
+            ),
+            json!({ "data": { "search": [{ "title": "Anime", "items": [{ "title": "Akira" }] }] } })
+        );
+        assert_eq!(
+            graphql(
+                &mut watchlist,
+                r#"{ random(list: "Anime") { list item { title } } }"#
+            ),
+            json!({ "data": { "random": { "list": "Anime", "item": { "title": "Akira" } } } })
+        );
+
+        let missing = graphql(&mut watchlist, r#"{ list(title: "Movis") { title } }"#);
+        assert_eq!(
+            missing["errors"][0]["message"],
+            "No such list - Movis! Did you mean Movies?"
+        );
+        let get = respond_api(
+            "GET",
+            "/graphql?query=%7B%20lists%20%7B%20title%20%7D%20%7D",
+            "",
+            &mut watchlist,
+        );
+        assert_eq!(
+            get.body,
+            r#"{"data":{"lists":[{"title":"Anime"},{"title":"Movies"}]}}"#
+        );
+    }
+
+    #[test]
+    fn mutations() {
+        let mut watchlist = WatchList::new();
+        watchlist.insert("Movies", vec![Item::new("Alien")]);
+
+        let added = graphql(
+            &mut watchlist,
+            r#"mutation { addItem(list: "Movies", item: { title: "Heat", year: 1995 }) { title year } }"#,
+        );
+        assert_eq!(
+            added,
+            json!({ "data": { "addItem": { "title": "Heat", "year": 1995 } } })
+        );
+        assert_eq!(watchlist["Movies"], vec!["Alien", "Heat"]);
+        graphql(
+            &mut watchlist,
+            r#"mutation { deleteItem(list: "Movies", item: "Alien") }"#,
+        );
+        assert_eq!(watchlist["Movies"], vec!["Heat"]);
+
+        // Only one change at a time, and none through GET
+        let both = graphql(
+            &mut watchlist,
+            r#"mutation { createList(title: "Anime") { title } deleteList(title: "Movies") }"#,
+        );
+        assert!(both["errors"][0]["message"]
+            .as_str()
+            .unwrap()
+            .contains("one change"));
+        let get = respond_api(
+            "GET",
+            "/graphql?query=mutation%20%7B%20deleteList(title%3A%20%22Movies%22)%20%7D",
+            "",
+            &mut watchlist,
+        );
+        assert!(get.body.contains("POST"));
+        assert_eq!(watchlist.titles().count(), 1);
+    }
+
+    #[test]
+    fn mutations_as_commands() {
+        let body =
+            |query: &str, variables| json!({ "query": query, "variables": variables }).to_string();
+        assert_eq!(
+            command(&body(
+                r#"mutation { addItem(list: "Movies", item: { title: "Alien" }) { title } }"#,
+                json!({})
+            )),
+            Some(vec![
+                "add".to_string(),
+                "Movies".to_string(),
+                "Alien".to_string()
+            ])
+        );
+        assert_eq!(
+            command(&body(
+                "mutation Delete($list: String!) { deleteList(title: $list) }",
+                json!({ "list": "Anime TV" })
+            )),
+            Some(vec!["delete".to_string(), "Anime TV".to_string()])
+        );
+        assert_eq!(command(&body("{ lists { title } }", json!({}))), None);
+        assert_eq!(command("{"), None);
+    }
+}
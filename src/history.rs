@@ -0,0 +1,117 @@
+//! Append-only log of every command which changed the lists
+
+use std::fmt::Display;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dates::Timestamp;
+use crate::{encryption, WatchListError};
+
+/// A command which changed the lists
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Operation {
+    pub at: Timestamp,
+    /// Name of the subcommand, like `add`
+    pub command: String,
+    pub args: Vec<String>,
+}
+
+impl Operation {
+    /// Creates an operation from the command line arguments, without the program name
+    pub fn new(mut args: Vec<String>, at: Timestamp) -> Self {
+        let command = match args.is_empty() {
+            true => String::new(),
+            false => args.remove(0),
+        };
+        Operation { at, command, args }
+    }
+}
+
+impl Display for Operation {
+    /// Shows the operation as it was typed, quoting arguments with spaces in them
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "wl {}", self.command)?;
+        for arg in &self.args {
+            match arg.contains(char::is_whitespace) || arg.is_empty() {
+                true => write!(f, " {arg:?}")?,
+                false => write!(f, " {arg}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Adds an operation to the end of the log next to the data file, one JSON object per line
+///
+/// The log of an encrypted data file can't be added to without decrypting it, so it's
+/// rewritten instead
+pub fn append(file_path: &Path, operation: &Operation) -> Result<(), WatchListError> {
+    let line = serde_json::to_string(operation).map_err(|e| WatchListError::IOError(e.into()))?;
+    let path = crate::sidecar_path(file_path, "history");
+    if encryption::is_encrypted(file_path)? {
+        let mut data = encryption::read_sidecar(file_path, &path)?.unwrap_or_default();
+        data.push_str(&line);
+        data.push('\n');
+        return encryption::write_sidecar(file_path, &path, data.as_bytes());
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{line}").map_err(WatchListError::IOError)
+}
+
+/// Reads the operations logged next to the data file, oldest first
+///
+/// Returns only the last `limit` operations if it is given
+pub fn read(file_path: &Path, limit: Option<usize>) -> Result<Vec<Operation>, WatchListError> {
+    let path = crate::sidecar_path(file_path, "history");
+    let Some(data) = encryption::read_sidecar(file_path, &path)? else {
+        return Ok(Vec::new());
+    };
+    let mut operations = data
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| serde_json::from_str(l).map_err(|e| WatchListError::ParseError(e.to_string())))
+        .collect::<Result<Vec<Operation>, _>>()?;
+    if let Some(limit) = limit {
+        operations.drain(..operations.len().saturating_sub(limit));
+    }
+    Ok(operations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn logging() {
+        let path = std::env::temp_dir().join(format!("wlist_history_{}.json", std::process::id()));
+        assert!(read(&path, None).unwrap().is_empty());
+
+        let at: Timestamp = "2023-03-01T12:00:00Z".parse().unwrap();
+        let commands = [
+            args(&["add", "Movies", "Dune"]),
+            args(&["delete", "Movies", "The Thing"]),
+            args(&["new", "TV"]),
+        ];
+        for command in &commands {
+            append(&path, &Operation::new(command.clone(), at)).unwrap();
+        }
+
+        let operations = read(&path, None).unwrap();
+        assert_eq!(operations.len(), 3);
+        assert_eq!(operations[1].command, "delete");
+        assert_eq!(operations[1].to_string(), r#"wl delete Movies "The Thing""#);
+
+        let operations = read(&path, Some(2)).unwrap();
+        assert_eq!(operations[0].to_string(), r#"wl delete Movies "The Thing""#);
+        assert_eq!(operations[1], Operation::new(args(&["new", "TV"]), at));
+        assert_eq!(read(&path, Some(10)).unwrap().len(), 3);
+        std::fs::remove_file(crate::sidecar_path(&path, "history")).unwrap();
+    }
+}
@@ -0,0 +1,348 @@
+//! Running commands after items are added, deleted or done, like to make a backup, send a
+//! notification or sync with another service
+//!
+//! Commands are read from `hooks` in the config file and run with the list and the title of
+//! the item as their arguments. Each change is also posted as JSON to the URLs in `webhooks`
+//! there, like `{"event":"add","list":"Movies","item":{"title":"Heat"}}`, which needs the
+//! `net` feature.
+//!
+//! Programs using the library can add code of their own with [`Hooks::add_hook`], which is
+//! run along with the commands whenever [`Hooks::fire`] is given the lists from before and
+//! after a change, the way `wl` does once it saved them. Hooks are passed around rather
+//! than kept for the whole program, so lists read with different config files each run
+//! their own
+
+use std::fmt::{self, Debug, Display};
+use std::process::Command;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::status::Status;
+use crate::{Item, WatchList};
+
+/// Called with each change once the lists are saved
+pub type Hook = Arc<dyn Fn(&Change) + Send + Sync>;
+
+/// What happened to an item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Event {
+    Add,
+    /// Removed from its list, which moving it to another list also does
+    Delete,
+    /// Marked as watched
+    Done,
+}
+
+impl Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Event::Add => "add",
+            Event::Delete => "delete",
+            Event::Done => "done",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Something which happened to an item in a list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub event: Event,
+    pub list: String,
+    pub item: Item,
+}
+
+/// Shell commands run after each event and URLs each change is posted to, from the config
+/// file, along with the code added by programs
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Hooks {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub add: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub delete: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub done: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub webhooks: Vec<String>,
+    /// Added with [`add_hook`](Hooks::add_hook), never saved
+    #[serde(skip)]
+    pub code: Code,
+}
+
+/// Code run for each change, which is only equal to the same code
+#[derive(Clone, Default)]
+pub struct Code(Vec<Hook>);
+
+impl PartialEq for Code {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len() && self.0.iter().zip(&other.0).all(|(a, b)| Arc::ptr_eq(a, b))
+    }
+}
+
+impl Eq for Code {}
+
+impl Debug for Code {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Code({} hook(s))", self.0.len())
+    }
+}
+
+impl Hooks {
+    /// Whether there are no hooks or commands to run, so the lists only have to be kept
+    /// from before a change when there are
+    pub fn is_empty(&self) -> bool {
+        *self == Hooks::default()
+    }
+
+    /// Adds code called with every change made to the lists
+    pub fn add_hook(&mut self, hook: impl Fn(&Change) + Send + Sync + 'static) {
+        self.code.0.push(Arc::new(hook));
+    }
+
+    /// Runs the hooks and commands for every change between `before` and `after`, giving
+    /// what went wrong with the commands and webhooks which failed
+    pub fn fire(&self, before: &WatchList, after: &WatchList) -> Vec<String> {
+        if before == after || self.is_empty() {
+            return Vec::new();
+        }
+        let mut failed = Vec::new();
+        for change in changes(before, after) {
+            for hook in &self.code.0 {
+                hook(&change);
+            }
+            for command in self.commands(change.event) {
+                if let Err(e) = run(command, &change) {
+                    failed.push(format!("`{command}` {e}"));
+                }
+            }
+            for url in &self.webhooks {
+                if let Err(e) = post(url, &change) {
+                    failed.push(format!("Posting to {url} {e}"));
+                }
+            }
+        }
+        failed
+    }
+
+    fn commands(&self, event: Event) -> &[String] {
+        match event {
+            Event::Add => &self.add,
+            Event::Delete => &self.delete,
+            Event::Done => &self.done,
+        }
+    }
+}
+
+/// Gets the items added, deleted or done between `before` and `after`
+pub fn changes(before: &WatchList, after: &WatchList) -> Vec<Change> {
+    let mut titles: Vec<&String> = before.titles().chain(after.titles()).collect();
+    titles.sort();
+    titles.dedup();
+
+    let mut changes = Vec::new();
+    for list in titles {
+        let (old, new) = (before.get(list), after.get(list));
+        let find = |items: Option<&Vec<Item>>, title: &str| {
+            items.and_then(|items| items.iter().find(|i| i.title == title).cloned())
+        };
+        let mut change = |event, item: &Item| {
+            changes.push(Change {
+                event,
+                list: list.clone(),
+                item: item.clone(),
+            })
+        };
+        for item in old.into_iter().flatten() {
+            if find(new, &item.title).is_none() {
+                change(Event::Delete, item);
+            }
+        }
+        for item in new.into_iter().flatten() {
+            match find(old, &item.title) {
+                None => change(Event::Add, item),
+                Some(was)
+                    if was.status() != Status::Watched && item.status() == Status::Watched =>
+                {
+                    change(Event::Done, item)
+                }
+                Some(_) => {}
+            }
+        }
+    }
+    changes
+}
+
+// Runs the command in the shell with the list and the title of the item as its arguments,
+// which are also given as WL_LIST and WL_ITEM along with the event as WL_EVENT
+fn run(command: &str, change: &Change) -> Result<(), String> {
+    let mut shell = match cfg!(windows) {
+        true => {
+            let mut shell = Command::new("cmd");
+            shell.arg("/C").arg(command);
+            shell
+        }
+        // The first argument after the command is its name, `$0`
+        false => {
+            let mut shell = Command::new("sh");
+            shell.arg("-c").arg(command).arg("wl");
+            shell
+        }
+    };
+    let status = shell
+        .arg(&change.list)
+        .arg(&change.item.title)
+        .env("WL_EVENT", change.event.to_string())
+        .env("WL_LIST", &change.list)
+        .env("WL_ITEM", &change.item.title)
+        .status()
+        .map_err(|e| format!("couldn't be run, {e}"))?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(format!("failed with {status}")),
+    }
+}
+
+// Posts the change as JSON to the webhook, which only has to answer with a success. The
+// item is always an object, even when it has nothing but a title
+#[cfg(feature = "net")]
+fn post(url: &str, change: &Change) -> Result<(), String> {
+    #[derive(Serialize)]
+    struct Payload<'a> {
+        event: Event,
+        list: &'a str,
+        item: PostedItem<'a>,
+    }
+    #[derive(Serialize)]
+    struct PostedItem<'a> {
+        title: &'a str,
+        #[serde(flatten)]
+        meta: &'a crate::Metadata,
+    }
+
+    let payload = Payload {
+        event: change.event,
+        list: &change.list,
+        item: PostedItem {
+            title: &change.item.title,
+            meta: &change.item.meta,
+        },
+    };
+    crate::http::agent()
+        .post(url)
+        .send_json(payload)
+        .map(|_| ())
+        .map_err(|e| format!("failed, {e}"))
+}
+
+#[cfg(not(feature = "net"))]
+fn post(_url: &str, _change: &Change) -> Result<(), String> {
+    Err("needs the net feature".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finding_changes() {
+        let mut before = WatchList::new();
+        before.insert("Movies", vec![Item::new("Alien"), Item::new("Heat")]);
+        before.insert("Books", vec![Item::new("Dune")]);
+        let mut after = before.clone();
+        assert_eq!(changes(&before, &after), Vec::new());
+
+        after.get_mut("Movies").unwrap().remove(0);
+        after.get_mut("Movies").unwrap()[0].set_status(Status::Watched);
+        after.remove("Books");
+        after.insert("Shows", vec![Item::new("Dune")]);
+        let found: Vec<(Event, String, String)> = changes(&before, &after)
+            .into_iter()
+            .map(|c| (c.event, c.list, c.item.title))
+            .collect();
+        let expected = [
+            (Event::Delete, "Books", "Dune"),
+            (Event::Delete, "Movies", "Alien"),
+            (Event::Done, "Movies", "Heat"),
+            (Event::Add, "Shows", "Dune"),
+        ]
+        .map(|(event, list, item)| (event, list.to_string(), item.to_string()));
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn running_code() {
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut hooks = Hooks::default();
+        assert!(hooks.is_empty());
+        let recorded = Arc::clone(&seen);
+        hooks.add_hook(move |change| recorded.lock().unwrap().push(change.item.title.clone()));
+        assert!(!hooks.is_empty());
+
+        let before = WatchList::new();
+        let mut after = before.clone();
+        after.insert("Movies", vec![Item::new("Heat")]);
+        assert!(hooks.fire(&before, &after).is_empty());
+        // Hooks from another config file don't run the code
+        assert!(Hooks::default().fire(&before, &after).is_empty());
+        assert_eq!(*seen.lock().unwrap(), ["Heat"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn running_commands() {
+        let change = Change {
+            event: Event::Add,
+            list: "Movies".to_string(),
+            item: Item::new("The Thing"),
+        };
+        let check = r#"[ "$1" = Movies ] && [ "$2" = "The Thing" ] && [ "$WL_EVENT" = add ]"#;
+        assert_eq!(run(check, &change), Ok(()));
+        assert!(run("exit 3", &change)
+            .unwrap_err()
+            .contains("exit status: 3"));
+    }
+
+    #[cfg(feature = "net")]
+    #[test]
+    fn posting_changes() {
+        use std::io::{BufRead, BufReader, Read, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                match line.to_lowercase().strip_prefix("content-length:") {
+                    Some(value) => length = value.trim().parse().unwrap(),
+                    None if line.trim().is_empty() => break,
+                    None => {}
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            stream
+                .write_all(b"HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n")
+                .unwrap();
+            String::from_utf8(body).unwrap()
+        });
+
+        let change = Change {
+            event: Event::Done,
+            list: "Movies".to_string(),
+            item: Item::new("Heat"),
+        };
+        assert_eq!(post(&url, &change), Ok(()));
+        let body: serde_json::Value = serde_json::from_str(&server.join().unwrap()).unwrap();
+        assert_eq!(body["event"], "done");
+        assert_eq!(body["list"], "Movies");
+        assert_eq!(body["item"]["title"], "Heat");
+    }
+}
@@ -0,0 +1,56 @@
+//! Helpers shared by the subcommands which talk to web services
+
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::WatchListError;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+pub(crate) fn network_error(e: ureq::Error) -> WatchListError {
+    WatchListError::NetworkError(e.to_string())
+}
+
+pub(crate) fn agent() -> ureq::Agent {
+    ureq::Agent::config_builder()
+        .timeout_global(Some(TIMEOUT))
+        .user_agent(concat!("wlist/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .into()
+}
+
+/// Sends a GET request with the given headers and parses the JSON response
+pub(crate) fn get_json<T: DeserializeOwned>(
+    url: &str,
+    headers: &[(&str, &str)],
+    query: &[(&str, &str)],
+) -> Result<T, WatchListError> {
+    let mut request = agent().get(url);
+    for (key, value) in headers {
+        request = request.header(*key, *value);
+    }
+    for (key, value) in query {
+        request = request.query(*key, *value);
+    }
+    request
+        .call()
+        .map_err(network_error)?
+        .body_mut()
+        .read_json()
+        .map_err(network_error)
+}
+
+/// Sends a POST request with a JSON body and parses the JSON response
+pub(crate) fn post_json<T: DeserializeOwned>(
+    url: &str,
+    headers: &[(&str, &str)],
+    body: &impl Serialize,
+) -> Result<T, ureq::Error> {
+    let mut request = agent().post(url);
+    for (key, value) in headers {
+        request = request.header(*key, *value);
+    }
+    request.send_json(body)?.body_mut().read_json()
+}
@@ -0,0 +1,557 @@
+use std::collections::HashMap;
+
+use crate::dedup::{self, Similarity};
+use crate::{xml, Item, WatchList, WatchListError};
+
+/// Number of items added, skipped and updated during an import
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    pub added: usize,
+    pub skipped: usize,
+    pub updated: usize,
+}
+
+/// Adds the items to a list, creating the list if it isn't present
+///
+/// Duplicate items are skipped and counted in the report,
+/// unless the imported item is watched in which case the
+/// item already in the list is marked as watched
+pub fn import_items(
+    watchlist: &mut WatchList,
+    title: &str,
+    items: impl IntoIterator<Item = Item>,
+) -> Result<ImportReport, WatchListError> {
+    import(watchlist, title, items, None)
+}
+
+/// Adds the items to a list like [`import_items`], also treating items whose
+/// titles are at least `threshold` similar to another as duplicates
+///
+/// See [`dedup::find_duplicates`] for how titles are compared
+pub fn import_similar(
+    watchlist: &mut WatchList,
+    title: &str,
+    items: impl IntoIterator<Item = Item>,
+    similarity: Similarity,
+    threshold: f64,
+) -> Result<ImportReport, WatchListError> {
+    import(watchlist, title, items, Some((similarity, threshold)))
+}
+
+fn import(
+    watchlist: &mut WatchList,
+    title: &str,
+    items: impl IntoIterator<Item = Item>,
+    similar: Option<(Similarity, f64)>,
+) -> Result<ImportReport, WatchListError> {
+    if !watchlist.contains(title) {
+        watchlist.list_add(title)?;
+    }
+    // The list was just created if it wasn't present
+    let list_items = watchlist.get_mut(title).unwrap();
+    let items: Vec<Item> = items.into_iter().collect();
+
+    // Items already in the list come first so that they're the ones kept
+    let existing = list_items.len();
+    let titles: Vec<&str> = list_items
+        .iter()
+        .chain(&items)
+        .map(|i| i.title.as_str())
+        .collect();
+    let duplicates = match similar {
+        Some((similarity, threshold)) => dedup::find_duplicates(&titles, similarity, threshold),
+        None => {
+            let mut first_of: HashMap<&str, usize> = HashMap::new();
+            titles
+                .iter()
+                .enumerate()
+                .map(|(position, title)| {
+                    let first = *first_of.entry(title).or_insert(position);
+                    (first != position).then_some(first)
+                })
+                .collect()
+        }
+    };
+
+    // Position in the list of the item each title ended up as
+    let mut positions: Vec<usize> = (0..existing).collect();
+    let mut report = ImportReport::default();
+    for (item, duplicate) in items.into_iter().zip(&duplicates[existing..]) {
+        match duplicate {
+            Some(original) => {
+                let position = positions[*original];
+                let original = &mut list_items[position];
+                if item.meta.watched && !original.meta.watched {
+                    original.meta.watched = true;
+                    report.updated += 1;
+                } else {
+                    report.skipped += 1;
+                }
+                positions.push(position);
+            }
+            None => {
+                positions.push(list_items.len());
+                list_items.push(item);
+                report.added += 1;
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Parses a plain text file containing one item per line
+///
+/// Blank lines are ignored
+pub fn parse_text(text: &str) -> Vec<Item> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(Item::from)
+        .collect()
+}
+
+/// Parses a CSV file into items
+///
+/// Items are read from the `item`, `title` or `name` column if the file has a
+/// header row containing one of them, otherwise from the first column
+pub fn parse_csv(text: &str) -> Result<Vec<Item>, WatchListError> {
+    let mut records = csv_records(text)?.into_iter().peekable();
+
+    let mut column = 0;
+    if let Some(header) = records.peek() {
+        let position = header
+            .iter()
+            .position(|h| ["item", "title", "name"].contains(&h.trim().to_lowercase().as_str()));
+        if let Some(p) = position {
+            column = p;
+            records.next();
+        }
+    }
+
+    Ok(records
+        .filter_map(|mut r| (column < r.len()).then(|| r.swap_remove(column)))
+        .map(|f| f.trim().to_string())
+        .filter(|f| !f.is_empty())
+        .map(Item::from)
+        .collect())
+}
+
+/// Splits CSV text into records of fields, handling quoted fields
+pub fn csv_records(text: &str) -> Result<Vec<Vec<String>>, WatchListError> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => in_quotes = false,
+            ('"', false) if field.is_empty() => in_quotes = true,
+            (',', false) => record.push(std::mem::take(&mut field)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                record.push(std::mem::take(&mut field));
+                // Skip blank lines
+                if record.len() > 1 || !record[0].is_empty() {
+                    records.push(std::mem::take(&mut record));
+                } else {
+                    record.clear();
+                }
+            }
+            (c, _) => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(WatchListError::ParseError(
+            "unterminated quoted field".to_string(),
+        ));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Parses a watchlist exported from Letterboxd
+///
+/// The release year and the Letterboxd link of each movie are kept
+pub fn parse_letterboxd(data: &str) -> Result<Vec<Item>, WatchListError> {
+    let mut records = csv_records(data)?.into_iter();
+    let header = records.next().unwrap_or_default();
+    let column = |name: &str| header.iter().position(|h| h.trim() == name);
+
+    let name = column("Name").ok_or(WatchListError::ParseError(
+        "not a Letterboxd export, missing the Name column".to_string(),
+    ))?;
+    let year = column("Year");
+    let uri = column("Letterboxd URI").or_else(|| column("URI"));
+
+    let mut items = Vec::new();
+    for record in records {
+        let field = |i: Option<usize>| {
+            i.and_then(|i| record.get(i))
+                .map(|f| f.trim())
+                .filter(|f| !f.is_empty())
+        };
+        let Some(title) = field(Some(name)) else {
+            continue;
+        };
+
+        let mut item = Item::new(title);
+        if let Some(year) = field(year).and_then(|y| y.parse().ok()) {
+            item = item.with_year(year);
+        }
+        if let Some(uri) = field(uri) {
+            item = item.with_url(uri);
+        }
+        items.push(item);
+    }
+    Ok(items)
+}
+
+/// Parses an anime or manga list exported from MyAnimeList
+///
+/// Dropped entries are left out and completed entries are marked as watched.
+/// Each item links to its page on MyAnimeList
+pub fn parse_mal(data: &str) -> Result<Vec<Item>, WatchListError> {
+    let root = xml::parse(data)?;
+    if root.name != "myanimelist" {
+        return Err(WatchListError::ParseError(
+            "not a MyAnimeList export".to_string(),
+        ));
+    }
+
+    let mut items = Vec::new();
+    for entry in &root.children {
+        let (title, id) = match entry.name.as_str() {
+            "anime" => (
+                entry.child_text("series_title"),
+                entry.child_text("series_animedb_id"),
+            ),
+            "manga" => (
+                entry.child_text("manga_title"),
+                entry.child_text("manga_mangadb_id"),
+            ),
+            _ => continue,
+        };
+        let title = title
+            .filter(|t| !t.is_empty())
+            .ok_or(WatchListError::ParseError(format!(
+                "{} entry without a title",
+                entry.name
+            )))?;
+
+        // Older exports use numeric status codes
+        let watched = match entry.child_text("my_status").unwrap_or_default() {
+            "Dropped" | "4" => continue,
+            "Completed" | "2" => true,
+            _ => false,
+        };
+
+        let mut item = Item::new(title).watched(watched);
+        if let Some(id) = id {
+            item = item.with_url(format!("https://myanimelist.net/{}/{id}", entry.name));
+            // Anime and manga are numbered separately on MyAnimeList
+            if entry.name == "anime" {
+                item = item.with_id("mal", id);
+            }
+        }
+        items.push(item);
+    }
+    Ok(items)
+}
+
+/// Parses a bookmarks file in the Netscape bookmark format exported by browsers
+///
+/// Only the bookmarks inside `folder` (including its subfolders)
+/// are returned if it is specified
+pub fn parse_bookmarks(html: &str, folder: Option<&str>) -> Result<Vec<Item>, WatchListError> {
+    // Folders which the parser is currently inside
+    let mut folders: Vec<String> = Vec::new();
+    // Folder title read from the last <H3> which will be entered on the next <DL>
+    let mut next_folder = String::new();
+    let mut folder_found = folder.is_none();
+    let mut items = Vec::new();
+
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        let end = rest[start..]
+            .find('>')
+            .map(|e| start + e)
+            .ok_or(WatchListError::ParseError("unclosed tag".to_string()))?;
+        let tag = &rest[start + 1..end];
+        rest = &rest[end + 1..];
+
+        let name = tag
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+        match name.as_str() {
+            "H3" => next_folder = decode_entities(text_until_close(rest)),
+            "DL" => {
+                folders.push(std::mem::take(&mut next_folder));
+                if folder.is_some_and(|f| folders.last().unwrap() == f) {
+                    folder_found = true;
+                }
+            }
+            "/DL" => {
+                folders.pop();
+            }
+            "A" => {
+                let in_folder = folder.is_none_or(|f| folders.iter().any(|d| d == f));
+                let title = decode_entities(text_until_close(rest));
+                if !in_folder || title.is_empty() {
+                    continue;
+                }
+                let item = match attribute(tag, "HREF") {
+                    Some(url) => Item::new(title).with_url(decode_entities(url)),
+                    None => Item::new(title),
+                };
+                items.push(item);
+            }
+            _ => {}
+        }
+    }
+
+    if !folder_found {
+        return Err(WatchListError::FolderNotPresent(
+            folder.unwrap_or_default().to_string(),
+        ));
+    }
+    Ok(items)
+}
+
+// Returns the text up to the start of the next tag
+fn text_until_close(text: &str) -> &str {
+    text[..text.find('<').unwrap_or(text.len())].trim()
+}
+
+// Gets the value of a quoted attribute from the inside of a tag
+pub(crate) fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let upper = tag.to_ascii_uppercase();
+    let start = upper.find(&format!(" {}=", name.to_ascii_uppercase()))? + name.len() + 2;
+    let quote = tag[start..]
+        .chars()
+        .next()
+        .filter(|q| *q == '"' || *q == '\'')?;
+    let len = tag[start + 1..].find(quote)?;
+    Some(&tag[start + 1..start + 1 + len])
+}
+
+pub(crate) fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOOKMARKS: &str = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks</H1>
+<DL><p>
+    <DT><H3 ADD_DATE="1675000000">To Watch</H3>
+    <DL><p>
+        <DT><A HREF="https://www.imdb.com/title/tt0133093/" ADD_DATE="1675000000">The Matrix</A>
+        <DT><H3>Anime</H3>
+        <DL><p>
+            <DT><A HREF="https://example.com/?a=1&amp;b=2">Cowboy Bebop</A>
+        </DL><p>
+    </DL><p>
+    <DT><A HREF="https://www.rust-lang.org/">Rust &amp; Friends</A>
+</DL><p>
+"#;
+
+    #[test]
+    fn bookmarks_folder() {
+        let items = parse_bookmarks(BOOKMARKS, Some("To Watch")).unwrap();
+        assert_eq!(
+            items,
+            vec![
+                Item::new("The Matrix").with_url("https://www.imdb.com/title/tt0133093/"),
+                Item::new("Cowboy Bebop").with_url("https://example.com/?a=1&b=2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn bookmarks_all() {
+        let items = parse_bookmarks(BOOKMARKS, None).unwrap();
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[2], "Rust & Friends");
+    }
+
+    #[test]
+    fn bookmarks_errors() {
+        assert_eq!(
+            parse_bookmarks(BOOKMARKS, Some("Music")).err().unwrap(),
+            WatchListError::FolderNotPresent("Music".to_string())
+        );
+    }
+
+    #[test]
+    fn text() {
+        let items = parse_text("Movie 1\n\n  Movie 2  \r\nMovie 3");
+        assert_eq!(items, vec!["Movie 1", "Movie 2", "Movie 3"]);
+    }
+
+    #[test]
+    fn csv() {
+        let data = "list,item\nMovies,\"Movie, 1\"\nMovies,\"The \"\"Movie\"\"\"\n\n";
+        assert_eq!(parse_csv(data).unwrap(), vec!["Movie, 1", "The \"Movie\""]);
+
+        // No header row
+        let data = "Movie 1,2001\nMovie 2,2002";
+        assert_eq!(parse_csv(data).unwrap(), vec!["Movie 1", "Movie 2"]);
+    }
+
+    #[test]
+    fn csv_errors() {
+        assert_eq!(
+            parse_csv("title\n\"Movie 1").err().unwrap(),
+            WatchListError::ParseError("unterminated quoted field".to_string())
+        );
+    }
+
+    #[test]
+    fn import_skips_duplicates() {
+        let mut watchlist = WatchList::new();
+        let items = vec![Item::new("A"), Item::new("B"), Item::new("A")];
+        let report = import_items(&mut watchlist, "Movies", items).unwrap();
+        assert_eq!(
+            report,
+            ImportReport {
+                added: 2,
+                skipped: 1,
+                updated: 0,
+            }
+        );
+        assert_eq!(watchlist["Movies"], vec!["A", "B"]);
+
+        let items = vec![Item::new("A").watched(true), Item::new("B")];
+        let report = import_items(&mut watchlist, "Movies", items).unwrap();
+        assert_eq!(report.updated, 1);
+        assert!(watchlist["Movies"][0].meta.watched);
+    }
+
+    #[test]
+    fn import_skips_similar() {
+        let mut watchlist = WatchList::new();
+        watchlist.insert("Movies".to_string(), vec!["Spider-Man: Homecoming".into()]);
+        let items = vec![
+            Item::new("spiderman homecoming").watched(true),
+            Item::new("The Thing"),
+            Item::new("The Things"),
+            Item::new("Alien"),
+        ];
+        let report = import_similar(
+            &mut watchlist,
+            "Movies",
+            items,
+            Similarity::Levenshtein,
+            0.9,
+        )
+        .unwrap();
+        assert_eq!(
+            report,
+            ImportReport {
+                added: 2,
+                skipped: 1,
+                updated: 1,
+            }
+        );
+        assert_eq!(
+            watchlist["Movies"],
+            vec!["Spider-Man: Homecoming", "The Thing", "Alien"]
+        );
+        assert!(watchlist["Movies"][0].meta.watched);
+    }
+
+    #[test]
+    fn letterboxd() {
+        let data = "Date,Name,Year,Letterboxd URI\n\
+            2023-01-02,Dune,2021,https://boxd.it/hTha\n\
+            2023-01-03,\"Crouching Tiger, Hidden Dragon\",2000,https://boxd.it/1Sa2\n\
+            2023-01-04,Untitled,,\n";
+        assert_eq!(
+            parse_letterboxd(data).unwrap(),
+            vec![
+                Item::new("Dune")
+                    .with_year(2021)
+                    .with_url("https://boxd.it/hTha"),
+                Item::new("Crouching Tiger, Hidden Dragon")
+                    .with_year(2000)
+                    .with_url("https://boxd.it/1Sa2"),
+                Item::new("Untitled"),
+            ]
+        );
+    }
+
+    #[test]
+    fn letterboxd_errors() {
+        assert_eq!(
+            parse_letterboxd("Title,Year\nDune,2021").err().unwrap(),
+            WatchListError::ParseError(
+                "not a Letterboxd export, missing the Name column".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn mal() {
+        let data = r#"<?xml version="1.0" encoding="UTF-8" ?>
+<myanimelist>
+    <myinfo>
+        <user_name>user</user_name>
+    </myinfo>
+    <anime>
+        <series_animedb_id>1</series_animedb_id>
+        <series_title><![CDATA[Cowboy Bebop]]></series_title>
+        <my_status>Plan to Watch</my_status>
+    </anime>
+    <anime>
+        <series_animedb_id>5114</series_animedb_id>
+        <series_title><![CDATA[Fullmetal Alchemist: Brotherhood]]></series_title>
+        <my_status>Completed</my_status>
+    </anime>
+    <anime>
+        <series_animedb_id>20</series_animedb_id>
+        <series_title><![CDATA[Naruto]]></series_title>
+        <my_status>Dropped</my_status>
+    </anime>
+</myanimelist>"#;
+        assert_eq!(
+            parse_mal(data).unwrap(),
+            vec![
+                Item::new("Cowboy Bebop")
+                    .with_url("https://myanimelist.net/anime/1")
+                    .with_id("mal", "1"),
+                Item::new("Fullmetal Alchemist: Brotherhood")
+                    .with_url("https://myanimelist.net/anime/5114")
+                    .with_id("mal", "5114")
+                    .watched(true),
+            ]
+        );
+    }
+
+    #[test]
+    fn mal_errors() {
+        assert_eq!(
+            parse_mal("<bookmarks></bookmarks>").err().unwrap(),
+            WatchListError::ParseError("not a MyAnimeList export".to_string())
+        );
+    }
+}
@@ -0,0 +1,279 @@
+//! Index of the trigrams in item titles which speeds up searching large lists
+//!
+//! Every item containing a search also contains each of its trigrams, so only the
+//! items having all of them need to be checked. The index is kept in a file next
+//! to the data file and each list is reindexed whenever it changes
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{encryption, Item, WatchList, WatchListError};
+
+/// Positions of the items in a list containing each trigram
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct ListIndex {
+    /// Hash of the titles of the items when the list was indexed, to notice if it was
+    /// changed elsewhere. Indexes saved before it was kept have none, so they're rebuilt
+    #[serde(default)]
+    titles: u64,
+    trigrams: HashMap<String, Vec<u32>>,
+}
+
+// Sequences of three characters in the text, without duplicates
+fn trigrams(text: &str) -> HashSet<String> {
+    let chars: Vec<char> = text.chars().collect();
+    chars.windows(3).map(|w| w.iter().collect()).collect()
+}
+
+// FNV-1a hash of the titles in order, which is the same on every machine and version so
+// it can be saved. Each title ends with a byte UTF-8 never uses, so moving the boundary
+// between two titles changes the hash
+fn titles_hash(items: &[Item]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in items
+        .iter()
+        .flat_map(|i| i.title.bytes().chain(std::iter::once(0xff)))
+    {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+impl ListIndex {
+    fn build(items: &[Item]) -> Self {
+        // Lowercased the same way as when matching, see `Item::matches`
+        let item_trigrams = crate::par_map(items, |i| trigrams(&i.title.to_ascii_lowercase()));
+        let mut trigrams_of: HashMap<String, Vec<u32>> = HashMap::new();
+        for (position, item_trigrams) in item_trigrams.into_iter().enumerate() {
+            for trigram in item_trigrams {
+                trigrams_of
+                    .entry(trigram)
+                    .or_default()
+                    .push(position as u32);
+            }
+        }
+        ListIndex {
+            titles: titles_hash(items),
+            trigrams: trigrams_of,
+        }
+    }
+
+    // Whether the index is of these items, rather than the ones the list had before it
+    // was changed elsewhere
+    fn indexes(&self, items: &[Item]) -> bool {
+        self.titles == titles_hash(items)
+    }
+
+    // Positions of the items having every trigram, in order
+    fn candidates(&self, trigrams: &HashSet<String>) -> Vec<u32> {
+        let mut lists: Vec<&Vec<u32>> = Vec::with_capacity(trigrams.len());
+        for trigram in trigrams {
+            match self.trigrams.get(trigram) {
+                Some(positions) => lists.push(positions),
+                None => return Vec::new(),
+            }
+        }
+        // Starting with the rarest trigram keeps the intersection small
+        lists.sort_by_key(|l| l.len());
+        let Some((first, rest)) = lists.split_first() else {
+            return Vec::new();
+        };
+        first
+            .iter()
+            .copied()
+            .filter(|p| rest.iter().all(|l| l.binary_search(p).is_ok()))
+            .collect()
+    }
+}
+
+/// Search index of every list, saved to a file
+#[derive(Debug, Default)]
+pub struct SearchIndex {
+    file_path: PathBuf,
+    path: PathBuf,
+    lists: HashMap<String, ListIndex>,
+}
+
+impl SearchIndex {
+    /// Reads the index next to the data file, starting with an empty one if there isn't any
+    pub fn from_file(file_path: &Path) -> Result<Self, WatchListError> {
+        let path = crate::sidecar_path(file_path, "index");
+        let lists = match encryption::read_sidecar(file_path, &path)? {
+            Some(data) => serde_json::from_str(&data)
+                .map_err(|e| WatchListError::ParseError(e.to_string()))?,
+            None => HashMap::new(),
+        };
+        Ok(SearchIndex {
+            file_path: file_path.to_path_buf(),
+            path,
+            lists,
+        })
+    }
+
+    pub fn to_file(&self) -> Result<(), WatchListError> {
+        let data =
+            serde_json::to_string(&self.lists).map_err(|e| WatchListError::IOError(e.into()))?;
+        encryption::write_sidecar(&self.file_path, &self.path, data.as_bytes())
+    }
+
+    /// Indexes every list from scratch
+    pub fn rebuild(&mut self, watchlist: &WatchList) {
+        self.lists = watchlist
+            .iter()
+            .map(|(title, items)| (title.clone(), ListIndex::build(items)))
+            .collect();
+    }
+
+    /// Reindexes the lists which changed between `before` and `after`, along with
+    /// any which haven't been indexed yet or were changed elsewhere since
+    ///
+    /// Returns the number of lists which were reindexed
+    pub fn update(&mut self, before: &WatchList, after: &WatchList) -> usize {
+        self.lists.retain(|title, _| after.contains(title));
+        let mut count = 0;
+        for (title, items) in after {
+            let stale = match self.lists.get(title) {
+                Some(index) => before.get(title) != Some(items) || !index.indexes(items),
+                None => true,
+            };
+            if stale {
+                self.lists.insert(title.clone(), ListIndex::build(items));
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Finds the items in a list whose titles contain the search, ignoring case
+    ///
+    /// Gives the same results as [`WatchList::list_search`], which it falls back to
+    /// for searches shorter than three characters and lists missing from the index
+    pub fn search<'a>(
+        &self,
+        watchlist: &'a WatchList,
+        title: &str,
+        search: &str,
+    ) -> Result<Vec<&'a Item>, WatchListError> {
+        let items = watchlist.get(title).ok_or(WatchListError::TitleNotPresent(
+            title.to_string(),
+            Vec::new(),
+        ))?;
+        let search_trigrams = trigrams(&search.to_lowercase());
+        match self.lists.get(title) {
+            Some(index) if index.indexes(items) && !search_trigrams.is_empty() => Ok(index
+                .candidates(&search_trigrams)
+                .into_iter()
+                .filter_map(|p| items.get(p as usize))
+                .filter(|i| i.matches(search))
+                .collect()),
+            _ => watchlist.list_search(title, search),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::watchlists;
+    use proptest::prelude::*;
+
+    fn setup() -> WatchList {
+        let mut watchlist = WatchList::new();
+        watchlist.insert(
+            "Movies".to_string(),
+            vec![
+                "Alien".into(),
+                "Aliens".into(),
+                "Dune".into(),
+                "ALIEN 3".into(),
+            ],
+        );
+        watchlist.insert("Anime".to_string(), vec!["Akira".into()]);
+        watchlist
+    }
+
+    #[test]
+    fn searching() {
+        let watchlist = setup();
+        let mut index = SearchIndex::default();
+        index.rebuild(&watchlist);
+        assert_eq!(
+            index.search(&watchlist, "Movies", "alien").unwrap(),
+            vec!["Alien", "Aliens", "ALIEN 3"]
+        );
+        assert_eq!(
+            index.search(&watchlist, "Movies", "ens").unwrap(),
+            vec!["Aliens"]
+        );
+        assert!(index
+            .search(&watchlist, "Movies", "xyz")
+            .unwrap()
+            .is_empty());
+        // Short searches check every item
+        assert_eq!(
+            index.search(&watchlist, "Movies", "un").unwrap(),
+            vec!["Dune"]
+        );
+        assert_eq!(
+            index.search(&watchlist, "TV", "dune").unwrap_err(),
+            WatchListError::TitleNotPresent("TV".to_string(), Vec::new())
+        );
+    }
+
+    #[test]
+    fn updating() {
+        let before = setup();
+        let mut index = SearchIndex::default();
+        assert_eq!(index.update(&before, &before), 2);
+
+        let mut after = before.clone();
+        after
+            .item_rename("Movies", "Dune", "Dune: Part Two")
+            .unwrap();
+        after.list_remove("Anime").unwrap();
+        assert_eq!(index.update(&before, &after), 1);
+        assert!(!index.lists.contains_key("Anime"));
+        assert_eq!(
+            index.search(&after, "Movies", "part").unwrap(),
+            vec!["Dune: Part Two"]
+        );
+
+        // Lists changed elsewhere are searched without the index
+        after.get_mut("Movies").unwrap().push("Part of Me".into());
+        assert_eq!(
+            index.search(&after, "Movies", "part").unwrap(),
+            vec!["Dune: Part Two", "Part of Me"]
+        );
+
+        // Even when the number of items stays the same, and they're reindexed next time
+        index.rebuild(&after);
+        let mut renamed = after.clone();
+        renamed.get_mut("Movies").unwrap()[0] = "Prometheus".into();
+        assert_eq!(
+            index.search(&renamed, "Movies", "prom").unwrap(),
+            vec!["Prometheus"]
+        );
+        assert_eq!(index.update(&renamed, &renamed), 1);
+        assert_eq!(
+            index.search(&renamed, "Movies", "prom").unwrap(),
+            vec!["Prometheus"]
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn same_as_scanning(watchlist in watchlists(), search in "[a-zA-Z ]{0,4}") {
+            let mut index = SearchIndex::default();
+            index.rebuild(&watchlist);
+            for title in watchlist.titles() {
+                prop_assert_eq!(
+                    index.search(&watchlist, title, &search).unwrap(),
+                    watchlist.list_search(title, &search).unwrap()
+                );
+            }
+        }
+    }
+}
@@ -1,271 +1,3132 @@
-use std::collections::HashMap;
+//! Library behind the `wl` watchlist manager
+//!
+//! Tools built on this crate should import from [`prelude`], which is the stable part
+//! of the API and only changes in a new major version. The other public modules exist
+//! for the `wl` binary, and the ones hidden from these docs (the search index, memory
+//! mapped reading and size limits on files) may change in any release.
+
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Display;
-use std::fs::{read_to_string, File};
-use std::io::{self, Write};
-use std::path::Path;
+#[cfg(feature = "fs")]
+use std::fs::File;
+#[cfg(feature = "fs")]
+use std::io::BufWriter;
+use std::io::{self, IsTerminal, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
-use colored::Colorize;
+use chrono::NaiveDate;
+use dates::Timestamp;
+use names::Names;
+use progress::Progress;
 use rand::seq::SliceRandom;
+use rand::Rng;
+use regex::RegexBuilder;
+use serde::{Deserialize, Serialize};
+use status::Status;
+
+#[cfg(feature = "net")]
+pub mod anilist;
+#[cfg(feature = "fs")]
+pub mod archive;
+pub mod check;
+#[cfg(feature = "clipboard")]
+pub mod clipboard;
+#[cfg(feature = "fs")]
+pub mod config;
+#[cfg(all(unix, feature = "fs"))]
+pub mod daemon;
+pub mod dates;
+pub mod dedup;
+pub mod diff;
+pub mod due;
+#[cfg(feature = "fs")]
+pub mod editor;
+#[cfg(feature = "fs")]
+pub mod encryption;
+pub mod export;
+pub mod fields;
+pub mod fold;
+pub mod forecast;
+pub mod fuzzy;
+#[cfg(feature = "fs")]
+pub mod git;
+pub mod grab;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+#[cfg(feature = "fs")]
+pub mod history;
+#[cfg(feature = "fs")]
+pub mod hooks;
+#[cfg(feature = "net")]
+mod http;
+pub mod import;
+#[cfg(feature = "fs")]
+#[doc(hidden)]
+pub mod index;
+#[doc(hidden)]
+pub mod limits;
+pub mod links;
+#[cfg(feature = "fs")]
+pub mod lock;
+pub mod merge;
+#[cfg(feature = "mmap")]
+#[doc(hidden)]
+pub mod mmap;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+mod names;
+pub mod notify;
+pub mod output;
+#[cfg(feature = "tui")]
+pub mod picker;
+#[cfg(feature = "fs")]
+pub mod picks;
+pub mod prelude;
+#[cfg(feature = "fs")]
+pub mod profiles;
+pub mod progress;
+pub mod query;
+pub mod recent;
+#[cfg(feature = "net")]
+pub mod remote;
+pub mod render;
+#[cfg(feature = "fs")]
+pub mod review;
+pub mod schema;
+pub mod selection;
+#[cfg(feature = "fs")]
+pub mod serve;
+#[cfg(feature = "fs")]
+pub mod setup;
+pub mod shell;
+#[cfg(feature = "fs")]
+pub mod stage;
+pub mod stats;
+pub mod status;
+pub mod suggest;
+#[cfg(feature = "fs")]
+pub mod sync;
+#[cfg(feature = "test-support")]
+pub mod testing;
+pub mod theme;
+#[cfg(feature = "tmdb")]
+pub mod tmdb;
+#[cfg(feature = "fs")]
+pub mod tmp;
+#[cfg(feature = "net")]
+pub mod trakt;
+#[cfg(feature = "fs")]
+pub mod trash;
+#[cfg(feature = "tui")]
+pub mod tui;
+#[cfg(feature = "fs")]
+pub mod undo;
+pub mod validation;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "fs")]
+pub mod workspace;
+pub mod xml;
 
 #[derive(Debug)]
 pub enum WatchListError {
     NoTitles,
     EmptyList(String),
     TitleAlreadyPresent(String),
-    TitleNotPresent(String),
+    /// Title of the list and the titles of lists it may have been meant to be
+    TitleNotPresent(String, Vec<String>),
     ItemAlreadyPresent(String, String),
     ItemToRemoveNotPresent(String),
+    FolderNotPresent(String),
+    ParseError(String),
+    NetworkError(String),
+    InvalidDate(String),
+    InvalidRating(u8),
+    InvalidProgress(String),
+    UnknownField(String),
+    LimitExceeded(String),
+    ProfileAlreadyPresent(String),
+    ProfileNotPresent(String),
+    InvalidProfile(String),
+    InvalidQuery(String),
+    InvalidRuntime(String),
+    InvalidPattern(String),
+    /// Saving needs the data file upgraded to this version, which wasn't agreed to
+    UpgradeRequired(u32),
+    /// Title which isn't allowed and the reason why
+    ValidationFailed(String, String),
+    WorkspaceNotPresent(String),
+    /// Reason an encrypted data file couldn't be read or written
+    EncryptionError(String),
+    /// What git printed when it failed
+    GitError(String),
+    /// What changed since the last push or pull, which pushing or pulling would overwrite
+    RemoteChanged(String),
+    NoMatches(String),
+    /// Question which couldn't be asked, as there's no one to answer it
+    NotInteractive(String),
+    InvalidUrl(String),
+    /// Title of the item without a link to open
+    NoUrl(String),
     IOError(io::Error),
 }
 
-// Rename to WatchList and delete above struct when done
-pub type WatchList = HashMap<String, Vec<String>>;
+/// Result of the functions of the library, failing with a [`WatchListError`]
+pub type Result<T, E = WatchListError> = std::result::Result<T, E>;
+
+// io::Error doesn't implement PartialEq so IO errors are compared by kind
+impl PartialEq for WatchListError {
+    fn eq(&self, other: &Self) -> bool {
+        use WatchListError::*;
+        match (self, other) {
+            (NoTitles, NoTitles) => true,
+            (EmptyList(a), EmptyList(b)) => a == b,
+            (TitleAlreadyPresent(a), TitleAlreadyPresent(b)) => a == b,
+            (TitleNotPresent(a1, a2), TitleNotPresent(b1, b2)) => a1 == b1 && a2 == b2,
+            (ItemAlreadyPresent(a1, a2), ItemAlreadyPresent(b1, b2)) => a1 == b1 && a2 == b2,
+            (ItemToRemoveNotPresent(a), ItemToRemoveNotPresent(b)) => a == b,
+            (FolderNotPresent(a), FolderNotPresent(b)) => a == b,
+            (ParseError(a), ParseError(b)) => a == b,
+            (NetworkError(a), NetworkError(b)) => a == b,
+            (InvalidDate(a), InvalidDate(b)) => a == b,
+            (InvalidRating(a), InvalidRating(b)) => a == b,
+            (InvalidProgress(a), InvalidProgress(b)) => a == b,
+            (UnknownField(a), UnknownField(b)) => a == b,
+            (LimitExceeded(a), LimitExceeded(b)) => a == b,
+            (ProfileAlreadyPresent(a), ProfileAlreadyPresent(b)) => a == b,
+            (ProfileNotPresent(a), ProfileNotPresent(b)) => a == b,
+            (InvalidProfile(a), InvalidProfile(b)) => a == b,
+            (InvalidQuery(a), InvalidQuery(b)) => a == b,
+            (InvalidRuntime(a), InvalidRuntime(b)) => a == b,
+            (InvalidPattern(a), InvalidPattern(b)) => a == b,
+            (UpgradeRequired(a), UpgradeRequired(b)) => a == b,
+            (ValidationFailed(a1, a2), ValidationFailed(b1, b2)) => a1 == b1 && a2 == b2,
+            (WorkspaceNotPresent(a), WorkspaceNotPresent(b)) => a == b,
+            (EncryptionError(a), EncryptionError(b)) => a == b,
+            (GitError(a), GitError(b)) => a == b,
+            (RemoteChanged(a), RemoteChanged(b)) => a == b,
+            (NoMatches(a), NoMatches(b)) => a == b,
+            (NotInteractive(a), NotInteractive(b)) => a == b,
+            (InvalidUrl(a), InvalidUrl(b)) => a == b,
+            (NoUrl(a), NoUrl(b)) => a == b,
+            (IOError(a), IOError(b)) => a.kind() == b.kind(),
+            _ => false,
+        }
+    }
+}
+
+impl WatchListError {
+    /// Gets the exit code for the error, different for each kind so scripts can tell them apart
+    ///
+    /// Codes start from 3 as 1 is used for IO errors and 2 for invalid arguments
+    pub fn exit_code(&self) -> u8 {
+        use WatchListError::*;
+        match self {
+            IOError(_) => 1,
+            NoMatches(_) => 3,
+            NoTitles => 4,
+            EmptyList(_) => 5,
+            TitleAlreadyPresent(_) => 6,
+            TitleNotPresent(_, _) => 7,
+            ItemAlreadyPresent(_, _) => 8,
+            ItemToRemoveNotPresent(_) => 9,
+            FolderNotPresent(_) => 10,
+            ParseError(_) => 11,
+            NetworkError(_) => 12,
+            InvalidDate(_) => 13,
+            InvalidRating(_) => 14,
+            InvalidProgress(_) => 15,
+            UnknownField(_) => 16,
+            LimitExceeded(_) => 17,
+            ProfileAlreadyPresent(_) => 18,
+            ProfileNotPresent(_) => 19,
+            InvalidProfile(_) => 20,
+            InvalidQuery(_) => 21,
+            InvalidRuntime(_) => 22,
+            InvalidPattern(_) => 23,
+            UpgradeRequired(_) => 24,
+            ValidationFailed(_, _) => 25,
+            WorkspaceNotPresent(_) => 26,
+            EncryptionError(_) => 27,
+            GitError(_) => 28,
+            RemoteChanged(_) => 29,
+            NotInteractive(_) => 30,
+            InvalidUrl(_) => 31,
+            NoUrl(_) => 32,
+        }
+    }
+
+    /// Fills in the lists a list which wasn't found may have been meant to be
+    pub fn with_suggestions(self, watchlist: &WatchList) -> Self {
+        match self {
+            WatchListError::TitleNotPresent(title, _) => {
+                let suggestions = suggest::similar_titles(watchlist.titles(), &title);
+                WatchListError::TitleNotPresent(title, suggestions)
+            }
+            e => e,
+        }
+    }
+}
+
+/// Describes the error in a way that can be shown to the user
+impl Display for WatchListError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use WatchListError::*;
+        match self {
+            NoTitles => write!(f, "No Lists Found!\nCreate a new one using the `new` subcommand. See `wl --help` for more info"),
+            EmptyList(t) => write!(f, "No Items Added to List - {t}!"),
+            TitleNotPresent(t, suggestions) if suggestions.is_empty() => write!(f, "No such list - {t}!"),
+            TitleNotPresent(t, suggestions) => {
+                write!(f, "No such list - {t}! Did you mean {}?", suggestions.join(" or "))
+            }
+            ItemAlreadyPresent(i, t) => write!(f, "{i} is already in the list - {t}!"),
+            ItemToRemoveNotPresent(i) => write!(f, "{i} not in the list!"),
+            TitleAlreadyPresent(t) => write!(f, "A list called {t} already exists"),
+            FolderNotPresent(folder) => write!(f, "No such folder - {folder}!"),
+            ParseError(e) => write!(f, "Failed to parse file: {e}"),
+            NetworkError(e) => write!(f, "Network request failed: {e}"),
+            InvalidDate(d) => write!(f, "Couldn't understand the date - {d}!"),
+            InvalidRating(r) => write!(f, "Ratings must be from 1 to 10, not {r}!"),
+            InvalidProgress(p) => write!(f, "Couldn't understand the progress - {p}, use something like s3e5 or ch120!"),
+            UnknownField(field) => {
+                let fields: Vec<&str> = fields::FIELDS.iter().map(|f| f.name).collect();
+                write!(f, "No such field - {field}! Choose from {}", fields.join(", "))
+            }
+            LimitExceeded(e) => write!(f, "Can't use the file, {e}!"),
+            ProfileAlreadyPresent(p) => write!(f, "A profile called {p} already exists"),
+            WorkspaceNotPresent(w) => write!(f, "No such workspace - {w}!\nAdd its data files to `workspaces` in the config file"),
+            EncryptionError(e) => write!(f, "Can't use the encrypted file, {e}!"),
+            GitError(e) => write!(f, "Git failed: {e}"),
+            RemoteChanged(what) => write!(f, "{what} since the last push or pull!\nRun the command again with --force to overwrite it"),
+            ProfileNotPresent(p) => write!(f, "No such profile - {p}!\nCreate it using `wl profile new {p}`"),
+            NoMatches(m) if m.is_empty() => write!(f, "No Matches!"),
+            NoMatches(m) => write!(f, "No Matches for '{m}'!"),
+            InvalidQuery(q) => write!(f, "Couldn't understand the filter, {q}!"),
+            InvalidPattern(e) => write!(f, "Invalid regular expression\n{e}"),
+            ValidationFailed(t, reason) => write!(f, "{t} isn't allowed, {reason}!"),
+            UpgradeRequired(v) => write!(f, "The Lists weren't saved as saving them upgrades the data file to version {v}\nRun the command again with --upgrade to upgrade it"),
+            InvalidRuntime(r) => write!(f, "Couldn't understand the runtime - {r}, use something like 1h30m or 90m!"),
+            InvalidProfile(p) => write!(f, "Profile names can only have letters, digits, - and _, not '{p}'!"),
+            NotInteractive(q) => write!(f, "Couldn't ask '{q}' as stdin isn't a terminal or --yes was given!\nWith --yes, questions with a default answer take it and confirmations are agreed to"),
+            InvalidUrl(u) => write!(f, "Links have to start with http:// or https://, not '{u}'!"),
+            NoUrl(i) => write!(f, "'{i}' has no link to open!\nSet one using `wl set-url`"),
+            IOError(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for WatchListError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WatchListError::IOError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for WatchListError {
+    fn from(error: io::Error) -> Self {
+        WatchListError::IOError(error)
+    }
+}
+
+// Files which aren't valid JSON, or are too large to read, can't be parsed
+pub(crate) fn read_error(error: io::Error) -> WatchListError {
+    match error.kind() {
+        io::ErrorKind::InvalidData => WatchListError::ParseError(error.to_string()),
+        _ => WatchListError::IOError(error),
+    }
+}
+
+/// An entry in a list
+///
+/// Items without any metadata are stored as plain strings
+/// so that older files can still be read
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "ItemRepr", into = "ItemRepr")]
+pub struct Item {
+    pub title: String,
+    pub meta: Metadata,
+}
+
+/// Optional information attached to an item
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Metadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub year: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub watched: bool,
+    /// Given up on without finishing it, see [`status::Status`]
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub dropped: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poster: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Score from 1 to 10
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<u8>,
+    /// Last episode or chapter reached
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress: Option<Progress>,
+    /// Identifiers on other services by service name, like `imdb` to `tt0133093`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub ids: BTreeMap<String, String>,
+    /// Length in minutes, of a movie or of all episodes of a show
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runtime: Option<u32>,
+    /// Shown before the other items of its list, in any order
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub pinned: bool,
+    /// When the item was added, unknown for items added before this was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub added_at: Option<Timestamp>,
+}
+
+fn is_false(b: &bool) -> bool {
+    !b
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ItemRepr {
+    Title(String),
+    Full {
+        title: String,
+        #[serde(flatten)]
+        meta: Metadata,
+    },
+}
+
+impl From<ItemRepr> for Item {
+    fn from(repr: ItemRepr) -> Self {
+        match repr {
+            ItemRepr::Title(title) => Item::new(title),
+            ItemRepr::Full { title, meta } => Item { title, meta },
+        }
+    }
+}
+
+impl From<Item> for ItemRepr {
+    fn from(item: Item) -> Self {
+        if item.meta == Metadata::default() {
+            ItemRepr::Title(item.title)
+        } else {
+            ItemRepr::Full {
+                title: item.title,
+                meta: item.meta,
+            }
+        }
+    }
+}
+
+impl Item {
+    pub fn new(title: impl Into<String>) -> Self {
+        Item {
+            title: title.into(),
+            meta: Metadata::default(),
+        }
+    }
+
+    pub fn with_url(mut self, url: impl Into<String>) -> Self {
+        self.meta.url = Some(url.into());
+        self
+    }
+
+    pub fn with_year(mut self, year: u16) -> Self {
+        self.meta.year = Some(year);
+        self
+    }
+
+    pub fn with_due(mut self, due: NaiveDate) -> Self {
+        self.meta.due = Some(due);
+        self
+    }
+
+    pub fn with_added_at(mut self, added_at: Timestamp) -> Self {
+        self.meta.added_at = Some(added_at);
+        self
+    }
+
+    pub fn watched(mut self, watched: bool) -> Self {
+        self.meta.watched = watched;
+        self
+    }
+
+    pub fn with_poster(mut self, poster: impl Into<String>) -> Self {
+        self.meta.poster = Some(poster.into());
+        self
+    }
+
+    pub fn with_progress(mut self, progress: Progress) -> Self {
+        self.meta.progress = Some(progress);
+        self
+    }
+
+    /// Sets the runtime of the item in minutes
+    pub fn with_runtime(mut self, minutes: u32) -> Self {
+        self.meta.runtime = Some(minutes);
+        self
+    }
+
+    /// Sets the identifier of the item on a service, like `tmdb`
+    pub fn with_id(mut self, service: &str, id: impl Into<String>) -> Self {
+        self.meta.ids.insert(service.to_lowercase(), id.into());
+        self
+    }
+
+    /// Gets the identifier of the item on a service
+    pub fn id(&self, service: &str) -> Option<&str> {
+        self.meta
+            .ids
+            .get(&service.to_lowercase())
+            .map(String::as_str)
+    }
+
+    /// Checks if two items are the same by their identifiers on the services
+    /// both have one for, `None` if there are no such services
+    pub fn same_ids(&self, other: &Item) -> Option<bool> {
+        let mut shared = self
+            .meta
+            .ids
+            .iter()
+            .filter_map(|(service, id)| Some(other.meta.ids.get(service)? == id))
+            .peekable();
+        shared.peek()?;
+        Some(shared.all(|same| same))
+    }
+
+    /// Adds tags which the item doesn't already have
+    pub fn with_tags<T: AsRef<str>>(mut self, tags: &[T]) -> Self {
+        for tag in tags {
+            let tag = tag.as_ref().trim();
+            if !tag.is_empty() && !self.has_tags(&[tag]) {
+                self.meta.tags.push(tag.to_string());
+            }
+        }
+        self
+    }
+
+    /// Checks if the title contains the search, ignoring case
+    pub fn matches(&self, search: &str) -> bool {
+        self.title
+            .to_ascii_lowercase()
+            .contains(&search.to_lowercase())
+    }
+
+    /// Byte ranges of the title containing the search, found like [`matches`](Item::matches)
+    /// finds them, none if the title doesn't contain it
+    pub fn match_spans(&self, search: &str) -> Vec<Range<usize>> {
+        let search = search.to_lowercase();
+        if search.is_empty() {
+            return Vec::new();
+        }
+        // Only ASCII letters change case, so the ranges are the same in the title
+        self.title
+            .to_ascii_lowercase()
+            .match_indices(&search)
+            .map(|(start, matched)| start..start + matched.len())
+            .collect()
+    }
+
+    /// Checks if the item has all of the tags, ignoring case
+    pub fn has_tags<T: AsRef<str>>(&self, tags: &[T]) -> bool {
+        tags.iter().all(|tag| {
+            self.meta
+                .tags
+                .iter()
+                .any(|t| t.to_lowercase() == tag.as_ref().trim().to_lowercase())
+        })
+    }
+}
+
+impl From<&str> for Item {
+    fn from(title: &str) -> Self {
+        Item::new(title)
+    }
+}
+
+impl From<&String> for Item {
+    fn from(title: &String) -> Self {
+        Item::new(title.as_str())
+    }
+}
+
+impl From<String> for Item {
+    fn from(title: String) -> Self {
+        Item::new(title)
+    }
+}
+
+impl Display for Item {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.title.fmt(f)
+    }
+}
+
+impl PartialEq<str> for Item {
+    fn eq(&self, other: &str) -> bool {
+        self.title == other
+    }
+}
+
+impl PartialEq<&str> for Item {
+    fn eq(&self, other: &&str) -> bool {
+        self.title == *other
+    }
+}
+
+impl PartialEq<String> for Item {
+    fn eq(&self, other: &String) -> bool {
+        &self.title == other
+    }
+}
+
+/// Item found by a search, along with the parts of its title which matched it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match<'a> {
+    pub item: &'a Item,
+    /// Byte ranges of the title which matched, in order
+    pub spans: Vec<Range<usize>>,
+}
+
+/// Orders in which the items of a list can be shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortBy {
+    /// The order the items were added in
+    #[default]
+    Added,
+    /// By title, ignoring case
+    Alpha,
+    /// Highest rated first, followed by unrated items
+    Rating,
+    /// Shuffled, in a different order each time
+    Random,
+}
+
+impl SortBy {
+    /// Sorts anything holding an item, like items paired with their list titles
+    ///
+    /// Sorting is stable so items which compare the same stay in the order they were in.
+    /// Pinned items come before the others in every order
+    pub fn sort<T>(self, items: &mut [T], item: impl Fn(&T) -> &Item) {
+        self.sort_with_rng(items, item, &mut rand::thread_rng());
+    }
+
+    /// Sorts like [`sort`](SortBy::sort), shuffling with `rng`, which can be seeded to
+    /// shuffle the same way every time
+    pub fn sort_with_rng<T>(self, items: &mut [T], item: impl Fn(&T) -> &Item, rng: &mut impl Rng) {
+        match self {
+            SortBy::Added => {}
+            SortBy::Alpha => items.sort_by_cached_key(|i| item(i).title.to_lowercase()),
+            SortBy::Rating => items.sort_by_key(|i| std::cmp::Reverse(item(i).meta.rating)),
+            SortBy::Random => items.shuffle(rng),
+        }
+        items.sort_by_key(|i| !item(i).meta.pinned);
+    }
+}
+
+/// Settings of a list, saved with it in the data file from [version 4](schema) on, so they
+/// follow the list when it's renamed or copied and come back with it when it's restored
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListSettings {
+    /// Left out of the lists random picks come from when no list is given, like a list of
+    /// finished items
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub no_random: bool,
+    /// Order the items are shown in unless another is asked for
+    #[serde(default, skip_serializing_if = "is_added")]
+    pub sort: SortBy,
+    /// Short names which can be used instead of the title, each only for one list
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+}
+
+fn is_added(sort: &SortBy) -> bool {
+    *sort == SortBy::Added
+}
+
+impl ListSettings {
+    pub fn is_default(&self) -> bool {
+        *self == ListSettings::default()
+    }
+}
+
+/// How items being added are checked against the items already in the list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicatePolicy {
+    /// Titles have to be exactly the same to be duplicates
+    #[default]
+    Exact,
+    /// Titles which only differ in case or spacing are duplicates, like "Dune" and " dune"
+    Normalized,
+    /// Titles which only differ in case, spacing or accents are duplicates, like "Pokémon"
+    /// and "pokemon"
+    Folded,
+    /// Add items even if the list already has them
+    Allow,
+}
+
+impl DuplicatePolicy {
+    /// Checks if two titles count as duplicates
+    pub fn same(self, a: &str, b: &str) -> bool {
+        match self {
+            DuplicatePolicy::Exact => a == b,
+            DuplicatePolicy::Normalized => normalize(a) == normalize(b),
+            DuplicatePolicy::Folded => DuplicatePolicy::key(a) == DuplicatePolicy::key(b),
+            DuplicatePolicy::Allow => false,
+        }
+    }
+
+    // Titles which are duplicates under any of the policies have the same key
+    pub(crate) fn key(title: &str) -> String {
+        fold::fold(&normalize(title))
+    }
+}
+
+// Spacing inside titles is collapsed too, so "Star  Wars" matches "Star Wars"
+fn normalize(title: &str) -> String {
+    title
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Lists of items by their titles, saved in the data file as a JSON object of lists, in
+/// one of the [versions](schema) of the file
+///
+/// ```
+/// use wlist::{DuplicatePolicy, WatchList};
+///
+/// let mut watchlist = WatchList::new();
+/// watchlist.list_add("Movies")?;
+/// watchlist.item_add("Movies", "Alien", DuplicatePolicy::Exact)?;
+/// assert!(watchlist.contains("Movies"));
+/// assert_eq!(watchlist.get("Movies").map(Vec::len), Some(1));
+/// # Ok::<(), wlist::WatchListError>(())
+/// ```
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct WatchList {
+    lists: HashMap<String, Vec<Item>>,
+    /// Titles of the items in each list for finding duplicates, built the first time an item
+    /// is added to the list. Lists handed out to be changed elsewhere are left out until then
+    #[serde(skip)]
+    names: HashMap<String, Names>,
+    /// Settings of the lists which have any, saved next to the lists in the data file
+    #[serde(skip)]
+    settings: HashMap<String, ListSettings>,
+    /// Rules the titles of items and lists being added are checked against, which aren't
+    /// saved and are left out when comparing lists
+    #[serde(skip)]
+    validator: validation::Validator,
+    /// Saved as compact JSON on one line instead of pretty-printed
+    #[serde(skip)]
+    compact: bool,
+}
+
+impl std::fmt::Debug for WatchList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WatchList")
+            .field("lists", &self.lists)
+            .field("settings", &self.settings().collect::<BTreeMap<_, _>>())
+            .finish()
+    }
+}
+
+impl PartialEq for WatchList {
+    fn eq(&self, other: &Self) -> bool {
+        self.lists == other.lists && self.settings().eq(other.settings())
+    }
+}
+
+impl Eq for WatchList {}
+
+/// Items moved by merging one list into another
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct MergeReport {
+    pub moved: usize,
+    /// Items dropped because they were already in the other list
+    pub duplicates: usize,
+}
 
+/// Methods of [`WatchList`] from when it was a `HashMap`, which are now inherent methods
+#[deprecated(note = "use the methods of `WatchList` instead")]
 pub trait WatchListFuncs<'a> {
-    fn from_file(file_path: &Path) -> io::Result<Self>
+    #[cfg(feature = "fs")]
+    fn from_file(file_path: &Path) -> Result<Self, WatchListError>
     where
         Self: Sized;
-    fn to_file(&self, file_path: &Path) -> io::Result<()>;
+    #[cfg(feature = "fs")]
+    fn to_file(&self, file_path: &Path) -> Result<(), WatchListError>;
     fn item_add(
         &mut self,
         title: &str,
-        item: &str,
-        no_duplicate: bool,
+        item: impl Into<Item>,
+        duplicates: DuplicatePolicy,
     ) -> Result<(), WatchListError>;
     fn item_remove(&mut self, title: &str, item: &str) -> Result<(), WatchListError>;
-    fn item_get_all(&self, title: &str) -> Result<&Vec<String>, WatchListError>;
-    fn item_get_random(&'a self, title: &str) -> Result<&'a String, WatchListError>;
+    fn item_rename(
+        &mut self,
+        title: &str,
+        item: &str,
+        new_item: &str,
+    ) -> Result<(), WatchListError>;
+    fn item_copy(&mut self, from: &str, to: &str, item: &str) -> Result<(), WatchListError>;
+    fn item_move(&mut self, from: &str, to: &str, item: &str) -> Result<(), WatchListError>;
+    fn item_set_rating(
+        &mut self,
+        title: &str,
+        item: &str,
+        rating: u8,
+    ) -> Result<(), WatchListError>;
+    fn item_set_progress(
+        &mut self,
+        title: &str,
+        item: &str,
+        progress: Progress,
+    ) -> Result<(), WatchListError>;
+    fn item_set_due(
+        &mut self,
+        title: &str,
+        item: &str,
+        due: Option<NaiveDate>,
+    ) -> Result<(), WatchListError>;
+    fn item_set_runtime(
+        &mut self,
+        title: &str,
+        item: &str,
+        runtime: Option<u32>,
+    ) -> Result<(), WatchListError>;
+    fn item_set_status(
+        &mut self,
+        title: &str,
+        item: &str,
+        status: Status,
+    ) -> Result<(), WatchListError>;
+    fn item_set_id(
+        &mut self,
+        title: &str,
+        item: &str,
+        service: &str,
+        id: Option<&str>,
+    ) -> Result<(), WatchListError>;
+    fn item_get_all(&self, title: &str) -> Result<&Vec<Item>, WatchListError>;
+    fn item_get_sorted(&self, title: &str, sort: SortBy) -> Result<Vec<&Item>, WatchListError>;
+    fn item_get_tagged(&self, title: &str, tags: &[String]) -> Result<Vec<&Item>, WatchListError>;
+    fn item_get_in_progress(&self, title: &str) -> Result<Vec<&Item>, WatchListError>;
+    fn item_get_random(&'a self, title: &str) -> Result<&'a Item, WatchListError>;
+    fn item_get_random_n(
+        &'a self,
+        title: &str,
+        count: usize,
+    ) -> Result<Vec<&'a Item>, WatchListError>;
     fn list_add(&mut self, title: &str) -> Result<(), WatchListError>;
     fn list_remove(&mut self, title: &str) -> Result<(), WatchListError>;
+    fn list_rename(&mut self, title: &str, new_title: &str) -> Result<(), WatchListError>;
+    fn list_merge(&mut self, from: &str, to: &str) -> Result<MergeReport, WatchListError>;
     fn list_get_all(&self) -> Result<Vec<&String>, WatchListError>;
     fn list_get_random(&'a self) -> Result<&'a String, WatchListError>;
-    fn list_search(&self, title: &str, search: &str) -> Result<Vec<&String>, WatchListError>;
+    fn list_get_random_weighted(&'a self) -> Result<&'a String, WatchListError>;
+    fn list_search(&self, title: &str, search: &str) -> Result<Vec<&Item>, WatchListError>;
+    fn list_search_fuzzy(&self, title: &str, search: &str) -> Result<Vec<&Item>, WatchListError>;
+    fn list_search_regex(&self, title: &str, pattern: &str) -> Result<Vec<&Item>, WatchListError>;
+    fn search_all(&self, search: &str) -> Vec<(&String, Vec<&Item>)>;
+    fn stats(&self) -> stats::Stats;
 }
 
-impl<'a> WatchListFuncs<'a> for WatchList {
-    fn from_file(file_path: &Path) -> io::Result<Self> {
-        let data = read_to_string(file_path)?;
-        let watchlist: WatchList = serde_json::from_str(&data)?;
-        Ok(watchlist)
+impl WatchList {
+    pub fn new() -> Self {
+        WatchList::default()
     }
 
-    fn to_file(&self, file_path: &Path) -> io::Result<()> {
-        let file = File::create(file_path)?;
-        serde_json::to_writer(file, self)?;
-        Ok(())
+    /// Items of the list, none if there is no such list
+    pub fn get(&self, title: &str) -> Option<&Vec<Item>> {
+        self.lists.get(title)
     }
 
-    fn item_add(
+    pub fn get_mut(&mut self, title: &str) -> Option<&mut Vec<Item>> {
+        self.names.remove(title);
+        self.lists.get_mut(title)
+    }
+
+    /// Items of the list, adding the list without any items first if there's no such list
+    pub fn get_or_insert(&mut self, title: impl Into<String>) -> &mut Vec<Item> {
+        let title = title.into();
+        self.names.remove(&title);
+        self.lists.entry(title).or_default()
+    }
+
+    pub fn contains(&self, title: &str) -> bool {
+        self.lists.contains_key(title)
+    }
+
+    /// Puts the items in as the list, returning the items it had before if it already existed.
+    /// Unlike [`list_add`](WatchList::list_add) the title isn't checked
+    pub fn insert(&mut self, title: impl Into<String>, items: Vec<Item>) -> Option<Vec<Item>> {
+        let title = title.into();
+        self.names.remove(&title);
+        self.lists.insert(title, items)
+    }
+
+    pub fn remove(&mut self, title: &str) -> Option<Vec<Item>> {
+        self.names.remove(title);
+        self.settings.remove(title);
+        self.lists.remove(title)
+    }
+
+    /// Settings of the list, none if it has none or there's no such list
+    pub fn list_settings(&self, title: &str) -> Option<&ListSettings> {
+        self.settings.get(title).filter(|s| !s.is_default())
+    }
+
+    /// Checks the titles of items and lists being added or renamed against the rules from the
+    /// config file, in place of the ones set before. Rules added by programs are kept
+    pub fn set_validation(&mut self, validation: validation::Validation) {
+        self.validator.validation = validation;
+    }
+
+    /// Adds a rule checked for the title of every item added, giving the reason a title
+    /// isn't allowed
+    pub fn add_item_rule(
         &mut self,
-        title: &str,
-        item: &str,
-        add_duplicate: bool,
-    ) -> Result<(), WatchListError> {
-        let list_items = self
-            .get_mut(title)
-            .ok_or(WatchListError::TitleNotPresent(title.to_string()))?;
+        rule: impl Fn(&str) -> Result<(), String> + Send + Sync + 'static,
+    ) {
+        self.validator.item_rules.push(std::sync::Arc::new(rule));
+    }
 
-        // Ignoring duplicate items if specified
-        if add_duplicate {
-            list_items.push(item.to_string());
-            return Ok(());
-        }
+    /// Adds a rule checked for the title of every list added, giving the reason a title
+    /// isn't allowed
+    pub fn add_list_rule(
+        &mut self,
+        rule: impl Fn(&str) -> Result<(), String> + Send + Sync + 'static,
+    ) {
+        self.validator.list_rules.push(std::sync::Arc::new(rule));
+    }
+
+    /// Saves the lists as compact JSON on one line from now on, instead of pretty-printed
+    pub fn set_compact(&mut self, compact: bool) {
+        self.compact = compact;
+    }
+
+    /// Checks if the lists are saved as compact JSON, see [`set_compact`](WatchList::set_compact)
+    pub fn compact(&self) -> bool {
+        self.compact
+    }
+
+    /// Rules the titles of items and lists are checked against
+    pub fn validator(&self) -> &validation::Validator {
+        &self.validator
+    }
 
-        // Check for duplicate items before adding otherwise
-        match list_items.iter().position(|l| l == item) {
-            Some(_) => Err(WatchListError::ItemAlreadyPresent(
-                item.to_string(),
+    /// Settings of the list to change, starting from the defaults
+    pub fn list_settings_mut(&mut self, title: &str) -> Result<&mut ListSettings, WatchListError> {
+        if !self.contains(title) {
+            return Err(WatchListError::TitleNotPresent(
                 title.to_string(),
-            ))?,
-            None => {
-                list_items.push(item.to_string());
-                Ok(())
-            }
+                Vec::new(),
+            ));
         }
+        Ok(self.settings.entry(title.to_string()).or_default())
     }
 
-    fn item_remove(&mut self, title: &str, item: &str) -> Result<(), WatchListError> {
-        let list_items = self
-            .get_mut(title)
-            .ok_or(WatchListError::TitleNotPresent(title.to_string()))?;
-        let index = list_items
+    /// Titles of the lists with settings other than the defaults along with them, in order
+    /// of title
+    pub fn settings(&self) -> impl Iterator<Item = (&String, &ListSettings)> {
+        let settings: BTreeMap<&String, &ListSettings> = self
+            .settings
             .iter()
-            .position(|l| l == item)
-            .ok_or(WatchListError::ItemToRemoveNotPresent(item.to_string()))?;
-        list_items.remove(index);
-
-        Ok(())
+            .filter(|(_, s)| !s.is_default())
+            .collect();
+        settings.into_iter()
     }
 
-    fn item_get_all(&self, title: &str) -> Result<&Vec<String>, WatchListError> {
-        let items = self
-            .get(title)
-            .ok_or(WatchListError::TitleNotPresent(title.to_string()))?;
-        if items.is_empty() {
-            return Err(WatchListError::EmptyList(title.to_string()));
-        }
-        Ok(items)
+    /// Whether the list is left out of random picks when no list is given
+    pub fn excluded_from_random(&self, title: &str) -> bool {
+        self.list_settings(title).is_some_and(|s| s.no_random)
     }
 
-    fn item_get_random(&'a self, title: &str) -> Result<&'a String, WatchListError> {
-        let mut rng = rand::thread_rng();
-        let random_item = self
-            .get(title)
-            .ok_or(WatchListError::TitleNotPresent(title.to_string()))?
-            .choose(&mut rng)
-            .ok_or(WatchListError::EmptyList(title.to_string()))?;
-        Ok(random_item)
+    /// Order the items of the list are shown in by default
+    pub fn list_sort(&self, title: &str) -> SortBy {
+        self.list_settings(title)
+            .map(|s| s.sort)
+            .unwrap_or_default()
     }
 
-    fn list_add(&mut self, title: &str) -> Result<(), WatchListError> {
-        match self.contains_key(title) {
-            true => Err(WatchListError::TitleAlreadyPresent(title.to_string())),
-            false => {
-                self.insert(title.to_string(), Vec::new());
-                Ok(())
-            }
-        }
+    /// Title of the list the alias is for, if it's the alias of one
+    pub fn list_for_alias(&self, name: &str) -> Option<&String> {
+        self.settings()
+            .find(|(_, s)| s.aliases.iter().any(|a| a == name))
+            .map(|(title, _)| title)
     }
 
-    fn list_remove(&mut self, title: &str) -> Result<(), WatchListError> {
-        self.remove(title)
-            .ok_or(WatchListError::TitleNotPresent(title.to_string()))?;
+    /// Makes the alias one for the list, taking it from the list it was for before
+    pub fn list_add_alias(&mut self, title: &str, name: &str) -> Result<(), WatchListError> {
+        if self.contains(name) {
+            return Err(WatchListError::TitleAlreadyPresent(name.to_string()));
+        }
+        if !self.contains(title) {
+            return Err(WatchListError::TitleNotPresent(
+                title.to_string(),
+                Vec::new(),
+            ));
+        }
+        self.list_remove_alias(name);
+        self.list_settings_mut(title)?
+            .aliases
+            .push(name.to_string());
         Ok(())
     }
 
-    fn list_get_all(&self) -> Result<Vec<&String>, WatchListError> {
-        let list_titles: Vec<&String> = self.keys().collect();
-        if list_titles.is_empty() {
-            return Err(WatchListError::NoTitles);
+    /// Removes the alias from the list it's for, giving the title of the list if there was one
+    pub fn list_remove_alias(&mut self, name: &str) -> Option<String> {
+        let title = self.list_for_alias(name)?.clone();
+        if let Some(settings) = self.settings.get_mut(&title) {
+            settings.aliases.retain(|a| a != name);
         }
-        Ok(list_titles)
+        Some(title)
     }
 
-    fn list_get_random(&'a self) -> Result<&'a String, WatchListError> {
-        let mut rng = rand::thread_rng();
-        let lists = self.list_get_all()?;
-        let random_list = lists.choose(&mut rng).ok_or(WatchListError::NoTitles)?;
-        Ok(random_list)
+    /// Titles of the lists, in no particular order
+    pub fn titles(&self) -> impl Iterator<Item = &String> {
+        self.lists.keys()
     }
 
-    fn list_search(&self, title: &str, search: &str) -> Result<Vec<&String>, WatchListError> {
-        Ok(self
-            .get(title)
-            .ok_or(WatchListError::TitleNotPresent(title.to_string()))?
-            .iter()
-            .filter(|i| i.to_ascii_lowercase().contains(&search.to_lowercase()))
-            .collect())
+    /// Items of every list, in no particular order
+    pub fn values(&self) -> impl Iterator<Item = &Vec<Item>> {
+        self.lists.values()
     }
-}
 
-pub fn input(prompt: &str, trim_input: bool) -> Result<String, WatchListError> {
-    let mut input = String::new();
-    print!("{prompt}");
-    io::stdout()
-        .flush()
-        .map_err(WatchListError::IOError)?;
-    io::stdin()
-        .read_line(&mut input)
-        .map_err(WatchListError::IOError)?;
+    /// Titles of the lists with their items, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Vec<Item>)> {
+        self.lists.iter()
+    }
 
-    if trim_input {
-        return Ok(input.trim().to_string());
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&String, &mut Vec<Item>)> {
+        self.names.clear();
+        self.lists.iter_mut()
+    }
+
+    /// Number of lists
+    pub fn len(&self) -> usize {
+        self.lists.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lists.is_empty()
+    }
+
+    /// Keeps only the lists the function returns true for
+    pub fn retain(&mut self, keep: impl FnMut(&String, &mut Vec<Item>) -> bool) {
+        self.names.clear();
+        self.lists.retain(keep);
+        self.settings
+            .retain(|title, _| self.lists.contains_key(title));
+    }
+
+    // Items of the list for changes which keep their titles, so the names of the list stay
+    // as they are
+    fn items_mut(&mut self, title: &str) -> Result<&mut Vec<Item>, WatchListError> {
+        self.lists
+            .get_mut(title)
+            .ok_or(WatchListError::TitleNotPresent(
+                title.to_string(),
+                Vec::new(),
+            ))
+    }
+
+    /// Reads the lists from JSON in any [version](schema) of the data file, upgrading them
+    /// from older versions like [`from_file`](WatchList::from_file) does
+    pub fn from_json(data: &str) -> Result<Self, WatchListError> {
+        let (watchlist, _) = schema::parse(data.as_bytes()).map_err(read_error)?;
+        limits::check_watchlist(&watchlist)?;
+        Ok(watchlist)
+    }
+
+    /// Writes the lists as JSON in the version [`to_file`](WatchList::to_file) would save
+    /// them in
+    pub fn to_json(&self) -> Result<String, WatchListError> {
+        let mut data = Vec::new();
+        schema::write(self, schema::version(self), self.compact, &mut data)?;
+        Ok(String::from_utf8(data).expect("JSON is written as UTF-8"))
+    }
+
+    /// Reads the lists from the file, decrypting it first if it's
+    /// [encrypted](encryption)
+    #[cfg(feature = "fs")]
+    pub fn from_file(file_path: &Path) -> Result<Self, WatchListError> {
+        Self::from_file_with_version(file_path).map(|(watchlist, _)| watchlist)
+    }
+
+    /// Reads the lists from the file along with the [version](schema) it's in, upgrading
+    /// them from older versions
+    #[cfg(feature = "fs")]
+    pub fn from_file_with_version(file_path: &Path) -> Result<(Self, u32), WatchListError> {
+        let (watchlist, version) = match encryption::read_file(file_path)? {
+            Some(data) => schema::parse(&data).map_err(read_error)?,
+            #[cfg(feature = "mmap")]
+            None => mmap::read_watchlist(file_path).map_err(read_error)?,
+            #[cfg(not(feature = "mmap"))]
+            None => limits::read_file(file_path)
+                .and_then(|data| schema::parse(data.as_bytes()))
+                .map_err(read_error)?,
+        };
+        limits::check_watchlist(&watchlist)?;
+        Ok((watchlist, version))
+    }
+
+    /// Saves the lists to the file, which stays encrypted if it already was
+    #[cfg(feature = "fs")]
+    pub fn to_file(&self, file_path: &Path) -> Result<(), WatchListError> {
+        self.to_file_with_version(file_path, schema::version(self))
+    }
+
+    /// Saves the lists to the file in the [version](schema), which has to be able to hold them
+    #[cfg(feature = "fs")]
+    pub fn to_file_with_version(
+        &self,
+        file_path: &Path,
+        version: u32,
+    ) -> Result<(), WatchListError> {
+        if encryption::is_encrypted(file_path)? {
+            let mut data = Vec::new();
+            schema::write(self, version, self.compact, &mut data)?;
+            return encryption::write_file(file_path, &data);
+        }
+        let mut file = BufWriter::new(File::create(file_path)?);
+        schema::write(self, version, self.compact, &mut file)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Reads the lists from the file, changes them and saves them back, holding the
+    /// [lock](lock::FileLock) on the file throughout so changes made at the same time
+    /// aren't lost. A file which doesn't exist yet has no lists
+    #[cfg(feature = "fs")]
+    pub fn update_file<T>(
+        file_path: &Path,
+        change: impl FnOnce(&mut WatchList) -> Result<T, WatchListError>,
+    ) -> Result<T, WatchListError> {
+        let _lock = lock::FileLock::acquire(file_path)?;
+        let (mut watchlist, version) = match WatchList::from_file_with_version(file_path) {
+            Err(WatchListError::IOError(e)) if e.kind() == io::ErrorKind::NotFound => {
+                (WatchList::new(), schema::CURRENT)
+            }
+            watchlist => watchlist?,
+        };
+        let result = change(&mut watchlist)?;
+        watchlist.to_file_with_version(file_path, schema::save_version(version, &watchlist))?;
+        Ok(result)
+    }
+
+    pub fn item_add(
+        &mut self,
+        title: &str,
+        item: impl Into<Item>,
+        duplicates: DuplicatePolicy,
+    ) -> Result<(), WatchListError> {
+        let item = item.into();
+        self.validator.check_item(&item.title)?;
+        let list_items = self
+            .lists
+            .get_mut(title)
+            .ok_or(WatchListError::TitleNotPresent(
+                title.to_string(),
+                Vec::new(),
+            ))?;
+        let names = self
+            .names
+            .entry(title.to_string())
+            .or_insert_with(|| Names::of(list_items));
+
+        if names.contains(&item.title, duplicates) {
+            return Err(WatchListError::ItemAlreadyPresent(
+                item.title,
+                title.to_string(),
+            ));
+        }
+        names.add(&item.title);
+        list_items.push(item);
+        Ok(())
+    }
+
+    pub fn item_remove(&mut self, title: &str, item: &str) -> Result<(), WatchListError> {
+        let list_items = self.items_mut(title)?;
+        let index = list_items
+            .iter()
+            .position(|l| l.title == item)
+            .ok_or(WatchListError::ItemToRemoveNotPresent(item.to_string()))?;
+        list_items.remove(index);
+        if let Some(names) = self.names.get_mut(title) {
+            names.remove(item);
+        }
+
+        Ok(())
+    }
+
+    pub fn item_rename(
+        &mut self,
+        title: &str,
+        item: &str,
+        new_item: &str,
+    ) -> Result<(), WatchListError> {
+        self.validator.check_item(new_item)?;
+        let list = self.items_mut(title)?;
+        if item != new_item && list.iter().any(|i| i.title == new_item) {
+            return Err(WatchListError::ItemAlreadyPresent(
+                new_item.to_string(),
+                title.to_string(),
+            ));
+        }
+        let entry = list
+            .iter_mut()
+            .find(|i| i.title == item)
+            .ok_or(WatchListError::ItemToRemoveNotPresent(item.to_string()))?;
+        entry.title = new_item.to_string();
+        if let Some(names) = self.names.get_mut(title) {
+            names.remove(item);
+            names.add(new_item);
+        }
+        Ok(())
+    }
+
+    pub fn item_copy(&mut self, from: &str, to: &str, item: &str) -> Result<(), WatchListError> {
+        let copy = self
+            .get(from)
+            .ok_or(WatchListError::TitleNotPresent(
+                from.to_string(),
+                Vec::new(),
+            ))?
+            .iter()
+            .find(|i| i.title == item)
+            .ok_or(WatchListError::ItemToRemoveNotPresent(item.to_string()))?
+            .clone();
+        self.item_add(to, copy, DuplicatePolicy::Exact)
+    }
+
+    pub fn item_move(&mut self, from: &str, to: &str, item: &str) -> Result<(), WatchListError> {
+        self.item_copy(from, to, item)?;
+        self.item_remove(from, item)
+    }
+
+    pub fn item_set_rating(
+        &mut self,
+        title: &str,
+        item: &str,
+        rating: u8,
+    ) -> Result<(), WatchListError> {
+        if !(1..=10).contains(&rating) {
+            return Err(WatchListError::InvalidRating(rating));
+        }
+        let entry = self
+            .items_mut(title)?
+            .iter_mut()
+            .find(|i| i.title == item)
+            .ok_or(WatchListError::ItemToRemoveNotPresent(item.to_string()))?;
+        entry.meta.rating = Some(rating);
+        Ok(())
+    }
+
+    pub fn item_set_pinned(
+        &mut self,
+        title: &str,
+        item: &str,
+        pinned: bool,
+    ) -> Result<(), WatchListError> {
+        let entry = self
+            .items_mut(title)?
+            .iter_mut()
+            .find(|i| i.title == item)
+            .ok_or(WatchListError::ItemToRemoveNotPresent(item.to_string()))?;
+        entry.meta.pinned = pinned;
+        Ok(())
+    }
+
+    /// Position of the item in the list in the order the items were added, counting from 0
+    pub fn item_position(&self, title: &str, item: &str) -> Result<usize, WatchListError> {
+        self.item_get_all(title)?
+            .iter()
+            .position(|i| i.title == item)
+            .ok_or(WatchListError::ItemToRemoveNotPresent(item.to_string()))
+    }
+
+    /// Moves the item to the position in the list, counting from 0 in the order the items
+    /// were added, or to the end if the list is shorter. Gives the position it ends up at
+    pub fn item_move_to(
+        &mut self,
+        title: &str,
+        item: &str,
+        position: usize,
+    ) -> Result<usize, WatchListError> {
+        let from = self.item_position(title, item)?;
+        let items = self.items_mut(title)?;
+        let moved = items.remove(from);
+        let to = position.min(items.len());
+        items.insert(to, moved);
+        Ok(to)
+    }
+
+    /// Item at the front of the list when it's worked through as a queue, the first one
+    /// still to watch in the order the items were added with pinned items first
+    pub fn item_get_next(&self, title: &str) -> Result<Option<&Item>, WatchListError> {
+        Ok(self
+            .item_get_sorted(title, SortBy::Added)?
+            .into_iter()
+            .find(|i| i.status() == Status::Unwatched))
+    }
+
+    /// Removes the item at the front of the list, see
+    /// [`item_get_next`](WatchList::item_get_next), giving back the item removed
+    pub fn item_pop_next(&mut self, title: &str) -> Result<Option<Item>, WatchListError> {
+        let Some(position) = self.next_position(title)? else {
+            return Ok(None);
+        };
+        Ok(self.item_remove_positions(title, &[position])?.pop())
+    }
+
+    /// Marks the item at the front of the list watched, see
+    /// [`item_get_next`](WatchList::item_get_next), giving back the item marked
+    pub fn item_finish_next(&mut self, title: &str) -> Result<Option<Item>, WatchListError> {
+        let Some(position) = self.next_position(title)? else {
+            return Ok(None);
+        };
+        let item = &mut self.items_mut(title)?[position];
+        item.set_status(Status::Watched);
+        Ok(Some(item.clone()))
+    }
+
+    fn next_position(&self, title: &str) -> Result<Option<usize>, WatchListError> {
+        let Some(next) = self.item_get_next(title)? else {
+            return Ok(None);
+        };
+        Ok(self.lists[title].iter().position(|i| std::ptr::eq(i, next)))
+    }
+
+    pub fn item_set_progress(
+        &mut self,
+        title: &str,
+        item: &str,
+        progress: Progress,
+    ) -> Result<(), WatchListError> {
+        let entry = self
+            .items_mut(title)?
+            .iter_mut()
+            .find(|i| i.title == item)
+            .ok_or(WatchListError::ItemToRemoveNotPresent(item.to_string()))?;
+        entry.meta.progress = Some(progress);
+        Ok(())
+    }
+
+    pub fn item_set_due(
+        &mut self,
+        title: &str,
+        item: &str,
+        due: Option<NaiveDate>,
+    ) -> Result<(), WatchListError> {
+        let entry = self
+            .items_mut(title)?
+            .iter_mut()
+            .find(|i| i.title == item)
+            .ok_or(WatchListError::ItemToRemoveNotPresent(item.to_string()))?;
+        entry.meta.due = due;
+        Ok(())
+    }
+
+    /// Sets the link of the item to its page elsewhere, which has to be a web address, or
+    /// removes it with `None`
+    pub fn item_set_url(
+        &mut self,
+        title: &str,
+        item: &str,
+        url: Option<&str>,
+    ) -> Result<(), WatchListError> {
+        let url = url.map(str::trim);
+        if let Some(url) = url {
+            links::check(url)?;
+        }
+        let entry = self
+            .items_mut(title)?
+            .iter_mut()
+            .find(|i| i.title == item)
+            .ok_or(WatchListError::ItemToRemoveNotPresent(item.to_string()))?;
+        entry.meta.url = url.map(str::to_string);
+        Ok(())
+    }
+
+    pub fn item_set_runtime(
+        &mut self,
+        title: &str,
+        item: &str,
+        runtime: Option<u32>,
+    ) -> Result<(), WatchListError> {
+        let entry = self
+            .items_mut(title)?
+            .iter_mut()
+            .find(|i| i.title == item)
+            .ok_or(WatchListError::ItemToRemoveNotPresent(item.to_string()))?;
+        entry.meta.runtime = runtime;
+        Ok(())
+    }
+
+    pub fn item_set_status(
+        &mut self,
+        title: &str,
+        item: &str,
+        status: Status,
+    ) -> Result<(), WatchListError> {
+        let entry = self
+            .items_mut(title)?
+            .iter_mut()
+            .find(|i| i.title == item)
+            .ok_or(WatchListError::ItemToRemoveNotPresent(item.to_string()))?;
+        entry.set_status(status);
+        Ok(())
+    }
+
+    pub fn item_set_id(
+        &mut self,
+        title: &str,
+        item: &str,
+        service: &str,
+        id: Option<&str>,
+    ) -> Result<(), WatchListError> {
+        let entry = self
+            .items_mut(title)?
+            .iter_mut()
+            .find(|i| i.title == item)
+            .ok_or(WatchListError::ItemToRemoveNotPresent(item.to_string()))?;
+        let service = service.trim().to_lowercase();
+        match id {
+            Some(id) => entry.meta.ids.insert(service, id.trim().to_string()),
+            None => entry.meta.ids.remove(&service),
+        };
+        Ok(())
+    }
+
+    pub fn item_get_all(&self, title: &str) -> Result<&Vec<Item>, WatchListError> {
+        let items = self.get(title).ok_or(WatchListError::TitleNotPresent(
+            title.to_string(),
+            Vec::new(),
+        ))?;
+        if items.is_empty() {
+            return Err(WatchListError::EmptyList(title.to_string()));
+        }
+        Ok(items)
+    }
+
+    pub fn item_get_sorted(&self, title: &str, sort: SortBy) -> Result<Vec<&Item>, WatchListError> {
+        let mut items: Vec<&Item> = self.item_get_all(title)?.iter().collect();
+        sort.sort(&mut items, |i| i);
+        Ok(items)
+    }
+
+    /// Item at the index of the list, counting from 0, with the items in the order of the
+    /// sort like `wl show` numbers them. With [`SortBy::Random`] it's any of the items
+    pub fn item_get_index(
+        &self,
+        title: &str,
+        index: usize,
+        sort: SortBy,
+    ) -> Result<&Item, WatchListError> {
+        self.item_get_sorted(title, sort)?
+            .get(index)
+            .copied()
+            .ok_or(WatchListError::ItemToRemoveNotPresent(format!(
+                "Item {}",
+                index + 1
+            )))
+    }
+
+    /// Removes the item at the index of the list in the order of the sort, see
+    /// [`item_get_index`](WatchList::item_get_index), giving back the item removed.
+    /// Only that item is removed even when the list has others with the same title
+    pub fn item_remove_index(
+        &mut self,
+        title: &str,
+        index: usize,
+        sort: SortBy,
+    ) -> Result<Item, WatchListError> {
+        let mut removed = self.item_remove_indices(title, &[index], sort)?;
+        Ok(removed.remove(0))
+    }
+
+    /// Removes the items at each of the indices like
+    /// [`item_remove_index`](WatchList::item_remove_index), giving back the items removed
+    /// in the order of the indices. Nothing is removed if any of the indices isn't in the
+    /// list, and an index given twice only removes its item once
+    pub fn item_remove_indices(
+        &mut self,
+        title: &str,
+        indices: &[usize],
+        sort: SortBy,
+    ) -> Result<Vec<Item>, WatchListError> {
+        let sorted = self.item_get_sorted(title, sort)?;
+        let mut positions = Vec::new();
+        for &index in indices {
+            let item = sorted
+                .get(index)
+                .ok_or(WatchListError::ItemToRemoveNotPresent(format!(
+                    "Item {}",
+                    index + 1
+                )))?;
+            let position = self[title]
+                .iter()
+                .position(|i| std::ptr::eq(i, *item))
+                .expect("the item was found in the list");
+            positions.push(position);
+        }
+        self.item_remove_positions(title, &positions)
+    }
+
+    /// Removes the items at each of the positions in the list, counting from 0 in the order
+    /// the items were added, like [`item_remove_indices`](WatchList::item_remove_indices)
+    pub fn item_remove_positions(
+        &mut self,
+        title: &str,
+        positions: &[usize],
+    ) -> Result<Vec<Item>, WatchListError> {
+        let count = self.item_get_all(title)?.len();
+        if let Some(position) = positions.iter().find(|&&p| p >= count) {
+            return Err(WatchListError::ItemToRemoveNotPresent(format!(
+                "Item {}",
+                position + 1
+            )));
+        }
+        let mut unique: Vec<usize> = Vec::new();
+        for &position in positions {
+            if !unique.contains(&position) {
+                unique.push(position);
+            }
+        }
+        let positions = unique;
+
+        let items = std::mem::take(self.items_mut(title)?);
+        let mut removed: Vec<Option<Item>> = positions.iter().map(|_| None).collect();
+        let mut kept = Vec::new();
+        for (position, item) in items.into_iter().enumerate() {
+            match positions.iter().position(|&p| p == position) {
+                Some(n) => removed[n] = Some(item),
+                None => kept.push(item),
+            }
+        }
+        *self.items_mut(title)? = kept;
+        let removed: Vec<Item> = removed.into_iter().flatten().collect();
+        if let Some(names) = self.names.get_mut(title) {
+            for item in &removed {
+                names.remove(&item.title);
+            }
+        }
+        Ok(removed)
+    }
+
+    pub fn item_get_tagged(
+        &self,
+        title: &str,
+        tags: &[String],
+    ) -> Result<Vec<&Item>, WatchListError> {
+        Ok(self
+            .get(title)
+            .ok_or(WatchListError::TitleNotPresent(
+                title.to_string(),
+                Vec::new(),
+            ))?
+            .iter()
+            .filter(|i| i.has_tags(tags))
+            .collect())
+    }
+
+    pub fn item_get_in_progress(&self, title: &str) -> Result<Vec<&Item>, WatchListError> {
+        Ok(self
+            .get(title)
+            .ok_or(WatchListError::TitleNotPresent(
+                title.to_string(),
+                Vec::new(),
+            ))?
+            .iter()
+            .filter(|i| i.meta.progress.is_some() && !i.meta.watched)
+            .collect())
+    }
+
+    pub fn item_get_random(&self, title: &str) -> Result<&Item, WatchListError> {
+        self.item_get_random_with_rng(title, &mut rand::thread_rng())
+    }
+
+    /// Picks a random item with `rng`, which can be seeded to pick the same item every time
+    pub fn item_get_random_with_rng(
+        &self,
+        title: &str,
+        rng: &mut impl Rng,
+    ) -> Result<&Item, WatchListError> {
+        let random_item = self
+            .get(title)
+            .ok_or(WatchListError::TitleNotPresent(
+                title.to_string(),
+                Vec::new(),
+            ))?
+            .choose(rng)
+            .ok_or(WatchListError::EmptyList(title.to_string()))?;
+        Ok(random_item)
+    }
+
+    // Items are never picked twice, so fewer than `count` are picked from shorter lists
+    pub fn item_get_random_n(
+        &self,
+        title: &str,
+        count: usize,
+    ) -> Result<Vec<&Item>, WatchListError> {
+        self.item_get_random_n_with_rng(title, count, &mut rand::thread_rng())
+    }
+
+    /// Picks `count` random items with `rng`, like [`item_get_random_with_rng`](Self::item_get_random_with_rng)
+    pub fn item_get_random_n_with_rng(
+        &self,
+        title: &str,
+        count: usize,
+        rng: &mut impl Rng,
+    ) -> Result<Vec<&Item>, WatchListError> {
+        let items = self.get(title).ok_or(WatchListError::TitleNotPresent(
+            title.to_string(),
+            Vec::new(),
+        ))?;
+        if items.is_empty() {
+            return Err(WatchListError::EmptyList(title.to_string()));
+        }
+        Ok(items.choose_multiple(rng, count).collect())
+    }
+
+    pub fn list_add(&mut self, title: &str) -> Result<(), WatchListError> {
+        self.validator.check_list(title)?;
+        match self.contains(title) {
+            true => Err(WatchListError::TitleAlreadyPresent(title.to_string())),
+            false => {
+                self.insert(title.to_string(), Vec::new());
+                Ok(())
+            }
+        }
+    }
+
+    pub fn list_remove(&mut self, title: &str) -> Result<(), WatchListError> {
+        self.remove(title).ok_or(WatchListError::TitleNotPresent(
+            title.to_string(),
+            Vec::new(),
+        ))?;
+        Ok(())
+    }
+
+    pub fn list_rename(&mut self, title: &str, new_title: &str) -> Result<(), WatchListError> {
+        self.validator.check_list(new_title)?;
+        if self.contains(new_title) {
+            return Err(WatchListError::TitleAlreadyPresent(new_title.to_string()));
+        }
+        let settings = self.settings.remove(title);
+        let items = self.remove(title).ok_or(WatchListError::TitleNotPresent(
+            title.to_string(),
+            Vec::new(),
+        ))?;
+        self.insert(new_title.to_string(), items);
+        if let Some(settings) = settings {
+            self.settings.insert(new_title.to_string(), settings);
+        }
+        Ok(())
+    }
+
+    /// Adds a new list with copies of every item of a list, along with their details and
+    /// the settings of the list other than its aliases
+    pub fn list_copy(&mut self, title: &str, new_title: &str) -> Result<(), WatchListError> {
+        self.validator.check_list(new_title)?;
+        if self.contains(new_title) {
+            return Err(WatchListError::TitleAlreadyPresent(new_title.to_string()));
+        }
+        let items = self.item_get_all(title)?.clone();
+        self.insert(new_title.to_string(), items);
+        if let Some(settings) = self.settings.get(title).cloned() {
+            let settings = ListSettings {
+                aliases: Vec::new(),
+                ..settings
+            };
+            self.settings.insert(new_title.to_string(), settings);
+        }
+        Ok(())
+    }
+
+    pub fn list_merge(&mut self, from: &str, to: &str) -> Result<MergeReport, WatchListError> {
+        if !self.contains(to) {
+            return Err(WatchListError::TitleNotPresent(to.to_string(), Vec::new()));
+        }
+        if from == to {
+            return match self.contains(from) {
+                true => Ok(MergeReport::default()),
+                false => Err(WatchListError::TitleNotPresent(
+                    from.to_string(),
+                    Vec::new(),
+                )),
+            };
+        }
+        let Some(items) = self.get(from) else {
+            return Err(WatchListError::TitleNotPresent(
+                from.to_string(),
+                Vec::new(),
+            ));
+        };
+        // Checked before anything is moved, so a title that isn't allowed leaves both lists
+        // as they were instead of losing the rest of the items
+        for item in items {
+            self.validator.check_item(&item.title)?;
+        }
+        let items = self.remove(from).unwrap_or_default();
+
+        let mut report = MergeReport::default();
+        for item in items {
+            match self.item_add(to, item, DuplicatePolicy::Exact) {
+                Ok(()) => report.moved += 1,
+                Err(WatchListError::ItemAlreadyPresent(..)) => report.duplicates += 1,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(report)
+    }
+
+    pub fn list_get_all(&self) -> Result<Vec<&String>, WatchListError> {
+        let list_titles: Vec<&String> = self.titles().collect();
+        if list_titles.is_empty() {
+            return Err(WatchListError::NoTitles);
+        }
+        Ok(list_titles)
+    }
+
+    /// Lists grouped under a category by their titles, like `Anime/Films` and `Anime/Series`
+    /// under `Anime`, in order of title. Categories can be nested, so `Anime/Films/Ghibli`
+    /// is under both `Anime` and `Anime/Films`
+    pub fn category_lists(&self, category: &str) -> Vec<&String> {
+        let mut titles: Vec<&String> = self.titles().filter(|t| in_category(t, category)).collect();
+        titles.sort();
+        titles
+    }
+
+    /// Checks if the title is a category of lists, rather than a list itself
+    pub fn is_category(&self, title: &str) -> bool {
+        !self.contains(title) && self.titles().any(|t| in_category(t, title))
+    }
+
+    // Lists excluded from random picks are only left out, so excluding every list
+    // is the same as having none
+    pub fn list_get_random(&self) -> Result<&String, WatchListError> {
+        self.list_get_random_with_rng(&mut rand::thread_rng())
+    }
+
+    /// Picks a random list with `rng`, which can be seeded to pick the same list every time
+    pub fn list_get_random_with_rng(&self, rng: &mut impl Rng) -> Result<&String, WatchListError> {
+        let lists = self.random_lists()?;
+        let random_list = lists.choose(rng).ok_or(WatchListError::NoTitles)?;
+        Ok(random_list)
+    }
+
+    /// Lists which random picks can come from, in order of title so a seeded pick doesn't
+    /// depend on the order the lists happen to be stored in
+    pub fn random_lists(&self) -> Result<Vec<&String>, WatchListError> {
+        let mut lists = self.list_get_all()?;
+        lists.retain(|l| !self.excluded_from_random(l));
+        lists.sort();
+        Ok(lists)
+    }
+
+    // Lists are as likely to be picked as they have items, which makes every item
+    // as likely as any other to be in the list picked
+    pub fn list_get_random_weighted(&self) -> Result<&String, WatchListError> {
+        self.list_get_random_weighted_with_rng(&mut rand::thread_rng())
+    }
+
+    /// Picks a random list by how many items it has with `rng`, like
+    /// [`list_get_random_with_rng`](Self::list_get_random_with_rng)
+    pub fn list_get_random_weighted_with_rng(
+        &self,
+        rng: &mut impl Rng,
+    ) -> Result<&String, WatchListError> {
+        let lists = self.random_lists()?;
+        match lists.choose_weighted(&mut *rng, |l| self[*l].len()) {
+            Ok(random_list) => Ok(random_list),
+            // Every list is empty
+            Err(_) => self.list_get_random_with_rng(rng),
+        }
+    }
+
+    pub fn list_search(&self, title: &str, search: &str) -> Result<Vec<&Item>, WatchListError> {
+        Ok(self
+            .get(title)
+            .ok_or(WatchListError::TitleNotPresent(
+                title.to_string(),
+                Vec::new(),
+            ))?
+            .iter()
+            .filter(|i| i.matches(search))
+            .collect())
+    }
+
+    /// Finds the items like [`list_search`](WatchList::list_search), along with where the
+    /// search is in their titles
+    pub fn list_search_matches(
+        &self,
+        title: &str,
+        search: &str,
+    ) -> Result<Vec<Match<'_>>, WatchListError> {
+        Ok(self
+            .list_search(title, search)?
+            .into_iter()
+            .map(|item| Match {
+                item,
+                spans: item.match_spans(search),
+            })
+            .collect())
+    }
+
+    /// Finds the items like [`list_search`](WatchList::list_search), but ignoring accents
+    /// too so "pokemon" finds "Pokémon"
+    pub fn list_search_folded(
+        &self,
+        title: &str,
+        search: &str,
+    ) -> Result<Vec<&Item>, WatchListError> {
+        Ok(self
+            .get(title)
+            .ok_or(WatchListError::TitleNotPresent(
+                title.to_string(),
+                Vec::new(),
+            ))?
+            .iter()
+            .filter(|i| fold::contains(&i.title, search))
+            .collect())
+    }
+
+    /// Finds the items like [`list_search_folded`](WatchList::list_search_folded), along
+    /// with where the search is in their titles
+    pub fn list_search_folded_matches(
+        &self,
+        title: &str,
+        search: &str,
+    ) -> Result<Vec<Match<'_>>, WatchListError> {
+        Ok(self
+            .list_search_folded(title, search)?
+            .into_iter()
+            .map(|item| Match {
+                item,
+                spans: fold::spans(&item.title, search),
+            })
+            .collect())
+    }
+
+    pub fn list_search_fuzzy(
+        &self,
+        title: &str,
+        search: &str,
+    ) -> Result<Vec<&Item>, WatchListError> {
+        let items = self.get(title).ok_or(WatchListError::TitleNotPresent(
+            title.to_string(),
+            Vec::new(),
+        ))?;
+        Ok(fuzzy::search(items, search))
+    }
+
+    /// Finds the items like [`list_search_fuzzy`](WatchList::list_search_fuzzy), along with
+    /// the letters of their titles which matched. Misspelled words don't match any letters
+    pub fn list_search_fuzzy_matches(
+        &self,
+        title: &str,
+        search: &str,
+    ) -> Result<Vec<Match<'_>>, WatchListError> {
+        let items = self.get(title).ok_or(WatchListError::TitleNotPresent(
+            title.to_string(),
+            Vec::new(),
+        ))?;
+        Ok(fuzzy::search_matches(items, search))
+    }
+
+    // Ignores case like the other searches, which `(?-i)` in the pattern turns off
+    pub fn list_search_regex(
+        &self,
+        title: &str,
+        pattern: &str,
+    ) -> Result<Vec<&Item>, WatchListError> {
+        let matches = self.list_search_regex_matches(title, pattern)?;
+        Ok(matches.into_iter().map(|m| m.item).collect())
+    }
+
+    /// Finds the items like [`list_search_regex`](WatchList::list_search_regex), along with
+    /// the parts of their titles the pattern matched
+    pub fn list_search_regex_matches(
+        &self,
+        title: &str,
+        pattern: &str,
+    ) -> Result<Vec<Match<'_>>, WatchListError> {
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| WatchListError::InvalidPattern(e.to_string()))?;
+        Ok(self
+            .get(title)
+            .ok_or(WatchListError::TitleNotPresent(
+                title.to_string(),
+                Vec::new(),
+            ))?
+            .iter()
+            .filter(|i| regex.is_match(&i.title))
+            .map(|item| Match {
+                item,
+                spans: regex
+                    .find_iter(&item.title)
+                    .map(|m| m.range())
+                    .filter(|span| !span.is_empty())
+                    .collect(),
+            })
+            .collect())
+    }
+
+    // Lists are in alphabetical order, leaving out the ones without matches
+    pub fn search_all(&self, search: &str) -> Vec<(&String, Vec<&Item>)> {
+        self.search_all_by(|i| i.matches(search))
+    }
+
+    /// Searches every list like [`search_all`](WatchList::search_all), but ignoring accents
+    /// like [`list_search_folded`](WatchList::list_search_folded)
+    pub fn search_all_folded(&self, search: &str) -> Vec<(&String, Vec<&Item>)> {
+        self.search_all_by(|i| fold::contains(&i.title, search))
+    }
+
+    fn search_all_by(&self, matches: impl Fn(&Item) -> bool) -> Vec<(&String, Vec<&Item>)> {
+        let mut results: Vec<(&String, Vec<&Item>)> = self
+            .iter()
+            .filter_map(|(title, items)| {
+                let matches: Vec<&Item> = items.iter().filter(|i| matches(i)).collect();
+                (!matches.is_empty()).then_some((title, matches))
+            })
+            .collect();
+        results.sort_by_key(|(title, _)| *title);
+        results
+    }
+
+    pub fn stats(&self) -> stats::Stats {
+        stats::Stats::of(self)
+    }
+}
+
+#[allow(deprecated)]
+impl<'a> WatchListFuncs<'a> for WatchList {
+    #[cfg(feature = "fs")]
+    fn from_file(file_path: &Path) -> Result<Self, WatchListError> {
+        WatchList::from_file(file_path)
+    }
+
+    #[cfg(feature = "fs")]
+    fn to_file(&self, file_path: &Path) -> Result<(), WatchListError> {
+        WatchList::to_file(self, file_path)
+    }
+
+    fn item_add(
+        &mut self,
+        title: &str,
+        item: impl Into<Item>,
+        duplicates: DuplicatePolicy,
+    ) -> Result<(), WatchListError> {
+        WatchList::item_add(self, title, item, duplicates)
+    }
+
+    fn item_remove(&mut self, title: &str, item: &str) -> Result<(), WatchListError> {
+        WatchList::item_remove(self, title, item)
+    }
+
+    fn item_rename(
+        &mut self,
+        title: &str,
+        item: &str,
+        new_item: &str,
+    ) -> Result<(), WatchListError> {
+        WatchList::item_rename(self, title, item, new_item)
+    }
+
+    fn item_copy(&mut self, from: &str, to: &str, item: &str) -> Result<(), WatchListError> {
+        WatchList::item_copy(self, from, to, item)
+    }
+
+    fn item_move(&mut self, from: &str, to: &str, item: &str) -> Result<(), WatchListError> {
+        WatchList::item_move(self, from, to, item)
+    }
+
+    fn item_set_rating(
+        &mut self,
+        title: &str,
+        item: &str,
+        rating: u8,
+    ) -> Result<(), WatchListError> {
+        WatchList::item_set_rating(self, title, item, rating)
+    }
+
+    fn item_set_progress(
+        &mut self,
+        title: &str,
+        item: &str,
+        progress: Progress,
+    ) -> Result<(), WatchListError> {
+        WatchList::item_set_progress(self, title, item, progress)
+    }
+
+    fn item_set_due(
+        &mut self,
+        title: &str,
+        item: &str,
+        due: Option<NaiveDate>,
+    ) -> Result<(), WatchListError> {
+        WatchList::item_set_due(self, title, item, due)
+    }
+
+    fn item_set_runtime(
+        &mut self,
+        title: &str,
+        item: &str,
+        runtime: Option<u32>,
+    ) -> Result<(), WatchListError> {
+        WatchList::item_set_runtime(self, title, item, runtime)
+    }
+
+    fn item_set_status(
+        &mut self,
+        title: &str,
+        item: &str,
+        status: Status,
+    ) -> Result<(), WatchListError> {
+        WatchList::item_set_status(self, title, item, status)
+    }
+
+    fn item_set_id(
+        &mut self,
+        title: &str,
+        item: &str,
+        service: &str,
+        id: Option<&str>,
+    ) -> Result<(), WatchListError> {
+        WatchList::item_set_id(self, title, item, service, id)
+    }
+
+    fn item_get_all(&self, title: &str) -> Result<&Vec<Item>, WatchListError> {
+        WatchList::item_get_all(self, title)
+    }
+
+    fn item_get_sorted(&self, title: &str, sort: SortBy) -> Result<Vec<&Item>, WatchListError> {
+        WatchList::item_get_sorted(self, title, sort)
+    }
+
+    fn item_get_tagged(&self, title: &str, tags: &[String]) -> Result<Vec<&Item>, WatchListError> {
+        WatchList::item_get_tagged(self, title, tags)
+    }
+
+    fn item_get_in_progress(&self, title: &str) -> Result<Vec<&Item>, WatchListError> {
+        WatchList::item_get_in_progress(self, title)
+    }
+
+    fn item_get_random(&'a self, title: &str) -> Result<&'a Item, WatchListError> {
+        WatchList::item_get_random(self, title)
+    }
+
+    fn item_get_random_n(
+        &'a self,
+        title: &str,
+        count: usize,
+    ) -> Result<Vec<&'a Item>, WatchListError> {
+        WatchList::item_get_random_n(self, title, count)
+    }
+
+    fn list_add(&mut self, title: &str) -> Result<(), WatchListError> {
+        WatchList::list_add(self, title)
+    }
+
+    fn list_remove(&mut self, title: &str) -> Result<(), WatchListError> {
+        WatchList::list_remove(self, title)
+    }
+
+    fn list_rename(&mut self, title: &str, new_title: &str) -> Result<(), WatchListError> {
+        WatchList::list_rename(self, title, new_title)
+    }
+
+    fn list_merge(&mut self, from: &str, to: &str) -> Result<MergeReport, WatchListError> {
+        WatchList::list_merge(self, from, to)
+    }
+
+    fn list_get_all(&self) -> Result<Vec<&String>, WatchListError> {
+        WatchList::list_get_all(self)
+    }
+
+    fn list_get_random(&'a self) -> Result<&'a String, WatchListError> {
+        WatchList::list_get_random(self)
+    }
+
+    fn list_get_random_weighted(&'a self) -> Result<&'a String, WatchListError> {
+        WatchList::list_get_random_weighted(self)
+    }
+
+    fn list_search(&self, title: &str, search: &str) -> Result<Vec<&Item>, WatchListError> {
+        WatchList::list_search(self, title, search)
+    }
+
+    fn list_search_fuzzy(&self, title: &str, search: &str) -> Result<Vec<&Item>, WatchListError> {
+        WatchList::list_search_fuzzy(self, title, search)
+    }
+
+    fn list_search_regex(&self, title: &str, pattern: &str) -> Result<Vec<&Item>, WatchListError> {
+        WatchList::list_search_regex(self, title, pattern)
+    }
+
+    fn search_all(&self, search: &str) -> Vec<(&String, Vec<&Item>)> {
+        WatchList::search_all(self, search)
+    }
+
+    fn stats(&self) -> stats::Stats {
+        WatchList::stats(self)
+    }
+}
+
+/// Items of the list, panicking if there is no such list like indexing a `HashMap` does
+impl<Q> std::ops::Index<&Q> for WatchList
+where
+    Q: std::hash::Hash + Eq + ?Sized,
+    String: std::borrow::Borrow<Q>,
+{
+    type Output = Vec<Item>;
+
+    fn index(&self, title: &Q) -> &Vec<Item> {
+        &self.lists[title]
+    }
+}
+
+impl FromIterator<(String, Vec<Item>)> for WatchList {
+    fn from_iter<T: IntoIterator<Item = (String, Vec<Item>)>>(lists: T) -> Self {
+        WatchList {
+            lists: lists.into_iter().collect(),
+            names: HashMap::new(),
+            settings: HashMap::new(),
+            validator: validation::Validator::default(),
+            compact: false,
+        }
+    }
+}
+
+impl Extend<(String, Vec<Item>)> for WatchList {
+    fn extend<T: IntoIterator<Item = (String, Vec<Item>)>>(&mut self, lists: T) {
+        for (title, items) in lists {
+            self.insert(title, items);
+        }
+    }
+}
+
+impl IntoIterator for WatchList {
+    type Item = (String, Vec<Item>);
+    type IntoIter = std::collections::hash_map::IntoIter<String, Vec<Item>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.lists.into_iter()
+    }
+}
+
+impl<'w> IntoIterator for &'w WatchList {
+    type Item = (&'w String, &'w Vec<Item>);
+    type IntoIter = std::collections::hash_map::Iter<'w, String, Vec<Item>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.lists.iter()
+    }
+}
+
+/// Checks if a list is grouped under a category, see [`WatchList::category_lists`]
+pub fn in_category(title: &str, category: &str) -> bool {
+    title
+        .strip_prefix(category)
+        .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Gets the path of a file stored alongside the data file
+///
+/// For example, the `tokens` file for `watchlist.json` is `watchlist.tokens.json`
+pub fn sidecar_path(file_path: &Path, name: &str) -> PathBuf {
+    let stem = file_path
+        .file_stem()
+        .map(|s| s.to_string_lossy())
+        .unwrap_or_default();
+    file_path.with_file_name(format!("{stem}.{name}.json"))
+}
+
+/// Maps each value in order, spread across every core with the `parallel` feature
+pub(crate) fn par_map<'a, T, U, F>(values: &'a [T], f: F) -> Vec<U>
+where
+    T: Sync,
+    U: Send,
+    F: Fn(&'a T) -> U + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        values.par_iter().map(f).collect()
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        values.iter().map(f).collect()
+    }
+}
+
+/// Fails with the question instead of waiting for an answer which can't come, when stdin
+/// isn't a terminal or questions are answered without asking because `yes` is given
+pub fn require_terminal(question: &str, yes: bool) -> Result<(), WatchListError> {
+    match yes || !io::stdin().is_terminal() {
+        true => Err(WatchListError::NotInteractive(question.trim().to_string())),
+        false => Ok(()),
+    }
+}
+
+/// Asks for a line of input, which needs someone to answer it, see [`require_terminal`]
+pub fn input(prompt: &str, trim_input: bool, yes: bool) -> Result<String, WatchListError> {
+    require_terminal(prompt, yes)?;
+    let mut input = String::new();
+    print!("{prompt}");
+    io::stdout().flush()?;
+    io::stdin().read_line(&mut input)?;
+
+    if trim_input {
+        return Ok(input.trim().to_string());
+    }
+    Ok(input)
+}
+
+/// Asks for a line of input where nothing entered means the default answer, which is
+/// taken without asking when `yes` is given
+pub fn input_or_default(prompt: &str, yes: bool) -> Result<String, WatchListError> {
+    match yes {
+        true => Ok(String::new()),
+        false => input(prompt, true, yes),
+    }
+}
+
+/// Asks a yes or no question defaulting to no, agreed to without asking when `yes` is given
+pub fn confirm(prompt: &str, yes: bool) -> Result<bool, WatchListError> {
+    match yes {
+        true => Ok(true),
+        false => Ok(input(prompt, true, yes)?.to_lowercase() == "y"),
+    }
+}
+
+/// Shows the numbered titles under a heading, failing when the output can't be written,
+/// like when the program reading it closed it, see [`output::is_closed`]
+pub fn list_display<T>(list: &[T], title: &str) -> Result<(), WatchListError>
+where
+    T: Display + Sized,
+{
+    output::write_list(&mut io::stdout().lock(), list, title)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::{collection, option, prelude::*};
+    use std::collections::HashSet;
+
+    fn setup() -> WatchList {
+        let data = r#"
+        {
+            "Movies": [ 
+                "Movie 1", 
+                "Movie 2", 
+                "Movie 3", 
+                "Movie 4" 
+            ],
+
+            "Manga": [ 
+                "Manga 1", 
+                "Manga 2", 
+                "Manga 3", 
+                "Manga 4" 
+            ]
+
+        }"#;
+        serde_json::from_str(data).unwrap()
+    }
+
+    // Text with unicode, control characters and odd whitespace
+    fn text() -> impl Strategy<Value = String> {
+        proptest::string::string_regex("(?s)[ a-z\\PC\\s\u{a0}\u{200b}\u{feff}\"',]{0,12}").unwrap()
+    }
+
+    fn metadata() -> impl Strategy<Value = Metadata> {
+        let progress = prop_oneof![
+            (1..100u32, 1..100u32)
+                .prop_map(|(season, episode)| Progress::Episode { season, episode }),
+            (1..1000u32).prop_map(Progress::Chapter),
+        ];
+        (
+            (option::of(text()), option::of(any::<u16>())),
+            option::of(
+                (0..100_000i32)
+                    .prop_map(|d| NaiveDate::from_num_days_from_ce_opt(700_000 + d).unwrap()),
+            ),
+            (any::<bool>(), any::<bool>(), any::<bool>()),
+            option::of(text()),
+            collection::vec(text(), 0..3),
+            option::of(1..=10u8),
+            option::of(progress),
+            (
+                collection::btree_map("[a-z]{1,8}", text(), 0..3),
+                option::of(1..10_000u32),
+            ),
+        )
+            .prop_map(
+                |(
+                    (url, year),
+                    due,
+                    (watched, dropped, pinned),
+                    poster,
+                    tags,
+                    rating,
+                    progress,
+                    (ids, runtime),
+                )| {
+                    Metadata {
+                        url,
+                        year,
+                        due,
+                        watched,
+                        dropped,
+                        poster,
+                        tags,
+                        rating,
+                        progress,
+                        ids,
+                        runtime,
+                        pinned,
+                        added_at: None,
+                    }
+                },
+            )
+    }
+
+    /// Items with any title and metadata, most of them with no metadata at all
+    pub(crate) fn items() -> impl Strategy<Value = Item> {
+        prop_oneof![
+            text().prop_map(Item::new),
+            (text(), metadata()).prop_map(|(title, meta)| Item { title, meta }),
+        ]
+    }
+
+    pub(crate) fn watchlists() -> impl Strategy<Value = WatchList> {
+        collection::hash_map(text(), collection::vec(items(), 0..5), 0..5)
+            .prop_map(WatchList::from_iter)
+    }
+
+    #[test]
+    fn add() {
+        let mut watchlist = setup();
+        watchlist
+            .item_add("Movies", "Movie 5", DuplicatePolicy::Exact)
+            .unwrap();
+        watchlist
+            .item_add("Movies", "Movie 10", DuplicatePolicy::Exact)
+            .unwrap();
+        watchlist
+            .item_add("Manga", "Manga 100", DuplicatePolicy::Exact)
+            .unwrap();
+        assert_eq!(
+            watchlist["Movies"],
+            vec!["Movie 1", "Movie 2", "Movie 3", "Movie 4", "Movie 5", "Movie 10"]
+        );
+
+        assert_eq!(
+            watchlist["Manga"],
+            vec!["Manga 1", "Manga 2", "Manga 3", "Manga 4", "Manga 100"]
+        );
+    }
+
+    #[test]
+    fn add_errors() {
+        let mut watchlist = setup();
+        assert_eq!(
+            watchlist
+                .item_add("TV", "Manga 999", DuplicatePolicy::Exact)
+                .err()
+                .unwrap(),
+            WatchListError::TitleNotPresent("TV".to_string(), Vec::new())
+        );
+
+        assert_eq!(
+            watchlist
+                .item_add("Movies", "Movie 1", DuplicatePolicy::Exact)
+                .err()
+                .unwrap(),
+            WatchListError::ItemAlreadyPresent("Movie 1".to_string(), "Movies".to_string())
+        );
+    }
+
+    #[test]
+    fn suggestions() {
+        let watchlist = setup();
+        let error = watchlist.item_get_all("movis").err().unwrap();
+        assert_eq!(
+            error,
+            WatchListError::TitleNotPresent("movis".to_string(), Vec::new())
+        );
+        assert_eq!(
+            error.with_suggestions(&watchlist),
+            WatchListError::TitleNotPresent("movis".to_string(), vec!["Movies".to_string()])
+        );
+        assert_eq!(
+            WatchListError::NoTitles.with_suggestions(&watchlist),
+            WatchListError::NoTitles
+        );
+    }
+
+    #[test]
+    fn duplicate_policies() {
+        let mut watchlist = setup();
+        watchlist
+            .item_add("Movies", " movie  1 ", DuplicatePolicy::Exact)
+            .unwrap();
+        assert_eq!(
+            watchlist
+                .item_add("Movies", "MOVIE 2", DuplicatePolicy::Normalized)
+                .err()
+                .unwrap(),
+            WatchListError::ItemAlreadyPresent("MOVIE 2".to_string(), "Movies".to_string())
+        );
+        watchlist
+            .item_add("Movies", "Movie 3", DuplicatePolicy::Allow)
+            .unwrap();
+        assert_eq!(watchlist["Movies"].len(), 6);
+
+        watchlist
+            .item_add("Movies", "Amélie", DuplicatePolicy::Normalized)
+            .unwrap();
+        assert!(DuplicatePolicy::Normalized.same("amélie", "Amélie"));
+        assert!(!DuplicatePolicy::Normalized.same("Amelie", "Amélie"));
+        assert_eq!(
+            watchlist
+                .item_add("Movies", " amelie", DuplicatePolicy::Folded)
+                .err()
+                .unwrap(),
+            WatchListError::ItemAlreadyPresent(" amelie".to_string(), "Movies".to_string())
+        );
+    }
+
+    #[test]
+    fn duplicates_after_changes() {
+        let mut watchlist = setup();
+        let exact = DuplicatePolicy::Exact;
+        watchlist.item_add("Movies", "Dune", exact).unwrap();
+        watchlist.item_rename("Movies", "Dune", "Dune 2").unwrap();
+        watchlist.item_add("Movies", "Dune", exact).unwrap();
+        assert!(watchlist.item_add("Movies", "Dune 2", exact).is_err());
+
+        watchlist.item_remove("Movies", "Dune").unwrap();
+        watchlist.item_add("Movies", "Dune", exact).unwrap();
+        let last = watchlist["Movies"].len() - 1;
+        watchlist.item_remove_positions("Movies", &[last]).unwrap();
+        watchlist.item_add("Movies", "Dune", exact).unwrap();
+
+        // Lists changed directly are checked against what they have now
+        watchlist.get_mut("Movies").unwrap()[0].title = "Alien".to_string();
+        assert!(watchlist.item_add("Movies", "Alien", exact).is_err());
+        watchlist.insert("Movies", vec![]);
+        watchlist.item_add("Movies", "Alien", exact).unwrap();
+        for (_, items) in watchlist.iter_mut() {
+            items.clear();
+        }
+        watchlist.item_add("Movies", "Alien", exact).unwrap();
+        watchlist.extend([("Movies".to_string(), vec![])]);
+        watchlist.item_add("Movies", "Alien", exact).unwrap();
+        assert_eq!(watchlist["Movies"], vec!["Alien"]);
+    }
+
+    #[test]
+    fn folded_search() {
+        let mut watchlist = WatchList::new();
+        watchlist.insert(
+            "Anime".to_string(),
+            vec![Item::new("Pokémon"), Item::new("Digimon")],
+        );
+        watchlist.insert("Films".to_string(), vec![Item::new("POKEMON 2000")]);
+        assert!(watchlist
+            .list_search("Anime", "pokemon")
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            watchlist.list_search_folded("Anime", "pokemon").unwrap(),
+            vec![&Item::new("Pokémon")]
+        );
+        let matches = watchlist
+            .list_search_folded_matches("Anime", "KÉMON")
+            .unwrap();
+        assert_eq!(matches[0].spans, vec![2..8]);
+        assert_eq!(watchlist.search_all("pokémon").len(), 1);
+        assert_eq!(watchlist.search_all_folded("pokémon").len(), 2);
+        assert_eq!(
+            watchlist.list_search_folded("Books", "").err().unwrap(),
+            WatchListError::TitleNotPresent("Books".to_string(), Vec::new())
+        );
+    }
+
+    #[test]
+    fn regex_search() {
+        let watchlist = setup();
+        let titles = |pattern: &str| -> Vec<&str> {
+            watchlist
+                .list_search_regex("Movies", pattern)
+                .unwrap()
+                .iter()
+                .map(|i| i.title.as_str())
+                .collect()
+        };
+        assert_eq!(titles("^movie [13]$"), vec!["Movie 1", "Movie 3"]);
+        assert_eq!(titles("(?-i)^movie"), Vec::<&str>::new());
+        assert!(matches!(
+            watchlist.list_search_regex("Movies", "movie ("),
+            Err(WatchListError::InvalidPattern(_))
+        ));
+        assert_eq!(
+            watchlist.list_search_regex("Books", "").err().unwrap(),
+            WatchListError::TitleNotPresent("Books".to_string(), Vec::new())
+        );
+    }
+
+    #[test]
+    fn match_spans() {
+        let mut watchlist = WatchList::new();
+        watchlist.insert(
+            "Anime".to_string(),
+            vec![Item::new("Pokémon Movie"), Item::new("Movie Movie")],
+        );
+        let spans = |matches: Vec<Match>| -> Vec<Vec<Range<usize>>> {
+            matches.into_iter().map(|m| m.spans).collect()
+        };
+        assert_eq!(
+            spans(watchlist.list_search_matches("Anime", "MOVIE").unwrap()),
+            vec![vec![9..14], vec![0..5, 6..11]]
+        );
+        assert_eq!(
+            spans(
+                watchlist
+                    .list_search_regex_matches("Anime", "mon|^m")
+                    .unwrap()
+            ),
+            vec![vec![5..8], vec![0..1]]
+        );
+        // Patterns which only match between letters don't highlight anything
+        assert_eq!(
+            spans(watchlist.list_search_regex_matches("Anime", "^").unwrap()),
+            vec![Vec::<Range<usize>>::new(); 2]
+        );
+        assert!(Item::new("Up").match_spans("").is_empty());
+    }
+
+    #[test]
+    fn searching_all() {
+        let mut watchlist = setup();
+        watchlist.insert("Books".to_string(), vec![Item::new("Manga Guide")]);
+        watchlist.insert("Empty".to_string(), vec![]);
+        let results = watchlist.search_all("MANGA");
+        let titles: Vec<&str> = results.iter().map(|(t, _)| t.as_str()).collect();
+        assert_eq!(titles, vec!["Books", "Manga"]);
+        assert_eq!(results[1].1.len(), 4);
+        assert!(watchlist.search_all("Anime").is_empty());
+    }
+
+    #[test]
+    fn exit_codes() {
+        use WatchListError::*;
+        let s = String::new;
+        let errors = [
+            IOError(io::ErrorKind::Other.into()),
+            NoMatches(s()),
+            NoTitles,
+            EmptyList(s()),
+            TitleAlreadyPresent(s()),
+            TitleNotPresent(s(), Vec::new()),
+            ItemAlreadyPresent(s(), s()),
+            ItemToRemoveNotPresent(s()),
+            FolderNotPresent(s()),
+            ParseError(s()),
+            NetworkError(s()),
+            InvalidDate(s()),
+            InvalidRating(0),
+            InvalidProgress(s()),
+            UnknownField(s()),
+            LimitExceeded(s()),
+            ProfileAlreadyPresent(s()),
+            ProfileNotPresent(s()),
+            InvalidProfile(s()),
+            InvalidQuery(s()),
+            InvalidRuntime(s()),
+            InvalidPattern(s()),
+            UpgradeRequired(2),
+            ValidationFailed(s(), s()),
+            WorkspaceNotPresent(s()),
+            EncryptionError(s()),
+            GitError(s()),
+            RemoteChanged(s()),
+            InvalidUrl(s()),
+            NoUrl(s()),
+        ];
+        let codes: HashSet<u8> = errors.iter().map(|e| e.exit_code()).collect();
+        assert_eq!(codes.len(), errors.len());
+        // 2 is left for invalid arguments
+        assert!(!codes.contains(&0) && !codes.contains(&2));
+    }
+
+    #[test]
+    fn lists() {
+        let mut watchlist = setup();
+        assert!(watchlist.contains("Movies") && !watchlist.contains("Books"));
+        assert_eq!(watchlist.get("Books"), None);
+        watchlist.get_or_insert("Books").push(Item::new("Dune"));
+        assert_eq!(watchlist.remove("Books"), Some(vec![Item::new("Dune")]));
+
+        // Saved as a plain object of lists, the same as before it was a struct
+        let json = serde_json::to_string(&watchlist).unwrap();
+        let map: HashMap<String, Vec<Item>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(map.len(), watchlist.len());
+        assert_eq!(map.into_iter().collect::<WatchList>(), watchlist);
+    }
+
+    #[test]
+    fn json_strings() {
+        let watchlist = WatchList::from_json(r#"{"Movies":["Alien"]}"#).unwrap();
+        assert_eq!(watchlist["Movies"], vec!["Alien"]);
+        assert_eq!(
+            WatchList::from_json(&watchlist.to_json().unwrap()).unwrap(),
+            watchlist
+        );
+        assert!(matches!(
+            WatchList::from_json(r#"{"version":99,"lists":{}}"#),
+            Err(WatchListError::ParseError(_))
+        ));
+
+        let mut compact = watchlist.clone();
+        compact.set_compact(true);
+        assert_eq!(compact.to_json().unwrap().lines().count(), 1);
+        // Only the lists it's set on are saved compact
+        assert!(watchlist.to_json().unwrap().lines().count() > 1);
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn concurrent_updates() {
+        let path = std::env::temp_dir().join(format!("wlist-updates-{}.json", std::process::id()));
+        WatchList::update_file(&path, |w| w.list_add("Movies")).unwrap();
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    WatchList::update_file(&path, |w| {
+                        w.item_add("Movies", format!("Movie {i}"), DuplicatePolicy::Exact)
+                    })
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap().unwrap();
+        }
+        let watchlist = WatchList::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(lock::lock_path(&path)).unwrap();
+        // None of the items were lost to another thread saving at the same time
+        assert_eq!(watchlist["Movies"].len(), 8);
+    }
+
+    #[test]
+    fn error_messages() {
+        use std::error::Error;
+        let missing =
+            WatchListError::TitleNotPresent("Movis".to_string(), vec!["Movies".to_string()]);
+        assert_eq!(
+            missing.to_string(),
+            "No such list - Movis! Did you mean Movies?"
+        );
+        assert!(missing.source().is_none());
+
+        let io_error: WatchListError = io::Error::new(io::ErrorKind::NotFound, "gone").into();
+        assert_eq!(io_error.to_string(), "gone");
+        assert_eq!(io_error.source().unwrap().to_string(), "gone");
+
+        // Files which aren't valid JSON can't be read
+        assert!(matches!(
+            WatchList::from_json("[not json"),
+            Err(WatchListError::ParseError(_))
+        ));
+        #[cfg(feature = "fs")]
+        {
+            let path =
+                std::env::temp_dir().join(format!("wlist-invalid-{}.json", std::process::id()));
+            std::fs::write(&path, "[not json").unwrap();
+            let result = WatchList::from_file(&path);
+            std::fs::remove_file(&path).unwrap();
+            assert!(matches!(result, Err(WatchListError::ParseError(_))));
+        }
+    }
+
+    #[test]
+    fn remove() {
+        let mut watchlist = setup();
+        watchlist.item_remove("Movies", "Movie 3").unwrap();
+        watchlist.item_remove("Manga", "Manga 3").unwrap();
+        watchlist.item_remove("Manga", "Manga 1").unwrap();
+        assert_eq!(watchlist["Movies"], vec!["Movie 1", "Movie 2", "Movie 4"]);
+        assert_eq!(watchlist["Manga"], vec!["Manga 2", "Manga 4"]);
+    }
+
+    #[test]
+    fn remove_errors() {
+        let mut watchlist = setup();
+        assert_eq!(
+            watchlist.item_remove("TV", "Manga 999").err().unwrap(),
+            WatchListError::TitleNotPresent("TV".to_string(), Vec::new())
+        );
+
+        assert_eq!(
+            watchlist.item_remove("Movies", "Manga 999").err().unwrap(),
+            WatchListError::ItemToRemoveNotPresent("Manga 999".to_string())
+        );
+    }
+
+    #[test]
+    fn rename() {
+        let mut watchlist = setup();
+        watchlist
+            .item_add(
+                "Movies",
+                Item::new("Movei 5").with_year(2023),
+                DuplicatePolicy::Exact,
+            )
+            .unwrap();
+        watchlist
+            .item_rename("Movies", "Movei 5", "Movie 5")
+            .unwrap();
+        assert_eq!(watchlist["Movies"][4], Item::new("Movie 5").with_year(2023));
+    }
+
+    #[test]
+    fn rename_errors() {
+        let mut watchlist = setup();
+        assert_eq!(
+            watchlist
+                .item_rename("TV", "Movie 1", "Movie 9")
+                .err()
+                .unwrap(),
+            WatchListError::TitleNotPresent("TV".to_string(), Vec::new())
+        );
+
+        assert_eq!(
+            watchlist
+                .item_rename("Movies", "Movie 9", "Movie 10")
+                .err()
+                .unwrap(),
+            WatchListError::ItemToRemoveNotPresent("Movie 9".to_string())
+        );
+
+        assert_eq!(
+            watchlist
+                .item_rename("Movies", "Movie 1", "Movie 2")
+                .err()
+                .unwrap(),
+            WatchListError::ItemAlreadyPresent("Movie 2".to_string(), "Movies".to_string())
+        );
+    }
+
+    #[test]
+    fn copy_and_move() {
+        let mut watchlist = setup();
+        watchlist.item_copy("Movies", "Manga", "Movie 1").unwrap();
+        watchlist.item_move("Movies", "Manga", "Movie 2").unwrap();
+        assert_eq!(watchlist["Movies"], vec!["Movie 1", "Movie 3", "Movie 4"]);
+        assert_eq!(
+            watchlist["Manga"],
+            vec!["Manga 1", "Manga 2", "Manga 3", "Manga 4", "Movie 1", "Movie 2"]
+        );
+    }
+
+    #[test]
+    fn copy_and_move_errors() {
+        let mut watchlist = setup();
+        assert_eq!(
+            watchlist.item_move("TV", "Movies", "Show 1").err().unwrap(),
+            WatchListError::TitleNotPresent("TV".to_string(), Vec::new())
+        );
+
+        assert_eq!(
+            watchlist
+                .item_move("Movies", "TV", "Movie 1")
+                .err()
+                .unwrap(),
+            WatchListError::TitleNotPresent("TV".to_string(), Vec::new())
+        );
+
+        assert_eq!(
+            watchlist
+                .item_copy("Movies", "Manga", "Movie 9")
+                .err()
+                .unwrap(),
+            WatchListError::ItemToRemoveNotPresent("Movie 9".to_string())
+        );
+
+        watchlist.item_copy("Movies", "Manga", "Movie 1").unwrap();
+        assert_eq!(
+            watchlist
+                .item_move("Movies", "Manga", "Movie 1")
+                .err()
+                .unwrap(),
+            WatchListError::ItemAlreadyPresent("Movie 1".to_string(), "Manga".to_string())
+        );
+        assert_eq!(watchlist["Movies"].len(), 4);
+    }
+
+    #[test]
+    fn tags() {
+        let mut watchlist = setup();
+        let alien = Item::new("Alien").with_tags(&["horror", "Classic", "horror", " "]);
+        assert_eq!(alien.meta.tags, vec!["horror", "Classic"]);
+        watchlist
+            .item_add("Movies", alien, DuplicatePolicy::Exact)
+            .unwrap();
+        watchlist
+            .item_add(
+                "Movies",
+                Item::new("Saw").with_tags(&["horror"]),
+                DuplicatePolicy::Exact,
+            )
+            .unwrap();
+
+        let tagged = |tags: &[&str]| -> Vec<String> {
+            let tags: Vec<String> = tags.iter().map(|t| t.to_string()).collect();
+            watchlist
+                .item_get_tagged("Movies", &tags)
+                .unwrap()
+                .iter()
+                .map(|i| i.title.clone())
+                .collect()
+        };
+        assert_eq!(tagged(&["Horror"]), vec!["Alien", "Saw"]);
+        assert_eq!(tagged(&["horror", "classic"]), vec!["Alien"]);
+        assert_eq!(tagged(&["comedy"]), Vec::<String>::new());
+        assert_eq!(tagged(&[]).len(), 6);
     }
-    Ok(input)
-}
 
-pub fn list_display<T>(list: &[T], title: &str)
-where
-    T: Display + Sized,
-{
-    println!("{: ^15}", title.italic().underline());
+    #[test]
+    fn ratings() {
+        let mut watchlist = setup();
+        watchlist.item_set_rating("Movies", "Movie 3", 9).unwrap();
+        watchlist.item_set_rating("Movies", "Movie 2", 4).unwrap();
+        watchlist.item_set_rating("Movies", "Movie 2", 7).unwrap();
+        assert_eq!(watchlist["Movies"][1].meta.rating, Some(7));
 
-    for (i, item) in list.iter().enumerate() {
-        println!("{: >5}. | {: <10}", format!("{}", i + 1).bold(), item);
+        assert_eq!(
+            watchlist.item_get_sorted("Movies", SortBy::Rating).unwrap(),
+            vec!["Movie 3", "Movie 2", "Movie 1", "Movie 4"]
+        );
+        assert_eq!(
+            watchlist.item_get_sorted("Movies", SortBy::Added).unwrap(),
+            vec!["Movie 1", "Movie 2", "Movie 3", "Movie 4"]
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn sorting() {
+        let mut watchlist = setup();
+        watchlist
+            .item_add("Movies", Item::new("alien"), DuplicatePolicy::Exact)
+            .unwrap();
+        watchlist
+            .item_add("Movies", Item::new("Zodiac"), DuplicatePolicy::Exact)
+            .unwrap();
+        assert_eq!(
+            watchlist.item_get_sorted("Movies", SortBy::Alpha).unwrap(),
+            vec!["alien", "Movie 1", "Movie 2", "Movie 3", "Movie 4", "Zodiac"]
+        );
 
-    fn setup() -> WatchList {
-        let data = r#"
-        {
-            "Movies": [ 
-                "Movie 1", 
-                "Movie 2", 
-                "Movie 3", 
-                "Movie 4" 
-            ],
+        let mut shuffled = watchlist.item_get_sorted("Movies", SortBy::Random).unwrap();
+        shuffled.sort_by_key(|i| i.title.to_lowercase());
+        assert_eq!(
+            shuffled,
+            watchlist.item_get_sorted("Movies", SortBy::Alpha).unwrap()
+        );
+        assert_eq!(
+            serde_json::from_str::<SortBy>(r#""alpha""#).unwrap(),
+            SortBy::Alpha
+        );
+        assert!(matches!(
+            watchlist.item_get_sorted("Films", SortBy::Alpha),
+            Err(WatchListError::TitleNotPresent(..))
+        ));
+    }
 
-            "Manga": [ 
-                "Manga 1", 
-                "Manga 2", 
-                "Manga 3", 
-                "Manga 4" 
-            ]
+    #[test]
+    fn by_index() {
+        let mut watchlist = setup();
+        watchlist.item_set_rating("Movies", "Movie 3", 9).unwrap();
+        watchlist
+            .item_add("Movies", "Movie 1", DuplicatePolicy::Allow)
+            .unwrap();
+        watchlist.item_set_rating("Movies", "Movie 1", 5).unwrap();
+        assert_eq!(
+            watchlist
+                .item_get_index("Movies", 1, SortBy::Added)
+                .unwrap(),
+            "Movie 2"
+        );
+        assert_eq!(
+            watchlist
+                .item_get_index("Movies", 0, SortBy::Rating)
+                .unwrap(),
+            "Movie 3"
+        );
+        assert_eq!(
+            watchlist.item_get_index("Movies", 5, SortBy::Added),
+            Err(WatchListError::ItemToRemoveNotPresent("Item 6".to_string()))
+        );
 
-        }"#;
-        serde_json::from_str(data).unwrap()
+        // Only the copy at the index is removed, not the first with the same title
+        let removed = watchlist
+            .item_remove_index("Movies", 4, SortBy::Added)
+            .unwrap();
+        assert_eq!(removed, Item::new("Movie 1"));
+        assert_eq!(watchlist["Movies"][0].meta.rating, Some(5));
+        assert_eq!(watchlist["Movies"].len(), 4);
+        assert!(matches!(
+            watchlist.item_remove_index("Films", 0, SortBy::Added),
+            Err(WatchListError::TitleNotPresent(..))
+        ));
+
+        // Every index is checked before any item is removed
+        assert_eq!(
+            watchlist.item_remove_indices("Movies", &[0, 9], SortBy::Added),
+            Err(WatchListError::ItemToRemoveNotPresent(
+                "Item 10".to_string()
+            ))
+        );
+        assert_eq!(watchlist["Movies"].len(), 4);
+        let removed = watchlist
+            .item_remove_indices("Movies", &[0, 3, 0], SortBy::Rating)
+            .unwrap();
+        assert_eq!(removed, vec!["Movie 3", "Movie 4"]);
+        assert_eq!(watchlist["Movies"], vec!["Movie 1", "Movie 2"]);
     }
 
     #[test]
-    fn add() {
+    fn pinning_and_moving() {
         let mut watchlist = setup();
-        watchlist.item_add("Movies", "Movie 5", false).unwrap();
-        watchlist.item_add("Movies", "Movie 10", false).unwrap();
-        watchlist.item_add("Manga", "Manga 100", false).unwrap();
+        watchlist
+            .item_set_pinned("Movies", "Movie 3", true)
+            .unwrap();
+        watchlist.item_set_rating("Movies", "Movie 4", 9).unwrap();
+        let titles = |sort| -> Vec<String> {
+            let items = watchlist.item_get_sorted("Movies", sort).unwrap();
+            items.iter().map(|i| i.title.clone()).collect()
+        };
+        assert_eq!(
+            titles(SortBy::Added),
+            ["Movie 3", "Movie 1", "Movie 2", "Movie 4"]
+        );
+        assert_eq!(
+            titles(SortBy::Rating),
+            ["Movie 3", "Movie 4", "Movie 1", "Movie 2"]
+        );
+        assert_eq!(titles(SortBy::Random)[0], "Movie 3");
+
+        // Moving changes the order the items were added in
+        assert_eq!(watchlist.item_move_to("Movies", "Movie 1", 9), Ok(3));
+        assert_eq!(watchlist.item_move_to("Movies", "Movie 4", 0), Ok(0));
         assert_eq!(
             watchlist["Movies"],
-            vec!["Movie 1", "Movie 2", "Movie 3", "Movie 4", "Movie 5", "Movie 10"]
+            vec!["Movie 4", "Movie 2", "Movie 3", "Movie 1"]
+        );
+        assert_eq!(watchlist.item_position("Movies", "Movie 3"), Ok(2));
+        assert_eq!(
+            watchlist.item_move_to("Movies", "Movie 9", 0),
+            Err(WatchListError::ItemToRemoveNotPresent(
+                "Movie 9".to_string()
+            ))
         );
 
+        let removed = watchlist.item_remove_positions("Movies", &[3, 0]).unwrap();
+        assert_eq!(removed, vec!["Movie 1", "Movie 4"]);
         assert_eq!(
-            watchlist["Manga"],
-            vec!["Manga 1", "Manga 2", "Manga 3", "Manga 4", "Manga 100"]
+            watchlist.item_remove_positions("Movies", &[2]),
+            Err(WatchListError::ItemToRemoveNotPresent("Item 3".to_string()))
         );
     }
 
     #[test]
-    fn add_errors() {
+    fn queue() {
         let mut watchlist = setup();
+        watchlist
+            .item_set_status("Movies", "Movie 1", Status::Dropped)
+            .unwrap();
         assert_eq!(
-            watchlist.item_add("TV", "Manga 999", false).err().unwrap(),
-            WatchListError::TitleNotPresent("TV".to_string())
+            watchlist.item_get_next("Movies").unwrap().unwrap().title,
+            "Movie 2"
         );
+        watchlist
+            .item_set_pinned("Movies", "Movie 4", true)
+            .unwrap();
 
+        let finished = watchlist.item_finish_next("Movies").unwrap().unwrap();
+        assert_eq!(finished.title, "Movie 4");
+        assert_eq!(finished.status(), Status::Watched);
+        let popped = watchlist.item_pop_next("Movies").unwrap().unwrap();
+        assert_eq!(popped.title, "Movie 2");
+        assert_eq!(watchlist["Movies"], vec!["Movie 1", "Movie 3", "Movie 4"]);
+
+        watchlist.item_pop_next("Movies").unwrap();
+        assert_eq!(watchlist.item_get_next("Movies"), Ok(None));
+        assert_eq!(watchlist.item_pop_next("Movies"), Ok(None));
+    }
+
+    #[test]
+    fn rating_errors() {
+        let mut watchlist = setup();
+        for rating in [0, 11] {
+            assert_eq!(
+                watchlist
+                    .item_set_rating("Movies", "Movie 1", rating)
+                    .err()
+                    .unwrap(),
+                WatchListError::InvalidRating(rating)
+            );
+        }
         assert_eq!(
             watchlist
-                .item_add("Movies", "Movie 1", false)
+                .item_set_rating("Movies", "Movie 9", 5)
                 .err()
                 .unwrap(),
-            WatchListError::ItemAlreadyPresent("Movie 1".to_string(), "Movies".to_string())
+            WatchListError::ItemToRemoveNotPresent("Movie 9".to_string())
         );
     }
 
     #[test]
-    fn remove() {
+    fn external_ids() {
         let mut watchlist = setup();
-        watchlist.item_remove("Movies", "Movie 3").unwrap();
-        watchlist.item_remove("Manga", "Manga 3").unwrap();
-        watchlist.item_remove("Manga", "Manga 1").unwrap();
-        assert_eq!(watchlist["Movies"], vec!["Movie 1", "Movie 2", "Movie 4"]);
-        assert_eq!(watchlist["Manga"], vec!["Manga 2", "Manga 4"]);
+        watchlist
+            .item_set_id("Movies", "Movie 1", "IMDb", Some("tt0133093"))
+            .unwrap();
+        watchlist
+            .item_set_id("Movies", "Movie 1", "tmdb", Some("603"))
+            .unwrap();
+        let item = &watchlist["Movies"][0];
+        assert_eq!(item.id("imdb"), Some("tt0133093"));
+        assert_eq!(item.id("TMDB"), Some("603"));
+
+        watchlist
+            .item_set_id("Movies", "Movie 1", "tmdb", None)
+            .unwrap();
+        assert_eq!(watchlist["Movies"][0].id("tmdb"), None);
+        assert_eq!(
+            watchlist
+                .item_set_id("Movies", "Movie 9", "imdb", Some("tt1"))
+                .unwrap_err(),
+            WatchListError::ItemToRemoveNotPresent("Movie 9".to_string())
+        );
+
+        let matrix = Item::new("The Matrix").with_id("imdb", "tt0133093");
+        assert_eq!(
+            matrix.same_ids(&Item::new("Matrix").with_id("imdb", "tt0133093")),
+            Some(true)
+        );
+        assert_eq!(
+            matrix.same_ids(&matrix.clone().with_id("tmdb", "603")),
+            Some(true)
+        );
+        assert_eq!(
+            matrix.same_ids(&Item::new("The Matrix").with_id("imdb", "tt1")),
+            Some(false)
+        );
+        assert_eq!(
+            matrix.same_ids(&Item::new("The Matrix").with_id("tmdb", "603")),
+            None
+        );
     }
 
     #[test]
-    fn remove_errors() {
+    fn progress() {
+        let mut watchlist = setup();
+        let episode = Progress::Episode {
+            season: 3,
+            episode: 5,
+        };
+        watchlist
+            .item_set_progress("Manga", "Manga 2", Progress::Chapter(120))
+            .unwrap();
+        watchlist
+            .item_set_progress("Movies", "Movie 1", episode)
+            .unwrap();
+        watchlist
+            .item_set_progress("Movies", "Movie 4", Progress::Chapter(1))
+            .unwrap();
+        watchlist.get_mut("Movies").unwrap()[3].meta.watched = true;
+
+        assert_eq!(
+            watchlist.item_get_in_progress("Movies").unwrap(),
+            vec!["Movie 1"]
+        );
+        assert_eq!(
+            watchlist.item_get_in_progress("Manga").unwrap()[0]
+                .meta
+                .progress,
+            Some(Progress::Chapter(120))
+        );
+        assert_eq!(
+            serde_json::to_string(&watchlist["Movies"][0]).unwrap(),
+            r#"{"title":"Movie 1","progress":"s3e5"}"#
+        );
+        assert_eq!(
+            watchlist
+                .item_set_progress("Movies", "Movie 9", episode)
+                .err()
+                .unwrap(),
+            WatchListError::ItemToRemoveNotPresent("Movie 9".to_string())
+        );
+    }
+
+    #[test]
+    fn due_dates() {
         let mut watchlist = setup();
+        let date = NaiveDate::from_ymd_opt(2023, 3, 1).unwrap();
+        watchlist
+            .item_set_due("Movies", "Movie 2", Some(date))
+            .unwrap();
+        assert_eq!(watchlist["Movies"][1].meta.due, Some(date));
+        watchlist.item_set_due("Movies", "Movie 2", None).unwrap();
+        assert_eq!(watchlist["Movies"][1], Item::new("Movie 2"));
+
         assert_eq!(
-            watchlist.item_remove("TV", "Manga 999").err().unwrap(),
-            WatchListError::TitleNotPresent("TV".to_string())
+            watchlist
+                .item_set_due("Movies", "Movie 9", Some(date))
+                .err()
+                .unwrap(),
+            WatchListError::ItemToRemoveNotPresent("Movie 9".to_string())
         );
+    }
 
+    #[test]
+    fn urls() {
+        let mut watchlist = setup();
+        let url = "https://www.imdb.com/title/tt0078748/";
+        watchlist
+            .item_set_url("Movies", "Movie 2", Some(&format!(" {url} ")))
+            .unwrap();
+        assert_eq!(watchlist["Movies"][1], Item::new("Movie 2").with_url(url));
         assert_eq!(
-            watchlist.item_remove("Movies", "Manga 999").err().unwrap(),
-            WatchListError::ItemToRemoveNotPresent("Manga 999".to_string())
+            watchlist.item_set_url("Movies", "Movie 2", Some("/bin/sh")),
+            Err(WatchListError::InvalidUrl("/bin/sh".to_string()))
         );
+        watchlist.item_set_url("Movies", "Movie 2", None).unwrap();
+        assert_eq!(watchlist["Movies"][1], Item::new("Movie 2"));
     }
 
     #[test]
@@ -273,8 +3134,8 @@ mod tests {
         use rand::seq::SliceRandom;
         let mut rng = rand::thread_rng();
 
-        let mut watchlist = setup();
-        let lists = vec!["Movies".to_string(), "Manga".to_string()];
+        let watchlist = setup();
+        let lists = ["Movies".to_string(), "Manga".to_string()];
         let list = lists.choose(&mut rng).unwrap();
 
         let item = watchlist.item_get_random(list).unwrap().clone();
@@ -282,13 +3143,163 @@ mod tests {
         println!("{item} is present in {list}");
     }
 
+    #[test]
+    fn random_n() {
+        let mut watchlist = setup();
+        let items = watchlist.item_get_random_n("Movies", 2).unwrap();
+        assert_eq!(items.len(), 2);
+        assert_ne!(items[0], items[1]);
+        assert!(items.iter().all(|i| watchlist["Movies"].contains(i)));
+
+        let len = watchlist["Movies"].len();
+        assert_eq!(
+            watchlist
+                .item_get_random_n("Movies", len + 5)
+                .unwrap()
+                .len(),
+            len
+        );
+        assert!(watchlist.item_get_random_n("Movies", 0).unwrap().is_empty());
+
+        watchlist.insert("Empty".to_string(), vec![]);
+        assert_eq!(
+            watchlist.item_get_random_n("Empty", 3),
+            Err(WatchListError::EmptyList("Empty".to_string()))
+        );
+    }
+
+    #[test]
+    fn seeded_random() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let watchlist = setup();
+        let seeded = || StdRng::seed_from_u64(42);
+
+        let item = watchlist.item_get_random_with_rng("Movies", &mut seeded());
+        for _ in 0..5 {
+            assert_eq!(
+                watchlist.item_get_random_with_rng("Movies", &mut seeded()),
+                item
+            );
+        }
+        let items = watchlist.item_get_random_n_with_rng("Movies", 3, &mut seeded());
+        assert_eq!(
+            watchlist.item_get_random_n_with_rng("Movies", 3, &mut seeded()),
+            items
+        );
+        let list = watchlist.list_get_random_weighted_with_rng(&mut seeded());
+        assert_eq!(
+            watchlist.list_get_random_weighted_with_rng(&mut seeded()),
+            list
+        );
+        assert_eq!(
+            watchlist.list_get_random_with_rng(&mut seeded()),
+            watchlist.list_get_random_with_rng(&mut seeded())
+        );
+
+        let shuffle = || {
+            let mut items: Vec<&Item> = watchlist["Movies"].iter().collect();
+            SortBy::Random.sort_with_rng(&mut items, |i| i, &mut seeded());
+            items
+        };
+        assert_eq!(shuffle(), shuffle());
+    }
+
+    #[test]
+    fn random_weighted() {
+        let mut watchlist = setup();
+        watchlist.insert("Empty".to_string(), vec![]);
+        for _ in 0..20 {
+            assert_ne!(watchlist.list_get_random_weighted().unwrap(), "Empty");
+        }
+
+        let empty = WatchList::from_iter([("Empty".to_string(), vec![])]);
+        assert_eq!(empty.list_get_random_weighted().unwrap(), "Empty");
+        assert_eq!(
+            WatchList::new().list_get_random_weighted(),
+            Err(WatchListError::NoTitles)
+        );
+    }
+
+    #[test]
+    fn random_excluded() {
+        let mut watchlist = WatchList::new();
+        watchlist.insert("Finished".to_string(), vec![Item::new("Alien")]);
+        watchlist.insert("Abandoned".to_string(), vec![Item::new("Heat")]);
+        watchlist.insert("Backlog".to_string(), vec![Item::new("Dune")]);
+        for title in ["Finished", "Abandoned"] {
+            watchlist.list_settings_mut(title).unwrap().no_random = true;
+        }
+        assert!(watchlist.excluded_from_random("Finished"));
+        for _ in 0..20 {
+            assert_eq!(watchlist.list_get_random().unwrap(), "Backlog");
+            assert_eq!(watchlist.list_get_random_weighted().unwrap(), "Backlog");
+        }
+
+        watchlist.remove("Backlog");
+        assert_eq!(watchlist.list_get_random(), Err(WatchListError::NoTitles));
+        assert_eq!(
+            watchlist.list_get_random_weighted(),
+            Err(WatchListError::NoTitles)
+        );
+    }
+
+    #[test]
+    fn list_settings() {
+        let mut watchlist = WatchList::new();
+        watchlist.insert("Finished".to_string(), vec![Item::new("Alien")]);
+        let before = watchlist.clone();
+        watchlist.list_settings_mut("Finished").unwrap().no_random = true;
+        assert_ne!(watchlist, before);
+        assert_eq!(
+            watchlist.list_settings_mut("Movies"),
+            Err(WatchListError::TitleNotPresent(
+                "Movies".to_string(),
+                Vec::new()
+            ))
+        );
+
+        // Settings follow the list and go with it
+        watchlist.list_rename("Finished", "Done").unwrap();
+        assert!(watchlist.excluded_from_random("Done"));
+        assert!(!watchlist.excluded_from_random("Finished"));
+        watchlist.list_settings_mut("Done").unwrap().sort = SortBy::Rating;
+        watchlist.list_copy("Done", "Seen").unwrap();
+        assert!(watchlist.excluded_from_random("Seen"));
+        assert_eq!(watchlist.list_sort("Seen"), SortBy::Rating);
+        watchlist.list_remove("Done").unwrap();
+        watchlist.list_add("Done").unwrap();
+        assert_eq!(watchlist.list_settings("Done"), None);
+        let titles: Vec<&String> = watchlist.settings().map(|(title, _)| title).collect();
+        assert_eq!(titles, ["Seen"]);
+
+        // Settings back at their defaults are the same as none
+        *watchlist.list_settings_mut("Seen").unwrap() = ListSettings::default();
+        assert_eq!(watchlist.settings().count(), 0);
+
+        // Aliases are only ever for one list, and aren't copied
+        watchlist.list_add_alias("Done", "s").unwrap();
+        watchlist.list_add_alias("Seen", "s").unwrap();
+        assert_eq!(watchlist.list_for_alias("s").unwrap(), "Seen");
+        assert_eq!(watchlist.list_settings("Done"), None);
+        watchlist.list_copy("Seen", "Watched").unwrap();
+        assert_eq!(watchlist.list_settings("Watched"), None);
+        assert_eq!(
+            watchlist.list_add_alias("Seen", "Done"),
+            Err(WatchListError::TitleAlreadyPresent("Done".to_string()))
+        );
+        assert_eq!(watchlist.list_remove_alias("s").unwrap(), "Seen");
+        assert_eq!(watchlist.list_remove_alias("s"), None);
+        assert_eq!(watchlist.list_sort("Seen"), SortBy::Added);
+    }
+
     #[test]
     fn random_errors() {
         let mut watchlist = setup();
 
         assert_eq!(
             watchlist.item_get_random("Random!").err().unwrap(),
-            WatchListError::TitleNotPresent("Random!".to_string())
+            WatchListError::TitleNotPresent("Random!".to_string(), Vec::new())
         );
 
         watchlist.insert("NEW LIST".to_string(), vec![]);
@@ -328,8 +3339,195 @@ mod tests {
         let mut watchlist = setup();
         assert_eq!(
             watchlist.list_remove("TV").err().unwrap(),
-            WatchListError::TitleNotPresent("TV".to_string())
+            WatchListError::TitleNotPresent("TV".to_string(), Vec::new())
+        );
+    }
+
+    #[test]
+    fn renaming_title() {
+        let mut watchlist = setup();
+        watchlist.list_rename("Movies", "Films").unwrap();
+        assert_eq!(watchlist.get("Movies"), None);
+        assert_eq!(
+            watchlist["Films"],
+            vec!["Movie 1", "Movie 2", "Movie 3", "Movie 4"]
+        );
+    }
+
+    #[test]
+    fn renaming_title_errors() {
+        let mut watchlist = setup();
+        assert_eq!(
+            watchlist.list_rename("TV", "Shows").err().unwrap(),
+            WatchListError::TitleNotPresent("TV".to_string(), Vec::new())
+        );
+
+        assert_eq!(
+            watchlist.list_rename("Movies", "Manga").err().unwrap(),
+            WatchListError::TitleAlreadyPresent("Manga".to_string())
+        );
+        assert_eq!(watchlist["Movies"].len(), 4);
+    }
+
+    #[test]
+    fn categories() {
+        let mut watchlist = setup();
+        for title in [
+            "Anime/Series",
+            "Anime/Films",
+            "Anime/Films/Ghibli",
+            "Animation",
+        ] {
+            watchlist.list_add(title).unwrap();
+        }
+        assert_eq!(
+            watchlist.category_lists("Anime"),
+            vec!["Anime/Films", "Anime/Films/Ghibli", "Anime/Series"]
+        );
+        assert_eq!(
+            watchlist.category_lists("Anime/Films"),
+            vec!["Anime/Films/Ghibli"]
+        );
+        assert!(watchlist.category_lists("Movies").is_empty());
+
+        assert!(watchlist.is_category("Anime"));
+        // Lists aren't categories, even with lists under them
+        assert!(!watchlist.is_category("Anime/Films"));
+        assert!(!watchlist.is_category("Anim"));
+        assert!(!watchlist.is_category("Movies"));
+    }
+
+    #[test]
+    fn copying_title() {
+        let mut watchlist = setup();
+        watchlist.item_set_rating("Movies", "Movie 2", 8).unwrap();
+        watchlist.list_copy("Movies", "Films").unwrap();
+        assert_eq!(watchlist["Films"], watchlist["Movies"]);
+        assert_eq!(watchlist["Films"][1].meta.rating, Some(8));
+
+        // The copy is changed on its own
+        watchlist.item_remove("Films", "Movie 1").unwrap();
+        assert_eq!(watchlist["Movies"].len(), 4);
+
+        assert_eq!(
+            watchlist.list_copy("TV", "Shows").err().unwrap(),
+            WatchListError::TitleNotPresent("TV".to_string(), Vec::new())
+        );
+        assert_eq!(
+            watchlist.list_copy("Movies", "Manga").err().unwrap(),
+            WatchListError::TitleAlreadyPresent("Manga".to_string())
+        );
+    }
+
+    #[test]
+    fn merging_titles() {
+        let mut watchlist = setup();
+        watchlist.list_add("Films").unwrap();
+        watchlist
+            .item_add("Films", "Movie 2", DuplicatePolicy::Exact)
+            .unwrap();
+        watchlist
+            .item_add("Films", "Movie 9", DuplicatePolicy::Exact)
+            .unwrap();
+
+        let report = watchlist.list_merge("Films", "Movies").unwrap();
+        assert_eq!(
+            report,
+            MergeReport {
+                moved: 1,
+                duplicates: 1
+            }
+        );
+        assert_eq!(watchlist.get("Films"), None);
+        assert_eq!(
+            watchlist["Movies"],
+            vec!["Movie 1", "Movie 2", "Movie 3", "Movie 4", "Movie 9"]
+        );
+    }
+
+    #[test]
+    fn merging_titles_errors() {
+        let mut watchlist = setup();
+        assert_eq!(
+            watchlist.list_merge("TV", "Movies").err().unwrap(),
+            WatchListError::TitleNotPresent("TV".to_string(), Vec::new())
+        );
+
+        assert_eq!(
+            watchlist.list_merge("Movies", "TV").err().unwrap(),
+            WatchListError::TitleNotPresent("TV".to_string(), Vec::new())
+        );
+
+        // An item that isn't allowed stops the merge before any items are moved
+        watchlist.list_add("Films").unwrap();
+        for title in ["Movie 5", "Movie 6"] {
+            watchlist
+                .item_add("Films", title, DuplicatePolicy::Exact)
+                .unwrap();
+        }
+        watchlist.add_item_rule(|title| match title == "Movie 6" {
+            true => Err("it's banned".to_string()),
+            false => Ok(()),
+        });
+        assert_eq!(
+            watchlist.list_merge("Films", "Movies").err().unwrap(),
+            WatchListError::ValidationFailed("Movie 6".to_string(), "it's banned".to_string())
+        );
+        assert_eq!(watchlist["Films"], vec!["Movie 5", "Movie 6"]);
+        assert_eq!(watchlist["Movies"].len(), 4);
+
+        // Merging a list into itself leaves it alone
+        assert_eq!(
+            watchlist.list_merge("Movies", "Movies").unwrap(),
+            MergeReport::default()
+        );
+        assert_eq!(watchlist["Movies"].len(), 4);
+    }
+
+    proptest! {
+        #[test]
+        fn json_round_trip(watchlist in watchlists()) {
+            let data = serde_json::to_string(&watchlist).unwrap();
+            prop_assert_eq!(serde_json::from_str::<WatchList>(&data).unwrap(), watchlist);
+        }
+
+        #[test]
+        fn toml_round_trip(watchlist in watchlists()) {
+            let data = toml::to_string(&watchlist).unwrap();
+            prop_assert_eq!(toml::from_str::<WatchList>(&data).unwrap(), watchlist);
+        }
+
+        #[test]
+        fn messagepack_round_trip(watchlist in watchlists()) {
+            let data = rmp_serde::to_vec_named(&watchlist).unwrap();
+            prop_assert_eq!(rmp_serde::from_slice::<WatchList>(&data).unwrap(), watchlist);
+        }
+    }
+
+    #[test]
+    fn sidecar_paths() {
+        assert_eq!(
+            sidecar_path(Path::new("/home/user/watchlist.json"), "tokens"),
+            Path::new("/home/user/watchlist.tokens.json")
+        );
+        assert_eq!(
+            sidecar_path(Path::new("list"), "tokens"),
+            Path::new("list.tokens.json")
+        );
+    }
+
+    #[test]
+    fn item_serialization() {
+        let items = vec![
+            Item::new("Movie 1"),
+            Item::new("Movie 2").with_url("https://example.com"),
+        ];
+        let json = serde_json::to_string(&items).unwrap();
+        assert_eq!(
+            json,
+            r#"["Movie 1",{"title":"Movie 2","url":"https://example.com"}]"#
         );
+        assert_eq!(serde_json::from_str::<Vec<Item>>(&json).unwrap(), items);
     }
 
     /*#[test]
@@ -0,0 +1,143 @@
+//! Limits on the size of files read so that a broken or malicious file
+//! can't use up all the memory
+
+#[cfg(feature = "fs")]
+use std::fs::File;
+#[cfg(feature = "fs")]
+use std::io::{self, Read};
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+use crate::{Item, WatchList, WatchListError};
+
+/// Largest file which is read, in bytes
+pub const MAX_FILE_SIZE: u64 = 64 * 1024 * 1024;
+/// Most characters allowed in the title of an item or list
+pub const MAX_TITLE_LENGTH: usize = 4096;
+/// Most lists allowed in a watchlist
+pub const MAX_LISTS: usize = 10_000;
+/// Most items allowed in a list or a single import
+pub const MAX_ITEMS: usize = 1_000_000;
+
+#[cfg(feature = "fs")]
+fn too_large(file_path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "{} is larger than {} MiB",
+            file_path.display(),
+            MAX_FILE_SIZE / 1024 / 1024
+        ),
+    )
+}
+
+/// Opens a file, refusing ones larger than [`MAX_FILE_SIZE`]
+///
+/// Returns the file along with its size
+#[cfg(feature = "fs")]
+pub fn open_file(file_path: &Path) -> io::Result<(File, u64)> {
+    let file = File::open(file_path)?;
+    let len = file.metadata()?.len();
+    if len > MAX_FILE_SIZE {
+        return Err(too_large(file_path));
+    }
+    Ok((file, len))
+}
+
+/// Reads a file as text, refusing ones larger than [`MAX_FILE_SIZE`]
+#[cfg(feature = "fs")]
+pub fn read_file(file_path: &Path) -> io::Result<String> {
+    let (file, _) = open_file(file_path)?;
+    // The size is checked again while reading in case the file grows after being opened
+    let mut data = String::new();
+    file.take(MAX_FILE_SIZE + 1).read_to_string(&mut data)?;
+    if data.len() as u64 > MAX_FILE_SIZE {
+        return Err(too_large(file_path));
+    }
+    Ok(data)
+}
+
+fn check_title(title: &str) -> Result<(), WatchListError> {
+    if title.chars().count() > MAX_TITLE_LENGTH {
+        let start: String = title.chars().take(20).collect();
+        return Err(WatchListError::LimitExceeded(format!(
+            "the title starting with '{start}' is longer than {MAX_TITLE_LENGTH} characters"
+        )));
+    }
+    Ok(())
+}
+
+/// Checks the number of items and the length of their titles
+pub fn check_items(items: &[Item]) -> Result<(), WatchListError> {
+    if items.len() > MAX_ITEMS {
+        return Err(WatchListError::LimitExceeded(format!(
+            "there are more than {MAX_ITEMS} items"
+        )));
+    }
+    items.iter().try_for_each(|i| check_title(&i.title))
+}
+
+/// Checks the number of lists and the items in each of them
+pub fn check_watchlist(watchlist: &WatchList) -> Result<(), WatchListError> {
+    if watchlist.len() > MAX_LISTS {
+        return Err(WatchListError::LimitExceeded(format!(
+            "there are more than {MAX_LISTS} lists"
+        )));
+    }
+    for (title, items) in watchlist {
+        check_title(title)?;
+        check_items(items)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn titles() {
+        let long = "a".repeat(MAX_TITLE_LENGTH + 1);
+        assert!(check_items(&[Item::new("a".repeat(MAX_TITLE_LENGTH))]).is_ok());
+        assert_eq!(
+            check_items(&[Item::new("Dune"), Item::new(long.clone())]),
+            Err(WatchListError::LimitExceeded(format!(
+                "the title starting with '{}' is longer than {MAX_TITLE_LENGTH} characters",
+                "a".repeat(20)
+            )))
+        );
+
+        let mut watchlist = WatchList::new();
+        watchlist.insert(long, Vec::new());
+        assert!(check_watchlist(&watchlist).is_err());
+    }
+
+    #[test]
+    fn counts() {
+        let watchlist: WatchList = (0..=MAX_LISTS)
+            .map(|i| (i.to_string(), Vec::new()))
+            .collect();
+        assert_eq!(
+            check_watchlist(&watchlist),
+            Err(WatchListError::LimitExceeded(format!(
+                "there are more than {MAX_LISTS} lists"
+            )))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn file_size() {
+        let path = std::env::temp_dir().join(format!("wlist_limits_{}.txt", std::process::id()));
+        let file = File::create(&path).unwrap();
+        file.set_len(MAX_FILE_SIZE + 1).unwrap();
+        assert_eq!(
+            read_file(&path).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        file.set_len(4).unwrap();
+        assert_eq!(read_file(&path).unwrap(), "\0\0\0\0");
+        std::fs::remove_file(path).unwrap();
+    }
+}
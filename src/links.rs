@@ -0,0 +1,93 @@
+//! Links from items to their pages elsewhere, like a streaming service or IMDb, and
+//! opening them in the web browser with `wl open`
+
+use std::env;
+use std::io;
+use std::process::Command;
+
+use crate::WatchListError;
+
+/// Checks that the link is a web address, so opening it can't run a program or open a
+/// file instead
+pub fn check(url: &str) -> Result<(), WatchListError> {
+    let lowercase = url.to_ascii_lowercase();
+    let web = ["http://", "https://"]
+        .iter()
+        .any(|scheme| lowercase.len() > scheme.len() && lowercase.starts_with(scheme));
+    match web && !url.contains(char::is_whitespace) {
+        true => Ok(()),
+        false => Err(WatchListError::InvalidUrl(url.to_string())),
+    }
+}
+
+/// Opens the link in the browser from `BROWSER`, or the default browser of the system
+pub fn open(url: &str) -> Result<(), WatchListError> {
+    let browser = env::var("BROWSER")
+        .ok()
+        .filter(|b| !b.trim().is_empty())
+        .unwrap_or_else(|| default_browser().to_string());
+    open_with(&browser, url)
+}
+
+/// Opens the link with the browser command, which can have arguments of its own like
+/// `firefox --new-tab`
+pub fn open_with(browser: &str, url: &str) -> Result<(), WatchListError> {
+    check(url)?;
+    let mut words = browser.split_whitespace();
+    let program = words.next().unwrap_or(default_browser());
+    match Command::new(program).args(words).arg(url).status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(io::Error::other(format!(
+            "the browser {program} exited with {status} when opening {url}"
+        ))
+        .into()),
+        Err(e) => Err(io::Error::new(
+            e.kind(),
+            format!("couldn't run the browser {program}, set BROWSER to the one to use: {e}"),
+        )
+        .into()),
+    }
+}
+
+// Programs which open a link in whichever browser is set as the default
+fn default_browser() -> &'static str {
+    if cfg!(windows) {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checking() {
+        assert!(check("https://www.imdb.com/title/tt0078748/").is_ok());
+        assert!(check("HTTP://example.com").is_ok());
+        for url in [
+            "file:///etc/passwd",
+            "example.com",
+            "https://",
+            "https://a b",
+        ] {
+            assert_eq!(check(url), Err(WatchListError::InvalidUrl(url.to_string())));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn opening() {
+        assert!(open_with("true --new-tab", "https://example.com").is_ok());
+        assert!(open_with("false", "https://example.com").is_err());
+        assert!(open_with("wl-no-such-browser", "https://example.com").is_err());
+        // Links are checked before anything is run
+        assert_eq!(
+            open_with("wl-no-such-browser", "/bin/sh"),
+            Err(WatchListError::InvalidUrl("/bin/sh".to_string()))
+        );
+    }
+}
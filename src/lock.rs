@@ -0,0 +1,76 @@
+//! Advisory locks which keep commands run at the same time, like a script and a shell,
+//! from overwriting each other's changes to a data file
+//!
+//! The lock is taken on a `.lock` file next to the data file, as the data file may not exist
+//! yet, and is let go when the [`FileLock`] is dropped. Only programs which take the lock
+//! wait for it, the data file itself can still be read and written by anything
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+
+use crate::WatchListError;
+
+/// Exclusive lock on a data file, held until it's dropped
+#[derive(Debug)]
+pub struct FileLock {
+    // Closing the file lets go of the lock
+    _file: File,
+}
+
+impl FileLock {
+    /// Takes the lock on the data file, waiting for whoever holds it to let go of it first
+    pub fn acquire(file_path: &Path) -> Result<FileLock, WatchListError> {
+        let file = open(file_path)?;
+        file.lock()?;
+        Ok(FileLock { _file: file })
+    }
+
+    /// Takes the lock on the data file, or gives none if something else is holding it
+    pub fn try_acquire(file_path: &Path) -> Result<Option<FileLock>, WatchListError> {
+        let file = open(file_path)?;
+        match file.try_lock() {
+            Ok(()) => Ok(Some(FileLock { _file: file })),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(e)) => Err(e.into()),
+        }
+    }
+}
+
+/// Gets the path of the file locked for the data file
+///
+/// For example, `watchlist.json` is locked through `watchlist.lock`
+pub fn lock_path(file_path: &Path) -> PathBuf {
+    file_path.with_extension("lock")
+}
+
+fn open(file_path: &Path) -> Result<File, WatchListError> {
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path(file_path))?;
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn locking() {
+        let path = env::temp_dir().join(format!("wlist-lock-{}.json", std::process::id()));
+        assert_eq!(
+            lock_path(Path::new("lists/watchlist.json")),
+            Path::new("lists/watchlist.lock")
+        );
+
+        let lock = FileLock::acquire(&path).unwrap();
+        assert!(FileLock::try_acquire(&path).unwrap().is_none());
+        drop(lock);
+        let again = FileLock::try_acquire(&path).unwrap();
+        assert!(again.is_some());
+        drop(again);
+        std::fs::remove_file(lock_path(&path)).unwrap();
+    }
+}
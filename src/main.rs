@@ -1,47 +1,268 @@
 use std::env;
 use std::error;
-use std::io;
-use std::{collections::HashMap, path::Path};
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
-use wlist::{WatchList, WatchListError::*, WatchListFuncs};
+use clap_complete::CompleteEnv;
+use wlist::config::Config;
+use wlist::lock::FileLock;
+use wlist::output;
+use wlist::profiles::{self, Profiles};
+use wlist::setup;
+use wlist::theme::{self, Style};
+use wlist::workspace;
+use wlist::{WatchList, WatchListError, WatchListError::*};
 mod cli;
 
 const WATCHLIST_FILE_PATH: &str = "watchlist.json";
 const WATCHLIST_ENV_VAR: &str = "WATCHLIST_FILE_PATH";
+const CONFIG_FILE_PATH: &str = "watchlist.config.json";
+const CONFIG_ENV_VAR: &str = "WATCHLIST_CONFIG_PATH";
 
-fn main() -> Result<(), Box<dyn error::Error>> {
-    let file_path = get_file_path();
-    let mut watchlists = match WatchList::from_file(Path::new(&file_path)) {
-        Ok(w) => w,
-        Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+fn main() -> Result<ExitCode, Box<dyn error::Error>> {
+    // Completes the command being typed and exits when run by a shell's tab completion
+    CompleteEnv::with_factory(cli::command).complete();
+    let cli = cli::parse();
 
-        // Handles IsADirectory Error with error code 21
-        // TODO: Replace with e.kind() == Errorkind::IsADirectory once it becomes stable
-        Err(e) if e.raw_os_error() == Some(21) => {
-            println!("Error! Couldnt Find File!\nCheck if the environment variable is set to a file and not a directory");
-            return Ok(());
+    let config_path = get_config_path();
+    let file_flag = cli::file_flag();
+    let default_file_path = file_flag
+        .clone()
+        .unwrap_or_else(|| get_file_path(&Config::default()));
+    if setup::is_first_run(Path::new(&config_path), Path::new(&default_file_path))
+        && io::stdin().is_terminal()
+        && cli::wants_first_run_setup()
+    {
+        match first_run_setup(Path::new(&config_path), Path::new(&default_file_path)) {
+            Ok(()) => println!("Saved settings to {config_path}\n"),
+            Err(e) => eprintln!("Setup failed! Using default settings\n{e}\n"),
+        }
+    }
+
+    let mut config = match Config::from_file(Path::new(&config_path)) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Error reading config file! Using default settings\n{e}");
+            Config::default()
+        }
+    };
+
+    let _context = theme::enter(cli::output_context(&cli, &config));
+
+    let workspace = match cli::workspace_flag() {
+        Some(name) => match config.workspace(&name) {
+            Ok(files) => Some((name, files.to_vec())),
+            Err(e) => return Ok(report_error(e)),
+        },
+        None => None,
+    };
+    let file_path = match (&workspace, file_flag) {
+        // Only names the files kept next to the data file, like the history
+        (Some((name, files)), _) => workspace::path(name, files).display().to_string(),
+        (None, Some(path)) => path,
+        (None, None) => match get_profile_path(&config, cli::profile_flag()) {
+            Ok(path) => path,
+            Err(e) => return Ok(report_error(e)),
+        },
+    };
+    // Run by the daemon for the file when one is running, which has the lists in memory
+    #[cfg(unix)]
+    if workspace.is_none() && cli::runs_in_daemon() {
+        let args = env::args().skip(1).collect();
+        let sent = wlist::daemon::Request::new(args, Path::new(&config_path))
+            .map_err(WatchListError::from)
+            .and_then(|request| wlist::daemon::send(Path::new(&file_path), &request));
+        match sent {
+            Ok(Some(code)) => return Ok(ExitCode::from(code)),
+            Ok(None) => {}
+            Err(e) => return Ok(report_error(e)),
+        }
+    }
+
+    let files = match &workspace {
+        Some((_, files)) => files.clone(),
+        None => vec![PathBuf::from(&file_path)],
+    };
+    // Held until the lists are saved, so commands run at the same time take turns
+    let _locks = match cli::needs_lock().then(|| lock_files(files)).transpose() {
+        Ok(locks) => locks,
+        Err(e) => return Ok(report_error(e)),
+    };
+
+    // Checking has to run on files which can't be read, so it's done before reading them
+    if let Some(checked) = cli::run_check(&config, Path::new(&file_path)) {
+        return Ok(checked.map_or_else(report_error, |()| ExitCode::SUCCESS));
+    }
+
+    let workspaced = workspace.is_some();
+    let mut run = cli::Run::default();
+    let mut watchlists = match workspace {
+        Some((_, files)) => {
+            let mut lists = Vec::new();
+            for file in files {
+                match read_lists(&file, &mut run) {
+                    Ok(l) => lists.push((file, l)),
+                    Err(code) => return Ok(code),
+                }
+            }
+            cli::use_workspace(lists, &mut run)
         }
-        Err(e) => return Err(Box::new(e)),
+        None => match read_lists(Path::new(&file_path), &mut run) {
+            Ok(l) => l,
+            Err(code) => return Ok(code),
+        },
     };
+    if !workspaced {
+        if let Err(e) =
+            cli::move_list_settings(&mut watchlists, &mut config, Path::new(&config_path))
+        {
+            return Ok(report_error(e));
+        }
+    }
+
+    watchlists.set_validation(config.validation.clone());
+
+    #[cfg(feature = "mqtt")]
+    let before = config.mqtt.as_ref().map(|_| watchlists.clone());
+    let unhooked = (!config.hooks.is_empty()).then(|| watchlists.clone());
+
+    // The lists are still saved after an error, as the command may have changed them first
+    let exit_code = match cli::cli_run(
+        &cli,
+        &mut watchlists,
+        &config,
+        Path::new(&file_path),
+        &mut run,
+    ) {
+        Ok(()) => ExitCode::SUCCESS,
+        // Whatever was reading the output stopped early, like `head`, which is no error
+        Err(e) if output::is_closed(&e) => ExitCode::SUCCESS,
+        Err(e) => report_error(e),
+    };
+
+    #[cfg(feature = "mqtt")]
+    if let (Some(mqtt), Some(before)) = (&config.mqtt, &before) {
+        if let Err(e) = wlist::mqtt::publish_changes(mqtt, before, &watchlists) {
+            eprintln!("Couldn't publish changes to MQTT broker\n{e}");
+        }
+    }
+    if let Err(e) = cli::save_lists(&watchlists, Path::new(&file_path), cli.answers(), &mut run) {
+        return Ok(report_error(e));
+    }
+    // Hooks run once the Lists are saved, so they see the changes in the file
+    if let Some(before) = &unhooked {
+        for failed in config.hooks.fire(before, &watchlists) {
+            eprintln!("Hook failed\n{failed}");
+        }
+    }
+    Ok(exit_code)
+}
 
-    match cli::cli_run(&mut watchlists) {
-        Err(NoTitles) => eprintln!("No Lists Found!\nCreate a new one using the `new` subcommand. See `wl --help` for more info"),
-        Err(EmptyList(t)) => eprintln!("No Items Added to List - {t}!"),
-        Err(TitleNotPresent(t)) => eprintln!("No such list - {t}!"),
-        Err(ItemAlreadyPresent(i, t)) => eprintln!("{i} is already in the list - {t}!"),
-        Err(ItemToRemoveNotPresent(i)) => eprintln!("{i} not in the list!"),
-        Err(TitleAlreadyPresent(t)) => eprintln!("A list called {t} already exists"),
-        Err(IOError(e)) => eprintln!("{e}"),
+// Locks every file, waiting for other commands using them to finish first. The files
+// are always locked in the same order, so two commands can't each wait on the other
+fn lock_files(mut files: Vec<PathBuf>) -> Result<Vec<FileLock>, WatchListError> {
+    files.sort();
+    let mut locks = Vec::new();
+    for file in files {
+        let lock = match FileLock::try_acquire(&file)? {
+            Some(lock) => lock,
+            None => {
+                if io::stderr().is_terminal() {
+                    eprintln!(
+                        "Waiting for another wl command to finish using {}...",
+                        file.display()
+                    );
+                }
+                FileLock::acquire(&file)?
+            }
+        };
+        locks.push(lock);
+    }
+    Ok(locks)
+}
+
+// Reads the lists from a data file, where a file which doesn't exist yet has
+// no lists, printing what went wrong if it can't be read
+fn read_lists(file_path: &Path, run: &mut cli::Run) -> Result<WatchList, ExitCode> {
+    match cli::read_lists(file_path, run) {
+        Ok(w) => Ok(w),
+        Err(IOError(e)) if e.kind() == io::ErrorKind::NotFound => Ok(WatchList::new()),
 
-        Ok(()) => {}
+        // Handles IsADirectory Error with error code 21
+        // TODO: Replace with e.kind() == Errorkind::IsADirectory once it becomes stable
+        Err(IOError(e)) if e.raw_os_error() == Some(21) => {
+            eprintln!("Error! Couldnt Find File!\nCheck if the environment variable is set to a file and not a directory");
+            Err(ExitCode::from(IOError(e).exit_code()))
+        }
+        // Broken files and ones over the size limits
+        Err(e @ (ParseError(_) | LimitExceeded(_))) => {
+            let reason = match &e {
+                ParseError(reason) => reason.clone(),
+                _ => e.to_string(),
+            };
+            eprintln!(
+                "Error! Couldn't read the lists from {}\n{reason}",
+                file_path.display()
+            );
+            Err(ExitCode::from(e.exit_code()))
+        }
+        Err(e) => Err(report_error(e)),
     }
-    watchlists.to_file(Path::new(&file_path))?;
+}
+
+// Prints the error and gets the exit code for it
+fn report_error(e: WatchListError) -> ExitCode {
+    let code = e.exit_code();
+    eprintln!("{}", theme::paint(&e.to_string(), Style::Error));
+    ExitCode::from(code)
+}
+
+fn first_run_setup(config_path: &Path, file_path: &Path) -> Result<(), WatchListError> {
+    let setup = setup::wizard(&mut io::stdin().lock(), &mut io::stdout(), file_path)?;
+    setup::apply(&setup, config_path, file_path)?;
     Ok(())
 }
 
-fn get_file_path() -> String {
-    match env::var(WATCHLIST_ENV_VAR) {
+// The environment variable takes priority over the config file, while
+// `--file` takes priority over both
+pub fn get_file_path(config: &Config) -> String {
+    match (env::var(WATCHLIST_ENV_VAR), &config.data_file) {
+        (Ok(path), _) => path,
+        (Err(_), Some(path)) => path.display().to_string(),
+        (Err(_), None) => WATCHLIST_FILE_PATH.to_string(),
+    }
+}
+
+// A profile given with `--profile` takes priority over the environment variable, which
+// takes priority over the default profile in the config file
+pub fn get_profile_path(
+    config: &Config,
+    profile: Option<String>,
+) -> Result<String, WatchListError> {
+    let profile = match profile {
+        Some(profile) => Some(profile),
+        None if env::var(WATCHLIST_ENV_VAR).is_ok() => None,
+        None => config.profile.clone(),
+    };
+    match profile {
+        Some(name) => Ok(get_profiles(config).path(&name)?.display().to_string()),
+        None => Ok(get_file_path(config)),
+    }
+}
+
+pub fn get_profiles(config: &Config) -> Profiles {
+    let default_file = PathBuf::from(get_file_path(config));
+    let dir = config
+        .profiles_dir
+        .clone()
+        .unwrap_or_else(|| profiles::default_dir(&default_file));
+    Profiles::new(&dir, &default_file)
+}
+
+pub fn get_config_path() -> String {
+    match env::var(CONFIG_ENV_VAR) {
         Ok(path) => path,
-        Err(_) => WATCHLIST_FILE_PATH.to_string(),
+        Err(_) => CONFIG_FILE_PATH.to_string(),
     }
 }
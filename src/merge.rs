@@ -0,0 +1,135 @@
+//! Merging of the lists from another watchlist file
+
+use crate::{Item, WatchList};
+
+/// How items which are in both watchlists are handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Keep the local item as it is
+    #[default]
+    Keep,
+    /// Replace the local item with the other one
+    Replace,
+    /// Fill in details missing from the local item from the other one
+    Combine,
+}
+
+/// Lists and items added or changed by a merge
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct MergeFileReport {
+    pub lists_added: usize,
+    pub added: usize,
+    pub updated: usize,
+    /// Items which were already present and left unchanged
+    pub skipped: usize,
+}
+
+fn combine(local: &Item, other: Item) -> Item {
+    let mut item = local.clone();
+    let meta = &mut item.meta;
+    meta.url = meta.url.take().or(other.meta.url);
+    meta.year = meta.year.or(other.meta.year);
+    meta.due = meta.due.or(other.meta.due);
+    meta.poster = meta.poster.take().or(other.meta.poster);
+    meta.progress = meta.progress.or(other.meta.progress);
+    meta.runtime = meta.runtime.or(other.meta.runtime);
+    meta.watched |= other.meta.watched;
+    meta.dropped |= other.meta.dropped;
+    item
+}
+
+/// Adds the lists and items of `other` which are missing from `watchlist`
+///
+/// Items with the same title in the same list are resolved with `strategy`
+pub fn merge_watchlists(
+    watchlist: &mut WatchList,
+    other: WatchList,
+    strategy: MergeStrategy,
+) -> MergeFileReport {
+    let mut report = MergeFileReport::default();
+    for (title, items) in other {
+        if !watchlist.contains(&title) {
+            report.lists_added += 1;
+        }
+        let list = watchlist.get_or_insert(title);
+
+        for item in items {
+            let Some(existing) = list.iter_mut().find(|i| i.title == item.title) else {
+                list.push(item);
+                report.added += 1;
+                continue;
+            };
+            let merged = match strategy {
+                MergeStrategy::Keep => existing.clone(),
+                MergeStrategy::Replace => item,
+                MergeStrategy::Combine => combine(existing, item),
+            };
+            if merged == *existing {
+                report.skipped += 1;
+            } else {
+                *existing = merged;
+                report.updated += 1;
+            }
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watchlists() -> (WatchList, WatchList) {
+        let mut local = WatchList::new();
+        local.insert(
+            "Movies".to_string(),
+            vec![Item::new("Dune").with_year(2021), Item::new("Alien")],
+        );
+        let mut other = WatchList::new();
+        other.insert(
+            "Movies".to_string(),
+            vec![
+                Item::new("Dune").with_url("https://example.com/dune"),
+                Item::new("Alien"),
+                Item::new("Heat"),
+            ],
+        );
+        other.insert("TV".to_string(), vec![Item::new("Lost")]);
+        (local, other)
+    }
+
+    #[test]
+    fn strategies() {
+        let (mut local, other) = watchlists();
+        let report = merge_watchlists(&mut local, other.clone(), MergeStrategy::Keep);
+        assert_eq!(
+            report,
+            MergeFileReport {
+                lists_added: 1,
+                added: 2,
+                updated: 0,
+                skipped: 2,
+            }
+        );
+        assert_eq!(local["Movies"], vec!["Dune", "Alien", "Heat"]);
+        assert_eq!(local["Movies"][0], Item::new("Dune").with_year(2021));
+        assert_eq!(local["TV"], vec!["Lost"]);
+
+        let (mut local, _) = watchlists();
+        let report = merge_watchlists(&mut local, other.clone(), MergeStrategy::Replace);
+        assert_eq!((report.updated, report.skipped), (1, 1));
+        assert_eq!(
+            local["Movies"][0],
+            Item::new("Dune").with_url("https://example.com/dune")
+        );
+
+        let (mut local, _) = watchlists();
+        merge_watchlists(&mut local, other, MergeStrategy::Combine);
+        assert_eq!(
+            local["Movies"][0],
+            Item::new("Dune")
+                .with_year(2021)
+                .with_url("https://example.com/dune")
+        );
+    }
+}
@@ -0,0 +1,49 @@
+//! Reading the data file through a memory map, so that very large files are
+//! parsed in place instead of being copied into memory first
+
+use std::io;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::{limits, schema, WatchList};
+
+/// Reads a watchlist and the version of the file by mapping the file into memory
+///
+/// Falls back to reading the file normally if it can't be mapped, like on
+/// platforms without memory maps or for files which aren't regular files
+pub fn read_watchlist(file_path: &Path) -> io::Result<(WatchList, u32)> {
+    let (file, len) = limits::open_file(file_path)?;
+    // Empty files can't be mapped
+    if len == 0 {
+        return schema::parse(&[]);
+    }
+    // SAFETY: the map is only read while parsing and dropped straight after. If another
+    // program truncates the file in the meantime the parse fails or reads garbage, which
+    // is no worse than reading a half written file
+    match unsafe { Mmap::map(&file) } {
+        Ok(map) => schema::parse(&map),
+        Err(_) => schema::parse(limits::read_file(file_path)?.as_bytes()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reading() {
+        let path = std::env::temp_dir().join(format!("wlist_mmap_{}.json", std::process::id()));
+        let mut watchlist = WatchList::new();
+        watchlist.insert("Movies".to_string(), vec!["Dune".into(), "Alien".into()]);
+        watchlist.to_file(&path).unwrap();
+        assert_eq!(read_watchlist(&path).unwrap(), (watchlist, 1));
+
+        std::fs::write(&path, "").unwrap();
+        assert_eq!(
+            read_watchlist(&path).unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,300 @@
+//! Publishing of list counts and new items to an MQTT broker
+//!
+//! Only the small part of MQTT 3.1.1 needed to publish retained messages
+//! with QoS 0 is implemented
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use serde_json::json;
+
+use crate::config::MqttConfig;
+use crate::{Item, WatchList, WatchListError};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+const KEEP_ALIVE_SECS: u16 = 60;
+
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+const DISCONNECT: u8 = 0xE0;
+const RETAIN: u8 = 0x01;
+
+// Appends a string prefixed by its length
+fn put_str(buf: &mut Vec<u8>, s: &[u8]) {
+    buf.extend_from_slice(&(s.len() as u16).to_be_bytes());
+    buf.extend_from_slice(s);
+}
+
+// Builds a packet from its type and the rest of its contents
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![header];
+    // The remaining length is stored 7 bits at a time
+    let mut len = body.len();
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+    packet.extend_from_slice(body);
+    packet
+}
+
+fn connect_packet(client_id: &str, username: Option<&str>, password: Option<&str>) -> Vec<u8> {
+    let mut body = Vec::new();
+    put_str(&mut body, b"MQTT");
+    // Protocol level of MQTT 3.1.1
+    body.push(4);
+    // Clean session
+    let mut flags = 0x02;
+    if username.is_some() {
+        flags |= 0x80;
+    }
+    if password.is_some() {
+        flags |= 0x40;
+    }
+    body.push(flags);
+    body.extend_from_slice(&KEEP_ALIVE_SECS.to_be_bytes());
+
+    put_str(&mut body, client_id.as_bytes());
+    for field in [username, password].into_iter().flatten() {
+        put_str(&mut body, field.as_bytes());
+    }
+    packet(CONNECT, &body)
+}
+
+fn publish_packet(topic: &str, payload: &[u8]) -> Vec<u8> {
+    let mut body = Vec::new();
+    put_str(&mut body, topic.as_bytes());
+    body.extend_from_slice(payload);
+    packet(PUBLISH | RETAIN, &body)
+}
+
+/// Connection to an MQTT broker
+pub struct Publisher<S: Read + Write> {
+    stream: S,
+}
+
+impl Publisher<TcpStream> {
+    /// Connects to the broker in the config
+    pub fn connect(config: &MqttConfig) -> Result<Self, WatchListError> {
+        let stream = TcpStream::connect((config.host.as_str(), config.port))?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        Publisher::new(
+            stream,
+            &config.client_id,
+            config.username.as_deref(),
+            config.password.as_deref(),
+        )
+    }
+}
+
+impl<S: Read + Write> Publisher<S> {
+    /// Sends the connect packet over a stream and waits for the broker to accept it
+    pub fn new(
+        mut stream: S,
+        client_id: &str,
+        username: Option<&str>,
+        password: Option<&str>,
+    ) -> Result<Self, WatchListError> {
+        stream.write_all(&connect_packet(client_id, username, password))?;
+
+        let mut connack = [0; 4];
+        stream.read_exact(&mut connack)?;
+        match connack {
+            [CONNACK, 2, _, 0] => Ok(Publisher { stream }),
+            [CONNACK, 2, _, code] => Err(WatchListError::NetworkError(format!(
+                "MQTT broker refused the connection (code {code})"
+            ))),
+            _ => Err(WatchListError::NetworkError(
+                "unexpected reply from MQTT broker".to_string(),
+            )),
+        }
+    }
+
+    /// Publishes a retained message
+    pub fn publish(&mut self, topic: &str, payload: &[u8]) -> Result<(), WatchListError> {
+        self.stream
+            .write_all(&publish_packet(topic, payload))
+            .map_err(WatchListError::IOError)
+    }
+
+    pub fn disconnect(mut self) -> Result<(), WatchListError> {
+        self.stream
+            .write_all(&packet(DISCONNECT, &[]))
+            .map_err(WatchListError::IOError)
+    }
+}
+
+/// Gets the items in `after` which weren't in the same list in `before`
+pub fn added_items<'a>(before: &WatchList, after: &'a WatchList) -> Vec<(&'a String, &'a Item)> {
+    after
+        .iter()
+        .flat_map(|(title, items)| items.iter().map(move |i| (title, i)))
+        .filter(|(title, item)| {
+            before
+                .get(title)
+                .is_none_or(|items| !items.iter().any(|i| i.title == item.title))
+        })
+        .collect()
+}
+
+/// Publishes the item counts of all lists and any items added since `before`
+///
+/// Nothing is published if the lists didn't change
+pub fn publish_changes(
+    config: &MqttConfig,
+    before: &WatchList,
+    after: &WatchList,
+) -> Result<(), WatchListError> {
+    if before == after {
+        return Ok(());
+    }
+
+    let mut publisher = Publisher::connect(config)?;
+    let counts: HashMap<&String, usize> = after
+        .iter()
+        .map(|(title, items)| (title, items.len()))
+        .collect();
+    publisher.publish(&config.counts_topic, json!(counts).to_string().as_bytes())?;
+
+    for (title, item) in added_items(before, after) {
+        let payload = json!({ "list": title, "item": item });
+        publisher.publish(&config.latest_topic, payload.to_string().as_bytes())?;
+    }
+    publisher.disconnect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    // Stream which replies with fixed bytes and records what is written
+    struct MockStream {
+        reply: Cursor<Vec<u8>>,
+        written: Vec<u8>,
+    }
+
+    impl Read for MockStream {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.reply.read(buf)
+        }
+    }
+
+    impl Write for MockStream {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.written.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn packets() {
+        assert_eq!(
+            connect_packet("wl", None, None),
+            [
+                &[0x10, 14, 0, 4][..],
+                b"MQTT",
+                &[4, 0x02, 0, 60, 0, 2],
+                b"wl"
+            ]
+            .concat()
+        );
+        assert_eq!(
+            connect_packet("wl", Some("u"), Some("p"))[9],
+            0x02 | 0x80 | 0x40
+        );
+        assert_eq!(
+            publish_packet("a/b", b"hi"),
+            [&[0x31, 7, 0, 3][..], b"a/b", b"hi"].concat()
+        );
+
+        // Lengths over 127 take more than one byte, 203 = 75 + 1 * 128
+        let long = publish_packet("t", &[0; 200]);
+        assert_eq!(&long[..3], &[0x31, 0x80 | 75, 1]);
+    }
+
+    #[test]
+    fn connecting() {
+        let stream = MockStream {
+            reply: Cursor::new(vec![0x20, 2, 0, 0]),
+            written: Vec::new(),
+        };
+        let mut publisher = Publisher::new(stream, "wl", None, None).unwrap();
+        publisher.publish("t", b"x").unwrap();
+        assert!(publisher
+            .stream
+            .written
+            .ends_with(&[0x31, 4, 0, 1, b't', b'x']));
+
+        let stream = MockStream {
+            reply: Cursor::new(vec![0x20, 2, 0, 5]),
+            written: Vec::new(),
+        };
+        assert_eq!(
+            Publisher::new(stream, "wl", None, None).err().unwrap(),
+            WatchListError::NetworkError("MQTT broker refused the connection (code 5)".to_string())
+        );
+    }
+
+    #[test]
+    fn publishing() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = MqttConfig {
+            host: "127.0.0.1".to_string(),
+            port: listener.local_addr().unwrap().port(),
+            ..Default::default()
+        };
+        let broker = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(&[0x20, 2, 0, 0]).unwrap();
+            let mut received = Vec::new();
+            stream.read_to_end(&mut received).unwrap();
+            received
+        });
+
+        let before = WatchList::new();
+        let mut after = WatchList::new();
+        after.insert("Movies".to_string(), vec![Item::new("Dune")]);
+        publish_changes(&config, &before, &after).unwrap();
+
+        let received = broker.join().unwrap();
+        let counts = publish_packet("watchlist/counts", br#"{"Movies":1}"#);
+        let latest = publish_packet("watchlist/latest", br#"{"item":"Dune","list":"Movies"}"#);
+        assert!(received.ends_with(&[counts, latest, vec![0xE0, 0]].concat()));
+    }
+
+    #[test]
+    fn added() {
+        let mut before = WatchList::new();
+        before.insert("Movies".to_string(), vec![Item::new("Dune")]);
+        let mut after = before.clone();
+        after.get_mut("Movies").unwrap().push(Item::new("Alien"));
+        after.insert("TV".to_string(), vec![Item::new("Lost")]);
+
+        let mut added: Vec<(&String, &str)> = added_items(&before, &after)
+            .into_iter()
+            .map(|(list, item)| (list, item.title.as_str()))
+            .collect();
+        added.sort();
+        assert_eq!(
+            added,
+            vec![
+                (&"Movies".to_string(), "Alien"),
+                (&"TV".to_string(), "Lost")
+            ]
+        );
+    }
+}
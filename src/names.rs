@@ -0,0 +1,75 @@
+//! Titles of the items in a list, kept so adding an item doesn't compare it with every
+//! item already there
+//!
+//! Titles are grouped by their [folded](crate::fold) form, the loosest way two titles can
+//! be duplicates, so only the titles in the same group need comparing with a new one
+
+use std::collections::HashMap;
+
+use crate::{DuplicatePolicy, Item};
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Names {
+    titles: HashMap<String, Vec<String>>,
+}
+
+impl Names {
+    pub fn of(items: &[Item]) -> Self {
+        let mut names = Names::default();
+        for item in items {
+            names.add(&item.title);
+        }
+        names
+    }
+
+    /// Checks if the list has an item which the policy counts as the same as the title
+    pub fn contains(&self, title: &str, duplicates: DuplicatePolicy) -> bool {
+        self.titles
+            .get(&DuplicatePolicy::key(title))
+            .is_some_and(|group| group.iter().any(|t| duplicates.same(t, title)))
+    }
+
+    pub fn add(&mut self, title: &str) {
+        self.titles
+            .entry(DuplicatePolicy::key(title))
+            .or_default()
+            .push(title.to_string());
+    }
+
+    /// Takes out one of the titles, since a list can have the same title more than once
+    pub fn remove(&mut self, title: &str) {
+        let key = DuplicatePolicy::key(title);
+        let Some(group) = self.titles.get_mut(&key) else {
+            return;
+        };
+        if let Some(position) = group.iter().position(|t| t == title) {
+            group.swap_remove(position);
+        }
+        if group.is_empty() {
+            self.titles.remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        let mut names = Names::of(&[Item::new("Pokémon"), Item::new("Dune"), Item::new("Dune")]);
+        assert!(names.contains("Pokémon", DuplicatePolicy::Exact));
+        assert!(!names.contains("POKÉMON", DuplicatePolicy::Exact));
+        assert!(names.contains("POKÉMON", DuplicatePolicy::Normalized));
+        assert!(!names.contains("pokemon", DuplicatePolicy::Normalized));
+        assert!(names.contains(" pokemon ", DuplicatePolicy::Folded));
+        assert!(!names.contains("Pokémon", DuplicatePolicy::Allow));
+
+        names.remove("Dune");
+        assert!(names.contains("Dune", DuplicatePolicy::Exact));
+        names.remove("Dune");
+        assert!(!names.contains("Dune", DuplicatePolicy::Exact));
+        names.remove("Alien");
+        assert_eq!(names.titles.len(), 1);
+    }
+}
@@ -0,0 +1,119 @@
+//! Reminders of items which are due soon with `wl notify`, meant to be run from cron or a
+//! systemd timer
+//!
+//! The reminder is printed for use without a desktop, and when built with the `notify`
+//! feature it's raised as a desktop notification too
+
+use chrono::NaiveDate;
+
+use crate::status::Status;
+use crate::{due, Item, WatchList};
+
+/// Items which are due soon, summed up in a line with the items listed under it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reminder {
+    pub summary: String,
+    /// One line for each item, soonest due first
+    pub body: Vec<String>,
+}
+
+/// Gets the items still to be watched which are due within `days` days of `today`, or have
+/// been due already. Nothing is given when there aren't any
+pub fn reminder(
+    watchlist: &WatchList,
+    today: NaiveDate,
+    days: u32,
+    format_date: impl Fn(NaiveDate) -> String,
+) -> Option<Reminder> {
+    let due: Vec<(&String, &Item)> = due::items_due_within(watchlist, today, days)
+        .into_iter()
+        .filter(|(_, item)| item.status() == Status::Unwatched)
+        .collect();
+    if due.is_empty() {
+        return None;
+    }
+
+    let overdue = due
+        .iter()
+        .filter(|(_, item)| due::is_overdue(item, today))
+        .count();
+    let mut summary = match days {
+        0 => format!("{} Item(s) due by today", due.len()),
+        days => format!("{} Item(s) due within {days} day(s)", due.len()),
+    };
+    if overdue > 0 {
+        summary.push_str(&format!(", {overdue} overdue"));
+    }
+    let body = due
+        .into_iter()
+        .map(|(list, item)| {
+            let date = item.meta.due.unwrap_or(today);
+            let when = match date {
+                d if d < today => format!("was due on {}", format_date(d)),
+                d if d == today => "due today".to_string(),
+                d => format!("due on {}", format_date(d)),
+            };
+            format!("{} ({list}) {when}", item.title)
+        })
+        .collect();
+    Some(Reminder { summary, body })
+}
+
+/// Raises the reminder as a desktop notification
+#[cfg(feature = "notify")]
+pub fn show(reminder: &Reminder) -> Result<(), String> {
+    notify_rust::Notification::new()
+        .appname("wl")
+        .summary(&reminder.summary)
+        .body(&reminder.body.join("\n"))
+        .show()
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn reminding() {
+        let mut watchlist = WatchList::new();
+        let mut watched = Item::new("Heat").with_due(date("2023-03-01"));
+        watched.set_status(Status::Watched);
+        watchlist.insert(
+            "Movies",
+            vec![
+                Item::new("Alien").with_due(date("2023-03-05")),
+                watched,
+                Item::new("Ran"),
+                Item::new("Dune").with_due(date("2023-03-20")),
+            ],
+        );
+        watchlist.insert(
+            "Books",
+            vec![Item::new("Emma").with_due(date("2023-02-25"))],
+        );
+        let today = date("2023-03-01");
+        let format = |d: NaiveDate| d.to_string();
+
+        let found = reminder(&watchlist, today, 7, format).unwrap();
+        assert_eq!(found.summary, "2 Item(s) due within 7 day(s), 1 overdue");
+        assert_eq!(
+            found.body,
+            [
+                "Emma (Books) was due on 2023-02-25",
+                "Alien (Movies) due on 2023-03-05"
+            ]
+        );
+
+        let found = reminder(&watchlist, date("2023-03-05"), 0, format).unwrap();
+        assert_eq!(found.summary, "2 Item(s) due by today, 1 overdue");
+        assert_eq!(found.body[1], "Alien (Movies) due today");
+
+        assert_eq!(reminder(&watchlist, date("2023-01-01"), 7, format), None);
+    }
+}
@@ -0,0 +1,295 @@
+//! Writing output as it's made, for output which can be much longer than the terminal
+//! like every item of every list or an export of all of them
+//!
+//! The program reading the output can stop before the end, like `wl show --all-items | head`,
+//! which makes writing the rest fail with [`io::ErrorKind::BrokenPipe`]. [`is_closed`]
+//! tells that apart from other errors, as there's nothing left to show it to
+//!
+//! Once paging is turned on with [`set_paging`], output to a terminal which is longer than
+//! the terminal is high goes through `$PAGER` (`less` by default), the way `git log` does
+
+use std::env;
+use std::fmt::Display;
+use std::io::{self, BufWriter, IsTerminal, StdoutLock, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::render::{Align, Column, Table};
+use crate::theme::{self, Style};
+use crate::WatchListError;
+
+static PAGING: AtomicBool = AtomicBool::new(false);
+
+/// Pager used when `$PAGER` isn't set
+const DEFAULT_PAGER: &str = "less";
+
+/// Turns paging output longer than the terminal on or off
+pub fn set_paging(paging: bool) {
+    PAGING.store(paging, Ordering::Relaxed);
+}
+
+/// Standard output, buffered until it's flushed, which has to be done once everything is written
+pub fn stdout() -> Paged {
+    let height = match PAGING.load(Ordering::Relaxed) && io::stdout().is_terminal() {
+        true => terminal_height(),
+        false => None,
+    };
+    let state = match height {
+        Some(height) => State::Waiting {
+            buffer: Vec::new(),
+            height,
+        },
+        None => State::Direct(BufWriter::new(io::stdout().lock())),
+    };
+    Paged { state }
+}
+
+/// Standard output which starts a pager once more lines are written than fit in the terminal
+pub struct Paged {
+    state: State,
+}
+
+enum State {
+    Direct(BufWriter<StdoutLock<'static>>),
+    // Held back until it's known whether it fits
+    Waiting {
+        buffer: Vec<u8>,
+        height: usize,
+    },
+    Piped {
+        pager: Child,
+        input: Option<BufWriter<ChildStdin>>,
+    },
+}
+
+impl Paged {
+    // Passes what was held back to the pager, or prints it when there is no pager to start
+    fn start_pager(&mut self, buffer: Vec<u8>) -> io::Result<()> {
+        let command = env::var("PAGER").unwrap_or_else(|_| DEFAULT_PAGER.to_string());
+        let mut words = command.split_whitespace();
+        let pager = match words.next() {
+            Some(program) if program != "cat" => Command::new(program)
+                .args(words)
+                .stdin(Stdio::piped())
+                // Colors are shown and short output isn't paged, unless set otherwise
+                .env(
+                    "LESS",
+                    env::var("LESS").unwrap_or_else(|_| "FRX".to_string()),
+                )
+                .spawn()
+                .ok(),
+            _ => None,
+        };
+        self.state = match pager {
+            Some(mut pager) => {
+                let input = pager.stdin.take().map(BufWriter::new);
+                State::Piped { pager, input }
+            }
+            None => State::Direct(BufWriter::new(io::stdout().lock())),
+        };
+        self.write_all(&buffer)
+    }
+}
+
+impl Write for Paged {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.state {
+            State::Direct(out) => out.write(buf),
+            State::Piped { input, .. } => match input {
+                Some(input) => input.write(buf),
+                None => Err(io::ErrorKind::BrokenPipe.into()),
+            },
+            State::Waiting { buffer, height } => {
+                buffer.extend_from_slice(buf);
+                let lines = buffer.iter().filter(|b| **b == b'\n').count();
+                // The prompt after the output takes a line too
+                if lines >= *height {
+                    let buffer = std::mem::take(buffer);
+                    self.start_pager(buffer)?;
+                }
+                Ok(buf.len())
+            }
+        }
+    }
+
+    // Everything held back fits in the terminal once the output is flushed
+    fn flush(&mut self) -> io::Result<()> {
+        if let State::Waiting { buffer, .. } = &mut self.state {
+            let buffer = std::mem::take(buffer);
+            self.state = State::Direct(BufWriter::new(io::stdout().lock()));
+            self.write_all(&buffer)?;
+        }
+        match &mut self.state {
+            State::Direct(out) => out.flush(),
+            State::Piped {
+                input: Some(input), ..
+            } => input.flush(),
+            _ => Ok(()),
+        }
+    }
+}
+
+// The pager is left to show the output until it's closed
+impl Drop for Paged {
+    fn drop(&mut self) {
+        let _ = self.flush();
+        if let State::Piped { pager, input } = &mut self.state {
+            drop(input.take());
+            let _ = pager.wait();
+        }
+    }
+}
+
+// Rows of the terminal, which `LINES` can set instead
+fn terminal_height() -> Option<usize> {
+    if let Some(lines) = env::var("LINES").ok().and_then(|l| l.parse().ok()) {
+        return Some(lines);
+    }
+    terminal_size().map(|(rows, _)| rows)
+}
+
+/// Columns of the terminal tables are fitted to, which `COLUMNS` can set instead, or none
+/// when the output isn't going to a terminal
+pub fn terminal_width() -> Option<usize> {
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+    if let Some(columns) = env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        return Some(columns);
+    }
+    terminal_size().map(|(_, columns)| columns)
+}
+
+// Rows and columns of the terminal
+#[cfg(unix)]
+fn terminal_size() -> Option<(usize, usize)> {
+    // SAFETY: winsize is plain data, which TIOCGWINSZ only fills in
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    let result = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+    (result == 0 && size.ws_row > 0 && size.ws_col > 0)
+        .then_some((usize::from(size.ws_row), usize::from(size.ws_col)))
+}
+
+#[cfg(not(unix))]
+fn terminal_size() -> Option<(usize, usize)> {
+    None
+}
+
+/// Whether writing failed because the program reading the output closed it
+pub fn is_closed(error: &WatchListError) -> bool {
+    matches!(error, WatchListError::IOError(e) if e.kind() == io::ErrorKind::BrokenPipe)
+}
+
+/// Writes the numbered titles under a heading, the way [`list_display`](crate::list_display)
+/// shows them
+pub fn write_list<T: Display>(out: &mut impl Write, list: &[T], title: &str) -> io::Result<()> {
+    // Without the heading and the table, but still numbered for picking matches
+    if theme::quiet() {
+        for (i, item) in list.iter().enumerate() {
+            writeln!(out, "{}. {item}", i + 1)?;
+        }
+        return Ok(());
+    }
+    if theme::accessible() {
+        writeln!(out, "{title}, {} item(s)", list.len())?;
+        for (i, item) in list.iter().enumerate() {
+            writeln!(out, "{}. {item}", i + 1)?;
+        }
+        return Ok(());
+    }
+    let mut table = Table::new()
+        .column(Column::new("#").align(Align::Right).style(Style::Index))
+        .column(Column::new("title"));
+    for (i, item) in list.iter().enumerate() {
+        table.row([format!("{}.", i + 1), item.to_string()]);
+    }
+    write_table(out, &table, title)
+}
+
+/// Writes the table fitted to the terminal under a heading, or without the heading in the
+/// quiet output mode
+pub fn write_table(out: &mut impl Write, table: &Table, title: &str) -> io::Result<()> {
+    if !theme::quiet() {
+        writeln!(out, "{: ^15}", theme::paint(title, Style::Heading))?;
+    }
+    for line in table.render(terminal_width()) {
+        writeln!(out, "{line}")?;
+    }
+    Ok(())
+}
+
+/// Writes the numbered titles with their numbers of items lined up next to them, under
+/// a heading
+pub fn write_counts<T: Display>(
+    out: &mut impl Write,
+    counts: &[(T, usize)],
+    title: &str,
+) -> io::Result<()> {
+    if theme::quiet() {
+        for (i, (list, count)) in counts.iter().enumerate() {
+            writeln!(out, "{}. {list} ({count})", i + 1)?;
+        }
+        return Ok(());
+    }
+    if theme::accessible() {
+        writeln!(out, "{title}, {} list(s)", counts.len())?;
+        for (i, (list, count)) in counts.iter().enumerate() {
+            writeln!(out, "{}. {list}, {count} item(s)", i + 1)?;
+        }
+        return Ok(());
+    }
+    let mut table = Table::new()
+        .column(Column::new("#").align(Align::Right).style(Style::Index))
+        .column(Column::new("title"))
+        .column(Column::new("items").align(Align::Right));
+    for (i, (list, count)) in counts.iter().enumerate() {
+        table.row([format!("{}.", i + 1), list.to_string(), count.to_string()]);
+    }
+    write_table(out, &table, title)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Stands in for a pipe which the program reading it closed after the first write
+    struct Closing(usize);
+
+    impl Write for Closing {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0 += 1;
+            match self.0 {
+                1 => Ok(buf.len()),
+                _ => Err(io::ErrorKind::BrokenPipe.into()),
+            }
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn closed_output() {
+        let error = write_list(&mut Closing(0), &["Alien", "Heat"], "Movies").unwrap_err();
+        assert!(is_closed(&WatchListError::IOError(error)));
+        assert!(!is_closed(&WatchListError::IOError(
+            io::ErrorKind::NotFound.into()
+        )));
+        assert!(!is_closed(&WatchListError::NoTitles));
+    }
+
+    #[test]
+    fn counts() {
+        let mut out = Vec::new();
+        write_counts(&mut out, &[("Anime", 12), ("Movies", 3)], "All Lists").unwrap();
+        let lines: Vec<String> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|l| l.trim_end().to_string())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].ends_with("Anime  | 12"));
+        assert!(lines[2].ends_with("Movies |  3"));
+    }
+}
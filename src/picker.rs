@@ -0,0 +1,184 @@
+//! Picking an item by typing part of its title, with the matches narrowed down as it's typed
+
+use std::io;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style as TuiStyle};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::{fuzzy, Item, WatchListError};
+
+const HELP: &str = "type to filter  up/down choose  enter pick  esc cancel";
+
+/// State of the picker, kept apart from the terminal so it can be driven by key presses alone
+pub struct Picker<'a> {
+    items: &'a [Item],
+    title: String,
+    filter: String,
+    selected: usize,
+    /// Index of the item picked in the items, once one is
+    pub picked: Option<usize>,
+    pub done: bool,
+}
+
+impl<'a> Picker<'a> {
+    /// Creates a picker over the items, starting with the filter already typed
+    pub fn new(items: &'a [Item], title: &str, filter: &str) -> Self {
+        Picker {
+            items,
+            title: title.to_string(),
+            filter: filter.to_string(),
+            selected: 0,
+            picked: None,
+            done: false,
+        }
+    }
+
+    /// Indices of the items matching the filter, closest matches first
+    fn matches(&self) -> Vec<usize> {
+        fuzzy::search(self.items, &self.filter)
+            .into_iter()
+            .filter_map(|item| self.items.iter().position(|i| std::ptr::eq(i, item)))
+            .collect()
+    }
+
+    /// Handles a single key press
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let matches = self.matches().len();
+        match key.code {
+            KeyCode::Enter => {
+                self.picked = self.matches().get(self.selected).copied();
+                self.done = self.picked.is_some();
+            }
+            KeyCode::Esc => self.done = true,
+            KeyCode::Char('c') if ctrl => self.done = true,
+            KeyCode::Down | KeyCode::Tab => {
+                self.selected = (self.selected + 1).min(matches.saturating_sub(1));
+            }
+            KeyCode::Char('n') if ctrl => {
+                self.selected = (self.selected + 1).min(matches.saturating_sub(1));
+            }
+            KeyCode::Up | KeyCode::BackTab => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Char('p') if ctrl => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Backspace => {
+                self.filter.pop();
+                self.selected = 0;
+            }
+            KeyCode::Char(c) => {
+                self.filter.push(c);
+                self.selected = 0;
+            }
+            _ => {}
+        }
+    }
+
+    /// Draws the picker onto a frame
+    pub fn draw(&self, frame: &mut Frame) {
+        let [filter_area, items_area, help_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let line = Line::from(format!("> {}", self.filter));
+        let cursor = (filter_area.x + line.width() as u16, filter_area.y);
+        frame.render_widget(Paragraph::new(line), filter_area);
+        frame.set_cursor_position(cursor);
+
+        let matches = self.matches();
+        let items: Vec<ListItem> = matches
+            .iter()
+            .map(|&i| ListItem::new(self.items[i].title.as_str()))
+            .collect();
+        let title = format!("{} ({}/{})", self.title, matches.len(), self.items.len());
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(Block::bordered().title(title))
+                .highlight_style(TuiStyle::default().add_modifier(Modifier::REVERSED)),
+            items_area,
+            &mut state,
+        );
+        frame.render_widget(Paragraph::new(HELP), help_area);
+    }
+}
+
+/// Lets the user pick one of the items, giving its index, or none if the picker is cancelled
+pub fn pick(items: &[Item], title: &str, filter: &str) -> Result<Option<usize>, WatchListError> {
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, Picker::new(items, title, filter));
+    ratatui::restore();
+    Ok(result?)
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, mut picker: Picker) -> io::Result<Option<usize>> {
+    while !picker.done {
+        terminal.draw(|frame| picker.draw(frame))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press {
+                picker.handle_key(key);
+            }
+        }
+    }
+    Ok(picker.picked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    fn press(picker: &mut Picker, keys: &str) {
+        for c in keys.chars() {
+            let code = match c {
+                '\n' => KeyCode::Enter,
+                '\x1b' => KeyCode::Esc,
+                '\x08' => KeyCode::Backspace,
+                'v' => KeyCode::Down,
+                '^' => KeyCode::Up,
+                c => KeyCode::Char(c),
+            };
+            picker.handle_key(KeyEvent::from(code));
+        }
+    }
+
+    #[test]
+    fn picking() {
+        let items = [Item::new("Dune"), Item::new("Alien"), Item::new("Aliens")];
+        let mut picker = Picker::new(&items, "Movies", "");
+        assert_eq!(picker.matches(), vec![0, 1, 2]);
+
+        // The filter narrows the matches, and choosing stops at the last one
+        press(&mut picker, "alienvvv^");
+        assert_eq!(picker.matches(), vec![1, 2]);
+        assert!(!picker.done);
+        press(&mut picker, "\x08\x08\x08\x08\x08dn\n");
+        assert!(picker.done);
+        assert_eq!(picker.picked, Some(0));
+
+        let mut terminal = Terminal::new(TestBackend::new(40, 6)).unwrap();
+        terminal.draw(|frame| picker.draw(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|c| c.symbol())
+            .collect();
+        assert!(screen.contains("Movies (1/3)"));
+
+        // Nothing is picked when there are no matches or the picker is cancelled
+        let mut picker = Picker::new(&items, "Movies", "zodiac");
+        press(&mut picker, "\n");
+        assert!(!picker.done);
+        press(&mut picker, "\x1b");
+        assert!(picker.done);
+        assert_eq!(picker.picked, None);
+    }
+}
@@ -0,0 +1,360 @@
+//! History of the items suggested by the random command, and the items picked for the day
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use chrono::{NaiveDate, TimeDelta};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::dates::Timestamp;
+use crate::{encryption, Item, WatchListError};
+
+/// When each item was last picked, saved to a file
+#[derive(Debug, Default)]
+pub struct PickHistory {
+    file_path: PathBuf,
+    path: PathBuf,
+    // List title -> item title -> time of the last pick
+    picks: HashMap<String, HashMap<String, Timestamp>>,
+}
+
+impl PickHistory {
+    /// Reads the pick history next to the data file, starting with none if there isn't any
+    pub fn from_file(file_path: &Path) -> Result<Self, WatchListError> {
+        let path = crate::sidecar_path(file_path, "picks");
+        let picks = match encryption::read_sidecar(file_path, &path)? {
+            Some(data) => serde_json::from_str(&data)
+                .map_err(|e| WatchListError::ParseError(e.to_string()))?,
+            None => HashMap::new(),
+        };
+        Ok(PickHistory {
+            file_path: file_path.to_path_buf(),
+            path,
+            picks,
+        })
+    }
+
+    pub fn to_file(&self) -> Result<(), WatchListError> {
+        let data =
+            serde_json::to_string(&self.picks).map_err(|e| WatchListError::IOError(e.into()))?;
+        encryption::write_sidecar(&self.file_path, &self.path, data.as_bytes())
+    }
+
+    pub fn last_picked(&self, list: &str, item: &str) -> Option<Timestamp> {
+        self.picks.get(list)?.get(item).copied()
+    }
+
+    /// Gets the titles of the items of the list picked most recently, latest first
+    pub fn recent(&self, list: &str, count: usize) -> Vec<&String> {
+        let mut picks: Vec<(&String, &Timestamp)> = self
+            .picks
+            .get(list)
+            .map(|picks| picks.iter().collect())
+            .unwrap_or_default();
+        picks.sort_by(|(_, a), (_, b)| b.cmp(a));
+        picks
+            .into_iter()
+            .take(count)
+            .map(|(item, _)| item)
+            .collect()
+    }
+
+    pub fn record(&mut self, list: &str, item: &str, picked_at: Timestamp) {
+        self.picks
+            .entry(list.to_string())
+            .or_default()
+            .insert(item.to_string(), picked_at);
+    }
+
+    /// Moves the picks of a list to its new title, returning whether there were any
+    pub fn rename_list(&mut self, title: &str, new_title: &str) -> bool {
+        match self.picks.remove(title) {
+            Some(picks) => {
+                self.picks.insert(new_title.to_string(), picks);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Chooses a random item from `items` of the list `title`, skipping the ones
+    /// picked within `cooldown` and the `recent` items of the list picked last
+    ///
+    /// If every item is skipped, the one picked the longest ago is chosen
+    pub fn pick<'w>(
+        &self,
+        title: &str,
+        items: &[&'w Item],
+        cooldown: TimeDelta,
+        recent: usize,
+        now: Timestamp,
+    ) -> Option<&'w Item> {
+        self.pick_n(title, items, 1, cooldown, recent, now).pop()
+    }
+
+    /// Chooses up to `count` different random items, skipping items the same way as [`pick`]
+    ///
+    /// If too few items aren't skipped, the ones picked the longest ago make up the rest
+    ///
+    /// [`pick`]: PickHistory::pick
+    pub fn pick_n<'w>(
+        &self,
+        title: &str,
+        items: &[&'w Item],
+        count: usize,
+        cooldown: TimeDelta,
+        recent: usize,
+        now: Timestamp,
+    ) -> Vec<&'w Item> {
+        let mut rng = rand::thread_rng();
+        self.pick_n_with_rng(title, items, count, cooldown, recent, now, &mut rng)
+    }
+
+    /// Chooses items like [`pick_n`] with `rng`, which can be seeded so the same history
+    /// picks the same items every time
+    ///
+    /// [`pick_n`]: PickHistory::pick_n
+    #[allow(clippy::too_many_arguments)]
+    pub fn pick_n_with_rng<'w>(
+        &self,
+        title: &str,
+        items: &[&'w Item],
+        count: usize,
+        cooldown: TimeDelta,
+        recent: usize,
+        now: Timestamp,
+        rng: &mut impl Rng,
+    ) -> Vec<&'w Item> {
+        let last_picked = |item: &Item| self.last_picked(title, &item.title);
+        let recent = self.recent(title, recent);
+
+        let fresh: Vec<&Item> = items
+            .iter()
+            .copied()
+            .filter(|i| last_picked(i).is_none_or(|t| now - t >= cooldown))
+            .filter(|i| !recent.contains(&&i.title))
+            .collect();
+        let mut picked: Vec<&Item> = fresh.choose_multiple(rng, count).copied().collect();
+        if picked.len() < count {
+            let mut rest: Vec<&Item> = items
+                .iter()
+                .copied()
+                .filter(|i| !picked.iter().any(|p| std::ptr::eq(*p, *i)))
+                .collect();
+            rest.sort_by_key(|i| last_picked(i));
+            picked.extend(rest.into_iter().take(count - picked.len()));
+        }
+        picked
+    }
+}
+
+/// Item picked for a day, which is picked again every time until the day is over
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailyPick {
+    pub date: NaiveDate,
+    pub list: String,
+    pub item: String,
+}
+
+/// Items picked for today, by the list they were asked for, saved to a file
+///
+/// Picks asked for without a list are kept under an empty title
+#[derive(Debug, Default)]
+pub struct DailyPicks {
+    file_path: PathBuf,
+    path: PathBuf,
+    picks: HashMap<String, DailyPick>,
+}
+
+impl DailyPicks {
+    /// Reads the daily picks next to the data file, starting with none if there isn't any
+    pub fn from_file(file_path: &Path) -> Result<Self, WatchListError> {
+        let path = crate::sidecar_path(file_path, "daily");
+        let picks = match encryption::read_sidecar(file_path, &path)? {
+            Some(data) => serde_json::from_str(&data)
+                .map_err(|e| WatchListError::ParseError(e.to_string()))?,
+            None => HashMap::new(),
+        };
+        Ok(DailyPicks {
+            file_path: file_path.to_path_buf(),
+            path,
+            picks,
+        })
+    }
+
+    pub fn to_file(&self) -> Result<(), WatchListError> {
+        let data =
+            serde_json::to_string(&self.picks).map_err(|e| WatchListError::IOError(e.into()))?;
+        encryption::write_sidecar(&self.file_path, &self.path, data.as_bytes())
+    }
+
+    /// Gets the item picked today when asking for the list, if there is one
+    pub fn get(&self, list: &str, today: NaiveDate) -> Option<&DailyPick> {
+        self.picks.get(list).filter(|p| p.date == today)
+    }
+
+    /// Keeps the item picked when asking for the list, forgetting the picks of earlier days
+    pub fn set(&mut self, list: &str, pick: DailyPick) {
+        let today = pick.date;
+        self.picks.insert(list.to_string(), pick);
+        self.picks.retain(|_, p| p.date == today);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(s: &str) -> Timestamp {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn cooldown() {
+        let (dune, alien) = (Item::new("Dune"), Item::new("Alien"));
+        let items = [&dune, &alien];
+        let now = time("2023-03-10T12:00:00Z");
+        let week = TimeDelta::days(7);
+
+        let mut history = PickHistory::default();
+        history.record("Movies", "Dune", time("2023-03-09T12:00:00Z"));
+        for _ in 0..10 {
+            assert_eq!(
+                history.pick("Movies", &items, week, 0, now).unwrap(),
+                "Alien"
+            );
+        }
+
+        // Every item is cooling down so the oldest pick is repeated
+        history.record("Movies", "Alien", time("2023-03-10T11:00:00Z"));
+        assert_eq!(
+            history.pick("Movies", &items, week, 0, now).unwrap(),
+            "Dune"
+        );
+
+        assert_eq!(history.pick("Movies", &[], week, 0, now), None);
+
+        // Picks older than the cooldown don't count
+        let day = TimeDelta::days(1);
+        for _ in 0..10 {
+            assert_eq!(history.pick("Movies", &items, day, 0, now).unwrap(), "Dune");
+        }
+    }
+
+    #[test]
+    fn recent_picks() {
+        let (dune, alien, heat) = (Item::new("Dune"), Item::new("Alien"), Item::new("Heat"));
+        let items = [&dune, &alien, &heat];
+        let now = time("2023-03-10T12:00:00Z");
+        let none = TimeDelta::zero();
+
+        let mut history = PickHistory::default();
+        history.record("Movies", "Dune", time("2023-01-01T12:00:00Z"));
+        history.record("Movies", "Alien", time("2023-03-01T12:00:00Z"));
+        history.record("Movies", "Heat", time("2023-02-01T12:00:00Z"));
+        assert_eq!(history.recent("Movies", 2), vec!["Alien", "Heat"]);
+        assert!(history.recent("Books", 2).is_empty());
+        for _ in 0..10 {
+            assert_eq!(
+                history.pick("Movies", &items, none, 2, now).unwrap(),
+                "Dune"
+            );
+        }
+
+        // Every item was picked recently so the oldest pick is repeated
+        assert_eq!(
+            history.pick("Movies", &items, none, 3, now).unwrap(),
+            "Dune"
+        );
+        assert_eq!(
+            history.pick("Movies", &items[1..], none, 3, now).unwrap(),
+            "Heat"
+        );
+    }
+
+    #[test]
+    fn picking_several() {
+        let (dune, alien, heat) = (Item::new("Dune"), Item::new("Alien"), Item::new("Heat"));
+        let items = [&dune, &alien, &heat];
+        let now = time("2023-03-10T12:00:00Z");
+        let week = TimeDelta::days(7);
+
+        let mut history = PickHistory::default();
+        history.record("Movies", "Dune", time("2023-03-09T12:00:00Z"));
+        history.record("Movies", "Alien", time("2023-03-08T12:00:00Z"));
+        assert_eq!(
+            history.pick_n("Movies", &items, 1, week, 0, now),
+            vec![&heat]
+        );
+        // Alien was picked before Dune, so it makes up the rest
+        let picked = history.pick_n("Movies", &items, 2, week, 0, now);
+        assert_eq!(picked, vec![&heat, &alien]);
+
+        let picked = history.pick_n("Movies", &items, 5, TimeDelta::zero(), 0, now);
+        assert_eq!(picked.len(), 3);
+        assert!(items.iter().all(|i| picked.contains(i)));
+        assert!(history.pick_n("Movies", &[], 2, week, 0, now).is_empty());
+    }
+
+    #[test]
+    fn seeded_picks() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let items: Vec<Item> = (1..=20).map(|n| Item::new(format!("Movie {n}"))).collect();
+        let items: Vec<&Item> = items.iter().collect();
+        let now = time("2023-03-10T12:00:00Z");
+        let history = PickHistory::default();
+
+        let pick = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            history.pick_n_with_rng("Movies", &items, 3, TimeDelta::zero(), 0, now, &mut rng)
+        };
+        assert_eq!(pick(42), pick(42));
+        assert_eq!(pick(42).len(), 3);
+        assert!((0..10).any(|seed| pick(seed) != pick(42)));
+    }
+
+    #[test]
+    fn daily_picks() {
+        let path = std::env::temp_dir().join(format!("wlist_daily_{}.json", std::process::id()));
+        let day = |d: &str| d.parse::<NaiveDate>().unwrap();
+        let pick = |date: &str, list: &str, item: &str| DailyPick {
+            date: day(date),
+            list: list.to_string(),
+            item: item.to_string(),
+        };
+
+        let mut daily = DailyPicks::from_file(&path).unwrap();
+        daily.set("Movies", pick("2023-03-09", "Movies", "Dune"));
+        daily.set("", pick("2023-03-09", "Anime", "Naruto"));
+        daily.to_file().unwrap();
+
+        let mut daily = DailyPicks::from_file(&path).unwrap();
+        assert_eq!(
+            daily.get("", day("2023-03-09")),
+            Some(&pick("2023-03-09", "Anime", "Naruto"))
+        );
+        assert_eq!(daily.get("Movies", day("2023-03-10")), None);
+        daily.set("Movies", pick("2023-03-10", "Movies", "Alien"));
+        assert_eq!(daily.get("", day("2023-03-09")), None);
+        assert_eq!(daily.picks.len(), 1);
+        std::fs::remove_file(crate::sidecar_path(&path, "daily")).unwrap();
+    }
+
+    #[test]
+    fn saved_history() {
+        let path = std::env::temp_dir().join(format!("wlist_picks_{}.json", std::process::id()));
+        let picked_at = time("2023-03-10T12:00:00Z");
+
+        let mut history = PickHistory::from_file(&path).unwrap();
+        assert_eq!(history.last_picked("Movies", "Dune"), None);
+        history.record("Movies", "Dune", picked_at);
+        history.to_file().unwrap();
+
+        let history = PickHistory::from_file(&path).unwrap();
+        assert_eq!(history.last_picked("Movies", "Dune"), Some(picked_at));
+        std::fs::remove_file(crate::sidecar_path(&path, "picks")).unwrap();
+    }
+}
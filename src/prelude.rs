@@ -0,0 +1,58 @@
+//! The stable part of the library, for tools built on top of it
+//!
+//! Everything exported here keeps working across minor and patch releases, and only a
+//! new major version changes or removes it. Other modules, in particular the ones
+//! storing and indexing the data file, may change in any release.
+//!
+//! ```
+//! use wlist::prelude::*;
+//!
+//! let mut watchlist = WatchList::new();
+//! watchlist.list_add("Movies")?;
+//! watchlist.item_add("Movies", "Alien", DuplicatePolicy::Exact)?;
+//! assert_eq!(watchlist.item_get_all("Movies")?, &vec![Item::new("Alien")]);
+//! # Ok::<(), WatchListError>(())
+//! ```
+
+pub use crate::progress::Progress;
+pub use crate::status::Status;
+pub use crate::{
+    DuplicatePolicy, Item, MergeReport, Metadata, Result, SortBy, WatchList, WatchListError,
+};
+// Kept until the next major version, for tools which still import it
+#[allow(deprecated)]
+pub use crate::WatchListFuncs;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "fs")]
+    use std::path::Path;
+
+    use super::*;
+
+    // Fails to build if the signatures of the stable functions change
+    #[test]
+    fn stable_api() {
+        #[cfg(feature = "fs")]
+        let _: fn(&Path) -> Result<WatchList> = WatchList::from_file;
+        #[cfg(feature = "fs")]
+        let _: fn(&WatchList, &Path) -> Result<()> = WatchList::to_file;
+        let _: fn(&str) -> Result<WatchList> = WatchList::from_json;
+        let _: fn(&WatchList) -> Result<String> = WatchList::to_json;
+        let _: fn(&mut WatchList, &str, Item, DuplicatePolicy) -> Result<()> = WatchList::item_add;
+        let _: fn(&mut WatchList, &str, &str) -> Result<()> = WatchList::item_remove;
+        let _: fn(&mut WatchList, &str) -> Result<()> = WatchList::list_add;
+        let _: for<'w> fn(&'w WatchList, &str) -> Result<&'w Vec<Item>> = WatchList::item_get_all;
+        let _: for<'w> fn(&'w WatchList, &str, SortBy) -> Result<Vec<&'w Item>> =
+            WatchList::item_get_sorted;
+        let _: for<'w> fn(&'w WatchList, &str, &str) -> Result<Vec<&'w Item>> =
+            WatchList::list_search;
+        let _: fn(&mut WatchList, &str, &str) -> Result<MergeReport> = WatchList::list_merge;
+        let _: fn(&Item) -> Status = Item::status;
+        let _: fn(&WatchListError) -> u8 = WatchListError::exit_code;
+
+        let item = Item::new("Alien");
+        assert_eq!(item.meta, Metadata::default());
+        assert_eq!("s1e3".parse::<Progress>().unwrap().to_string(), "s1e3");
+    }
+}
@@ -0,0 +1,144 @@
+//! Independent sets of lists, each kept in its own data file
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{WatchList, WatchListError};
+
+/// Name of the profile stored in the usual data file
+pub const DEFAULT: &str = "default";
+
+/// Profiles stored as `<name>.json` files in a directory, besides the default one
+#[derive(Debug, Clone)]
+pub struct Profiles {
+    dir: PathBuf,
+    default_file: PathBuf,
+}
+
+impl Profiles {
+    pub fn new(dir: &Path, default_file: &Path) -> Self {
+        Profiles {
+            dir: dir.to_path_buf(),
+            default_file: default_file.to_path_buf(),
+        }
+    }
+
+    /// Gets the data file of an existing profile
+    pub fn path(&self, name: &str) -> Result<PathBuf, WatchListError> {
+        if name == DEFAULT {
+            return Ok(self.default_file.clone());
+        }
+        let path = self.file(name)?;
+        if !path.is_file() {
+            return Err(WatchListError::ProfileNotPresent(name.to_string()));
+        }
+        Ok(path)
+    }
+
+    /// Gets the names of the profiles, the default one first and then alphabetically
+    pub fn names(&self) -> Result<Vec<String>, WatchListError> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![DEFAULT.to_string()]),
+            Err(e) => return Err(WatchListError::IOError(e)),
+        };
+        let mut names = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "json") && path.is_file() {
+                if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
+                    if check_name(name).is_ok() {
+                        names.push(name.to_string());
+                    }
+                }
+            }
+        }
+        names.sort();
+        names.insert(0, DEFAULT.to_string());
+        Ok(names)
+    }
+
+    /// Creates a profile without any lists, returning its data file
+    pub fn create(&self, name: &str) -> Result<PathBuf, WatchListError> {
+        let path = self.file(name)?;
+        if name == DEFAULT || path.exists() {
+            return Err(WatchListError::ProfileAlreadyPresent(name.to_string()));
+        }
+        fs::create_dir_all(&self.dir)?;
+        WatchList::new().to_file(&path)?;
+        Ok(path)
+    }
+
+    fn file(&self, name: &str) -> Result<PathBuf, WatchListError> {
+        check_name(name)?;
+        Ok(self.dir.join(format!("{name}.json")))
+    }
+}
+
+/// Gets the directory profiles are kept in when it isn't configured,
+/// like `watchlist.profiles` next to `watchlist.json`
+pub fn default_dir(data_file: &Path) -> PathBuf {
+    let stem = data_file
+        .file_stem()
+        .map(|s| s.to_string_lossy())
+        .unwrap_or_default();
+    data_file.with_file_name(format!("{stem}.profiles"))
+}
+
+// Names become file names, so only letters, digits, '-' and '_' are allowed
+fn check_name(name: &str) -> Result<(), WatchListError> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(WatchListError::InvalidProfile(name.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn creating() {
+        let dir = std::env::temp_dir().join(format!("wlist_profiles_{}", std::process::id()));
+        let default_file = dir.join("watchlist.json");
+        let profiles = Profiles::new(&default_dir(&default_file), &default_file);
+        assert_eq!(profiles.names().unwrap(), vec!["default"]);
+        assert_eq!(profiles.path("default").unwrap(), default_file);
+        assert_eq!(
+            profiles.path("partner").err().unwrap(),
+            WatchListError::ProfileNotPresent("partner".to_string())
+        );
+
+        let path = profiles.create("partner").unwrap();
+        assert_eq!(path, dir.join("watchlist.profiles").join("partner.json"));
+        assert!(WatchList::from_file(&path).unwrap().is_empty());
+        profiles.create("kids").unwrap();
+        assert_eq!(
+            profiles.names().unwrap(),
+            vec!["default", "kids", "partner"]
+        );
+        assert_eq!(profiles.path("partner").unwrap(), path);
+
+        assert_eq!(
+            profiles.create("partner").err().unwrap(),
+            WatchListError::ProfileAlreadyPresent("partner".to_string())
+        );
+        assert_eq!(
+            profiles.create("default").err().unwrap(),
+            WatchListError::ProfileAlreadyPresent("default".to_string())
+        );
+        for name in ["", "../partner", "a b"] {
+            assert_eq!(
+                profiles.create(name).err().unwrap(),
+                WatchListError::InvalidProfile(name.to_string())
+            );
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,100 @@
+//! How far along a show or book an item is
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::WatchListError;
+
+/// Position reached in an item, stored in the same short form it is entered in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Progress {
+    /// Season and episode of a show, like `s3e5`
+    Episode { season: u32, episode: u32 },
+    /// Chapter of a book or manga, like `ch120`
+    Chapter(u32),
+}
+
+// Parses a positive number
+fn number(s: &str) -> Option<u32> {
+    s.parse().ok().filter(|n| *n > 0)
+}
+
+impl FromStr for Progress {
+    type Err = WatchListError;
+
+    /// Accepts `s3e5` or `ch120`, ignoring case and spaces
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let input: String = s.split_whitespace().collect::<String>().to_lowercase();
+        let progress = if let Some(chapter) = input.strip_prefix("ch") {
+            number(chapter).map(Progress::Chapter)
+        } else if let Some((season, episode)) = input
+            .strip_prefix('s')
+            .and_then(|rest| rest.split_once('e'))
+        {
+            number(season)
+                .zip(number(episode))
+                .map(|(season, episode)| Progress::Episode { season, episode })
+        } else {
+            None
+        };
+        progress.ok_or(WatchListError::InvalidProgress(s.to_string()))
+    }
+}
+
+impl Display for Progress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Progress::Episode { season, episode } => write!(f, "s{season}e{episode}"),
+            Progress::Chapter(chapter) => write!(f, "ch{chapter}"),
+        }
+    }
+}
+
+impl TryFrom<String> for Progress {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse().map_err(|_| format!("invalid progress - {s}"))
+    }
+}
+
+impl From<Progress> for String {
+    fn from(progress: Progress) -> Self {
+        progress.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing() {
+        assert_eq!(
+            "s3e5".parse(),
+            Ok(Progress::Episode {
+                season: 3,
+                episode: 5
+            })
+        );
+        assert_eq!(
+            "S10 E12".parse(),
+            Ok(Progress::Episode {
+                season: 10,
+                episode: 12
+            })
+        );
+        assert_eq!("ch120".parse(), Ok(Progress::Chapter(120)));
+        assert_eq!("Ch 7".parse::<Progress>().unwrap().to_string(), "ch7");
+
+        for invalid in ["", "s3", "e5", "s0e1", "ch", "chapter 3", "s3e5x", "ch-1"] {
+            assert_eq!(
+                invalid.parse::<Progress>(),
+                Err(WatchListError::InvalidProgress(invalid.to_string()))
+            );
+        }
+    }
+}
@@ -0,0 +1,368 @@
+//! Filter expressions picking items by their fields, like `tag=horror AND status=unwatched`
+//!
+//! Comparisons are joined with `AND`, `OR` and `NOT` and grouped with parentheses.
+//! Any field from [`crate::fields::FIELDS`] can be compared, along with `list`
+//! for the list an item is in and `tag` as another name for `tags`
+
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::fields::{self, FIELDS};
+use crate::{Item, WatchList, WatchListError};
+
+/// A parsed filter expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    Compare {
+        field: String,
+        op: Op,
+        value: String,
+    },
+}
+
+/// Ways a field can be compared with a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// The field contains the value, ignoring case
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    // Quoted words are never keywords
+    Quoted(String),
+    Op(Op),
+    Open,
+    Close,
+}
+
+impl Query {
+    /// Checks if an item from the list matches the expression
+    pub fn matches(&self, list: &str, item: &Item) -> bool {
+        match self {
+            Query::And(a, b) => a.matches(list, item) && b.matches(list, item),
+            Query::Or(a, b) => a.matches(list, item) || b.matches(list, item),
+            Query::Not(q) => !q.matches(list, item),
+            Query::Compare { field, op, value } => {
+                let field_value = match field.as_str() {
+                    "list" => Value::String(list.to_string()),
+                    name => FIELDS
+                        .iter()
+                        .find(|f| f.name == name)
+                        .map_or(Value::Null, |f| f.value(item)),
+                };
+                compare(&field_value, *op, value)
+            }
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = WatchListError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(s)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let query = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(query),
+            Some(token) => Err(invalid(&format!("unexpected {}", describe(token)))),
+        }
+    }
+}
+
+/// Gets the items from every list which match the expression, with the lists in alphabetical order
+pub fn filter<'a>(watchlist: &'a WatchList, query: &Query) -> Vec<(&'a str, &'a Item)> {
+    let mut titles: Vec<&String> = watchlist.titles().collect();
+    titles.sort();
+    titles
+        .into_iter()
+        .flat_map(|title| {
+            watchlist[title]
+                .iter()
+                .filter(|item| query.matches(title, item))
+                .map(move |item| (title.as_str(), item))
+        })
+        .collect()
+}
+
+fn invalid(message: &str) -> WatchListError {
+    WatchListError::InvalidQuery(message.to_string())
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Word(w) | Token::Quoted(w) => format!("'{w}'"),
+        Token::Op(_) => "comparison".to_string(),
+        Token::Open => "'('".to_string(),
+        Token::Close => "')'".to_string(),
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, WatchListError> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        chars.next();
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '=' => Token::Op(Op::Eq),
+            '~' => Token::Op(Op::Contains),
+            '!' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::Ne),
+            '<' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::Le),
+            '<' => Token::Op(Op::Lt),
+            '>' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::Ge),
+            '>' => Token::Op(Op::Gt),
+            '"' => {
+                let mut word = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(invalid("missing closing quote")),
+                    }
+                }
+                Token::Quoted(word)
+            }
+            c => {
+                let mut word = c.to_string();
+                while let Some(c) =
+                    chars.next_if(|c| !c.is_whitespace() && !"()=~!<>\"".contains(*c))
+                {
+                    word.push(c);
+                }
+                Token::Word(word)
+            }
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+// Recursive descent parser where NOT binds tighter than AND, which binds tighter than OR
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        match self.tokens.get(self.pos) {
+            Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn or(&mut self) -> Result<Query, WatchListError> {
+        let mut query = self.and()?;
+        while self.keyword("or") {
+            query = Query::Or(Box::new(query), Box::new(self.and()?));
+        }
+        Ok(query)
+    }
+
+    fn and(&mut self) -> Result<Query, WatchListError> {
+        let mut query = self.not()?;
+        while self.keyword("and") {
+            query = Query::And(Box::new(query), Box::new(self.not()?));
+        }
+        Ok(query)
+    }
+
+    fn not(&mut self) -> Result<Query, WatchListError> {
+        if self.keyword("not") {
+            return Ok(Query::Not(Box::new(self.not()?)));
+        }
+        match self.next() {
+            Some(Token::Open) => {
+                let query = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(query),
+                    _ => Err(invalid("missing ')'")),
+                }
+            }
+            Some(Token::Word(field) | Token::Quoted(field)) => {
+                let field = match field.to_lowercase().as_str() {
+                    "tag" => "tags".to_string(),
+                    "list" => "list".to_string(),
+                    _ => fields::lookup(&[&field])?[0].name.to_string(),
+                };
+                let Some(Token::Op(op)) = self.next() else {
+                    return Err(invalid(&format!("expected a comparison after '{field}'")));
+                };
+                match self.next() {
+                    Some(Token::Word(value) | Token::Quoted(value)) => {
+                        Ok(Query::Compare { field, op, value })
+                    }
+                    _ => Err(invalid(&format!(
+                        "expected a value to compare '{field}' with"
+                    ))),
+                }
+            }
+            Some(token) => Err(invalid(&format!("unexpected {}", describe(&token)))),
+            None => Err(invalid("expected a comparison like tag=horror")),
+        }
+    }
+}
+
+// Lists match if any of their values do, while missing values only match `!=`
+fn compare(field: &Value, op: Op, value: &str) -> bool {
+    match field {
+        Value::Null => op == Op::Ne,
+        Value::Array(values) if op == Op::Ne => !values.iter().any(|v| compare(v, Op::Eq, value)),
+        Value::Array(values) => values.iter().any(|v| compare(v, op, value)),
+        Value::Object(map) => map.iter().any(|(k, v)| {
+            compare(
+                &Value::String(format!("{k}:{}", v.as_str().unwrap_or_default())),
+                op,
+                value,
+            )
+        }),
+        Value::String(s) => compare_text(s, op, value),
+        other => compare_text(&other.to_string(), op, value),
+    }
+}
+
+fn compare_text(text: &str, op: Op, value: &str) -> bool {
+    let (text, value) = (text.to_lowercase(), value.to_lowercase());
+    // Numbers like ratings and years are compared by value, and everything else
+    // alphabetically which also puts ISO dates in order
+    let ordering = match (text.parse::<f64>(), value.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        _ => text.cmp(&value),
+    };
+    match op {
+        Op::Eq => ordering == Ordering::Equal,
+        Op::Ne => ordering != Ordering::Equal,
+        Op::Lt => ordering == Ordering::Less,
+        Op::Le => ordering != Ordering::Greater,
+        Op::Gt => ordering == Ordering::Greater,
+        Op::Ge => ordering != Ordering::Less,
+        Op::Contains => text.contains(&value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compare(field: &str, op: Op, value: &str) -> Query {
+        Query::Compare {
+            field: field.to_string(),
+            op,
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn parsing() {
+        assert_eq!(
+            "tag=horror AND NOT (rating < 5 or title~\"the thing\")"
+                .parse::<Query>()
+                .unwrap(),
+            Query::And(
+                Box::new(compare("tags", Op::Eq, "horror")),
+                Box::new(Query::Not(Box::new(Query::Or(
+                    Box::new(compare("rating", Op::Lt, "5")),
+                    Box::new(compare("title", Op::Contains, "the thing")),
+                )))),
+            )
+        );
+        // AND binds tighter than OR
+        assert_eq!(
+            "list=TV or Status=watched and year>=2000"
+                .parse::<Query>()
+                .unwrap(),
+            Query::Or(
+                Box::new(compare("list", Op::Eq, "TV")),
+                Box::new(Query::And(
+                    Box::new(compare("status", Op::Eq, "watched")),
+                    Box::new(compare("year", Op::Ge, "2000")),
+                )),
+            )
+        );
+
+        assert_eq!(
+            "genre=horror".parse::<Query>().err().unwrap(),
+            WatchListError::UnknownField("genre".to_string())
+        );
+        for broken in [
+            "",
+            "tag",
+            "tag=",
+            "(tag=a",
+            "tag=a)",
+            "tag=a AND",
+            "title=\"a",
+        ] {
+            assert!(
+                matches!(
+                    broken.parse::<Query>(),
+                    Err(WatchListError::InvalidQuery(_))
+                ),
+                "{broken}"
+            );
+        }
+    }
+
+    #[test]
+    fn filtering() {
+        let mut watchlist = WatchList::new();
+        watchlist.insert(
+            "Movies".to_string(),
+            vec![
+                Item::new("Alien").with_tags(&["horror", "scifi"]),
+                Item::new("The Thing").with_tags(&["Horror"]).watched(true),
+                Item::new("Dune").with_year(2021),
+            ],
+        );
+        watchlist.insert(
+            "Books".to_string(),
+            vec![Item::new("It").with_tags(&["horror"])],
+        );
+        watchlist.get_mut("Movies").unwrap()[0].meta.rating = Some(9);
+
+        let titles = |query: &str| -> Vec<&str> {
+            filter(&watchlist, &query.parse().unwrap())
+                .iter()
+                .map(|(_, i)| i.title.as_str())
+                .collect()
+        };
+        assert_eq!(titles("tag=horror"), vec!["It", "Alien", "The Thing"]);
+        assert_eq!(
+            titles("tag=horror AND status=unwatched"),
+            vec!["It", "Alien"]
+        );
+        assert_eq!(titles("tag!=horror"), vec!["Dune"]);
+        assert_eq!(
+            titles("list=movies and not tag=scifi"),
+            vec!["The Thing", "Dune"]
+        );
+        assert_eq!(titles("rating>=9 or year>2020"), vec!["Alien", "Dune"]);
+        assert_eq!(titles("rating<9"), Vec::<&str>::new());
+        assert_eq!(titles("title~the"), vec!["The Thing"]);
+    }
+}
@@ -0,0 +1,76 @@
+//! Items added lately, for `wl show --recent` and `wl show --since`
+
+use std::cmp::Reverse;
+
+use chrono::NaiveDate;
+
+use crate::{dates, Item, WatchList};
+
+/// Gets all items which know when they were added (along with their list titles), newest first
+pub fn items_by_added(watchlist: &WatchList) -> Vec<(&String, &Item)> {
+    let mut added: Vec<(&String, &Item)> = watchlist
+        .iter()
+        .flat_map(|(title, items)| items.iter().map(move |i| (title, i)))
+        .filter(|(_, item)| item.meta.added_at.is_some())
+        .collect();
+    added.sort_by_key(|(title, item)| (Reverse(item.meta.added_at), *title));
+    added
+}
+
+/// Gets all items (along with their list titles) added on `since` or after it in the local
+/// time zone, newest first
+pub fn items_added_since(watchlist: &WatchList, since: NaiveDate) -> Vec<(&String, &Item)> {
+    let mut added = items_by_added(watchlist);
+    added.retain(|(_, item)| {
+        item.meta
+            .added_at
+            .is_some_and(|at| dates::local_date(&at) >= since)
+    });
+    added
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dates::Timestamp;
+
+    fn at(s: &str) -> Timestamp {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn by_added() {
+        let mut watchlist = WatchList::new();
+        watchlist.insert(
+            "Movies".to_string(),
+            vec![
+                Item::new("Movie 1").with_added_at(at("2023-02-01T12:00:00Z")),
+                Item::new("Movie 2"),
+                Item::new("Movie 3").with_added_at(at("2023-03-10T12:00:00Z")),
+            ],
+        );
+        watchlist.insert(
+            "TV".to_string(),
+            vec![Item::new("Show 1").with_added_at(at("2023-03-01T12:00:00Z"))],
+        );
+
+        let added = items_by_added(&watchlist);
+        let titles: Vec<(&str, &str)> = added
+            .iter()
+            .map(|(list, i)| (list.as_str(), i.title.as_str()))
+            .collect();
+        assert_eq!(
+            titles,
+            vec![
+                ("Movies", "Movie 3"),
+                ("TV", "Show 1"),
+                ("Movies", "Movie 1")
+            ]
+        );
+
+        let since = items_added_since(&watchlist, "2023-02-15".parse().unwrap());
+        let titles: Vec<&str> = since.iter().map(|(_, i)| i.title.as_str()).collect();
+        assert_eq!(titles, vec!["Movie 3", "Show 1"]);
+        assert!(items_added_since(&watchlist, "2024-01-01".parse().unwrap()).is_empty());
+    }
+}
@@ -0,0 +1,319 @@
+//! Tables sized to fit their content and the width of the terminal, like the numbered
+//! items of a list along with their ratings, statuses and tags
+//!
+//! ```
+//! use wlist::render::{Align, Column, Table};
+//!
+//! let mut table = Table::new()
+//!     .column(Column::new("#").align(Align::Right))
+//!     .column(Column::new("title"))
+//!     .column(Column::new("rating").align(Align::Right));
+//! table.row(["1.", "Alien", "9/10"]);
+//! table.row(["2.", "The Thing", ""]);
+//! assert_eq!(table.render(None), ["1. | Alien     | 9/10", "2. | The Thing"]);
+//! ```
+//!
+//! Cells can hold colored text, whose escape codes don't count towards the width of their
+//! column. When the table is wider than the terminal, the widest columns are narrowed and
+//! text which doesn't fit is cut short with "…"
+
+use crate::theme::{self, Style};
+
+/// Columns aren't narrowed below this many characters to fit the terminal
+pub const MIN_WIDTH: usize = 6;
+
+/// Side of its column text is lined up against
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Left,
+    Right,
+}
+
+/// Column of a table, with the name shown above it when the table has headers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    header: String,
+    align: Align,
+    style: Option<Style>,
+}
+
+impl Column {
+    pub fn new(header: &str) -> Self {
+        Column {
+            header: header.to_string(),
+            align: Align::Left,
+            style: None,
+        }
+    }
+
+    pub fn align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// Styles every cell of the column with the current theme
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = Some(style);
+        self
+    }
+}
+
+/// Rows of cells lined up in columns
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    columns: Vec<Column>,
+    rows: Vec<Vec<String>>,
+    separator: String,
+    headers: bool,
+}
+
+impl Default for Table {
+    fn default() -> Self {
+        Table {
+            columns: Vec::new(),
+            rows: Vec::new(),
+            separator: " | ".to_string(),
+            headers: false,
+        }
+    }
+}
+
+impl Table {
+    pub fn new() -> Self {
+        Table::default()
+    }
+
+    pub fn column(mut self, column: Column) -> Self {
+        self.columns.push(column);
+        self
+    }
+
+    /// Text put between the columns, " | " by default
+    pub fn separator(mut self, separator: &str) -> Self {
+        self.separator = separator.to_string();
+        self
+    }
+
+    /// Shows the names of the columns in the first row
+    ///
+    /// Columns without anything in them are left out unless the headers are shown
+    pub fn headers(mut self) -> Self {
+        self.headers = true;
+        self
+    }
+
+    /// Adds a row, where missing cells are left empty and extra cells are dropped
+    pub fn row<T: Into<String>>(&mut self, cells: impl IntoIterator<Item = T>) {
+        let mut cells: Vec<String> = cells.into_iter().map(Into::into).collect();
+        cells.resize(self.columns.len(), String::new());
+        self.rows.push(cells);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Lines of the table, no wider than `max_width` characters when possible
+    pub fn render(&self, max_width: Option<usize>) -> Vec<String> {
+        let mut rows: Vec<Vec<&str>> = Vec::new();
+        if self.headers {
+            rows.push(self.columns.iter().map(|c| c.header.as_str()).collect());
+        }
+        rows.extend(
+            self.rows
+                .iter()
+                .map(|row| row.iter().map(String::as_str).collect()),
+        );
+        let shown: Vec<usize> = (0..self.columns.len())
+            .filter(|&c| self.headers || rows.iter().any(|row| !row[c].is_empty()))
+            .collect();
+
+        let mut widths: Vec<usize> = shown
+            .iter()
+            .map(|&c| rows.iter().map(|row| width(row[c])).max().unwrap_or(0))
+            .collect();
+        if let Some(max_width) = max_width {
+            narrow(&mut widths, max_width, width(&self.separator));
+        }
+
+        rows.iter()
+            .enumerate()
+            .map(|(r, row)| {
+                let mut cells: Vec<String> = shown
+                    .iter()
+                    .zip(&widths)
+                    .enumerate()
+                    .map(|(i, (&c, &width))| {
+                        let column = &self.columns[c];
+                        let text = truncate(row[c], width);
+                        // The last column isn't padded when nothing follows it
+                        let padding = match (column.align, i + 1 == shown.len()) {
+                            (Align::Left, true) => 0,
+                            _ => width - self::width(&text),
+                        };
+                        let text = match (column.style, self.headers && r == 0) {
+                            (_, true) => theme::paint(&text, Style::Heading).to_string(),
+                            (Some(style), false) if !text.is_empty() => {
+                                theme::paint(&text, style).to_string()
+                            }
+                            _ => text,
+                        };
+                        match column.align {
+                            Align::Left => format!("{text}{}", " ".repeat(padding)),
+                            Align::Right => format!("{}{text}", " ".repeat(padding)),
+                        }
+                    })
+                    .collect();
+                // Nothing is left dangling after the last cell with anything in it
+                while cells.last().is_some_and(|c| c.trim().is_empty()) {
+                    cells.pop();
+                }
+                cells.join(&self.separator).trim_end().to_string()
+            })
+            .collect()
+    }
+}
+
+// Takes a character at a time from the widest columns until the table fits, or every
+// column is as narrow as it can be
+fn narrow(widths: &mut [usize], max_width: usize, separator: usize) {
+    let separators = separator * widths.len().saturating_sub(1);
+    let mut total: usize = widths.iter().sum::<usize>() + separators;
+    while total > max_width {
+        let Some(widest) = widths
+            .iter_mut()
+            .filter(|w| **w > MIN_WIDTH)
+            .max_by_key(|w| **w)
+        else {
+            break;
+        };
+        *widest -= 1;
+        total -= 1;
+    }
+}
+
+/// Number of characters the text takes up on the terminal, leaving out color codes
+pub fn width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => {
+                chars.by_ref().find(|c| c.is_ascii_alphabetic());
+            }
+            _ => width += 1,
+        }
+    }
+    width
+}
+
+/// Cuts the text short with "…" to fit in the width, keeping its color codes
+pub fn truncate(text: &str, max_width: usize) -> String {
+    if width(text) <= max_width {
+        return text.to_string();
+    }
+    let mut truncated = String::new();
+    let mut colored = false;
+    let mut left = max_width.saturating_sub(1);
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => {
+                colored = true;
+                truncated.push(c);
+                for c in chars.by_ref() {
+                    truncated.push(c);
+                    if c.is_ascii_alphabetic() {
+                        break;
+                    }
+                }
+            }
+            _ if left == 0 => break,
+            _ => {
+                truncated.push(c);
+                left -= 1;
+            }
+        }
+    }
+    if max_width > 0 {
+        truncated.push('…');
+    }
+    // Colors cut off along with their text are reset
+    if colored {
+        truncated.push_str("\x1b[0m");
+    }
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items() -> Table {
+        let mut table = Table::new()
+            .column(Column::new("#").align(Align::Right))
+            .column(Column::new("title"))
+            .column(Column::new("rating").align(Align::Right))
+            .column(Column::new("tags"));
+        table.row(["1.", "Alien", "9/10", "#horror"]);
+        table.row([
+            "10.",
+            "The Texas Chain Saw Massacre",
+            "",
+            "#horror #classic",
+        ]);
+        table
+    }
+
+    #[test]
+    fn sizing() {
+        colored::control::set_override(false);
+        assert_eq!(
+            items().render(None),
+            [
+                " 1. | Alien                        | 9/10 | #horror",
+                "10. | The Texas Chain Saw Massacre |      | #horror #classic",
+            ]
+        );
+
+        // Empty columns are only shown along with the headers
+        let mut table = items();
+        table.row(["11.", "Heat"]);
+        table.rows.iter_mut().for_each(|row| row[2].clear());
+        assert_eq!(table.render(None)[2], "11. | Heat");
+        assert_eq!(
+            table.headers().render(None)[0],
+            "  # | title                        | rating | tags"
+        );
+    }
+
+    #[test]
+    fn narrowing() {
+        colored::control::set_override(false);
+        let lines = items().render(Some(40));
+        assert_eq!(
+            lines,
+            [
+                " 1. | Alien        | 9/10 | #horror",
+                "10. | The Texas C… |      | #horror #cl…",
+            ]
+        );
+        assert!(lines.iter().all(|l| width(l) <= 40));
+
+        // Columns don't get narrower than the minimum
+        let lines = items().render(Some(10));
+        assert_eq!(lines[1], "10. | The T… |      | #horr…");
+    }
+
+    #[test]
+    fn colored_text() {
+        let red = "\x1b[31mAlien\x1b[0m";
+        assert_eq!(width(red), 5);
+        assert_eq!(width("Amélie"), 6);
+        assert_eq!(truncate(red, 5), red);
+        assert_eq!(truncate(red, 3), "\x1b[31mAl…\x1b[0m");
+        assert_eq!(truncate("Alien", 1), "…");
+        assert_eq!(truncate("Alien", 0), "");
+    }
+}
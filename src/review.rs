@@ -0,0 +1,123 @@
+//! Reviews stepping through the items of a list one at a time to decide what to do with each
+//!
+//! The items already reviewed are saved after every decision, so a review which was
+//! interrupted carries on from where it stopped
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::{encryption, Item, WatchListError};
+
+/// What to do with the item being reviewed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Keep,
+    Delete,
+    /// Move the item to the archive list
+    Archive,
+    /// Add tags to the item and keep it
+    Tag,
+    /// Stop reviewing, to carry on later
+    Quit,
+}
+
+impl FromStr for Decision {
+    type Err = ();
+
+    /// Accepts the first letter or the whole word, ignoring case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "" | "k" | "keep" => Ok(Decision::Keep),
+            "d" | "delete" => Ok(Decision::Delete),
+            "a" | "archive" => Ok(Decision::Archive),
+            "t" | "tag" => Ok(Decision::Tag),
+            "q" | "quit" => Ok(Decision::Quit),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Items already reviewed in each list, saved to a file
+#[derive(Debug, Default)]
+pub struct Reviews {
+    file_path: PathBuf,
+    path: PathBuf,
+    // List title -> titles of the items reviewed so far
+    reviewed: HashMap<String, Vec<String>>,
+}
+
+impl Reviews {
+    /// Reads the reviews in progress next to the data file, starting with none if there isn't any
+    pub fn from_file(file_path: &Path) -> Result<Self, WatchListError> {
+        let path = crate::sidecar_path(file_path, "review");
+        let reviewed = match encryption::read_sidecar(file_path, &path)? {
+            Some(data) => serde_json::from_str(&data)
+                .map_err(|e| WatchListError::ParseError(e.to_string()))?,
+            None => HashMap::new(),
+        };
+        Ok(Reviews {
+            file_path: file_path.to_path_buf(),
+            path,
+            reviewed,
+        })
+    }
+
+    pub fn to_file(&self) -> Result<(), WatchListError> {
+        let data =
+            serde_json::to_string(&self.reviewed).map_err(|e| WatchListError::IOError(e.into()))?;
+        encryption::write_sidecar(&self.file_path, &self.path, data.as_bytes())
+    }
+
+    /// Gets the items of the list which haven't been reviewed yet, in order
+    pub fn remaining<'a>(&self, list: &str, items: &'a [Item]) -> Vec<&'a Item> {
+        let reviewed = self.reviewed.get(list);
+        items
+            .iter()
+            .filter(|i| reviewed.is_none_or(|r| !r.contains(&i.title)))
+            .collect()
+    }
+
+    pub fn mark(&mut self, list: &str, item: &str) {
+        self.reviewed
+            .entry(list.to_string())
+            .or_default()
+            .push(item.to_string());
+    }
+
+    /// Forgets the progress through the list so the next review starts from the beginning
+    pub fn restart(&mut self, list: &str) {
+        self.reviewed.remove(list);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resuming() {
+        let path = std::env::temp_dir().join(format!("wlist_review_{}.json", std::process::id()));
+        let items = [Item::new("Alien"), Item::new("Dune"), Item::new("Heat")];
+
+        let mut reviews = Reviews::from_file(&path).unwrap();
+        assert_eq!(reviews.remaining("Movies", &items).len(), 3);
+        reviews.mark("Movies", "Alien");
+        reviews.mark("Movies", "Heat");
+        reviews.to_file().unwrap();
+
+        let mut reviews = Reviews::from_file(&path).unwrap();
+        assert_eq!(reviews.remaining("Movies", &items), vec![&items[1]]);
+        assert_eq!(reviews.remaining("Books", &items).len(), 3);
+        reviews.restart("Movies");
+        assert_eq!(reviews.remaining("Movies", &items).len(), 3);
+        std::fs::remove_file(crate::sidecar_path(&path, "review")).unwrap();
+
+        assert_eq!("".parse(), Ok(Decision::Keep));
+        assert_eq!(" D ".parse(), Ok(Decision::Delete));
+        assert_eq!("archive".parse(), Ok(Decision::Archive));
+        assert_eq!("t".parse(), Ok(Decision::Tag));
+        assert_eq!("Quit".parse(), Ok(Decision::Quit));
+        assert_eq!("x".parse::<Decision>(), Err(()));
+    }
+}
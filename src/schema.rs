@@ -0,0 +1,466 @@
+//! Versions of the format of the data file
+//!
+//! Rewriting a file into a newer version can stop older versions of `wl` from reading
+//! it, so the changes are shown and agreed to first and a copy of the file in the old
+//! version is kept next to it
+//!
+//! Files from version 3 on hold their version next to the lists, and from version 4 on the
+//! [settings](crate::ListSettings) of the lists too, while older ones are told apart by how
+//! their items are saved. Files in older versions are read by running the
+//! [migrations](MIGRATIONS) after their version one by one, so each only has to upgrade
+//! the version before it
+
+use std::collections::BTreeMap;
+#[cfg(feature = "fs")]
+use std::fs;
+use std::io::{self, Write};
+#[cfg(feature = "fs")]
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{Item, ListSettings, Metadata, WatchList};
+
+/// Newest version of the data file this version of `wl` writes
+pub const CURRENT: u32 = 4;
+
+/// What changed in each version, oldest first
+pub const CHANGES: &[(u32, &str)] = &[
+    (
+        2,
+        "Items with tags, ratings, progress, due dates or other details are saved as objects \
+         instead of plain titles, which older versions of wl can't read",
+    ),
+    (
+        3,
+        "The version of the file is saved in it along with the lists, so files saved by \
+         newer versions of wl are recognised instead of failing to parse",
+    ),
+    (
+        4,
+        "Settings of lists, like leaving them out of random picks, are saved next to the \
+         lists so they follow a list when it's renamed, which older versions of wl would drop",
+    ),
+];
+
+/// Step upgrading the data of a file to a version from the one before it
+pub struct Migration {
+    pub to: u32,
+    pub migrate: fn(Value) -> Value,
+}
+
+/// Steps upgrading files in older versions, oldest first
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        to: 2,
+        migrate: to_v2,
+    },
+    Migration {
+        to: 3,
+        migrate: to_v3,
+    },
+    Migration {
+        to: 4,
+        migrate: to_v4,
+    },
+];
+
+// Items with details can be saved as objects in version 2, but plain titles are saved the
+// same way, so every file in version 1 is already one in version 2
+fn to_v2(data: Value) -> Value {
+    data
+}
+
+fn to_v3(data: Value) -> Value {
+    json!({ "version": 3, "lists": data })
+}
+
+// Files in version 3 are files in version 4 where no list has any settings
+fn to_v4(data: Value) -> Value {
+    json!({ "version": 4, "lists": data["lists"] })
+}
+
+// The data file from version 3 on, with the settings of the lists from version 4 on
+#[derive(Serialize, Deserialize)]
+struct Versioned<L, S> {
+    version: u32,
+    lists: L,
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    settings: Option<S>,
+}
+
+// Settings of the lists as they're read, where settings of lists which aren't in the
+// file are left out
+type Settings = BTreeMap<String, ListSettings>;
+
+fn with_settings(mut watchlist: WatchList, settings: Option<Settings>) -> WatchList {
+    for (title, settings) in settings.unwrap_or_default() {
+        if let Ok(list) = watchlist.list_settings_mut(&title) {
+            *list = settings;
+        }
+    }
+    watchlist
+}
+
+/// Gets the oldest version able to hold the lists
+///
+/// When items were added doesn't count, as a file which only holds titles isn't upgraded
+/// just to keep it, see [`save_version`]
+pub fn needed(watchlist: &WatchList) -> u32 {
+    if watchlist.settings().next().is_some() {
+        return 4;
+    }
+    match watchlist.values().flatten().any(has_details) {
+        true => 2,
+        false => 1,
+    }
+}
+
+fn has_details(item: &Item) -> bool {
+    match item.meta.added_at {
+        None => item.meta != Metadata::default(),
+        Some(_) => {
+            let meta = Metadata {
+                added_at: None,
+                ..item.meta.clone()
+            };
+            meta != Metadata::default()
+        }
+    }
+}
+
+/// Gets the version the lists are saved in when the file is in `file_version`
+///
+/// Files stay in their version if it can hold the lists, and are saved in the current
+/// version otherwise. Files in version 1 stay plain lists of titles which every version of
+/// `wl` can read, leaving out when the items were added
+pub fn save_version(file_version: u32, watchlist: &WatchList) -> u32 {
+    match needed(watchlist) {
+        needed if file_version >= needed => file_version,
+        _ => CURRENT,
+    }
+}
+
+/// Gets the version a file holding the lists is saved in when the version it's in isn't
+/// known, which is version 1 when they only hold titles
+pub fn version(watchlist: &WatchList) -> u32 {
+    let dated = watchlist
+        .values()
+        .flatten()
+        .any(|i| i.meta.added_at.is_some());
+    match needed(watchlist) {
+        1 if !dated => 1,
+        _ => CURRENT,
+    }
+}
+
+/// Gets the version the data of a file is in
+pub fn detect(data: &Value) -> u32 {
+    if let (Some(version), Some(_)) = (
+        data.get("version").and_then(Value::as_u64),
+        data.get("lists"),
+    ) {
+        return u32::try_from(version).unwrap_or(u32::MAX);
+    }
+    let objects = data
+        .as_object()
+        .into_iter()
+        .flat_map(|lists| lists.values())
+        .filter_map(Value::as_array)
+        .flatten()
+        .any(Value::is_object);
+    match objects {
+        true => 2,
+        false => 1,
+    }
+}
+
+/// Upgrades the data of a file from its version to the current one
+pub fn migrate(mut data: Value, from: u32) -> Value {
+    for migration in MIGRATIONS.iter().filter(|m| m.to > from) {
+        data = (migration.migrate)(data);
+    }
+    data
+}
+
+/// Reads the lists from the data of a file in any version up to the current one, along
+/// with the version it was in
+pub fn parse(data: &[u8]) -> io::Result<(WatchList, u32)> {
+    // Files in the current version are read straight into the lists, without going
+    // through a copy of the whole file first
+    if let Ok(file) = serde_json::from_slice::<Versioned<WatchList, Settings>>(data) {
+        if file.version == CURRENT {
+            return Ok((with_settings(file.lists, file.settings), CURRENT));
+        }
+    }
+    let data: Value = serde_json::from_slice(data)?;
+    let version = detect(&data);
+    if version > CURRENT {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("the file is in version {version}, saved by a newer version of wl than this one which only reads up to version {CURRENT}"),
+        ));
+    }
+    let file: Versioned<WatchList, Settings> = serde_json::from_value(migrate(data, version))?;
+    Ok((with_settings(file.lists, file.settings), version))
+}
+
+/// Writes the lists in the version, which has to be able to hold them, pretty-printed
+/// unless `compact` is given
+///
+/// Lists are written sorted by title, so the same lists are always written the same way
+/// and files kept in version control only change where the lists did
+pub fn write(
+    watchlist: &WatchList,
+    version: u32,
+    compact: bool,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    let lists: BTreeMap<&String, &Vec<Item>> = watchlist.iter().collect();
+    match version {
+        // Only titles are left, so when the items were added is left out
+        1 => {
+            let titles: BTreeMap<&String, Vec<&str>> = lists
+                .iter()
+                .map(|(title, items)| (*title, items.iter().map(|i| i.title.as_str()).collect()))
+                .collect();
+            write_json(&titles, compact, &mut writer)
+        }
+        2 => write_json(&lists, compact, &mut writer),
+        3 => {
+            let file = Versioned::<_, ()> {
+                version,
+                lists,
+                settings: None,
+            };
+            write_json(&file, compact, &mut writer)
+        }
+        version => {
+            let settings: BTreeMap<&String, &ListSettings> = watchlist.settings().collect();
+            let file = Versioned {
+                version,
+                lists,
+                settings: Some(settings).filter(|s| !s.is_empty()),
+            };
+            write_json(&file, compact, &mut writer)
+        }
+    }
+}
+
+fn write_json(data: &impl Serialize, compact: bool, mut writer: impl Write) -> io::Result<()> {
+    match compact {
+        true => serde_json::to_writer(writer, data)?,
+        false => {
+            serde_json::to_writer_pretty(&mut writer, data)?;
+            writer.write_all(b"\n")?;
+        }
+    }
+    Ok(())
+}
+
+/// Gets what changes when upgrading from one version to another
+pub fn changes(from: u32, to: u32) -> Vec<&'static str> {
+    CHANGES
+        .iter()
+        .filter(|(version, _)| (from + 1..=to).contains(version))
+        .map(|(_, change)| *change)
+        .collect()
+}
+
+/// Copies the file as it is before upgrading it, for older versions of `wl` to keep using
+#[cfg(feature = "fs")]
+pub fn backup(file_path: &Path, version: u32) -> io::Result<PathBuf> {
+    let backup = crate::sidecar_path(file_path, &format!("v{version}"));
+    fs::copy(file_path, &backup)?;
+    Ok(backup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Item;
+
+    #[test]
+    fn versions() {
+        let mut watchlist = WatchList::new();
+        assert_eq!(version(&watchlist), 1);
+        watchlist.insert("Movies".to_string(), vec![Item::new("Alien")]);
+        assert_eq!(version(&watchlist), 1);
+        watchlist.item_set_rating("Movies", "Alien", 8).unwrap();
+        assert_eq!(version(&watchlist), CURRENT);
+
+        // Files stay in their version while it can hold the lists
+        assert_eq!(needed(&watchlist), 2);
+        assert_eq!(save_version(2, &watchlist), 2);
+        assert_eq!(save_version(1, &watchlist), CURRENT);
+        assert_eq!(save_version(1, &WatchList::new()), 1);
+        assert_eq!(save_version(CURRENT, &WatchList::new()), CURRENT);
+
+        // Files in version 1 aren't upgraded only to keep when items were added
+        let now = "2023-03-01T12:00:00Z".parse().unwrap();
+        let mut dated = WatchList::new();
+        dated.insert(
+            "Movies".to_string(),
+            vec![Item::new("Alien").with_added_at(now)],
+        );
+        assert_eq!(needed(&dated), 1);
+        assert_eq!(save_version(1, &dated), 1);
+        assert_eq!(save_version(2, &dated), 2);
+        assert_eq!(version(&dated), CURRENT);
+
+        assert_eq!(changes(1, 2).len(), 1);
+        assert_eq!(changes(1, CURRENT).len(), 3);
+
+        // Settings of lists are only saved from version 4 on
+        watchlist.list_settings_mut("Movies").unwrap().no_random = true;
+        assert_eq!(needed(&watchlist), 4);
+        assert_eq!(save_version(3, &watchlist), 4);
+        assert!(changes(2, 2).is_empty());
+        assert!(changes(1, 1).is_empty());
+    }
+
+    #[test]
+    fn detecting() {
+        assert_eq!(detect(&json!({ "Movies": ["Alien"], "Books": [] })), 1);
+        assert_eq!(
+            detect(&json!({ "Movies": ["Alien", { "title": "Heat" }] })),
+            2
+        );
+        assert_eq!(detect(&json!({ "version": 3, "lists": {} })), 3);
+        assert_eq!(detect(&json!({ "version": 7, "lists": {} })), 7);
+        // A list which happens to be called "version"
+        assert_eq!(detect(&json!({ "version": ["Alien"], "lists": [] })), 1);
+    }
+
+    #[test]
+    fn migrations() {
+        let v1 = json!({ "Movies": ["Alien"] });
+        let v2 = to_v2(v1.clone());
+        assert_eq!(v2, v1);
+        let v2 = json!({ "Movies": ["Alien", { "title": "Heat", "rating": 8 }] });
+        let v3 = to_v3(v2.clone());
+        assert_eq!(v3, json!({ "version": 3, "lists": v2 }));
+        let v4 = to_v4(v3.clone());
+        assert_eq!(v4, json!({ "version": 4, "lists": v2 }));
+
+        // Every step is run from the version of the file on
+        assert_eq!(migrate(v1.clone(), 1), json!({ "version": 4, "lists": v1 }));
+        assert_eq!(migrate(v3.clone(), 3), v4);
+        assert_eq!(migrate(v4.clone(), 4), v4);
+        assert_eq!(MIGRATIONS.last().map(|m| m.to), Some(CURRENT));
+    }
+
+    #[test]
+    fn reading_and_writing() {
+        let mut watchlist = WatchList::new();
+        watchlist.insert("Movies".to_string(), vec![Item::new("Alien")]);
+        for (data, version) in [
+            (r#"{"Movies":["Alien"]}"#, 1),
+            (r#"{"Movies":[{"title":"Alien"}]}"#, 2),
+            (r#"{"version":3,"lists":{"Movies":["Alien"]}}"#, 3),
+            (r#"{"version":4,"lists":{"Movies":["Alien"]}}"#, 4),
+        ] {
+            assert_eq!(
+                parse(data.as_bytes()).unwrap(),
+                (watchlist.clone(), version)
+            );
+        }
+        let newer = parse(br#"{"version":5,"lists":{}}"#).unwrap_err();
+        assert_eq!(newer.kind(), io::ErrorKind::InvalidData);
+        assert!(parse(br#"{"version":3,"lists":{"Movies":[1]}}"#).is_err());
+
+        let mut data = Vec::new();
+        write(&watchlist, 1, true, &mut data).unwrap();
+        assert_eq!(data, br#"{"Movies":["Alien"]}"#);
+        let now = "2023-03-01T12:00:00Z".parse().unwrap();
+        let dated: WatchList = [(
+            "Movies".to_string(),
+            vec![Item::new("Alien").with_added_at(now)],
+        )]
+        .into_iter()
+        .collect();
+        data.clear();
+        write(&dated, 1, true, &mut data).unwrap();
+        assert_eq!(data, br#"{"Movies":["Alien"]}"#);
+        data.clear();
+        write(&dated, 2, true, &mut data).unwrap();
+        assert_eq!(parse(&data).unwrap(), (dated, 2));
+        watchlist.item_set_rating("Movies", "Alien", 8).unwrap();
+        data.clear();
+        write(&watchlist, 2, true, &mut data).unwrap();
+        assert_eq!(parse(&data).unwrap(), (watchlist.clone(), 2));
+        data.clear();
+        write(&watchlist, CURRENT, true, &mut data).unwrap();
+        assert_eq!(
+            String::from_utf8(data.clone()).unwrap(),
+            r#"{"version":4,"lists":{"Movies":[{"title":"Alien","rating":8}]}}"#
+        );
+        assert_eq!(parse(&data).unwrap(), (watchlist.clone(), CURRENT));
+    }
+
+    #[test]
+    fn list_settings() {
+        let mut watchlist = WatchList::new();
+        watchlist.insert("Movies".to_string(), vec![Item::new("Alien")]);
+        watchlist.insert("Finished".to_string(), vec![Item::new("Heat")]);
+        watchlist.list_settings_mut("Finished").unwrap().no_random = true;
+        let mut data = Vec::new();
+        write(&watchlist, CURRENT, true, &mut data).unwrap();
+        assert_eq!(
+            String::from_utf8(data.clone()).unwrap(),
+            r#"{"version":4,"lists":{"Finished":["Heat"],"Movies":["Alien"]},"settings":{"Finished":{"no_random":true}}}"#
+        );
+        let (read, _) = parse(&data).unwrap();
+        assert_eq!(read, watchlist);
+        assert!(read.excluded_from_random("Finished"));
+
+        // Settings of lists which aren't in the file are dropped
+        let (read, _) = parse(
+            br#"{"version":4,"lists":{"Movies":[]},"settings":{"Anime":{"no_random":true}}}"#,
+        )
+        .unwrap();
+        assert_eq!(read.settings().count(), 0);
+    }
+
+    #[test]
+    fn pretty_printing() {
+        let mut watchlist = WatchList::new();
+        for title in ["Shows", "Books", "Movies"] {
+            watchlist.insert(title.to_string(), vec![Item::new("Dune")]);
+        }
+        let mut data = Vec::new();
+        write(&watchlist, 1, false, &mut data).unwrap();
+        assert_eq!(
+            String::from_utf8(data.clone()).unwrap(),
+            "{\n  \"Books\": [\n    \"Dune\"\n  ],\n  \"Movies\": [\n    \"Dune\"\n  ],\n  \"Shows\": [\n    \"Dune\"\n  ]\n}\n"
+        );
+        assert_eq!(parse(&data).unwrap(), (watchlist.clone(), 1));
+
+        // Lists are written in the same order whichever order they're kept in
+        for titles in [["Movies", "Shows", "Books"], ["Books", "Shows", "Movies"]] {
+            let mut again = WatchList::new();
+            for title in titles {
+                again.insert(title.to_string(), vec![Item::new("Dune")]);
+            }
+            let mut written = Vec::new();
+            write(&again, 1, false, &mut written).unwrap();
+            assert_eq!(written, data);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn backups() {
+        let path = std::env::temp_dir().join(format!("wlist_schema_{}.json", std::process::id()));
+        fs::write(&path, r#"{"Movies":["Alien"]}"#).unwrap();
+        let backup = backup(&path, 1).unwrap();
+        assert!(backup.ends_with(format!("wlist_schema_{}.v1.json", std::process::id())));
+        assert_eq!(
+            fs::read_to_string(&backup).unwrap(),
+            r#"{"Movies":["Alien"]}"#
+        );
+        fs::remove_file(path).unwrap();
+        fs::remove_file(backup).unwrap();
+    }
+}
@@ -0,0 +1,48 @@
+//! Picking several of the numbered items shown at once, like `1,3-5`
+
+/// Reads numbers and ranges of numbers separated by commas, giving the indices of the
+/// items picked counting from 0, in the order they were given and each only once
+///
+/// Numbers count from 1 up to the number of items, and none if any of them is out of
+/// that range, a range runs backwards or something isn't a number
+pub fn parse(input: &str, count: usize) -> Option<Vec<usize>> {
+    let number = |n: &str| {
+        n.trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|n| (1..=count).contains(n))
+    };
+    let mut picked = Vec::new();
+    for part in input.split(',') {
+        let (start, end) = match part.split_once('-') {
+            Some((start, end)) => (number(start)?, number(end)?),
+            None => (number(part)?, number(part)?),
+        };
+        if start > end {
+            return None;
+        }
+        for n in start..=end {
+            if !picked.contains(&(n - 1)) {
+                picked.push(n - 1);
+            }
+        }
+    }
+    Some(picked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selections() {
+        assert_eq!(parse("2", 5), Some(vec![1]));
+        assert_eq!(parse("1,3-5", 5), Some(vec![0, 2, 3, 4]));
+        assert_eq!(parse(" 4 , 1 - 2,2", 5), Some(vec![3, 0, 1]));
+        assert_eq!(parse("3-3", 5), Some(vec![2]));
+
+        for invalid in ["", "0", "6", "1,", "4-2", "1-9", "a", "1-2-3"] {
+            assert_eq!(parse(invalid, 5), None, "{invalid}");
+        }
+    }
+}
@@ -0,0 +1,1235 @@
+//! Read-only web page and JSON for sharing a single list, and a JSON API for reading and
+//! changing every list
+//!
+//! The API has these endpoints, which reply with JSON and take JSON in the body:
+//!
+//! | Request | Reply |
+//! | ------- | ----- |
+//! | `GET /lists` | Titles of the lists with their numbers of items |
+//! | `POST /lists` with `{"title": "Movies"}` | Creates the list |
+//! | `GET /lists/{title}` | Items of the list, with how many there are in `total` |
+//! | `DELETE /lists/{title}` | Deletes the list |
+//! | `POST /lists/{title}/items` with an item, like `"Alien"` or `{"title": "Alien", "year": 1979}` | Adds the item |
+//! | `DELETE /lists/{title}/items/{item}` | Deletes the item |
+//! | `GET /lists/{title}/random` | Random item of the list |
+//! | `GET /random` | Random item of a random list |
+//! | `GET /search?q={text}` | Items of every list containing the text |
+//! | `GET /stats` | Counts of the items in every list, like `wl stats --format json` |
+//! | `GET /openapi.json` | [OpenAPI](https://www.openapis.org) document describing the API, made from [`ROUTES`] |
+//!
+//! `GET /lists?include=items` gives the items of every list in `contents` along with their
+//! titles, so a page can get everything it shows in one request. With the `graphql`
+//! feature, `/graphql` takes [GraphQL](https://graphql.org) for picking just the fields it
+//! needs at any depth, see [`graphql`](crate::graphql)
+//!
+//! `GET /lists` and `GET /lists/{title}` take `offset` and `limit` to get a page at a time.
+//! The items of a list can also be picked with a filter in `filter`, written like the ones
+//! of smart lists, and `fields` picks the fields each item is given with, like
+//! `/lists/Movies?filter=status%3Dunwatched&fields=title,year&limit=20`
+//!
+//! Errors are replied to with the status code for what went wrong, like `404 Not Found` for
+//! a list which doesn't exist, and `{"error": "..."}` describing it
+//!
+//! When the API is served with a token, every request to it needs the token in an
+//! `Authorization: Bearer {token}` header, or it's refused with `401 Unauthorized`
+//!
+//! Requests which change the lists need `Content-Type: application/json`, which a page on
+//! another site can't send without the browser asking first, so it can't make changes
+//! through a browser which has the page open. Each change is made like the `wl` command
+//! it does, given by [`command`], so it can be undone and shows up in the history
+//!
+//! Along with the API, `/` is a web page built into the program which uses it to show the
+//! lists, add and delete items, and pick a random item
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::fields;
+use crate::query::Query;
+use crate::{DuplicatePolicy, Item, WatchList, WatchListError};
+
+/// Environment variable the token of the API is read from when it isn't given
+pub const TOKEN_VAR: &str = "WATCHLIST_SERVE_TOKEN";
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Bodies longer than this many bytes are refused
+const MAX_BODY: usize = 64 * 1024;
+
+/// Web page served along with the API
+const PAGE: &str = include_str!("serve.html");
+
+/// Reply to a request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: &'static str,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    fn text(status: &'static str, body: &str) -> Self {
+        Response {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: body.to_string(),
+        }
+    }
+
+    pub(crate) fn json(status: &'static str, body: Value) -> Self {
+        Response {
+            status,
+            content_type: "application/json",
+            body: body.to_string(),
+        }
+    }
+
+    pub fn error(error: &WatchListError) -> Self {
+        Response::json(status(error), json!({ "error": error.to_string() }))
+    }
+}
+
+/// Status code of the reply to a request which failed with the error
+pub fn status(error: &WatchListError) -> &'static str {
+    use WatchListError::*;
+    match error {
+        NoTitles
+        | EmptyList(_)
+        | TitleNotPresent(..)
+        | ItemToRemoveNotPresent(_)
+        | FolderNotPresent(_)
+        | ProfileNotPresent(_)
+        | WorkspaceNotPresent(_)
+        | NoUrl(_)
+        | NoMatches(_) => "404 Not Found",
+        TitleAlreadyPresent(_)
+        | ItemAlreadyPresent(..)
+        | ProfileAlreadyPresent(_)
+        | RemoteChanged(_) => "409 Conflict",
+        ParseError(_) | InvalidDate(_) | InvalidRating(_) | InvalidProgress(_)
+        | InvalidRuntime(_) | InvalidProfile(_) | InvalidQuery(_) | InvalidPattern(_)
+        | InvalidUrl(_) | UnknownField(_) => "400 Bad Request",
+        ValidationFailed(..) => "422 Unprocessable Entity",
+        LimitExceeded(_) => "413 Content Too Large",
+        NetworkError(_) => "502 Bad Gateway",
+        UpgradeRequired(_) | EncryptionError(_) | GitError(_) | NotInteractive(_) | IOError(_) => {
+            "500 Internal Server Error"
+        }
+    }
+}
+
+/// Escapes text for use in HTML
+pub fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Renders a list as a standalone web page
+pub fn render_html(title: &str, items: &[Item]) -> String {
+    let title = html_escape(title);
+    let mut rows = String::new();
+    for item in items {
+        let name = html_escape(&item.title);
+        let name = match item.meta.url.as_deref().filter(|u| u.starts_with("http")) {
+            Some(url) => format!(r#"<a href="{}">{name}</a>"#, html_escape(url)),
+            None => name,
+        };
+        let year = item
+            .meta
+            .year
+            .map(|y| format!(r#" <span class="year">{y}</span>"#))
+            .unwrap_or_default();
+        let class = if item.meta.watched {
+            r#" class="watched""#
+        } else {
+            ""
+        };
+        rows.push_str(&format!("<li{class}>{name}{year}</li>\n"));
+    }
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{title}</title>
+<style>
+body {{ font-family: system-ui, sans-serif; max-width: 40rem; margin: 2rem auto; padding: 0 1rem; color: #222; }}
+h1 {{ font-weight: 600; }}
+li {{ padding: 0.3rem 0; }}
+a {{ color: #2a5db0; text-decoration: none; }}
+.year {{ color: #888; }}
+.watched {{ text-decoration: line-through; color: #888; }}
+footer {{ margin-top: 2rem; color: #888; font-size: 0.9rem; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+<ol>
+{rows}</ol>
+<footer>{count} item(s) &middot; <a href="list.json">JSON</a></footer>
+</body>
+</html>
+"#,
+        count = items.len()
+    )
+}
+
+/// Works out the reply to a request for the shared list
+///
+/// Only GET and HEAD requests are allowed so nothing can be changed
+pub fn respond(method: &str, path: &str, title: &str, items: &[Item]) -> Response {
+    if method != "GET" && method != "HEAD" {
+        return Response::text("405 Method Not Allowed", "Only reading is allowed");
+    }
+    // Query strings are ignored
+    let path = path.split('?').next().unwrap_or_default();
+    match path {
+        "/" | "/index.html" => Response {
+            status: "200 OK",
+            content_type: "text/html; charset=utf-8",
+            body: render_html(title, items),
+        },
+        "/list.json" => Response {
+            status: "200 OK",
+            content_type: "application/json",
+            body: json!({ "list": title, "items": items }).to_string(),
+        },
+        _ => Response::text("404 Not Found", "Not found"),
+    }
+}
+
+/// Web page for browsing and changing every list through the API, which leaves out adding
+/// and deleting items when the lists are `read_only`
+pub fn page(read_only: bool) -> Response {
+    Response {
+        status: "200 OK",
+        content_type: "text/html; charset=utf-8",
+        body: PAGE.replace("/*READ_ONLY*/false", &read_only.to_string()),
+    }
+}
+
+/// Whether the request is for the web page rather than the API
+pub fn is_page(method: &str, path: &str) -> bool {
+    let path = path.split('?').next().unwrap_or_default();
+    !changes(method) && matches!(path, "/" | "/index.html")
+}
+
+/// Whether the request can change the lists
+pub fn changes(method: &str) -> bool {
+    method != "GET" && method != "HEAD"
+}
+
+/// An endpoint of the API, which requests to it are handled by and the OpenAPI document
+/// is made from
+pub struct Route {
+    pub method: &'static str,
+    /// Path with the parts which change in braces, like `/lists/{title}`
+    pub path: &'static str,
+    pub summary: &'static str,
+    /// Names of the query parameters it takes, with what they're for
+    pub query: &'static [(&'static str, &'static str)],
+    /// What it takes in the body, if it takes one
+    pub body: Option<&'static str>,
+    /// Status code it replies with when it succeeds
+    pub status: &'static str,
+    /// Works out the reply to a request to it
+    pub handler: fn(&Call, &mut WatchList) -> Result<Response, WatchListError>,
+}
+
+impl Route {
+    // Parts of the path of a request in braces in this route's path, in order, if the path
+    // is this route's whatever its method
+    fn params<'a>(&self, segments: &[&'a str]) -> Option<Vec<&'a str>> {
+        let parts: Vec<&str> = self.path.split('/').filter(|s| !s.is_empty()).collect();
+        if parts.len() != segments.len() {
+            return None;
+        }
+        let mut params = Vec::new();
+        for (part, segment) in parts.iter().zip(segments) {
+            match part.starts_with('{') {
+                true => params.push(*segment),
+                false if part == segment => (),
+                false => return None,
+            }
+        }
+        Some(params)
+    }
+}
+
+/// A request to the API, as it's given to the handler of its route
+pub struct Call<'a> {
+    /// Parts of the path in braces in the route's path, in order, like the title in
+    /// `/lists/{title}`
+    pub params: Vec<&'a str>,
+    pub query: &'a str,
+    pub body: &'a str,
+}
+
+const PAGE_QUERY: [(&str, &str); 2] = [
+    ("offset", "Number of entries to skip"),
+    ("limit", "Most entries to reply with"),
+];
+
+/// Every endpoint of the API, which requests are handled by and which is also served as an
+/// OpenAPI document at `/openapi.json`, so the two can't differ
+pub const ROUTES: &[Route] = &[
+    Route {
+        method: "GET",
+        path: "/lists",
+        summary: "Titles of the lists with their numbers of items",
+        query: &[
+            PAGE_QUERY[0],
+            PAGE_QUERY[1],
+            (
+                "include",
+                "`items` to give the items of each list in `contents` too",
+            ),
+            ("fields", "Fields to give each item with, like title,year"),
+        ],
+        body: None,
+        status: "200",
+        handler: lists,
+    },
+    Route {
+        method: "POST",
+        path: "/lists",
+        summary: "Creates a list",
+        query: &[],
+        body: Some(r#"The title of the list, like {"title": "Movies"}"#),
+        status: "201",
+        handler: create_list,
+    },
+    Route {
+        method: "GET",
+        path: "/lists/{title}",
+        summary: "Items of the list, with how many there are in `total`",
+        query: &[
+            PAGE_QUERY[0],
+            PAGE_QUERY[1],
+            (
+                "filter",
+                "Filter the items have to match, like status=unwatched",
+            ),
+            ("fields", "Fields to give each item with, like title,year"),
+        ],
+        body: None,
+        status: "200",
+        handler: list_items,
+    },
+    Route {
+        method: "DELETE",
+        path: "/lists/{title}",
+        summary: "Deletes the list",
+        query: &[],
+        body: None,
+        status: "204",
+        handler: delete_list,
+    },
+    Route {
+        method: "POST",
+        path: "/lists/{title}/items",
+        summary: "Adds an item to the list",
+        query: &[],
+        body: Some(r#"The item, like "Alien" or {"title": "Alien", "year": 1979}"#),
+        status: "201",
+        handler: add_item,
+    },
+    Route {
+        method: "DELETE",
+        path: "/lists/{title}/items/{item}",
+        summary: "Deletes the item from the list",
+        query: &[],
+        body: None,
+        status: "204",
+        handler: delete_item,
+    },
+    Route {
+        method: "GET",
+        path: "/lists/{title}/random",
+        summary: "Random item of the list",
+        query: &[],
+        body: None,
+        status: "200",
+        handler: random_item,
+    },
+    Route {
+        method: "GET",
+        path: "/random",
+        summary: "Random item of a random list",
+        query: &[],
+        body: None,
+        status: "200",
+        handler: random,
+    },
+    Route {
+        method: "GET",
+        path: "/search",
+        summary: "Items of every list containing the text",
+        query: &[("q", "Text to search for")],
+        body: None,
+        status: "200",
+        handler: search,
+    },
+    Route {
+        method: "GET",
+        path: "/stats",
+        summary: "Counts of the items in every list, like `wl stats --format json`",
+        query: &[],
+        body: None,
+        status: "200",
+        handler: stats,
+    },
+    Route {
+        method: "GET",
+        path: "/openapi.json",
+        summary: "OpenAPI document describing the API",
+        query: &[],
+        body: None,
+        status: "200",
+        handler: |_, _| Ok(Response::json("200 OK", openapi())),
+    },
+    #[cfg(feature = "graphql")]
+    Route {
+        method: "GET",
+        path: "/graphql",
+        summary: "Runs a GraphQL query, which can't change the lists",
+        query: &[
+            ("query", "The query, like { lists { title } }"),
+            ("variables", "Values of its variables, as a JSON object"),
+            ("operationName", "Operation of the query to run"),
+        ],
+        body: None,
+        status: "200",
+        handler: crate::graphql::get,
+    },
+    #[cfg(feature = "graphql")]
+    Route {
+        method: "POST",
+        path: "/graphql",
+        summary: "Runs a GraphQL query or mutation",
+        query: &[],
+        body: Some(r#"The query, like {"query": "{ lists { title } }"}"#),
+        status: "200",
+        handler: crate::graphql::post,
+    },
+];
+
+/// OpenAPI document describing the API, made from [`ROUTES`] so it can't miss an endpoint
+pub fn openapi() -> Value {
+    let mut paths = serde_json::Map::new();
+    for route in ROUTES {
+        let mut parameters: Vec<Value> = route
+            .path
+            .split('/')
+            .filter_map(|part| part.strip_prefix('{')?.strip_suffix('}'))
+            .map(|name| {
+                let schema = json!({ "type": "string" });
+                json!({ "name": name, "in": "path", "required": true, "schema": schema })
+            })
+            .collect();
+        parameters.extend(route.query.iter().map(|(name, description)| {
+            let kind = match *name {
+                "offset" | "limit" => "integer",
+                _ => "string",
+            };
+            let schema = json!({ "type": kind });
+            json!({ "name": name, "in": "query", "description": description, "schema": schema })
+        }));
+        let error = json!({ "$ref": "#/components/schemas/Error" });
+        let mut operation = json!({
+            "summary": route.summary,
+            "parameters": parameters,
+            "responses": {
+                route.status: { "description": route.summary },
+                "default": {
+                    "description": "What went wrong",
+                    "content": { "application/json": { "schema": error } },
+                },
+            },
+        });
+        if let Some(body) = route.body {
+            let schema = match route.path {
+                "/lists" => json!({
+                    "type": "object",
+                    "required": ["title"],
+                    "properties": { "title": { "type": "string" } },
+                }),
+                "/graphql" => json!({
+                    "type": "object",
+                    "required": ["query"],
+                    "properties": {
+                        "query": { "type": "string" },
+                        "variables": { "type": "object" },
+                        "operationName": { "type": "string" },
+                    },
+                }),
+                _ => json!({ "$ref": "#/components/schemas/Item" }),
+            };
+            operation["requestBody"] = json!({
+                "description": body,
+                "required": true,
+                "content": { "application/json": { "schema": schema } },
+            });
+        }
+        let methods = paths.entry(route.path).or_insert_with(|| json!({}));
+        methods[route.method.to_lowercase()] = operation;
+    }
+    json!({
+        "openapi": "3.0.3",
+        "info": { "title": "wl", "version": env!("CARGO_PKG_VERSION") },
+        "paths": paths,
+        // The token is only needed when the API is served with one
+        "security": [{}, { "bearer": [] }],
+        "components": {
+            "securitySchemes": { "bearer": { "type": "http", "scheme": "bearer" } },
+            "schemas": {
+                "Item": {
+                    "oneOf": [
+                        { "type": "string" },
+                        {
+                            "type": "object",
+                            "required": ["title"],
+                            "properties": { "title": { "type": "string" } },
+                            "additionalProperties": true,
+                        },
+                    ],
+                },
+                "Error": {
+                    "type": "object",
+                    "properties": { "error": { "type": "string" } },
+                },
+            },
+        },
+    })
+}
+
+/// Whether a request which changes the lists says its body is JSON, which is needed so
+/// other sites can't make changes without the browser asking first
+pub fn allows_change(content_type: Option<&str>) -> bool {
+    content_type
+        .and_then(|c| c.split(';').next())
+        .is_some_and(|c| c.trim().eq_ignore_ascii_case("application/json"))
+}
+
+/// Arguments of the `wl` command which makes the same change as a request to the API, to
+/// record it with. Gives none for requests which don't change the lists
+pub fn command(method: &str, path: &str, body: &str) -> Option<Vec<String>> {
+    let path = path.split('?').next().unwrap_or_default();
+    let segments: Vec<String> = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(percent_decode)
+        .collect();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    let args: Vec<&str> = match (method, segments.as_slice()) {
+        ("POST", ["lists"]) => {
+            #[derive(Deserialize)]
+            struct NewList {
+                title: String,
+            }
+            let new = serde_json::from_str::<NewList>(body).ok()?;
+            return Some(vec!["new".to_string(), new.title]);
+        }
+        ("DELETE", ["lists", title]) => vec!["delete", title],
+        ("POST", ["lists", title, "items"]) => {
+            let item = serde_json::from_str::<Item>(body).ok()?;
+            return Some(vec!["add".to_string(), title.to_string(), item.title]);
+        }
+        ("DELETE", ["lists", title, "items", item]) => vec!["delete", title, item],
+        #[cfg(feature = "graphql")]
+        ("POST", ["graphql"]) => return crate::graphql::command(body),
+        _ => return None,
+    };
+    Some(args.into_iter().map(String::from).collect())
+}
+
+/// Works out the reply to a request to the API, changing the lists for requests which
+/// aren't GET or HEAD
+pub fn respond_api(method: &str, path: &str, body: &str, watchlist: &mut WatchList) -> Response {
+    let (path, query) = path.split_once('?').unwrap_or((path, ""));
+    let segments: Vec<String> = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(percent_decode)
+        .collect();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    let method = match method {
+        "HEAD" => "GET",
+        method => method,
+    };
+    let mut allowed = false;
+    for route in ROUTES {
+        let Some(params) = route.params(&segments) else {
+            continue;
+        };
+        if route.method != method {
+            allowed = true;
+            continue;
+        }
+        let call = Call {
+            params,
+            query,
+            body,
+        };
+        return (route.handler)(&call, watchlist)
+            .unwrap_or_else(|e| Response::error(&e.with_suggestions(watchlist)));
+    }
+    match allowed {
+        true => Response::json(
+            "405 Method Not Allowed",
+            json!({ "error": format!("{method} isn't allowed here") }),
+        ),
+        false => Response::json("404 Not Found", json!({ "error": "Not found" })),
+    }
+}
+
+// Titles of the lists with their numbers of items, and their items too with
+// `include=items`, so a page can get everything it shows in one request
+fn lists(call: &Call, watchlist: &mut WatchList) -> Result<Response, WatchListError> {
+    let query = call.query;
+    let (offset, limit) = page_of(query)?;
+    let fields = fields_of(query)?;
+    let include_items = query_param(query, "include").is_some_and(|i| i == "items");
+    let mut titles: Vec<&String> = watchlist.titles().collect();
+    titles.sort();
+    let lists: Vec<Value> = titles
+        .into_iter()
+        .skip(offset)
+        .take(limit)
+        .map(|title| {
+            let items = &watchlist[title];
+            let mut list = json!({ "title": title, "items": items.len() });
+            if include_items {
+                list["contents"] = items.iter().map(|i| item_json(i, &fields)).collect();
+            }
+            list
+        })
+        .collect();
+    Ok(Response::json("200 OK", json!(lists)))
+}
+
+// Fields picked with `fields` in the query, none to give every field
+fn fields_of(query: &str) -> Result<Option<Vec<&'static fields::Field>>, WatchListError> {
+    query_param(query, "fields")
+        .map(|names| fields::lookup(&names.split(',').collect::<Vec<_>>()))
+        .transpose()
+}
+
+fn item_json(item: &Item, fields: &Option<Vec<&fields::Field>>) -> Value {
+    match fields {
+        Some(fields) => fields::json_object(item, fields),
+        None => json!(item),
+    }
+}
+
+// Items of the list picked by the `filter`, `fields`, `offset` and `limit` of the query
+fn list_items(call: &Call, watchlist: &mut WatchList) -> Result<Response, WatchListError> {
+    let (title, query) = (call.params[0], call.query);
+    let items = watchlist
+        .get(title)
+        .ok_or_else(|| WatchListError::TitleNotPresent(title.to_string(), Vec::new()))?;
+    let filter = query_param(query, "filter")
+        .map(|filter| filter.parse::<Query>())
+        .transpose()?;
+    let fields = fields_of(query)?;
+    let (offset, limit) = page_of(query)?;
+
+    let picked: Vec<&Item> = items
+        .iter()
+        .filter(|item| filter.as_ref().is_none_or(|f| f.matches(title, item)))
+        .collect();
+    let page: Vec<Value> = picked
+        .iter()
+        .skip(offset)
+        .take(limit)
+        .map(|item| item_json(item, &fields))
+        .collect();
+    Ok(Response::json(
+        "200 OK",
+        json!({ "list": title, "items": page, "total": picked.len() }),
+    ))
+}
+
+fn create_list(call: &Call, watchlist: &mut WatchList) -> Result<Response, WatchListError> {
+    #[derive(Deserialize)]
+    struct NewList {
+        title: String,
+    }
+    match parse::<NewList>(call.body) {
+        Ok(new) => watchlist
+            .list_add(&new.title)
+            .map(|()| Response::json("201 Created", json!({ "title": new.title }))),
+        Err(response) => Ok(response),
+    }
+}
+
+fn delete_list(call: &Call, watchlist: &mut WatchList) -> Result<Response, WatchListError> {
+    watchlist
+        .list_remove(call.params[0])
+        .map(|()| Response::text("204 No Content", ""))
+}
+
+fn add_item(call: &Call, watchlist: &mut WatchList) -> Result<Response, WatchListError> {
+    match parse::<Item>(call.body) {
+        Ok(item) => watchlist
+            .item_add(call.params[0], item.clone(), DuplicatePolicy::Exact)
+            .map(|()| Response::json("201 Created", json!(item))),
+        Err(response) => Ok(response),
+    }
+}
+
+fn delete_item(call: &Call, watchlist: &mut WatchList) -> Result<Response, WatchListError> {
+    watchlist
+        .item_remove(call.params[0], call.params[1])
+        .map(|()| Response::text("204 No Content", ""))
+}
+
+fn random_item(call: &Call, watchlist: &mut WatchList) -> Result<Response, WatchListError> {
+    let title = call.params[0];
+    watchlist
+        .item_get_random(title)
+        .map(|item| Response::json("200 OK", json!({ "list": title, "item": item })))
+}
+
+fn random(_: &Call, watchlist: &mut WatchList) -> Result<Response, WatchListError> {
+    let title = watchlist.list_get_random_weighted()?;
+    let item = watchlist.item_get_random(title)?;
+    Ok(Response::json(
+        "200 OK",
+        json!({ "list": title, "item": item }),
+    ))
+}
+
+fn search(call: &Call, watchlist: &mut WatchList) -> Result<Response, WatchListError> {
+    let Some(search) = query_param(call.query, "q") else {
+        return Ok(Response::json(
+            "400 Bad Request",
+            json!({ "error": "Searching needs the text to search for in `q`" }),
+        ));
+    };
+    let results: Vec<Value> = watchlist
+        .search_all(&search)
+        .into_iter()
+        .map(|(list, items)| json!({ "list": list, "items": items }))
+        .collect();
+    Ok(Response::json("200 OK", json!(results)))
+}
+
+fn stats(_: &Call, watchlist: &mut WatchList) -> Result<Response, WatchListError> {
+    Ok(Response::json("200 OK", json!(watchlist.stats())))
+}
+
+// Number of entries to skip and the most to reply with, from `offset` and `limit`
+fn page_of(query: &str) -> Result<(usize, usize), WatchListError> {
+    let number = |name: &str| {
+        query_param(query, name)
+            .map(|n| {
+                n.parse::<usize>().map_err(|_| {
+                    WatchListError::ParseError(format!("`{name}` has to be a number, not {n}"))
+                })
+            })
+            .transpose()
+    };
+    Ok((
+        number("offset")?.unwrap_or(0),
+        number("limit")?.unwrap_or(usize::MAX),
+    ))
+}
+
+/// Whether the request may use the API, which needs the token in its `Authorization`
+/// header when the API is served with one
+pub fn authorized(token: Option<&str>, authorization: Option<&str>) -> bool {
+    let Some(token) = token else {
+        return true;
+    };
+    let given = authorization
+        .and_then(|a| a.trim().strip_prefix("Bearer "))
+        .unwrap_or_default()
+        .trim();
+    // Compared in full every time, so how long the check takes doesn't give the token away
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |differ, (a, b)| differ | (a ^ b))
+            == 0
+}
+
+pub(crate) fn parse<'a, T: Deserialize<'a>>(body: &'a str) -> Result<T, Response> {
+    serde_json::from_str(body).map_err(|e| {
+        Response::json(
+            "400 Bad Request",
+            json!({ "error": format!("Couldn't read the body, {e}") }),
+        )
+    })
+}
+
+pub(crate) fn query_param(query: &str, name: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| percent_decode(&value.replace('+', " ")))
+}
+
+/// Decodes `%XX` escapes in part of a URL, leaving ones which aren't valid as they are
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// Method, path, headers and body of a request
+struct Request {
+    method: String,
+    path: String,
+    content_type: Option<String>,
+    authorization: Option<String>,
+    body: Result<String, Response>,
+}
+
+fn read_request(stream: &TcpStream) -> io::Result<Request> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Only the length and type of the body and the token are needed, but every header is
+    // read so the client doesn't see a reset connection
+    let (mut length, mut content_type, mut authorization) = (0, None, None);
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().unwrap_or(0);
+            } else if name.eq_ignore_ascii_case("content-type") {
+                content_type = Some(value.trim().to_string());
+            } else if name.eq_ignore_ascii_case("authorization") {
+                authorization = Some(value.trim().to_string());
+            }
+        }
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (
+        parts.next().unwrap_or_default().to_string(),
+        parts.next().unwrap_or("/").to_string(),
+    );
+    let body = match length {
+        0 => Ok(String::new()),
+        length if length > MAX_BODY => Err(Response::error(&WatchListError::LimitExceeded(
+            format!("the body is longer than {MAX_BODY} bytes"),
+        ))),
+        length => {
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body)?;
+            Ok(String::from_utf8_lossy(&body).into_owned())
+        }
+    };
+    Ok(Request {
+        method,
+        path,
+        content_type,
+        authorization,
+        body,
+    })
+}
+
+fn handle(stream: TcpStream, file_path: &Path, title: &str) -> io::Result<()> {
+    let request = read_request(&stream)?;
+    // The file is read for every request so that changes show up without a restart
+    let response = match WatchList::from_file(file_path) {
+        Ok(watchlist) => match watchlist.get(title) {
+            Some(items) => respond(&request.method, &request.path, title, items),
+            None => Response::text("404 Not Found", "The list no longer exists"),
+        },
+        Err(_) => Response::text("500 Internal Server Error", "Couldn't read the list"),
+    };
+    write_response(&stream, &request.method, &response)
+}
+
+fn handle_api(
+    stream: TcpStream,
+    file_path: &Path,
+    read_only: bool,
+    token: Option<&str>,
+    change: &mut impl FnMut(&str, &str, &str) -> Result<Response, WatchListError>,
+) -> io::Result<()> {
+    let request = read_request(&stream)?;
+    let response = match (&request.body, changes(&request.method)) {
+        // The page holds no lists, so it's served to anyone and given the token in its URL
+        _ if is_page(&request.method, &request.path) => page(read_only),
+        _ if !authorized(token, request.authorization.as_deref()) => Response::json(
+            "401 Unauthorized",
+            json!({ "error": "The API needs the token it's served with" }),
+        ),
+        (Err(response), _) => response.clone(),
+        (Ok(_), true) if read_only => Response::json(
+            "405 Method Not Allowed",
+            json!({ "error": "The lists are read-only" }),
+        ),
+        (Ok(_), true) if !allows_change(request.content_type.as_deref()) => Response::json(
+            "415 Unsupported Media Type",
+            json!({ "error": "Changes need `Content-Type: application/json`" }),
+        ),
+        (Ok(body), true) => {
+            change(&request.method, &request.path, body).unwrap_or_else(|e| Response::error(&e))
+        }
+        (Ok(body), false) => match WatchList::from_file(file_path) {
+            Ok(mut watchlist) => respond_api(&request.method, &request.path, body, &mut watchlist),
+            Err(e) => Response::error(&e),
+        },
+    };
+    write_response(&stream, &request.method, &response)
+}
+
+fn write_response(mut stream: &TcpStream, method: &str, response: &Response) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.content_type,
+        response.body.len()
+    )?;
+    if method != "HEAD" {
+        stream.write_all(response.body.as_bytes())?;
+    }
+    stream.flush()
+}
+
+/// Serves a read-only page and JSON for the list `title` in the file at `file_path`
+///
+/// Requests are handled one at a time until the process is stopped
+pub fn serve(address: &str, file_path: &Path, title: &str) -> Result<(), WatchListError> {
+    let watchlist = WatchList::from_file(file_path)?;
+    if !watchlist.contains(title) {
+        return Err(WatchListError::TitleNotPresent(
+            title.to_string(),
+            Vec::new(),
+        ));
+    }
+
+    let listener = TcpListener::bind(address)?;
+    for stream in listener.incoming() {
+        // A failed connection shouldn't stop the server
+        if let Err(e) = stream.and_then(|s| handle(s, file_path, title)) {
+            eprintln!("Request failed: {e}");
+        }
+    }
+    Ok(())
+}
+
+/// Serves the JSON API for every list in the file at `file_path`, refusing changes when
+/// `read_only` is set and requests without the `token` when there is one
+///
+/// Requests which change the lists are given to `change` with their method, path and body,
+/// which makes the change with [`respond_api`] and saves it. Requests are handled one at a
+/// time until the process is stopped
+pub fn serve_api(
+    address: &str,
+    file_path: &Path,
+    read_only: bool,
+    token: Option<&str>,
+    mut change: impl FnMut(&str, &str, &str) -> Result<Response, WatchListError>,
+) -> Result<(), WatchListError> {
+    let listener = TcpListener::bind(address)?;
+    for stream in listener.incoming() {
+        // A failed connection shouldn't stop the server
+        let handled = stream.and_then(|s| handle_api(s, file_path, read_only, token, &mut change));
+        if let Err(e) = handled {
+            eprintln!("Request failed: {e}");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages() {
+        let items = vec![
+            Item::new("Alien <1979>").with_url("https://example.com/alien"),
+            Item::new("Dune").with_year(2021).watched(true),
+            Item::new("Bad").with_url("javascript:alert(1)"),
+        ];
+
+        let page = respond("GET", "/", "Movies & TV", &items);
+        assert_eq!(page.status, "200 OK");
+        assert!(page.body.contains("<h1>Movies &amp; TV</h1>"));
+        assert!(page
+            .body
+            .contains(r#"<li><a href="https://example.com/alien">Alien &lt;1979&gt;</a></li>"#));
+        assert!(page
+            .body
+            .contains(r#"<li class="watched">Dune <span class="year">2021</span></li>"#));
+        assert!(page.body.contains("<li>Bad</li>"));
+
+        let json = respond("GET", "/list.json?x=1", "Movies", &items[1..2]);
+        assert_eq!(json.content_type, "application/json");
+        assert_eq!(
+            json.body,
+            r#"{"items":[{"title":"Dune","watched":true,"year":2021}],"list":"Movies"}"#
+        );
+    }
+
+    #[test]
+    fn api() {
+        let mut watchlist = WatchList::new();
+        watchlist.insert("Movies", vec![Item::new("Alien")]);
+        let mut api = |method, path, body| respond_api(method, path, body, &mut watchlist);
+
+        let created = api("POST", "/lists", r#"{"title": "Anime TV"}"#);
+        assert_eq!(created.status, "201 Created");
+        let added = api(
+            "POST",
+            "/lists/Anime%20TV/items",
+            r#"{"title": "Akira", "year": 1988}"#,
+        );
+        assert_eq!(added.status, "201 Created");
+        assert_eq!(
+            api("GET", "/lists", "").body,
+            r#"[{"items":1,"title":"Anime TV"},{"items":1,"title":"Movies"}]"#
+        );
+        assert_eq!(
+            api("GET", "/lists/Anime%20TV", "").body,
+            r#"{"items":[{"title":"Akira","year":1988}],"list":"Anime TV","total":1}"#
+        );
+        assert_eq!(
+            api("GET", "/search?q=ali", "").body,
+            r#"[{"items":["Alien"],"list":"Movies"}]"#
+        );
+        assert_eq!(
+            api("DELETE", "/lists/Movies/items/Alien", "").status,
+            "204 No Content"
+        );
+        assert_eq!(
+            api("GET", "/lists/Movies/random", "").status,
+            "404 Not Found"
+        );
+        assert_eq!(api("DELETE", "/lists/Movies", "").status, "204 No Content");
+        assert_eq!(
+            api("GET", "/random", "").body,
+            r#"{"item":{"title":"Akira","year":1988},"list":"Anime TV"}"#
+        );
+    }
+
+    #[test]
+    fn api_errors() {
+        let mut watchlist = WatchList::new();
+        watchlist.insert("Movies", vec![Item::new("Alien")]);
+        let mut api = |method, path, body| respond_api(method, path, body, &mut watchlist);
+
+        let missing = api("GET", "/lists/Movis", "");
+        assert_eq!(missing.status, "404 Not Found");
+        assert_eq!(
+            missing.body,
+            r#"{"error":"No such list - Movis! Did you mean Movies?"}"#
+        );
+        assert_eq!(
+            api("POST", "/lists/Movies/items", r#""Alien""#).status,
+            "409 Conflict"
+        );
+        assert_eq!(api("POST", "/lists", "{").status, "400 Bad Request");
+        assert_eq!(api("GET", "/search", "").status, "400 Bad Request");
+        assert_eq!(api("PUT", "/lists", "").status, "405 Method Not Allowed");
+        assert_eq!(api("GET", "/../watchlist.json", "").status, "404 Not Found");
+
+        assert_eq!(
+            status(&WatchListError::InvalidRating(11)),
+            "400 Bad Request"
+        );
+        assert_eq!(
+            status(&WatchListError::IOError(io::ErrorKind::NotFound.into())),
+            "500 Internal Server Error"
+        );
+        assert_eq!(percent_decode("Anime%20TV%2"), "Anime TV%2");
+    }
+
+    #[test]
+    fn pages_of_items() {
+        let mut watchlist = WatchList::new();
+        let items = ["Alien", "Dune", "Heat", "Ran", "Up"].map(Item::new);
+        watchlist.insert("Movies", items.to_vec());
+        watchlist.insert("Anime", vec![Item::new("Akira").with_year(1988)]);
+        let mut api = |path| respond_api("GET", path, "", &mut watchlist);
+
+        assert_eq!(
+            api("/lists/Movies?offset=1&limit=2&fields=title").body,
+            r#"{"items":[{"title":"Dune"},{"title":"Heat"}],"list":"Movies","total":5}"#
+        );
+        assert_eq!(
+            api("/lists/Movies?filter=title%3Dran%20OR%20title%3Dup&offset=1").body,
+            r#"{"items":["Up"],"list":"Movies","total":2}"#
+        );
+        assert_eq!(
+            api("/lists/Anime?fields=year,status").body,
+            r#"{"items":[{"status":"unwatched","year":1988}],"list":"Anime","total":1}"#
+        );
+        assert_eq!(
+            api("/lists?limit=1").body,
+            r#"[{"items":1,"title":"Anime"}]"#
+        );
+        assert_eq!(
+            api("/lists?include=items&fields=title&offset=1&limit=1").body,
+            r#"[{"contents":[{"title":"Alien"},{"title":"Dune"},{"title":"Heat"},{"title":"Ran"},{"title":"Up"}],"items":5,"title":"Movies"}]"#
+        );
+        assert_eq!(api("/lists?offset=-1").status, "400 Bad Request");
+        assert_eq!(api("/lists/Movies?fields=colour").status, "400 Bad Request");
+        assert_eq!(api("/lists/Movies?filter=(").status, "400 Bad Request");
+    }
+
+    #[test]
+    fn openapi_document() {
+        let document = openapi();
+        for route in ROUTES {
+            let method = route.method.to_lowercase();
+            assert!(document["paths"][route.path][&method].is_object());
+        }
+        let item = &document["paths"]["/lists/{title}/items/{item}"]["delete"];
+        assert_eq!(item["parameters"][1]["name"], "item");
+        assert!(item["responses"]["204"].is_object());
+
+        let mut watchlist = WatchList::new();
+        let served = respond_api("GET", "/openapi.json", "", &mut watchlist);
+        assert_eq!(served.body, document.to_string());
+        assert_eq!(
+            respond_api("PUT", "/lists/Movies/items/Alien", "", &mut watchlist).status,
+            "405 Method Not Allowed"
+        );
+    }
+
+    #[test]
+    fn routes_in_openapi_document() {
+        let document = openapi();
+        let mut watchlist = WatchList::new();
+        watchlist.insert("Movies", vec![Item::new("Alien")]);
+        // Every request which is handled is to a route in the document
+        for route in ROUTES {
+            let path = route
+                .path
+                .replace("{title}", "Movies")
+                .replace("{item}", "Alien");
+            let body = match route.body {
+                Some(_) => r#"{"title": "Heat"}"#,
+                None => "",
+            };
+            let response = respond_api(route.method, &path, body, &mut watchlist.clone());
+            assert!(!response.status.starts_with("405"), "{path}");
+            assert_ne!(response.body, r#"{"error":"Not found"}"#, "{path}");
+            let method = route.method.to_lowercase();
+            assert!(document["paths"][route.path][&method].is_object());
+        }
+        assert_eq!(
+            respond_api("GET", "/lists/Movies/items", "", &mut watchlist).status,
+            "405 Method Not Allowed"
+        );
+        assert_eq!(
+            respond_api("GET", "/graph", "", &mut watchlist).status,
+            "404 Not Found"
+        );
+    }
+
+    #[test]
+    fn tokens() {
+        assert!(authorized(None, None));
+        assert!(authorized(Some("s3cret"), Some("Bearer s3cret")));
+        assert!(!authorized(Some("s3cret"), Some("Bearer s3cre")));
+        assert!(!authorized(Some("s3cret"), Some("Basic s3cret")));
+        assert!(!authorized(Some("s3cret"), None));
+    }
+
+    #[test]
+    fn changes_as_commands() {
+        assert!(allows_change(Some("application/json; charset=utf-8")));
+        assert!(!allows_change(Some("text/plain")));
+        assert!(!allows_change(None));
+
+        let command = |method, path, body| command(method, path, body).map(|a| a.join(" "));
+        assert_eq!(
+            command("POST", "/lists", r#"{"title": "Anime TV"}"#).as_deref(),
+            Some("new Anime TV")
+        );
+        assert_eq!(
+            command(
+                "POST",
+                "/lists/Movies/items",
+                r#"{"title": "Akira", "year": 1988}"#
+            )
+            .as_deref(),
+            Some("add Movies Akira")
+        );
+        assert_eq!(
+            command("DELETE", "/lists/Movies/items/Alien%203", "").as_deref(),
+            Some("delete Movies Alien 3")
+        );
+        assert_eq!(
+            command("DELETE", "/lists/Movies", "").as_deref(),
+            Some("delete Movies")
+        );
+        assert_eq!(command("GET", "/lists/Movies", ""), None);
+        assert_eq!(command("POST", "/lists", "{"), None);
+    }
+
+    #[test]
+    fn web_page() {
+        assert!(is_page("GET", "/"));
+        assert!(is_page("HEAD", "/index.html?list=Movies"));
+        assert!(!is_page("POST", "/"));
+        assert!(!is_page("GET", "/lists"));
+
+        let page = page(false);
+        assert_eq!(page.content_type, "text/html; charset=utf-8");
+        assert!(page.body.contains("const readOnly = false;"));
+        assert!(self::page(true).body.contains("const readOnly = true;"));
+    }
+
+    #[test]
+    fn read_only() {
+        for method in ["POST", "PUT", "DELETE", "PATCH"] {
+            assert_eq!(
+                respond(method, "/list.json", "Movies", &[]).status,
+                "405 Method Not Allowed"
+            );
+        }
+        assert_eq!(
+            respond("GET", "/../watchlist.json", "Movies", &[]).status,
+            "404 Not Found"
+        );
+    }
+}
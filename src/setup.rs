@@ -0,0 +1,183 @@
+//! Wizard which asks for the basic settings on the first run
+
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::theme::Theme;
+use crate::{WatchList, WatchListError};
+
+/// Lists created when none are entered
+pub const DEFAULT_LISTS: &[&str] = &["Movies", "TV"];
+
+/// Answers given to the wizard
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Setup {
+    /// Config with the chosen data file and theme
+    pub config: Config,
+    /// Lists to start with
+    pub lists: Vec<String>,
+}
+
+/// Checks if neither the config file nor the data file exist yet
+pub fn is_first_run(config_path: &Path, data_path: &Path) -> bool {
+    !config_path.exists() && !data_path.exists()
+}
+
+// Asks a question, returning the trimmed answer or `None` if it was left empty
+fn ask(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    question: &str,
+) -> Result<Option<String>, WatchListError> {
+    write!(output, "{question}: ")?;
+    output.flush()?;
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    let answer = answer.trim();
+    Ok((!answer.is_empty()).then(|| answer.to_string()))
+}
+
+fn parse_theme(name: &str) -> Option<Theme> {
+    serde_json::from_value(serde_json::Value::String(name.to_lowercase())).ok()
+}
+
+/// Asks where to keep the lists, which lists to start with and which colors to use
+///
+/// Empty answers keep the defaults and invalid themes are asked for again
+pub fn wizard(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    default_data_path: &Path,
+) -> Result<Setup, WatchListError> {
+    writeln!(
+        output,
+        "Welcome to watchlist! Press Enter to keep the default in brackets."
+    )?;
+    let mut config = Config::default();
+
+    let question = format!(
+        "Where should your lists be stored [{}]",
+        default_data_path.display()
+    );
+    config.data_file = ask(input, output, &question)?.map(PathBuf::from);
+
+    let question = format!(
+        "Lists to start with, separated by commas [{}]",
+        DEFAULT_LISTS.join(", ")
+    );
+    let lists = match ask(input, output, &question)? {
+        Some(answer) => answer
+            .split(',')
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .collect(),
+        None => DEFAULT_LISTS.iter().map(|l| l.to_string()).collect(),
+    };
+
+    config.theme = loop {
+        let question =
+            "Color theme, one of default, solarized, monochrome or high-contrast [default]";
+        match ask(input, output, question)? {
+            None => break Theme::Default,
+            Some(name) => match parse_theme(&name) {
+                Some(theme) => break theme,
+                None => writeln!(output, "No such theme - {name}!")?,
+            },
+        }
+    };
+
+    Ok(Setup { config, lists })
+}
+
+/// Writes the config and creates the lists in the data file, keeping any lists already in it
+///
+/// Returns the path of the data file
+pub fn apply(
+    setup: &Setup,
+    config_path: &Path,
+    default_data_path: &Path,
+) -> Result<PathBuf, WatchListError> {
+    setup.config.to_file(config_path)?;
+
+    let data_path = setup
+        .config
+        .data_file
+        .clone()
+        .unwrap_or_else(|| default_data_path.to_path_buf());
+    let mut watchlist = match data_path.exists() {
+        true => WatchList::from_file(&data_path)?,
+        false => WatchList::new(),
+    };
+    for list in &setup.lists {
+        // Lists which already exist are kept as they are
+        let _ = watchlist.list_add(list);
+    }
+    watchlist.to_file(&data_path)?;
+    Ok(data_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn answers() {
+        let mut output = Vec::new();
+        let mut input = Cursor::new("/tmp/lists.json\nAnime, Manga,,\nneon\nSolarized\n");
+        let setup = wizard(&mut input, &mut output, Path::new("watchlist.json")).unwrap();
+        assert_eq!(
+            setup.config.data_file,
+            Some(PathBuf::from("/tmp/lists.json"))
+        );
+        assert_eq!(setup.lists, vec!["Anime", "Manga"]);
+        assert_eq!(setup.config.theme, Theme::Solarized);
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .contains("No such theme - neon!"));
+
+        // Empty answers keep the defaults
+        let setup = wizard(
+            &mut Cursor::new("\n\n\n"),
+            &mut Vec::new(),
+            Path::new("w.json"),
+        )
+        .unwrap();
+        assert_eq!(
+            setup,
+            Setup {
+                config: Config::default(),
+                lists: vec!["Movies".to_string(), "TV".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn applying() {
+        let dir = std::env::temp_dir().join(format!("wlist_setup_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (config_path, data_path) = (dir.join("config.json"), dir.join("watchlist.json"));
+        assert!(is_first_run(&config_path, &data_path));
+
+        let mut existing = WatchList::new();
+        existing.insert("Movies".to_string(), vec!["Dune".into()]);
+        existing.to_file(&data_path).unwrap();
+
+        let setup = Setup {
+            config: Config {
+                theme: Theme::Monochrome,
+                ..Default::default()
+            },
+            lists: vec!["Movies".to_string(), "Books".to_string()],
+        };
+        assert_eq!(apply(&setup, &config_path, &data_path).unwrap(), data_path);
+        assert!(!is_first_run(&config_path, &data_path));
+
+        assert_eq!(Config::from_file(&config_path).unwrap(), setup.config);
+        let watchlist = WatchList::from_file(&data_path).unwrap();
+        assert_eq!(watchlist["Movies"], vec!["Dune"]);
+        assert!(watchlist["Books"].is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
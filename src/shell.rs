@@ -0,0 +1,59 @@
+//! Splitting the lines typed into `wl shell` into arguments
+
+/// Splits a line into arguments at whitespace, like a shell would
+///
+/// Arguments can be quoted with double or single quotes to keep spaces in
+/// them, and a backslash outside of single quotes escapes the next character
+pub fn split_args(line: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut arg: Option<String> = None;
+    let mut quote = None;
+
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (c, quote) {
+            ('\\', Some('"') | None) => {
+                let escaped = chars
+                    .next()
+                    .ok_or("nothing to escape at the end of the line")?;
+                arg.get_or_insert_with(String::new).push(escaped);
+            }
+            ('"' | '\'', None) => {
+                quote = Some(c);
+                // Quotes can make an empty argument
+                arg.get_or_insert_with(String::new);
+            }
+            (c, Some(q)) if c == q => quote = None,
+            (c, None) if c.is_whitespace() => args.extend(arg.take()),
+            (c, _) => arg.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    if let Some(q) = quote {
+        return Err(format!("unterminated {q} quote"));
+    }
+    args.extend(arg);
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splitting() {
+        assert_eq!(
+            split_args(r#"add Movies "The Thing" 'Blade Runner'  Alien"#).unwrap(),
+            vec!["add", "Movies", "The Thing", "Blade Runner", "Alien"]
+        );
+        assert_eq!(
+            split_args(r#"add "Don't Look Up" It\'s\ Alive 'say "hi"' """#).unwrap(),
+            vec!["add", "Don't Look Up", "It's Alive", r#"say "hi""#, ""]
+        );
+        assert_eq!(split_args("  ").unwrap(), Vec::<String>::new());
+        assert_eq!(
+            split_args(r#"add Movies "Dune"#).unwrap_err(),
+            "unterminated \" quote"
+        );
+    }
+}
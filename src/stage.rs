@@ -0,0 +1,223 @@
+//! Staging area where changes are collected, reviewed and applied together
+
+use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::status::Status;
+use crate::{encryption, DuplicatePolicy, Item, WatchList, WatchListError};
+
+/// A change waiting to be applied
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum Change {
+    Add {
+        list: String,
+        item: Item,
+    },
+    Remove {
+        list: String,
+        item: String,
+    },
+    Move {
+        from: String,
+        to: String,
+        item: String,
+    },
+    SetStatus {
+        list: String,
+        item: String,
+        status: Status,
+    },
+}
+
+impl Change {
+    fn apply(&self, watchlist: &mut WatchList) -> Result<(), WatchListError> {
+        match self {
+            Change::Add { list, item } => {
+                watchlist.item_add(list, item.clone(), DuplicatePolicy::Exact)
+            }
+            Change::Remove { list, item } => watchlist.item_remove(list, item),
+            Change::Move { from, to, item } => watchlist.item_move(from, to, item),
+            Change::SetStatus { list, item, status } => {
+                watchlist.item_set_status(list, item, *status)
+            }
+        }
+    }
+}
+
+impl Change {
+    /// Describes the change in words, without the symbols used by the diff
+    pub fn describe(&self) -> String {
+        match self {
+            Change::Add { list, item } => format!("Add {item} to {list}"),
+            Change::Remove { list, item } => format!("Remove {item} from {list}"),
+            Change::Move { from, to, item } => format!("Move {item} from {from} to {to}"),
+            Change::SetStatus { list, item, status } => {
+                format!("Mark {item} in {list} as {status}")
+            }
+        }
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::Add { list, item } => write!(f, "+ {item} ({list})"),
+            Change::Remove { list, item } => write!(f, "- {item} ({list})"),
+            Change::Move { from, to, item } => write!(f, "~ {item} ({from} -> {to})"),
+            Change::SetStatus { list, item, status } => write!(f, "* {item} ({list}: {status})"),
+        }
+    }
+}
+
+/// Changes saved to a file until they are applied
+#[derive(Debug, Default)]
+pub struct Stage {
+    file_path: PathBuf,
+    path: PathBuf,
+    pub changes: Vec<Change>,
+}
+
+impl Stage {
+    /// Reads the staged changes next to the data file, starting with none if there isn't any
+    pub fn from_file(file_path: &Path) -> Result<Self, WatchListError> {
+        let path = crate::sidecar_path(file_path, "stage");
+        let changes = match encryption::read_sidecar(file_path, &path)? {
+            Some(data) => serde_json::from_str(&data)
+                .map_err(|e| WatchListError::ParseError(e.to_string()))?,
+            None => Vec::new(),
+        };
+        Ok(Stage {
+            file_path: file_path.to_path_buf(),
+            path,
+            changes,
+        })
+    }
+
+    /// Saves the changes, removing the file once there are none
+    pub fn to_file(&self) -> Result<(), WatchListError> {
+        if self.changes.is_empty() {
+            return match fs::remove_file(&self.path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(WatchListError::IOError(e)),
+                _ => Ok(()),
+            };
+        }
+        let data =
+            serde_json::to_string(&self.changes).map_err(|e| WatchListError::IOError(e.into()))?;
+        encryption::write_sidecar(&self.file_path, &self.path, data.as_bytes())
+    }
+
+    // Applies every staged change to a copy of the watchlist
+    fn staged(&self, watchlist: &WatchList) -> Result<WatchList, WatchListError> {
+        let mut staged = watchlist.clone();
+        for change in &self.changes {
+            change.apply(&mut staged)?;
+        }
+        Ok(staged)
+    }
+
+    /// Stages a change, checking that it can be applied after the ones already staged
+    pub fn push(&mut self, watchlist: &WatchList, change: Change) -> Result<(), WatchListError> {
+        change.apply(&mut self.staged(watchlist)?)?;
+        self.changes.push(change);
+        Ok(())
+    }
+
+    /// Applies all the staged changes and clears them, returning how many there were
+    ///
+    /// The watchlist is left untouched if any change fails
+    pub fn apply(&mut self, watchlist: &mut WatchList) -> Result<usize, WatchListError> {
+        *watchlist = self.staged(watchlist)?;
+        let applied = self.changes.len();
+        self.changes.clear();
+        Ok(applied)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watchlist() -> WatchList {
+        let mut watchlist = WatchList::new();
+        watchlist.insert(
+            "Movies".to_string(),
+            vec![Item::new("Dune"), Item::new("Lost")],
+        );
+        watchlist.insert("TV".to_string(), vec![]);
+        watchlist
+    }
+
+    #[test]
+    fn staging() {
+        let mut watchlist = watchlist();
+        let mut stage = Stage::default();
+        let changes = [
+            Change::Add {
+                list: "Movies".to_string(),
+                item: Item::new("Alien"),
+            },
+            Change::Move {
+                from: "Movies".to_string(),
+                to: "TV".to_string(),
+                item: "Lost".to_string(),
+            },
+            Change::Remove {
+                list: "Movies".to_string(),
+                item: "Alien".to_string(),
+            },
+            Change::SetStatus {
+                list: "Movies".to_string(),
+                item: "Dune".to_string(),
+                status: Status::Dropped,
+            },
+        ];
+        for change in changes {
+            stage.push(&watchlist, change).unwrap();
+        }
+        let diff: Vec<String> = stage.changes.iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            diff,
+            [
+                "+ Alien (Movies)",
+                "~ Lost (Movies -> TV)",
+                "- Alien (Movies)",
+                "* Dune (Movies: dropped)"
+            ]
+        );
+
+        // Nothing changes until the stage is applied
+        assert_eq!(watchlist, self::watchlist());
+        assert_eq!(stage.apply(&mut watchlist).unwrap(), 4);
+        assert_eq!(watchlist["Movies"], vec!["Dune"]);
+        assert_eq!(watchlist["Movies"][0].status(), Status::Dropped);
+        assert_eq!(watchlist["TV"], vec!["Lost"]);
+        assert!(stage.changes.is_empty());
+    }
+
+    #[test]
+    fn staging_errors() {
+        let mut watchlist = watchlist();
+        let mut stage = Stage::default();
+        let remove = Change::Remove {
+            list: "Movies".to_string(),
+            item: "Dune".to_string(),
+        };
+        stage.push(&watchlist, remove.clone()).unwrap();
+        assert_eq!(
+            stage.push(&watchlist, remove.clone()).err().unwrap(),
+            WatchListError::ItemToRemoveNotPresent("Dune".to_string())
+        );
+
+        // Changes which no longer apply leave the watchlist as it was
+        watchlist.item_remove("Movies", "Dune").unwrap();
+        let before = watchlist.clone();
+        assert!(stage.apply(&mut watchlist).is_err());
+        assert_eq!(watchlist, before);
+        assert_eq!(stage.changes, vec![remove]);
+    }
+}
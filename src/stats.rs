@@ -0,0 +1,299 @@
+//! Counts of the items in the lists, and activity per day drawn as a calendar heatmap,
+//! like the contribution graph on GitHub
+
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Locale, NaiveDate, TimeDelta};
+use serde::Serialize;
+
+use crate::dates;
+use crate::status::Status;
+use crate::{Item, WatchList};
+
+/// Number of days items count as recently added for
+pub const RECENT_DAYS: i64 = 30;
+
+/// Counts of the items in every list, made by [`WatchList::stats`](crate::WatchList::stats)
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Stats {
+    /// Counts for each list, sorted by title
+    pub lists: Vec<ListStats>,
+    /// Counts for the items of all the lists together
+    pub total: Counts,
+    /// Title of the list with the most items, the first by title when several have as many
+    pub largest: Option<String>,
+    /// Items added in the last [`RECENT_DAYS`] days, which is only known from the history
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recently_added: Option<usize>,
+}
+
+/// Counts of the items in a list
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ListStats {
+    pub title: String,
+    #[serde(flatten)]
+    pub counts: Counts,
+}
+
+/// Number of items with each status
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Counts {
+    pub items: usize,
+    pub watched: usize,
+    pub unwatched: usize,
+    pub dropped: usize,
+    /// Share of the items which were watched, from 0 to 1, none without any items
+    pub watched_ratio: Option<f64>,
+}
+
+impl Counts {
+    fn of<'a>(items: impl IntoIterator<Item = &'a Item>) -> Self {
+        let mut counts = Counts::default();
+        for item in items {
+            counts.items += 1;
+            match item.status() {
+                Status::Watched => counts.watched += 1,
+                Status::Unwatched => counts.unwatched += 1,
+                Status::Dropped => counts.dropped += 1,
+            }
+        }
+        counts.watched_ratio = match counts.items {
+            0 => None,
+            items => Some(counts.watched as f64 / items as f64),
+        };
+        counts
+    }
+}
+
+impl Stats {
+    pub(crate) fn of(watchlist: &WatchList) -> Self {
+        let mut lists: Vec<ListStats> = watchlist
+            .iter()
+            .map(|(title, items)| ListStats {
+                title: title.clone(),
+                counts: Counts::of(items),
+            })
+            .collect();
+        lists.sort_by(|a, b| a.title.cmp(&b.title));
+        // The first of the largest lists, as max_by_key gives the last
+        let largest = lists
+            .iter()
+            .rev()
+            .max_by_key(|l| l.counts.items)
+            .map(|l| l.title.clone());
+        Stats {
+            lists,
+            total: Counts::of(watchlist.values().flatten()),
+            largest,
+            recently_added: None,
+        }
+    }
+}
+
+/// Number of weeks shown, so a whole year fits
+pub const WEEKS: i64 = 53;
+
+/// Shades for days with more and more activity, from none to the busiest
+const SHADES: [char; 5] = ['·', '░', '▒', '▓', '█'];
+
+/// What happened to items on a day
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Activity {
+    pub added: usize,
+    pub completed: usize,
+}
+
+impl Activity {
+    pub fn total(&self) -> usize {
+        self.added + self.completed
+    }
+}
+
+/// Activity on each day
+#[derive(Debug, Default)]
+pub struct Heatmap {
+    days: BTreeMap<NaiveDate, Activity>,
+}
+
+impl Heatmap {
+    /// Adds to the activity of a day
+    pub fn record(&mut self, day: NaiveDate, activity: Activity) {
+        let entry = self.days.entry(day).or_default();
+        entry.added += activity.added;
+        entry.completed += activity.completed;
+    }
+
+    /// Gets the activity of a day
+    pub fn get(&self, day: NaiveDate) -> Activity {
+        self.days.get(&day).copied().unwrap_or_default()
+    }
+
+    /// Gets the days with any activity from `first` to `last`, oldest first
+    pub fn active_days(&self, first: NaiveDate, last: NaiveDate) -> Vec<(NaiveDate, Activity)> {
+        self.days
+            .range(first..=last)
+            .filter(|(_, a)| a.total() > 0)
+            .map(|(d, a)| (*d, *a))
+            .collect()
+    }
+
+    /// Draws the weeks up to the one with `last` in them as columns, with a row for each
+    /// day of the week starting from Monday and the months labelled above
+    pub fn render(&self, last: NaiveDate, locale: Locale) -> Vec<String> {
+        let first = first_day(last);
+        let busiest = self
+            .active_days(first, last)
+            .iter()
+            .map(|(_, a)| a.total())
+            .max()
+            .unwrap_or(0);
+
+        // Months are labelled above their first Monday, when there is room
+        let mut months = String::new();
+        for week in 0..WEEKS {
+            let monday = first + TimeDelta::weeks(week);
+            let width = months.chars().count() as i64;
+            if monday.day() <= 7 && (width == 0 || width < week) {
+                let label = dates::format_date(monday, "%b", locale);
+                months.push_str(&" ".repeat((week - width) as usize));
+                months.push_str(&label);
+            }
+        }
+        let mut lines = vec![format!("    {}", months.trim_end())];
+
+        for weekday in 0..7 {
+            let label = match weekday {
+                0 | 2 | 4 => dates::format_date(first + TimeDelta::days(weekday), "%a", locale),
+                _ => String::new(),
+            };
+            let row: String = (0..WEEKS)
+                .map(|week| first + TimeDelta::days(week * 7 + weekday))
+                .map(|day| match day > last {
+                    true => ' ',
+                    false => shade(self.get(day).total(), busiest),
+                })
+                .collect();
+            lines.push(format!("{:<4}{}", truncate(&label, 3), row.trim_end()));
+        }
+        lines
+    }
+}
+
+/// Gets the Monday the heatmap ending on `last` starts on
+pub fn first_day(last: NaiveDate) -> NaiveDate {
+    let monday = last - TimeDelta::days(last.weekday().num_days_from_monday() as i64);
+    monday - TimeDelta::weeks(WEEKS - 1)
+}
+
+// Shades are spread evenly up to the busiest day, so any activity shows up
+fn shade(total: usize, busiest: usize) -> char {
+    if total == 0 || busiest == 0 {
+        return SHADES[0];
+    }
+    let level = (total * (SHADES.len() - 1)).div_ceil(busiest);
+    SHADES[level.min(SHADES.len() - 1)]
+}
+
+fn truncate(text: &str, chars: usize) -> String {
+    text.chars().take(chars).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn counting() {
+        let mut watchlist: WatchList = serde_json::from_str(
+            r#"{
+                "Movies": ["Alien", { "title": "Heat", "watched": true }, "Ran"],
+                "Anime": [{ "title": "Lain", "dropped": true }, "Mushishi", "Monster"],
+                "Manga": []
+            }"#,
+        )
+        .unwrap();
+        watchlist.get_mut("Movies").unwrap()[2].meta.watched = true;
+        let stats = Stats::of(&watchlist);
+
+        let titles: Vec<&str> = stats.lists.iter().map(|l| l.title.as_str()).collect();
+        assert_eq!(titles, ["Anime", "Manga", "Movies"]);
+        assert_eq!(stats.largest.as_deref(), Some("Anime"));
+        assert_eq!(
+            stats.total,
+            Counts {
+                items: 6,
+                watched: 2,
+                unwatched: 3,
+                dropped: 1,
+                watched_ratio: Some(2.0 / 6.0),
+            }
+        );
+        assert_eq!(stats.lists[1].counts.watched_ratio, None);
+
+        let json = serde_json::to_value(&stats).unwrap();
+        assert_eq!(json["lists"][2]["title"], "Movies");
+        assert_eq!(json["lists"][2]["watched"], 2);
+        assert!(json.get("recently_added").is_none());
+        assert_eq!(Stats::of(&WatchList::new()).largest, None);
+    }
+
+    #[test]
+    fn rendering() {
+        let last = date("2024-03-13");
+        assert_eq!(first_day(last), date("2023-03-13"));
+
+        let mut heatmap = Heatmap::default();
+        heatmap.record(
+            last,
+            Activity {
+                added: 4,
+                completed: 0,
+            },
+        );
+        heatmap.record(
+            date("2024-03-11"),
+            Activity {
+                added: 1,
+                completed: 0,
+            },
+        );
+        heatmap.record(
+            date("2024-03-11"),
+            Activity {
+                added: 0,
+                completed: 1,
+            },
+        );
+        // Too old to be shown
+        heatmap.record(
+            date("2023-03-12"),
+            Activity {
+                added: 9,
+                completed: 9,
+            },
+        );
+        assert_eq!(
+            heatmap.get(date("2024-03-11")),
+            Activity {
+                added: 1,
+                completed: 1
+            }
+        );
+        assert_eq!(heatmap.active_days(first_day(last), last).len(), 2);
+
+        let lines = heatmap.render(last, Locale::POSIX);
+        assert_eq!(lines.len(), 8);
+        assert!(lines[0].starts_with("       Apr May"));
+        assert!(lines[0].ends_with("Mar"));
+        let empty = "·".repeat(WEEKS as usize - 1);
+        assert_eq!(lines[1], format!("Mon {empty}▒"));
+        assert_eq!(lines[2], format!("    {empty}·"));
+        assert_eq!(lines[3], format!("Wed {empty}█"));
+        // Days after the last one are left blank
+        assert_eq!(lines[4], format!("    {empty}"));
+    }
+}
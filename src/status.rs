@@ -0,0 +1,65 @@
+//! Where an item stands, from not yet watched to finished or given up on
+
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Item;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Unwatched,
+    Watched,
+    /// Given up on without finishing it
+    Dropped,
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Status::Unwatched => "unwatched",
+            Status::Watched => "watched",
+            Status::Dropped => "dropped",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl Item {
+    /// Gets the status of the item, where watched wins over dropped
+    pub fn status(&self) -> Status {
+        match (self.meta.watched, self.meta.dropped) {
+            (true, _) => Status::Watched,
+            (false, true) => Status::Dropped,
+            (false, false) => Status::Unwatched,
+        }
+    }
+
+    pub fn set_status(&mut self, status: Status) {
+        self.meta.watched = status == Status::Watched;
+        self.meta.dropped = status == Status::Dropped;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transitions() {
+        let mut item = Item::new("Lost");
+        assert_eq!(item.status(), Status::Unwatched);
+        item.set_status(Status::Dropped);
+        assert_eq!(item.status(), Status::Dropped);
+        assert!(!item.meta.watched);
+        item.set_status(Status::Watched);
+        assert_eq!(item.status(), Status::Watched);
+        assert!(!item.meta.dropped);
+        item.meta.dropped = true;
+        assert_eq!(item.status(), Status::Watched);
+        item.set_status(Status::Unwatched);
+        assert_eq!(item, Item::new("Lost"));
+        assert_eq!(Status::Dropped.to_string(), "dropped");
+    }
+}
@@ -0,0 +1,133 @@
+//! Suggestions of the list a title which doesn't exist was meant to be, for typos like "Movis",
+//! and of the list items added without one most likely belong in
+
+use std::collections::HashMap;
+
+use crate::dedup::{self, Similarity};
+use crate::WatchList;
+
+/// Similarity from 0 to 1 a title needs to be suggested
+const MIN_SIMILARITY: f64 = 0.7;
+
+/// Most titles suggested at once
+const MAX_SUGGESTIONS: usize = 3;
+
+/// How much the share of the items added to a list before counts for, next to how alike
+/// the items are to the ones already in it
+const PAST_WEIGHT: f64 = 0.25;
+
+/// Gets the titles similar to one which doesn't exist, most similar first
+pub fn similar_titles<'a>(
+    titles: impl IntoIterator<Item = &'a String>,
+    title: &str,
+) -> Vec<String> {
+    let title = title.to_lowercase();
+    let mut similar: Vec<(f64, &String)> = titles
+        .into_iter()
+        .map(|t| {
+            let similarity = strsim::normalized_damerau_levenshtein(&t.to_lowercase(), &title);
+            (similarity, t)
+        })
+        .filter(|(similarity, _)| *similarity >= MIN_SIMILARITY)
+        .collect();
+    // Titles as similar as each other are sorted so the suggestions don't change between runs
+    similar.sort_by(|(a, a_title), (b, b_title)| b.total_cmp(a).then(a_title.cmp(b_title)));
+    similar
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, t)| t.clone())
+        .collect()
+}
+
+/// Gets the titles of every list, the ones the items most likely belong in first
+///
+/// Each item is compared to the item in the list most like it, and lists which more items
+/// were added to before, counted by list in `past`, are favoured a little
+pub fn likely_lists(
+    watchlist: &WatchList,
+    items: &[&str],
+    past: &HashMap<String, usize>,
+) -> Vec<String> {
+    let items: Vec<String> = items.iter().map(|i| dedup::normalize(i)).collect();
+    let total_past: usize = past.values().sum();
+    let mut likely: Vec<(f64, &String)> = watchlist
+        .iter()
+        .map(|(title, list)| {
+            let titles: Vec<String> = list.iter().map(|i| dedup::normalize(&i.title)).collect();
+            let similarity: f64 = items
+                .iter()
+                .map(|item| {
+                    titles
+                        .iter()
+                        .map(|t| {
+                            Similarity::TokenSet
+                                .compare(item, t)
+                                .max(Similarity::Levenshtein.compare(item, t))
+                        })
+                        .fold(0.0, f64::max)
+                })
+                .sum::<f64>()
+                / items.len().max(1) as f64;
+            let share = match total_past {
+                0 => 0.0,
+                total => past.get(title).copied().unwrap_or(0) as f64 / total as f64,
+            };
+            (similarity + PAST_WEIGHT * share, title)
+        })
+        .collect();
+    likely.sort_by(|(a, a_title), (b, b_title)| b.total_cmp(a).then(a_title.cmp(b_title)));
+    likely.into_iter().map(|(_, t)| t.clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suggesting() {
+        let titles: Vec<String> = ["Movies", "Movie Night", "Anime", "TV", "tv"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        assert_eq!(similar_titles(&titles, "Movis"), vec!["Movies"]);
+        assert_eq!(similar_titles(&titles, "ANIME"), vec!["Anime"]);
+        assert_eq!(similar_titles(&titles, "Tv"), vec!["TV", "tv"]);
+        assert_eq!(similar_titles(&titles, "Books"), Vec::<String>::new());
+        assert_eq!(similar_titles(&[], "Movies"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn likely() {
+        let watchlist: WatchList = [
+            ("Movies", vec!["The Dark Knight", "Alien", "Heat"]),
+            ("Anime", vec!["Cowboy Bebop", "Naruto Shippuden"]),
+            ("Books", vec![]),
+        ]
+        .into_iter()
+        .map(|(title, items)| {
+            (
+                title.to_string(),
+                items.into_iter().map(crate::Item::new).collect(),
+            )
+        })
+        .collect();
+        let none = HashMap::new();
+        assert_eq!(
+            likely_lists(&watchlist, &["The Dark Knight Rises"], &none),
+            vec!["Movies", "Anime", "Books"]
+        );
+        assert_eq!(
+            likely_lists(&watchlist, &["Naruto", "Bebop"], &none)[0],
+            "Anime"
+        );
+
+        // Lists items were added to before win when the items are like none of them
+        let past = HashMap::from([("Books".to_string(), 5), ("Movies".to_string(), 1)]);
+        assert_eq!(likely_lists(&watchlist, &["Xyz"], &past)[0], "Books");
+        assert_eq!(
+            likely_lists(&watchlist, &["The Dark Knight Rises"], &past)[0],
+            "Movies"
+        );
+        assert!(likely_lists(&WatchList::new(), &["Alien"], &past).is_empty());
+    }
+}
@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+use std::fs::{self, read_to_string};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::dates::{self, Timestamp};
+use crate::{import, Item, WatchList, WatchListError};
+
+/// How differences between a local list and a remote service are resolved
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConflictStrategy {
+    /// Keep items from both sides
+    #[default]
+    Merge,
+    /// Make the remote match the local list
+    LocalWins,
+    /// Make the local list match the remote
+    RemoteWins,
+}
+
+/// Changes needed to bring a local list and a remote service in sync
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SyncPlan<'a> {
+    /// Remote items to add to the local list
+    pub pull: Vec<&'a Item>,
+    /// Local items to add to the remote
+    pub push: Vec<&'a Item>,
+    /// Local items to remove from the local list
+    pub remove_local: Vec<&'a Item>,
+    /// Remote items to remove from the remote
+    pub remove_remote: Vec<&'a Item>,
+}
+
+/// Items added and removed on each side during a sync
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    pub pulled: usize,
+    pub pushed: usize,
+    pub removed_local: usize,
+    pub removed_remote: usize,
+}
+
+impl std::ops::AddAssign for SyncReport {
+    fn add_assign(&mut self, other: Self) {
+        self.pulled += other.pulled;
+        self.pushed += other.pushed;
+        self.removed_local += other.removed_local;
+        self.removed_remote += other.removed_remote;
+    }
+}
+
+// Items are matched by their IDs where both have one for the same service, and
+// otherwise by title since services don't always agree on the case of titles
+fn same_item(a: &Item, b: &Item) -> bool {
+    a.same_ids(b)
+        .unwrap_or_else(|| a.title.trim().eq_ignore_ascii_case(b.title.trim()))
+}
+
+/// Works out the changes needed on each side to sync `local` and `remote`
+pub fn plan<'a>(local: &'a [Item], remote: &'a [Item], strategy: ConflictStrategy) -> SyncPlan<'a> {
+    let local_only = local
+        .iter()
+        .filter(|l| !remote.iter().any(|r| same_item(l, r)));
+    let remote_only = remote
+        .iter()
+        .filter(|r| !local.iter().any(|l| same_item(l, r)));
+
+    let mut plan = SyncPlan::default();
+    match strategy {
+        ConflictStrategy::Merge => {
+            plan.push = local_only.collect();
+            plan.pull = remote_only.collect();
+        }
+        ConflictStrategy::LocalWins => {
+            plan.push = local_only.collect();
+            plan.remove_remote = remote_only.collect();
+        }
+        ConflictStrategy::RemoteWins => {
+            plan.remove_local = local_only.collect();
+            plan.pull = remote_only.collect();
+        }
+    }
+    plan
+}
+
+/// Pulls and removes the items in the plan from a local list
+pub fn apply_local(
+    watchlist: &mut WatchList,
+    title: &str,
+    plan: &SyncPlan,
+) -> Result<(), WatchListError> {
+    import::import_items(watchlist, title, plan.pull.iter().map(|i| (*i).clone()))?;
+    for item in &plan.remove_local {
+        watchlist.item_remove(title, &item.title)?;
+    }
+    Ok(())
+}
+
+/// Access token for a remote service
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Token {
+    pub access_token: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<Timestamp>,
+}
+
+impl Token {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|e| e <= dates::now())
+    }
+}
+
+/// Access tokens for remote services, saved to a file
+#[derive(Debug, Default)]
+pub struct TokenStore {
+    path: PathBuf,
+    tokens: HashMap<String, Token>,
+}
+
+impl TokenStore {
+    /// Reads the stored tokens, starting with none if the file doesn't exist
+    pub fn from_file(file_path: &Path) -> Result<Self, WatchListError> {
+        let tokens = match read_to_string(file_path) {
+            Ok(data) => serde_json::from_str(&data)
+                .map_err(|e| WatchListError::ParseError(e.to_string()))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(WatchListError::IOError(e)),
+        };
+        Ok(TokenStore {
+            path: file_path.to_path_buf(),
+            tokens,
+        })
+    }
+
+    pub fn get(&self, service: &str) -> Option<&Token> {
+        self.tokens.get(service)
+    }
+
+    /// Stores the token for a service and saves all the tokens
+    ///
+    /// The file is only readable by the current user on Unix
+    pub fn set(&mut self, service: &str, token: Token) -> Result<(), WatchListError> {
+        self.tokens.insert(service.to_string(), token);
+
+        let data =
+            serde_json::to_string(&self.tokens).map_err(|e| WatchListError::IOError(e.into()))?;
+        fs::write(&self.path, data)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&self.path, fs::Permissions::from_mode(0o600))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titles(items: &[&Item]) -> Vec<String> {
+        items.iter().map(|i| i.title.clone()).collect()
+    }
+
+    #[test]
+    fn sync_plans() {
+        let local = vec![Item::new("Dune"), Item::new("Alien")];
+        let remote = vec![Item::new("alien"), Item::new("Heat")];
+
+        let merge = plan(&local, &remote, ConflictStrategy::Merge);
+        assert_eq!(titles(&merge.push), vec!["Dune"]);
+        assert_eq!(titles(&merge.pull), vec!["Heat"]);
+        assert!(merge.remove_local.is_empty() && merge.remove_remote.is_empty());
+
+        let local_wins = plan(&local, &remote, ConflictStrategy::LocalWins);
+        assert_eq!(titles(&local_wins.push), vec!["Dune"]);
+        assert_eq!(titles(&local_wins.remove_remote), vec!["Heat"]);
+        assert!(local_wins.pull.is_empty() && local_wins.remove_local.is_empty());
+
+        let remote_wins = plan(&local, &remote, ConflictStrategy::RemoteWins);
+        assert_eq!(titles(&remote_wins.pull), vec!["Heat"]);
+        assert_eq!(titles(&remote_wins.remove_local), vec!["Dune"]);
+        assert!(remote_wins.push.is_empty() && remote_wins.remove_remote.is_empty());
+    }
+
+    #[test]
+    fn matching_by_id() {
+        let local = vec![
+            Item::new("Shingeki no Kyojin").with_id("anilist", "16498"),
+            Item::new("Akira").with_id("anilist", "47"),
+        ];
+        let remote = vec![
+            Item::new("Attack on Titan").with_id("anilist", "16498"),
+            Item::new("Akira").with_id("anilist", "7"),
+        ];
+        let merge = plan(&local, &remote, ConflictStrategy::Merge);
+        assert_eq!(titles(&merge.push), vec!["Akira"]);
+        assert_eq!(titles(&merge.pull), vec!["Akira"]);
+    }
+
+    #[test]
+    fn apply_plan() {
+        let mut watchlist = WatchList::new();
+        watchlist.insert(
+            "Movies".to_string(),
+            vec![Item::new("Dune"), Item::new("Alien")],
+        );
+        let local = watchlist["Movies"].clone();
+        let remote = vec![Item::new("Alien"), Item::new("Heat")];
+
+        let remote_wins = plan(&local, &remote, ConflictStrategy::RemoteWins);
+        apply_local(&mut watchlist, "Movies", &remote_wins).unwrap();
+        assert_eq!(watchlist["Movies"], vec!["Alien", "Heat"]);
+    }
+
+    #[test]
+    fn token_store() {
+        let path = std::env::temp_dir().join(format!("wlist_tokens_{}.json", std::process::id()));
+        let token = Token {
+            access_token: "abc".to_string(),
+            refresh_token: None,
+            expires_at: None,
+        };
+
+        let mut store = TokenStore::from_file(&path).unwrap();
+        assert_eq!(store.get("trakt"), None);
+        store.set("trakt", token.clone()).unwrap();
+
+        let store = TokenStore::from_file(&path).unwrap();
+        assert_eq!(store.get("trakt"), Some(&token));
+        assert!(!token.is_expired());
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,240 @@
+//! Running `wl` in a sandbox for testing whole commands, built with the `test-support` feature
+//!
+//! Each [`Sandbox`] gets a temporary home directory of its own holding the data and config
+//! files, so commands never touch the real lists and the output of each command can be
+//! checked on its own. Packagers can point it at the `wl` they built:
+//!
+//! ```no_run
+//! use wlist::testing::Sandbox;
+//!
+//! let sandbox = Sandbox::new("/usr/bin/wl")?;
+//! sandbox.run(&["new", "Movies"])?.success();
+//! sandbox.run(&["add", "Movies", "Alien"])?.success();
+//! assert_eq!(sandbox.run(&["random", "Movies"])?.success().stdout, "Alien\n");
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{WatchList, WatchListError};
+
+// Sandboxes made by the same process are told apart by number
+static SANDBOXES: AtomicUsize = AtomicUsize::new(0);
+
+/// Environment variables which change where `wl` keeps its files, what it connects to or
+/// how it prints, which are removed so the ones of the person running the tests don't leak in
+const CLEARED_VARS: &[&str] = &[
+    "WATCHLIST_FILE_PATH",
+    "WATCHLIST_CONFIG_PATH",
+    "TMDB_API_KEY",
+    "TRAKT_CLIENT_ID",
+    "TRAKT_CLIENT_SECRET",
+    "ANILIST_TOKEN",
+    "COMPLETE",
+    "CLICOLOR_FORCE",
+    "WATCHLIST_PASSPHRASE",
+    "WATCHLIST_SERVE_TOKEN",
+];
+
+/// A temporary home directory `wl` is run in, which is removed when it's dropped
+#[derive(Debug)]
+pub struct Sandbox {
+    binary: PathBuf,
+    home: PathBuf,
+    vars: Vec<(String, String)>,
+}
+
+/// What a command printed and the exit code it finished with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Output {
+    /// Exit code, none if the command was killed by a signal
+    pub code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl Output {
+    /// Checks the command succeeded, panicking with what it printed if it didn't
+    #[track_caller]
+    pub fn success(self) -> Self {
+        assert_eq!(self.code, Some(0), "the command failed\n{}", self.stderr);
+        self
+    }
+
+    /// Checks the command failed with the exit code, panicking with what it printed if it didn't
+    #[track_caller]
+    pub fn failure(self, code: u8) -> Self {
+        assert_eq!(
+            self.code,
+            Some(i32::from(code)),
+            "the command didn't fail with exit code {code}\n{}{}",
+            self.stdout,
+            self.stderr
+        );
+        self
+    }
+}
+
+impl Sandbox {
+    /// Makes an empty home directory for running the `wl` binary at the path in
+    pub fn new(binary: impl Into<PathBuf>) -> io::Result<Self> {
+        let number = SANDBOXES.fetch_add(1, Ordering::Relaxed);
+        let home = env::temp_dir().join(format!("wlist-sandbox-{}-{number}", std::process::id()));
+        // Left over from an earlier run which was killed before cleaning up
+        if home.exists() {
+            fs::remove_dir_all(&home)?;
+        }
+        fs::create_dir_all(&home)?;
+        Ok(Sandbox {
+            binary: binary.into(),
+            home,
+            vars: Vec::new(),
+        })
+    }
+
+    /// Sets an environment variable for every command, like the passphrase of an
+    /// encrypted data file
+    pub fn with_var(mut self, var: &str, value: &str) -> Self {
+        self.vars.push((var.to_string(), value.to_string()));
+        self
+    }
+
+    /// Home directory the commands are run in
+    pub fn home(&self) -> &Path {
+        &self.home
+    }
+
+    /// Data file the commands use
+    pub fn data_file(&self) -> PathBuf {
+        self.home.join("watchlist.json")
+    }
+
+    /// Config file the commands use
+    pub fn config_file(&self) -> PathBuf {
+        self.home.join("watchlist.config.json")
+    }
+
+    /// Runs `wl` with the arguments and nothing to read from stdin
+    pub fn run(&self, args: &[&str]) -> io::Result<Output> {
+        self.run_with_input(args, "")
+    }
+
+    /// Runs `wl` with the arguments and the input to read from stdin, like the commands of
+    /// `wl batch -`. Questions aren't asked as stdin isn't a terminal
+    pub fn run_with_input(&self, args: &[&str], input: &str) -> io::Result<Output> {
+        let mut child = self
+            .command(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        // Commands which don't ask anything close stdin before it's written to
+        if let Some(mut stdin) = child.stdin.take() {
+            match stdin.write_all(input.as_bytes()) {
+                Err(e) if e.kind() != io::ErrorKind::BrokenPipe => return Err(e),
+                _ => {}
+            }
+        }
+        let output = child.wait_with_output()?;
+        Ok(Output {
+            code: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+
+    /// Runs `wl` with the arguments, only reading the first line of its output before
+    /// closing it, like piping it to `head -1`
+    pub fn run_first_line(&self, args: &[&str]) -> io::Result<Output> {
+        let mut child = self
+            .command(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let mut first = String::new();
+        if let Some(stdout) = child.stdout.take() {
+            io::BufRead::read_line(&mut io::BufReader::new(stdout), &mut first)?;
+        }
+        let output = child.wait_with_output()?;
+        Ok(Output {
+            code: output.status.code(),
+            stdout: first,
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+
+    /// Starts `wl` with the arguments without waiting for it to finish, for commands which run
+    /// until they're stopped like `wl daemon`
+    pub fn spawn(&self, args: &[&str]) -> io::Result<Child> {
+        self.command(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+    }
+
+    fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(&self.binary);
+        for var in CLEARED_VARS {
+            command.env_remove(var);
+        }
+        command
+            .args(args)
+            .current_dir(&self.home)
+            .env("HOME", &self.home)
+            .env("XDG_CONFIG_HOME", self.home.join(".config"))
+            .env("XDG_DATA_HOME", self.home.join(".local/share"))
+            .env("WATCHLIST_FILE_PATH", self.data_file())
+            .env("WATCHLIST_CONFIG_PATH", self.config_file())
+            .env("NO_COLOR", "1")
+            .envs(self.vars.iter().map(|(var, value)| (var, value)));
+        command
+    }
+
+    /// Reads the lists saved in the data file, none if nothing was saved yet
+    pub fn lists(&self) -> Result<WatchList, WatchListError> {
+        match WatchList::from_file(&self.data_file()) {
+            Err(WatchListError::IOError(e)) if e.kind() == io::ErrorKind::NotFound => {
+                Ok(WatchList::new())
+            }
+            lists => lists,
+        }
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.home);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sandboxes() {
+        let missing = "wlist-sandbox-missing-binary";
+        let (a, b) = (
+            Sandbox::new(missing).unwrap(),
+            Sandbox::new(missing).unwrap(),
+        );
+        assert_ne!(a.home(), b.home());
+        assert!(a.data_file().starts_with(a.home()));
+        assert_eq!(a.lists().unwrap(), WatchList::new());
+
+        let home = a.home().to_path_buf();
+        drop(a);
+        assert!(!home.exists());
+        assert!(matches!(
+            b.run(&[]),
+            Err(e) if e.kind() == io::ErrorKind::NotFound
+        ));
+    }
+}
@@ -0,0 +1,431 @@
+//! Color themes for everything printed to the terminal
+//!
+//! Colors are only used when printing to a terminal and `NO_COLOR` isn't set, which
+//! `colored` checks before styling anything
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::ops::Range;
+
+use colored::{Color, ColoredString, Colorize};
+use serde::{Deserialize, Serialize};
+
+thread_local! {
+    static CONTEXT: RefCell<Context> = RefCell::new(Context::default());
+}
+
+/// How everything printed by a run of a command is styled
+///
+/// Each run enters its own with [`enter`], so one run in the daemon or the shell doesn't
+/// leave its theme or `--quiet` behind for the next
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Context {
+    pub theme: Theme,
+    /// Colors used instead of the ones of the theme for some uses of text
+    pub colors: BTreeMap<Style, Paint>,
+    /// Colors and text styles are dropped entirely and output is kept to a single
+    /// column of plain sentences which screen readers can follow
+    pub accessible: bool,
+    /// Only results and errors are printed
+    pub quiet: bool,
+}
+
+/// Puts back the context from before [`enter`] when it's dropped
+#[must_use = "the context is left as soon as this is dropped"]
+pub struct Entered(Option<Context>);
+
+impl Drop for Entered {
+    fn drop(&mut self) {
+        if let Some(context) = self.0.take() {
+            CONTEXT.set(context);
+        }
+    }
+}
+
+/// Set of colors used for output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    #[default]
+    Default,
+    Solarized,
+    /// Only bold, italic and similar styles, no colors
+    Monochrome,
+    HighContrast,
+}
+
+/// What a piece of text is used for, which decides how it is styled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Style {
+    /// Titles of lists
+    Heading,
+    /// Numbers of items in a list
+    Index,
+    /// Extra details shown after an item, like ratings and tags
+    Detail,
+    Added,
+    Removed,
+    Changed,
+    /// Items whose due date has passed
+    Overdue,
+    Warning,
+    Error,
+    /// Parts of titles matching a search
+    Match,
+}
+
+/// Color and text styles used instead of the ones of the theme, written like
+/// "bright cyan bold" or "#268bd2 on black underline"
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Paint {
+    text: String,
+    color: Option<Color>,
+    background: Option<Color>,
+    effects: Vec<Effect>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Effect {
+    Bold,
+    Dimmed,
+    Italic,
+    Underline,
+    Strikethrough,
+    Reversed,
+}
+
+impl Paint {
+    /// Styles the text
+    pub fn apply(&self, text: &str) -> ColoredString {
+        let mut painted = text.normal();
+        if let Some(color) = self.color {
+            painted = painted.color(color);
+        }
+        if let Some(background) = self.background {
+            painted = painted.on_color(background);
+        }
+        for effect in &self.effects {
+            painted = match effect {
+                Effect::Bold => painted.bold(),
+                Effect::Dimmed => painted.dimmed(),
+                Effect::Italic => painted.italic(),
+                Effect::Underline => painted.underline(),
+                Effect::Strikethrough => painted.strikethrough(),
+                Effect::Reversed => painted.reversed(),
+            };
+        }
+        painted
+    }
+}
+
+// Names like "red" and "bright red", or hex codes like "#dc322f"
+fn parse_color(name: &str) -> Option<Color> {
+    match name.strip_prefix('#') {
+        Some(hex) if hex.len() == 6 => {
+            let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+            Some(Color::TrueColor {
+                r: channel(0)?,
+                g: channel(2)?,
+                b: channel(4)?,
+            })
+        }
+        Some(_) => None,
+        None => name.parse().ok(),
+    }
+}
+
+impl TryFrom<String> for Paint {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        let mut paint = Paint {
+            text: text.clone(),
+            color: None,
+            background: None,
+            effects: Vec::new(),
+        };
+        let mut words = text.split_whitespace();
+        while let Some(word) = words.next() {
+            let effect = match word.to_lowercase().as_str() {
+                "bold" => Effect::Bold,
+                "dimmed" | "dim" => Effect::Dimmed,
+                "italic" => Effect::Italic,
+                "underline" => Effect::Underline,
+                "strikethrough" => Effect::Strikethrough,
+                "reversed" => Effect::Reversed,
+                // The color after "on" is the background
+                "on" => {
+                    let color = read_color(words.next(), &mut words).ok_or(format!(
+                        "expected a background color after 'on' in '{text}'"
+                    ))?;
+                    paint.background = Some(color);
+                    continue;
+                }
+                _ => {
+                    let color = read_color(Some(word), &mut words)
+                        .ok_or(format!("unknown color or style '{word}' in '{text}'"))?;
+                    paint.color = Some(color);
+                    continue;
+                }
+            };
+            paint.effects.push(effect);
+        }
+        Ok(paint)
+    }
+}
+
+// Reads a color, along with the next word for the bright colors
+fn read_color<'a>(
+    word: Option<&'a str>,
+    words: &mut impl Iterator<Item = &'a str>,
+) -> Option<Color> {
+    match word? {
+        bright if bright.eq_ignore_ascii_case("bright") => {
+            parse_color(&format!("bright {}", words.next()?))
+        }
+        word => parse_color(word),
+    }
+}
+
+impl From<Paint> for String {
+    fn from(paint: Paint) -> Self {
+        paint.text
+    }
+}
+
+impl Display for Paint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+// Colors of the Solarized palette
+const SOLARIZED_YELLOW: (u8, u8, u8) = (0xb5, 0x89, 0x00);
+const SOLARIZED_ORANGE: (u8, u8, u8) = (0xcb, 0x4b, 0x16);
+const SOLARIZED_RED: (u8, u8, u8) = (0xdc, 0x32, 0x2f);
+const SOLARIZED_BLUE: (u8, u8, u8) = (0x26, 0x8b, 0xd2);
+const SOLARIZED_CYAN: (u8, u8, u8) = (0x2a, 0xa1, 0x98);
+const SOLARIZED_GREEN: (u8, u8, u8) = (0x85, 0x99, 0x00);
+const SOLARIZED_BASE01: (u8, u8, u8) = (0x58, 0x6e, 0x75);
+
+fn solarized(text: &str, (r, g, b): (u8, u8, u8)) -> ColoredString {
+    text.truecolor(r, g, b)
+}
+
+impl Theme {
+    /// Styles text for the given use
+    pub fn paint(self, text: &str, style: Style) -> ColoredString {
+        use Style::*;
+        match (self, style) {
+            (Theme::Default, Heading) => text.italic().underline(),
+            (Theme::Default, Index) => text.bold(),
+            (Theme::Default, Detail) => text.dimmed(),
+            (Theme::Default, Added) => text.green(),
+            (Theme::Default, Removed | Overdue | Error) => text.red(),
+            (Theme::Default, Changed | Warning) => text.yellow(),
+            (Theme::Default, Match) => text.yellow().bold(),
+
+            (Theme::Solarized, Heading) => solarized(text, SOLARIZED_BLUE).bold().underline(),
+            (Theme::Solarized, Index) => solarized(text, SOLARIZED_CYAN),
+            (Theme::Solarized, Detail) => solarized(text, SOLARIZED_BASE01),
+            (Theme::Solarized, Added) => solarized(text, SOLARIZED_GREEN),
+            (Theme::Solarized, Removed | Overdue) => solarized(text, SOLARIZED_RED),
+            (Theme::Solarized, Changed) => solarized(text, SOLARIZED_YELLOW),
+            (Theme::Solarized, Warning) => solarized(text, SOLARIZED_ORANGE),
+            (Theme::Solarized, Error) => solarized(text, SOLARIZED_RED).bold(),
+            (Theme::Solarized, Match) => solarized(text, SOLARIZED_YELLOW).bold(),
+
+            (Theme::Monochrome, Heading) => text.bold().underline(),
+            (Theme::Monochrome, Index | Added | Overdue | Warning | Error) => text.bold(),
+            (Theme::Monochrome, Detail) => text.dimmed(),
+            (Theme::Monochrome, Removed) => text.strikethrough(),
+            (Theme::Monochrome, Changed) => text.italic(),
+            (Theme::Monochrome, Match) => text.bold().underline(),
+
+            (Theme::HighContrast, Heading) => text.bright_white().bold().underline(),
+            (Theme::HighContrast, Index) => text.bright_yellow().bold(),
+            (Theme::HighContrast, Detail) => text.bright_cyan(),
+            (Theme::HighContrast, Added) => text.bright_green().bold(),
+            (Theme::HighContrast, Removed | Overdue) => text.bright_red().bold(),
+            (Theme::HighContrast, Changed) => text.bright_yellow().bold(),
+            (Theme::HighContrast, Warning) => text.black().on_bright_yellow(),
+            (Theme::HighContrast, Error) => text.bright_white().on_red().bold(),
+            (Theme::HighContrast, Match) => text.black().on_bright_cyan(),
+        }
+    }
+}
+
+/// Styles everything printed with the context until what it gives is dropped
+pub fn enter(context: Context) -> Entered {
+    Entered(Some(CONTEXT.replace(context)))
+}
+
+/// Gets the context output is styled with
+pub fn context() -> Context {
+    CONTEXT.with_borrow(Context::clone)
+}
+
+/// Gets the theme in use
+pub fn current() -> Theme {
+    CONTEXT.with_borrow(|c| c.theme)
+}
+
+/// Checks if the accessible output mode is on, see [`Context::accessible`]
+pub fn accessible() -> bool {
+    CONTEXT.with_borrow(|c| c.accessible)
+}
+
+/// Checks if the quiet output mode is on, where only results and errors are printed
+pub fn quiet() -> bool {
+    CONTEXT.with_borrow(|c| c.quiet)
+}
+
+/// Styles text with the current theme, or the colors set for its use
+pub fn paint(text: &str, style: Style) -> ColoredString {
+    CONTEXT.with_borrow(|c| match c.colors.get(&style) {
+        _ if c.accessible => text.normal(),
+        Some(paint) => paint.apply(text),
+        None => c.theme.paint(text, style),
+    })
+}
+
+/// Styles the parts of the text in the byte ranges as matches of a search, see
+/// [`Style::Match`]
+pub fn highlight(text: &str, spans: &[Range<usize>]) -> String {
+    let mut highlighted = String::new();
+    let mut end = 0;
+    for span in spans {
+        let (Some(before), Some(matched)) = (text.get(end..span.start), text.get(span.clone()))
+        else {
+            continue;
+        };
+        highlighted.push_str(before);
+        highlighted.push_str(&paint(matched, Style::Match).to_string());
+        end = span.end;
+    }
+    highlighted.push_str(&text[end..]);
+    highlighted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn painting() {
+        colored::control::set_override(true);
+        assert_eq!(
+            Theme::Default.paint("Movies", Style::Heading).to_string(),
+            "Movies".italic().underline().to_string()
+        );
+        assert_eq!(
+            Theme::Solarized.paint("x", Style::Added).to_string(),
+            "\x1b[38;2;133;153;0mx\x1b[0m"
+        );
+        // Nothing in the monochrome theme has a color
+        for style in [
+            Style::Heading,
+            Style::Index,
+            Style::Detail,
+            Style::Added,
+            Style::Removed,
+            Style::Changed,
+            Style::Overdue,
+            Style::Warning,
+            Style::Error,
+            Style::Match,
+        ] {
+            let painted = Theme::Monochrome.paint("x", style);
+            assert_eq!((painted.fgcolor(), painted.bgcolor()), (None, None));
+        }
+
+        assert_eq!(
+            serde_json::from_str::<Theme>(r#""high-contrast""#).unwrap(),
+            Theme::HighContrast
+        );
+    }
+
+    #[test]
+    fn custom_colors() {
+        colored::control::set_override(true);
+        let colors: BTreeMap<Style, Paint> = serde_json::from_str(
+            r##"{ "index": "bright cyan bold", "heading": "#268bd2 on black underline" }"##,
+        )
+        .unwrap();
+        assert_eq!(
+            colors[&Style::Index].apply("1").to_string(),
+            "1".bright_cyan().bold().to_string()
+        );
+        assert_eq!(
+            colors[&Style::Heading].apply("Movies").to_string(),
+            "Movies"
+                .truecolor(0x26, 0x8b, 0xd2)
+                .on_black()
+                .underline()
+                .to_string()
+        );
+        assert_eq!(
+            serde_json::to_string(&colors[&Style::Index]).unwrap(),
+            r#""bright cyan bold""#
+        );
+
+        for wrong in ["blinking", "bright", "red on", "#12345", "on bold"] {
+            let error = Paint::try_from(wrong.to_string()).unwrap_err();
+            assert!(error.contains(wrong), "{error}");
+        }
+        assert!(serde_json::from_str::<BTreeMap<Style, Paint>>(r#"{ "titles": "red" }"#).is_err());
+    }
+
+    #[test]
+    fn contexts() {
+        colored::control::set_override(true);
+        let solarized = Context {
+            theme: Theme::Solarized,
+            quiet: true,
+            ..Context::default()
+        };
+        {
+            let _solarized = enter(solarized.clone());
+            assert_eq!(context(), solarized);
+            // Entered again with another theme, like the next command run by the daemon
+            let monochrome = enter(Context {
+                theme: Theme::Monochrome,
+                ..Context::default()
+            });
+            assert_eq!((current(), quiet()), (Theme::Monochrome, false));
+            drop(monochrome);
+            assert_eq!(current(), Theme::Solarized);
+        }
+        assert_eq!(context(), Context::default());
+
+        let _accessible = enter(Context {
+            theme: Theme::HighContrast,
+            accessible: true,
+            ..Context::default()
+        });
+        assert_eq!(paint("Movies", Style::Heading).to_string(), "Movies");
+    }
+
+    #[test]
+    fn highlighting() {
+        colored::control::set_override(true);
+        let matched = |text| paint(text, Style::Match).to_string();
+        assert_eq!(
+            highlight("The Dark Knight", &[4..8, 9..15]),
+            format!("The {} {}", matched("Dark"), matched("Knight"))
+        );
+        assert_eq!(
+            highlight("Dark City", &[0..1, 1..2, 5..9]),
+            format!("{}{}rk {}", matched("D"), matched("a"), matched("City"))
+        );
+        // Ranges which overlap or aren't in the text are left out
+        assert_eq!(
+            highlight("Up", &[0..1, 0..2, 1..9]),
+            format!("{}p", matched("U"))
+        );
+        assert_eq!(highlight("Up", &[]), "Up");
+    }
+}
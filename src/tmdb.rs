@@ -0,0 +1,87 @@
+//! Lookup of movie details on TMDB
+
+use serde::Deserialize;
+
+use crate::http;
+use crate::{Item, WatchListError};
+
+const API: &str = "https://api.themoviedb.org/3";
+const POSTER_URL: &str = "https://image.tmdb.org/t/p/w500";
+
+#[derive(Deserialize)]
+struct SearchResults {
+    results: Vec<Movie>,
+}
+
+#[derive(Deserialize)]
+struct Movie {
+    id: u64,
+    title: String,
+    // Empty for movies without a known release date
+    #[serde(default)]
+    release_date: Option<String>,
+    poster_path: Option<String>,
+}
+
+impl Movie {
+    fn item(&self) -> Item {
+        let mut item = Item::new(&self.title)
+            .with_url(format!("https://www.themoviedb.org/movie/{}", self.id))
+            .with_id("tmdb", self.id.to_string());
+        let year = self
+            .release_date
+            .as_deref()
+            .and_then(|d| d.get(..4))
+            .and_then(|y| y.parse().ok());
+        if let Some(year) = year {
+            item = item.with_year(year);
+        }
+        if let Some(poster) = &self.poster_path {
+            item = item.with_poster(format!("{POSTER_URL}{poster}"));
+        }
+        item
+    }
+}
+
+/// Searches TMDB for movies matching `query` with a v3 API key
+///
+/// Matches are returned as items with the canonical title, year, page and poster URL
+pub fn search_movies(query: &str, api_key: &str) -> Result<Vec<Item>, WatchListError> {
+    let results: SearchResults = http::get_json(
+        &format!("{API}/search/movie"),
+        &[("Accept", "application/json")],
+        &[("query", query), ("api_key", api_key)],
+    )?;
+    Ok(results.results.iter().map(Movie::item).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn movie_items() {
+        let results: SearchResults = serde_json::from_str(
+            r#"{ "page": 1, "results": [
+                { "id": 438631, "title": "Dune", "release_date": "2021-09-15", "poster_path": "/d5NXSklXo0qyIYkgV94XAgMIckC.jpg" },
+                { "id": 1, "title": "Dune: The Sequel", "release_date": "", "poster_path": null }
+            ] }"#,
+        )
+        .unwrap();
+        let items: Vec<Item> = results.results.iter().map(Movie::item).collect();
+        assert_eq!(
+            items[0],
+            Item::new("Dune")
+                .with_url("https://www.themoviedb.org/movie/438631")
+                .with_id("tmdb", "438631")
+                .with_year(2021)
+                .with_poster("https://image.tmdb.org/t/p/w500/d5NXSklXo0qyIYkgV94XAgMIckC.jpg")
+        );
+        assert_eq!(
+            items[1],
+            Item::new("Dune: The Sequel")
+                .with_url("https://www.themoviedb.org/movie/1")
+                .with_id("tmdb", "1")
+        );
+    }
+}
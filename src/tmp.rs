@@ -0,0 +1,96 @@
+//! Temporary list for drafting items before adding them to a real list
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::import::{self, ImportReport};
+use crate::{encryption, Item, WatchList, WatchListError};
+
+/// Items kept in a file of their own until they are committed or discarded
+#[derive(Debug, Default)]
+pub struct TmpList {
+    file_path: PathBuf,
+    path: PathBuf,
+    pub items: Vec<Item>,
+}
+
+impl TmpList {
+    /// Reads the temporary list next to the data file, starting empty if there isn't one
+    pub fn from_file(file_path: &Path) -> Result<Self, WatchListError> {
+        let path = crate::sidecar_path(file_path, "tmp");
+        let items = match encryption::read_sidecar(file_path, &path)? {
+            Some(data) => serde_json::from_str(&data)
+                .map_err(|e| WatchListError::ParseError(e.to_string()))?,
+            None => Vec::new(),
+        };
+        Ok(TmpList {
+            file_path: file_path.to_path_buf(),
+            path,
+            items,
+        })
+    }
+
+    /// Saves the items, removing the file once the list is empty
+    pub fn to_file(&self) -> Result<(), WatchListError> {
+        if self.items.is_empty() {
+            return match fs::remove_file(&self.path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(WatchListError::IOError(e)),
+                _ => Ok(()),
+            };
+        }
+        let data =
+            serde_json::to_string(&self.items).map_err(|e| WatchListError::IOError(e.into()))?;
+        encryption::write_sidecar(&self.file_path, &self.path, data.as_bytes())
+    }
+
+    /// Adds an item unless it is already in the list, returning whether it was added
+    pub fn add(&mut self, item: impl Into<Item>) -> bool {
+        let item = item.into();
+        if self.items.iter().any(|i| i.title == item.title) {
+            return false;
+        }
+        self.items.push(item);
+        true
+    }
+
+    /// Adds the items to the list `title` in the watchlist, creating it if needed,
+    /// and empties the temporary list
+    pub fn commit(
+        &mut self,
+        watchlist: &mut WatchList,
+        title: &str,
+    ) -> Result<ImportReport, WatchListError> {
+        let report = import::import_items(watchlist, title, self.items.iter().cloned())?;
+        self.items.clear();
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drafting() {
+        let path = std::env::temp_dir().join(format!("wlist_tmp_{}.json", std::process::id()));
+        let mut tmp = TmpList::from_file(&path).unwrap();
+        assert!(tmp.add("Dune"));
+        assert!(tmp.add("Alien"));
+        assert!(!tmp.add("Dune"));
+        tmp.to_file().unwrap();
+
+        let mut tmp = TmpList::from_file(&path).unwrap();
+        assert_eq!(tmp.items, vec!["Dune", "Alien"]);
+
+        let mut watchlist = WatchList::new();
+        watchlist.insert("Movies".to_string(), vec![Item::new("Alien")]);
+        let report = tmp.commit(&mut watchlist, "Movies").unwrap();
+        assert_eq!((report.added, report.skipped), (1, 1));
+        assert_eq!(watchlist["Movies"], vec!["Alien", "Dune"]);
+
+        // Committing empties the list and removes its file
+        tmp.to_file().unwrap();
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,300 @@
+//! Two-way sync of a list with a Trakt watchlist
+
+use std::thread;
+use std::time::Duration;
+
+use chrono::TimeDelta;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::dates;
+use crate::http::{self, network_error};
+use crate::sync::{self, ConflictStrategy, SyncReport, Token, TokenStore};
+use crate::{Item, WatchList, WatchListError};
+
+const API: &str = "https://api.trakt.tv";
+/// Name the token is stored under
+pub const SERVICE: &str = "trakt";
+/// Services whose IDs Trakt knows, in the order items are looked up by them
+const ID_SERVICES: [&str; 4] = ["trakt", "imdb", "tmdb", "tvdb"];
+
+#[derive(Deserialize)]
+struct DeviceCode {
+    device_code: String,
+    user_code: String,
+    verification_url: String,
+    expires_in: u64,
+    interval: u64,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: String,
+    expires_in: i64,
+}
+
+impl From<TokenResponse> for Token {
+    fn from(response: TokenResponse) -> Self {
+        Token {
+            access_token: response.access_token,
+            refresh_token: Some(response.refresh_token),
+            expires_at: Some(dates::now() + TimeDelta::seconds(response.expires_in)),
+        }
+    }
+}
+
+// A movie or show on the watchlist or in search results
+#[derive(Deserialize)]
+struct Entry {
+    #[serde(rename = "type")]
+    kind: String,
+    movie: Option<Media>,
+    show: Option<Media>,
+}
+
+#[derive(Deserialize)]
+struct Media {
+    title: String,
+    year: Option<u16>,
+    ids: Value,
+}
+
+impl Entry {
+    fn media(&self) -> Option<&Media> {
+        self.movie.as_ref().or(self.show.as_ref())
+    }
+
+    fn item(&self) -> Option<Item> {
+        let media = self.media()?;
+        let mut item = Item::new(&media.title);
+        if let Some(year) = media.year {
+            item = item.with_year(year);
+        }
+        if let Some(slug) = media.ids.get("slug").and_then(Value::as_str) {
+            item = item.with_url(format!("https://trakt.tv/{}s/{slug}", self.kind));
+        }
+        for service in ID_SERVICES {
+            match &media.ids[service] {
+                Value::String(id) => item = item.with_id(service, id),
+                Value::Number(id) => item = item.with_id(service, id.to_string()),
+                _ => {}
+            }
+        }
+        Some(item)
+    }
+}
+
+/// Client for the Trakt API using the credentials of a Trakt app
+pub struct Trakt {
+    client_id: String,
+    client_secret: String,
+    token: Option<Token>,
+}
+
+impl Trakt {
+    pub fn new(client_id: &str, client_secret: &str, tokens: &TokenStore) -> Self {
+        Trakt {
+            client_id: client_id.to_string(),
+            client_secret: client_secret.to_string(),
+            token: tokens.get(SERVICE).cloned(),
+        }
+    }
+
+    fn headers(&self) -> Vec<(&str, String)> {
+        let mut headers = vec![
+            ("trakt-api-version", "2".to_string()),
+            ("trakt-api-key", self.client_id.clone()),
+        ];
+        if let Some(token) = &self.token {
+            headers.push(("Authorization", format!("Bearer {}", token.access_token)));
+        }
+        headers
+    }
+
+    fn get<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<T, WatchListError> {
+        let headers = self.headers();
+        let headers: Vec<(&str, &str)> = headers.iter().map(|(k, v)| (*k, v.as_str())).collect();
+        http::get_json(&format!("{API}{path}"), &headers, query)
+    }
+
+    fn post(&self, path: &str, body: &Value) -> Result<Value, ureq::Error> {
+        let headers = self.headers();
+        let headers: Vec<(&str, &str)> = headers.iter().map(|(k, v)| (*k, v.as_str())).collect();
+        http::post_json(&format!("{API}{path}"), &headers, body)
+    }
+
+    /// Makes sure there is a valid access token, refreshing an expired one
+    /// or going through the device authorization flow if there is none
+    ///
+    /// `show_code` is called with the verification URL and the code the user has to enter there
+    pub fn authorize(
+        &mut self,
+        tokens: &mut TokenStore,
+        show_code: impl Fn(&str, &str),
+    ) -> Result<(), WatchListError> {
+        let token = match &self.token {
+            Some(token) if !token.is_expired() => return Ok(()),
+            Some(Token {
+                refresh_token: Some(refresh),
+                ..
+            }) => self.refresh(refresh)?,
+            _ => self.device_flow(show_code)?,
+        };
+        tokens.set(SERVICE, token.clone())?;
+        self.token = Some(token);
+        Ok(())
+    }
+
+    fn refresh(&self, refresh_token: &str) -> Result<Token, WatchListError> {
+        let body = json!({
+            "refresh_token": refresh_token,
+            "client_id": self.client_id,
+            "client_secret": self.client_secret,
+            "redirect_uri": "urn:ietf:wg:oauth:2.0:oob",
+            "grant_type": "refresh_token",
+        });
+        let response: TokenResponse =
+            http::post_json(&format!("{API}/oauth/token"), &[], &body).map_err(network_error)?;
+        Ok(response.into())
+    }
+
+    fn device_flow(&self, show_code: impl Fn(&str, &str)) -> Result<Token, WatchListError> {
+        let code: DeviceCode = http::post_json(
+            &format!("{API}/oauth/device/code"),
+            &[],
+            &json!({ "client_id": self.client_id }),
+        )
+        .map_err(network_error)?;
+        show_code(&code.verification_url, &code.user_code);
+
+        let body = json!({
+            "code": code.device_code,
+            "client_id": self.client_id,
+            "client_secret": self.client_secret,
+        });
+        let mut interval = code.interval;
+        let mut waited = 0;
+        while waited < code.expires_in {
+            thread::sleep(Duration::from_secs(interval));
+            waited += interval;
+
+            match http::post_json::<TokenResponse>(&format!("{API}/oauth/device/token"), &[], &body)
+            {
+                Ok(response) => return Ok(response.into()),
+                // Waiting for the user to enter the code
+                Err(ureq::Error::StatusCode(400)) => {}
+                // Polling too quickly
+                Err(ureq::Error::StatusCode(429)) => interval += 1,
+                Err(ureq::Error::StatusCode(418)) => {
+                    return Err(WatchListError::NetworkError(
+                        "authorization was denied".to_string(),
+                    ))
+                }
+                Err(e) => return Err(network_error(e)),
+            }
+        }
+        Err(WatchListError::NetworkError(
+            "authorization code expired".to_string(),
+        ))
+    }
+
+    // Seasons and episodes on the watchlist are left out
+    fn watchlist(&self) -> Result<Vec<Entry>, WatchListError> {
+        let entries: Vec<Entry> = self.get("/sync/watchlist", &[])?;
+        Ok(entries
+            .into_iter()
+            .filter(|e| matches!(e.kind.as_str(), "movie" | "show") && e.media().is_some())
+            .collect())
+    }
+
+    // Trakt needs IDs to add items, so local items are looked up by any IDs they have,
+    // or else by title
+    fn search(&self, item: &Item) -> Result<Option<Entry>, WatchListError> {
+        for service in ID_SERVICES {
+            if let Some(id) = item.id(service) {
+                let results: Vec<Entry> = self.get(&format!("/search/{service}/{id}"), &[])?;
+                let found = results
+                    .into_iter()
+                    .find(|e| matches!(e.kind.as_str(), "movie" | "show") && e.media().is_some());
+                if let Some(entry) = found {
+                    return Ok(Some(entry));
+                }
+            }
+        }
+        let year = item.meta.year.map(|y| y.to_string());
+        let mut query = vec![("query", item.title.as_str()), ("limit", "1")];
+        if let Some(year) = &year {
+            query.push(("years", year));
+        }
+        let results: Vec<Entry> = self.get("/search/movie,show", &query)?;
+        Ok(results.into_iter().next())
+    }
+
+    // Sends movies and shows to one of the watchlist endpoints
+    fn send(&self, path: &str, entries: &[&Entry]) -> Result<(), WatchListError> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let ids = |kind: &str| -> Vec<Value> {
+            entries
+                .iter()
+                .filter(|e| e.kind == kind)
+                .filter_map(|e| e.media())
+                .map(|m| json!({ "ids": m.ids }))
+                .collect()
+        };
+        let body = json!({ "movies": ids("movie"), "shows": ids("show") });
+        self.post(path, &body).map_err(network_error)?;
+        Ok(())
+    }
+
+    /// Syncs a local list with the Trakt watchlist, creating the list if it doesn't exist
+    ///
+    /// Items which can't be found on Trakt are not pushed
+    pub fn sync(
+        &self,
+        watchlist: &mut WatchList,
+        title: &str,
+        strategy: ConflictStrategy,
+    ) -> Result<SyncReport, WatchListError> {
+        if !watchlist.contains(title) {
+            watchlist.list_add(title)?;
+        }
+
+        let remote_entries = self.watchlist()?;
+        // Entries were filtered to the ones with media so each has an item
+        let remote: Vec<Item> = remote_entries.iter().filter_map(Entry::item).collect();
+        let local = watchlist[title].clone();
+        let plan = sync::plan(&local, &remote, strategy);
+
+        let mut to_push = Vec::new();
+        for item in &plan.push {
+            if let Some(entry) = self.search(item)? {
+                to_push.push(entry);
+            }
+        }
+        self.send("/sync/watchlist", &to_push.iter().collect::<Vec<_>>())?;
+
+        let to_remove: Vec<&Entry> = remote_entries
+            .iter()
+            .zip(&remote)
+            .filter(|(_, item)| plan.remove_remote.contains(item))
+            .map(|(entry, _)| entry)
+            .collect();
+        self.send("/sync/watchlist/remove", &to_remove)?;
+
+        sync::apply_local(watchlist, title, &plan)?;
+
+        Ok(SyncReport {
+            pulled: plan.pull.len(),
+            pushed: to_push.len(),
+            removed_local: plan.remove_local.len(),
+            removed_remote: to_remove.len(),
+        })
+    }
+}
@@ -0,0 +1,268 @@
+//! Deleted lists and items kept around so they can be restored
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::TimeDelta;
+use serde::{Deserialize, Serialize};
+
+use crate::dates::Timestamp;
+use crate::diff::{self, Difference};
+use crate::{encryption, DuplicatePolicy, Item, ListSettings, WatchList, WatchListError};
+
+/// Something which was deleted
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Trashed {
+    List {
+        title: String,
+        items: Vec<Item>,
+        #[serde(default, skip_serializing_if = "ListSettings::is_default")]
+        settings: ListSettings,
+    },
+    Item {
+        list: String,
+        item: Item,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrashEntry {
+    pub deleted_at: Timestamp,
+    #[serde(flatten)]
+    pub trashed: Trashed,
+}
+
+/// Deleted lists and items saved to a file, oldest first
+#[derive(Debug, Default)]
+pub struct Trash {
+    file_path: PathBuf,
+    path: PathBuf,
+    pub entries: Vec<TrashEntry>,
+}
+
+impl Trash {
+    /// Reads the trash next to the data file, starting with an empty one if there isn't any
+    pub fn from_file(file_path: &Path) -> Result<Self, WatchListError> {
+        let path = crate::sidecar_path(file_path, "trash");
+        let entries = match encryption::read_sidecar(file_path, &path)? {
+            Some(data) => serde_json::from_str(&data)
+                .map_err(|e| WatchListError::ParseError(e.to_string()))?,
+            None => Vec::new(),
+        };
+        Ok(Trash {
+            file_path: file_path.to_path_buf(),
+            path,
+            entries,
+        })
+    }
+
+    /// Saves the trash, removing the file once it is empty
+    pub fn to_file(&self) -> Result<(), WatchListError> {
+        if self.entries.is_empty() {
+            return match fs::remove_file(&self.path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(WatchListError::IOError(e)),
+                _ => Ok(()),
+            };
+        }
+        let data =
+            serde_json::to_string(&self.entries).map_err(|e| WatchListError::IOError(e.into()))?;
+        encryption::write_sidecar(&self.file_path, &self.path, data.as_bytes())
+    }
+
+    pub fn add(&mut self, trashed: Trashed, deleted_at: Timestamp) {
+        self.entries.push(TrashEntry {
+            deleted_at,
+            trashed,
+        });
+    }
+
+    /// Puts the entry at `index` back into the watchlist and removes it from the trash
+    ///
+    /// Restored items are added back to their list, recreating it if it was deleted since.
+    /// Nothing is restored if a list or item with the same title is already present
+    pub fn restore(
+        &mut self,
+        index: usize,
+        watchlist: &mut WatchList,
+    ) -> Result<TrashEntry, WatchListError> {
+        let entry = self
+            .entries
+            .get(index)
+            .ok_or(WatchListError::ItemToRemoveNotPresent(format!(
+                "Entry {}",
+                index + 1
+            )))?;
+        match &entry.trashed {
+            Trashed::List {
+                title,
+                items,
+                settings,
+            } => {
+                watchlist.list_add(title)?;
+                watchlist.insert(title.clone(), items.clone());
+                // Aliases given to other lists since it was deleted stay with them
+                let mut settings = settings.clone();
+                settings
+                    .aliases
+                    .retain(|a| watchlist.list_for_alias(a).is_none() && !watchlist.contains(a));
+                *watchlist.list_settings_mut(title)? = settings;
+            }
+            Trashed::Item { list, item } => {
+                if !watchlist.contains(list) {
+                    watchlist.list_add(list)?;
+                }
+                watchlist.item_add(list, item.clone(), DuplicatePolicy::Exact)?;
+            }
+        }
+        Ok(self.entries.remove(index))
+    }
+
+    /// Removes the newest entry for each list and item which is in `after` but not in
+    /// `before`, like the ones brought back by undoing the command which deleted them, so
+    /// they can't be restored a second time. Returns how many entries were removed
+    pub fn remove_returned(&mut self, before: &WatchList, after: &WatchList) -> usize {
+        let mut count = 0;
+        for difference in diff::diff(before, after) {
+            let position = self
+                .entries
+                .iter()
+                .rposition(|e| match (&e.trashed, &difference) {
+                    (Trashed::List { title, .. }, Difference::ListAdded { list, .. }) => {
+                        title == list
+                    }
+                    (
+                        Trashed::Item { list, item },
+                        Difference::ItemAdded {
+                            list: to,
+                            item: title,
+                        },
+                    ) => list == to && item.title == *title,
+                    _ => false,
+                });
+            if let Some(position) = position {
+                self.entries.remove(position);
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Removes entries deleted more than `max_age` before `now`, returning how many there were
+    pub fn purge(&mut self, max_age: TimeDelta, now: Timestamp) -> usize {
+        let count = self.entries.len();
+        self.entries.retain(|e| now - e.deleted_at <= max_age);
+        count - self.entries.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SortBy;
+
+    fn timestamp(s: &str) -> Timestamp {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn restoring() {
+        let mut watchlist = WatchList::new();
+        watchlist.insert("Movies".to_string(), vec![Item::new("Dune")]);
+        watchlist.list_add_alias("Movies", "m").unwrap();
+        let mut trash = Trash::default();
+        let now = timestamp("2023-03-01T12:00:00Z");
+        trash.add(
+            Trashed::List {
+                title: "TV".to_string(),
+                items: vec![Item::new("Lost")],
+                settings: ListSettings {
+                    no_random: true,
+                    sort: SortBy::Alpha,
+                    aliases: vec!["t".to_string(), "m".to_string()],
+                },
+            },
+            now,
+        );
+        for (list, item) in [("Movies", "Alien"), ("Movies", "Dune"), ("Anime", "Akira")] {
+            let trashed = Trashed::Item {
+                list: list.to_string(),
+                item: Item::new(item),
+            };
+            trash.add(trashed, now);
+        }
+
+        trash.restore(0, &mut watchlist).unwrap();
+        assert_eq!(watchlist["TV"], vec!["Lost"]);
+        assert!(watchlist.excluded_from_random("TV"));
+        assert_eq!(watchlist.list_sort("TV"), SortBy::Alpha);
+        assert_eq!(watchlist.list_for_alias("t").unwrap(), "TV");
+        assert_eq!(watchlist.list_for_alias("m").unwrap(), "Movies");
+        trash.restore(0, &mut watchlist).unwrap();
+        assert_eq!(watchlist["Movies"], vec!["Dune", "Alien"]);
+
+        // Deleted lists are recreated for their items
+        trash.restore(1, &mut watchlist).unwrap();
+        assert_eq!(watchlist["Anime"], vec!["Akira"]);
+
+        assert_eq!(
+            trash.restore(0, &mut watchlist).err().unwrap(),
+            WatchListError::ItemAlreadyPresent("Dune".to_string(), "Movies".to_string())
+        );
+        assert_eq!(trash.entries.len(), 1);
+        assert_eq!(
+            trash.restore(5, &mut watchlist).err().unwrap(),
+            WatchListError::ItemToRemoveNotPresent("Entry 6".to_string())
+        );
+    }
+
+    #[test]
+    fn returning() {
+        let mut before = WatchList::new();
+        before.insert("Movies".to_string(), vec![Item::new("Alien")]);
+        let mut trash = Trash::default();
+        let now = timestamp("2023-03-01T12:00:00Z");
+        for (list, item) in [("Movies", "Alien"), ("Movies", "Heat"), ("Movies", "Alien")] {
+            let trashed = Trashed::Item {
+                list: list.to_string(),
+                item: Item::new(item),
+            };
+            trash.add(trashed, now);
+        }
+        let trashed = Trashed::List {
+            title: "TV".to_string(),
+            items: vec![Item::new("Lost")],
+            settings: ListSettings::default(),
+        };
+        trash.add(trashed, now);
+
+        // Undoing deleting the list and the second Alien brings them back
+        let mut after = before.clone();
+        after.insert("TV".to_string(), vec![Item::new("Lost")]);
+        after.get_mut("Movies").unwrap().push(Item::new("Alien"));
+        assert_eq!(trash.remove_returned(&before, &after), 2);
+        assert_eq!(trash.entries.len(), 2);
+        assert!(matches!(
+            &trash.entries[1].trashed,
+            Trashed::Item { item, .. } if item.title == "Heat"
+        ));
+        assert_eq!(trash.remove_returned(&before, &before), 0);
+    }
+
+    #[test]
+    fn purging() {
+        let mut trash = Trash::default();
+        for day in ["2023-01-01", "2023-02-20", "2023-02-28"] {
+            let trashed = Trashed::Item {
+                list: "Movies".to_string(),
+                item: Item::new(day),
+            };
+            trash.add(trashed, timestamp(&format!("{day}T00:00:00Z")));
+        }
+        let now = timestamp("2023-03-01T00:00:00Z");
+        assert_eq!(trash.purge(TimeDelta::days(30), now), 1);
+        assert_eq!(trash.purge(TimeDelta::days(5), now), 1);
+        assert_eq!(trash.entries.len(), 1);
+    }
+}
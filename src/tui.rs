@@ -0,0 +1,558 @@
+//! Interactive terminal interface with the lists on the left and their items on the right
+
+use std::io;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Modifier, Style as TuiStyle};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Clear, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::trash::Trashed;
+use crate::{dates, DuplicatePolicy, Item, WatchList, WatchListError};
+
+const HELP: &str = "a add  d delete  r rename  w watched  / search  p random  tab switch  q quit";
+
+/// Side of the screen which the keys act on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pane {
+    Lists,
+    Items,
+}
+
+/// What the text being typed is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Prompt {
+    AddList,
+    AddItem,
+    Rename,
+    Search,
+}
+
+impl Prompt {
+    fn title(self) -> &'static str {
+        match self {
+            Prompt::AddList => "New List",
+            Prompt::AddItem => "New Item",
+            Prompt::Rename => "Rename",
+            Prompt::Search => "Search",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Mode {
+    Normal,
+    Input {
+        prompt: Prompt,
+        text: String,
+    },
+    /// Item picked at random, shown until any key is pressed
+    Picked(String),
+}
+
+/// State of the interface, kept apart from the terminal so it can be driven by key presses alone
+pub struct App<'a> {
+    watchlist: &'a mut WatchList,
+    lists: Vec<String>,
+    list_index: usize,
+    item_index: usize,
+    pane: Pane,
+    mode: Mode,
+    /// Only items containing this are shown
+    filter: String,
+    status: String,
+    /// Everything deleted, to be moved to the trash
+    pub trashed: Vec<Trashed>,
+    pub quit: bool,
+}
+
+impl<'a> App<'a> {
+    /// Creates the interface for a watchlist
+    pub fn new(watchlist: &'a mut WatchList) -> Self {
+        let mut app = App {
+            watchlist,
+            lists: Vec::new(),
+            list_index: 0,
+            item_index: 0,
+            pane: Pane::Lists,
+            mode: Mode::Normal,
+            filter: String::new(),
+            status: HELP.to_string(),
+            trashed: Vec::new(),
+            quit: false,
+        };
+        app.refresh_lists();
+        app
+    }
+
+    // Lists are kept sorted since the watchlist itself has no order
+    fn refresh_lists(&mut self) {
+        self.lists = self.watchlist.titles().cloned().collect();
+        self.lists.sort_by_key(|l| l.to_lowercase());
+        self.list_index = self.list_index.min(self.lists.len().saturating_sub(1));
+    }
+
+    fn current_list(&self) -> Option<&String> {
+        self.lists.get(self.list_index)
+    }
+
+    /// Items of the selected list which match the search
+    fn visible_items(&self) -> Vec<&Item> {
+        match self.current_list() {
+            Some(list) => self
+                .watchlist
+                .list_search(list, &self.filter)
+                .unwrap_or_default(),
+            None => Vec::new(),
+        }
+    }
+
+    fn current_item(&self) -> Option<String> {
+        self.visible_items()
+            .get(self.item_index)
+            .map(|i| i.title.clone())
+    }
+
+    fn report(&mut self, result: Result<String, WatchListError>) {
+        self.status = match result {
+            Ok(message) => message,
+            Err(e) => e.to_string(),
+        };
+    }
+
+    fn select_list(&mut self, title: &str) {
+        self.refresh_lists();
+        if let Some(index) = self.lists.iter().position(|l| l == title) {
+            self.list_index = index;
+        }
+    }
+
+    fn select_item(&mut self, title: &str) {
+        if let Some(index) = self.visible_items().iter().position(|i| i.title == title) {
+            self.item_index = index;
+        }
+    }
+
+    fn move_selection(&mut self, down: bool) {
+        let (index, len) = match self.pane {
+            Pane::Lists => (&mut self.list_index, self.lists.len()),
+            Pane::Items => {
+                let len = self.visible_items().len();
+                (&mut self.item_index, len)
+            }
+        };
+        *index = match down {
+            true => (*index + 1).min(len.saturating_sub(1)),
+            false => index.saturating_sub(1),
+        };
+        if self.pane == Pane::Lists {
+            self.item_index = 0;
+            self.filter.clear();
+        }
+    }
+
+    /// Handles a single key press
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        match std::mem::replace(&mut self.mode, Mode::Normal) {
+            Mode::Normal => self.handle_normal_key(key.code),
+            Mode::Picked(_) => {}
+            Mode::Input { prompt, mut text } => match key.code {
+                KeyCode::Enter => self.submit(prompt, text.trim()),
+                KeyCode::Esc => {
+                    if prompt == Prompt::Search {
+                        self.filter.clear();
+                    }
+                    self.status = HELP.to_string();
+                }
+                code => {
+                    match code {
+                        KeyCode::Backspace => {
+                            text.pop();
+                        }
+                        KeyCode::Char(c) => text.push(c),
+                        _ => {}
+                    }
+                    // Items are filtered while the search is typed
+                    if prompt == Prompt::Search {
+                        self.filter = text.clone();
+                        self.item_index = 0;
+                    }
+                    self.mode = Mode::Input { prompt, text };
+                }
+            },
+        }
+    }
+
+    fn handle_normal_key(&mut self, code: KeyCode) {
+        let input = |prompt| Mode::Input {
+            prompt,
+            text: String::new(),
+        };
+        match code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Esc if !self.filter.is_empty() => {
+                self.filter.clear();
+                self.item_index = 0;
+            }
+            KeyCode::Esc => self.quit = true,
+            KeyCode::Tab | KeyCode::Left | KeyCode::Right | KeyCode::Char('h' | 'l') => {
+                self.pane = match self.pane {
+                    Pane::Lists if self.current_list().is_some() => Pane::Items,
+                    _ => Pane::Lists,
+                };
+            }
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(true),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(false),
+            KeyCode::Char('a') => {
+                self.mode = match self.pane {
+                    Pane::Lists => input(Prompt::AddList),
+                    Pane::Items => input(Prompt::AddItem),
+                }
+            }
+            KeyCode::Char('r') => {
+                let selected = match self.pane {
+                    Pane::Lists => self.current_list().cloned(),
+                    Pane::Items => self.current_item(),
+                };
+                if let Some(text) = selected {
+                    self.mode = Mode::Input {
+                        prompt: Prompt::Rename,
+                        text,
+                    };
+                }
+            }
+            KeyCode::Char('/') if self.current_list().is_some() => {
+                self.pane = Pane::Items;
+                self.mode = Mode::Input {
+                    prompt: Prompt::Search,
+                    text: self.filter.clone(),
+                };
+            }
+            KeyCode::Char('d') => self.delete(),
+            KeyCode::Char('w') => self.toggle_watched(),
+            KeyCode::Char('p') => self.pick_random(),
+            _ => {}
+        }
+    }
+
+    fn submit(&mut self, prompt: Prompt, text: &str) {
+        if text.is_empty() && prompt != Prompt::Search {
+            self.status = HELP.to_string();
+            return;
+        }
+        let list = self.current_list().cloned().unwrap_or_default();
+        let result = match prompt {
+            Prompt::AddList => self.watchlist.list_add(text).map(|_| {
+                self.select_list(text);
+                format!("Created {text}")
+            }),
+            Prompt::AddItem => self
+                .watchlist
+                .item_add(
+                    &list,
+                    Item::new(text).with_added_at(dates::now()),
+                    DuplicatePolicy::Exact,
+                )
+                .map(|_| {
+                    self.filter.clear();
+                    self.select_item(text);
+                    format!("Added {text} to {list}")
+                }),
+            Prompt::Rename if self.pane == Pane::Lists => {
+                self.watchlist.list_rename(&list, text).map(|_| {
+                    self.select_list(text);
+                    format!("Renamed {list} to {text}")
+                })
+            }
+            Prompt::Rename => {
+                let item = self.current_item().unwrap_or_default();
+                self.watchlist.item_rename(&list, &item, text).map(|_| {
+                    self.select_item(text);
+                    format!("Renamed {item} to {text}")
+                })
+            }
+            Prompt::Search => Ok(match text.is_empty() {
+                true => HELP.to_string(),
+                false => format!("Showing items matching '{text}', Esc to show all"),
+            }),
+        };
+        self.report(result);
+    }
+
+    fn delete(&mut self) {
+        let Some(list) = self.current_list().cloned() else {
+            return;
+        };
+        let result = match self.pane {
+            Pane::Lists => {
+                let items = self.watchlist[&list].clone();
+                let settings = self.watchlist.list_settings(&list).cloned();
+                self.watchlist.list_remove(&list).map(|_| {
+                    self.trashed.push(Trashed::List {
+                        title: list.clone(),
+                        items,
+                        settings: settings.unwrap_or_default(),
+                    });
+                    self.refresh_lists();
+                    format!("Deleted {list}")
+                })
+            }
+            Pane::Items => {
+                let Some(title) = self.current_item() else {
+                    return;
+                };
+                let item = self.watchlist[&list]
+                    .iter()
+                    .find(|i| i.title == title)
+                    .cloned();
+                self.watchlist.item_remove(&list, &title).map(|_| {
+                    if let Some(item) = item {
+                        self.trashed.push(Trashed::Item {
+                            list: list.clone(),
+                            item,
+                        });
+                    }
+                    self.item_index = self
+                        .item_index
+                        .min(self.visible_items().len().saturating_sub(1));
+                    format!("Deleted {title} from {list}")
+                })
+            }
+        };
+        self.report(result);
+    }
+
+    fn toggle_watched(&mut self) {
+        let (Some(list), Some(title)) = (self.current_list().cloned(), self.current_item()) else {
+            return;
+        };
+        if self.pane != Pane::Items {
+            return;
+        }
+        if let Some(item) = self
+            .watchlist
+            .get_mut(&list)
+            .and_then(|items| items.iter_mut().find(|i| i.title == title))
+        {
+            item.meta.watched = !item.meta.watched;
+            self.status = match item.meta.watched {
+                true => format!("Marked {title} as watched"),
+                false => format!("Marked {title} as not watched"),
+            };
+        }
+    }
+
+    fn pick_random(&mut self) {
+        let Some(list) = self.current_list().cloned() else {
+            return;
+        };
+        match self.watchlist.item_get_random(&list) {
+            Ok(item) => self.mode = Mode::Picked(item.title.clone()),
+            Err(e) => self.report(Err(e)),
+        }
+    }
+
+    /// Draws the interface onto a frame
+    pub fn draw(&self, frame: &mut Frame) {
+        let selected = TuiStyle::default().add_modifier(Modifier::REVERSED);
+        let [main, status] =
+            Layout::vertical([Constraint::Min(3), Constraint::Length(1)]).areas(frame.area());
+        let [lists_area, items_area] =
+            Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)])
+                .areas(main);
+
+        let pane_block = |title: String, pane| {
+            let block = Block::bordered().title(title);
+            match self.pane == pane {
+                true => block.border_style(TuiStyle::default().add_modifier(Modifier::BOLD)),
+                false => block,
+            }
+        };
+
+        let lists: Vec<ListItem> = self
+            .lists
+            .iter()
+            .map(|l| ListItem::new(l.as_str()))
+            .collect();
+        let mut state = ListState::default().with_selected(Some(self.list_index));
+        frame.render_stateful_widget(
+            List::new(lists)
+                .block(pane_block("Lists".to_string(), Pane::Lists))
+                .highlight_style(selected),
+            lists_area,
+            &mut state,
+        );
+
+        let items: Vec<ListItem> = self
+            .visible_items()
+            .iter()
+            .map(|i| match i.meta.watched {
+                true => ListItem::new(format!("{} (watched)", i.title))
+                    .style(TuiStyle::default().add_modifier(Modifier::DIM)),
+                false => ListItem::new(i.title.as_str()),
+            })
+            .collect();
+        let title = match (self.current_list(), self.filter.is_empty()) {
+            (Some(list), true) => list.clone(),
+            (Some(list), false) => format!("{list} matching '{}'", self.filter),
+            (None, _) => "Items".to_string(),
+        };
+        let mut state = ListState::default();
+        if self.pane == Pane::Items {
+            state.select(Some(self.item_index));
+        }
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(pane_block(title, Pane::Items))
+                .highlight_style(selected),
+            items_area,
+            &mut state,
+        );
+
+        match &self.mode {
+            Mode::Normal => frame.render_widget(Paragraph::new(self.status.as_str()), status),
+            Mode::Input { prompt, text } => {
+                let line = Line::from(format!("{}: {text}", prompt.title()));
+                let cursor = (status.x + line.width() as u16, status.y);
+                frame.render_widget(Paragraph::new(line), status);
+                frame.set_cursor_position(cursor);
+            }
+            Mode::Picked(item) => {
+                let area = popup_area(main, item.chars().count() as u16 + 4);
+                frame.render_widget(Clear, area);
+                frame.render_widget(
+                    Paragraph::new(item.as_str()).block(Block::bordered().title("Random Pick")),
+                    area,
+                );
+            }
+        }
+    }
+}
+
+// Centered box just big enough for one line of text
+fn popup_area(area: Rect, width: u16) -> Rect {
+    let width = width.max(15).min(area.width);
+    let height = 3.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+/// Runs the interface until it is quit, returning everything which was deleted
+pub fn run(watchlist: &mut WatchList) -> Result<Vec<Trashed>, WatchListError> {
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, App::new(watchlist));
+    ratatui::restore();
+    Ok(result?)
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, mut app: App) -> io::Result<Vec<Trashed>> {
+    while !app.quit {
+        terminal.draw(|frame| app.draw(frame))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press {
+                app.handle_key(key);
+            }
+        }
+    }
+    Ok(app.trashed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    fn setup() -> WatchList {
+        let mut watchlist = WatchList::new();
+        watchlist.insert(
+            "Movies".to_string(),
+            vec!["Dune".into(), "Alien".into(), "Aliens".into()],
+        );
+        watchlist.insert("Anime".to_string(), vec!["Akira".into()]);
+        watchlist
+    }
+
+    fn press(app: &mut App, keys: &str) {
+        for c in keys.chars() {
+            let code = match c {
+                '\n' => KeyCode::Enter,
+                '\t' => KeyCode::Tab,
+                '\x1b' => KeyCode::Esc,
+                '\x08' => KeyCode::Backspace,
+                c => KeyCode::Char(c),
+            };
+            app.handle_key(KeyEvent::from(code));
+        }
+    }
+
+    #[test]
+    fn editing() {
+        let mut watchlist = setup();
+        let mut app = App::new(&mut watchlist);
+        assert_eq!(app.lists, vec!["Anime", "Movies"]);
+
+        // Add an item to the second list and rename it
+        press(&mut app, "j\taThe Thing\n");
+        assert_eq!(app.status, "Added The Thing to Movies");
+        press(&mut app, "r\x08\x08\x08\x08\x08Fly\n");
+        assert_eq!(app.current_item().unwrap(), "The Fly");
+
+        // Search for an item, mark it as watched and delete another
+        press(&mut app, "/ali\nw");
+        assert_eq!(app.visible_items(), vec!["Alien", "Aliens"]);
+        press(&mut app, "jd\x1b");
+        assert_eq!(app.visible_items().len(), 3);
+
+        // Lists can't be renamed to one which already exists
+        press(&mut app, "\tr\x08\x08\x08\x08\x08\x08Anime\n");
+        assert_eq!(
+            app.status,
+            WatchListError::TitleAlreadyPresent("Anime".to_string()).to_string()
+        );
+        press(&mut app, "kd\x1b");
+        assert!(app.quit);
+
+        let trashed = app.trashed;
+        assert_eq!(trashed.len(), 2);
+        assert_eq!(
+            trashed[1],
+            Trashed::List {
+                title: "Anime".to_string(),
+                items: vec!["Akira".into()],
+                settings: crate::ListSettings::default(),
+            }
+        );
+        assert_eq!(watchlist.len(), 1);
+        assert_eq!(watchlist["Movies"], vec!["Dune", "Alien", "The Fly"]);
+        assert!(watchlist["Movies"][1].meta.watched);
+    }
+
+    #[test]
+    fn drawing() {
+        let mut watchlist = setup();
+        let mut app = App::new(&mut watchlist);
+        press(&mut app, "jp");
+        let mut terminal = Terminal::new(TestBackend::new(60, 10)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|c| c.symbol())
+            .collect();
+        assert!(screen.contains("Lists"));
+        assert!(screen.contains("Random Pick"));
+
+        // Any key closes the random pick
+        press(&mut app, " ");
+        assert_eq!(app.mode, Mode::Normal);
+    }
+}
@@ -0,0 +1,150 @@
+//! Snapshots of the lists taken before each change so it can be undone
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{encryption, ListSettings, WatchList, WatchListError};
+
+/// Number of changes which can be undone
+pub const MAX_SNAPSHOTS: usize = 10;
+
+/// The lists as they were before a command changed them, along with their settings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "SavedSnapshot", into = "SavedSnapshot")]
+pub struct Snapshot {
+    /// Command which made the change, as it was typed
+    pub command: String,
+    pub lists: WatchList,
+}
+
+// Settings of the lists are saved next to them, like in the data file
+#[derive(Serialize, Deserialize)]
+struct SavedSnapshot {
+    command: String,
+    lists: WatchList,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    settings: BTreeMap<String, ListSettings>,
+}
+
+impl From<SavedSnapshot> for Snapshot {
+    fn from(saved: SavedSnapshot) -> Self {
+        let mut lists = saved.lists;
+        for (title, settings) in saved.settings {
+            if let Ok(list) = lists.list_settings_mut(&title) {
+                *list = settings;
+            }
+        }
+        Snapshot {
+            command: saved.command,
+            lists,
+        }
+    }
+}
+
+impl From<Snapshot> for SavedSnapshot {
+    fn from(snapshot: Snapshot) -> Self {
+        let settings = snapshot
+            .lists
+            .settings()
+            .map(|(title, settings)| (title.clone(), settings.clone()))
+            .collect();
+        SavedSnapshot {
+            command: snapshot.command,
+            lists: snapshot.lists,
+            settings,
+        }
+    }
+}
+
+/// Recent snapshots saved to a file, newest last
+#[derive(Debug, Default)]
+pub struct UndoHistory {
+    file_path: PathBuf,
+    path: PathBuf,
+    snapshots: Vec<Snapshot>,
+}
+
+impl UndoHistory {
+    /// Reads the undo history next to the data file, starting with none if there isn't any
+    pub fn from_file(file_path: &Path) -> Result<Self, WatchListError> {
+        let path = crate::sidecar_path(file_path, "undo");
+        let snapshots = match encryption::read_sidecar(file_path, &path)? {
+            Some(data) => serde_json::from_str(&data)
+                .map_err(|e| WatchListError::ParseError(e.to_string()))?,
+            None => Vec::new(),
+        };
+        Ok(UndoHistory {
+            file_path: file_path.to_path_buf(),
+            path,
+            snapshots,
+        })
+    }
+
+    pub fn to_file(&self) -> Result<(), WatchListError> {
+        let data = serde_json::to_string(&self.snapshots)
+            .map_err(|e| WatchListError::IOError(e.into()))?;
+        encryption::write_sidecar(&self.file_path, &self.path, data.as_bytes())
+    }
+
+    /// Saves the lists from before `command` changed them, dropping the oldest snapshot
+    /// once there are more than [`MAX_SNAPSHOTS`]
+    pub fn record(&mut self, command: impl Into<String>, lists: WatchList) {
+        self.snapshots.push(Snapshot {
+            command: command.into(),
+            lists,
+        });
+        if self.snapshots.len() > MAX_SNAPSHOTS {
+            self.snapshots.remove(0);
+        }
+    }
+
+    /// Takes the most recent snapshot, if there is one
+    pub fn undo(&mut self) -> Option<Snapshot> {
+        self.snapshots.pop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Item;
+
+    #[test]
+    fn undoing() {
+        let path = std::env::temp_dir().join(format!("wlist_undo_{}.json", std::process::id()));
+        let mut history = UndoHistory::from_file(&path).unwrap();
+        for i in 0..=MAX_SNAPSHOTS {
+            let mut lists = WatchList::new();
+            lists.insert("Movies".to_string(), vec![Item::new(format!("Movie {i}"))]);
+            history.record(format!("add Movies \"Movie {}\"", i + 1), lists);
+        }
+        history.to_file().unwrap();
+
+        let mut history = UndoHistory::from_file(&path).unwrap();
+        let last = history.undo().unwrap();
+        assert_eq!(
+            last.command,
+            format!("add Movies \"Movie {}\"", MAX_SNAPSHOTS + 1)
+        );
+        assert_eq!(last.lists["Movies"], vec![format!("Movie {MAX_SNAPSHOTS}")]);
+
+        // The oldest snapshot was dropped
+        let mut remaining = 1;
+        while history.undo().is_some() {
+            remaining += 1;
+        }
+        assert_eq!(remaining, MAX_SNAPSHOTS);
+
+        // Settings of the lists are undone along with them
+        let mut lists = WatchList::new();
+        lists.insert("Finished".to_string(), vec![Item::new("Alien")]);
+        lists.list_settings_mut("Finished").unwrap().no_random = true;
+        history.record("set Finished --random", lists.clone());
+        history.to_file().unwrap();
+        let mut history = UndoHistory::from_file(&path).unwrap();
+        assert_eq!(history.undo().unwrap().lists, lists);
+        std::fs::remove_file(crate::sidecar_path(&path, "undo")).unwrap();
+    }
+}
@@ -0,0 +1,177 @@
+//! Rules the titles of items and lists have to follow, checked whenever one is added or renamed
+//!
+//! Rules are read from the config file and set on the lists with
+//! [`WatchList::set_validation`](crate::WatchList::set_validation), and programs using the
+//! library can add rules of their own with
+//! [`WatchList::add_item_rule`](crate::WatchList::add_item_rule) and
+//! [`add_list_rule`](crate::WatchList::add_list_rule). They're kept with the lists rather
+//! than for the whole program, so lists read from different config files each follow
+//! their own rules
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::WatchListError;
+
+/// Gives the reason a title isn't allowed, if it isn't
+pub type Rule = Arc<dyn Fn(&str) -> Result<(), String> + Send + Sync>;
+
+/// Limits on titles which can be set in the config file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rules {
+    /// Most characters a title can have
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+    /// Characters titles can't contain
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub forbidden_chars: String,
+    /// Text every title has to start with
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+}
+
+impl Rules {
+    /// Checks a title, giving the reason it isn't allowed if it breaks a rule
+    pub fn check(&self, title: &str) -> Result<(), String> {
+        if let Some(max) = self.max_length.filter(|max| title.chars().count() > *max) {
+            return Err(format!("it's longer than {max} characters"));
+        }
+        if let Some(c) = title.chars().find(|c| self.forbidden_chars.contains(*c)) {
+            return Err(format!("it contains '{c}'"));
+        }
+        match &self.prefix {
+            Some(prefix) if !title.starts_with(prefix.as_str()) => {
+                Err(format!("it doesn't start with '{prefix}'"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Rules for the titles of items and lists from the config file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Validation {
+    pub items: Rules,
+    pub lists: Rules,
+}
+
+impl Validation {
+    pub fn is_empty(&self) -> bool {
+        *self == Validation::default()
+    }
+}
+
+/// Every rule the titles of the items and lists of a [`WatchList`](crate::WatchList) are
+/// checked against, the ones from the config file and the ones added by programs
+#[derive(Clone, Default)]
+pub struct Validator {
+    pub(crate) validation: Validation,
+    pub(crate) item_rules: Vec<Rule>,
+    pub(crate) list_rules: Vec<Rule>,
+}
+
+impl Validator {
+    pub fn check_item(&self, title: &str) -> Result<(), WatchListError> {
+        check(&self.validation.items, &self.item_rules, title)
+    }
+
+    pub fn check_list(&self, title: &str) -> Result<(), WatchListError> {
+        check(&self.validation.lists, &self.list_rules, title)
+    }
+}
+
+fn check(rules: &Rules, added: &[Rule], title: &str) -> Result<(), WatchListError> {
+    rules
+        .check(title)
+        .and_then(|()| added.iter().try_for_each(|rule| rule(title)))
+        .map_err(|reason| WatchListError::ValidationFailed(title.to_string(), reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DuplicatePolicy, WatchList};
+
+    #[test]
+    fn rules() {
+        let rules = Rules {
+            max_length: Some(10),
+            forbidden_chars: "/|".to_string(),
+            prefix: Some("[".to_string()),
+        };
+        assert_eq!(rules.check("[Alien]"), Ok(()));
+        assert_eq!(
+            rules.check("[Alien] Director's Cut"),
+            Err("it's longer than 10 characters".to_string())
+        );
+        assert_eq!(rules.check("[AC/DC]"), Err("it contains '/'".to_string()));
+        assert_eq!(
+            rules.check("Alien"),
+            Err("it doesn't start with '['".to_string())
+        );
+        assert_eq!(Rules::default().check(""), Ok(()));
+    }
+
+    #[test]
+    fn added_rules() {
+        let mut watchlist = WatchList::new();
+        watchlist.add_item_rule(|title| match title.contains('\u{1}') {
+            true => Err("it contains a control character".to_string()),
+            false => Ok(()),
+        });
+        watchlist.add_list_rule(|title| match title.starts_with('\u{1}') {
+            true => Err("it starts with a control character".to_string()),
+            false => Ok(()),
+        });
+
+        assert_eq!(
+            watchlist.list_add("\u{1}Movies"),
+            Err(WatchListError::ValidationFailed(
+                "\u{1}Movies".to_string(),
+                "it starts with a control character".to_string()
+            ))
+        );
+        watchlist.list_add("Movies").unwrap();
+        assert_eq!(
+            watchlist
+                .item_add("Movies", "Alien\u{1}", DuplicatePolicy::Exact)
+                .err()
+                .unwrap(),
+            WatchListError::ValidationFailed(
+                "Alien\u{1}".to_string(),
+                "it contains a control character".to_string()
+            )
+        );
+        watchlist
+            .item_add("Movies", "Alien", DuplicatePolicy::Exact)
+            .unwrap();
+        assert!(matches!(
+            watchlist.item_rename("Movies", "Alien", "\u{1}Alien"),
+            Err(WatchListError::ValidationFailed(..))
+        ));
+        assert!(watchlist.list_rename("Movies", "\u{1}Films").is_err());
+        assert_eq!(watchlist["Movies"], vec!["Alien"]);
+        // Other lists aren't held to the rules
+        assert!(WatchList::new().list_add("\u{1}Movies").is_ok());
+    }
+
+    #[test]
+    fn config_rules() {
+        let mut watchlist = WatchList::new();
+        let validation = |max_length| Validation {
+            lists: Rules {
+                max_length: Some(max_length),
+                ..Rules::default()
+            },
+            ..Validation::default()
+        };
+        watchlist.set_validation(validation(3));
+        assert!(watchlist.list_add("Movies").is_err());
+        // Set again like for another config file, the rules replace the ones before
+        watchlist.set_validation(validation(6));
+        assert!(watchlist.list_add("Movies").is_ok());
+    }
+}
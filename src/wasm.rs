@@ -0,0 +1,129 @@
+//! Bindings for using the lists from JavaScript, like in a browser extension
+//!
+//! Built with the `wasm` feature and without the default ones, which read and save files,
+//! through `wasm-pack build -- --no-default-features --features wasm`. The lists are kept
+//! in memory and handed over as JSON in the format of the data file, for the page to store
+//! wherever suits it
+//!
+//! ```js
+//! import { WatchList } from "wlist";
+//!
+//! const watchlist = WatchList.fromJson(localStorage.getItem("watchlist") ?? "{}");
+//! watchlist.addList("Movies");
+//! watchlist.add("Movies", "Alien");
+//! console.log(watchlist.random("Movies"));
+//! localStorage.setItem("watchlist", watchlist.toJson());
+//! ```
+
+use wasm_bindgen::prelude::*;
+
+use crate::{DuplicatePolicy, WatchList, WatchListError};
+
+/// Lists of items by their titles, see [`WatchList`](crate::WatchList). Errors are thrown
+/// as JavaScript errors with the message `wl` would print
+#[wasm_bindgen(js_name = WatchList)]
+#[derive(Debug, Default)]
+pub struct JsWatchList {
+    watchlist: WatchList,
+}
+
+#[wasm_bindgen(js_class = WatchList)]
+impl JsWatchList {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        JsWatchList::default()
+    }
+
+    /// Reads the lists from JSON in any version of the data file
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(data: &str) -> Result<JsWatchList, JsError> {
+        Ok(JsWatchList {
+            watchlist: WatchList::from_json(data)?,
+        })
+    }
+
+    /// Writes the lists as JSON, the way `wl` saves the data file
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> Result<String, JsError> {
+        Ok(self.watchlist.to_json()?)
+    }
+
+    /// Titles of the lists, in alphabetical order
+    pub fn lists(&self) -> Vec<String> {
+        let mut titles: Vec<String> = self.watchlist.titles().cloned().collect();
+        titles.sort();
+        titles
+    }
+
+    #[wasm_bindgen(js_name = addList)]
+    pub fn add_list(&mut self, title: &str) -> Result<(), JsError> {
+        Ok(self.watchlist.list_add(title)?)
+    }
+
+    #[wasm_bindgen(js_name = removeList)]
+    pub fn remove_list(&mut self, title: &str) -> Result<(), JsError> {
+        Ok(self.watchlist.list_remove(title)?)
+    }
+
+    /// Titles of the items of the list, in the order they were added
+    pub fn items(&self, list: &str) -> Result<Vec<String>, JsError> {
+        let items = self
+            .watchlist
+            .get(list)
+            .ok_or(WatchListError::TitleNotPresent(
+                list.to_string(),
+                Vec::new(),
+            ))?;
+        Ok(items.iter().map(|i| i.title.clone()).collect())
+    }
+
+    /// Adds the item to the list, refusing titles already in it which only differ in case
+    /// or spacing
+    pub fn add(&mut self, list: &str, item: &str) -> Result<(), JsError> {
+        Ok(self
+            .watchlist
+            .item_add(list, item, DuplicatePolicy::Normalized)?)
+    }
+
+    pub fn remove(&mut self, list: &str, item: &str) -> Result<(), JsError> {
+        Ok(self.watchlist.item_remove(list, item)?)
+    }
+
+    /// Titles of the items of the list containing the search, ignoring case and accents
+    pub fn search(&self, list: &str, search: &str) -> Result<Vec<String>, JsError> {
+        let items = self.watchlist.list_search_folded(list, search)?;
+        Ok(items.into_iter().map(|i| i.title.clone()).collect())
+    }
+
+    /// Title of any one of the items of the list
+    pub fn random(&self, list: &str) -> Result<String, JsError> {
+        Ok(self.watchlist.item_get_random(list)?.title.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Errors can only be made into JavaScript errors when running in WebAssembly
+    #[test]
+    fn bindings() {
+        let mut watchlist = JsWatchList::from_json(r#"{"Movies":["Alien"]}"#).unwrap();
+        watchlist.add_list("Anime").unwrap();
+        watchlist.add("Anime", "Pokémon").unwrap();
+        watchlist.add("Anime", "Akira").unwrap();
+        watchlist.remove("Anime", "Akira").unwrap();
+        assert_eq!(watchlist.lists(), vec!["Anime", "Movies"]);
+        assert_eq!(watchlist.items("Anime").unwrap(), vec!["Pokémon"]);
+        assert_eq!(
+            watchlist.search("Anime", "pokemon").unwrap(),
+            vec!["Pokémon"]
+        );
+        assert_eq!(watchlist.random("Movies").unwrap(), "Alien");
+
+        let again = JsWatchList::from_json(&watchlist.to_json().unwrap()).unwrap();
+        assert_eq!(again.watchlist, watchlist.watchlist);
+        watchlist.remove_list("Anime").unwrap();
+        assert_eq!(watchlist.lists(), vec!["Movies"]);
+    }
+}
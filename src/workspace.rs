@@ -0,0 +1,165 @@
+//! Workspaces, named sets of data files used together like a personal and a shared one
+//!
+//! The lists of every file in a workspace are used at once, with their titles prefixed
+//! by the name of the file they're in, like `shared/Movies` for the list Movies in
+//! `shared.json`. Saving splits them back into the files they came from
+
+use std::path::{Path, PathBuf};
+
+use crate::{Item, ListSettings, WatchList};
+
+/// Gets the name the titles of lists in the file are prefixed with, its name without the extension
+pub fn file_name(file_path: &Path) -> String {
+    file_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Gets the path the files next to the data file are named after for the workspace,
+/// like its history, which is never written to itself
+pub fn path(name: &str, files: &[PathBuf]) -> PathBuf {
+    let file_name = format!("{name}.workspace.json");
+    match files.first() {
+        Some(file) => file.with_file_name(file_name),
+        None => PathBuf::from(file_name),
+    }
+}
+
+/// Joins the lists of each file into one, prefixing their titles with the name of their file
+pub fn join(files: Vec<(PathBuf, WatchList)>) -> WatchList {
+    let mut joined = WatchList::new();
+    for (path, lists) in files {
+        let name = file_name(&path);
+        let settings: Vec<(String, ListSettings)> = lists
+            .settings()
+            .map(|(title, settings)| (format!("{name}/{title}"), settings.clone()))
+            .collect();
+        for (title, items) in lists {
+            joined.insert(format!("{name}/{title}"), items);
+        }
+        for (title, settings) in settings {
+            if let Ok(joined) = joined.list_settings_mut(&title) {
+                *joined = settings;
+            }
+        }
+    }
+    joined
+}
+
+/// Splits the lists back into the files they're from, where lists without the name of one
+/// of the files in their title, like ones created since joining them, go in the first file
+pub fn split(watchlist: &WatchList, files: &[PathBuf]) -> Vec<(PathBuf, WatchList)> {
+    let mut split: Vec<(PathBuf, WatchList)> = files
+        .iter()
+        .map(|path| {
+            let mut lists = WatchList::new();
+            lists.set_compact(watchlist.compact());
+            (path.clone(), lists)
+        })
+        .collect();
+    for (full_title, items) in watchlist {
+        let (index, title) = full_title
+            .split_once('/')
+            .and_then(|(name, rest)| {
+                let index = files.iter().position(|f| file_name(f) == name)?;
+                Some((index, rest))
+            })
+            .unwrap_or((0, full_title));
+        if let Some((_, lists)) = split.get_mut(index) {
+            lists.insert(title.to_string(), items.clone());
+            if let Some(settings) = watchlist.list_settings(full_title) {
+                *lists
+                    .list_settings_mut(title)
+                    .expect("the list was just added") = settings.clone();
+            }
+        }
+    }
+    split
+}
+
+/// Combines the lists with the same title in every file into one, leaving out the file names
+///
+/// Items in more than one of the lists are only kept once
+pub fn combine(watchlist: &WatchList) -> WatchList {
+    let mut titles: Vec<&String> = watchlist.titles().collect();
+    // Sorted so the items of each file are always in the same order
+    titles.sort();
+    let mut combined = WatchList::new();
+    for title in titles {
+        let short = title.split_once('/').map_or(title.as_str(), |(_, t)| t);
+        let items: &mut Vec<Item> = combined.get_or_insert(short);
+        for item in &watchlist[title] {
+            if !items.iter().any(|i| i.title == item.title) {
+                items.push(item.clone());
+            }
+        }
+    }
+    combined
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lists(lists: &[(&str, &[&str])]) -> WatchList {
+        lists
+            .iter()
+            .map(|(title, items)| {
+                (
+                    title.to_string(),
+                    items.iter().map(|i| Item::new(*i)).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn joining_and_splitting() {
+        let files = vec![
+            PathBuf::from("/lists/personal.json"),
+            PathBuf::from("/lists/shared.json"),
+        ];
+        let personal = lists(&[("Movies", &["Alien"]), ("AC/DC", &["Highway to Hell"])]);
+        let mut shared = lists(&[("Movies", &["Heat", "Alien"])]);
+        shared.list_settings_mut("Movies").unwrap().no_random = true;
+        let mut joined = join(vec![
+            (files[0].clone(), personal.clone()),
+            (files[1].clone(), shared.clone()),
+        ]);
+        let mut expected = lists(&[
+            ("personal/Movies", &["Alien"]),
+            ("personal/AC/DC", &["Highway to Hell"]),
+            ("shared/Movies", &["Heat", "Alien"]),
+        ]);
+        expected
+            .list_settings_mut("shared/Movies")
+            .unwrap()
+            .no_random = true;
+        assert_eq!(joined, expected);
+        assert_eq!(
+            split(&joined, &files),
+            vec![(files[0].clone(), personal), (files[1].clone(), shared)]
+        );
+
+        joined.insert("Books".to_string(), vec![]);
+        assert!(split(&joined, &files)[0].1.contains("Books"));
+        assert_eq!(
+            path("media", &files),
+            PathBuf::from("/lists/media.workspace.json")
+        );
+    }
+
+    #[test]
+    fn combining() {
+        let joined = lists(&[
+            ("shared/Movies", &["Heat", "Alien"]),
+            ("personal/Movies", &["Alien", "Dune"]),
+            ("personal/Books", &["Emma"]),
+        ]);
+        assert_eq!(
+            combine(&joined),
+            lists(&[("Movies", &["Alien", "Dune", "Heat"]), ("Books", &["Emma"])])
+        );
+    }
+}
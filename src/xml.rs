@@ -0,0 +1,143 @@
+use crate::import::decode_entities;
+use crate::WatchListError;
+
+/// An XML element with its text content and child elements
+///
+/// Attributes are not kept as none of the supported formats need them
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Element {
+    pub name: String,
+    pub text: String,
+    pub children: Vec<Element>,
+}
+
+impl Element {
+    /// Gets the first child element with the given name
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    /// Gets the trimmed text of the first child element with the given name
+    pub fn child_text(&self, name: &str) -> Option<&str> {
+        self.child(name).map(|c| c.text.trim())
+    }
+}
+
+fn parse_error(msg: &str) -> WatchListError {
+    WatchListError::ParseError(format!("invalid XML: {msg}"))
+}
+
+/// Parses an XML document and returns its root element
+pub fn parse(xml: &str) -> Result<Element, WatchListError> {
+    let mut stack: Vec<Element> = Vec::new();
+    let mut root = None;
+
+    let mut rest = xml;
+    while !rest.is_empty() {
+        let Some(start) = rest.find('<') else {
+            push_text(&mut stack, rest);
+            break;
+        };
+        push_text(&mut stack, &rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+            let end = cdata.find("]]>").ok_or(parse_error("unclosed CDATA"))?;
+            if let Some(element) = stack.last_mut() {
+                element.text.push_str(&cdata[..end]);
+            }
+            rest = &cdata[end + 3..];
+            continue;
+        }
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            let end = comment.find("-->").ok_or(parse_error("unclosed comment"))?;
+            rest = &comment[end + 3..];
+            continue;
+        }
+
+        let end = rest.find('>').ok_or(parse_error("unclosed tag"))?;
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        // Declarations and processing instructions
+        if tag.starts_with('?') || tag.starts_with('!') {
+            continue;
+        }
+
+        if let Some(name) = tag.strip_prefix('/') {
+            let element = stack.pop().ok_or(parse_error("unexpected closing tag"))?;
+            if element.name != name.trim() {
+                return Err(parse_error(&format!(
+                    "expected </{}> but found </{}>",
+                    element.name,
+                    name.trim()
+                )));
+            }
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(element),
+                None => root = Some(element),
+            }
+            continue;
+        }
+
+        let self_closing = tag.ends_with('/');
+        let name = tag
+            .trim_end_matches('/')
+            .split_whitespace()
+            .next()
+            .ok_or(parse_error("empty tag"))?;
+        let element = Element {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        match (self_closing, stack.last_mut()) {
+            (true, Some(parent)) => parent.children.push(element),
+            (true, None) => root = Some(element),
+            (false, _) => stack.push(element),
+        }
+    }
+
+    if let Some(element) = stack.last() {
+        return Err(parse_error(&format!("unclosed element <{}>", element.name)));
+    }
+    root.ok_or(parse_error("no root element"))
+}
+
+fn push_text(stack: &mut [Element], text: &str) {
+    if let Some(element) = stack.last_mut() {
+        element.text.push_str(&decode_entities(text));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_elements() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8" ?>
+            <!-- comment -->
+            <root>
+                <item id="1"><title><![CDATA[A <b> & C]]></title><empty/></item>
+                <item><title>D &amp; E</title></item>
+            </root>"#;
+        let root = parse(xml).unwrap();
+        assert_eq!(root.name, "root");
+        assert_eq!(root.children.len(), 2);
+        assert_eq!(root.children[0].child_text("title"), Some("A <b> & C"));
+        assert!(root.children[0].child("empty").is_some());
+        assert_eq!(root.children[1].child_text("title"), Some("D & E"));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            parse("<root><a></b></root>").err().unwrap(),
+            parse_error("expected </a> but found </b>")
+        );
+        assert_eq!(
+            parse("<root><a></a>").err().unwrap(),
+            parse_error("unclosed element <root>")
+        );
+    }
+}
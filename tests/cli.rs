@@ -0,0 +1,363 @@
+//! Whole commands run in a sandbox, with `cargo test --features test-support`
+
+use wlist::testing::Sandbox;
+
+fn sandbox() -> Sandbox {
+    Sandbox::new(env!("CARGO_BIN_EXE_wl")).unwrap()
+}
+
+#[test]
+fn init_add_random_delete() {
+    let sandbox = sandbox();
+    let init = sandbox.run(&["init"]).unwrap().success();
+    assert!(init.stdout.contains("Wrote the default settings"));
+    assert!(sandbox.config_file().exists());
+
+    sandbox.run(&["new", "Movies"]).unwrap().success();
+    sandbox
+        .run(&["add", "Movies", "Alien", "Heat"])
+        .unwrap()
+        .success();
+    // Items are compared by title, as they're stamped with when they were added
+    let lists = sandbox.lists().unwrap();
+    assert_eq!(lists["Movies"], vec!["Alien", "Heat"]);
+    assert!(lists["Movies"].iter().all(|i| i.meta.added_at.is_some()));
+
+    let random = sandbox.run(&["random", "Movies"]).unwrap().success();
+    assert!(["Alien\n", "Heat\n"].contains(&random.stdout.as_str()));
+    let both = sandbox
+        .run(&["random", "Movies", "--count", "2", "--format", "plain"])
+        .unwrap()
+        .success();
+    assert_eq!(both.stdout.lines().count(), 2);
+
+    // Questions aren't asked without a terminal, instead --yes takes their default answers
+    sandbox
+        .run_with_input(&["delete", "Movies", "Alien"], "1\n")
+        .unwrap()
+        .failure(30);
+    sandbox
+        .run(&["delete", "Movies", "Alien", "--yes"])
+        .unwrap()
+        .success();
+    assert_eq!(sandbox.lists().unwrap()["Movies"], vec!["Heat"]);
+    let random = sandbox
+        .run(&["random", "Movies", "--allow-repeat"])
+        .unwrap()
+        .success();
+    assert_eq!(random.stdout, "Heat\n");
+}
+
+#[test]
+fn quiet_and_machine_output() {
+    let sandbox = sandbox();
+    sandbox.run(&["new", "Movies"]).unwrap().success();
+    let added = sandbox
+        .run(&["-q", "add", "Movies", "Alien"])
+        .unwrap()
+        .success();
+    assert_eq!(added.stdout, "");
+
+    let listed = sandbox
+        .run(&["show", "Movies", "--format", "tsv"])
+        .unwrap()
+        .success();
+    assert!(listed.stdout.starts_with("Movies\t1\tAlien\t"));
+    let exported = sandbox
+        .run(&["export", "--format", "csv"])
+        .unwrap()
+        .success();
+    assert_eq!(exported.stdout, "list,item\nMovies,Alien\n");
+}
+
+#[test]
+fn errors() {
+    let sandbox = sandbox();
+    sandbox.run(&["random"]).unwrap().failure(4);
+    sandbox.run(&["new", "Movies"]).unwrap().success();
+    sandbox.run(&["random", "Movies"]).unwrap().failure(5);
+
+    let missing = sandbox.run(&["add", "Movis", "Alien"]).unwrap().failure(7);
+    assert!(missing.stderr.contains("Did you mean Movies?"));
+    sandbox
+        .run(&["--lenient", "add", "Movis", "Alien"])
+        .unwrap()
+        .success();
+    assert_eq!(sandbox.lists().unwrap()["Movies"], vec!["Alien"]);
+
+    // Nothing to ask which list to add to in
+    sandbox.run(&["add", "Heat"]).unwrap().failure(2);
+
+    sandbox
+        .run(&["set-url", "Movies", "Alien", "file:///etc/passwd"])
+        .unwrap()
+        .failure(31);
+    sandbox
+        .run(&["open", "Movies", "Alien"])
+        .unwrap()
+        .failure(32);
+}
+
+#[test]
+fn failed_batch() {
+    let sandbox = sandbox();
+    sandbox.run(&["init"]).unwrap().success();
+    let config = std::fs::read_to_string(sandbox.config_file()).unwrap();
+    sandbox.run(&["new", "Movies"]).unwrap().success();
+
+    let script = "add Movies Alien\nnew Horror --smart \"tag=horror\"\nadd Films Heat\n";
+    sandbox
+        .run_with_input(&["batch", "-"], script)
+        .unwrap()
+        .failure(7);
+    // The smart list saved in the config file is taken out again with the items added
+    assert_eq!(
+        std::fs::read_to_string(sandbox.config_file()).unwrap(),
+        config
+    );
+    assert!(sandbox.lists().unwrap()["Movies"].is_empty());
+
+    // Nor is the item deleted before the failing line left in the trash
+    sandbox.run(&["add", "Movies", "A"]).unwrap().success();
+    let trash = sandbox.run(&["trash", "show"]).unwrap().success();
+    let script = "delete Movies --index 1\nrate Movies Nope 5\n";
+    sandbox
+        .run_with_input(&["batch", "-"], script)
+        .unwrap()
+        .failure(9);
+    assert_eq!(sandbox.lists().unwrap()["Movies"], vec!["A"]);
+    assert_eq!(
+        sandbox.run(&["trash", "show"]).unwrap().success().stdout,
+        trash.stdout
+    );
+}
+
+#[test]
+fn shell_output() {
+    let sandbox = sandbox();
+    sandbox.run(&["new", "Movies"]).unwrap().success();
+    // `--quiet` only lasts for the line it's given on
+    let input = "--quiet add Movies Alien\nadd Movies Heat\n";
+    let shell = sandbox.run_with_input(&["shell"], input).unwrap().success();
+    assert_eq!(shell.stdout.matches("Added").count(), 1);
+    assert_eq!(sandbox.lists().unwrap()["Movies"], vec!["Alien", "Heat"]);
+}
+
+#[test]
+fn closed_output() {
+    let sandbox = sandbox();
+    // More than a pipe holds, so the output is still being written when it's closed
+    for n in 0..3000 {
+        let args = vec![
+            "add".to_string(),
+            "Movies".to_string(),
+            format!("{n:0>100}"),
+        ];
+        let operation = wlist::history::Operation::new(args, wlist::dates::now());
+        wlist::history::append(&sandbox.data_file(), &operation).unwrap();
+    }
+    let history = sandbox.run_first_line(&["history"]).unwrap().success();
+    assert!(history.stdout.contains("History"));
+    assert_eq!(history.stderr, "");
+}
+
+#[test]
+fn diff_files() {
+    let sandbox = sandbox();
+    sandbox.run(&["new", "Movies"]).unwrap().success();
+    sandbox.run(&["add", "Movies", "Alien"]).unwrap().success();
+    let backup = sandbox.home().join("backup.json");
+    std::fs::copy(sandbox.data_file(), &backup).unwrap();
+    sandbox.run(&["add", "Movies", "Heat"]).unwrap().success();
+
+    let backup = backup.to_str().unwrap();
+    let diff = sandbox.run(&["diff", backup]).unwrap().success();
+    assert_eq!(diff.stdout, "- Heat (Movies)\n");
+    let diff = sandbox
+        .run(&["diff", backup, "--format", "json"])
+        .unwrap()
+        .success();
+    assert_eq!(
+        diff.stdout.trim(),
+        r#"[{"change":"item_removed","list":"Movies","item":"Heat"}]"#
+    );
+}
+
+#[test]
+fn seeded_random() {
+    let sandbox = sandbox();
+    sandbox.run(&["new", "Movies"]).unwrap().success();
+    sandbox
+        .run(&[
+            "add", "Movies", "Alien", "Heat", "Ran", "Up", "Jaws", "Dune",
+        ])
+        .unwrap()
+        .success();
+
+    let args = ["random", "Movies", "--seed", "42", "--count", "3"];
+    let first = sandbox.run(&args).unwrap().success();
+    for _ in 0..3 {
+        assert_eq!(sandbox.run(&args).unwrap().success().stdout, first.stdout);
+    }
+
+    // Picking the list too, which every run of wl stores in a different order
+    for list in ["TV", "Books", "Anime", "Games", "Music"] {
+        sandbox.run(&["new", list]).unwrap().success();
+        sandbox
+            .run(&["add", list, "A", "B", "C"])
+            .unwrap()
+            .success();
+    }
+    for seed in ["1", "42"] {
+        let args = ["random", "--seed", seed, "--format", "tsv"];
+        let first = sandbox.run(&args).unwrap().success();
+        for _ in 0..5 {
+            assert_eq!(sandbox.run(&args).unwrap().success().stdout, first.stdout);
+        }
+    }
+}
+
+#[test]
+fn daily_pick() {
+    let sandbox = sandbox();
+    sandbox.run(&["new", "Movies"]).unwrap().success();
+    sandbox
+        .run(&["add", "Movies", "Alien", "Heat", "Ran", "Up"])
+        .unwrap()
+        .success();
+
+    let first = sandbox.run(&["random", "--daily"]).unwrap().success();
+    for _ in 0..5 {
+        let again = sandbox.run(&["random", "--daily"]).unwrap().success();
+        assert_eq!(again.stdout, first.stdout);
+    }
+    sandbox
+        .run(&["random", "--daily", "--count", "2"])
+        .unwrap()
+        .failure(2);
+}
+
+#[cfg(feature = "encryption")]
+#[test]
+fn encrypted_files() {
+    let sandbox = sandbox().with_var(wlist::encryption::PASSPHRASE_VAR, "hunter2");
+    sandbox.run(&["new", "Movies"]).unwrap().success();
+    sandbox.run(&["add", "Movies", "Zardoz"]).unwrap().success();
+    sandbox.run(&["encrypt"]).unwrap().success();
+    sandbox
+        .run(&["add", "-y", "Movies", "Solaris", "Stalker"])
+        .unwrap()
+        .success();
+    sandbox
+        .run(&["delete", "-y", "Movies", "Solaris"])
+        .unwrap()
+        .success();
+    sandbox.run(&["undo"]).unwrap().success();
+    sandbox
+        .run(&["search", "Movies", "Stalk"])
+        .unwrap()
+        .success();
+    sandbox.run(&["random", "Movies"]).unwrap().success();
+    sandbox.run(&["tmp", "add", "Solyaris"]).unwrap().success();
+    let shown = sandbox.run(&["history"]).unwrap().success();
+    assert!(shown.stdout.contains("wl add -y Movies Solaris Stalker"));
+
+    for entry in std::fs::read_dir(sandbox.home()).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_file() {
+            let data = String::from_utf8_lossy(&std::fs::read(&path).unwrap()).into_owned();
+            for title in ["Zardoz", "Solaris", "Stalker", "Solyaris"] {
+                assert!(!data.contains(title), "{title} is in {}", path.display());
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn daemon() {
+    let sandbox = sandbox();
+    sandbox.run(&["new", "Movies"]).unwrap().success();
+    let mut daemon = sandbox.spawn(&["daemon"]).unwrap();
+    let socket = sandbox.home().join("watchlist.sock");
+    for _ in 0..500 {
+        if socket.exists() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    assert!(socket.exists(), "the daemon didn't start");
+
+    sandbox.run(&["add", "Movies", "Alien"]).unwrap().success();
+    let missing = sandbox.run(&["add", "Movis", "Heat"]).unwrap().failure(7);
+    assert!(missing.stderr.contains("Did you mean Movies?"));
+    assert_eq!(sandbox.lists().unwrap()["Movies"], vec!["Alien"]);
+
+    // Output settings only last for the command they're given to
+    let quiet = sandbox.run(&["--quiet", "add", "Movies", "Ran"]).unwrap();
+    assert_eq!(quiet.success().stdout, "");
+    let added = sandbox.run(&["add", "Movies", "Up"]).unwrap().success();
+    assert!(added.stdout.contains("Added"));
+
+    // Changes made to the file without the daemon are read again
+    std::fs::write(sandbox.data_file(), r#"{"Books":["Dune"]}"#).unwrap();
+    let shown = sandbox
+        .run(&["show", "Books", "--format", "plain"])
+        .unwrap()
+        .success();
+    assert_eq!(shown.stdout, "Dune\n");
+
+    daemon.kill().unwrap();
+    daemon.wait().unwrap();
+}
+
+#[test]
+fn serve_changes() {
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+
+    let sandbox = sandbox();
+    sandbox.run(&["new", "Movies"]).unwrap().success();
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let mut server = sandbox
+        .spawn(&["serve", "--port", &port.to_string()])
+        .unwrap();
+    let request = |request: &str| {
+        for _ in 0..500 {
+            if let Ok(mut stream) = TcpStream::connect(("127.0.0.1", port)) {
+                stream.write_all(request.as_bytes()).unwrap();
+                let mut response = String::new();
+                stream.read_to_string(&mut response).unwrap();
+                return response;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        panic!("the server didn't start");
+    };
+
+    // Forms on other sites can't send JSON, so changes without it are refused
+    let refused = request("DELETE /lists/Movies HTTP/1.1\r\n\r\n");
+    assert!(refused.starts_with("HTTP/1.1 415"), "{refused}");
+    let added = request(
+        "POST /lists/Movies/items HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: 7\r\n\r\n\"Alien\"",
+    );
+    assert!(added.starts_with("HTTP/1.1 201"), "{added}");
+    let deleted = request(
+        "DELETE /lists/Movies/items/Alien HTTP/1.1\r\nContent-Type: application/json\r\n\r\n",
+    );
+    assert!(deleted.starts_with("HTTP/1.1 204"), "{deleted}");
+    server.kill().unwrap();
+    server.wait().unwrap();
+
+    let history = sandbox.run(&["history"]).unwrap().success();
+    assert!(history.stdout.contains("wl add Movies Alien"));
+    assert!(history.stdout.contains("wl delete Movies Alien"));
+    let trash = sandbox.run(&["trash", "show"]).unwrap().success();
+    assert!(trash.stdout.contains("'Alien' from Movies"));
+    sandbox.run(&["undo"]).unwrap().success();
+    assert_eq!(sandbox.lists().unwrap()["Movies"], vec!["Alien"]);
+}